@@ -0,0 +1,68 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+#![warn(clippy::pedantic)]
+
+use std::{
+    hint::black_box,
+    io,
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use cringe_lang::{
+    bytecode::{compiler, vm::Vm},
+    driver::{self, Compilation},
+    interpreter::Interpreter,
+};
+
+const ITERATIONS: u32 = 10;
+
+const PROGRAMS: [&str; 3] = ["fibonacci", "sieve", "shapes"];
+
+fn time(mut run: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        run();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn bench(name: &str) {
+    let file = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("benches/programs")
+        .join(name)
+        .with_extension("cringe");
+    let Compilation {
+        tree,
+        program,
+        layouts,
+    } = driver::compile(&file).expect("benchmark programs compile");
+    let module = compiler::compile(&tree, &program, &layouts);
+
+    let ast = time(|| {
+        let result = Interpreter::new(&tree, &program, &layouts, io::sink()).run();
+        black_box(result.expect("benchmark programs run"));
+    });
+    let bytecode = time(|| {
+        let result = Vm::new(&module, io::sink()).run();
+        black_box(result.expect("benchmark programs run"));
+    });
+    let speedup = ast.as_secs_f64() / bytecode.as_secs_f64();
+    println!("{name:<16} ast {ast:>12.3?}   bytecode {bytecode:>12.3?}   speedup {speedup:>6.1}x");
+}
+
+fn main() {
+    // the interpreter recurses once per call, give it room
+    let runner = thread::Builder::new()
+        .stack_size(1 << 28)
+        .spawn(|| {
+            for name in PROGRAMS {
+                bench(name);
+            }
+        })
+        .expect("spawning benchmark thread");
+    runner.join().expect("benchmark thread panicked");
+}
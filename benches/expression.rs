@@ -0,0 +1,64 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+#![warn(clippy::pedantic)]
+
+use std::{fmt::Write, hint::black_box, time::Instant};
+
+use cringe_lang::parser::Parser;
+
+const ITERATIONS: u32 = 50;
+
+fn wrap(expression: &str) -> String {
+    format!("fn f() -> int {{ return {expression}; }}\n")
+}
+
+fn nested_parentheses(depth: usize) -> String {
+    wrap(&format!("{}1{}", "(".repeat(depth), " + 1)".repeat(depth)))
+}
+
+fn nested_unary(depth: usize) -> String {
+    wrap(&format!("{}x", "- ".repeat(depth)))
+}
+
+fn nested_calls(depth: usize) -> String {
+    wrap(&format!("{}x{}", "f(".repeat(depth), ")".repeat(depth)))
+}
+
+fn operator_chain(length: usize) -> String {
+    let operators = [
+        "||", "&&", "|", "^", "&", "==", "!=", "<", "<=", ">", ">=", "<<", ">>", "+", "-", "*",
+        "/", "%",
+    ];
+    let mut expression = String::from("a");
+    for i in 0..length {
+        write!(expression, " {} a{i}", operators[i % operators.len()]).unwrap();
+    }
+    wrap(&expression)
+}
+
+fn bench(name: &str, source: &str) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let mut parser = Parser::new(source.as_bytes(), 0);
+        black_box(parser.parse().expect("benchmark source must parse"));
+    }
+    let elapsed = start.elapsed() / ITERATIONS;
+    println!("{name:<32} {elapsed:>12.3?} per parse");
+}
+
+fn main() {
+    // deeper programs are rejected, see `parser::MAX_NESTING`
+    for depth in [10, 100, 250] {
+        bench(
+            &format!("parentheses depth {depth}"),
+            &nested_parentheses(depth),
+        );
+        bench(&format!("unary depth {depth}"), &nested_unary(depth));
+        bench(&format!("calls depth {depth}"), &nested_calls(depth));
+    }
+    for length in [100, 250, 500] {
+        bench(&format!("operator chain {length}"), &operator_chain(length));
+    }
+}
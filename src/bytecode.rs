@@ -0,0 +1,255 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+use std::fmt::{self, Display};
+
+use crate::parser::tokens::Location;
+
+pub mod compiler;
+pub mod vm;
+
+/// One instruction of the stack machine.
+///
+/// The operand stack holds words. A value takes as many words as its layout
+/// has bytes divided by eight, rounded up, with its bytes in little endian
+/// order; unit takes none. Sizes and offsets are in bytes, addresses are
+/// those of the emulated memory and `slot`s are offsets from the frame of
+/// the current call. Jump targets are indices into the code of the
+/// function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Const(u64),
+    /// Pushes the string constant with the given index as a `str`.
+    Str(u32),
+    /// Pushes `size` zero bytes.
+    Zero(u32),
+    /// Drops `size` bytes.
+    Pop(u32),
+    /// Pushes the address of a slot.
+    Slot(u32),
+    /// Pops an address and pushes the value of `size` bytes there.
+    Load(u32),
+    /// Pops an address and a value and writes the value there.
+    Store(u32),
+    LoadSlot(u32, u32),
+    StoreSlot(u32, u32),
+    /// Adds a constant to the address on top.
+    Offset(u32),
+    /// Replaces a value of `whole` bytes by its `size` bytes at `offset`.
+    Extract {
+        whole: u32,
+        offset: u32,
+        size: u32,
+    },
+    /// Pops a value of `size` bytes and writes it into the value of
+    /// `whole` bytes below at `offset`.
+    Insert {
+        whole: u32,
+        offset: u32,
+        size: u32,
+    },
+    /// Pops an index and an array and pushes the address of the element of
+    /// `size` bytes, panics if the index is out of bounds.
+    Element(u32),
+    /// Pops an index and a string and pushes the byte, panics if the index
+    /// is out of bounds.
+    Byte,
+    /// Replaces an array or string by its length.
+    Len,
+    /// Pops `count` elements of `size` bytes and pushes an array of them in
+    /// a new heap block.
+    Array {
+        count: u32,
+        size: u32,
+        align: u32,
+    },
+    Neg,
+    Not,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    SignedDiv,
+    Rem,
+    SignedRem,
+    Shl,
+    Shr,
+    SignedShr,
+    And,
+    Or,
+    Xor,
+    Eq,
+    Ne,
+    Lt,
+    SignedLt,
+    Le,
+    SignedLe,
+    Gt,
+    SignedGt,
+    Ge,
+    SignedGe,
+    /// Compares the bytes of two strings.
+    StrEq,
+    Jump(u32),
+    /// Pops a word and jumps if it is zero.
+    JumpIfFalse(u32),
+    /// Pops a word and jumps if it is not zero.
+    JumpIfTrue(u32),
+    /// Stores the height of the operand stack in a slot, for leaving a loop
+    /// from within an expression.
+    Mark(u32),
+    /// Cuts the operand stack to the height stored in a slot.
+    Reset(u32),
+    /// Pushes whether the counter of the range whose state starts at the
+    /// slot lies within its end, see [`compiler`].
+    RangeTest {
+        slot: u32,
+        signed: bool,
+        inclusive: bool,
+    },
+    /// Advances the counter of a range by its step.
+    RangeStep {
+        slot: u32,
+        signed: bool,
+    },
+    Call(u32),
+    /// Returns the value of `size` bytes on top.
+    Return(u32),
+    /// Panics with the string constant with the given index.
+    Trap(u32),
+    Print,
+    Println,
+    PrintInt,
+    PrintUInt,
+    /// Pops a count and pushes a new heap block for as many values of the
+    /// given layout.
+    Alloc {
+        size: u32,
+        align: u32,
+    },
+    Free,
+}
+
+// keeps the dispatch loop cache friendly
+const _: () = assert!(size_of::<Op>() <= 16);
+
+/// A compiled function, the frame holds its parameters and locals followed
+/// by the slots the compiler needs for matches and loops.
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub name: String,
+    pub frame_size: u32,
+    pub frame_align: u32,
+    /// Slot and size of every parameter.
+    pub parameters: Vec<(u32, u32)>,
+    pub code: Vec<Op>,
+    /// The source of every instruction.
+    pub locations: Vec<Location>,
+}
+
+/// A compiled program.
+#[derive(Debug, Clone, Default)]
+pub struct Module {
+    pub functions: Vec<Function>,
+    pub strings: Vec<String>,
+    /// The index of `main`, if there is one without parameters.
+    pub entry: Option<u32>,
+    /// `main` returns an `int` that is the result of the program.
+    pub returns_int: bool,
+}
+
+/// Words of the operand stack a value of `size` bytes takes.
+#[must_use]
+pub fn words(size: u32) -> usize {
+    size.div_ceil(8) as usize
+}
+
+impl Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::Const(value) => write!(f, "const {value}"),
+            Op::Str(index) => write!(f, "str #{index}"),
+            Op::Zero(size) => write!(f, "zero {size}"),
+            Op::Pop(size) => write!(f, "pop {size}"),
+            Op::Slot(slot) => write!(f, "slot {slot}"),
+            Op::Load(size) => write!(f, "load {size}"),
+            Op::Store(size) => write!(f, "store {size}"),
+            Op::LoadSlot(slot, size) => write!(f, "load.slot {slot} {size}"),
+            Op::StoreSlot(slot, size) => write!(f, "store.slot {slot} {size}"),
+            Op::Offset(offset) => write!(f, "offset {offset}"),
+            Op::Extract {
+                whole,
+                offset,
+                size,
+            } => write!(f, "extract {whole} {offset} {size}"),
+            Op::Insert {
+                whole,
+                offset,
+                size,
+            } => write!(f, "insert {whole} {offset} {size}"),
+            Op::Element(size) => write!(f, "element {size}"),
+            Op::Array { count, size, align } => write!(f, "array {count} {size} {align}"),
+            Op::Jump(target) => write!(f, "jump @{target}"),
+            Op::JumpIfFalse(target) => write!(f, "jump.false @{target}"),
+            Op::JumpIfTrue(target) => write!(f, "jump.true @{target}"),
+            Op::Mark(slot) => write!(f, "mark {slot}"),
+            Op::Reset(slot) => write!(f, "reset {slot}"),
+            Op::RangeTest {
+                slot,
+                signed,
+                inclusive,
+            } => {
+                let end = if *inclusive { "..=" } else { ".." };
+                let sign = if *signed { "int" } else { "uint" };
+                write!(f, "range.test {slot} {sign} {end}")
+            }
+            Op::RangeStep { slot, signed } => {
+                write!(
+                    f,
+                    "range.step {slot} {}",
+                    if *signed { "int" } else { "uint" }
+                )
+            }
+            Op::Call(function) => write!(f, "call ${function}"),
+            Op::Return(size) => write!(f, "return {size}"),
+            Op::Trap(index) => write!(f, "trap #{index}"),
+            Op::Alloc { size, align } => write!(f, "alloc {size} {align}"),
+            simple => {
+                let name = format!("{simple:?}");
+                let mut mnemonic = String::new();
+                for (i, c) in name.char_indices() {
+                    if c.is_uppercase() && i > 0 {
+                        mnemonic.push('.');
+                    }
+                    mnemonic.push(c.to_ascii_lowercase());
+                }
+                f.write_str(&mnemonic)
+            }
+        }
+    }
+}
+
+/// The disassembly: every function with its instructions and their source
+/// locations, followed by the string constants.
+impl Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, function) in self.functions.iter().enumerate() {
+            writeln!(
+                f,
+                "${index} {} (frame {} bytes, align {})",
+                function.name, function.frame_size, function.frame_align
+            )?;
+            let locations: Vec<String> =
+                function.locations.iter().map(ToString::to_string).collect();
+            let width = locations.iter().map(String::len).max().unwrap_or(0);
+            for (pc, (op, location)) in function.code.iter().zip(&locations).enumerate() {
+                writeln!(f, "  {pc:>5}  {location:<width$} {op}")?;
+            }
+        }
+        for (index, string) in self.strings.iter().enumerate() {
+            writeln!(f, "#{index} {string:?}")?;
+        }
+        Ok(())
+    }
+}
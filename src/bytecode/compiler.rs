@@ -0,0 +1,861 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! Translates a checked, monomorphic program into bytecode.
+//!
+//! Values of locals live in slots of the frame, like in the interpreter, so
+//! taking their address needs no special care. A match stores its scrutinee
+//! in a slot of its own and tests the patterns against it. Every loop marks
+//! the height of the operand stack in a slot, `break` and `continue` cut the
+//! stack back to it since they may leave the loop from within an
+//! expression. The state of a range is four words: the counter, the end, the
+//! step and whether the counter left the range of its type.
+
+use std::collections::HashMap;
+
+use crate::{
+    interpreter::Intrinsic,
+    module::{DefId, ModuleTree},
+    parser::{
+        ast::{BinaryOperator, UnaryOperator},
+        tokens::Location,
+    },
+    sema::{
+        layout::{Layout, Layouts},
+        resolve::LocalId,
+        typed::{
+            Arm, ArmBody, Expr, ExprKind, Instance, Iteration, Pattern, PatternKind, Stmt,
+            StmtKind, TypedFunction, TypedProgram,
+        },
+        types::Ty,
+    },
+};
+
+use super::{Function, Module, Op};
+
+/// Compiles every function of `program` that has a body.
+///
+/// # Panics
+///
+/// Panics if the program exceeds the limits of the format, e.g. a frame of
+/// more than 4 GiB.
+#[must_use]
+pub fn compile(tree: &ModuleTree, program: &TypedProgram, layouts: &Layouts) -> Module {
+    let functions = program
+        .functions
+        .iter()
+        .filter(|(_, function)| !function.external)
+        .enumerate()
+        .map(|(index, (instance, _))| (instance, u32::try_from(index).expect("few functions")))
+        .collect::<HashMap<_, _>>();
+    let intrinsics = Intrinsic::ALL
+        .iter()
+        .filter_map(|(name, intrinsic)| Some((tree.prelude(name)?, *intrinsic)))
+        .collect();
+    let mut compiler = Compiler {
+        program,
+        layouts,
+        functions,
+        intrinsics,
+        strings: Vec::new(),
+        interned: HashMap::new(),
+    };
+
+    let mut module = Module::default();
+    for (instance, function) in &program.functions {
+        if function.external {
+            continue;
+        }
+        if function.name == "main" && instance.generics.is_empty() && function.parameters.is_empty()
+        {
+            module.entry = Some(compiler.functions[instance]);
+            module.returns_int = function.return_type == Ty::Int;
+        }
+        module.functions.push(compiler.function(function));
+    }
+    module.strings = compiler.strings;
+    module
+}
+
+struct Compiler<'p> {
+    program: &'p TypedProgram,
+    layouts: &'p Layouts,
+    functions: HashMap<&'p Instance, u32>,
+    intrinsics: HashMap<DefId, Intrinsic>,
+    strings: Vec<String>,
+    interned: HashMap<String, u32>,
+}
+
+impl Compiler<'_> {
+    fn function(&mut self, function: &TypedFunction) -> Function {
+        let mut emitter = Emitter {
+            compiler: self,
+            code: Vec::new(),
+            locations: Vec::new(),
+            slots: HashMap::new(),
+            frame: Layout::UNIT,
+            loops: Vec::new(),
+        };
+        for local in function.locals() {
+            if !emitter.slots.contains_key(&local) {
+                let layout = emitter.layout(&emitter.compiler.program.local(local).ty);
+                let slot = emitter.slot(layout);
+                emitter.slots.insert(local, slot);
+            }
+        }
+        let parameters = function
+            .parameters
+            .iter()
+            .map(|parameter| {
+                let ty = &emitter.compiler.program.local(*parameter).ty;
+                (emitter.slots[parameter], emitter.size(ty))
+            })
+            .collect();
+
+        emitter.block(&function.body);
+        // flow checking guarantees that only functions without a result
+        // reach their end
+        let end = if function.return_type == Ty::Unit {
+            Op::Return(0)
+        } else {
+            Op::Trap(
+                emitter
+                    .compiler
+                    .string("reached the end of a function with a result"),
+            )
+        };
+        emitter.emit(end, function.location);
+
+        Function {
+            name: function.name.clone(),
+            frame_size: u32::try_from(emitter.frame.size).expect("frames are small"),
+            frame_align: u32::try_from(emitter.frame.align).expect("alignments are small"),
+            parameters,
+            code: emitter.code,
+            locations: emitter.locations,
+        }
+    }
+
+    fn string(&mut self, string: &str) -> u32 {
+        if let Some(index) = self.interned.get(string) {
+            return *index;
+        }
+        let index = u32::try_from(self.strings.len()).expect("few strings");
+        self.strings.push(string.to_string());
+        self.interned.insert(string.to_string(), index);
+        index
+    }
+}
+
+/// The compiler of a single function.
+struct Emitter<'c, 'p> {
+    compiler: &'c mut Compiler<'p>,
+    code: Vec<Op>,
+    locations: Vec<Location>,
+    slots: HashMap<LocalId, u32>,
+    frame: Layout,
+    loops: Vec<Loop>,
+}
+
+struct Loop {
+    label: Option<String>,
+    /// The slot holding the height of the operand stack.
+    mark: u32,
+    /// Slot and size of the value of a `loop` expression.
+    result: Option<(u32, u32)>,
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
+}
+
+impl Emitter<'_, '_> {
+    fn emit(&mut self, op: Op, location: Location) -> usize {
+        self.code.push(op);
+        self.locations.push(location);
+        self.code.len() - 1
+    }
+
+    fn here(&self) -> u32 {
+        u32::try_from(self.code.len()).expect("functions are small")
+    }
+
+    /// Points the jump at `at` to `target`.
+    fn patch_to(&mut self, at: usize, target: u32) {
+        match &mut self.code[at] {
+            Op::Jump(to) | Op::JumpIfFalse(to) | Op::JumpIfTrue(to) => *to = target,
+            op => unreachable!("{op} is not a jump"),
+        }
+    }
+
+    /// Points the jump at `at` to the next instruction.
+    fn patch(&mut self, at: usize) {
+        self.patch_to(at, self.here());
+    }
+
+    /// Reserves a slot in the frame.
+    fn slot(&mut self, layout: Layout) -> u32 {
+        let offset = self.frame.size.next_multiple_of(layout.align);
+        self.frame.size = offset + layout.size;
+        self.frame.align = self.frame.align.max(layout.align);
+        u32::try_from(offset).expect("frames are small")
+    }
+
+    fn layout(&self, ty: &Ty) -> Layout {
+        self.compiler.layouts.of(ty)
+    }
+
+    fn size(&self, ty: &Ty) -> u32 {
+        u32::try_from(self.layout(ty).size).expect("values are small")
+    }
+
+    fn local(&self, local: LocalId) -> u32 {
+        self.slots[&local]
+    }
+
+    fn block(&mut self, body: &[Stmt]) {
+        for statement in body {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &Stmt) {
+        let location = statement.location;
+        match &statement.kind {
+            StmtKind::Block(body) => self.block(body),
+            StmtKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.expression(condition);
+                let skip = self.emit(Op::JumpIfFalse(0), location);
+                self.block(then);
+                if let Some(otherwise) = otherwise {
+                    let end = self.emit(Op::Jump(0), location);
+                    self.patch(skip);
+                    self.block(otherwise);
+                    self.patch(end);
+                } else {
+                    self.patch(skip);
+                }
+            }
+            StmtKind::For {
+                label,
+                binding,
+                iteration,
+                body,
+            } => self.for_loop(label.as_ref(), *binding, iteration, body, location),
+            StmtKind::Return(value) => {
+                let size = value.as_ref().map_or(0, |value| {
+                    self.expression(value);
+                    self.size(&value.ty)
+                });
+                self.emit(Op::Return(size), location);
+            }
+            StmtKind::Break { label, value } => {
+                let target = self.target(label.as_ref());
+                if let Some(value) = value {
+                    self.expression(value);
+                    let size = self.size(&value.ty);
+                    let store = match self.loops[target].result {
+                        Some((slot, _)) => Op::StoreSlot(slot, size),
+                        None => Op::Pop(size),
+                    };
+                    self.emit(store, location);
+                }
+                self.emit(Op::Reset(self.loops[target].mark), location);
+                let jump = self.emit(Op::Jump(0), location);
+                self.loops[target].breaks.push(jump);
+            }
+            StmtKind::Continue { label } => {
+                let target = self.target(label.as_ref());
+                self.emit(Op::Reset(self.loops[target].mark), location);
+                let jump = self.emit(Op::Jump(0), location);
+                self.loops[target].continues.push(jump);
+            }
+            StmtKind::Let { local, value } => {
+                if let Some(value) = value {
+                    self.expression(value);
+                    let op = Op::StoreSlot(self.local(*local), self.size(&value.ty));
+                    self.emit(op, location);
+                }
+            }
+            StmtKind::Assign { target, value } => {
+                self.expression(value);
+                let size = self.size(&value.ty);
+                if let ExprKind::Local(local) = &target.kind {
+                    self.emit(Op::StoreSlot(self.local(*local), size), location);
+                } else {
+                    self.place(target);
+                    self.emit(Op::Store(size), location);
+                }
+            }
+            StmtKind::Expr(expression) => {
+                self.expression(expression);
+                let size = self.size(&expression.ty);
+                if size > 0 {
+                    self.emit(Op::Pop(size), location);
+                }
+            }
+        }
+    }
+
+    /// The index of the loop a `break` or `continue` refers to.
+    fn target(&self, label: Option<&String>) -> usize {
+        self.loops
+            .iter()
+            .rposition(|candidate| label.is_none() || candidate.label.as_ref() == label)
+            .expect("checked to be inside the loop")
+    }
+
+    fn enter_loop(
+        &mut self,
+        label: Option<&String>,
+        result: Option<(u32, u32)>,
+        location: Location,
+    ) {
+        let mark = self.slot(Layout::WORD);
+        self.emit(Op::Mark(mark), location);
+        self.loops.push(Loop {
+            label: label.cloned(),
+            mark,
+            result,
+            breaks: Vec::new(),
+            continues: Vec::new(),
+        });
+    }
+
+    /// Ends the innermost loop, `continue` jumps to `next` and `break` to
+    /// the next instruction.
+    fn leave_loop(&mut self, next: u32) {
+        let finished = self.loops.pop().expect("a loop was entered");
+        for jump in finished.continues {
+            self.patch_to(jump, next);
+        }
+        for jump in finished.breaks {
+            self.patch(jump);
+        }
+    }
+
+    fn for_loop(
+        &mut self,
+        label: Option<&String>,
+        binding: LocalId,
+        iteration: &Iteration,
+        body: &[Stmt],
+        location: Location,
+    ) {
+        let slot = self.local(binding);
+        let size = self.size(&self.compiler.program.local(binding).ty);
+        match iteration {
+            Iteration::Range {
+                start,
+                end,
+                step,
+                inclusive,
+            } => {
+                let signed = start.ty == Ty::Int;
+                let state = self.slot(Layout { size: 32, align: 8 });
+                self.expression(start);
+                self.emit(Op::StoreSlot(state, 8), location);
+                self.expression(end);
+                self.emit(Op::StoreSlot(state + 8, 8), location);
+                match step {
+                    Some(step) => self.expression(step),
+                    None => _ = self.emit(Op::Const(1), location),
+                }
+                self.emit(Op::StoreSlot(state + 16, 8), location);
+                self.emit(Op::Const(0), location);
+                self.emit(Op::StoreSlot(state + 24, 8), location);
+                self.emit(Op::LoadSlot(state + 16, 8), location);
+                let valid = self.emit(Op::JumpIfTrue(0), location);
+                let message = self.compiler.string("range with a step of zero");
+                self.emit(Op::Trap(message), start.location);
+                self.patch(valid);
+
+                self.enter_loop(label, None, location);
+                let head = self.here();
+                let test = Op::RangeTest {
+                    slot: state,
+                    signed,
+                    inclusive: *inclusive,
+                };
+                self.emit(test, location);
+                let exit = self.emit(Op::JumpIfFalse(0), location);
+                self.emit(Op::LoadSlot(state, 8), location);
+                self.emit(Op::StoreSlot(slot, 8), location);
+                self.block(body);
+                let next = self.here();
+                self.emit(
+                    Op::RangeStep {
+                        slot: state,
+                        signed,
+                    },
+                    location,
+                );
+                self.emit(Op::Jump(head), location);
+                self.patch(exit);
+                self.leave_loop(next);
+            }
+            Iteration::Elements(iterable) => {
+                let element = match &iterable.ty {
+                    Ty::Array(element) => self.size(element),
+                    _ => 1,
+                };
+                let state = self.slot(Layout { size: 24, align: 8 });
+                self.expression(iterable);
+                self.emit(Op::StoreSlot(state, 16), location);
+                self.emit(Op::Const(0), location);
+                self.emit(Op::StoreSlot(state + 16, 8), location);
+
+                self.enter_loop(label, None, location);
+                let head = self.here();
+                self.emit(Op::LoadSlot(state + 16, 8), location);
+                self.emit(Op::LoadSlot(state + 8, 8), location);
+                self.emit(Op::Lt, location);
+                let exit = self.emit(Op::JumpIfFalse(0), location);
+                self.emit(Op::LoadSlot(state, 8), location);
+                self.emit(Op::LoadSlot(state + 16, 8), location);
+                self.emit(Op::Const(element.into()), location);
+                self.emit(Op::Mul, location);
+                self.emit(Op::Add, location);
+                self.emit(Op::Load(element), iterable.location);
+                self.emit(Op::StoreSlot(slot, size), location);
+                self.block(body);
+                let next = self.here();
+                self.emit(Op::LoadSlot(state + 16, 8), location);
+                self.emit(Op::Const(1), location);
+                self.emit(Op::Add, location);
+                self.emit(Op::StoreSlot(state + 16, 8), location);
+                self.emit(Op::Jump(head), location);
+                self.patch(exit);
+                self.leave_loop(next);
+            }
+            Iteration::Iterator { iterator, next } => {
+                self.iterator_loop(label, slot, size, iterator, next, body, location);
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn iterator_loop(
+        &mut self,
+        label: Option<&String>,
+        slot: u32,
+        size: u32,
+        iterator: &Expr,
+        next: &Expr,
+        body: &[Stmt],
+        location: Location,
+    ) {
+        let ExprKind::Call(instance, _) = &next.kind else {
+            unreachable!("next is a call")
+        };
+        let Ty::Enum(id, _) = &next.ty else {
+            unreachable!("next returns an option")
+        };
+        let layouts = self.compiler.layouts;
+        let layout = &layouts.enums[&next.ty];
+        let (option_layout, tag) = (layout.layout, layout.tag.size);
+        let some = self.compiler.program.enums[id]
+            .variants
+            .iter()
+            .position(|variant| variant.name == "Some")
+            .expect("Option has a variant Some");
+        let payload = layout.variants[some][0];
+
+        let pointer = self.slot(Layout::WORD);
+        self.expression(iterator);
+        if !matches!(iterator.ty, Ty::Pointer(_)) {
+            // a struct value is iterated as a copy
+            let copy = self.slot(self.layout(&iterator.ty));
+            self.emit(Op::StoreSlot(copy, self.size(&iterator.ty)), location);
+            self.emit(Op::Slot(copy), location);
+        }
+        self.emit(Op::StoreSlot(pointer, 8), location);
+        let option = self.slot(option_layout);
+        let option_size = u32::try_from(option_layout.size).expect("values are small");
+
+        self.enter_loop(label, None, location);
+        let head = self.here();
+        self.emit(Op::LoadSlot(pointer, 8), next.location);
+        self.emit(Op::Call(self.compiler.functions[instance]), next.location);
+        self.emit(Op::StoreSlot(option, option_size), location);
+        self.emit(Op::LoadSlot(option, to_u32(tag)), location);
+        self.emit(Op::Const(some as u64), location);
+        self.emit(Op::Eq, location);
+        let exit = self.emit(Op::JumpIfFalse(0), location);
+        self.emit(Op::LoadSlot(option + to_u32(payload), size), location);
+        self.emit(Op::StoreSlot(slot, size), location);
+        self.block(body);
+        self.emit(Op::Jump(head), location);
+        self.patch(exit);
+        self.leave_loop(head);
+    }
+
+    /// Pushes the value of an expression.
+    fn expression(&mut self, expression: &Expr) {
+        let location = expression.location;
+        let ty = &expression.ty;
+        match &expression.kind {
+            ExprKind::Int(int) => _ = self.emit(Op::Const(*int), location),
+            ExprKind::Bool(bool) => _ = self.emit(Op::Const(u64::from(*bool)), location),
+            ExprKind::Str(str) => {
+                let index = self.compiler.string(str);
+                self.emit(Op::Str(index), location);
+            }
+            ExprKind::SizeOf(ty) => _ = self.emit(Op::Const(self.layout(ty).size), location),
+            ExprKind::Local(local) => {
+                self.emit(Op::LoadSlot(self.local(*local), self.size(ty)), location);
+            }
+            ExprKind::Unary(UnaryOperator::Dereference, _)
+            | ExprKind::Index(..)
+            | ExprKind::Field(..)
+                if expression.is_place() =>
+            {
+                self.place(expression);
+                self.emit(Op::Load(self.size(ty)), location);
+            }
+            ExprKind::Unary(UnaryOperator::Address, operand) => self.place(operand),
+            ExprKind::Unary(operator, operand) => {
+                self.expression(operand);
+                if operand.ty == Ty::Bool {
+                    self.emit(Op::Const(1), location);
+                    self.emit(Op::Xor, location);
+                } else if *operator == UnaryOperator::Minus {
+                    self.emit(Op::Neg, location);
+                } else {
+                    self.emit(Op::Not, location);
+                }
+            }
+            ExprKind::Binary(operator, lhs, rhs) => self.binary(*operator, lhs, rhs, location),
+            ExprKind::Call(instance, arguments) => self.call(instance, arguments, location),
+            // only strings are indexed without being a place
+            ExprKind::Index(base, index) => {
+                self.expression(base);
+                self.expression(index);
+                self.emit(Op::Byte, location);
+            }
+            ExprKind::Field(base, field) => {
+                self.expression(base);
+                let offset = self.compiler.layouts.structs[&base.ty].offsets[*field];
+                let op = Op::Extract {
+                    whole: self.size(&base.ty),
+                    offset: to_u32(offset),
+                    size: self.size(ty),
+                };
+                self.emit(op, location);
+            }
+            ExprKind::Len(base) => {
+                self.expression(base);
+                if matches!(base.ty, Ty::Pointer(_)) {
+                    self.emit(Op::Load(to_u32(Layout::FAT_POINTER.size)), location);
+                }
+                self.emit(Op::Len, location);
+            }
+            ExprKind::StructInit(_, fields) => {
+                let layouts = self.compiler.layouts;
+                self.compose(ty, fields, &layouts.structs[ty].offsets, location);
+            }
+            ExprKind::Variant(_, variant, fields) => self.variant(ty, *variant, fields, location),
+            ExprKind::ArrayInit(elements) => self.array(ty, elements, location),
+            ExprKind::Loop { label, body } => {
+                self.loop_expression(label.as_ref(), body, ty, location);
+            }
+            ExprKind::Match { scrutinee, arms } => self.match_expression(scrutinee, arms, location),
+            ExprKind::InterfaceCall(..) | ExprKind::Error => {
+                unreachable!("only checked, monomorphic programs are compiled")
+            }
+        }
+    }
+
+    fn variant(&mut self, ty: &Ty, variant: usize, fields: &[Expr], location: Location) {
+        let layouts = self.compiler.layouts;
+        let layout = &layouts.enums[ty];
+        let whole = self.size(ty);
+        self.emit(Op::Zero(whole), location);
+        self.emit(Op::Const(variant as u64), location);
+        let op = Op::Insert {
+            whole,
+            offset: 0,
+            size: to_u32(layout.tag.size),
+        };
+        self.emit(op, location);
+        self.insert_fields(whole, fields, &layout.variants[variant], location);
+    }
+
+    fn array(&mut self, ty: &Ty, elements: &[Expr], location: Location) {
+        let Ty::Array(element) = ty else {
+            unreachable!("array literals are arrays")
+        };
+        for element in elements {
+            self.expression(element);
+        }
+        let layout = self.layout(element);
+        let op = Op::Array {
+            count: u32::try_from(elements.len()).expect("literals are small"),
+            size: to_u32(layout.size),
+            align: to_u32(layout.align),
+        };
+        self.emit(op, location);
+    }
+
+    fn loop_expression(
+        &mut self,
+        label: Option<&String>,
+        body: &[Stmt],
+        ty: &Ty,
+        location: Location,
+    ) {
+        let size = self.size(ty);
+        let result = (size > 0).then(|| (self.slot(self.layout(ty)), size));
+        self.enter_loop(label, result, location);
+        let head = self.here();
+        self.block(body);
+        self.emit(Op::Jump(head), location);
+        self.leave_loop(head);
+        if let Some((slot, size)) = result {
+            self.emit(Op::LoadSlot(slot, size), location);
+        }
+    }
+
+    fn compose(&mut self, ty: &Ty, fields: &[Expr], offsets: &[u64], location: Location) {
+        let whole = self.size(ty);
+        self.emit(Op::Zero(whole), location);
+        self.insert_fields(whole, fields, offsets, location);
+    }
+
+    fn insert_fields(&mut self, whole: u32, fields: &[Expr], offsets: &[u64], location: Location) {
+        for (field, offset) in fields.iter().zip(offsets) {
+            self.expression(field);
+            let op = Op::Insert {
+                whole,
+                offset: to_u32(*offset),
+                size: self.size(&field.ty),
+            };
+            self.emit(op, location);
+        }
+    }
+
+    /// Pushes the address of a place expression.
+    fn place(&mut self, expression: &Expr) {
+        let location = expression.location;
+        match &expression.kind {
+            ExprKind::Local(local) => _ = self.emit(Op::Slot(self.local(*local)), location),
+            ExprKind::Unary(UnaryOperator::Dereference, pointer) => self.expression(pointer),
+            ExprKind::Field(base, field) => {
+                let structure = match &base.ty {
+                    Ty::Pointer(pointee) => {
+                        self.expression(base);
+                        &**pointee
+                    }
+                    structure => {
+                        self.place(base);
+                        structure
+                    }
+                };
+                let offset = self.compiler.layouts.structs[structure].offsets[*field];
+                if offset > 0 {
+                    self.emit(Op::Offset(to_u32(offset)), location);
+                }
+            }
+            ExprKind::Index(base, index) => {
+                self.expression(base);
+                self.expression(index);
+                match &base.ty {
+                    Ty::Pointer(element) => {
+                        self.emit(Op::Const(self.layout(element).size), location);
+                        self.emit(Op::Mul, location);
+                        self.emit(Op::Add, location);
+                    }
+                    Ty::Array(element) => _ = self.emit(Op::Element(self.size(element)), location),
+                    _ => unreachable!("strings are not places"),
+                }
+            }
+            _ => unreachable!("checked to be a place"),
+        }
+    }
+
+    fn binary(&mut self, operator: BinaryOperator, lhs: &Expr, rhs: &Expr, location: Location) {
+        use BinaryOperator as B;
+
+        self.expression(lhs);
+        if let B::LogicalAnd | B::LogicalOr = operator {
+            // the right-hand side only runs if it decides the result
+            let decided = operator == B::LogicalOr;
+            let skip = self.emit(
+                if decided {
+                    Op::JumpIfTrue(0)
+                } else {
+                    Op::JumpIfFalse(0)
+                },
+                location,
+            );
+            self.expression(rhs);
+            let end = self.emit(Op::Jump(0), location);
+            self.patch(skip);
+            self.emit(Op::Const(u64::from(decided)), location);
+            self.patch(end);
+            return;
+        }
+        self.expression(rhs);
+
+        if lhs.ty == Ty::Str {
+            self.emit(Op::StrEq, location);
+            if operator == B::NotEqual {
+                self.emit(Op::Const(1), location);
+                self.emit(Op::Xor, location);
+            }
+            return;
+        }
+        let signed = lhs.ty == Ty::Int;
+        let op = match operator {
+            B::Add => Op::Add,
+            B::Sub => Op::Sub,
+            B::Mul => Op::Mul,
+            B::Div if signed => Op::SignedDiv,
+            B::Div => Op::Div,
+            B::Rem if signed => Op::SignedRem,
+            B::Rem => Op::Rem,
+            B::ShiftLeft => Op::Shl,
+            B::ShiftRight if signed => Op::SignedShr,
+            B::ShiftRight => Op::Shr,
+            B::And => Op::And,
+            B::Or => Op::Or,
+            B::Xor => Op::Xor,
+            B::Equal => Op::Eq,
+            B::NotEqual => Op::Ne,
+            B::Less if signed => Op::SignedLt,
+            B::Less => Op::Lt,
+            B::LessEqual if signed => Op::SignedLe,
+            B::LessEqual => Op::Le,
+            B::Greater if signed => Op::SignedGt,
+            B::Greater => Op::Gt,
+            B::GreaterEqual if signed => Op::SignedGe,
+            B::GreaterEqual => Op::Ge,
+            B::LogicalAnd | B::LogicalOr => unreachable!("short circuits are handled above"),
+        };
+        self.emit(op, location);
+    }
+
+    fn call(&mut self, instance: &Instance, arguments: &[Expr], location: Location) {
+        let mut size = 0;
+        for argument in arguments {
+            self.expression(argument);
+            size += self.size(&argument.ty).next_multiple_of(8);
+        }
+        let function = &self.compiler.program.functions[instance];
+        if !function.external {
+            self.emit(Op::Call(self.compiler.functions[instance]), location);
+            return;
+        }
+        let op = match self.compiler.intrinsics.get(&instance.id) {
+            Some(Intrinsic::Print) => Op::Print,
+            Some(Intrinsic::Println) => Op::Println,
+            Some(Intrinsic::PrintInt) => Op::PrintInt,
+            Some(Intrinsic::PrintUInt) => Op::PrintUInt,
+            Some(Intrinsic::Alloc) => {
+                let element = self.layout(&instance.generics[0]);
+                Op::Alloc {
+                    size: to_u32(element.size),
+                    align: to_u32(element.align),
+                }
+            }
+            Some(Intrinsic::Free) => Op::Free,
+            None => {
+                let message = format!("extern function {} is not available", function.name);
+                self.emit(Op::Pop(size), location);
+                Op::Trap(self.compiler.string(&message))
+            }
+        };
+        self.emit(op, location);
+    }
+
+    fn match_expression(&mut self, scrutinee: &Expr, arms: &[Arm], location: Location) {
+        self.expression(scrutinee);
+        let value = self.slot(self.layout(&scrutinee.ty));
+        self.emit(Op::StoreSlot(value, self.size(&scrutinee.ty)), location);
+
+        let mut ends = Vec::new();
+        for arm in arms {
+            let mut failures = Vec::new();
+            self.pattern(&arm.pattern, value, &mut failures);
+            if let Some(guard) = &arm.guard {
+                self.expression(guard);
+                failures.push(self.emit(Op::JumpIfFalse(0), guard.location));
+            }
+            match &arm.body {
+                ArmBody::Expr(value) => self.expression(value),
+                ArmBody::Block(body) => self.block(body),
+            }
+            ends.push(self.emit(Op::Jump(0), arm.location));
+            for failure in failures {
+                self.patch(failure);
+            }
+        }
+        let message = self.compiler.string("no arm matched");
+        self.emit(Op::Trap(message), location);
+        for end in ends {
+            self.patch(end);
+        }
+    }
+
+    /// Tests the value in `slot` against `pattern`, writes the bindings and
+    /// adds a jump for every way the test can fail.
+    fn pattern(&mut self, pattern: &Pattern, slot: u32, failures: &mut Vec<usize>) {
+        let location = pattern.location;
+        let size = self.size(&pattern.ty);
+        let constant = match &pattern.kind {
+            PatternKind::Wildcard => return,
+            PatternKind::Binding(local) => {
+                self.emit(Op::LoadSlot(slot, size), location);
+                self.emit(Op::StoreSlot(self.local(*local), size), location);
+                return;
+            }
+            PatternKind::Variant(_, variant, fields) => {
+                let layout = &self.compiler.layouts.enums[&pattern.ty];
+                let tag = to_u32(layout.tag.size);
+                let offsets = layout.variants[*variant].clone();
+                self.emit(Op::LoadSlot(slot, tag), location);
+                self.emit(Op::Const(*variant as u64), location);
+                self.emit(Op::Eq, location);
+                failures.push(self.emit(Op::JumpIfFalse(0), location));
+                self.fields(fields, &offsets, slot, failures);
+                return;
+            }
+            PatternKind::Struct(_, fields) => {
+                let offsets = self.compiler.layouts.structs[&pattern.ty].offsets.clone();
+                self.fields(fields, &offsets, slot, failures);
+                return;
+            }
+            PatternKind::Int(int) => Op::Const(*int),
+            PatternKind::Bool(bool) => Op::Const(u64::from(*bool)),
+            PatternKind::Str(str) => Op::Str(self.compiler.string(str)),
+        };
+        self.emit(Op::LoadSlot(slot, size), location);
+        self.emit(constant, location);
+        let compare = if pattern.ty == Ty::Str {
+            Op::StrEq
+        } else {
+            Op::Eq
+        };
+        self.emit(compare, location);
+        failures.push(self.emit(Op::JumpIfFalse(0), location));
+    }
+
+    fn fields(
+        &mut self,
+        fields: &[Pattern],
+        offsets: &[u64],
+        slot: u32,
+        failures: &mut Vec<usize>,
+    ) {
+        for (field, offset) in fields.iter().zip(offsets) {
+            self.pattern(field, slot + to_u32(*offset), failures);
+        }
+    }
+}
+
+fn to_u32(value: u64) -> u32 {
+    u32::try_from(value).expect("sizes and offsets of values are small")
+}
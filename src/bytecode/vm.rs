@@ -0,0 +1,563 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+use std::io::{self, Write};
+
+use crate::{
+    Error,
+    interpreter::{
+        DEFAULT_MAX_DEPTH,
+        memory::{Fault, Memory},
+    },
+    sema::layout::Layout,
+};
+
+use super::{Module, Op, words};
+
+/// Runs a [`Module`] with the same emulated memory and the same semantics
+/// as the [`Interpreter`](crate::interpreter::Interpreter), in a loop
+/// instead of by recursion.
+pub struct Vm<'m, W: Write> {
+    module: &'m Module,
+    output: W,
+    memory: Memory,
+    max_depth: usize,
+    stack: Vec<u64>,
+    frames: Vec<Frame>,
+    /// Address of the bytes of every string constant.
+    strings: Vec<u64>,
+}
+
+/// An active call.
+struct Frame {
+    base: u64,
+    /// The top of the memory stack before the call.
+    top: u64,
+    /// The height of the operand stack without the arguments.
+    height: usize,
+    /// Function and instruction to continue with after the call, `None`
+    /// for the entry.
+    caller: Option<(usize, usize)>,
+}
+
+/// Why an instruction failed, the location is added by the dispatch loop.
+enum Failure {
+    Panic(String),
+    Io(io::Error),
+}
+
+impl From<Fault> for Failure {
+    fn from(fault: Fault) -> Self {
+        Failure::Panic(fault.to_string())
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(error: io::Error) -> Self {
+        Failure::Io(error)
+    }
+}
+
+impl<'m, W: Write> Vm<'m, W> {
+    /// Creates a machine for `module` that prints to `output`.
+    pub fn new(module: &'m Module, output: W) -> Self {
+        let mut memory = Memory::new();
+        let strings = module
+            .strings
+            .iter()
+            .map(|string| constant(&mut memory, string))
+            .collect();
+        Self {
+            module,
+            output,
+            memory,
+            max_depth: DEFAULT_MAX_DEPTH,
+            stack: Vec::new(),
+            frames: Vec::new(),
+            strings,
+        }
+    }
+
+    /// Limits the number of nested calls, exceeding it stops the program.
+    #[must_use]
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Calls `main` and returns its result, or 0 if it returns nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MissingMain`] if the module has no entry,
+    /// [`Error::Panic`] and [`Error::StackOverflow`] if the program fails
+    /// and I/O errors of the output.
+    pub fn run(&mut self) -> Result<i64, Error> {
+        let entry = self.module.entry.ok_or(Error::MissingMain)?;
+        let result = self.execute(entry as usize);
+        self.output.flush()?;
+        let result = result?;
+        Ok(if self.module.returns_int {
+            result.cast_signed()
+        } else {
+            0
+        })
+    }
+
+    /// The dispatch loop, returns the first word of the result of `entry`.
+    fn execute(&mut self, entry: usize) -> Result<u64, Error> {
+        let module = self.module;
+        let mut current = entry;
+        let mut code = &module.functions[current].code[..];
+        let mut pc = 0;
+        self.enter(current, None);
+
+        loop {
+            let op = code[pc];
+            pc += 1;
+            let result = match op {
+                Op::Jump(target) => {
+                    pc = target as usize;
+                    Ok(())
+                }
+                Op::JumpIfFalse(target) => {
+                    if self.pop() == 0 {
+                        pc = target as usize;
+                    }
+                    Ok(())
+                }
+                Op::JumpIfTrue(target) => {
+                    if self.pop() != 0 {
+                        pc = target as usize;
+                    }
+                    Ok(())
+                }
+                Op::Call(function) => {
+                    if self.frames.len() >= self.max_depth {
+                        return Err(Error::StackOverflow {
+                            depth: self.max_depth,
+                            location: module.functions[current].locations[pc - 1],
+                        });
+                    }
+                    self.enter(function as usize, Some((current, pc)));
+                    current = function as usize;
+                    code = &module.functions[current].code;
+                    pc = 0;
+                    Ok(())
+                }
+                Op::Return(size) => {
+                    let frame = self.frames.pop().expect("code only runs inside a call");
+                    let words = words(size);
+                    let start = self.stack.len() - words;
+                    self.stack.copy_within(start.., frame.height);
+                    self.stack.truncate(frame.height + words);
+                    self.memory.pop(frame.top);
+                    let Some((caller, resume)) = frame.caller else {
+                        return Ok(self.stack.first().copied().unwrap_or(0));
+                    };
+                    current = caller;
+                    code = &module.functions[current].code;
+                    pc = resume;
+                    Ok(())
+                }
+                op => self.step(op),
+            };
+            if let Err(failure) = result {
+                return Err(match failure {
+                    Failure::Panic(message) => Error::Panic {
+                        message,
+                        location: module.functions[current].locations[pc - 1],
+                    },
+                    Failure::Io(error) => Error::Io(error),
+                });
+            }
+        }
+    }
+
+    /// Pushes the frame of `function` and moves its arguments into it.
+    fn enter(&mut self, function: usize, caller: Option<(usize, usize)>) {
+        let function = &self.module.functions[function];
+        let top = self.memory.top();
+        let base = self.memory.push(Layout {
+            size: function.frame_size.into(),
+            align: function.frame_align.into(),
+        });
+        for (slot, size) in function.parameters.iter().rev() {
+            self.store(base + u64::from(*slot), *size)
+                .expect("frames are on the stack");
+        }
+        self.frames.push(Frame {
+            base,
+            top,
+            height: self.stack.len(),
+            caller,
+        });
+    }
+
+    /// Executes every instruction that does not change the control flow.
+    #[allow(clippy::too_many_lines)]
+    fn step(&mut self, op: Op) -> Result<(), Failure> {
+        match op {
+            Op::Const(value) => self.stack.push(value),
+            Op::Str(index) => {
+                self.stack.push(self.strings[index as usize]);
+                self.stack
+                    .push(self.module.strings[index as usize].len() as u64);
+            }
+            Op::Zero(size) => self.stack.resize(self.stack.len() + words(size), 0),
+            Op::Pop(size) => self.stack.truncate(self.stack.len() - words(size)),
+            Op::Slot(slot) => self.stack.push(self.base() + u64::from(slot)),
+            Op::Load(size) => {
+                let address = self.pop();
+                self.load(address, size)?;
+            }
+            Op::Store(size) => {
+                let address = self.pop();
+                self.store(address, size)?;
+            }
+            Op::LoadSlot(slot, size) => self.load(self.base() + u64::from(slot), size)?,
+            Op::StoreSlot(slot, size) => self.store(self.base() + u64::from(slot), size)?,
+            Op::Offset(offset) => *self.top() += u64::from(offset),
+            Op::Extract {
+                whole,
+                offset,
+                size,
+            } => {
+                let start = self.stack.len() - words(whole);
+                if offset % 8 == 0 {
+                    // fields at word boundaries are whole words, but the
+                    // last one may be shared with the next field
+                    let first = start + offset as usize / 8;
+                    self.stack.copy_within(first..first + words(size), start);
+                    self.stack.truncate(start + words(size));
+                    *self.top() &= mask(size);
+                    return Ok(());
+                }
+                let field = (0..size)
+                    .map(|i| byte(&self.stack[start..], (offset + i) as usize))
+                    .collect::<Vec<_>>();
+                self.stack.truncate(start);
+                self.push_bytes(&field);
+            }
+            Op::Insert {
+                whole,
+                offset,
+                size,
+            } => {
+                let field = self.stack.len() - words(size);
+                let start = field - words(whole);
+                if offset % 8 == 0 {
+                    let first = start + offset as usize / 8;
+                    for i in 0..words(size) {
+                        let keep = if i + 1 == words(size) { !mask(size) } else { 0 };
+                        let value = self.stack[field + i];
+                        let word = &mut self.stack[first + i];
+                        *word = (*word & keep) | (value & !keep);
+                    }
+                    self.stack.truncate(field);
+                    return Ok(());
+                }
+                for i in 0..size as usize {
+                    let value = byte(&self.stack[field..], i);
+                    set_byte(&mut self.stack[start..field], offset as usize + i, value);
+                }
+                self.stack.truncate(field);
+            }
+            Op::Element(size) => {
+                let index = self.pop();
+                let (address, length) = self.pop_fat();
+                check_bounds(index, length)?;
+                self.stack.push(address + index * u64::from(size));
+            }
+            Op::Byte => {
+                let index = self.pop();
+                let (address, length) = self.pop_fat();
+                check_bounds(index, length)?;
+                let byte = self.memory.read(address + index, 1)?[0];
+                self.stack.push(byte.into());
+            }
+            Op::Len => {
+                let length = self.pop();
+                *self.top() = length;
+            }
+            Op::Array { count, size, align } => {
+                let element = words(size);
+                let start = self.stack.len() - count as usize * element;
+                let address = self.memory.allocate(Layout {
+                    size: u64::from(size) * u64::from(count),
+                    align: align.into(),
+                });
+                for i in 0..count as usize {
+                    let value = &self.stack[start + i * element..start + (i + 1) * element];
+                    write_words(
+                        &mut self.memory,
+                        address + (i as u64) * u64::from(size),
+                        value,
+                        size,
+                    )?;
+                }
+                self.stack.truncate(start);
+                self.stack.push(address);
+                self.stack.push(count.into());
+            }
+            Op::Neg => *self.top() = self.top().wrapping_neg(),
+            Op::Not => *self.top() = !*self.top(),
+            Op::Div | Op::SignedDiv | Op::Rem | Op::SignedRem if self.stack.last() == Some(&0) => {
+                return Err(Failure::Panic("division by zero".to_string()));
+            }
+            Op::StrEq => {
+                let (b, b_length) = self.pop_fat();
+                let (a, a_length) = self.pop_fat();
+                let equal = self.memory.read(a, a_length)? == self.memory.read(b, b_length)?;
+                self.stack.push(equal.into());
+            }
+            Op::Mark(slot) => {
+                let height = self.stack.len() as u64;
+                self.memory
+                    .write(self.base() + u64::from(slot), &height.to_le_bytes())?;
+            }
+            Op::Reset(slot) => {
+                let height = self.word_at(self.base() + u64::from(slot))?;
+                self.stack
+                    .truncate(usize::try_from(height).expect("heights fit"));
+            }
+            Op::RangeTest {
+                slot,
+                signed,
+                inclusive,
+            } => {
+                let state = self.base() + u64::from(slot);
+                let value = self.wide(state, signed)?;
+                let end = self.wide(state + 8, signed)?;
+                let step = self.wide(state + 16, signed)?;
+                let done = self.word_at(state + 24)? != 0;
+                let more = match (step > 0, inclusive) {
+                    (true, true) => value <= end,
+                    (true, false) => value < end,
+                    (false, true) => value >= end,
+                    (false, false) => value > end,
+                };
+                self.stack.push(u64::from(!done && more));
+            }
+            Op::RangeStep { slot, signed } => {
+                let state = self.base() + u64::from(slot);
+                let next = self.wide(state, signed)? + self.wide(state + 16, signed)?;
+                let fits = if signed {
+                    i64::try_from(next).map(i64::cast_unsigned).ok()
+                } else {
+                    u64::try_from(next).ok()
+                };
+                match fits {
+                    Some(next) => self.memory.write(state, &next.to_le_bytes())?,
+                    None => self.memory.write(state + 24, &1u64.to_le_bytes())?,
+                }
+            }
+            Op::Trap(index) => {
+                return Err(Failure::Panic(self.module.strings[index as usize].clone()));
+            }
+            Op::Print | Op::Println => {
+                let (address, length) = self.pop_fat();
+                self.output.write_all(self.memory.read(address, length)?)?;
+                if op == Op::Println {
+                    self.output.write_all(b"\n")?;
+                }
+            }
+            Op::PrintInt => {
+                let value = self.pop().cast_signed();
+                write!(self.output, "{value}")?;
+            }
+            Op::PrintUInt => {
+                let value = self.pop();
+                write!(self.output, "{value}")?;
+            }
+            Op::Alloc { size, align } => {
+                let count = self.pop();
+                let size = u64::from(size)
+                    .checked_mul(count)
+                    .ok_or_else(|| Failure::Panic("allocation too large".to_string()))?;
+                let address = self.memory.allocate(Layout {
+                    size,
+                    align: align.into(),
+                });
+                self.stack.push(address);
+            }
+            Op::Free => {
+                let address = self.pop();
+                self.memory.free(address)?;
+            }
+            binary => {
+                let rhs = self.pop();
+                let lhs = self.top();
+                *lhs = arithmetic(binary, *lhs, rhs);
+            }
+        }
+        Ok(())
+    }
+
+    fn base(&self) -> u64 {
+        self.frames
+            .last()
+            .expect("code only runs inside a call")
+            .base
+    }
+
+    fn pop(&mut self) -> u64 {
+        self.stack
+            .pop()
+            .expect("the operand stack holds the operands")
+    }
+
+    fn top(&mut self) -> &mut u64 {
+        self.stack
+            .last_mut()
+            .expect("the operand stack holds the operands")
+    }
+
+    /// Pops an array or string, address and length.
+    fn pop_fat(&mut self) -> (u64, u64) {
+        let length = self.pop();
+        (self.pop(), length)
+    }
+
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        self.stack.extend(bytes.chunks(8).map(|chunk| {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u64::from_le_bytes(word)
+        }));
+    }
+
+    /// Pushes the value of `size` bytes at `address`.
+    fn load(&mut self, address: u64, size: u32) -> Result<(), Fault> {
+        if size == 8 {
+            let word = self.word_at(address)?;
+            self.stack.push(word);
+            return Ok(());
+        }
+        let bytes = self.memory.read(address, size.into())?;
+        self.stack.extend(bytes.chunks(8).map(|chunk| {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u64::from_le_bytes(word)
+        }));
+        Ok(())
+    }
+
+    /// Pops a value of `size` bytes and writes it to `address`.
+    fn store(&mut self, address: u64, size: u32) -> Result<(), Fault> {
+        let start = self.stack.len() - words(size);
+        write_words(&mut self.memory, address, &self.stack[start..], size)?;
+        self.stack.truncate(start);
+        Ok(())
+    }
+
+    fn word_at(&self, address: u64) -> Result<u64, Fault> {
+        let bytes = self.memory.read(address, 8)?;
+        Ok(u64::from_le_bytes(
+            bytes.try_into().expect("a word has 8 bytes"),
+        ))
+    }
+
+    /// The word at `address` as a signed or unsigned integer.
+    fn wide(&self, address: u64, signed: bool) -> Result<i128, Fault> {
+        let word = self.word_at(address)?;
+        Ok(if signed {
+            word.cast_signed().into()
+        } else {
+            word.into()
+        })
+    }
+}
+
+/// Places a string constant on the heap.
+fn constant(memory: &mut Memory, string: &str) -> u64 {
+    let address = memory.allocate(Layout {
+        size: string.len() as u64,
+        align: 1,
+    });
+    memory
+        .write(address, string.as_bytes())
+        .expect("the block was just allocated");
+    address
+}
+
+/// Writes the first `size` bytes of `words` to `address`.
+fn write_words(memory: &mut Memory, address: u64, words: &[u64], size: u32) -> Result<(), Fault> {
+    const SMALL: usize = 64;
+    if size as usize <= SMALL {
+        let mut bytes = [0; SMALL];
+        for (i, word) in words.iter().enumerate() {
+            bytes[8 * i..8 * i + 8].copy_from_slice(&word.to_le_bytes());
+        }
+        return memory.write(address, &bytes[..size as usize]);
+    }
+    let mut rest = size as usize;
+    for (i, word) in words.iter().enumerate() {
+        let length = rest.min(8);
+        memory.write(address + 8 * i as u64, &word.to_le_bytes()[..length])?;
+        rest -= length;
+    }
+    Ok(())
+}
+
+/// The bits of the last word of a value of `size` bytes that belong to it.
+fn mask(size: u32) -> u64 {
+    match size % 8 {
+        0 => u64::MAX,
+        rest => (1 << (8 * rest)) - 1,
+    }
+}
+
+fn byte(words: &[u64], index: usize) -> u8 {
+    words[index / 8].to_le_bytes()[index % 8]
+}
+
+fn set_byte(words: &mut [u64], index: usize, value: u8) {
+    let mut bytes = words[index / 8].to_le_bytes();
+    bytes[index % 8] = value;
+    words[index / 8] = u64::from_le_bytes(bytes);
+}
+
+fn check_bounds(index: u64, length: u64) -> Result<(), Failure> {
+    if index < length {
+        Ok(())
+    } else {
+        Err(Failure::Panic(format!(
+            "index {index} is out of bounds for length {length}"
+        )))
+    }
+}
+
+/// Integer arithmetic wraps and shifts use the amount modulo 64, like in
+/// the interpreter.
+fn arithmetic(op: Op, a: u64, b: u64) -> u64 {
+    let (x, y) = (a.cast_signed(), b.cast_signed());
+    let shift = u32::try_from(b % 64).expect("below 64");
+    match op {
+        Op::Add => a.wrapping_add(b),
+        Op::Sub => a.wrapping_sub(b),
+        Op::Mul => a.wrapping_mul(b),
+        Op::Div => a / b,
+        Op::SignedDiv => x.wrapping_div(y).cast_unsigned(),
+        Op::Rem => a % b,
+        Op::SignedRem => x.wrapping_rem(y).cast_unsigned(),
+        Op::Shl => a.wrapping_shl(shift),
+        Op::Shr => a.wrapping_shr(shift),
+        Op::SignedShr => x.wrapping_shr(shift).cast_unsigned(),
+        Op::And => a & b,
+        Op::Or => a | b,
+        Op::Xor => a ^ b,
+        Op::Eq => (a == b).into(),
+        Op::Ne => (a != b).into(),
+        Op::Lt => (a < b).into(),
+        Op::SignedLt => (x < y).into(),
+        Op::Le => (a <= b).into(),
+        Op::SignedLe => (x <= y).into(),
+        Op::Gt => (a > b).into(),
+        Op::SignedGt => (x > y).into(),
+        Op::Ge => (a >= b).into(),
+        Op::SignedGe => (x >= y).into(),
+        op => unreachable!("{op} is not arithmetic"),
+    }
+}
@@ -0,0 +1,185 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! Native code generation from the IR.
+//!
+//! The backends emit assembly for the system assembler. Functions with a
+//! body and the externs of `std`, which the runtime in `runtime.c`
+//! provides, get symbols prefixed with `cr.` so they cannot clash with the
+//! C library. Any other extern function is the C function named by the
+//! last segment of its path. The runtime's `main` calls the program's.
+//! The x86-64 backend keeps values in the registers `regalloc` assigns,
+//! RISC-V and AArch64 share an emitter driven by their `Machine`.
+//! The LLVM backend writes IR text with the same symbols for `llc`. The C
+//! backend instead translates the checked program into source and the
+//! WebAssembly one emits modules that import their I/O from the host.
+
+pub mod aarch64;
+pub mod c;
+pub mod link;
+pub mod llvm;
+pub mod machine;
+pub mod regalloc;
+pub mod riscv64;
+pub mod wasm;
+pub mod x86_64;
+
+use std::{collections::BTreeMap, fmt::Write, str::FromStr};
+
+use crate::ir::{Function, Module, Terminator};
+
+/// The C source of the runtime every native program links.
+pub const RUNTIME: &str = include_str!("codegen/runtime.c");
+
+/// The exit status of a program that panicked.
+pub const PANIC_STATUS: i32 = 101;
+
+/// The instruction set of native code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Architecture {
+    #[default]
+    X86_64,
+    Riscv64,
+    Aarch64,
+}
+
+impl FromStr for Architecture {
+    type Err = ();
+
+    /// Parses the architecture at the start of a target triple.
+    fn from_str(triple: &str) -> Result<Self, ()> {
+        match triple.split('-').next() {
+            Some("x86_64") => Ok(Self::X86_64),
+            Some("riscv64" | "riscv64gc") => Ok(Self::Riscv64),
+            Some("aarch64" | "arm64") => Ok(Self::Aarch64),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Architecture {
+    /// Emits the assembly of a module.
+    #[must_use]
+    pub fn emit(self, module: &Module) -> String {
+        match self {
+            Self::X86_64 => x86_64::emit(module),
+            Self::Riscv64 => riscv64::emit(module),
+            Self::Aarch64 => aarch64::emit(module),
+        }
+    }
+
+    /// The live ranges and places of the values of every function.
+    #[must_use]
+    pub fn live_ranges(self, module: &Module) -> String {
+        match self {
+            Self::X86_64 => x86_64::live_ranges(module),
+            Self::Riscv64 => machine::live_ranges::<riscv64::Riscv64>(module),
+            Self::Aarch64 => machine::live_ranges::<aarch64::Aarch64>(module),
+        }
+    }
+
+    /// The assembler and C compiler building executables, those of the
+    /// system on the same architecture and the GNU cross tools otherwise.
+    #[must_use]
+    pub fn tools(self) -> (String, String) {
+        let (name, host) = match self {
+            Self::X86_64 => ("x86_64", cfg!(target_arch = "x86_64")),
+            Self::Riscv64 => ("riscv64", cfg!(target_arch = "riscv64")),
+            Self::Aarch64 => ("aarch64", cfg!(target_arch = "aarch64")),
+        };
+        if host {
+            return ("as".to_string(), "cc".to_string());
+        }
+        (
+            format!("{name}-linux-gnu-as"),
+            format!("{name}-linux-gnu-gcc"),
+        )
+    }
+}
+
+/// The symbol of a function in the assembly.
+#[must_use]
+pub fn symbol(function: &Function) -> String {
+    let name = function.name.as_str();
+    if function.is_extern() && !name.starts_with("std::") {
+        let base = name.split_once('<').map_or(name, |(base, _)| base);
+        return mangle(base.rsplit("::").next().unwrap_or(base));
+    }
+    if function.is_extern() {
+        // every instance of a generic extern is the same function
+        let base = name.split_once('<').map_or(name, |(base, _)| base);
+        return format!("cr.{}", mangle(base));
+    }
+    format!("cr.{}", mangle(name))
+}
+
+/// Makes a name a valid symbol: `::` becomes `.`, other characters than
+/// letters, digits and `_` become `$` and their code in hex.
+fn mangle(name: &str) -> String {
+    let mut symbol = String::with_capacity(name.len());
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ':' if chars.peek() == Some(&':') => {
+                chars.next();
+                symbol.push('.');
+            }
+            c if c.is_ascii_alphanumeric() || c == '_' || c == '.' => symbol.push(c),
+            c => {
+                write!(symbol, "${:x}", u32::from(c)).expect("writing to a string");
+            }
+        }
+    }
+    symbol
+}
+
+/// The names of the files the traps of a module are in, by file id: their
+/// paths, or the ids for IR read from text.
+#[must_use]
+pub fn trap_files(module: &Module) -> BTreeMap<usize, String> {
+    module
+        .functions
+        .iter()
+        .flat_map(|function| &function.blocks)
+        .filter(|block| matches!(block.terminator, Terminator::Trap(_)))
+        .map(|block| {
+            let location = block.location;
+            let name = location.file.map_or_else(
+                || location.fid.to_string(),
+                |file| file.display().to_string(),
+            );
+            (location.fid, name)
+        })
+        .collect()
+}
+
+/// Writes the string constants of a module as read-only data, followed by
+/// the names of the files of its traps at `.Lfile<id>`, ended by a zero.
+fn data(module: &Module, out: &mut String) {
+    let files = trap_files(module);
+    if module.data.is_empty() && files.is_empty() {
+        return;
+    }
+    out.push_str("\t.section .rodata\n");
+    for (index, string) in module.data.iter().enumerate() {
+        let _ = writeln!(out, ".Ldata{index}:");
+        bytes(string.as_bytes(), out);
+    }
+    for (fid, name) in files {
+        let _ = writeln!(out, ".Lfile{fid}:");
+        bytes(name.as_bytes(), out);
+        out.push_str("\t.byte 0\n");
+    }
+}
+
+fn bytes(bytes: &[u8], out: &mut String) {
+    for chunk in bytes.chunks(16) {
+        let bytes = chunk
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        let _ = writeln!(out, "\t.byte {bytes}");
+    }
+}
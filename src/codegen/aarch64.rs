@@ -0,0 +1,321 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! AArch64 assembly for the AAPCS64 procedure call standard of Linux.
+//!
+//! `x9` to `x12` are scratch registers, `x16` holds offsets and frame sizes
+//! out of the reach of an immediate. `x18` is left to the platform.
+
+use crate::ir::{BinaryOp, CompareOp, Module, Type, UnaryOp};
+
+use super::{
+    machine::{self, Asm, Machine},
+    regalloc::Registers,
+};
+
+/// The register offsets out of the reach of an immediate are built in.
+const TEMPORARY: &str = "x16";
+
+/// The AArch64 target.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Aarch64;
+
+/// Emits the assembly of a module.
+#[must_use]
+pub fn emit(module: &Module) -> String {
+    machine::emit(&Aarch64, module)
+}
+
+/// The 32 bit view of a 64 bit register.
+fn word(register: &str) -> String {
+    format!("w{}", &register[1..])
+}
+
+impl Aarch64 {
+    /// Adds an offset to the stack pointer.
+    fn adjust(self, out: &mut Asm, offset: i64) {
+        let op = if offset < 0 { "sub" } else { "add" };
+        let magnitude = offset.unsigned_abs();
+        if magnitude < 4096 {
+            out.line(format!("{op} sp, sp, #{magnitude}"));
+        } else {
+            self.immediate(out, TEMPORARY, magnitude);
+            out.line(format!("{op} sp, sp, {TEMPORARY}"));
+        }
+    }
+
+    /// Loads or stores with `op` and its unscaled form `unscaled`.
+    fn memory(self, out: &mut Asm, ops: [&str; 2], register: &str, base: &str, offset: i64) {
+        let [op, unscaled] = ops;
+        if offset == 0 {
+            out.line(format!("{op} {register}, [{base}]"));
+        } else if (-256..256).contains(&offset) {
+            out.line(format!("{unscaled} {register}, [{base}, #{offset}]"));
+        } else {
+            self.immediate(out, TEMPORARY, offset.cast_unsigned());
+            out.line(format!("{op} {register}, [{base}, {TEMPORARY}]"));
+        }
+    }
+
+    fn sign_extend(out: &mut Asm, ty: Type, destination: &str, source: &str) {
+        let shift = match ty {
+            Type::I64 | Type::Ptr => {
+                out.line(format!("mov {destination}, {source}"));
+                return;
+            }
+            Type::I1 => 63,
+            ty => 64 - 8 * ty.size(),
+        };
+        out.line(format!("lsl {destination}, {source}, #{shift}"));
+        out.line(format!("asr {destination}, {destination}, #{shift}"));
+    }
+}
+
+impl Machine for Aarch64 {
+    const REGISTERS: Registers = Registers {
+        caller_saved: &["x13", "x14", "x15"],
+        callee_saved: &[
+            "x19", "x20", "x21", "x22", "x23", "x24", "x25", "x26", "x27", "x28",
+        ],
+    };
+    const ARGUMENTS: &'static [&'static str] = &["x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7"];
+    const SCRATCH: [&'static str; 4] = ["x9", "x10", "x11", "x12"];
+    const FRAME: &'static str = "x29";
+
+    fn prologue(&self, out: &mut Asm, frame: u64) {
+        out.line("stp x29, x30, [sp, #-16]!");
+        out.line("mov x29, sp");
+        if frame > 0 {
+            self.adjust(out, -to_i64(frame));
+        }
+    }
+
+    fn epilogue(&self, out: &mut Asm) {
+        out.line("mov sp, x29");
+        out.line("ldp x29, x30, [sp], #16");
+        out.line("ret");
+    }
+
+    fn call(&self, out: &mut Asm, symbol: &str) {
+        out.line(format!("bl {symbol}"));
+    }
+
+    fn reserve(&self, out: &mut Asm, bytes: u64) {
+        self.adjust(out, -to_i64(bytes));
+    }
+
+    fn release(&self, out: &mut Asm, bytes: u64) {
+        self.adjust(out, to_i64(bytes));
+    }
+
+    fn argument(&self, out: &mut Asm, source: &str, index: u64) {
+        self.memory(out, ["str", "stur"], source, "sp", to_i64(8 * index));
+    }
+
+    fn immediate(&self, out: &mut Asm, destination: &str, value: u64) {
+        if value < 1 << 16 {
+            out.line(format!("mov {destination}, #{value}"));
+            return;
+        }
+        if !value < 1 << 16 {
+            out.line(format!("movn {destination}, #{}", !value));
+            return;
+        }
+        let mut first = true;
+        for shift in (0..64).step_by(16) {
+            let chunk = (value >> shift) & 0xffff;
+            if chunk != 0 {
+                let op = if first { "movz" } else { "movk" };
+                out.line(format!("{op} {destination}, #{chunk}, lsl #{shift}"));
+                first = false;
+            }
+        }
+    }
+
+    fn mov(&self, out: &mut Asm, destination: &str, source: &str) {
+        if destination != source {
+            out.line(format!("mov {destination}, {source}"));
+        }
+    }
+
+    fn load(&self, out: &mut Asm, ty: Type, destination: &str, base: &str, offset: i64) {
+        let (ops, register) = match ty {
+            Type::I1 | Type::I8 => (["ldrb", "ldurb"], word(destination)),
+            Type::I16 => (["ldrh", "ldurh"], word(destination)),
+            Type::I32 => (["ldr", "ldur"], word(destination)),
+            Type::I64 | Type::Ptr => (["ldr", "ldur"], destination.to_string()),
+        };
+        self.memory(out, ops, &register, base, offset);
+    }
+
+    fn store(&self, out: &mut Asm, ty: Type, source: &str, base: &str, offset: i64) {
+        let (ops, register) = match ty {
+            Type::I1 | Type::I8 => (["strb", "sturb"], word(source)),
+            Type::I16 => (["strh", "sturh"], word(source)),
+            Type::I32 => (["str", "stur"], word(source)),
+            Type::I64 | Type::Ptr => (["str", "stur"], source.to_string()),
+        };
+        self.memory(out, ops, &register, base, offset);
+    }
+
+    fn address(&self, out: &mut Asm, destination: &str, base: &str, offset: i64) {
+        let op = if offset < 0 { "sub" } else { "add" };
+        let magnitude = offset.unsigned_abs();
+        if magnitude < 4096 {
+            out.line(format!("{op} {destination}, {base}, #{magnitude}"));
+        } else {
+            self.immediate(out, TEMPORARY, magnitude);
+            out.line(format!("{op} {destination}, {base}, {TEMPORARY}"));
+        }
+    }
+
+    fn data(&self, out: &mut Asm, destination: &str, label: &str) {
+        out.line(format!("adrp {destination}, {label}"));
+        out.line(format!("add {destination}, {destination}, :lo12:{label}"));
+    }
+
+    fn zero_extend(&self, out: &mut Asm, ty: Type, register: &str) {
+        if !matches!(ty, Type::I64 | Type::Ptr) {
+            out.line(format!("and {register}, {register}, #{:#x}", ty.mask()));
+        }
+    }
+
+    fn unary(&self, out: &mut Asm, op: UnaryOp, destination: &str, operand: &str) {
+        let op = match op {
+            UnaryOp::Neg => "neg",
+            UnaryOp::Not => "mvn",
+        };
+        out.line(format!("{op} {destination}, {operand}"));
+    }
+
+    fn binary(&self, out: &mut Asm, op: BinaryOp, ty: Type, operands: [&str; 3]) {
+        let [destination, lhs, rhs] = operands;
+        let [.., lhs_scratch, rhs_scratch] = Self::SCRATCH;
+        let bits = match ty {
+            Type::I1 => 1,
+            ty => 8 * ty.size(),
+        };
+        let op = match op {
+            BinaryOp::Add => "add",
+            BinaryOp::Sub => "sub",
+            BinaryOp::Mul => "mul",
+            BinaryOp::And => "and",
+            BinaryOp::Or => "orr",
+            BinaryOp::Xor => "eor",
+            BinaryOp::UDiv => "udiv",
+            BinaryOp::URem => {
+                out.line(format!("udiv {lhs_scratch}, {lhs}, {rhs}"));
+                out.line(format!("msub {destination}, {lhs_scratch}, {rhs}, {lhs}"));
+                return;
+            }
+            BinaryOp::SDiv | BinaryOp::SRem => {
+                // sdiv wraps the quotient of the smallest integer and -1
+                // and yields the remainder 0 through msub
+                let (lhs, rhs) = if bits == 64 {
+                    (lhs, rhs)
+                } else {
+                    Self::sign_extend(out, ty, lhs_scratch, lhs);
+                    Self::sign_extend(out, ty, rhs_scratch, rhs);
+                    (lhs_scratch, rhs_scratch)
+                };
+                if op == BinaryOp::SDiv {
+                    out.line(format!("sdiv {destination}, {lhs}, {rhs}"));
+                } else {
+                    let quotient = if bits == 64 { lhs_scratch } else { TEMPORARY };
+                    out.line(format!("sdiv {quotient}, {lhs}, {rhs}"));
+                    out.line(format!("msub {destination}, {quotient}, {rhs}, {lhs}"));
+                }
+                return;
+            }
+            BinaryOp::Shl | BinaryOp::LShr | BinaryOp::AShr => {
+                // a one bit shift amount is always cut to 0
+                if bits == 1 {
+                    self.mov(out, destination, lhs);
+                    return;
+                }
+                out.line(format!("and {rhs_scratch}, {rhs}, #{}", bits - 1));
+                let (op, lhs) = match op {
+                    BinaryOp::Shl => ("lsl", lhs),
+                    BinaryOp::LShr => ("lsr", lhs),
+                    _ => {
+                        Self::sign_extend(out, ty, lhs_scratch, lhs);
+                        ("asr", lhs_scratch)
+                    }
+                };
+                out.line(format!("{op} {destination}, {lhs}, {rhs_scratch}"));
+                return;
+            }
+        };
+        out.line(format!("{op} {destination}, {lhs}, {rhs}"));
+    }
+
+    fn compare(&self, out: &mut Asm, op: CompareOp, ty: Type, operands: [&str; 3]) {
+        let [destination, mut lhs, mut rhs] = operands;
+        let condition = match op {
+            CompareOp::Eq => "eq",
+            CompareOp::Ne => "ne",
+            CompareOp::Slt => "lt",
+            CompareOp::Sle => "le",
+            CompareOp::Sgt => "gt",
+            CompareOp::Sge => "ge",
+            CompareOp::Ult => "lo",
+            CompareOp::Ule => "ls",
+            CompareOp::Ugt => "hi",
+            CompareOp::Uge => "hs",
+        };
+        let signed = matches!(
+            op,
+            CompareOp::Slt | CompareOp::Sle | CompareOp::Sgt | CompareOp::Sge
+        );
+        if signed && !matches!(ty, Type::I64 | Type::Ptr) {
+            let [.., lhs_scratch, rhs_scratch] = Self::SCRATCH;
+            Self::sign_extend(out, ty, lhs_scratch, lhs);
+            Self::sign_extend(out, ty, rhs_scratch, rhs);
+            (lhs, rhs) = (lhs_scratch, rhs_scratch);
+        }
+        out.line(format!("cmp {lhs}, {rhs}"));
+        out.line(format!("cset {destination}, {condition}"));
+    }
+
+    fn select(&self, out: &mut Asm, destination: &str, operands: [&str; 3]) {
+        let [condition, then, otherwise] = operands;
+        out.line(format!("cmp {condition}, #0"));
+        out.line(format!("csel {destination}, {then}, {otherwise}, ne"));
+    }
+
+    fn copy(&self, out: &mut Asm, to: &str, from: &str, size: u64) {
+        let [target, source, count, byte] = Self::SCRATCH;
+        self.mov(out, target, to);
+        self.mov(out, source, from);
+        self.immediate(out, count, size);
+        out.label("1");
+        out.line(format!("cbz {count}, 2f"));
+        out.line(format!("ldrb {}, [{source}], #1", word(byte)));
+        out.line(format!("strb {}, [{target}], #1", word(byte)));
+        out.line(format!("sub {count}, {count}, #1"));
+        out.line("b 1b");
+        out.label("2");
+    }
+
+    fn jump(&self, out: &mut Asm, label: &str) {
+        out.line(format!("b {label}"));
+    }
+
+    fn branch_if_zero(&self, out: &mut Asm, register: &str, label: &str) {
+        out.line(format!("cbz {register}, {label}"));
+    }
+
+    fn branch_if_equal(&self, out: &mut Asm, lhs: &str, rhs: &str, label: &str) {
+        out.line(format!("cmp {lhs}, {rhs}"));
+        out.line(format!("b.eq {label}"));
+    }
+
+    fn unreachable(&self, out: &mut Asm) {
+        out.line("brk #1");
+    }
+}
+
+fn to_i64(value: u64) -> i64 {
+    i64::try_from(value).expect("frames are small")
+}
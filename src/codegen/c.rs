@@ -0,0 +1,1665 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! C99 source from the checked program.
+//!
+//! The translation keeps the shape of the source: every function, struct
+//! and local keeps its name, with namespaces and type arguments folded into
+//! a prefix and a suffix, enums become tagged unions and `str` a pointer
+//! and a length. Loops and matches are statements in C, so where one is
+//! used as a value its result goes to a variable declared before it, and
+//! operands evaluated before it are kept in temporaries. `break` and
+//! `continue` of an outer, labelled loop become `goto`. A `#line` before
+//! every statement points back at the cringe source.
+//!
+//! The output is a single file that includes the runtime in `c/prelude.h`.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write,
+};
+
+use crate::{
+    interpreter::Intrinsic,
+    module::{DefId, ModuleTree},
+    parser::{
+        ast::{BinaryOperator, UnaryOperator},
+        tokens::Location,
+    },
+    sema::{
+        layout::Layouts,
+        resolve::LocalId,
+        typed::{
+            Arm, ArmBody, Expr, ExprKind, Instance, Iteration, Pattern, PatternKind, Stmt,
+            StmtKind, TypedFunction, TypedProgram, for_each_expr,
+        },
+        types::Ty,
+    },
+};
+
+/// The runtime every translated program starts with.
+pub const PRELUDE: &str = include_str!("c/prelude.h");
+
+/// Words of C and of the headers the prelude includes that no name of the
+/// program may take, besides the names the prelude defines.
+const RESERVED: &str = "\
+    auto break case char const continue default do double else enum extern float for goto if inline \
+    int long register restrict return short signed sizeof static struct switch typedef union \
+    unsigned void volatile while _Bool _Complex _Imaginary main NULL EOF FILE errno assert offsetof \
+    stdin stdout stderr abort abs atexit atof atoi atol bsearch div getenv labs malloc qsort rand \
+    realloc srand strtod strtol strtoul system clearerr fclose feof ferror fgetc fgets fopen fputc \
+    fputs fread freopen fscanf fseek ftell getc getchar gets perror putc puts remove rename rewind \
+    scanf setbuf snprintf sprintf sscanf tmpfile ungetc memchr memcpy memmove memset strcat strchr \
+    strcmp strcpy strerror strlen strncat strncmp strncpy strrchr strstr strtok bool true false \
+    calloc free exit fflush fprintf fwrite printf putchar size_t int8_t int16_t int32_t int64_t \
+    uint8_t uint16_t uint32_t uint64_t INT64_C UINT64_C INT64_MIN INT64_MAX UINT64_MAX";
+
+/// Precedences of C operators, higher binds tighter.
+const PRIMARY: u8 = 16;
+const UNARY: u8 = 15;
+const MULTIPLICATIVE: u8 = 13;
+const ADDITIVE: u8 = 12;
+const SHIFT: u8 = 11;
+const RELATIONAL: u8 = 10;
+const EQUALITY: u8 = 9;
+const BIT_AND: u8 = 8;
+const BIT_XOR: u8 = 7;
+const BIT_OR: u8 = 6;
+const LOGICAL_AND: u8 = 5;
+const LOGICAL_OR: u8 = 4;
+
+/// Translates a checked, monomorphic program into a C file.
+#[must_use]
+pub fn emit(tree: &ModuleTree, program: &TypedProgram, layouts: &Layouts) -> String {
+    let mut translation = Translation::new(tree, program, layouts);
+    let mut functions = String::new();
+    let mut prototypes = String::new();
+    for (instance, function) in &program.functions {
+        if translation.intrinsics.contains_key(&instance.id) {
+            continue;
+        }
+        let signature = translation.signature(instance, function);
+        if function.external {
+            let _ = writeln!(prototypes, "{signature};");
+            continue;
+        }
+        let _ = writeln!(prototypes, "static {signature};");
+        let body = Body::new(&mut translation, function).translate(function);
+        functions.push('\n');
+        functions.push_str(&translation.position(function.location));
+        let _ = write!(functions, "static {signature} {{\n{body}}}\n");
+    }
+    let definitions = translation.definitions();
+
+    let mut out = String::from(PRELUDE);
+    out.push('\n');
+    out.push_str(&definitions);
+    out.push('\n');
+    out.push_str(&prototypes);
+    out.push_str(&functions);
+    out.push_str(&translation.entry());
+    out
+}
+
+/// The names taken in a scope, new ones are made unique with a number.
+#[derive(Debug, Clone, Default)]
+struct Names {
+    taken: HashSet<String>,
+}
+
+impl Names {
+    fn reserve(&mut self, name: &str) {
+        self.taken.insert(name.to_string());
+    }
+
+    fn fresh(&mut self, base: &str) -> String {
+        if self.taken.insert(base.to_string()) {
+            return base.to_string();
+        }
+        let mut number = 2;
+        while !self.taken.insert(format!("{base}_{number}")) {
+            number += 1;
+        }
+        format!("{base}_{number}")
+    }
+}
+
+/// An expression of C with the precedence of its outermost operator.
+#[derive(Debug, Clone)]
+struct Code {
+    text: String,
+    precedence: u8,
+}
+
+impl Code {
+    fn new(text: String, precedence: u8) -> Self {
+        Self { text, precedence }
+    }
+
+    fn primary(text: String) -> Self {
+        Self::new(text, PRIMARY)
+    }
+
+    /// The text as an operand that needs at least `precedence`.
+    fn at(&self, precedence: u8) -> String {
+        if self.precedence < precedence {
+            format!("({})", self.text)
+        } else {
+            self.text.clone()
+        }
+    }
+}
+
+struct Translation<'p> {
+    tree: &'p ModuleTree,
+    program: &'p TypedProgram,
+    layouts: &'p Layouts,
+    intrinsics: HashMap<DefId, Intrinsic>,
+    globals: Names,
+    functions: HashMap<&'p Instance, String>,
+    /// The names of the structs, enums and arrays.
+    types: HashMap<Ty, String>,
+    /// The constants of the tags of every enum.
+    tags: HashMap<Ty, Vec<String>>,
+    /// The array types in the order they were first used.
+    arrays: Vec<Ty>,
+}
+
+impl<'p> Translation<'p> {
+    fn new(tree: &'p ModuleTree, program: &'p TypedProgram, layouts: &'p Layouts) -> Self {
+        let intrinsics = Intrinsic::ALL
+            .iter()
+            .filter_map(|(name, intrinsic)| Some((tree.prelude(name)?, *intrinsic)))
+            .collect::<HashMap<_, _>>();
+        let mut globals = Names::default();
+        for name in RESERVED.split_whitespace().chain(prelude_names()) {
+            globals.reserve(name);
+        }
+        let mut translation = Self {
+            tree,
+            program,
+            layouts,
+            intrinsics,
+            globals,
+            functions: HashMap::new(),
+            types: HashMap::new(),
+            tags: HashMap::new(),
+            arrays: Vec::new(),
+        };
+
+        // externs keep their names, everything else makes way for them
+        for (instance, function) in &program.functions {
+            let name = match translation.intrinsics.get(&instance.id) {
+                Some(intrinsic) => intrinsic_name(*intrinsic).to_string(),
+                None if function.external => {
+                    let name = function.name.split('<').next().unwrap_or_default();
+                    let name = name.rsplit("::").next().unwrap_or(name).to_string();
+                    translation.globals.reserve(&name);
+                    name
+                }
+                None => continue,
+            };
+            translation.functions.insert(instance, name);
+        }
+        for ty in layouts.structs.keys().chain(layouts.enums.keys()) {
+            let name = translation
+                .globals
+                .fresh(&sanitize(&ty.display(tree).to_string()));
+            translation.types.insert(ty.clone(), name);
+        }
+        for ty in layouts.enums.keys() {
+            let Ty::Enum(id, _) = ty else {
+                unreachable!("enum layouts are of enums")
+            };
+            let name = translation.types[ty].clone();
+            let tags = program.enums[id]
+                .variants
+                .iter()
+                .map(|variant| {
+                    let tag = format!("{name}_{}", variant.name);
+                    translation.globals.fresh(&tag)
+                })
+                .collect();
+            translation.tags.insert(ty.clone(), tags);
+        }
+        for (instance, function) in &program.functions {
+            if !translation.functions.contains_key(instance) {
+                let name = translation.globals.fresh(&sanitize(&function.name));
+                translation.functions.insert(instance, name);
+            }
+        }
+        translation
+    }
+
+    fn c_type(&mut self, ty: &Ty) -> String {
+        match ty {
+            Ty::Unit | Ty::Param(..) | Ty::Var(_) | Ty::Error => "void".to_string(),
+            Ty::Bool => "bool".to_string(),
+            Ty::Int => "int64_t".to_string(),
+            Ty::UInt => "uint64_t".to_string(),
+            Ty::Str => "cr_str".to_string(),
+            Ty::Pointer(pointee) => {
+                let pointee = self.c_type(pointee);
+                if pointee.ends_with('*') {
+                    format!("{pointee}*")
+                } else {
+                    format!("{pointee} *")
+                }
+            }
+            Ty::Array(element) => {
+                if let Some(name) = self.types.get(ty) {
+                    return name.clone();
+                }
+                let base = format!("cr_array_{}", self.identifier(element));
+                let name = self.globals.fresh(&base);
+                self.types.insert(ty.clone(), name.clone());
+                self.arrays.push(ty.clone());
+                name
+            }
+            Ty::Struct(..) | Ty::Enum(..) => self.types[ty].clone(),
+        }
+    }
+
+    /// A type as part of a name.
+    fn identifier(&mut self, ty: &Ty) -> String {
+        match ty {
+            Ty::Unit | Ty::Param(..) | Ty::Var(_) | Ty::Error => "unit".to_string(),
+            Ty::Bool => "bool".to_string(),
+            Ty::Int => "int".to_string(),
+            Ty::UInt => "uint".to_string(),
+            Ty::Str => "str".to_string(),
+            Ty::Pointer(pointee) => format!("ptr_{}", self.identifier(pointee)),
+            Ty::Array(element) => format!("{}_array", self.identifier(element)),
+            Ty::Struct(..) | Ty::Enum(..) => self.types[ty].clone(),
+        }
+    }
+
+    /// `static` is left to the caller, externs do not have it.
+    fn signature(&mut self, instance: &Instance, function: &TypedFunction) -> String {
+        let name = self.functions[instance].clone();
+        let mut parameters = Vec::new();
+        let mut names = self.globals.clone();
+        for parameter in &function.parameters {
+            let local = self.program.local(*parameter);
+            if local.ty != Ty::Unit {
+                let ty = self.c_type(&local.ty);
+                parameters.push(declaration(&ty, &names.fresh(&sanitize(&local.name))));
+            }
+        }
+        if parameters.is_empty() {
+            parameters.push("void".to_string());
+        }
+        let result = self.c_type(&function.return_type);
+        declaration(&result, &format!("{name}({})", parameters.join(", ")))
+    }
+
+    /// A `#line` directive for a location.
+    fn position(&self, location: Location) -> String {
+        let file = self.tree.files[location.fid].display().to_string();
+        format!("#line {} {}\n", location.l, string_literal(&file))
+    }
+
+    /// The fields of a struct or of a variant of an enum, with their types
+    /// for the type arguments of `ty`.
+    fn fields(&self, ty: &Ty, variant: Option<usize>) -> Vec<(String, Ty)> {
+        let (Ty::Struct(id, generics) | Ty::Enum(id, generics)) = ty else {
+            unreachable!("only structs and enums have fields")
+        };
+        let fields = match variant {
+            Some(variant) => &self.program.enums[id].variants[variant].fields,
+            None => &self.program.structs[id].fields,
+        };
+        fields
+            .iter()
+            .map(|field| (member(&field.name), field.ty.substitute(*id, generics)))
+            .collect()
+    }
+
+    /// The typedefs and definitions of every struct, enum and array, a type
+    /// after the ones it contains.
+    fn definitions(&mut self) -> String {
+        let mut bodies = String::new();
+        let mut done = HashSet::new();
+        let types = self.layouts.structs.keys().chain(self.layouts.enums.keys());
+        for ty in types.cloned().collect::<Vec<_>>() {
+            self.define(&ty, &mut done, &mut bodies);
+        }
+        let mut arrays = String::new();
+        let mut index = 0;
+        // an array of arrays adds another while they are defined
+        while let Some(ty) = self.arrays.get(index).cloned() {
+            let Ty::Array(element) = &ty else {
+                unreachable!("only arrays are listed")
+            };
+            let pointer = self.c_type(&Ty::pointer((**element).clone()));
+            let name = &self.types[&ty];
+            let _ = write!(
+                arrays,
+                "\nstruct {name} {{\n    {};\n    uint64_t length;\n}};\n",
+                declaration(&pointer, "data")
+            );
+            index += 1;
+        }
+
+        let mut out = String::new();
+        let mut typedefs = self
+            .types
+            .iter()
+            .map(|(ty, name)| {
+                let keyword = if matches!(ty, Ty::Enum(..)) {
+                    "union"
+                } else {
+                    "struct"
+                };
+                format!("typedef {keyword} {name} {name};\n")
+            })
+            .collect::<Vec<_>>();
+        typedefs.sort();
+        out.extend(typedefs);
+        out.push_str(&arrays);
+        out.push_str(&bodies);
+        out
+    }
+
+    fn define(&mut self, ty: &Ty, done: &mut HashSet<Ty>, out: &mut String) {
+        if !matches!(ty, Ty::Struct(..) | Ty::Enum(..)) || !done.insert(ty.clone()) {
+            return;
+        }
+        let Ty::Enum(id, _) = ty else {
+            let fields = self.fields(ty, None);
+            for (_, field) in &fields {
+                self.define(field, done, out);
+            }
+            let name = self.types[ty].clone();
+            let _ = writeln!(out, "\nstruct {name} {{");
+            self.members(&fields, "    ", out);
+            out.push_str("};\n");
+            return;
+        };
+        let variants = &self.program.enums[id].variants;
+        let fields = (0..variants.len())
+            .map(|variant| self.fields(ty, Some(variant)))
+            .collect::<Vec<_>>();
+        for (_, field) in fields.iter().flatten() {
+            self.define(field, done, out);
+        }
+        let name = self.types[ty].clone();
+        let tag = format!("uint{}_t", 8 * self.layouts.enums[ty].tag.size);
+        out.push_str("\nenum {\n");
+        for tag in &self.tags[ty] {
+            let _ = writeln!(out, "    {tag},");
+        }
+        let _ = write!(out, "}};\n\nunion {name} {{\n    {tag} tag;\n");
+        for (variant, fields) in variants.iter().zip(&fields) {
+            let _ = writeln!(out, "    struct {{\n        {tag} tag;");
+            self.members(fields, "        ", out);
+            let _ = writeln!(out, "    }} {};", member(&variant.name));
+        }
+        out.push_str("};\n");
+    }
+
+    fn members(&mut self, fields: &[(String, Ty)], indent: &str, out: &mut String) {
+        let mut empty = true;
+        for (name, ty) in fields {
+            if *ty != Ty::Unit {
+                let ty = self.c_type(ty);
+                let _ = writeln!(out, "{indent}{};", declaration(&ty, name));
+                empty = false;
+            }
+        }
+        // C has no empty structs, variants always have their tag
+        if empty && indent.len() == 4 {
+            let _ = writeln!(out, "{indent}char unused;");
+        }
+    }
+
+    /// The C entry point, calling `main` and keeping the low byte of its
+    /// result as the exit status.
+    fn entry(&self) -> String {
+        let Some((instance, function)) = self
+            .program
+            .functions
+            .iter()
+            .find(|(_, function)| function.name == "main")
+        else {
+            return String::new();
+        };
+        let name = &self.functions[instance];
+        if function.return_type == Ty::Unit {
+            format!("\nint main(void) {{\n    {name}();\n    return 0;\n}}\n")
+        } else {
+            format!("\nint main(void) {{\n    return (int)({name}() & 0xff);\n}}\n")
+        }
+    }
+}
+
+fn intrinsic_name(intrinsic: Intrinsic) -> &'static str {
+    match intrinsic {
+        Intrinsic::Print => "std_print",
+        Intrinsic::Println => "std_println",
+        Intrinsic::PrintInt => "std_print_int",
+        Intrinsic::PrintUInt => "std_print_uint",
+        Intrinsic::Alloc => "std_alloc",
+        Intrinsic::Free => "std_free",
+    }
+}
+
+/// The names the prelude defines, they all have its prefixes.
+fn prelude_names() -> impl Iterator<Item = &'static str> {
+    PRELUDE
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .filter(|word| {
+            ["cr_", "CR_", "std_"]
+                .iter()
+                .any(|prefix| word.starts_with(prefix))
+        })
+}
+
+/// Makes a cringe name a C identifier, `::` and type arguments become `_`.
+fn sanitize(name: &str) -> String {
+    let name = name
+        .replace("::", "_")
+        .replace("[]", "_array")
+        .replace('*', "ptr_");
+    let mut identifier = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            identifier.push(c);
+        } else if c != '>' && !identifier.ends_with('_') {
+            identifier.push('_');
+        }
+    }
+    identifier
+}
+
+/// The name of a field in C, fields of tuple variants are named by their
+/// position and `tag` is taken by the tag of enums.
+fn member(name: &str) -> String {
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{name}")
+    } else if name == "tag"
+        || RESERVED.split_whitespace().any(|word| word == name)
+        || name == "bool"
+    {
+        format!("{name}_")
+    } else {
+        name.to_string()
+    }
+}
+
+/// Declares `name` with type `ty`, `*` sticks to the name.
+fn declaration(ty: &str, name: &str) -> String {
+    if ty.ends_with('*') {
+        format!("{ty}{name}")
+    } else {
+        format!("{ty} {name}")
+    }
+}
+
+/// A C string literal with the bytes of `text`.
+fn string_literal(text: &str) -> String {
+    let mut literal = String::from("\"");
+    for byte in text.bytes() {
+        match byte {
+            b'"' => literal.push_str("\\\""),
+            b'\\' => literal.push_str("\\\\"),
+            // no trigraphs
+            b'?' => literal.push_str("\\?"),
+            b'\n' => literal.push_str("\\n"),
+            b'\t' => literal.push_str("\\t"),
+            0x20..=0x7e => literal.push(char::from(byte)),
+            _ => {
+                let _ = write!(literal, "\\{byte:03o}");
+            }
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+fn literal(value: u64, ty: &Ty) -> Code {
+    if *ty == Ty::Int {
+        let value = value.cast_signed();
+        return match value {
+            i64::MIN => Code::primary("INT64_MIN".to_string()),
+            value if i32::try_from(value).is_err() => Code::primary(format!("INT64_C({value})")),
+            value if value < 0 => Code::new(value.to_string(), UNARY),
+            value => Code::primary(value.to_string()),
+        };
+    }
+    if value > i32::MAX as u64 {
+        Code::primary(format!("UINT64_C({value})"))
+    } else {
+        Code::primary(value.to_string())
+    }
+}
+
+/// The value of an integer constant, also a negated one.
+fn constant(expression: &Expr) -> Option<i64> {
+    match &expression.kind {
+        ExprKind::Int(value) => Some(value.cast_signed()),
+        ExprKind::Unary(UnaryOperator::Minus, operand) => constant(operand).map(i64::wrapping_neg),
+        _ => None,
+    }
+}
+
+fn is_literal(expression: &Expr) -> bool {
+    matches!(
+        expression.kind,
+        ExprKind::Int(_) | ExprKind::Bool(_) | ExprKind::Str(_) | ExprKind::SizeOf(_)
+    ) || constant(expression).is_some()
+}
+
+/// Returns `true` if evaluating the expression calls a function.
+fn has_call(expression: &Expr) -> bool {
+    let mut call = false;
+    expression.for_each_expr(&mut |expression| {
+        call |= matches!(
+            expression.kind,
+            ExprKind::Call(..)
+                | ExprKind::ArrayInit(_)
+                | ExprKind::Loop { .. }
+                | ExprKind::Match { .. }
+        );
+    });
+    call
+}
+
+/// Returns `true` if the expression may panic on its own.
+fn may_trap(expression: &Expr) -> bool {
+    let mut trap = false;
+    expression.for_each_expr(&mut |expression| {
+        trap |= match &expression.kind {
+            ExprKind::Index(base, _) => !matches!(base.ty, Ty::Pointer(_)),
+            ExprKind::Binary(operator, ..) => {
+                matches!(operator, BinaryOperator::Div | BinaryOperator::Rem)
+            }
+            _ => false,
+        };
+    });
+    trap
+}
+
+/// Where a loop or a match leaves its value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Target {
+    /// The value is not used, only its effects.
+    Discard,
+    Variable(String),
+    /// The value is returned from the function.
+    Return,
+}
+
+struct LoopScope {
+    label: Option<String>,
+    /// The C label, `_next` and `_end` are appended for `continue` and
+    /// `break`.
+    jump: Option<String>,
+    target: Target,
+    continued: bool,
+    broken: bool,
+}
+
+/// The translation of the body of a function.
+struct Body<'t, 'p> {
+    translation: &'t mut Translation<'p>,
+    names: Names,
+    labels: Names,
+    locals: HashMap<LocalId, String>,
+    /// Locals whose address is taken, calls may change them.
+    addressed: HashSet<LocalId>,
+    /// Locals that are read or written, others are not bound by patterns.
+    used: HashSet<LocalId>,
+    out: String,
+    depth: usize,
+    /// The file and line of the last `#line`.
+    position: Option<(usize, usize)>,
+    loops: Vec<LoopScope>,
+}
+
+impl<'t, 'p> Body<'t, 'p> {
+    fn new(translation: &'t mut Translation<'p>, function: &TypedFunction) -> Self {
+        let mut names = translation.globals.clone();
+        let program = translation.program;
+        let locals = function
+            .locals()
+            .into_iter()
+            .map(|local| (local, names.fresh(&sanitize(&program.local(local).name))))
+            .collect::<BTreeMap<_, _>>();
+        let (mut addressed, mut used) = (HashSet::new(), HashSet::new());
+        for_each_expr(&function.body, &mut |expression| {
+            if let ExprKind::Local(local) = expression.kind {
+                used.insert(local);
+            }
+            if let ExprKind::Unary(UnaryOperator::Address, operand) = &expression.kind
+                && let ExprKind::Local(local) = operand.kind
+            {
+                addressed.insert(local);
+            }
+        });
+        Self {
+            translation,
+            names,
+            labels: Names::default(),
+            locals: locals.into_iter().collect(),
+            addressed,
+            used,
+            out: String::new(),
+            depth: 1,
+            position: Some((function.location.fid, function.location.l)),
+            loops: Vec::new(),
+        }
+    }
+
+    fn translate(mut self, function: &TypedFunction) -> String {
+        for statement in &function.body {
+            self.statement(statement);
+        }
+        self.out
+    }
+
+    fn line(&mut self, line: impl AsRef<str>) {
+        for _ in 0..self.depth {
+            self.out.push_str("    ");
+        }
+        self.out.push_str(line.as_ref());
+        self.out.push('\n');
+    }
+
+    /// Points the following lines at `location` unless they already are.
+    fn location(&mut self, location: Location) {
+        if self.position != Some((location.fid, location.l)) {
+            self.position = Some((location.fid, location.l));
+            let directive = self.translation.position(location);
+            self.out.push_str(&directive);
+        }
+    }
+
+    /// Runs `f` with the lines it adds set aside, returns them separately.
+    fn capture<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> (T, String) {
+        let saved = std::mem::take(&mut self.out);
+        let result = f(self);
+        (result, std::mem::replace(&mut self.out, saved))
+    }
+
+    fn c_type(&mut self, ty: &Ty) -> String {
+        self.translation.c_type(ty)
+    }
+
+    /// Keeps a value in a new variable.
+    fn temporary(&mut self, ty: &Ty, code: &Code) -> Code {
+        let name = self.names.fresh("tmp");
+        let ty = self.c_type(ty);
+        self.line(format!("{} = {};", declaration(&ty, &name), code.text));
+        Code::primary(name)
+    }
+
+    /// The file, line and column the checks of the prelude report.
+    fn location_arguments(&self, location: Location) -> String {
+        let file = self.translation.tree.files[location.fid]
+            .display()
+            .to_string();
+        format!("{}, {}, {}", string_literal(&file), location.l, location.c)
+    }
+
+    fn nested(&mut self, body: &[Stmt]) {
+        self.depth += 1;
+        for statement in body {
+            self.statement(statement);
+        }
+        self.depth -= 1;
+    }
+
+    fn statement(&mut self, statement: &Stmt) {
+        self.location(statement.location);
+        match &statement.kind {
+            StmtKind::Block(body) => {
+                self.line("{");
+                self.nested(body);
+                self.line("}");
+            }
+            StmtKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let condition = self.operand(condition);
+                self.if_statement(&condition, then, otherwise.as_deref());
+            }
+            StmtKind::For {
+                label,
+                binding,
+                iteration,
+                body,
+            } => {
+                let label = label.as_ref();
+                match &**iteration {
+                    Iteration::Range {
+                        start,
+                        end,
+                        step,
+                        inclusive,
+                    } => {
+                        let range = (start, end, step.as_ref(), *inclusive);
+                        self.range_loop(label, *binding, range, body);
+                    }
+                    Iteration::Elements(iterable) => {
+                        self.elements_loop(label, *binding, iterable, body);
+                    }
+                    Iteration::Iterator { iterator, next } => {
+                        self.iterator_loop(label, *binding, (iterator, next), body);
+                    }
+                }
+            }
+            StmtKind::Return(None) => self.line("return;"),
+            StmtKind::Return(Some(value)) => self.produce(value, &Target::Return),
+            StmtKind::Break { label, value } => {
+                let index = self.target(label.as_ref());
+                if let Some(value) = value {
+                    let target = self.loops[index].target.clone();
+                    self.produce(value, &target);
+                    if target == Target::Return {
+                        return;
+                    }
+                }
+                if index + 1 == self.loops.len() {
+                    self.line("break;");
+                } else {
+                    self.loops[index].broken = true;
+                    let jump = self.loops[index]
+                        .jump
+                        .clone()
+                        .expect("outer loops have labels");
+                    self.line(format!("goto {jump}_end;"));
+                }
+            }
+            StmtKind::Continue { label } => {
+                let index = self.target(label.as_ref());
+                if index + 1 == self.loops.len() {
+                    self.line("continue;");
+                } else {
+                    self.loops[index].continued = true;
+                    let jump = self.loops[index]
+                        .jump
+                        .clone()
+                        .expect("outer loops have labels");
+                    self.line(format!("goto {jump}_next;"));
+                }
+            }
+            StmtKind::Let { local, value } => self.let_statement(*local, value.as_ref()),
+            StmtKind::Assign { target, value } => self.assign(target, value),
+            StmtKind::Expr(expression) => self.produce(expression, &Target::Discard),
+        }
+    }
+
+    fn if_statement(&mut self, condition: &Code, then: &[Stmt], otherwise: Option<&[Stmt]>) {
+        self.line(format!("if ({}) {{", condition.text));
+        self.nested(then);
+        let Some(otherwise) = otherwise else {
+            self.line("}");
+            return;
+        };
+        if let [
+            Stmt {
+                kind:
+                    StmtKind::If {
+                        condition,
+                        then,
+                        otherwise,
+                    },
+                ..
+            },
+        ] = otherwise
+        {
+            self.depth += 1;
+            let (condition, hoisted) = self.capture(|body| body.operand(condition));
+            self.depth -= 1;
+            if hoisted.is_empty() {
+                self.out.truncate(self.out.len() - 2);
+                self.out.push_str("} else ");
+                let depth = std::mem::replace(&mut self.depth, 0);
+                self.if_statement(&condition, then, otherwise.as_deref());
+                self.depth = depth;
+                return;
+            }
+            self.line("} else {");
+            self.out.push_str(&hoisted);
+            self.depth += 1;
+            self.if_statement(&condition, then, otherwise.as_deref());
+            self.depth -= 1;
+            self.line("}");
+            return;
+        }
+        self.line("} else {");
+        self.nested(otherwise);
+        self.line("}");
+    }
+
+    fn let_statement(&mut self, local: LocalId, value: Option<&Expr>) {
+        let program = self.translation.program;
+        let ty = &program.local(local).ty;
+        let name = self.locals[&local].clone();
+        if *ty == Ty::Unit {
+            if let Some(value) = value {
+                self.produce(value, &Target::Discard);
+            }
+            return;
+        }
+        let c_type = self.c_type(ty);
+        match value {
+            Some(
+                value @ Expr {
+                    kind: ExprKind::Loop { .. } | ExprKind::Match { .. },
+                    ..
+                },
+            ) => {
+                self.line(format!("{};", declaration(&c_type, &name)));
+                self.construct(value, Target::Variable(name));
+            }
+            Some(value) => {
+                let code = self.operand(value);
+                self.line(format!("{} = {};", declaration(&c_type, &name), code.text));
+            }
+            None => self.line(format!("{};", declaration(&c_type, &name))),
+        }
+    }
+
+    /// Assigns after evaluating the value and then the place, like cringe.
+    fn assign(&mut self, target: &Expr, value: &Expr) {
+        if value.ty == Ty::Unit {
+            self.produce(value, &Target::Discard);
+            self.produce(target, &Target::Discard);
+            return;
+        }
+        let value_code = self.operand(value);
+        let (place, hoisted) = self.capture(|body| body.operand(target));
+        let reordered = has_call(value) && (has_call(target) || may_trap(target));
+        let value_code = if !is_literal(value) && (!hoisted.is_empty() || reordered) {
+            self.temporary(&value.ty, &value_code)
+        } else {
+            value_code
+        };
+        self.out.push_str(&hoisted);
+        self.line(format!("{} = {};", place.text, value_code.text));
+    }
+
+    /// Evaluates an expression and hands its value to `target`.
+    fn produce(&mut self, value: &Expr, target: &Target) {
+        if !matches!(value.kind, ExprKind::Loop { .. } | ExprKind::Match { .. }) {
+            let code = self.expression(value);
+            self.deliver(target, code, value);
+        } else if value.ty != Ty::Unit {
+            self.construct(value, target.clone());
+        } else {
+            self.construct(value, Target::Discard);
+            if *target == Target::Return {
+                self.line("return;");
+            }
+        }
+    }
+
+    fn deliver(&mut self, target: &Target, code: Option<Code>, value: &Expr) {
+        match (target, code) {
+            (Target::Variable(name), Some(code)) => self.line(format!("{name} = {};", code.text)),
+            (Target::Return, Some(code)) => self.line(format!("return {};", code.text)),
+            (Target::Return, None) => self.line("return;"),
+            (Target::Discard, Some(code)) if has_call(value) => {
+                self.line(format!("{};", code.text));
+            }
+            _ => {}
+        }
+    }
+
+    /// The index of the loop a `break` or `continue` refers to.
+    fn target(&self, label: Option<&String>) -> usize {
+        self.loops
+            .iter()
+            .rposition(|candidate| label.is_none() || candidate.label.as_ref() == label)
+            .expect("checked to be inside the loop")
+    }
+
+    /// The value of an expression that has one.
+    fn operand(&mut self, expression: &Expr) -> Code {
+        self.expression(expression)
+            .expect("operands are not of type unit")
+    }
+
+    /// Expressions evaluated from left to right. C does not order the
+    /// operands of an operator or a call, so one that a later operand could
+    /// change or that has effects of its own is kept in a temporary first.
+    fn operands(&mut self, expressions: &[&Expr]) -> Vec<Option<Code>> {
+        let translated = expressions
+            .iter()
+            .map(|expression| self.capture(|body| body.expression(expression)))
+            .collect::<Vec<_>>();
+        let hoists = translated
+            .iter()
+            .map(|(_, hoisted)| !hoisted.is_empty())
+            .collect::<Vec<_>>();
+        let mut codes = Vec::new();
+        for (index, (code, hoisted)) in translated.into_iter().enumerate() {
+            self.out.push_str(&hoisted);
+            let expression = expressions[index];
+            let statements_follow = hoists[index + 1..].iter().any(|hoists| *hoists);
+            let calls_follow = expressions[index + 1..].iter().any(|later| has_call(later));
+            let keep = match &expression.kind {
+                _ if is_literal(expression) => false,
+                ExprKind::Local(local) if !self.addressed.contains(local) => statements_follow,
+                ExprKind::Unary(UnaryOperator::Address, operand)
+                    if matches!(operand.kind, ExprKind::Local(_)) =>
+                {
+                    false
+                }
+                _ => statements_follow || calls_follow,
+            };
+            codes.push(match code {
+                Some(code) if keep => Some(self.temporary(&expression.ty, &code)),
+                code => code,
+            });
+        }
+        codes
+    }
+
+    /// The value of an expression, `None` for the unit type. Effects of the
+    /// expression that do not fit into a C expression are added as lines.
+    fn expression(&mut self, expression: &Expr) -> Option<Code> {
+        let location = expression.location;
+        let ty = &expression.ty;
+        match &expression.kind {
+            ExprKind::Int(value) => Some(literal(*value, ty)),
+            ExprKind::Bool(value) => Some(Code::primary(value.to_string())),
+            ExprKind::Str(text) => Some(Code::primary(format!("CR_STR({})", string_literal(text)))),
+            ExprKind::SizeOf(ty) => {
+                let size = self.translation.layouts.of(ty).size;
+                Some(literal(size, &Ty::UInt))
+            }
+            ExprKind::Local(local) => {
+                (*ty != Ty::Unit).then(|| Code::primary(self.locals[local].clone()))
+            }
+            ExprKind::Unary(operator, operand) => self.unary(*operator, operand, ty),
+            ExprKind::Binary(operator, lhs, rhs) => {
+                Some(self.binary(*operator, lhs, rhs, location))
+            }
+            ExprKind::Call(instance, arguments) => self.call(instance, arguments, ty),
+            ExprKind::Index(base, index) => Some(self.index(base, index, location)),
+            ExprKind::Field(base, field) => {
+                let base_code = self.operand(base);
+                let (structure, access) = match &base.ty {
+                    Ty::Pointer(pointee) => (&**pointee, "->"),
+                    structure => (structure, "."),
+                };
+                let (name, _) = &self.translation.fields(structure, None)[*field];
+                if *ty == Ty::Unit {
+                    self.deliver(&Target::Discard, Some(base_code), base);
+                    return None;
+                }
+                let text = format!("{}{access}{name}", base_code.at(PRIMARY));
+                Some(Code::primary(text))
+            }
+            ExprKind::Len(base) => {
+                let base_code = self.operand(base);
+                let access = if matches!(base.ty, Ty::Pointer(_)) {
+                    "->"
+                } else {
+                    "."
+                };
+                Some(Code::primary(format!(
+                    "{}{access}length",
+                    base_code.at(PRIMARY)
+                )))
+            }
+            ExprKind::StructInit(_, fields) => {
+                let names = self.translation.fields(ty, None);
+                let initialisers = self.initialisers(fields, &names);
+                let name = self.c_type(ty);
+                let initialisers = if initialisers.is_empty() {
+                    "0".to_string()
+                } else {
+                    initialisers
+                };
+                Some(Code::primary(format!("({name}){{{initialisers}}}")))
+            }
+            ExprKind::Variant(_, variant, fields) => {
+                let names = self.translation.fields(ty, Some(*variant));
+                let initialisers = self.initialisers(fields, &names);
+                let name = self.c_type(ty);
+                let Ty::Enum(id, _) = ty else {
+                    unreachable!("variants are enums")
+                };
+                let program = self.translation.program;
+                let member = member(&program.enums[id].variants[*variant].name);
+                let tag = &self.translation.tags[ty][*variant];
+                let separator = if initialisers.is_empty() { "" } else { ", " };
+                Some(Code::primary(format!(
+                    "({name}){{.{member} = {{.tag = {tag}{separator}{initialisers}}}}}"
+                )))
+            }
+            ExprKind::ArrayInit(elements) => Some(self.array(ty, elements)),
+            ExprKind::Loop { .. } | ExprKind::Match { .. } => {
+                if *ty == Ty::Unit {
+                    self.construct(expression, Target::Discard);
+                    return None;
+                }
+                let name = self.names.fresh("value");
+                let c_type = self.c_type(ty);
+                self.line(format!("{};", declaration(&c_type, &name)));
+                self.construct(expression, Target::Variable(name.clone()));
+                Some(Code::primary(name))
+            }
+            ExprKind::InterfaceCall(..) | ExprKind::Error => {
+                unreachable!("only checked, monomorphic programs are translated")
+            }
+        }
+    }
+
+    /// Designated initialisers of the fields of a struct or variant.
+    fn initialisers(&mut self, fields: &[Expr], names: &[(String, Ty)]) -> String {
+        let fields = fields.iter().collect::<Vec<_>>();
+        let codes = self.operands(&fields);
+        codes
+            .into_iter()
+            .zip(names)
+            .filter_map(|(code, (name, _))| Some(format!(".{name} = {}", code?.text)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn unary(&mut self, operator: UnaryOperator, operand: &Expr, ty: &Ty) -> Option<Code> {
+        if operator == UnaryOperator::Minus
+            && let Some(value) = constant(operand)
+        {
+            return Some(literal(value.wrapping_neg().cast_unsigned(), ty));
+        }
+        let code = self.expression(operand);
+        let Some(code) = code else {
+            // the address of a unit
+            return (operator == UnaryOperator::Address).then(|| Code::primary("NULL".into()));
+        };
+        let text = match operator {
+            UnaryOperator::Dereference if *ty == Ty::Unit => return None,
+            UnaryOperator::Dereference => format!("*{}", code.at(UNARY)),
+            UnaryOperator::Address => format!("&{}", code.at(UNARY)),
+            UnaryOperator::Minus => {
+                return Some(Code::primary(format!("cr_int_neg({})", code.text)));
+            }
+            UnaryOperator::Not if operand.ty == Ty::Bool => format!("!{}", code.at(UNARY)),
+            UnaryOperator::Not => format!("~{}", code.at(UNARY)),
+            UnaryOperator::Sizeof => unreachable!("sizeof is replaced by its value"),
+        };
+        Some(Code::new(text, UNARY))
+    }
+
+    fn binary(
+        &mut self,
+        operator: BinaryOperator,
+        lhs: &Expr,
+        rhs: &Expr,
+        location: Location,
+    ) -> Code {
+        use BinaryOperator as B;
+
+        if let B::LogicalAnd | B::LogicalOr = operator {
+            return self.short_circuit(operator == B::LogicalOr, lhs, rhs);
+        }
+        let codes = self.operands(&[lhs, rhs]);
+        let [Some(left), Some(right)] = &codes[..] else {
+            unreachable!("operands are not of type unit")
+        };
+        if lhs.ty == Ty::Str {
+            let equal = format!("cr_str_eq({}, {})", left.text, right.text);
+            return if operator == B::NotEqual {
+                Code::new(format!("!{equal}"), UNARY)
+            } else {
+                Code::primary(equal)
+            };
+        }
+
+        let signed = lhs.ty == Ty::Int;
+        let kind = if signed { "int" } else { "uint" };
+        let helper = match operator {
+            B::Add if signed => Some("cr_int_add"),
+            B::Sub if signed => Some("cr_int_sub"),
+            B::Mul if signed => Some("cr_int_mul"),
+            B::ShiftLeft if signed => Some("cr_int_shl"),
+            B::ShiftRight if signed => Some("cr_int_shr"),
+            B::ShiftLeft | B::ShiftRight if constant(rhs).is_none_or(|amount| amount >= 64) => {
+                Some(if operator == B::ShiftLeft {
+                    "cr_uint_shl"
+                } else {
+                    "cr_uint_shr"
+                })
+            }
+            _ => None,
+        };
+        if let Some(helper) = helper {
+            return Code::primary(format!("{helper}({}, {})", left.text, right.text));
+        }
+        if let B::Div | B::Rem = operator {
+            let name = if operator == B::Div { "div" } else { "rem" };
+            return Code::primary(format!(
+                "cr_{kind}_{name}({}, {}, {})",
+                left.text,
+                right.text,
+                self.location_arguments(location)
+            ));
+        }
+        let (symbol, precedence) = match operator {
+            B::Mul => ("*", MULTIPLICATIVE),
+            B::Add => ("+", ADDITIVE),
+            B::Sub => ("-", ADDITIVE),
+            B::ShiftLeft => ("<<", SHIFT),
+            B::ShiftRight => (">>", SHIFT),
+            B::Less => ("<", RELATIONAL),
+            B::LessEqual => ("<=", RELATIONAL),
+            B::Greater => (">", RELATIONAL),
+            B::GreaterEqual => (">=", RELATIONAL),
+            B::Equal => ("==", EQUALITY),
+            B::NotEqual => ("!=", EQUALITY),
+            B::And => ("&", BIT_AND),
+            B::Xor => ("^", BIT_XOR),
+            B::Or => ("|", BIT_OR),
+            B::Div | B::Rem | B::LogicalAnd | B::LogicalOr => unreachable!("handled above"),
+        };
+        Code::new(
+            format!(
+                "{} {symbol} {}",
+                Self::side(left, precedence, false),
+                Self::side(right, precedence, true)
+            ),
+            precedence,
+        )
+    }
+
+    /// An operand of a binary operator. Only arithmetic mixes without
+    /// parentheses, as compilers warn about the rest.
+    fn side(code: &Code, precedence: u8, right: bool) -> String {
+        let needed = if precedence < ADDITIVE {
+            UNARY
+        } else if right {
+            precedence + 1
+        } else {
+            precedence
+        };
+        code.at(needed)
+    }
+
+    /// `&&` and `||`, a right-hand side that needs statements of its own
+    /// becomes an `if`.
+    fn short_circuit(&mut self, or: bool, lhs: &Expr, rhs: &Expr) -> Code {
+        let precedence = if or { LOGICAL_OR } else { LOGICAL_AND };
+        let left = self.operand(lhs);
+        self.depth += 1;
+        let (right, hoisted) = self.capture(|body| body.operand(rhs));
+        self.depth -= 1;
+        if hoisted.is_empty() {
+            let symbol = if or { "||" } else { "&&" };
+            let left = if left.precedence == precedence {
+                left.text
+            } else {
+                left.at(UNARY)
+            };
+            let text = format!("{left} {symbol} {}", right.at(UNARY));
+            return Code::new(text, precedence);
+        }
+        let result = self.temporary(&Ty::Bool, &left);
+        let condition = if or {
+            format!("!{}", result.text)
+        } else {
+            result.text.clone()
+        };
+        self.line(format!("if ({condition}) {{"));
+        self.out.push_str(&hoisted);
+        self.depth += 1;
+        self.line(format!("{} = {};", result.text, right.text));
+        self.depth -= 1;
+        self.line("}");
+        result
+    }
+
+    fn call(&mut self, instance: &Instance, arguments: &[Expr], ty: &Ty) -> Option<Code> {
+        let arguments = arguments.iter().collect::<Vec<_>>();
+        let codes = self
+            .operands(&arguments)
+            .into_iter()
+            .flatten()
+            .map(|code| code.text)
+            .collect::<Vec<_>>();
+        if let Some(Intrinsic::Alloc) = self.translation.intrinsics.get(&instance.id) {
+            let element = &instance.generics[0];
+            let size = if *element == Ty::Unit {
+                "0".to_string()
+            } else {
+                format!("sizeof({})", self.c_type(element))
+            };
+            let pointer = self.c_type(ty);
+            let text = format!("({pointer})std_alloc({}, {size})", codes[0]);
+            return Some(Code::new(text, UNARY));
+        }
+        let text = format!(
+            "{}({})",
+            self.translation.functions[instance],
+            codes.join(", ")
+        );
+        if *ty == Ty::Unit {
+            self.line(format!("{text};"));
+            return None;
+        }
+        Some(Code::primary(text))
+    }
+
+    fn index(&mut self, base: &Expr, index: &Expr, location: Location) -> Code {
+        let codes = self.operands(&[base, index]);
+        let [Some(base_code), Some(index_code)] = &codes[..] else {
+            unreachable!("operands are not of type unit")
+        };
+        let at = self.location_arguments(location);
+        match &base.ty {
+            Ty::Str => Code::primary(format!(
+                "cr_str_at({}, {}, {at})",
+                base_code.text, index_code.text
+            )),
+            Ty::Array(_) => {
+                // the array is named twice
+                let simple = base_code
+                    .text
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+                let array = if simple {
+                    base_code.clone()
+                } else {
+                    self.temporary(&base.ty, base_code)
+                };
+                Code::primary(format!(
+                    "{0}.data[cr_index({1}, {0}.length, {at})]",
+                    array.text, index_code.text
+                ))
+            }
+            _ => Code::primary(format!("{}[{}]", base_code.at(PRIMARY), index_code.text)),
+        }
+    }
+
+    /// An array literal, its elements in a new heap block.
+    fn array(&mut self, ty: &Ty, elements: &[Expr]) -> Code {
+        let Ty::Array(element) = ty else {
+            unreachable!("array literals are arrays")
+        };
+        let elements = elements.iter().collect::<Vec<_>>();
+        let codes = self.operands(&elements);
+        let name = self.c_type(ty);
+        let pointer = self.c_type(&Ty::pointer((**element).clone()));
+        let element_type = self.c_type(element);
+        let data = self.names.fresh("elements");
+        self.line(format!(
+            "{} = ({pointer})std_alloc({}, sizeof({element_type}));",
+            declaration(&pointer, &data),
+            codes.len()
+        ));
+        for (index, code) in codes.iter().enumerate() {
+            if let Some(code) = code {
+                self.line(format!("{data}[{index}] = {};", code.text));
+            }
+        }
+        Code::primary(format!("({name}){{{data}, {}}}", codes.len()))
+    }
+
+    /// A loop or a match, its value goes to `target`.
+    fn construct(&mut self, expression: &Expr, target: Target) {
+        match &expression.kind {
+            ExprKind::Loop { label, body } => {
+                self.loop_body(label.as_ref(), target, "for (;;) {", |_| {}, body);
+            }
+            ExprKind::Match { scrutinee, arms } => self.match_expression(scrutinee, arms, &target),
+            _ => unreachable!("only loops and matches are constructs"),
+        }
+    }
+
+    /// A C loop around `body`, `prologue` adds the first lines of every
+    /// pass.
+    fn loop_body(
+        &mut self,
+        label: Option<&String>,
+        target: Target,
+        header: &str,
+        prologue: impl FnOnce(&mut Self),
+        body: &[Stmt],
+    ) {
+        let jump = label.map(|label| self.labels.fresh(label));
+        self.line(header);
+        self.depth += 1;
+        prologue(self);
+        self.loops.push(LoopScope {
+            label: label.cloned(),
+            jump,
+            target,
+            continued: false,
+            broken: false,
+        });
+        for statement in body {
+            self.statement(statement);
+        }
+        let scope = self.loops.pop().expect("pushed above");
+        if scope.continued {
+            let jump = scope
+                .jump
+                .as_ref()
+                .expect("only labelled loops are jumped to");
+            self.line(format!("{jump}_next:;"));
+        }
+        self.depth -= 1;
+        self.line("}");
+        if scope.broken {
+            let jump = scope
+                .jump
+                .as_ref()
+                .expect("only labelled loops are jumped to");
+            self.line(format!("{jump}_end:;"));
+        }
+    }
+
+    fn range_loop(
+        &mut self,
+        label: Option<&String>,
+        binding: LocalId,
+        (start, end, step, inclusive): (&Expr, &Expr, Option<&Expr>, bool),
+        body: &[Stmt],
+    ) {
+        let name = self.locals[&binding].clone();
+        let ty = start.ty.clone();
+        let c_type = self.c_type(&ty);
+        let mut operands = vec![start, end];
+        operands.extend(step);
+        let codes = self.operands(&operands);
+        let (first, last) = (
+            codes[0].clone().expect("integers"),
+            codes[1].clone().expect("integers"),
+        );
+        let bound = |body: &mut Self, suffix: &str| {
+            if is_literal(end) {
+                return (last.text.clone(), None);
+            }
+            let bound = body.names.fresh(&format!("{name}_{suffix}"));
+            (bound.clone(), Some(bound))
+        };
+
+        if step.is_none() && !inclusive {
+            let (bound, declared) = bound(self, "end");
+            let declarations = match declared {
+                Some(declared) => format!("{name} = {}, {declared} = {}", first.text, last.text),
+                None => format!("{name} = {}", first.text),
+            };
+            let header = format!("for ({c_type} {declarations}; {name} < {bound}; {name}++) {{");
+            self.loop_body(label, Target::Discard, &header, |_| {}, body);
+            return;
+        }
+
+        self.line(format!("{} = {};", declaration(&c_type, &name), first.text));
+        let (bound, declared) = bound(self, "last");
+        if declared.is_some() {
+            self.line(format!("{} = {};", declaration(&c_type, &bound), last.text));
+        }
+        let (step, upwards) = match step {
+            None => ("1".to_string(), Some(true)),
+            Some(step) if constant(step).is_some_and(|step| step != 0) => {
+                let code = codes[2].clone().expect("integers");
+                (
+                    code.text,
+                    constant(step).map(|step| step > 0 || ty == Ty::UInt),
+                )
+            }
+            Some(_) => {
+                let code = codes[2].clone().expect("integers");
+                let variable = self.names.fresh(&format!("{name}_step"));
+                let kind = if ty == Ty::Int { "int" } else { "uint" };
+                self.line(format!(
+                    "{} = cr_{kind}_step({}, {});",
+                    declaration(&c_type, &variable),
+                    code.text,
+                    self.location_arguments(start.location)
+                ));
+                (variable, (ty == Ty::UInt).then_some(true))
+            }
+        };
+        let (up, down) = if inclusive { ("<=", ">=") } else { ("<", ">") };
+        let condition = match upwards {
+            Some(true) => format!("{name} {up} {bound}"),
+            Some(false) => format!("{name} {down} {bound}"),
+            None => format!("({step} > 0 ? {name} {up} {bound} : {name} {down} {bound})"),
+        };
+        let wrapped = self.names.fresh(&format!("{name}_wrapped"));
+        let kind = if ty == Ty::Int { "int" } else { "uint" };
+        let header = format!(
+            "for (bool {wrapped} = false; !{wrapped} && {condition}; \
+             {wrapped} = cr_{kind}_advance(&{name}, {step})) {{"
+        );
+        self.loop_body(label, Target::Discard, &header, |_| {}, body);
+    }
+
+    fn elements_loop(
+        &mut self,
+        label: Option<&String>,
+        binding: LocalId,
+        iterable: &Expr,
+        body: &[Stmt],
+    ) {
+        let name = self.locals[&binding].clone();
+        let code = self.operand(iterable);
+        let items = self.names.fresh(&format!("{name}_items"));
+        let index = self.names.fresh(&format!("{name}_index"));
+        let c_type = self.c_type(&iterable.ty);
+        self.line(format!("{} = {};", declaration(&c_type, &items), code.text));
+        let header = format!("for (uint64_t {index} = 0; {index} < {items}.length; {index}++) {{");
+        let program = self.translation.program;
+        let ty = &program.local(binding).ty;
+        let element = self.c_type(ty);
+        let data = if iterable.ty == Ty::Str {
+            "bytes"
+        } else {
+            "data"
+        };
+        let unit = *ty == Ty::Unit;
+        let prologue = |body: &mut Self| {
+            if !unit {
+                body.line(format!(
+                    "{} = {items}.{data}[{index}];",
+                    declaration(&element, &name)
+                ));
+            }
+        };
+        self.loop_body(label, Target::Discard, &header, prologue, body);
+    }
+
+    fn iterator_loop(
+        &mut self,
+        label: Option<&String>,
+        binding: LocalId,
+        (iterator, next): (&Expr, &Expr),
+        body: &[Stmt],
+    ) {
+        let ExprKind::Call(instance, _) = &next.kind else {
+            unreachable!("next is a call")
+        };
+        let Ty::Enum(id, _) = &next.ty else {
+            unreachable!("next returns an option")
+        };
+        let name = self.locals[&binding].clone();
+        let code = self.operand(iterator);
+        // a struct value is iterated as a copy
+        let pointer = if matches!(iterator.ty, Ty::Pointer(_)) {
+            let stable = matches!(&iterator.kind, ExprKind::Unary(UnaryOperator::Address, operand)
+                if matches!(operand.kind, ExprKind::Local(_)));
+            if stable {
+                code.text
+            } else {
+                self.temporary(&iterator.ty, &code).text
+            }
+        } else {
+            let copy = self.names.fresh(&format!("{name}_iterator"));
+            let c_type = self.c_type(&iterator.ty);
+            self.line(format!("{} = {};", declaration(&c_type, &copy), code.text));
+            format!("&{copy}")
+        };
+        let program = self.translation.program;
+        let some = program.enums[id]
+            .variants
+            .iter()
+            .position(|variant| variant.name == "Some")
+            .expect("Option has a variant Some");
+        let option = self.c_type(&next.ty);
+        let option_name = self.names.fresh(&format!("{name}_next"));
+        let function = self.translation.functions[instance].clone();
+        let tag = self.translation.tags[&next.ty][some].clone();
+        let payload = self.translation.fields(&next.ty, Some(some))[0].0.clone();
+        let ty = &program.local(binding).ty;
+        let element = self.c_type(ty);
+        let unit = *ty == Ty::Unit;
+        let prologue = |body: &mut Self| {
+            body.line(format!("{option} {option_name} = {function}({pointer});"));
+            body.line(format!("if ({option_name}.tag != {tag}) {{"));
+            body.line("    break;");
+            body.line("}");
+            if !unit {
+                body.line(format!(
+                    "{} = {option_name}.Some.{payload};",
+                    declaration(&element, &name)
+                ));
+            }
+        };
+        self.loop_body(label, Target::Discard, "for (;;) {", prologue, body);
+    }
+
+    fn match_expression(&mut self, scrutinee: &Expr, arms: &[Arm], target: &Target) {
+        let code = self.operand(scrutinee);
+        let value = match scrutinee.kind {
+            ExprKind::Local(_) => code,
+            _ => self.temporary(&scrutinee.ty, &code),
+        };
+        let mut tests = arms
+            .iter()
+            .map(|arm| {
+                let (mut tests, mut bindings) = (Vec::new(), Vec::new());
+                self.pattern(&arm.pattern, &value.text, &mut tests, &mut bindings);
+                (tests, bindings)
+            })
+            .collect::<Vec<_>>();
+        // matches are exhaustive, the last arm needs no test unless guarded
+        if let (Some(arm), Some((last, _))) = (arms.last(), tests.last_mut())
+            && arm.guard.is_none()
+        {
+            last.clear();
+        }
+
+        if arms.iter().all(|arm| arm.guard.is_none()) {
+            for (index, (arm, (tests, bindings))) in arms.iter().zip(tests).enumerate() {
+                let keyword = if index == 0 { "" } else { "} else " };
+                if tests.is_empty() {
+                    self.line(if index == 0 { "{" } else { "} else {" });
+                    self.arm(arm, &bindings, target);
+                    break;
+                }
+                self.line(format!("{keyword}if ({}) {{", tests.join(" && ")));
+                self.arm(arm, &bindings, target);
+            }
+            self.line("}");
+            return;
+        }
+
+        // a failing guard goes on with the next arm
+        let end = self.labels.fresh("matched");
+        for (arm, (tests, bindings)) in arms.iter().zip(tests) {
+            if tests.is_empty() {
+                self.line("{");
+            } else {
+                self.line(format!("if ({}) {{", tests.join(" && ")));
+            }
+            self.depth += 1;
+            self.bind(&bindings);
+            let guarded = arm.guard.as_ref().map(|guard| self.operand(guard));
+            if let Some(guard) = &guarded {
+                self.line(format!("if ({}) {{", guard.text));
+                self.depth += 1;
+            }
+            self.depth -= 1;
+            self.arm(arm, &[], target);
+            self.depth += 1;
+            self.line(format!("goto {end};"));
+            if guarded.is_some() {
+                self.depth -= 1;
+                self.line("}");
+            }
+            self.depth -= 1;
+            self.line("}");
+            if tests.is_empty() && arm.guard.is_none() {
+                break;
+            }
+        }
+        self.line(format!("{end}:;"));
+    }
+
+    fn bind(&mut self, bindings: &[(LocalId, String)]) {
+        let program = self.translation.program;
+        for (local, access) in bindings {
+            let ty = &program.local(*local).ty;
+            if *ty != Ty::Unit && self.used.contains(local) {
+                let ty = self.c_type(ty);
+                let name = &self.locals[local];
+                self.line(format!("{} = {access};", declaration(&ty, name)));
+            }
+        }
+    }
+
+    fn arm(&mut self, arm: &Arm, bindings: &[(LocalId, String)], target: &Target) {
+        self.depth += 1;
+        self.location(arm.location);
+        self.bind(bindings);
+        match &arm.body {
+            ArmBody::Expr(value) => self.produce(value, target),
+            ArmBody::Block(body) => {
+                for statement in body {
+                    self.statement(statement);
+                }
+            }
+        }
+        self.depth -= 1;
+    }
+
+    /// Collects the conditions for `access` to match `pattern` and the
+    /// locals it binds.
+    fn pattern(
+        &mut self,
+        pattern: &Pattern,
+        access: &str,
+        tests: &mut Vec<String>,
+        bindings: &mut Vec<(LocalId, String)>,
+    ) {
+        match &pattern.kind {
+            PatternKind::Wildcard => {}
+            PatternKind::Binding(local) => bindings.push((*local, access.to_string())),
+            PatternKind::Int(value) => {
+                tests.push(format!("{access} == {}", literal(*value, &pattern.ty).text));
+            }
+            PatternKind::Bool(true) => tests.push(access.to_string()),
+            PatternKind::Bool(false) => tests.push(format!("!{access}")),
+            PatternKind::Str(text) => tests.push(format!(
+                "cr_str_eq({access}, CR_STR({}))",
+                string_literal(text)
+            )),
+            PatternKind::Variant(id, variant, fields) => {
+                let tag = &self.translation.tags[&pattern.ty][*variant];
+                tests.push(format!("{access}.tag == {tag}"));
+                let program = self.translation.program;
+                let name = member(&program.enums[id].variants[*variant].name);
+                let names = self.translation.fields(&pattern.ty, Some(*variant));
+                for (field, (member, _)) in fields.iter().zip(names) {
+                    self.pattern(field, &format!("{access}.{name}.{member}"), tests, bindings);
+                }
+            }
+            PatternKind::Struct(_, fields) => {
+                let names = self.translation.fields(&pattern.ty, None);
+                for (field, (member, _)) in fields.iter().zip(names) {
+                    self.pattern(field, &format!("{access}.{member}"), tests, bindings);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,177 @@
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+/* the runtime of cringe programs translated to C */
+
+#define CR_PANIC_STATUS 101
+#define CR_MAX_ALLOCATION ((uint64_t)1 << 32)
+
+typedef struct cr_str {
+    const uint8_t *bytes;
+    uint64_t length;
+} cr_str;
+
+#define CR_STR(literal) ((cr_str){(const uint8_t *)(literal), sizeof(literal) - 1})
+
+static void cr_trap(const char *message, const char *file, int line, int column) {
+    fflush(stdout);
+    fprintf(stderr, "%s:%d:%d: panicked: %s\n", file, line, column, message);
+    exit(CR_PANIC_STATUS);
+}
+
+static void cr_panic(const char *message) {
+    fflush(stdout);
+    fprintf(stderr, "panicked: %s\n", message);
+    exit(CR_PANIC_STATUS);
+}
+
+/* the index if it is below the length, a panic otherwise */
+static inline uint64_t cr_index(uint64_t index, uint64_t length, const char *file, int line, int column) {
+    if (index >= length) {
+        char message[96];
+        snprintf(message, sizeof message, "index %llu is out of bounds for length %llu",
+                 (unsigned long long)index, (unsigned long long)length);
+        cr_trap(message, file, line, column);
+    }
+    return index;
+}
+
+static inline uint64_t cr_str_at(cr_str text, uint64_t index, const char *file, int line, int column) {
+    return text.bytes[cr_index(index, text.length, file, line, column)];
+}
+
+static inline bool cr_str_eq(cr_str a, cr_str b) {
+    return a.length == b.length && (a.length == 0 || memcmp(a.bytes, b.bytes, a.length) == 0);
+}
+
+/* signed arithmetic wraps around like in cringe instead of being undefined */
+
+static inline int64_t cr_int_add(int64_t a, int64_t b) {
+    return (int64_t)((uint64_t)a + (uint64_t)b);
+}
+
+static inline int64_t cr_int_sub(int64_t a, int64_t b) {
+    return (int64_t)((uint64_t)a - (uint64_t)b);
+}
+
+static inline int64_t cr_int_mul(int64_t a, int64_t b) {
+    return (int64_t)((uint64_t)a * (uint64_t)b);
+}
+
+static inline int64_t cr_int_neg(int64_t a) {
+    return (int64_t)(0 - (uint64_t)a);
+}
+
+static inline int64_t cr_int_div(int64_t a, int64_t b, const char *file, int line, int column) {
+    if (b == 0) {
+        cr_trap("division by zero", file, line, column);
+    }
+    return b == -1 ? cr_int_neg(a) : a / b;
+}
+
+static inline int64_t cr_int_rem(int64_t a, int64_t b, const char *file, int line, int column) {
+    if (b == 0) {
+        cr_trap("division by zero", file, line, column);
+    }
+    return b == -1 ? 0 : a % b;
+}
+
+static inline uint64_t cr_uint_div(uint64_t a, uint64_t b, const char *file, int line, int column) {
+    if (b == 0) {
+        cr_trap("division by zero", file, line, column);
+    }
+    return a / b;
+}
+
+static inline uint64_t cr_uint_rem(uint64_t a, uint64_t b, const char *file, int line, int column) {
+    if (b == 0) {
+        cr_trap("division by zero", file, line, column);
+    }
+    return a % b;
+}
+
+/* shift amounts are taken modulo the width */
+
+static inline int64_t cr_int_shl(int64_t a, int64_t b) {
+    return (int64_t)((uint64_t)a << (b & 63));
+}
+
+static inline int64_t cr_int_shr(int64_t a, int64_t b) {
+    uint64_t shifted = (uint64_t)a >> (b & 63);
+    return (int64_t)(a < 0 ? ~(~(uint64_t)0 >> (b & 63)) | shifted : shifted);
+}
+
+static inline uint64_t cr_uint_shl(uint64_t a, uint64_t b) {
+    return a << (b & 63);
+}
+
+static inline uint64_t cr_uint_shr(uint64_t a, uint64_t b) {
+    return a >> (b & 63);
+}
+
+static inline int64_t cr_int_step(int64_t step, const char *file, int line, int column) {
+    if (step == 0) {
+        cr_trap("range with a step of zero", file, line, column);
+    }
+    return step;
+}
+
+static inline uint64_t cr_uint_step(uint64_t step, const char *file, int line, int column) {
+    if (step == 0) {
+        cr_trap("range with a step of zero", file, line, column);
+    }
+    return step;
+}
+
+/* advances the counter of a range, true once it leaves its type */
+
+static inline bool cr_int_advance(int64_t *counter, int64_t step) {
+    int64_t next = cr_int_add(*counter, step);
+    bool wrapped = step > 0 ? next < *counter : next > *counter;
+    *counter = next;
+    return wrapped;
+}
+
+static inline bool cr_uint_advance(uint64_t *counter, uint64_t step) {
+    uint64_t next = *counter + step;
+    bool wrapped = next < *counter;
+    *counter = next;
+    return wrapped;
+}
+
+/* std */
+
+static inline void std_print(cr_str text) {
+    fwrite(text.bytes, 1, text.length, stdout);
+}
+
+static inline void std_println(cr_str text) {
+    fwrite(text.bytes, 1, text.length, stdout);
+    putchar('\n');
+}
+
+static inline void std_print_int(int64_t value) {
+    printf("%lld", (long long)value);
+}
+
+static inline void std_print_uint(uint64_t value) {
+    printf("%llu", (unsigned long long)value);
+}
+
+static inline void *std_alloc(uint64_t count, uint64_t size) {
+    if (size != 0 && count > CR_MAX_ALLOCATION / size) {
+        cr_panic("allocation too large");
+    }
+    void *memory = calloc(count * size == 0 ? 1 : count * size, 1);
+    if (!memory) {
+        cr_panic("out of memory");
+    }
+    return memory;
+}
+
+static inline void std_free(void *pointer) {
+    free(pointer);
+}
@@ -0,0 +1,104 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! Building executables with the system assembler and C compiler.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process::{self, Command},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::Error;
+
+use super::RUNTIME;
+
+/// Assembles `assembly` with `as` and links it with the runtime by `cc`
+/// into the executable `output`.
+///
+/// # Errors
+///
+/// Returns [`Error::Tool`] if a tool cannot be started or fails and
+/// [`Error::Io`] if the intermediate files cannot be written.
+pub fn link(assembly: &str, output: &Path) -> Result<(), Error> {
+    link_with(assembly, output, "as", "cc")
+}
+
+/// Like [`link`], with the given assembler and C compiler.
+///
+/// # Errors
+///
+/// See [`link`].
+pub fn link_with(
+    assembly: &str,
+    output: &Path,
+    assembler: &str,
+    compiler: &str,
+) -> Result<(), Error> {
+    let directory = Scratch::new()?;
+    let source = directory.0.join("program.s");
+    let object = directory.0.join("program.o");
+    let runtime = directory.0.join("runtime.c");
+    fs::write(&source, assembly)?;
+    fs::write(&runtime, RUNTIME)?;
+    tool(Command::new(assembler).arg("-o").arg(&object).arg(&source))?;
+    tool(
+        Command::new(compiler)
+            .arg("-o")
+            .arg(output)
+            .arg(&object)
+            .arg(&runtime),
+    )
+}
+
+/// Runs a tool to completion, its diagnostics become the error message.
+fn tool(command: &mut Command) -> Result<(), Error> {
+    let name = command.get_program().to_string_lossy().into_owned();
+    let result = command.output().map_err(|error| Error::Tool {
+        tool: name.clone(),
+        message: error.to_string(),
+    })?;
+    if result.status.success() {
+        return Ok(());
+    }
+    Err(Error::Tool {
+        tool: name,
+        message: String::from_utf8_lossy(&result.stderr).trim().to_string(),
+    })
+}
+
+/// A temporary directory removed when dropped.
+struct Scratch(PathBuf);
+
+impl Scratch {
+    /// Creates a directory nobody else has, trying another name while the
+    /// chosen one exists.
+    fn new() -> Result<Self, Error> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        loop {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.subsec_nanos());
+            let name = format!(
+                "cringe-{}-{}-{nanos:08x}",
+                process::id(),
+                COUNT.fetch_add(1, Ordering::Relaxed)
+            );
+            let path = env::temp_dir().join(name);
+            match fs::create_dir(&path) {
+                Ok(()) => return Ok(Self(path)),
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,907 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! Textual LLVM IR for `llc` or `clang`, written without the LLVM
+//! libraries.
+//!
+//! Values keep their integer types, pointers are opaque `ptr`s and block
+//! parameters become phi nodes; an edge that cannot carry its own phi
+//! entries, because its terminator reaches the block twice, goes through a
+//! block of its own. Aggregate types become packed structs with their
+//! padding spelled out, so every field is at the offset of the layout
+//! whatever the data layout of the target, and typed slots are allocated as
+//! such a struct with field addresses computed by `getelementptr`. Symbols
+//! are the ones of the native backend, so the result links with the same
+//! runtime. Source locations become debug metadata for the functions and
+//! every instruction.
+
+use std::{collections::HashMap, fmt::Write, path::PathBuf};
+
+use crate::{
+    ir::{
+        Aggregate, BinaryOp, BlockCall, BlockId, CompareOp, FieldType, Function, Inst, InstKind,
+        Module, Terminator, Type, TypeId, UnaryOp, Value,
+    },
+    parser::tokens::Location,
+    target::Target,
+};
+
+use super::{symbol, trap_files};
+
+/// The runtime's handler for failed checks, see `runtime.c`.
+const TRAP: &str = "cr.trap";
+
+const MEMCPY: &str = "llvm.memcpy.p0.p0.i64";
+
+/// Emits the module as LLVM IR for `target`; `files` are the source files
+/// by their id, for the debug metadata.
+#[must_use]
+pub fn emit(module: &Module, files: &[PathBuf], target: &Target) -> String {
+    let mut metadata = Metadata::new(files);
+    let mut out = String::new();
+    if let Some(root) = files.first() {
+        let _ = writeln!(
+            out,
+            "source_filename = {}",
+            quote(&root.display().to_string())
+        );
+    }
+    let _ = writeln!(out, "target triple = {}", quote(&target.triple));
+    out.push('\n');
+
+    let structs = structs(&module.types);
+    for (aggregate, llvm) in module.types.iter().zip(&structs) {
+        let _ = writeln!(
+            out,
+            "{} = type <{{ {} }}>",
+            llvm.name,
+            llvm.elements.join(", ")
+        );
+        metadata.aggregate(aggregate, &module.types);
+    }
+    if !structs.is_empty() {
+        out.push('\n');
+    }
+
+    for (index, string) in module.data.iter().enumerate() {
+        let value = if string.is_empty() {
+            "zeroinitializer".to_string()
+        } else {
+            format!("c{}", quote(string))
+        };
+        let _ = writeln!(
+            out,
+            "@data.{index} = private unnamed_addr constant [{} x i8] {value}",
+            string.len()
+        );
+    }
+    // the names of the files traps report, ended by a zero
+    let files = trap_files(module);
+    for (fid, name) in &files {
+        let _ = writeln!(
+            out,
+            "@file.{fid} = private unnamed_addr constant [{} x i8] c{}",
+            name.len() + 1,
+            quote(&format!("{name}\0"))
+        );
+    }
+    if !module.data.is_empty() || !files.is_empty() {
+        out.push('\n');
+    }
+
+    let mut declared = Vec::new();
+    for function in module
+        .functions
+        .iter()
+        .filter(|function| function.is_extern())
+    {
+        let symbol = symbol(function);
+        // instances of a generic extern share the symbol
+        if !declared.contains(&symbol) {
+            let _ = writeln!(
+                out,
+                "declare {} {}({})",
+                result(function),
+                global(&symbol),
+                parameters(function, None)
+            );
+            declared.push(symbol);
+        }
+    }
+    let _ = writeln!(
+        out,
+        "declare void {}(ptr, i64, ptr, i64, i64) noreturn",
+        global(TRAP)
+    );
+    let _ = writeln!(out, "declare void @{MEMCPY}(ptr, ptr, i64, i1)");
+
+    for function in module
+        .functions
+        .iter()
+        .filter(|function| !function.is_extern())
+    {
+        out.push('\n');
+        let scope = metadata.subprogram(function);
+        FunctionEmitter::new(module, function, &structs, &mut metadata, scope).emit(&mut out);
+    }
+
+    out.push('\n');
+    metadata.write(&mut out);
+    out
+}
+
+/// An aggregate as an LLVM struct.
+struct Struct {
+    name: String,
+    elements: Vec<String>,
+    /// The element index of every field of the aggregate.
+    indices: Vec<usize>,
+}
+
+fn structs(types: &[Aggregate]) -> Vec<Struct> {
+    let mut names: HashMap<String, usize> = HashMap::new();
+    let mut structs: Vec<Struct> = Vec::new();
+    for aggregate in types {
+        let count = names.entry(aggregate.name.clone()).or_default();
+        *count += 1;
+        let name = if *count == 1 {
+            local(&aggregate.name)
+        } else {
+            local(&format!("{}.{count}", aggregate.name))
+        };
+        let mut elements = Vec::new();
+        let mut indices = Vec::new();
+        let mut end = 0;
+        for field in &aggregate.fields {
+            if field.offset > end {
+                elements.push(format!("[{} x i8]", field.offset - end));
+            }
+            indices.push(elements.len());
+            let size = match field.ty {
+                FieldType::Scalar(ty) => {
+                    elements.push(llvm_type(ty).to_string());
+                    ty.size()
+                }
+                FieldType::Aggregate(id) => {
+                    elements.push(structs[id.index()].name.clone());
+                    types[id.index()].size
+                }
+            };
+            end = field.offset + size;
+        }
+        if aggregate.size > end {
+            elements.push(format!("[{} x i8]", aggregate.size - end));
+        }
+        structs.push(Struct {
+            name,
+            elements,
+            indices,
+        });
+    }
+    structs
+}
+
+fn llvm_type(ty: Type) -> &'static str {
+    match ty {
+        Type::I1 => "i1",
+        Type::I8 => "i8",
+        Type::I16 => "i16",
+        Type::I32 => "i32",
+        Type::I64 => "i64",
+        Type::Ptr => "ptr",
+    }
+}
+
+/// The result type of a function. The runtime expects `main` to return
+/// an `i64` even if it has no result.
+fn result(function: &Function) -> &'static str {
+    match function.signature.result {
+        Some(ty) => llvm_type(ty),
+        None if function.name == "main" => "i64",
+        None => "void",
+    }
+}
+
+/// The parameter list of a function, with the names of the entry block's
+/// parameters for a definition. Narrow integers are zero extended as C
+/// expects.
+fn parameters(function: &Function, names: Option<&[Value]>) -> String {
+    function
+        .signature
+        .params
+        .iter()
+        .enumerate()
+        .map(|(index, ty)| {
+            let extension = if matches!(ty, Type::Ptr | Type::I64) {
+                ""
+            } else {
+                " zeroext"
+            };
+            match names {
+                Some(names) => format!("{}{extension} {}", llvm_type(*ty), name(names[index])),
+                None => format!("{}{extension}", llvm_type(*ty)),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn name(value: Value) -> String {
+    format!("%v{}", value.0)
+}
+
+/// Whether a name can be used without quotes.
+fn plain(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| !c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '$' | '.' | '_'))
+}
+
+fn global(name: &str) -> String {
+    if plain(name) {
+        format!("@{name}")
+    } else {
+        format!("@{}", quote(name))
+    }
+}
+
+fn local(name: &str) -> String {
+    if plain(name) {
+        format!("%{name}")
+    } else {
+        format!("%{}", quote(name))
+    }
+}
+
+/// A string in quotes, with `"`, `\` and unprintable bytes escaped in hex.
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for byte in text.bytes() {
+        if (byte.is_ascii_graphic() && byte != b'"' && byte != b'\\') || byte == b' ' {
+            quoted.push(char::from(byte));
+        } else {
+            let _ = write!(quoted, "\\{byte:02X}");
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The constant of type `ty` with the given bits.
+fn constant(ty: Type, bits: u64) -> String {
+    match ty {
+        Type::I1 if bits & 1 == 0 => "false".to_string(),
+        Type::I1 => "true".to_string(),
+        Type::Ptr if bits == 0 => "null".to_string(),
+        Type::Ptr => format!("inttoptr (i64 {} to ptr)", bits.cast_signed()),
+        ty => {
+            // LLVM reads integers as signed values of their type
+            let shift = 64 - 8 * ty.size();
+            ((bits << shift).cast_signed() >> shift).to_string()
+        }
+    }
+}
+
+/// The debug metadata, numbered in the order the nodes are created.
+struct Metadata<'f> {
+    files: &'f [PathBuf],
+    nodes: Vec<String>,
+    unique: HashMap<String, usize>,
+    /// The node of every source file by its id.
+    file_nodes: HashMap<usize, usize>,
+    /// The composite type of every aggregate.
+    types: Vec<usize>,
+}
+
+impl<'f> Metadata<'f> {
+    fn new(files: &'f [PathBuf]) -> Self {
+        let mut metadata = Self {
+            files,
+            nodes: Vec::new(),
+            unique: HashMap::new(),
+            file_nodes: HashMap::new(),
+            types: Vec::new(),
+        };
+        // the compile unit comes first, it is written once everything
+        // it refers to is known
+        metadata.nodes.push(String::new());
+        metadata
+    }
+
+    /// A node, shared with equal ones.
+    fn node(&mut self, text: String) -> usize {
+        if let Some(index) = self.unique.get(&text) {
+            return *index;
+        }
+        self.nodes.push(text.clone());
+        self.unique.insert(text, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    fn distinct(&mut self, text: &str) -> usize {
+        self.nodes.push(format!("distinct {text}"));
+        self.nodes.len() - 1
+    }
+
+    fn file(&mut self, fid: usize) -> usize {
+        if let Some(node) = self.file_nodes.get(&fid) {
+            return *node;
+        }
+        let path = self.files.get(fid).cloned().unwrap_or_default();
+        let filename = path
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+        let directory = path
+            .parent()
+            .map_or_else(String::new, |parent| parent.display().to_string());
+        let node = self.node(format!(
+            "!DIFile(filename: {}, directory: {})",
+            quote(&filename),
+            quote(&directory)
+        ));
+        self.file_nodes.insert(fid, node);
+        node
+    }
+
+    fn scalar(&mut self, ty: Type) -> usize {
+        self.node(match ty {
+            Type::I1 => "!DIBasicType(name: \"bool\", size: 8, encoding: DW_ATE_boolean)".into(),
+            Type::Ptr => {
+                "!DIDerivedType(tag: DW_TAG_pointer_type, baseType: null, size: 64)".into()
+            }
+            ty => format!(
+                "!DIBasicType(name: \"{}\", size: {}, encoding: DW_ATE_signed)",
+                llvm_type(ty),
+                8 * ty.size()
+            ),
+        })
+    }
+
+    /// Describes the next aggregate, after the ones it contains.
+    fn aggregate(&mut self, aggregate: &Aggregate, types: &[Aggregate]) {
+        let file = self.file(0);
+        let members = aggregate
+            .fields
+            .iter()
+            .map(|field| {
+                let (base, size) = match field.ty {
+                    FieldType::Scalar(ty) => (self.scalar(ty), ty.size()),
+                    FieldType::Aggregate(id) => (self.types[id.index()], types[id.index()].size),
+                };
+                let member = self.node(format!(
+                    "!DIDerivedType(tag: DW_TAG_member, name: {}, file: !{file}, \
+                     baseType: !{base}, size: {}, offset: {})",
+                    quote(&field.name),
+                    8 * size,
+                    8 * field.offset
+                ));
+                format!("!{member}")
+            })
+            .collect::<Vec<_>>();
+        let elements = self.node(format!("!{{{}}}", members.join(", ")));
+        let composite = self.distinct(&format!(
+            "!DICompositeType(tag: DW_TAG_structure_type, name: {}, file: !{file}, size: {}, \
+             align: {}, elements: !{elements})",
+            quote(&aggregate.name),
+            8 * aggregate.size,
+            8 * aggregate.align
+        ));
+        self.types.push(composite);
+    }
+
+    fn subprogram(&mut self, function: &Function) -> usize {
+        let location = function.location;
+        let file = self.file(location.fid);
+        let mut types = vec![match function.signature.result {
+            Some(ty) => format!("!{}", self.scalar(ty)),
+            None => "null".to_string(),
+        }];
+        for ty in &function.signature.params {
+            types.push(format!("!{}", self.scalar(*ty)));
+        }
+        let types = self.node(format!("!{{{}}}", types.join(", ")));
+        let ty = self.node(format!("!DISubroutineType(types: !{types})"));
+        self.distinct(&format!(
+            "!DISubprogram(name: {}, linkageName: {}, scope: !{file}, file: !{file}, line: {}, \
+             type: !{ty}, scopeLine: {}, spFlags: DISPFlagDefinition, unit: !0)",
+            quote(&function.name),
+            quote(&symbol(function)),
+            location.l,
+            location.l
+        ))
+    }
+
+    fn location(&mut self, location: Location, scope: usize) -> usize {
+        self.node(format!(
+            "!DILocation(line: {}, column: {}, scope: !{scope})",
+            location.l, location.c
+        ))
+    }
+
+    fn write(mut self, out: &mut String) {
+        let file = self.file(0);
+        let types = self
+            .types
+            .iter()
+            .map(|node| format!("!{node}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let retained = self.node(format!("!{{{types}}}"));
+        self.nodes[0] = format!(
+            "distinct !DICompileUnit(language: DW_LANG_C99, file: !{file}, producer: \
+             \"cringe-lang\", isOptimized: false, runtimeVersion: 0, emissionKind: FullDebug, \
+             retainedTypes: !{retained})"
+        );
+        let dwarf = self.node("!{i32 7, !\"Dwarf Version\", i32 4}".into());
+        let version = self.node("!{i32 2, !\"Debug Info Version\", i32 3}".into());
+        out.push_str("!llvm.dbg.cu = !{!0}\n");
+        let _ = writeln!(out, "!llvm.module.flags = !{{!{dwarf}, !{version}}}");
+        out.push('\n');
+        for (index, node) in self.nodes.iter().enumerate() {
+            let _ = writeln!(out, "!{index} = {node}");
+        }
+    }
+}
+
+struct FunctionEmitter<'m, 'd> {
+    module: &'m Module,
+    function: &'m Function,
+    structs: &'m [Struct],
+    metadata: &'d mut Metadata<'m>,
+    /// The subprogram of the function.
+    scope: usize,
+    out: String,
+    /// The text of values that are constants.
+    constants: HashMap<Value, String>,
+    /// The bits of the integer constants.
+    bits: HashMap<Value, u64>,
+    /// The aggregate at every address known to hold one.
+    typed: HashMap<Value, TypeId>,
+    /// The label and arguments of every edge into a block.
+    incoming: HashMap<BlockId, Vec<(String, &'m [Value])>>,
+    /// The label of every edge of a terminator that gets a block of its
+    /// own, by block and index of the successor, with its target.
+    edges: HashMap<(BlockId, usize), BlockId>,
+    temporaries: usize,
+}
+
+impl<'m, 'd> FunctionEmitter<'m, 'd> {
+    fn new(
+        module: &'m Module,
+        function: &'m Function,
+        structs: &'m [Struct],
+        metadata: &'d mut Metadata<'m>,
+        scope: usize,
+    ) -> Self {
+        let mut constants = HashMap::new();
+        let mut bits = HashMap::new();
+        for inst in function.blocks.iter().flat_map(|block| &block.insts) {
+            let Some(result) = inst.result else { continue };
+            match inst.kind {
+                InstKind::Const(value) => {
+                    constants.insert(result, constant(function.ty(result), value));
+                    bits.insert(result, value);
+                }
+                InstKind::Data(index) => {
+                    constants.insert(result, format!("@data.{index}"));
+                }
+                _ => {}
+            }
+        }
+
+        let mut incoming: HashMap<BlockId, Vec<(String, &'m [Value])>> = HashMap::new();
+        let mut edges = HashMap::new();
+        for id in function.block_ids() {
+            let successors = function.block(id).terminator.successors();
+            for (index, target) in successors.iter().enumerate() {
+                let twice = successors
+                    .iter()
+                    .filter(|other| other.block == target.block)
+                    .count()
+                    > 1;
+                let label = if twice && !function.block(target.block).params.is_empty() {
+                    edges.insert((id, index), target.block);
+                    format!("%{id}.{index}")
+                } else {
+                    format!("%{id}")
+                };
+                incoming
+                    .entry(target.block)
+                    .or_default()
+                    .push((label, &target.args));
+            }
+        }
+
+        Self {
+            module,
+            function,
+            structs,
+            metadata,
+            scope,
+            out: String::new(),
+            constants,
+            bits,
+            typed: HashMap::new(),
+            incoming,
+            edges,
+            temporaries: 0,
+        }
+    }
+
+    fn emit(mut self, out: &mut String) {
+        let function = self.function;
+        let entry = function.block(BlockId(0));
+        let _ = writeln!(
+            out,
+            "define {} {}({}) !dbg !{} {{",
+            result(function),
+            global(&symbol(function)),
+            parameters(function, Some(&entry.params)),
+            self.scope
+        );
+        for id in function.block_ids() {
+            let _ = writeln!(self.out, "{id}:");
+            if id == BlockId(0) {
+                // every slot is allocated once, on entry
+                let slots = function.blocks.iter().flat_map(|block| &block.insts);
+                for inst in slots.filter(|inst| matches!(inst.kind, InstKind::Slot { .. })) {
+                    self.inst(inst);
+                }
+            }
+            self.phis(id);
+            let block = function.block(id);
+            for inst in &block.insts {
+                if !matches!(inst.kind, InstKind::Slot { .. }) {
+                    self.inst(inst);
+                }
+            }
+            self.terminator(id, &block.terminator, block.location);
+        }
+        let mut edges = self.edges.iter().collect::<Vec<_>>();
+        edges.sort();
+        for ((id, index), target) in edges {
+            let _ = writeln!(self.out, "{id}.{index}:");
+            let _ = writeln!(self.out, "  br label %{target}");
+        }
+        out.push_str(&self.out);
+        out.push_str("}\n");
+    }
+
+    fn operand(&self, value: Value) -> String {
+        self.constants
+            .get(&value)
+            .cloned()
+            .unwrap_or_else(|| name(value))
+    }
+
+    /// The operand with its type.
+    fn typed_operand(&self, value: Value) -> String {
+        format!(
+            "{} {}",
+            llvm_type(self.function.ty(value)),
+            self.operand(value)
+        )
+    }
+
+    fn temporary(&mut self) -> String {
+        self.temporaries += 1;
+        format!("%t{}", self.temporaries)
+    }
+
+    /// Writes an instruction with its location.
+    fn line(&mut self, text: &str, location: Location) {
+        let node = self.metadata.location(location, self.scope);
+        let _ = writeln!(self.out, "  {text}, !dbg !{node}");
+    }
+
+    fn phis(&mut self, id: BlockId) {
+        if id == BlockId(0) {
+            return;
+        }
+        let params = &self.function.block(id).params;
+        for (index, param) in params.iter().enumerate() {
+            let entries = self.incoming[&id]
+                .iter()
+                .map(|(label, args)| format!("[ {}, {label} ]", self.operand(args[index])))
+                .collect::<Vec<_>>();
+            let _ = writeln!(
+                self.out,
+                "  {} = phi {} {}",
+                name(*param),
+                llvm_type(self.function.ty(*param)),
+                entries.join(", ")
+            );
+        }
+    }
+
+    fn inst(&mut self, inst: &Inst) {
+        let location = inst.location;
+        let defined = inst.result.map(|value| self.function.ty(value));
+        let text = match &inst.kind {
+            InstKind::Const(_) | InstKind::Data(_) => return,
+            InstKind::Unary(op, value) => {
+                let ty = llvm_type(self.function.ty(*value));
+                let operand = self.operand(*value);
+                match op {
+                    UnaryOp::Neg => format!("sub {ty} 0, {operand}"),
+                    UnaryOp::Not if ty == "i1" => format!("xor i1 {operand}, true"),
+                    UnaryOp::Not => format!("xor {ty} {operand}, -1"),
+                }
+            }
+            InstKind::Binary(op, lhs, rhs) => {
+                let ty = self.function.ty(*lhs);
+                self.binary(*op, ty, *lhs, *rhs, location)
+            }
+            InstKind::Compare(op, lhs, rhs) => format!(
+                "icmp {} {}, {}",
+                predicate(*op),
+                self.typed_operand(*lhs),
+                self.operand(*rhs)
+            ),
+            InstKind::Select(condition, then, otherwise) => format!(
+                "select i1 {}, {}, {}",
+                self.operand(*condition),
+                self.typed_operand(*then),
+                self.typed_operand(*otherwise)
+            ),
+            InstKind::Extend(value) => {
+                let to = defined.expect("extend has a result");
+                format!("zext {} to {}", self.typed_operand(*value), llvm_type(to))
+            }
+            InstKind::Slot { size, align, ty } => {
+                let result = inst.result.expect("slots have a result");
+                match ty {
+                    Some(ty) => {
+                        self.typed.insert(result, *ty);
+                        format!("alloca {}, align {align}", self.structs[ty.index()].name)
+                    }
+                    None => format!("alloca [{size} x i8], align {align}"),
+                }
+            }
+            InstKind::Load(address) => {
+                let ty = defined.expect("loads have a result");
+                format!(
+                    "load {}, ptr {}, align 1",
+                    llvm_type(ty),
+                    self.operand(*address)
+                )
+            }
+            InstKind::Store { value, address } => format!(
+                "store {}, ptr {}, align 1",
+                self.typed_operand(*value),
+                self.operand(*address)
+            ),
+            InstKind::PtrAdd(address, offset) => {
+                let result = inst.result.expect("ptradd has a result");
+                self.ptr_add(result, *address, *offset)
+            }
+            InstKind::Copy { to, from, size } => format!(
+                "call void @{MEMCPY}(ptr {}, ptr {}, i64 {size}, i1 false)",
+                self.operand(*to),
+                self.operand(*from)
+            ),
+            InstKind::Call(callee, arguments) => {
+                let callee = &self.module.functions[callee.index()];
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| self.typed_operand(*argument))
+                    .collect::<Vec<_>>();
+                format!(
+                    "call {} {}({})",
+                    result(callee),
+                    global(&symbol(callee)),
+                    arguments.join(", ")
+                )
+            }
+        };
+        match (inst.result, &inst.kind) {
+            // a call of `main` returns the `i64` nobody asked for
+            (Some(value), _) => self.line(&format!("{} = {text}", name(value)), location),
+            (None, InstKind::Call(callee, _))
+                if result(&self.module.functions[callee.index()]) != "void" =>
+            {
+                let discarded = self.temporary();
+                self.line(&format!("{discarded} = {text}"), location);
+            }
+            (None, _) => self.line(&text, location),
+        }
+    }
+
+    /// Arithmetic with the semantics of the IR: shift amounts wrap around
+    /// the width and dividing the minimum by -1 wraps instead of being
+    /// undefined.
+    fn binary(
+        &mut self,
+        op: BinaryOp,
+        ty: Type,
+        lhs: Value,
+        rhs: Value,
+        location: Location,
+    ) -> String {
+        let llvm = llvm_type(ty);
+        let (lhs, mut rhs) = (self.operand(lhs), self.operand(rhs));
+        let instruction = match op {
+            BinaryOp::Add => "add",
+            BinaryOp::Sub => "sub",
+            BinaryOp::Mul => "mul",
+            BinaryOp::UDiv => "udiv",
+            BinaryOp::URem => "urem",
+            BinaryOp::And => "and",
+            BinaryOp::Or => "or",
+            BinaryOp::Xor => "xor",
+            BinaryOp::Shl | BinaryOp::LShr | BinaryOp::AShr => {
+                let amount = self.temporary();
+                let bits = 8 * ty.size();
+                self.line(
+                    &format!("{amount} = and {llvm} {rhs}, {}", bits - 1),
+                    location,
+                );
+                rhs = amount;
+                match op {
+                    BinaryOp::Shl => "shl",
+                    BinaryOp::LShr => "lshr",
+                    _ => "ashr",
+                }
+            }
+            BinaryOp::SDiv | BinaryOp::SRem => {
+                let minus_one = self.temporary();
+                self.line(&format!("{minus_one} = icmp eq {llvm} {rhs}, -1"), location);
+                let divisor = self.temporary();
+                self.line(
+                    &format!("{divisor} = select i1 {minus_one}, {llvm} 1, {llvm} {rhs}"),
+                    location,
+                );
+                let (instruction, special) = if op == BinaryOp::SDiv {
+                    let negated = self.temporary();
+                    self.line(&format!("{negated} = sub {llvm} 0, {lhs}"), location);
+                    ("sdiv", negated)
+                } else {
+                    ("srem", "0".to_string())
+                };
+                let quotient = self.temporary();
+                self.line(
+                    &format!("{quotient} = {instruction} {llvm} {lhs}, {divisor}"),
+                    location,
+                );
+                return format!("select i1 {minus_one}, {llvm} {special}, {llvm} {quotient}");
+            }
+        };
+        format!("{instruction} {llvm} {lhs}, {rhs}")
+    }
+
+    /// A field of a typed address by its index, any other address by a
+    /// byte offset.
+    fn ptr_add(&mut self, result: Value, address: Value, offset: Value) -> String {
+        let base = self.operand(address);
+        if let (Some(&outer), Some(&bytes)) = (self.typed.get(&address), self.bits.get(&offset)) {
+            let mut indices = Vec::new();
+            let (mut ty, mut bytes) = (outer, bytes);
+            let found = loop {
+                let aggregate = &self.module.types[ty.index()];
+                let Some((index, field)) = aggregate
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .find(|(_, field)| field.offset <= bytes)
+                else {
+                    break None;
+                };
+                indices.push(self.structs[ty.index()].indices[index]);
+                bytes -= field.offset;
+                match field.ty {
+                    FieldType::Aggregate(inner)
+                        if bytes < self.module.types[inner.index()].size =>
+                    {
+                        if bytes == 0 {
+                            break Some(Some(inner));
+                        }
+                        ty = inner;
+                    }
+                    _ if bytes == 0 => break Some(None),
+                    _ => break None,
+                }
+            };
+            if let Some(inner) = found {
+                if let Some(inner) = inner {
+                    self.typed.insert(result, inner);
+                }
+                let indices = indices
+                    .iter()
+                    .map(|index| format!("i32 {index}"))
+                    .collect::<Vec<_>>();
+                return format!(
+                    "getelementptr inbounds {}, ptr {base}, i32 0, {}",
+                    self.structs[outer.index()].name,
+                    indices.join(", ")
+                );
+            }
+        }
+        format!("getelementptr i8, ptr {base}, i64 {}", self.operand(offset))
+    }
+
+    fn target(&self, id: BlockId, index: usize, target: &BlockCall) -> String {
+        if self.edges.contains_key(&(id, index)) {
+            format!("label %{id}.{index}")
+        } else {
+            format!("label %{}", target.block)
+        }
+    }
+
+    fn terminator(&mut self, id: BlockId, terminator: &Terminator, location: Location) {
+        let text = match terminator {
+            Terminator::Jump(target) => format!("br {}", self.target(id, 0, target)),
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => format!(
+                "br i1 {}, {}, {}",
+                self.operand(*condition),
+                self.target(id, 0, then),
+                self.target(id, 1, otherwise)
+            ),
+            Terminator::Switch {
+                value,
+                cases,
+                default,
+            } => {
+                let ty = self.function.ty(*value);
+                let default = self.target(id, cases.len(), default);
+                let cases = cases
+                    .iter()
+                    .enumerate()
+                    .map(|(index, (case, target))| {
+                        format!(
+                            "{} {}, {}",
+                            llvm_type(ty),
+                            constant(ty, *case),
+                            self.target(id, index, target)
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                format!(
+                    "switch {}, {default} [ {} ]",
+                    self.typed_operand(*value),
+                    cases.join(" ")
+                )
+            }
+            Terminator::Return(Some(value)) => format!("ret {}", self.typed_operand(*value)),
+            Terminator::Return(None) if self.function.name == "main" => "ret i64 0".to_string(),
+            Terminator::Return(None) => "ret void".to_string(),
+            Terminator::Trap(index) => {
+                let length = self.module.data[*index as usize].len();
+                self.line(
+                    &format!(
+                        "call void {}(ptr @data.{index}, i64 {length}, ptr @file.{}, i64 {}, i64 {})",
+                        global(TRAP),
+                        location.fid,
+                        location.l,
+                        location.c
+                    ),
+                    location,
+                );
+                "unreachable".to_string()
+            }
+            Terminator::Unreachable => "unreachable".to_string(),
+        };
+        self.line(&text, location);
+    }
+}
+
+fn predicate(op: CompareOp) -> &'static str {
+    match op {
+        CompareOp::Eq => "eq",
+        CompareOp::Ne => "ne",
+        CompareOp::Slt => "slt",
+        CompareOp::Sle => "sle",
+        CompareOp::Sgt => "sgt",
+        CompareOp::Sge => "sge",
+        CompareOp::Ult => "ult",
+        CompareOp::Ule => "ule",
+        CompareOp::Ugt => "ugt",
+        CompareOp::Uge => "uge",
+    }
+}
@@ -0,0 +1,532 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! Assembly for load-store architectures from a description of the target.
+//!
+//! A [`Machine`] names its registers, how calls pass arguments and which
+//! instructions implement every operation; the emitter here does the rest
+//! for all of them. Values live where the register allocator puts them,
+//! spilled ones are loaded into the scratch register of their operand. The
+//! frame register points at the saved frame register with the return
+//! address above it, then the arguments passed on the stack. Spill slots,
+//! saved registers and the slots of the function follow below.
+
+use std::fmt::Write;
+
+use crate::ir::{
+    BinaryOp, BlockCall, BlockId, CompareOp, Function, Inst, InstKind, Module, Terminator, Type,
+    UnaryOp, Value,
+};
+
+use super::{
+    regalloc::{self, Allocation, Place, Registers},
+    symbol,
+};
+
+/// Assembly text, one instruction per line.
+#[derive(Debug, Default)]
+pub struct Asm(String);
+
+impl Asm {
+    pub fn line(&mut self, line: impl AsRef<str>) {
+        self.0.push('\t');
+        self.0.push_str(line.as_ref());
+        self.0.push('\n');
+    }
+
+    /// Places a label at the current position.
+    pub fn label(&mut self, label: &str) {
+        self.0.push_str(label);
+        self.0.push_str(":\n");
+    }
+}
+
+/// The description of a 64 bit load-store target.
+///
+/// Instruction sequences may write their destination, which can be one of
+/// their operands, only after reading all operands. They may use the
+/// scratch registers that hold none of their operands.
+pub trait Machine {
+    /// Registers values live in.
+    const REGISTERS: Registers;
+    /// The registers passing the first integer arguments, the first also
+    /// returns the result.
+    const ARGUMENTS: &'static [&'static str];
+    /// Registers never allocated: spilled operands are loaded into the
+    /// one of their position, the last breaks cycles of moves.
+    const SCRATCH: [&'static str; 4];
+    /// The register the frame is addressed from.
+    const FRAME: &'static str;
+
+    /// Saves the return address and frame register, points the frame
+    /// register at them and reserves `frame` bytes below.
+    fn prologue(&self, out: &mut Asm, frame: u64);
+    /// Frees the frame and returns.
+    fn epilogue(&self, out: &mut Asm);
+    fn call(&self, out: &mut Asm, symbol: &str);
+    /// Moves the stack pointer down by `bytes`, a multiple of 16.
+    fn reserve(&self, out: &mut Asm, bytes: u64);
+    /// Moves the stack pointer up by `bytes`.
+    fn release(&self, out: &mut Asm, bytes: u64);
+    /// Stores the stack argument with the given index.
+    fn argument(&self, out: &mut Asm, source: &str, index: u64);
+    /// Fixes up an argument of type `ty` for a C function.
+    fn extern_argument(&self, _out: &mut Asm, _ty: Type, _register: &str) {}
+
+    fn immediate(&self, out: &mut Asm, destination: &str, value: u64);
+    fn mov(&self, out: &mut Asm, destination: &str, source: &str);
+    /// Loads a zero extended value of type `ty` from `base` plus `offset`.
+    fn load(&self, out: &mut Asm, ty: Type, destination: &str, base: &str, offset: i64);
+    fn store(&self, out: &mut Asm, ty: Type, source: &str, base: &str, offset: i64);
+    /// Computes `base` plus `offset`.
+    fn address(&self, out: &mut Asm, destination: &str, base: &str, offset: i64);
+    /// Computes the address of a label in the data.
+    fn data(&self, out: &mut Asm, destination: &str, label: &str);
+    /// Clears the bits of a register above its type.
+    fn zero_extend(&self, out: &mut Asm, ty: Type, register: &str);
+
+    fn unary(&self, out: &mut Asm, op: UnaryOp, destination: &str, operand: &str);
+    /// An operation on zero extended operands of type `ty`, the bits of the
+    /// result above it are cleared afterwards.
+    fn binary(&self, out: &mut Asm, op: BinaryOp, ty: Type, operands: [&str; 3]);
+    /// Sets the destination to 1 if the comparison holds, 0 otherwise.
+    fn compare(&self, out: &mut Asm, op: CompareOp, ty: Type, operands: [&str; 3]);
+    /// Picks the second operand if the first is not zero, else the third.
+    fn select(&self, out: &mut Asm, destination: &str, operands: [&str; 3]);
+    /// Copies `size` bytes, the operands are in the first two scratch
+    /// registers or allocated ones.
+    fn copy(&self, out: &mut Asm, to: &str, from: &str, size: u64);
+
+    fn jump(&self, out: &mut Asm, label: &str);
+    fn branch_if_zero(&self, out: &mut Asm, register: &str, label: &str);
+    fn branch_if_equal(&self, out: &mut Asm, lhs: &str, rhs: &str, label: &str);
+    /// Stops the program on code that is never reached.
+    fn unreachable(&self, out: &mut Asm);
+}
+
+/// Emits the assembly of a module.
+#[must_use]
+pub fn emit<M: Machine>(machine: &M, module: &Module) -> String {
+    let mut out = String::new();
+    for (index, function) in module.functions.iter().enumerate() {
+        if !function.is_extern() {
+            FunctionEmitter::new(machine, module, index, function).emit(&mut out);
+        }
+    }
+    super::data(module, &mut out);
+    out.push_str("\t.section .note.GNU-stack,\"\",@progbits\n");
+    out
+}
+
+/// The live ranges and places of the values of every function.
+#[must_use]
+pub fn live_ranges<M: Machine>(module: &Module) -> String {
+    module
+        .functions
+        .iter()
+        .filter(|function| !function.is_extern())
+        .map(|function| regalloc::allocate(function, &M::REGISTERS).to_string())
+        .collect()
+}
+
+struct FunctionEmitter<'m, M> {
+    machine: &'m M,
+    module: &'m Module,
+    index: usize,
+    function: &'m Function,
+    allocation: Allocation,
+    out: Asm,
+    /// The offset from the frame register of every `slot` instruction.
+    slots: Vec<Option<i64>>,
+    frame: i64,
+    /// Labels within blocks, numbered per function.
+    labels: usize,
+}
+
+impl<'m, M: Machine> FunctionEmitter<'m, M> {
+    fn new(machine: &'m M, module: &'m Module, index: usize, function: &'m Function) -> Self {
+        let allocation = regalloc::allocate(function, &M::REGISTERS);
+        let saved = u64::from(allocation.spill_slots) + allocation.saved.len() as u64;
+        let mut frame = to_i64(8 * saved);
+        let mut slots = vec![None; function.types.len()];
+        for inst in function.blocks.iter().flat_map(|block| &block.insts) {
+            if let (InstKind::Slot { size, align, .. }, Some(result)) = (&inst.kind, inst.result) {
+                let (size, align) = (to_i64(*size), to_i64((*align).min(16)));
+                frame = (frame + size + align - 1) / align * align;
+                slots[result.index()] = Some(-frame);
+            }
+        }
+        Self {
+            machine,
+            module,
+            index,
+            function,
+            allocation,
+            out: Asm::default(),
+            slots,
+            frame: (frame + 15) / 16 * 16,
+            labels: 0,
+        }
+    }
+
+    fn block_label(&self, block: BlockId) -> String {
+        format!(".Lf{}b{}", self.index, block.0)
+    }
+
+    fn label(&mut self) -> String {
+        self.labels += 1;
+        format!(".Lf{}l{}", self.index, self.labels)
+    }
+
+    /// The offset from the frame register of a spill slot.
+    fn spill(slot: u32) -> i64 {
+        -8 * (i64::from(slot) + 1)
+    }
+
+    /// The offset from the frame register where the callee saved register
+    /// with the given index is kept.
+    fn save_area(&self, index: usize) -> i64 {
+        Self::spill(self.allocation.spill_slots + u32::try_from(index).expect("few registers"))
+    }
+
+    fn place(&self, value: Value) -> Place {
+        self.allocation
+            .place(value)
+            .unwrap_or_else(|| panic!("{value} is never used"))
+    }
+
+    /// The register holding an operand, the scratch register of its
+    /// position if it is spilled.
+    fn operand(&mut self, value: Value, position: usize) -> &'static str {
+        match self.place(value) {
+            Place::Register(register) => register,
+            Place::Spill(slot) => {
+                let scratch = M::SCRATCH[position];
+                self.machine.load(
+                    &mut self.out,
+                    Type::I64,
+                    scratch,
+                    M::FRAME,
+                    Self::spill(slot),
+                );
+                scratch
+            }
+        }
+    }
+
+    /// Moves a value into a register.
+    fn operand_into(&mut self, value: Value, register: &'static str) {
+        self.transfer(Place::Register(register), self.place(value));
+    }
+
+    fn transfer(&mut self, destination: Place, source: Place) {
+        let frame = M::FRAME;
+        match (destination, source) {
+            (destination, source) if destination == source => {}
+            (Place::Register(destination), Place::Register(source)) => {
+                self.machine.mov(&mut self.out, destination, source);
+            }
+            (Place::Register(destination), Place::Spill(slot)) => {
+                let offset = Self::spill(slot);
+                self.machine
+                    .load(&mut self.out, Type::I64, destination, frame, offset);
+            }
+            (Place::Spill(slot), Place::Register(source)) => {
+                let offset = Self::spill(slot);
+                self.machine
+                    .store(&mut self.out, Type::I64, source, frame, offset);
+            }
+            (Place::Spill(destination), Place::Spill(source)) => {
+                let scratch = M::SCRATCH[0];
+                let (to, from) = (Self::spill(destination), Self::spill(source));
+                self.machine
+                    .load(&mut self.out, Type::I64, scratch, frame, from);
+                self.machine
+                    .store(&mut self.out, Type::I64, scratch, frame, to);
+            }
+        }
+    }
+
+    /// The register to compute a result in.
+    fn destination(&self, result: Option<Value>) -> &'static str {
+        match result.and_then(|result| self.allocation.place(result)) {
+            Some(Place::Register(register)) => register,
+            _ => M::SCRATCH[0],
+        }
+    }
+
+    /// Cuts the result in `register` to its type and spills it if needed.
+    fn finish(&mut self, result: Option<Value>, register: &'static str) {
+        let Some(result) = result else {
+            return;
+        };
+        let Some(place) = self.allocation.place(result) else {
+            return;
+        };
+        let ty = self.function.ty(result);
+        if !matches!(ty, Type::I64 | Type::Ptr) {
+            self.machine.zero_extend(&mut self.out, ty, register);
+        }
+        self.transfer(place, Place::Register(register));
+    }
+
+    fn emit(mut self, out: &mut String) {
+        let symbol = symbol(self.function);
+        let _ = write!(
+            out,
+            "\t.text\n\t.globl {symbol}\n\t.type {symbol}, @function\n{symbol}:\n"
+        );
+        self.machine
+            .prologue(&mut self.out, self.frame.unsigned_abs());
+        for (index, register) in self.allocation.saved.clone().into_iter().enumerate() {
+            let offset = self.save_area(index);
+            self.machine
+                .store(&mut self.out, Type::I64, register, M::FRAME, offset);
+        }
+        let entry = self.function.block(BlockId(0));
+        for (index, param) in entry.params.iter().enumerate() {
+            let Some(place) = self.allocation.place(*param) else {
+                continue;
+            };
+            if let Some(register) = M::ARGUMENTS.get(index) {
+                self.transfer(place, Place::Register(register));
+            } else {
+                let offset = to_i64(16 + 8 * (index - M::ARGUMENTS.len()) as u64);
+                let scratch = M::SCRATCH[0];
+                self.machine
+                    .load(&mut self.out, Type::I64, scratch, M::FRAME, offset);
+                self.transfer(place, Place::Register(scratch));
+            }
+        }
+        for id in self.function.block_ids() {
+            let label = self.block_label(id);
+            self.out.label(&label);
+            let block = self.function.block(id);
+            for inst in &block.insts {
+                self.inst(inst);
+            }
+            self.terminator(&block.terminator, block.location);
+        }
+        out.push_str(&self.out.0);
+        let _ = writeln!(out, "\t.size {symbol}, .-{symbol}");
+    }
+
+    fn inst(&mut self, inst: &Inst) {
+        let result = inst.result;
+        let destination = self.destination(result);
+        match &inst.kind {
+            InstKind::Const(value) => self.machine.immediate(&mut self.out, destination, *value),
+            InstKind::Unary(op, value) => {
+                let operand = self.operand(*value, 0);
+                self.machine.unary(&mut self.out, *op, destination, operand);
+            }
+            InstKind::Binary(op, lhs, rhs) => {
+                let ty = self.function.ty(*lhs);
+                let operands = [destination, self.operand(*lhs, 0), self.operand(*rhs, 1)];
+                self.machine.binary(&mut self.out, *op, ty, operands);
+            }
+            InstKind::Compare(op, lhs, rhs) => {
+                let ty = self.function.ty(*lhs);
+                let operands = [destination, self.operand(*lhs, 0), self.operand(*rhs, 1)];
+                self.machine.compare(&mut self.out, *op, ty, operands);
+            }
+            InstKind::Select(condition, then, otherwise) => {
+                let operands = [
+                    self.operand(*condition, 0),
+                    self.operand(*then, 1),
+                    self.operand(*otherwise, 2),
+                ];
+                self.machine.select(&mut self.out, destination, operands);
+            }
+            InstKind::Extend(value) => {
+                let result = result.expect("extensions have a result");
+                if let Some(place) = self.allocation.place(result) {
+                    // a move, gone if both sides were coalesced
+                    self.transfer(place, self.place(*value));
+                }
+                return;
+            }
+            InstKind::Slot { .. } => {
+                let result = result.expect("slots have a result");
+                let offset = self.slots[result.index()].expect("every slot has a place");
+                self.machine
+                    .address(&mut self.out, destination, M::FRAME, offset);
+            }
+            InstKind::Load(address) => {
+                let ty = self.function.ty(result.expect("loads have a result"));
+                let address = self.operand(*address, 0);
+                self.machine
+                    .load(&mut self.out, ty, destination, address, 0);
+            }
+            InstKind::Store { value, address } => {
+                let ty = self.function.ty(*value);
+                let value = self.operand(*value, 0);
+                let address = self.operand(*address, 1);
+                self.machine.store(&mut self.out, ty, value, address, 0);
+            }
+            InstKind::PtrAdd(address, offset) => {
+                let operands = [
+                    destination,
+                    self.operand(*address, 0),
+                    self.operand(*offset, 1),
+                ];
+                self.machine
+                    .binary(&mut self.out, BinaryOp::Add, Type::I64, operands);
+            }
+            InstKind::Copy { to, from, size } => {
+                let (to, from) = (self.operand(*to, 0), self.operand(*from, 1));
+                self.machine.copy(&mut self.out, to, from, *size);
+            }
+            InstKind::Data(index) => {
+                self.machine
+                    .data(&mut self.out, destination, &format!(".Ldata{index}"));
+            }
+            InstKind::Call(callee, arguments) => {
+                self.call(&self.module.functions[callee.index()], arguments);
+                if result.is_some() {
+                    self.machine
+                        .mov(&mut self.out, destination, M::ARGUMENTS[0]);
+                }
+            }
+        }
+        self.finish(result, destination);
+    }
+
+    /// Calls a function, the result is left in the first argument register.
+    fn call(&mut self, callee: &Function, arguments: &[Value]) {
+        let registers = M::ARGUMENTS.len().min(arguments.len());
+        let stacked = (arguments.len() - registers) as u64;
+        // the stack is 16 byte aligned at every call
+        let reserved = (8 * stacked).div_ceil(16) * 16;
+        if reserved > 0 {
+            self.machine.reserve(&mut self.out, reserved);
+        }
+        for (index, argument) in (0..).zip(&arguments[registers..]) {
+            let source = self.operand(*argument, 0);
+            self.machine.argument(&mut self.out, source, index);
+        }
+        for (argument, register) in arguments.iter().zip(M::ARGUMENTS) {
+            self.operand_into(*argument, register);
+            if callee.is_extern() {
+                let ty = self.function.ty(*argument);
+                self.machine.extern_argument(&mut self.out, ty, register);
+            }
+        }
+        self.machine.call(&mut self.out, &symbol(callee));
+        if reserved > 0 {
+            self.machine.release(&mut self.out, reserved);
+        }
+    }
+
+    /// Passes the arguments of a jump to the parameters of its target, all
+    /// are read before any is written.
+    fn arguments(&mut self, target: &BlockCall) {
+        let params = &self.function.block(target.block).params;
+        let mut moves = target
+            .args
+            .iter()
+            .zip(params)
+            .filter_map(|(arg, param)| {
+                let destination = self.allocation.place(*param)?;
+                let source = self.place(*arg);
+                (destination != source).then_some((destination, source))
+            })
+            .collect::<Vec<_>>();
+        let temporary = Place::Register(M::SCRATCH[3]);
+        while !moves.is_empty() {
+            let ready = moves
+                .iter()
+                .position(|(destination, _)| moves.iter().all(|(_, source)| source != destination));
+            if let Some(index) = ready {
+                let (destination, source) = moves.remove(index);
+                self.transfer(destination, source);
+                continue;
+            }
+            // only cycles are left, the first one is broken by saving
+            // the value its first move overwrites
+            let blocked = moves[0].0;
+            self.transfer(temporary, blocked);
+            for (_, source) in &mut moves {
+                if *source == blocked {
+                    *source = temporary;
+                }
+            }
+        }
+    }
+
+    fn jump(&mut self, target: &BlockCall) {
+        self.arguments(target);
+        let label = self.block_label(target.block);
+        self.machine.jump(&mut self.out, &label);
+    }
+
+    fn terminator(&mut self, terminator: &Terminator, location: crate::parser::tokens::Location) {
+        match terminator {
+            Terminator::Jump(target) => self.jump(target),
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let label = self.label();
+                let condition = self.operand(*condition, 0);
+                self.machine
+                    .branch_if_zero(&mut self.out, condition, &label);
+                self.jump(then);
+                self.out.label(&label);
+                self.jump(otherwise);
+            }
+            Terminator::Switch {
+                value,
+                cases,
+                default,
+            } => {
+                let value = self.operand(*value, 0);
+                let labels = cases.iter().map(|_| self.label()).collect::<Vec<_>>();
+                let scratch = M::SCRATCH[1];
+                for ((case, _), label) in cases.iter().zip(&labels) {
+                    self.machine.immediate(&mut self.out, scratch, *case);
+                    self.machine
+                        .branch_if_equal(&mut self.out, value, scratch, label);
+                }
+                self.jump(default);
+                for ((_, target), label) in cases.iter().zip(&labels) {
+                    self.out.label(label);
+                    self.jump(target);
+                }
+            }
+            Terminator::Return(value) => {
+                if let Some(value) = value {
+                    self.operand_into(*value, M::ARGUMENTS[0]);
+                } else if self.function.name == "main" {
+                    self.machine.immediate(&mut self.out, M::ARGUMENTS[0], 0);
+                }
+                for (index, register) in self.allocation.saved.clone().into_iter().enumerate() {
+                    let offset = self.save_area(index);
+                    self.machine
+                        .load(&mut self.out, Type::I64, register, M::FRAME, offset);
+                }
+                self.machine.epilogue(&mut self.out);
+            }
+            Terminator::Trap(index) => {
+                let length = self.module.data[*index as usize].len() as u64;
+                let registers = M::ARGUMENTS;
+                let out = &mut self.out;
+                self.machine
+                    .data(out, registers[0], &format!(".Ldata{index}"));
+                self.machine.immediate(out, registers[1], length);
+                self.machine
+                    .data(out, registers[2], &format!(".Lfile{}", location.fid));
+                self.machine.immediate(out, registers[3], location.l as u64);
+                self.machine.immediate(out, registers[4], location.c as u64);
+                self.machine.call(&mut self.out, "cr.trap");
+            }
+            Terminator::Unreachable => self.machine.unreachable(&mut self.out),
+        }
+    }
+}
+
+fn to_i64(value: u64) -> i64 {
+    i64::try_from(value).expect("frames are small")
+}
@@ -0,0 +1,391 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! Linear scan register allocation on the IR.
+//!
+//! The instructions of a function are numbered in block order, two apart
+//! so a block ends between its terminator and the next block. Liveness
+//! over the blocks gives every value one interval, from its definition to
+//! its last use, covering the holes between. The intervals are handed
+//! registers in order of their start, spilling the one that ends last when
+//! none is free. A value live across a call only gets a register the call
+//! preserves. Block arguments and extensions are moves, which disappear
+//! when the allocator can give both sides the same register.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::ir::{Function, InstKind, Value};
+
+/// The registers of a target the allocator hands out.
+#[derive(Debug, Clone, Copy)]
+pub struct Registers {
+    /// Registers a call may overwrite.
+    pub caller_saved: &'static [&'static str],
+    /// Registers a call preserves, the function saves those it uses.
+    pub callee_saved: &'static [&'static str],
+}
+
+/// Where a value lives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Place {
+    Register(&'static str),
+    /// The index of an eight byte spill slot in the frame.
+    Spill(u32),
+}
+
+/// The positions a value is live in, both included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interval {
+    pub value: Value,
+    pub start: u32,
+    pub end: u32,
+    /// Whether a call happens while the value is live.
+    pub crosses_call: bool,
+}
+
+/// The place of every value of a function.
+#[derive(Debug, Clone)]
+pub struct Allocation {
+    name: String,
+    /// The first and last position of every block.
+    blocks: Vec<(u32, u32)>,
+    /// Ordered by start.
+    pub intervals: Vec<Interval>,
+    places: Vec<Option<Place>>,
+    pub spill_slots: u32,
+    /// The callee saved registers in use.
+    pub saved: Vec<&'static str>,
+    /// The moves of block arguments and extensions.
+    pub moves: usize,
+    /// The moves between values in the same place.
+    pub coalesced: usize,
+}
+
+impl Allocation {
+    /// The place of a value, `None` if it is never used.
+    #[must_use]
+    pub fn place(&self, value: Value) -> Option<Place> {
+        self.places[value.index()]
+    }
+}
+
+impl fmt::Display for Allocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "fn {}", self.name)?;
+        let blocks = self
+            .blocks
+            .iter()
+            .enumerate()
+            .map(|(index, (start, end))| format!("b{index} {start}..{end}"))
+            .collect::<Vec<_>>();
+        writeln!(f, "  {}", blocks.join(", "))?;
+        for interval in &self.intervals {
+            let range = format!("[{}, {}]", interval.start, interval.end);
+            write!(f, "  {:<6} {range:<12} ", interval.value.to_string())?;
+            match self.place(interval.value) {
+                Some(Place::Register(register)) => write!(f, "{register}")?,
+                Some(Place::Spill(slot)) => write!(f, "spill {slot}")?,
+                None => unreachable!("every interval is allocated"),
+            }
+            if interval.crosses_call {
+                write!(f, ", crosses a call")?;
+            }
+            writeln!(f)?;
+        }
+        write!(
+            f,
+            "  {} of {} moves coalesced, {} spill slots",
+            self.coalesced, self.moves, self.spill_slots
+        )?;
+        if !self.saved.is_empty() {
+            write!(f, ", saves {}", self.saved.join(" "))?;
+        }
+        writeln!(f)
+    }
+}
+
+/// Allocates the values of a function with a body to the registers.
+///
+/// # Panics
+///
+/// Panics if the function needs more than `u32::MAX` spill slots.
+#[must_use]
+pub fn allocate(function: &Function, registers: &Registers) -> Allocation {
+    let (blocks, calls) = number(function);
+    let intervals = intervals(function, &blocks, &calls);
+    let hints = hints(function);
+    let mut places = vec![None; function.types.len()];
+
+    // registers, spilling the interval that ends last when all are taken
+    let mut active: Vec<(usize, &'static str)> = Vec::new();
+    let mut spilled = Vec::new();
+    for (index, interval) in intervals.iter().enumerate() {
+        active.retain(|(other, _)| intervals[*other].end > interval.start);
+        let allowed = if interval.crosses_call {
+            registers.callee_saved.to_vec()
+        } else {
+            [registers.caller_saved, registers.callee_saved].concat()
+        };
+        let free = |register: &&'static str| {
+            allowed.contains(register) && active.iter().all(|(_, taken)| taken != register)
+        };
+        let hinted =
+            hints
+                .get(&interval.value)
+                .into_iter()
+                .flatten()
+                .find_map(|other| match places[other.index()] {
+                    Some(Place::Register(register)) if free(&register) => Some(register),
+                    _ => None,
+                });
+        if let Some(register) = hinted.or_else(|| allowed.iter().copied().find(|r| free(r))) {
+            places[interval.value.index()] = Some(Place::Register(register));
+            active.push((index, register));
+            continue;
+        }
+        let victim = active
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, register))| allowed.contains(register))
+            .max_by_key(|(_, (other, _))| intervals[*other].end)
+            .map(|(position, _)| position)
+            .filter(|position| intervals[active[*position].0].end > interval.end);
+        if let Some(position) = victim {
+            let (other, register) = active.swap_remove(position);
+            places[interval.value.index()] = Some(Place::Register(register));
+            active.push((index, register));
+            spilled.push(other);
+        } else {
+            spilled.push(index);
+        }
+    }
+
+    // spill slots, reused once their interval ends
+    for index in &spilled {
+        places[intervals[*index].value.index()] = None;
+    }
+    spilled.sort_unstable();
+    let mut slots: Vec<u32> = Vec::new();
+    for index in spilled {
+        let interval = &intervals[index];
+        let free = |slot: usize| slots[slot] <= interval.start;
+        let hinted =
+            hints
+                .get(&interval.value)
+                .into_iter()
+                .flatten()
+                .find_map(|other| match places[other.index()] {
+                    Some(Place::Spill(slot)) if free(slot as usize) => Some(slot as usize),
+                    _ => None,
+                });
+        let found = hinted.or_else(|| (0..slots.len()).find(|slot| free(*slot)));
+        let slot = found.unwrap_or_else(|| {
+            slots.push(0);
+            slots.len() - 1
+        });
+        slots[slot] = interval.end;
+        places[interval.value.index()] =
+            Some(Place::Spill(u32::try_from(slot).expect("frames are small")));
+    }
+
+    let saved = registers
+        .callee_saved
+        .iter()
+        .copied()
+        .filter(|register| places.contains(&Some(Place::Register(register))))
+        .collect();
+    let mut allocation = Allocation {
+        name: function.name.clone(),
+        blocks,
+        intervals,
+        places,
+        spill_slots: u32::try_from(slots.len()).expect("frames are small"),
+        saved,
+        moves: 0,
+        coalesced: 0,
+    };
+    for (from, to) in moves(function) {
+        if let Some(place) = allocation.place(to) {
+            allocation.moves += 1;
+            if allocation.place(from) == Some(place) {
+                allocation.coalesced += 1;
+            }
+        }
+    }
+    allocation
+}
+
+/// Numbers the instructions, returning the first and last position of
+/// every block and the positions of the calls.
+fn number(function: &Function) -> (Vec<(u32, u32)>, Vec<u32>) {
+    let mut blocks = Vec::with_capacity(function.blocks.len());
+    let mut calls = Vec::new();
+    let mut position = 0;
+    for block in &function.blocks {
+        let start = position;
+        for inst in &block.insts {
+            position += 2;
+            if matches!(inst.kind, InstKind::Call(..)) {
+                calls.push(position);
+            }
+        }
+        // the terminator, then the end of the block
+        position += 2;
+        blocks.push((start, position + 1));
+        position += 2;
+    }
+    (blocks, calls)
+}
+
+/// The values live at the start of every block.
+fn live_in(function: &Function) -> Vec<HashSet<Value>> {
+    let mut summaries = Vec::with_capacity(function.blocks.len());
+    for block in &function.blocks {
+        let mut defined = block.params.iter().copied().collect::<HashSet<_>>();
+        let mut used = HashSet::new();
+        for inst in &block.insts {
+            used.extend(
+                inst.kind
+                    .operands()
+                    .into_iter()
+                    .filter(|operand| !defined.contains(operand)),
+            );
+            defined.extend(inst.result);
+        }
+        let terminator = &block.terminator;
+        let args = terminator
+            .successors()
+            .into_iter()
+            .flat_map(|target| target.args.iter().copied());
+        used.extend(
+            terminator
+                .operands()
+                .into_iter()
+                .chain(args)
+                .filter(|operand| !defined.contains(operand)),
+        );
+        summaries.push((used, defined));
+    }
+
+    let mut live = vec![HashSet::new(); function.blocks.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for id in function.block_ids().collect::<Vec<_>>().into_iter().rev() {
+            let (used, defined) = &summaries[id.index()];
+            let mut values = used.clone();
+            for target in function.block(id).terminator.successors() {
+                values.extend(
+                    live[target.block.index()]
+                        .iter()
+                        .filter(|value| !defined.contains(value)),
+                );
+            }
+            if values != live[id.index()] {
+                live[id.index()] = values;
+                changed = true;
+            }
+        }
+    }
+    live
+}
+
+/// The interval of every value that is used, ordered by start.
+fn intervals(function: &Function, blocks: &[(u32, u32)], calls: &[u32]) -> Vec<Interval> {
+    let live = live_in(function);
+    let mut bounds: Vec<Option<(u32, u32)>> = vec![None; function.types.len()];
+    let mut extend = |value: Value, position: u32| {
+        let bound = &mut bounds[value.index()];
+        *bound = Some(bound.map_or((position, position), |(start, end)| {
+            (start.min(position), end.max(position))
+        }));
+    };
+    for id in function.block_ids() {
+        let block = function.block(id);
+        let (start, end) = blocks[id.index()];
+        for value in &live[id.index()] {
+            extend(*value, start);
+        }
+        for value in block.params.iter().copied() {
+            extend(value, start);
+        }
+        let mut position = start;
+        for inst in &block.insts {
+            position += 2;
+            for operand in inst.kind.operands() {
+                extend(operand, position);
+            }
+            if let Some(result) = inst.result {
+                extend(result, position);
+            }
+        }
+        position += 2;
+        let terminator = &block.terminator;
+        for target in terminator.successors() {
+            for arg in &target.args {
+                extend(*arg, position);
+            }
+            for value in &live[target.block.index()] {
+                extend(*value, end);
+            }
+        }
+        for operand in terminator.operands() {
+            extend(operand, position);
+        }
+    }
+
+    let mut intervals = bounds
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, bound)| {
+            let (start, end) = bound.filter(|(start, end)| start < end)?;
+            Some(Interval {
+                value: Value(u32::try_from(index).expect("values are numbered by u32")),
+                start,
+                end,
+                crosses_call: calls.iter().any(|call| start < *call && *call < end),
+            })
+        })
+        .collect::<Vec<_>>();
+    intervals.sort_by_key(|interval| (interval.start, interval.value));
+    intervals
+}
+
+/// The source and destination of every move.
+fn moves(function: &Function) -> Vec<(Value, Value)> {
+    let mut moves = Vec::new();
+    for block in &function.blocks {
+        for inst in &block.insts {
+            if let (InstKind::Extend(value), Some(result)) = (&inst.kind, inst.result) {
+                moves.push((*value, result));
+            }
+        }
+        for target in block.terminator.successors() {
+            let params = &function.block(target.block).params;
+            moves.extend(
+                target
+                    .args
+                    .iter()
+                    .copied()
+                    .zip(params.iter().copied())
+                    .filter(|(arg, param)| arg != param),
+            );
+        }
+    }
+    moves
+}
+
+/// The values every value is moved from or to, which it would rather share
+/// a register with.
+fn hints(function: &Function) -> HashMap<Value, Vec<Value>> {
+    let mut hints: HashMap<Value, Vec<Value>> = HashMap::new();
+    for (from, to) in moves(function) {
+        hints.entry(from).or_default().push(to);
+        hints.entry(to).or_default().push(from);
+    }
+    hints
+}
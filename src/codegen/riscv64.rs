@@ -0,0 +1,317 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! RV64GC assembly for the LP64 ABI of Linux.
+//!
+//! `t0` to `t3` are scratch registers, `t6` addresses what an immediate
+//! offset cannot reach. Conditional branches only reach 4 KiB, so they
+//! jump over a `j` to their target.
+
+use crate::ir::{BinaryOp, CompareOp, Module, Type, UnaryOp};
+
+use super::{
+    machine::{self, Asm, Machine},
+    regalloc::Registers,
+};
+
+/// The register offsets out of the reach of an immediate are added with.
+const TEMPORARY: &str = "t6";
+
+/// The RV64GC target.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Riscv64;
+
+/// Emits the assembly of a module.
+#[must_use]
+pub fn emit(module: &Module) -> String {
+    machine::emit(&Riscv64, module)
+}
+
+/// Whether an offset fits the 12 bit immediate of a load, store or `addi`.
+fn fits(offset: i64) -> bool {
+    (-2048..2048).contains(&offset)
+}
+
+impl Riscv64 {
+    /// `operand(base)` for the load or store `op`.
+    fn memory(out: &mut Asm, op: &str, register: &str, base: &str, offset: i64) {
+        if fits(offset) {
+            out.line(format!("{op} {register}, {offset}({base})"));
+        } else {
+            out.line(format!("li {TEMPORARY}, {offset}"));
+            out.line(format!("add {TEMPORARY}, {base}, {TEMPORARY}"));
+            out.line(format!("{op} {register}, 0({TEMPORARY})"));
+        }
+    }
+
+    /// Adds an offset to the stack pointer.
+    fn adjust(out: &mut Asm, offset: i64) {
+        if fits(offset) {
+            out.line(format!("addi sp, sp, {offset}"));
+        } else {
+            out.line(format!("li {TEMPORARY}, {offset}"));
+            out.line(format!("add sp, sp, {TEMPORARY}"));
+        }
+    }
+
+    fn sign_extend(out: &mut Asm, ty: Type, destination: &str, source: &str) {
+        let shift = match ty {
+            Type::I64 | Type::Ptr => {
+                out.line(format!("mv {destination}, {source}"));
+                return;
+            }
+            Type::I1 => 63,
+            ty => 64 - 8 * ty.size(),
+        };
+        out.line(format!("slli {destination}, {source}, {shift}"));
+        out.line(format!("srai {destination}, {destination}, {shift}"));
+    }
+
+    /// Skips a jump to `label` if the branch `op` is taken.
+    fn far(out: &mut Asm, op: &str, label: &str) {
+        out.line(format!("{op}, 1f"));
+        out.line(format!("j {label}"));
+        out.label("1");
+    }
+}
+
+impl Machine for Riscv64 {
+    const REGISTERS: Registers = Registers {
+        caller_saved: &["t4", "t5"],
+        callee_saved: &[
+            "s1", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11",
+        ],
+    };
+    const ARGUMENTS: &'static [&'static str] = &["a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7"];
+    const SCRATCH: [&'static str; 4] = ["t0", "t1", "t2", "t3"];
+    const FRAME: &'static str = "s0";
+
+    fn prologue(&self, out: &mut Asm, frame: u64) {
+        out.line("addi sp, sp, -16");
+        out.line("sd ra, 8(sp)");
+        out.line("sd s0, 0(sp)");
+        out.line("mv s0, sp");
+        if frame > 0 {
+            Self::adjust(out, -to_i64(frame));
+        }
+    }
+
+    fn epilogue(&self, out: &mut Asm) {
+        out.line("mv sp, s0");
+        out.line("ld ra, 8(sp)");
+        out.line("ld s0, 0(sp)");
+        out.line("addi sp, sp, 16");
+        out.line("ret");
+    }
+
+    fn call(&self, out: &mut Asm, symbol: &str) {
+        out.line(format!("call {symbol}"));
+    }
+
+    fn reserve(&self, out: &mut Asm, bytes: u64) {
+        Self::adjust(out, -to_i64(bytes));
+    }
+
+    fn release(&self, out: &mut Asm, bytes: u64) {
+        Self::adjust(out, to_i64(bytes));
+    }
+
+    fn argument(&self, out: &mut Asm, source: &str, index: u64) {
+        Self::memory(out, "sd", source, "sp", to_i64(8 * index));
+    }
+
+    fn extern_argument(&self, out: &mut Asm, ty: Type, register: &str) {
+        // the ABI passes 32 bit integers sign extended
+        if ty == Type::I32 {
+            out.line(format!("sext.w {register}, {register}"));
+        }
+    }
+
+    fn immediate(&self, out: &mut Asm, destination: &str, value: u64) {
+        out.line(format!("li {destination}, {}", value.cast_signed()));
+    }
+
+    fn mov(&self, out: &mut Asm, destination: &str, source: &str) {
+        if destination != source {
+            out.line(format!("mv {destination}, {source}"));
+        }
+    }
+
+    fn load(&self, out: &mut Asm, ty: Type, destination: &str, base: &str, offset: i64) {
+        let op = match ty {
+            Type::I1 | Type::I8 => "lbu",
+            Type::I16 => "lhu",
+            Type::I32 => "lwu",
+            Type::I64 | Type::Ptr => "ld",
+        };
+        Self::memory(out, op, destination, base, offset);
+    }
+
+    fn store(&self, out: &mut Asm, ty: Type, source: &str, base: &str, offset: i64) {
+        let op = match ty {
+            Type::I1 | Type::I8 => "sb",
+            Type::I16 => "sh",
+            Type::I32 => "sw",
+            Type::I64 | Type::Ptr => "sd",
+        };
+        Self::memory(out, op, source, base, offset);
+    }
+
+    fn address(&self, out: &mut Asm, destination: &str, base: &str, offset: i64) {
+        if fits(offset) {
+            out.line(format!("addi {destination}, {base}, {offset}"));
+        } else {
+            out.line(format!("li {TEMPORARY}, {offset}"));
+            out.line(format!("add {destination}, {base}, {TEMPORARY}"));
+        }
+    }
+
+    fn data(&self, out: &mut Asm, destination: &str, label: &str) {
+        out.line(format!("lla {destination}, {label}"));
+    }
+
+    fn zero_extend(&self, out: &mut Asm, ty: Type, register: &str) {
+        match ty {
+            Type::I1 => out.line(format!("andi {register}, {register}, 1")),
+            Type::I8 => out.line(format!("andi {register}, {register}, 255")),
+            Type::I16 | Type::I32 => {
+                let shift = 64 - 8 * ty.size();
+                out.line(format!("slli {register}, {register}, {shift}"));
+                out.line(format!("srli {register}, {register}, {shift}"));
+            }
+            Type::I64 | Type::Ptr => {}
+        }
+    }
+
+    fn unary(&self, out: &mut Asm, op: UnaryOp, destination: &str, operand: &str) {
+        let op = match op {
+            UnaryOp::Neg => "neg",
+            UnaryOp::Not => "not",
+        };
+        out.line(format!("{op} {destination}, {operand}"));
+    }
+
+    fn binary(&self, out: &mut Asm, op: BinaryOp, ty: Type, operands: [&str; 3]) {
+        let [destination, lhs, rhs] = operands;
+        let [.., lhs_scratch, rhs_scratch] = Self::SCRATCH;
+        let bits = match ty {
+            Type::I1 => 1,
+            ty => 8 * ty.size(),
+        };
+        let op = match op {
+            BinaryOp::Add => "add",
+            BinaryOp::Sub => "sub",
+            BinaryOp::Mul => "mul",
+            BinaryOp::And => "and",
+            BinaryOp::Or => "or",
+            BinaryOp::Xor => "xor",
+            BinaryOp::UDiv => "divu",
+            BinaryOp::URem => "remu",
+            BinaryOp::SDiv | BinaryOp::SRem => {
+                let op = if op == BinaryOp::SDiv { "div" } else { "rem" };
+                if bits == 64 {
+                    out.line(format!("{op} {destination}, {lhs}, {rhs}"));
+                } else {
+                    // the quotient of the smallest integer and -1 wraps
+                    // once cut to the type
+                    Self::sign_extend(out, ty, lhs_scratch, lhs);
+                    Self::sign_extend(out, ty, rhs_scratch, rhs);
+                    out.line(format!("{op} {destination}, {lhs_scratch}, {rhs_scratch}"));
+                }
+                return;
+            }
+            BinaryOp::Shl | BinaryOp::LShr | BinaryOp::AShr => {
+                out.line(format!("andi {rhs_scratch}, {rhs}, {}", bits - 1));
+                let (op, lhs) = match op {
+                    BinaryOp::Shl => ("sll", lhs),
+                    BinaryOp::LShr => ("srl", lhs),
+                    _ => {
+                        Self::sign_extend(out, ty, lhs_scratch, lhs);
+                        ("sra", lhs_scratch)
+                    }
+                };
+                out.line(format!("{op} {destination}, {lhs}, {rhs_scratch}"));
+                return;
+            }
+        };
+        out.line(format!("{op} {destination}, {lhs}, {rhs}"));
+    }
+
+    fn compare(&self, out: &mut Asm, op: CompareOp, ty: Type, operands: [&str; 3]) {
+        let [destination, mut lhs, mut rhs] = operands;
+        let signed = matches!(
+            op,
+            CompareOp::Slt | CompareOp::Sle | CompareOp::Sgt | CompareOp::Sge
+        );
+        if signed && !matches!(ty, Type::I64 | Type::Ptr) {
+            let [.., lhs_scratch, rhs_scratch] = Self::SCRATCH;
+            Self::sign_extend(out, ty, lhs_scratch, lhs);
+            Self::sign_extend(out, ty, rhs_scratch, rhs);
+            (lhs, rhs) = (lhs_scratch, rhs_scratch);
+        }
+        let less = if signed { "slt" } else { "sltu" };
+        let (op, lhs, rhs, invert) = match op {
+            CompareOp::Eq | CompareOp::Ne => {
+                out.line(format!("xor {destination}, {lhs}, {rhs}"));
+                let op = if op == CompareOp::Eq { "seqz" } else { "snez" };
+                out.line(format!("{op} {destination}, {destination}"));
+                return;
+            }
+            CompareOp::Slt | CompareOp::Ult => (less, lhs, rhs, false),
+            CompareOp::Sge | CompareOp::Uge => (less, lhs, rhs, true),
+            CompareOp::Sgt | CompareOp::Ugt => (less, rhs, lhs, false),
+            CompareOp::Sle | CompareOp::Ule => (less, rhs, lhs, true),
+        };
+        out.line(format!("{op} {destination}, {lhs}, {rhs}"));
+        if invert {
+            out.line(format!("xori {destination}, {destination}, 1"));
+        }
+    }
+
+    fn select(&self, out: &mut Asm, destination: &str, operands: [&str; 3]) {
+        let [condition, then, otherwise] = operands;
+        let scratch = Self::SCRATCH[3];
+        out.line(format!("mv {scratch}, {then}"));
+        out.line(format!("bnez {condition}, 1f"));
+        out.line(format!("mv {scratch}, {otherwise}"));
+        out.label("1");
+        out.line(format!("mv {destination}, {scratch}"));
+    }
+
+    fn copy(&self, out: &mut Asm, to: &str, from: &str, size: u64) {
+        let [target, source, count, byte] = Self::SCRATCH;
+        self.mov(out, target, to);
+        self.mov(out, source, from);
+        self.immediate(out, count, size);
+        out.label("1");
+        out.line(format!("beqz {count}, 2f"));
+        out.line(format!("lbu {byte}, 0({source})"));
+        out.line(format!("sb {byte}, 0({target})"));
+        out.line(format!("addi {target}, {target}, 1"));
+        out.line(format!("addi {source}, {source}, 1"));
+        out.line(format!("addi {count}, {count}, -1"));
+        out.line("j 1b");
+        out.label("2");
+    }
+
+    fn jump(&self, out: &mut Asm, label: &str) {
+        out.line(format!("j {label}"));
+    }
+
+    fn branch_if_zero(&self, out: &mut Asm, register: &str, label: &str) {
+        Self::far(out, &format!("bnez {register}"), label);
+    }
+
+    fn branch_if_equal(&self, out: &mut Asm, lhs: &str, rhs: &str, label: &str) {
+        Self::far(out, &format!("bne {lhs}, {rhs}"), label);
+    }
+
+    fn unreachable(&self, out: &mut Asm) {
+        out.line("unimp");
+    }
+}
+
+fn to_i64(value: u64) -> i64 {
+    i64::try_from(value).expect("frames are small")
+}
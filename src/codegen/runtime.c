@@ -0,0 +1,90 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+/*
+ * The runtime of native cringe programs: the extern functions of std, the
+ * handler for panics and the C entry point. Symbols with a '.' are only
+ * reachable from the generated assembly.
+ */
+
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#define PANIC_STATUS 101
+#define MAX_ALLOCATION ((uint64_t)1 << 32)
+
+struct str {
+    const char *bytes;
+    uint64_t length;
+};
+
+int64_t cringe_main(void) __asm__("cr.main");
+
+/* 'file' is the name of the source file, ended by a zero */
+_Noreturn void cringe_trap(const char *message, uint64_t length, const char *file, int64_t line,
+                           int64_t column) __asm__("cr.trap");
+
+_Noreturn void cringe_trap(const char *message, uint64_t length, const char *file, int64_t line,
+                           int64_t column) {
+    fflush(stdout);
+    fprintf(stderr, "%s:%lld:%lld: panicked: %.*s\n", file, (long long)line, (long long)column,
+            (int)length, message);
+    exit(PANIC_STATUS);
+}
+
+static _Noreturn void panic(const char *message) {
+    fflush(stdout);
+    fprintf(stderr, "panicked: %s\n", message);
+    exit(PANIC_STATUS);
+}
+
+void cringe_print(const struct str *text) __asm__("cr.std.print");
+
+void cringe_print(const struct str *text) {
+    fwrite(text->bytes, 1, text->length, stdout);
+}
+
+void cringe_println(const struct str *text) __asm__("cr.std.println");
+
+void cringe_println(const struct str *text) {
+    fwrite(text->bytes, 1, text->length, stdout);
+    putchar('\n');
+}
+
+void cringe_print_int(int64_t value) __asm__("cr.std.print_int");
+
+void cringe_print_int(int64_t value) {
+    printf("%lld", (long long)value);
+}
+
+void cringe_print_uint(uint64_t value) __asm__("cr.std.print_uint");
+
+void cringe_print_uint(uint64_t value) {
+    printf("%llu", (unsigned long long)value);
+}
+
+void *cringe_alloc(uint64_t bytes) __asm__("cr.std.alloc");
+
+void *cringe_alloc(uint64_t bytes) {
+    if (bytes > MAX_ALLOCATION) {
+        panic("allocation too large");
+    }
+    void *memory = calloc(1, bytes ? bytes : 1);
+    if (!memory) {
+        panic("out of memory");
+    }
+    return memory;
+}
+
+void cringe_free(void *pointer) __asm__("cr.std.free");
+
+void cringe_free(void *pointer) {
+    free(pointer);
+}
+
+int main(void) {
+    /* like a process exit status, only the low byte is kept */
+    return (int)(cringe_main() & 0xff);
+}
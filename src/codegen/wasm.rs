@@ -0,0 +1,836 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! WebAssembly modules, as text or binary, for running programs in a
+//! sandbox.
+//!
+//! Every IR value is an `i64` local, zero extended from its type like on
+//! x86-64, and pointers are addresses in the 32 bit linear memory, so
+//! structs, enums, strings and arrays keep their layout. The memory holds
+//! the string constants from address 16, then a stack of
+//! [`STACK_SIZE`] bytes for the slots of the functions and then the heap.
+//! `std::alloc` takes fresh memory from the heap, growing the memory as
+//! needed, and `std::free` does not reuse it.
+//!
+//! Blocks become the arms of a `br_table` in a loop that is entered again
+//! for every jump that does not go to the next block. I/O goes through
+//! functions the host provides in the module `cringe`:
+//!
+//! - `write(address: i32, length: i32)` writes bytes to the output,
+//! - `print_int(value: i64)` and `print_uint(value: i64)`,
+//! - `trap(message: i32, length: i32, file: i32, file_length: i32,
+//!   line: i32, column: i32)` and `panic(message: i32, length: i32)` stop
+//!   the program, `file` is the name of the source file.
+//!
+//! Other extern functions are imported from the module `env` by the last
+//! segment of their path. The module exports its `memory` and `main`.
+
+mod module;
+
+use std::collections::BTreeMap;
+
+use crate::ir::{
+    BinaryOp, BlockCall, BlockId, CompareOp, Function, Inst, InstKind, Module, Terminator, Type,
+    UnaryOp, Value,
+};
+
+use super::trap_files;
+
+pub use module::Wasm;
+use module::{Access, Func, FuncType, Global, Import, Numeric, Op, ValType};
+
+/// Bytes of memory for the slots of the active calls.
+pub const STACK_SIZE: u32 = 1 << 20;
+
+/// The address of the first string constant, lower ones stay unused.
+const DATA_START: u32 = 16;
+
+const PAGE_SIZE: u32 = 1 << 16;
+
+/// The largest allocation, like the native runtime.
+const MAX_ALLOCATION: i64 = 1 << 32;
+
+/// The globals, the stack pointer and the start of free memory.
+const STACK_POINTER: u32 = 0;
+const HEAP: u32 = 1;
+
+/// The imports of the module `cringe`.
+const WRITE: u32 = 0;
+const PRINT_INT: u32 = 1;
+const PRINT_UINT: u32 = 2;
+const TRAP: u32 = 3;
+const PANIC: u32 = 4;
+
+/// The module for the program as WebAssembly text.
+#[must_use]
+pub fn text(module: &Module) -> String {
+    translate(module).text()
+}
+
+/// The module for the program in the binary format.
+#[must_use]
+pub fn binary(module: &Module) -> Vec<u8> {
+    translate(module).binary()
+}
+
+/// Translates the functions of the module with the runtime they need.
+///
+/// # Panics
+///
+/// Panics if the module does not fit into the 32-bit address space.
+#[must_use]
+#[allow(clippy::too_many_lines)]
+pub fn translate(module: &Module) -> Wasm {
+    let i32s = |count| vec![ValType::I32; count];
+    let host = |name: &str, params| Import {
+        module: "cringe".to_string(),
+        name: name.to_string(),
+        ty: FuncType {
+            params,
+            results: Vec::new(),
+        },
+    };
+    let mut wasm = Wasm {
+        imports: vec![
+            host("write", i32s(2)),
+            host("print_int", vec![ValType::I64]),
+            host("print_uint", vec![ValType::I64]),
+            host("trap", i32s(6)),
+            host("panic", i32s(2)),
+        ],
+        ..Wasm::default()
+    };
+
+    // the string constants, the names of the files of the traps, then the
+    // messages of the runtime
+    let mut data = Vec::new();
+    let mut address = DATA_START;
+    let mut place = |bytes: &[u8]| {
+        let placed = (address, len(bytes.len()));
+        data.push((address, bytes.to_vec()));
+        address += len(bytes.len());
+        placed
+    };
+    let constants = module
+        .data
+        .iter()
+        .map(|string| place(string.as_bytes()))
+        .collect::<Vec<_>>();
+    let files = trap_files(module)
+        .into_iter()
+        .map(|(fid, name)| (fid, place(name.as_bytes())))
+        .collect::<BTreeMap<_, _>>();
+    let messages = Messages {
+        newline: place(b"\n"),
+        too_large: place(b"allocation too large"),
+        out_of_memory: place(b"out of memory"),
+        stack_overflow: place(b"stack overflow"),
+    };
+    let stack_limit = address.next_multiple_of(16);
+    let stack_top = stack_limit + STACK_SIZE;
+    wasm.data = data
+        .into_iter()
+        .filter(|(_, bytes)| !bytes.is_empty())
+        .collect();
+    wasm.pages = stack_top.div_ceil(PAGE_SIZE);
+    wasm.globals = vec![
+        Global {
+            name: "stack_pointer".to_string(),
+            value: i64::from(stack_top),
+        },
+        Global {
+            name: "heap".to_string(),
+            value: i64::from(stack_top),
+        },
+    ];
+
+    // imports come first in the index space, then the runtime
+    let mut runtime: Vec<&str> = Vec::new();
+    let callees = module
+        .functions
+        .iter()
+        .map(|function| {
+            let base = function.name.split('<').next().unwrap_or_default();
+            match base {
+                _ if !function.is_extern() => Callee::Defined,
+                "std::print_int" => Callee::Import(PRINT_INT),
+                "std::print_uint" => Callee::Import(PRINT_UINT),
+                "std::print" | "std::println" | "std::alloc" | "std::free" => {
+                    if !runtime.contains(&base) {
+                        runtime.push(base);
+                    }
+                    Callee::Runtime(base)
+                }
+                _ => {
+                    let name = base.rsplit("::").next().unwrap_or(base);
+                    let known = wasm
+                        .imports
+                        .iter()
+                        .position(|import| import.module == "env" && import.name == name);
+                    Callee::Import(len(known.unwrap_or_else(|| {
+                        wasm.imports.push(Import {
+                            module: "env".to_string(),
+                            name: name.to_string(),
+                            ty: func_type(function),
+                        });
+                        wasm.imports.len() - 1
+                    })))
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    let imports = len(wasm.imports.len());
+    let mut next = imports + len(runtime.len());
+    let callees = callees
+        .into_iter()
+        .map(|callee| match callee {
+            Callee::Import(index) => index,
+            Callee::Runtime(name) => {
+                let position = runtime.iter().position(|known| *known == name);
+                imports + len(position.expect("listed above"))
+            }
+            Callee::Defined => {
+                next += 1;
+                next - 1
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let context = Context {
+        module,
+        callees: &callees,
+        constants: &constants,
+        files: &files,
+        messages: &messages,
+        stack_limit,
+    };
+    for name in runtime {
+        wasm.functions.push(context.runtime(name));
+    }
+    for function in module
+        .functions
+        .iter()
+        .filter(|function| !function.is_extern())
+    {
+        wasm.functions
+            .push(FunctionEmitter::new(&context, function).emit());
+    }
+    wasm
+}
+
+/// Where a function of the IR module is in the index space.
+enum Callee<'m> {
+    Import(u32),
+    /// A function of `std` the runtime defines.
+    Runtime(&'m str),
+    Defined,
+}
+
+fn len(length: usize) -> u32 {
+    u32::try_from(length).expect("modules are small")
+}
+
+fn func_type(function: &Function) -> FuncType {
+    FuncType {
+        params: vec![ValType::I64; function.signature.params.len()],
+        results: function
+            .signature
+            .result
+            .map(|_| ValType::I64)
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// The addresses and lengths of the messages of the runtime.
+struct Messages {
+    newline: (u32, u32),
+    too_large: (u32, u32),
+    out_of_memory: (u32, u32),
+    stack_overflow: (u32, u32),
+}
+
+struct Context<'m> {
+    module: &'m Module,
+    /// The index of every function of the IR module.
+    callees: &'m [u32],
+    /// The address and length of every string constant.
+    constants: &'m [(u32, u32)],
+    /// The address and length of the name of every file with a trap.
+    files: &'m BTreeMap<usize, (u32, u32)>,
+    messages: &'m Messages,
+    /// The lowest address of the stack.
+    stack_limit: u32,
+}
+
+impl Context<'_> {
+    /// A function of `std` the runtime defines.
+    fn runtime(&self, name: &str) -> Func {
+        let pointer = |name: &str| Func {
+            name: name.to_string(),
+            ty: FuncType {
+                params: vec![ValType::I64],
+                results: Vec::new(),
+            },
+            local_names: vec!["text".to_string()],
+            locals: Vec::new(),
+            body: Vec::new(),
+            export: None,
+        };
+        let print = |body: &mut Vec<Op>| {
+            body.extend([
+                Op::LocalGet(0),
+                Op::Numeric(Numeric::I32WrapI64),
+                Op::Memory(Access::Load, 0),
+                Op::Numeric(Numeric::I32WrapI64),
+                Op::LocalGet(0),
+                Op::Numeric(Numeric::I32WrapI64),
+                Op::Memory(Access::Load, 8),
+                Op::Numeric(Numeric::I32WrapI64),
+                Op::Call(WRITE),
+            ]);
+        };
+        match name {
+            "std::print" => {
+                let mut function = pointer(name);
+                print(&mut function.body);
+                function
+            }
+            "std::println" => {
+                let mut function = pointer(name);
+                print(&mut function.body);
+                let (address, length) = self.messages.newline;
+                function.body.extend([
+                    Op::I32Const(constant(address)),
+                    Op::I32Const(constant(length)),
+                    Op::Call(WRITE),
+                ]);
+                function
+            }
+            "std::free" => {
+                let mut function = pointer(name);
+                function.local_names = vec!["pointer".to_string()];
+                function
+            }
+            "std::alloc" => self.alloc(),
+            _ => unreachable!("the runtime defines {name}"),
+        }
+    }
+
+    /// Takes fresh memory, rounded up to 16 bytes, from the heap. Memory
+    /// from `memory.grow` is zero and never reused, so it needs no
+    /// clearing.
+    fn alloc(&self) -> Func {
+        let (bytes, address, end, pages) = (0, 1, 2, 3);
+        let panic = |(message, length): (u32, u32)| {
+            [
+                Op::I32Const(constant(message)),
+                Op::I32Const(constant(length)),
+                Op::Call(PANIC),
+                Op::Unreachable,
+            ]
+        };
+        let mut body = vec![
+            Op::LocalGet(bytes),
+            Op::I64Const(MAX_ALLOCATION),
+            Op::Numeric(Numeric::I64GtU),
+            Op::If(None),
+        ];
+        body.extend(panic(self.messages.too_large));
+        body.extend([
+            Op::End,
+            Op::GlobalGet(HEAP),
+            Op::LocalTee(address),
+            Op::LocalGet(bytes),
+            Op::I64Const(15),
+            Op::Numeric(Numeric::I64Add),
+            Op::I64Const(-16),
+            Op::Numeric(Numeric::I64And),
+            Op::Numeric(Numeric::I64Add),
+            Op::LocalTee(end),
+            Op::GlobalSet(HEAP),
+            // the pages missing for the new end of the heap
+            Op::LocalGet(end),
+            Op::I64Const(i64::from(PAGE_SIZE - 1)),
+            Op::Numeric(Numeric::I64Add),
+            Op::I64Const(16),
+            Op::Numeric(Numeric::I64ShrU),
+            Op::MemorySize,
+            Op::Numeric(Numeric::I64ExtendI32U),
+            Op::Numeric(Numeric::I64Sub),
+            Op::LocalTee(pages),
+            Op::I64Const(0),
+            Op::Numeric(Numeric::I64GtS),
+            Op::If(None),
+            Op::LocalGet(pages),
+            Op::Numeric(Numeric::I32WrapI64),
+            Op::MemoryGrow,
+            Op::I32Const(-1),
+            Op::Numeric(Numeric::I32Eq),
+            Op::If(None),
+        ]);
+        body.extend(panic(self.messages.out_of_memory));
+        body.extend([Op::End, Op::End, Op::LocalGet(address)]);
+        Func {
+            name: "std::alloc".to_string(),
+            ty: FuncType {
+                params: vec![ValType::I64],
+                results: vec![ValType::I64],
+            },
+            local_names: ["bytes", "address", "end", "pages"]
+                .map(String::from)
+                .to_vec(),
+            locals: vec![ValType::I64; 3],
+            body,
+            export: None,
+        }
+    }
+}
+
+fn constant(value: u32) -> i32 {
+    value.cast_signed()
+}
+
+struct FunctionEmitter<'c, 'm> {
+    context: &'c Context<'m>,
+    function: &'m Function,
+    body: Vec<Op>,
+    params: u32,
+    /// The offset from the frame of the slot of every `slot` instruction.
+    slots: Vec<Option<u64>>,
+    frame: u64,
+    /// The block being emitted.
+    current: usize,
+    /// The blocks and ifs opened in the current block.
+    nesting: u32,
+}
+
+impl<'c, 'm> FunctionEmitter<'c, 'm> {
+    fn new(context: &'c Context<'m>, function: &'m Function) -> Self {
+        let mut frame = 0u64;
+        let mut slots = vec![None; function.types.len()];
+        for inst in function.blocks.iter().flat_map(|block| &block.insts) {
+            if let (InstKind::Slot { size, align, .. }, Some(result)) = (&inst.kind, inst.result) {
+                let offset = frame.next_multiple_of((*align).clamp(1, 16));
+                slots[result.index()] = Some(offset);
+                frame = offset + size;
+            }
+        }
+        Self {
+            context,
+            function,
+            body: Vec::new(),
+            params: len(function.signature.params.len()),
+            slots,
+            frame: frame.next_multiple_of(16),
+            current: 0,
+            nesting: 0,
+        }
+    }
+
+    fn local(&self, value: Value) -> u32 {
+        self.params + value.0
+    }
+
+    /// The number of the block to enter next.
+    fn block_local(&self) -> u32 {
+        self.params + len(self.function.types.len())
+    }
+
+    /// The address of the slots of the call.
+    fn frame_local(&self) -> u32 {
+        self.block_local() + 1
+    }
+
+    fn op(&mut self, op: Op) {
+        self.body.push(op);
+    }
+
+    fn numeric(&mut self, numeric: Numeric) {
+        self.body.push(Op::Numeric(numeric));
+    }
+
+    fn get(&mut self, value: Value) {
+        self.op(Op::LocalGet(self.local(value)));
+    }
+
+    fn frame_size(&self) -> i64 {
+        i64::try_from(self.frame).expect("frames are small")
+    }
+
+    fn emit(mut self) -> Func {
+        let entry = self.function.block(BlockId(0));
+        for (index, param) in entry.params.iter().enumerate() {
+            self.op(Op::LocalGet(len(index)));
+            self.op(Op::LocalSet(self.local(*param)));
+        }
+        if self.frame > 0 {
+            self.op(Op::GlobalGet(STACK_POINTER));
+            self.op(Op::I64Const(self.frame_size()));
+            self.numeric(Numeric::I64Sub);
+            self.op(Op::LocalTee(self.frame_local()));
+            self.op(Op::I64Const(i64::from(self.context.stack_limit)));
+            self.numeric(Numeric::I64LtU);
+            self.op(Op::If(None));
+            let (message, length) = self.context.messages.stack_overflow;
+            self.body.extend([
+                Op::I32Const(constant(message)),
+                Op::I32Const(constant(length)),
+                Op::Call(PANIC),
+                Op::Unreachable,
+                Op::End,
+            ]);
+            self.op(Op::LocalGet(self.frame_local()));
+            self.op(Op::GlobalSet(STACK_POINTER));
+        }
+
+        let blocks = self.function.blocks.len();
+        self.op(Op::Loop(Some("dispatch".to_string())));
+        for block in (0..blocks).rev() {
+            self.op(Op::Block(Some(format!("b{block}"))));
+        }
+        self.op(Op::LocalGet(self.block_local()));
+        let targets = (0..len(blocks)).collect::<Vec<_>>();
+        self.op(Op::BrTable(targets, len(blocks) - 1));
+        for id in self.function.block_ids() {
+            self.op(Op::End);
+            self.current = id.index();
+            let block = self.function.block(id);
+            for inst in &block.insts {
+                self.inst(inst);
+            }
+            self.terminator(&block.terminator, block.location);
+        }
+        self.op(Op::End);
+        self.op(Op::Unreachable);
+
+        let mut local_names = (0..self.params)
+            .map(|index| format!("p{index}"))
+            .collect::<Vec<_>>();
+        local_names.extend((0..self.function.types.len()).map(|value| format!("v{value}")));
+        local_names.extend(["block", "frame"].map(String::from));
+        let mut locals = vec![ValType::I64; self.function.types.len()];
+        locals.extend([ValType::I32, ValType::I64]);
+        let name = self.function.name.clone();
+        Func {
+            export: (name == "main").then(|| name.clone()),
+            name,
+            ty: func_type(self.function),
+            local_names,
+            locals,
+            body: self.body,
+        }
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn inst(&mut self, inst: &Inst) {
+        match &inst.kind {
+            InstKind::Const(value) => {
+                let result = inst.result.expect("constants have a result");
+                let masked = value & self.function.ty(result).mask();
+                self.op(Op::I64Const(masked.cast_signed()));
+            }
+            InstKind::Unary(op, value) => {
+                match op {
+                    UnaryOp::Neg => {
+                        self.op(Op::I64Const(0));
+                        self.get(*value);
+                        self.numeric(Numeric::I64Sub);
+                    }
+                    UnaryOp::Not => {
+                        self.get(*value);
+                        self.op(Op::I64Const(-1));
+                        self.numeric(Numeric::I64Xor);
+                    }
+                }
+                self.mask(self.function.ty(*value));
+            }
+            InstKind::Binary(op, lhs, rhs) => self.binary(*op, *lhs, *rhs),
+            InstKind::Compare(op, lhs, rhs) => {
+                let signed = matches!(
+                    op,
+                    CompareOp::Slt | CompareOp::Sle | CompareOp::Sgt | CompareOp::Sge
+                );
+                let ty = self.function.ty(*lhs);
+                for operand in [*lhs, *rhs] {
+                    self.get(operand);
+                    if signed {
+                        self.sign_extend(ty);
+                    }
+                }
+                self.numeric(match op {
+                    CompareOp::Eq => Numeric::I64Eq,
+                    CompareOp::Ne => Numeric::I64Ne,
+                    CompareOp::Slt => Numeric::I64LtS,
+                    CompareOp::Sle => Numeric::I64LeS,
+                    CompareOp::Sgt => Numeric::I64GtS,
+                    CompareOp::Sge => Numeric::I64GeS,
+                    CompareOp::Ult => Numeric::I64LtU,
+                    CompareOp::Ule => Numeric::I64LeU,
+                    CompareOp::Ugt => Numeric::I64GtU,
+                    CompareOp::Uge => Numeric::I64GeU,
+                });
+                self.numeric(Numeric::I64ExtendI32U);
+            }
+            InstKind::Select(condition, then, otherwise) => {
+                self.get(*then);
+                self.get(*otherwise);
+                self.get(*condition);
+                self.numeric(Numeric::I32WrapI64);
+                self.op(Op::Select);
+            }
+            // values are zero extended already
+            InstKind::Extend(value) => self.get(*value),
+            InstKind::Slot { .. } => {
+                let result = inst.result.expect("slots have a result");
+                let offset = self.slots[result.index()].expect("every slot has a place");
+                self.op(Op::LocalGet(self.frame_local()));
+                if offset > 0 {
+                    self.op(Op::I64Const(offset.cast_signed()));
+                    self.numeric(Numeric::I64Add);
+                }
+            }
+            InstKind::Load(address) => {
+                let result = inst.result.expect("loads have a result");
+                self.address(*address);
+                let access = match self.function.ty(result) {
+                    Type::I1 | Type::I8 => Access::Load8,
+                    Type::I16 => Access::Load16,
+                    Type::I32 => Access::Load32,
+                    Type::I64 | Type::Ptr => Access::Load,
+                };
+                self.op(Op::Memory(access, 0));
+            }
+            InstKind::Store { value, address } => {
+                self.address(*address);
+                self.get(*value);
+                let access = match self.function.ty(*value) {
+                    Type::I1 | Type::I8 => Access::Store8,
+                    Type::I16 => Access::Store16,
+                    Type::I32 => Access::Store32,
+                    Type::I64 | Type::Ptr => Access::Store,
+                };
+                self.op(Op::Memory(access, 0));
+            }
+            InstKind::PtrAdd(address, offset) => {
+                self.get(*address);
+                self.get(*offset);
+                self.numeric(Numeric::I64Add);
+            }
+            InstKind::Copy { to, from, size } => {
+                self.address(*to);
+                self.address(*from);
+                let size = i32::try_from(*size).expect("copies are small");
+                self.op(Op::I32Const(size));
+                self.op(Op::MemoryCopy);
+            }
+            InstKind::Data(index) => {
+                let (address, _) = self.context.constants[*index as usize];
+                self.op(Op::I64Const(i64::from(address)));
+            }
+            InstKind::Call(callee, arguments) => {
+                for argument in arguments {
+                    self.get(*argument);
+                }
+                self.op(Op::Call(self.context.callees[callee.index()]));
+                let returns = self.context.module.functions[callee.index()]
+                    .signature
+                    .result
+                    .is_some();
+                if returns && inst.result.is_none() {
+                    self.op(Op::Drop);
+                }
+            }
+        }
+        if let Some(result) = inst.result {
+            self.op(Op::LocalSet(self.local(result)));
+        }
+    }
+
+    /// A value as a 32 bit address.
+    fn address(&mut self, value: Value) {
+        self.get(value);
+        self.numeric(Numeric::I32WrapI64);
+    }
+
+    /// Cuts the value on the stack to the bits of its type.
+    fn mask(&mut self, ty: Type) {
+        if !matches!(ty, Type::I64 | Type::Ptr) {
+            self.op(Op::I64Const(ty.mask().cast_signed()));
+            self.numeric(Numeric::I64And);
+        }
+    }
+
+    /// Sign extends the value on the stack from its type.
+    fn sign_extend(&mut self, ty: Type) {
+        match ty {
+            Type::I1 => {
+                self.op(Op::I64Const(63));
+                self.numeric(Numeric::I64Shl);
+                self.op(Op::I64Const(63));
+                self.numeric(Numeric::I64ShrS);
+            }
+            Type::I8 => self.numeric(Numeric::I64Extend8S),
+            Type::I16 => self.numeric(Numeric::I64Extend16S),
+            Type::I32 => self.numeric(Numeric::I64Extend32S),
+            Type::I64 | Type::Ptr => {}
+        }
+    }
+
+    fn binary(&mut self, op: BinaryOp, lhs: Value, rhs: Value) {
+        let ty = self.function.ty(lhs);
+        let simple = match op {
+            BinaryOp::Add => Some(Numeric::I64Add),
+            BinaryOp::Sub => Some(Numeric::I64Sub),
+            BinaryOp::Mul => Some(Numeric::I64Mul),
+            BinaryOp::UDiv => Some(Numeric::I64DivU),
+            BinaryOp::URem => Some(Numeric::I64RemU),
+            BinaryOp::And => Some(Numeric::I64And),
+            BinaryOp::Or => Some(Numeric::I64Or),
+            BinaryOp::Xor => Some(Numeric::I64Xor),
+            _ => None,
+        };
+        match op {
+            _ if simple.is_some() => {
+                self.get(lhs);
+                self.get(rhs);
+                self.numeric(simple.expect("checked above"));
+            }
+            // the quotient of the smallest integer and -1 overflows,
+            // `i64.div_s` traps where the IR wraps
+            BinaryOp::SDiv => {
+                self.get(rhs);
+                self.sign_extend(ty);
+                self.op(Op::I64Const(-1));
+                self.numeric(Numeric::I64Eq);
+                self.op(Op::If(Some(ValType::I64)));
+                self.op(Op::I64Const(0));
+                self.get(lhs);
+                self.numeric(Numeric::I64Sub);
+                self.op(Op::Else);
+                for operand in [lhs, rhs] {
+                    self.get(operand);
+                    self.sign_extend(ty);
+                }
+                self.numeric(Numeric::I64DivS);
+                self.op(Op::End);
+            }
+            BinaryOp::SRem => {
+                for operand in [lhs, rhs] {
+                    self.get(operand);
+                    self.sign_extend(ty);
+                }
+                self.numeric(Numeric::I64RemS);
+            }
+            _ => {
+                self.get(lhs);
+                if op == BinaryOp::AShr {
+                    self.sign_extend(ty);
+                }
+                self.get(rhs);
+                let bits = if ty == Type::I1 { 1 } else { 8 * ty.size() };
+                self.op(Op::I64Const((bits - 1).cast_signed()));
+                self.numeric(Numeric::I64And);
+                self.numeric(match op {
+                    BinaryOp::Shl => Numeric::I64Shl,
+                    BinaryOp::LShr => Numeric::I64ShrU,
+                    _ => Numeric::I64ShrS,
+                });
+            }
+        }
+        self.mask(ty);
+    }
+
+    /// Passes the arguments of a jump to the parameters of its target, all
+    /// are read before any is written, then continues with the target. A
+    /// jump to the next block may fall through if nothing follows it.
+    fn jump(&mut self, target: &BlockCall, fall_through: bool) {
+        let params = &self.function.block(target.block).params;
+        let moves = target
+            .args
+            .iter()
+            .zip(params)
+            .filter(|(arg, param)| arg != param)
+            .collect::<Vec<_>>();
+        for (arg, _) in &moves {
+            self.get(**arg);
+        }
+        for (_, param) in moves.iter().rev() {
+            self.op(Op::LocalSet(self.local(**param)));
+        }
+        if fall_through && target.block.index() == self.current + 1 {
+            return;
+        }
+        self.op(Op::I32Const(constant(target.block.0)));
+        self.op(Op::LocalSet(self.block_local()));
+        // the blocks after this one are still open
+        let depth = len(self.function.blocks.len() - 1 - self.current) + self.nesting;
+        self.op(Op::Br(depth));
+    }
+
+    fn terminator(&mut self, terminator: &Terminator, location: crate::parser::tokens::Location) {
+        match terminator {
+            Terminator::Jump(target) => self.jump(target, true),
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.get(*condition);
+                self.numeric(Numeric::I32WrapI64);
+                self.op(Op::If(None));
+                self.nesting += 1;
+                self.jump(then, true);
+                self.op(Op::Else);
+                self.jump(otherwise, true);
+                self.nesting -= 1;
+                self.op(Op::End);
+            }
+            Terminator::Switch {
+                value,
+                cases,
+                default,
+            } => {
+                for (case, target) in cases {
+                    self.get(*value);
+                    self.op(Op::I64Const(case.cast_signed()));
+                    self.numeric(Numeric::I64Eq);
+                    self.op(Op::If(None));
+                    self.nesting += 1;
+                    self.jump(target, false);
+                    self.nesting -= 1;
+                    self.op(Op::End);
+                }
+                self.jump(default, true);
+            }
+            Terminator::Return(value) => {
+                if self.frame > 0 {
+                    self.op(Op::LocalGet(self.frame_local()));
+                    self.op(Op::I64Const(self.frame_size()));
+                    self.numeric(Numeric::I64Add);
+                    self.op(Op::GlobalSet(STACK_POINTER));
+                }
+                if let Some(value) = value {
+                    self.get(*value);
+                }
+                self.op(Op::Return);
+            }
+            Terminator::Trap(index) => {
+                let (message, length) = self.context.constants[*index as usize];
+                let (file, file_length) = self.context.files[&location.fid];
+                self.body.extend([
+                    Op::I32Const(constant(message)),
+                    Op::I32Const(constant(length)),
+                    Op::I32Const(constant(file)),
+                    Op::I32Const(constant(file_length)),
+                    Op::I32Const(constant(len(location.l))),
+                    Op::I32Const(constant(len(location.c))),
+                    Op::Call(TRAP),
+                    Op::Unreachable,
+                ]);
+            }
+            Terminator::Unreachable => self.op(Op::Unreachable),
+        }
+    }
+}
@@ -0,0 +1,661 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! A WebAssembly module with the instructions the backend uses, written as
+//! text or in the binary format.
+
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValType {
+    I32,
+    I64,
+}
+
+impl ValType {
+    fn name(self) -> &'static str {
+        match self {
+            ValType::I32 => "i32",
+            ValType::I64 => "i64",
+        }
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            ValType::I32 => 0x7f,
+            ValType::I64 => 0x7e,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FuncType {
+    pub params: Vec<ValType>,
+    pub results: Vec<ValType>,
+}
+
+/// Instructions without immediates that only work on the operand stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Numeric {
+    I32Eq,
+    I64Eq,
+    I64Ne,
+    I64LtS,
+    I64LtU,
+    I64GtS,
+    I64GtU,
+    I64LeS,
+    I64LeU,
+    I64GeS,
+    I64GeU,
+    I64Add,
+    I64Sub,
+    I64Mul,
+    I64DivS,
+    I64DivU,
+    I64RemS,
+    I64RemU,
+    I64And,
+    I64Or,
+    I64Xor,
+    I64Shl,
+    I64ShrS,
+    I64ShrU,
+    I32WrapI64,
+    I64ExtendI32U,
+    I64Extend8S,
+    I64Extend16S,
+    I64Extend32S,
+}
+
+impl Numeric {
+    fn info(self) -> (&'static str, u8) {
+        match self {
+            Numeric::I32Eq => ("i32.eq", 0x46),
+            Numeric::I64Eq => ("i64.eq", 0x51),
+            Numeric::I64Ne => ("i64.ne", 0x52),
+            Numeric::I64LtS => ("i64.lt_s", 0x53),
+            Numeric::I64LtU => ("i64.lt_u", 0x54),
+            Numeric::I64GtS => ("i64.gt_s", 0x55),
+            Numeric::I64GtU => ("i64.gt_u", 0x56),
+            Numeric::I64LeS => ("i64.le_s", 0x57),
+            Numeric::I64LeU => ("i64.le_u", 0x58),
+            Numeric::I64GeS => ("i64.ge_s", 0x59),
+            Numeric::I64GeU => ("i64.ge_u", 0x5a),
+            Numeric::I64Add => ("i64.add", 0x7c),
+            Numeric::I64Sub => ("i64.sub", 0x7d),
+            Numeric::I64Mul => ("i64.mul", 0x7e),
+            Numeric::I64DivS => ("i64.div_s", 0x7f),
+            Numeric::I64DivU => ("i64.div_u", 0x80),
+            Numeric::I64RemS => ("i64.rem_s", 0x81),
+            Numeric::I64RemU => ("i64.rem_u", 0x82),
+            Numeric::I64And => ("i64.and", 0x83),
+            Numeric::I64Or => ("i64.or", 0x84),
+            Numeric::I64Xor => ("i64.xor", 0x85),
+            Numeric::I64Shl => ("i64.shl", 0x86),
+            Numeric::I64ShrS => ("i64.shr_s", 0x87),
+            Numeric::I64ShrU => ("i64.shr_u", 0x88),
+            Numeric::I32WrapI64 => ("i32.wrap_i64", 0xa7),
+            Numeric::I64ExtendI32U => ("i64.extend_i32_u", 0xad),
+            Numeric::I64Extend8S => ("i64.extend8_s", 0xc2),
+            Numeric::I64Extend16S => ("i64.extend16_s", 0xc3),
+            Numeric::I64Extend32S => ("i64.extend32_s", 0xc4),
+        }
+    }
+}
+
+/// Loads and stores of `i64` values, narrower ones are zero extended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Load,
+    Load8,
+    Load16,
+    Load32,
+    Store,
+    Store8,
+    Store16,
+    Store32,
+}
+
+impl Access {
+    /// The name, the opcode and the alignment as a power of two.
+    fn info(self) -> (&'static str, u8, u32) {
+        match self {
+            Access::Load => ("i64.load", 0x29, 3),
+            Access::Load8 => ("i64.load8_u", 0x31, 0),
+            Access::Load16 => ("i64.load16_u", 0x33, 1),
+            Access::Load32 => ("i64.load32_u", 0x35, 2),
+            Access::Store => ("i64.store", 0x37, 3),
+            Access::Store8 => ("i64.store8", 0x3c, 0),
+            Access::Store16 => ("i64.store16", 0x3d, 1),
+            Access::Store32 => ("i64.store32", 0x3e, 2),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+    /// A block with an optional name for the text format.
+    Block(Option<String>),
+    Loop(Option<String>),
+    If(Option<ValType>),
+    Else,
+    End,
+    /// A branch to the label this many blocks out.
+    Br(u32),
+    BrIf(u32),
+    BrTable(Vec<u32>, u32),
+    Return,
+    Unreachable,
+    Call(u32),
+    Drop,
+    Select,
+    LocalGet(u32),
+    LocalSet(u32),
+    LocalTee(u32),
+    GlobalGet(u32),
+    GlobalSet(u32),
+    I32Const(i32),
+    I64Const(i64),
+    /// A memory access at an address plus a constant offset.
+    Memory(Access, u32),
+    MemorySize,
+    MemoryGrow,
+    MemoryCopy,
+    Numeric(Numeric),
+}
+
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub module: String,
+    pub name: String,
+    pub ty: FuncType,
+}
+
+#[derive(Debug, Clone)]
+pub struct Func {
+    /// The name in the name section, also the identifier in the text.
+    pub name: String,
+    pub ty: FuncType,
+    /// The names of the parameters followed by the locals.
+    pub local_names: Vec<String>,
+    pub locals: Vec<ValType>,
+    pub body: Vec<Op>,
+    pub export: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Global {
+    pub name: String,
+    pub value: i64,
+}
+
+/// A module with one memory, mutable `i64` globals and functions
+/// following the imported ones in the index space.
+#[derive(Debug, Clone, Default)]
+pub struct Wasm {
+    pub imports: Vec<Import>,
+    pub functions: Vec<Func>,
+    /// The pages of the memory, it is exported as `memory`.
+    pub pages: u32,
+    pub globals: Vec<Global>,
+    /// Bytes placed in memory at an address.
+    pub data: Vec<(u32, Vec<u8>)>,
+}
+
+impl Wasm {
+    /// The identifiers of the functions in the text, unique and with
+    /// characters the text allows only.
+    fn identifiers(&self) -> Vec<String> {
+        let mut taken = std::collections::HashSet::new();
+        let names = self
+            .imports
+            .iter()
+            .map(|import| format!("{}.{}", import.module, import.name))
+            .chain(self.functions.iter().map(|function| function.name.clone()));
+        names
+            .map(|name| {
+                let base = identifier(&name);
+                let mut unique = base.clone();
+                let mut number = 2;
+                while !taken.insert(unique.clone()) {
+                    unique = format!("{base}_{number}");
+                    number += 1;
+                }
+                unique
+            })
+            .collect()
+    }
+
+    #[must_use]
+    pub fn text(&self) -> String {
+        let identifiers = self.identifiers();
+        let mut out = String::from("(module\n");
+        for (import, id) in self.imports.iter().zip(&identifiers) {
+            let _ = writeln!(
+                out,
+                "  (import {} {} (func ${id}{}))",
+                string(import.module.as_bytes()),
+                string(import.name.as_bytes()),
+                signature(&import.ty, None)
+            );
+        }
+        let _ = writeln!(out, "  (memory (export \"memory\") {})", self.pages);
+        for global in &self.globals {
+            let _ = writeln!(
+                out,
+                "  (global ${} (mut i64) (i64.const {}))",
+                global.name, global.value
+            );
+        }
+        let functions = &identifiers[self.imports.len()..];
+        for (function, id) in self.functions.iter().zip(functions) {
+            out.push_str("\n  (func $");
+            out.push_str(id);
+            if let Some(export) = &function.export {
+                let _ = write!(out, " (export {})", string(export.as_bytes()));
+            }
+            out.push_str(&signature(&function.ty, Some(&function.local_names)));
+            out.push('\n');
+            let names = &function.local_names[function.ty.params.len()..];
+            for (local, name) in function.locals.iter().zip(names) {
+                let _ = writeln!(out, "    (local ${name} {})", local.name());
+            }
+            self.body(function, &identifiers, &mut out);
+            out.push_str("  )\n");
+        }
+        if !self.data.is_empty() {
+            out.push('\n');
+        }
+        for (address, bytes) in &self.data {
+            let _ = writeln!(out, "  (data (i32.const {address}) {})", string(bytes));
+        }
+        out.push_str(")\n");
+        out
+    }
+
+    fn body(&self, function: &Func, identifiers: &[String], out: &mut String) {
+        // the names of the enclosing blocks, innermost last
+        let mut labels: Vec<Option<&String>> = Vec::new();
+        let label = |labels: &[Option<&String>], depth: u32| match labels
+            [labels.len() - 1 - depth as usize]
+        {
+            Some(name) => format!("${name}"),
+            None => depth.to_string(),
+        };
+        let local = |index: u32| format!("${}", function.local_names[index as usize]);
+        for op in &function.body {
+            if matches!(op, Op::End | Op::Else) {
+                labels.pop();
+            }
+            for _ in 0..=labels.len() + 1 {
+                out.push_str("  ");
+            }
+            match op {
+                Op::Block(name) | Op::Loop(name) => {
+                    out.push_str(if matches!(op, Op::Block(_)) {
+                        "block"
+                    } else {
+                        "loop"
+                    });
+                    if let Some(name) = name {
+                        let _ = write!(out, " ${name}");
+                    }
+                    labels.push(name.as_ref());
+                }
+                Op::If(result) => {
+                    out.push_str("if");
+                    if let Some(result) = result {
+                        let _ = write!(out, " (result {})", result.name());
+                    }
+                    labels.push(None);
+                }
+                Op::Else => {
+                    out.push_str("else");
+                    labels.push(None);
+                }
+                Op::End => out.push_str("end"),
+                Op::Br(depth) => {
+                    let _ = write!(out, "br {}", label(&labels, *depth));
+                }
+                Op::BrIf(depth) => {
+                    let _ = write!(out, "br_if {}", label(&labels, *depth));
+                }
+                Op::BrTable(targets, default) => {
+                    out.push_str("br_table");
+                    for target in targets.iter().chain([default]) {
+                        let _ = write!(out, " {}", label(&labels, *target));
+                    }
+                }
+                Op::Return => out.push_str("return"),
+                Op::Unreachable => out.push_str("unreachable"),
+                Op::Call(index) => {
+                    let _ = write!(out, "call ${}", identifiers[*index as usize]);
+                }
+                Op::Drop => out.push_str("drop"),
+                Op::Select => out.push_str("select"),
+                Op::LocalGet(index) => {
+                    let _ = write!(out, "local.get {}", local(*index));
+                }
+                Op::LocalSet(index) => {
+                    let _ = write!(out, "local.set {}", local(*index));
+                }
+                Op::LocalTee(index) => {
+                    let _ = write!(out, "local.tee {}", local(*index));
+                }
+                Op::GlobalGet(index) => {
+                    let _ = write!(out, "global.get ${}", self.globals[*index as usize].name);
+                }
+                Op::GlobalSet(index) => {
+                    let _ = write!(out, "global.set ${}", self.globals[*index as usize].name);
+                }
+                Op::I32Const(value) => {
+                    let _ = write!(out, "i32.const {value}");
+                }
+                Op::I64Const(value) => {
+                    let _ = write!(out, "i64.const {value}");
+                }
+                Op::Memory(access, offset) => {
+                    out.push_str(access.info().0);
+                    if *offset > 0 {
+                        let _ = write!(out, " offset={offset}");
+                    }
+                }
+                Op::MemorySize => out.push_str("memory.size"),
+                Op::MemoryGrow => out.push_str("memory.grow"),
+                Op::MemoryCopy => out.push_str("memory.copy"),
+                Op::Numeric(numeric) => out.push_str(numeric.info().0),
+            }
+            out.push('\n');
+        }
+    }
+
+    #[must_use]
+    pub fn binary(&self) -> Vec<u8> {
+        let mut types: Vec<&FuncType> = Vec::new();
+        let mut imports = Vec::new();
+        unsigned(&mut imports, self.imports.len() as u64);
+        for import in &self.imports {
+            name(&mut imports, &import.module);
+            name(&mut imports, &import.name);
+            imports.push(0x00);
+            unsigned(&mut imports, type_index(&mut types, &import.ty));
+        }
+        let mut functions = Vec::new();
+        unsigned(&mut functions, self.functions.len() as u64);
+        for function in &self.functions {
+            unsigned(&mut functions, type_index(&mut types, &function.ty));
+        }
+
+        let mut out = b"\0asm\x01\0\0\0".to_vec();
+        let mut contents = Vec::new();
+        unsigned(&mut contents, types.len() as u64);
+        for ty in &types {
+            contents.push(0x60);
+            for list in [&ty.params, &ty.results] {
+                unsigned(&mut contents, list.len() as u64);
+                contents.extend(list.iter().map(|ty| ty.code()));
+            }
+        }
+        section(&mut out, 1, &contents);
+        section(&mut out, 2, &imports);
+        section(&mut out, 3, &functions);
+
+        let mut memory = vec![1, 0x00];
+        unsigned(&mut memory, u64::from(self.pages));
+        section(&mut out, 5, &memory);
+
+        let mut globals = Vec::new();
+        unsigned(&mut globals, self.globals.len() as u64);
+        for global in &self.globals {
+            globals.extend([ValType::I64.code(), 0x01, 0x42]);
+            signed(&mut globals, global.value);
+            globals.push(0x0b);
+        }
+        section(&mut out, 6, &globals);
+
+        let mut exports = Vec::new();
+        let exported = self
+            .functions
+            .iter()
+            .enumerate()
+            .filter_map(|(index, function)| Some((index, function.export.as_ref()?)))
+            .collect::<Vec<_>>();
+        unsigned(&mut exports, exported.len() as u64 + 1);
+        name(&mut exports, "memory");
+        exports.extend([0x02, 0x00]);
+        for (index, export) in exported {
+            name(&mut exports, export);
+            exports.push(0x00);
+            unsigned(&mut exports, (self.imports.len() + index) as u64);
+        }
+        section(&mut out, 7, &exports);
+
+        let mut code = Vec::new();
+        unsigned(&mut code, self.functions.len() as u64);
+        for function in &self.functions {
+            let body = function_body(function);
+            unsigned(&mut code, body.len() as u64);
+            code.extend(body);
+        }
+        section(&mut out, 10, &code);
+
+        let mut data = Vec::new();
+        unsigned(&mut data, self.data.len() as u64);
+        for (address, bytes) in &self.data {
+            data.extend([0x00, 0x41]);
+            signed(&mut data, i64::from(*address));
+            data.push(0x0b);
+            unsigned(&mut data, bytes.len() as u64);
+            data.extend(bytes);
+        }
+        section(&mut out, 11, &data);
+
+        // the function names, for debuggers and traces
+        let mut names = Vec::new();
+        name(&mut names, "name");
+        let mut function_names = Vec::new();
+        let count = self.imports.len() + self.functions.len();
+        unsigned(&mut function_names, count as u64);
+        let all = self
+            .imports
+            .iter()
+            .map(|import| format!("{}.{}", import.module, import.name))
+            .chain(self.functions.iter().map(|function| function.name.clone()));
+        for (index, function) in all.enumerate() {
+            unsigned(&mut function_names, index as u64);
+            name(&mut function_names, &function);
+        }
+        names.push(1);
+        unsigned(&mut names, function_names.len() as u64);
+        names.extend(function_names);
+        section(&mut out, 0, &names);
+        out
+    }
+}
+
+/// The index of a type in the type section, added if it is new.
+fn type_index<'w>(types: &mut Vec<&'w FuncType>, ty: &'w FuncType) -> u64 {
+    let index = types.iter().position(|known| *known == ty);
+    index.unwrap_or_else(|| {
+        types.push(ty);
+        types.len() - 1
+    }) as u64
+}
+
+fn function_body(function: &Func) -> Vec<u8> {
+    let mut body = Vec::new();
+    // runs of locals of the same type
+    let mut runs: Vec<(u64, ValType)> = Vec::new();
+    for local in &function.locals {
+        match runs.last_mut() {
+            Some((count, ty)) if ty == local => *count += 1,
+            _ => runs.push((1, *local)),
+        }
+    }
+    unsigned(&mut body, runs.len() as u64);
+    for (count, ty) in runs {
+        unsigned(&mut body, count);
+        body.push(ty.code());
+    }
+    for op in &function.body {
+        encode(op, &mut body);
+    }
+    body.push(0x0b);
+    body
+}
+
+fn encode(op: &Op, out: &mut Vec<u8>) {
+    match op {
+        Op::Block(_) => out.extend([0x02, 0x40]),
+        Op::Loop(_) => out.extend([0x03, 0x40]),
+        Op::If(result) => out.extend([0x04, result.map_or(0x40, ValType::code)]),
+        Op::Else => out.push(0x05),
+        Op::End => out.push(0x0b),
+        Op::Br(depth) => {
+            out.push(0x0c);
+            unsigned(out, u64::from(*depth));
+        }
+        Op::BrIf(depth) => {
+            out.push(0x0d);
+            unsigned(out, u64::from(*depth));
+        }
+        Op::BrTable(targets, default) => {
+            out.push(0x0e);
+            unsigned(out, targets.len() as u64);
+            for target in targets.iter().chain([default]) {
+                unsigned(out, u64::from(*target));
+            }
+        }
+        Op::Return => out.push(0x0f),
+        Op::Unreachable => out.push(0x00),
+        Op::Call(index) => {
+            out.push(0x10);
+            unsigned(out, u64::from(*index));
+        }
+        Op::Drop => out.push(0x1a),
+        Op::Select => out.push(0x1b),
+        Op::LocalGet(index)
+        | Op::LocalSet(index)
+        | Op::LocalTee(index)
+        | Op::GlobalGet(index)
+        | Op::GlobalSet(index) => {
+            out.push(match op {
+                Op::LocalGet(_) => 0x20,
+                Op::LocalSet(_) => 0x21,
+                Op::LocalTee(_) => 0x22,
+                Op::GlobalGet(_) => 0x23,
+                _ => 0x24,
+            });
+            unsigned(out, u64::from(*index));
+        }
+        Op::I32Const(value) => {
+            out.push(0x41);
+            signed(out, i64::from(*value));
+        }
+        Op::I64Const(value) => {
+            out.push(0x42);
+            signed(out, *value);
+        }
+        Op::Memory(access, offset) => {
+            let (_, opcode, align) = access.info();
+            out.push(opcode);
+            unsigned(out, u64::from(align));
+            unsigned(out, u64::from(*offset));
+        }
+        Op::MemorySize => out.extend([0x3f, 0x00]),
+        Op::MemoryGrow => out.extend([0x40, 0x00]),
+        Op::MemoryCopy => out.extend([0xfc, 10, 0x00, 0x00]),
+        Op::Numeric(numeric) => out.push(numeric.info().1),
+    }
+}
+
+fn section(out: &mut Vec<u8>, id: u8, contents: &[u8]) {
+    out.push(id);
+    unsigned(out, contents.len() as u64);
+    out.extend(contents);
+}
+
+fn name(out: &mut Vec<u8>, name: &str) {
+    unsigned(out, name.len() as u64);
+    out.extend(name.as_bytes());
+}
+
+/// Unsigned LEB128.
+fn unsigned(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Signed LEB128.
+fn signed(out: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7f).to_le_bytes()[0];
+        value >>= 7;
+        let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+        if done {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// The parameters and results of a function, with names if given.
+fn signature(ty: &FuncType, names: Option<&[String]>) -> String {
+    let mut out = String::new();
+    for (index, param) in ty.params.iter().enumerate() {
+        match names {
+            Some(names) => {
+                let _ = write!(out, " (param ${} {})", names[index], param.name());
+            }
+            None => {
+                let _ = write!(out, " (param {})", param.name());
+            }
+        }
+    }
+    for result in &ty.results {
+        let _ = write!(out, " (result {})", result.name());
+    }
+    out
+}
+
+/// A text identifier for a name, other characters than the ones the text
+/// allows in identifiers become `_`.
+fn identifier(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "!#$%&'*+-./:<=>?@\\^_`|~".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// A string of the text format.
+fn string(bytes: &[u8]) -> String {
+    let mut out = String::from("\"");
+    for byte in bytes {
+        match byte {
+            b'"' | b'\\' => {
+                let _ = write!(out, "\\{}", char::from(*byte));
+            }
+            0x20..=0x7e => out.push(char::from(*byte)),
+            _ => {
+                let _ = write!(out, "\\{byte:02x}");
+            }
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,489 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! x86-64 assembly for the System V ABI in GAS syntax (AT&T operand order).
+//!
+//! Every value lives in the register the allocator gave it or in a spill
+//! slot of the frame, zero extended from its type, and instructions work on
+//! `%rax` and `%rcx`. Arguments and results follow the C calling
+//! convention, so extern functions can be C functions.
+
+use std::fmt::Write;
+
+use crate::ir::{
+    BinaryOp, BlockCall, BlockId, CompareOp, Function, Inst, InstKind, Module, Terminator, Type,
+    UnaryOp, Value,
+};
+
+use super::{
+    regalloc::{self, Allocation, Place, Registers},
+    symbol,
+};
+
+/// The registers taking the first integer arguments.
+const ARGUMENTS: [&str; 6] = ["%rdi", "%rsi", "%rdx", "%rcx", "%r8", "%r9"];
+
+/// The registers values live in, apart from those the instructions and
+/// calls use themselves.
+pub const REGISTERS: Registers = Registers {
+    caller_saved: &["%r10", "%r11"],
+    callee_saved: &["%rbx", "%r12", "%r13", "%r14", "%r15"],
+};
+
+/// Emits the assembly of a module.
+#[must_use]
+pub fn emit(module: &Module) -> String {
+    let mut out = String::new();
+    for (index, function) in module.functions.iter().enumerate() {
+        if !function.is_extern() {
+            FunctionEmitter::new(module, index, function).emit(&mut out);
+        }
+    }
+    super::data(module, &mut out);
+    out.push_str("\t.section .note.GNU-stack,\"\",@progbits\n");
+    out
+}
+
+/// The live ranges and places of the values of every function.
+#[must_use]
+pub fn live_ranges(module: &Module) -> String {
+    module
+        .functions
+        .iter()
+        .filter(|function| !function.is_extern())
+        .map(|function| regalloc::allocate(function, &REGISTERS).to_string())
+        .collect()
+}
+
+struct FunctionEmitter<'m> {
+    module: &'m Module,
+    index: usize,
+    function: &'m Function,
+    allocation: Allocation,
+    out: String,
+    /// The offset from `%rbp` of the stack slot of every `slot` instruction.
+    slots: Vec<Option<i64>>,
+    frame: i64,
+    /// Labels within blocks, numbered per function.
+    labels: usize,
+}
+
+impl<'m> FunctionEmitter<'m> {
+    fn new(module: &'m Module, index: usize, function: &'m Function) -> Self {
+        let allocation = regalloc::allocate(function, &REGISTERS);
+        let saved = allocation.spill_slots as usize + allocation.saved.len();
+        let mut frame = i64::try_from(8 * saved).expect("frames are small");
+        let mut slots = vec![None; function.types.len()];
+        for inst in function.blocks.iter().flat_map(|block| &block.insts) {
+            if let (InstKind::Slot { size, align, .. }, Some(result)) = (&inst.kind, inst.result) {
+                let (size, align) = (to_i64(*size), to_i64((*align).min(16)));
+                frame = (frame + size + align - 1) / align * align;
+                slots[result.index()] = Some(-frame);
+            }
+        }
+        Self {
+            module,
+            index,
+            function,
+            allocation,
+            out: String::new(),
+            slots,
+            frame: (frame + 15) / 16 * 16,
+            labels: 0,
+        }
+    }
+
+    fn line(&mut self, line: impl AsRef<str>) {
+        self.out.push('\t');
+        self.out.push_str(line.as_ref());
+        self.out.push('\n');
+    }
+
+    fn block_label(&self, block: BlockId) -> String {
+        format!(".Lf{}b{}", self.index, block.0)
+    }
+
+    /// Places a label at the current position.
+    fn place(&mut self, label: &str) {
+        self.out.push_str(label);
+        self.out.push_str(":\n");
+    }
+
+    fn label(&mut self) -> String {
+        self.labels += 1;
+        format!(".Lf{}l{}", self.index, self.labels)
+    }
+
+    /// The register or spill slot of a value that is used.
+    fn home(&self, value: Value) -> String {
+        match self.allocation.place(value) {
+            Some(Place::Register(register)) => register.to_string(),
+            Some(Place::Spill(slot)) => format!("{}(%rbp)", -8 * (i64::from(slot) + 1)),
+            None => panic!("{value} is never used"),
+        }
+    }
+
+    /// Where the prologue saves a callee saved register.
+    fn save_area(&self, index: usize) -> String {
+        let slot = self.allocation.spill_slots as usize + index;
+        format!("{}(%rbp)", -8 * (to_i64(slot as u64) + 1))
+    }
+
+    fn load(&mut self, value: Value, register: &str) {
+        self.line(format!("movq {}, {register}", self.home(value)));
+    }
+
+    /// Stores `%rax` as `value`, cut to its type, unless it is never used.
+    fn store(&mut self, value: Value) {
+        if self.allocation.place(value).is_none() {
+            return;
+        }
+        match self.function.ty(value) {
+            Type::I1 => self.line("andl $1, %eax"),
+            Type::I8 => self.line("movzbl %al, %eax"),
+            Type::I16 => self.line("movzwl %ax, %eax"),
+            Type::I32 => self.line("movl %eax, %eax"),
+            Type::I64 | Type::Ptr => {}
+        }
+        self.line(format!("movq %rax, {}", self.home(value)));
+    }
+
+    /// Sign extends an integer of type `ty` in the 64 bit `register`.
+    fn sign_extend(&mut self, ty: Type, register: &str) {
+        let bits = match ty {
+            Type::I1 => 1,
+            Type::I64 | Type::Ptr => return,
+            ty => 8 * ty.size(),
+        };
+        self.line(format!("shlq ${}, {register}", 64 - bits));
+        self.line(format!("sarq ${}, {register}", 64 - bits));
+    }
+
+    fn emit(mut self, out: &mut String) {
+        let symbol = symbol(self.function);
+        let _ = write!(
+            out,
+            "\t.text\n\t.globl {symbol}\n\t.type {symbol}, @function\n{symbol}:\n"
+        );
+        self.line("pushq %rbp");
+        self.line("movq %rsp, %rbp");
+        if self.frame > 0 {
+            self.line(format!("subq ${}, %rsp", self.frame));
+        }
+        for (index, register) in self.allocation.saved.clone().into_iter().enumerate() {
+            self.line(format!("movq {register}, {}", self.save_area(index)));
+        }
+        let entry = self.function.block(BlockId(0));
+        for (index, param) in entry.params.iter().enumerate() {
+            if self.allocation.place(*param).is_none() {
+                continue;
+            }
+            if let Some(register) = ARGUMENTS.get(index) {
+                self.line(format!("movq {register}, {}", self.home(*param)));
+            } else {
+                let offset = 16 + 8 * (index - ARGUMENTS.len());
+                self.line(format!("movq {offset}(%rbp), %rax"));
+                self.line(format!("movq %rax, {}", self.home(*param)));
+            }
+        }
+        for id in self.function.block_ids() {
+            let label = self.block_label(id);
+            self.place(&label);
+            let block = self.function.block(id);
+            for inst in &block.insts {
+                self.inst(inst);
+            }
+            self.terminator(&block.terminator, block.location);
+        }
+        out.push_str(&self.out);
+        let _ = writeln!(out, "\t.size {symbol}, .-{symbol}");
+    }
+
+    fn inst(&mut self, inst: &Inst) {
+        if let (InstKind::Extend(value), Some(result)) = (&inst.kind, inst.result) {
+            // a coalesced move
+            if self.allocation.place(*value) == self.allocation.place(result) {
+                return;
+            }
+        }
+        match &inst.kind {
+            InstKind::Const(value) => {
+                if i32::try_from(value.cast_signed()).is_ok() {
+                    self.line(format!("movq ${}, %rax", value.cast_signed()));
+                } else {
+                    self.line(format!("movabsq ${}, %rax", value.cast_signed()));
+                }
+            }
+            InstKind::Unary(op, value) => {
+                self.load(*value, "%rax");
+                self.line(match op {
+                    UnaryOp::Neg => "negq %rax",
+                    UnaryOp::Not => "notq %rax",
+                });
+            }
+            InstKind::Binary(op, lhs, rhs) => {
+                let ty = self.function.ty(*lhs);
+                self.load(*lhs, "%rax");
+                self.load(*rhs, "%rcx");
+                self.binary(*op, ty);
+            }
+            InstKind::Compare(op, lhs, rhs) => {
+                let ty = self.function.ty(*lhs);
+                self.load(*lhs, "%rax");
+                self.load(*rhs, "%rcx");
+                if matches!(
+                    op,
+                    CompareOp::Slt | CompareOp::Sle | CompareOp::Sgt | CompareOp::Sge
+                ) {
+                    self.sign_extend(ty, "%rax");
+                    self.sign_extend(ty, "%rcx");
+                }
+                self.line("cmpq %rcx, %rax");
+                self.line(format!("set{} %al", condition(*op)));
+                self.line("movzbl %al, %eax");
+            }
+            InstKind::Select(condition, then, otherwise) => {
+                self.load(*condition, "%rdx");
+                self.load(*then, "%rax");
+                self.load(*otherwise, "%rcx");
+                self.line("testq %rdx, %rdx");
+                self.line("cmoveq %rcx, %rax");
+            }
+            InstKind::Extend(value) => self.load(*value, "%rax"),
+            InstKind::Slot { .. } => {
+                let result = inst.result.expect("slots have a result");
+                let offset = self.slots[result.index()].expect("every slot has a place");
+                self.line(format!("leaq {offset}(%rbp), %rax"));
+            }
+            InstKind::Load(address) => {
+                self.load(*address, "%rcx");
+                let result = inst.result.expect("loads have a result");
+                self.line(match self.function.ty(result) {
+                    Type::I1 | Type::I8 => "movzbl (%rcx), %eax",
+                    Type::I16 => "movzwl (%rcx), %eax",
+                    Type::I32 => "movl (%rcx), %eax",
+                    Type::I64 | Type::Ptr => "movq (%rcx), %rax",
+                });
+            }
+            InstKind::Store { value, address } => {
+                self.load(*value, "%rax");
+                self.load(*address, "%rcx");
+                self.line(match self.function.ty(*value) {
+                    Type::I1 | Type::I8 => "movb %al, (%rcx)",
+                    Type::I16 => "movw %ax, (%rcx)",
+                    Type::I32 => "movl %eax, (%rcx)",
+                    Type::I64 | Type::Ptr => "movq %rax, (%rcx)",
+                });
+            }
+            InstKind::PtrAdd(address, offset) => {
+                self.load(*address, "%rax");
+                self.line(format!("addq {}, %rax", self.home(*offset)));
+            }
+            InstKind::Copy { to, from, size } => {
+                self.load(*to, "%rdi");
+                self.load(*from, "%rsi");
+                self.line(format!("movq ${size}, %rcx"));
+                self.line("rep movsb");
+            }
+            InstKind::Data(index) => self.line(format!("leaq .Ldata{index}(%rip), %rax")),
+            InstKind::Call(callee, arguments) => {
+                let callee = &self.module.functions[callee.index()];
+                self.call(&symbol(callee), callee.is_extern(), arguments);
+            }
+        }
+        if let Some(result) = inst.result {
+            self.store(result);
+        }
+    }
+
+    fn binary(&mut self, op: BinaryOp, ty: Type) {
+        let bits = match ty {
+            Type::I1 => 1,
+            ty => 8 * ty.size(),
+        };
+        match op {
+            BinaryOp::Add => self.line("addq %rcx, %rax"),
+            BinaryOp::Sub => self.line("subq %rcx, %rax"),
+            BinaryOp::Mul => self.line("imulq %rcx, %rax"),
+            BinaryOp::And => self.line("andq %rcx, %rax"),
+            BinaryOp::Or => self.line("orq %rcx, %rax"),
+            BinaryOp::Xor => self.line("xorq %rcx, %rax"),
+            BinaryOp::SDiv | BinaryOp::SRem => {
+                self.sign_extend(ty, "%rax");
+                self.sign_extend(ty, "%rcx");
+                // the quotient of the smallest integer and -1 overflows,
+                // idiv would fault where the IR wraps
+                let (divide, done) = (self.label(), self.label());
+                self.line("cmpq $-1, %rcx");
+                self.line(format!("jne {divide}"));
+                self.line(if op == BinaryOp::SDiv {
+                    "negq %rax"
+                } else {
+                    "xorl %eax, %eax"
+                });
+                self.line(format!("jmp {done}"));
+                self.place(&divide);
+                self.line("cqto");
+                self.line("idivq %rcx");
+                if op == BinaryOp::SRem {
+                    self.line("movq %rdx, %rax");
+                }
+                self.place(&done);
+            }
+            BinaryOp::UDiv | BinaryOp::URem => {
+                self.line("xorl %edx, %edx");
+                self.line("divq %rcx");
+                if op == BinaryOp::URem {
+                    self.line("movq %rdx, %rax");
+                }
+            }
+            BinaryOp::Shl | BinaryOp::LShr | BinaryOp::AShr => {
+                self.line(format!("andl ${}, %ecx", bits - 1));
+                if op == BinaryOp::AShr {
+                    self.sign_extend(ty, "%rax");
+                }
+                self.line(match op {
+                    BinaryOp::Shl => "shlq %cl, %rax",
+                    BinaryOp::LShr => "shrq %cl, %rax",
+                    _ => "sarq %cl, %rax",
+                });
+            }
+        }
+    }
+
+    /// Calls a function with the C calling convention, the result is left
+    /// in `%rax`.
+    fn call(&mut self, symbol: &str, external: bool, arguments: &[Value]) {
+        let stacked = arguments.len().saturating_sub(ARGUMENTS.len());
+        // the stack is 16 byte aligned at every call
+        let padding = if stacked % 2 == 1 { 8 } else { 0 };
+        if padding > 0 {
+            self.line("subq $8, %rsp");
+        }
+        for argument in arguments[ARGUMENTS.len().min(arguments.len())..]
+            .iter()
+            .rev()
+        {
+            self.line(format!("pushq {}", self.home(*argument)));
+        }
+        for (argument, register) in arguments.iter().zip(ARGUMENTS) {
+            self.load(*argument, register);
+        }
+        if external {
+            self.line(format!("call {symbol}@PLT"));
+        } else {
+            self.line(format!("call {symbol}"));
+        }
+        let pushed = 8 * stacked + padding;
+        if pushed > 0 {
+            self.line(format!("addq ${pushed}, %rsp"));
+        }
+    }
+
+    /// Passes the arguments of a jump to the parameters of its target, all
+    /// are read before any is written.
+    fn arguments(&mut self, target: &BlockCall) {
+        let params = &self.function.block(target.block).params;
+        let moves = target
+            .args
+            .iter()
+            .zip(params)
+            .filter(|(arg, param)| {
+                let place = self.allocation.place(**param);
+                place.is_some() && self.allocation.place(**arg) != place
+            })
+            .map(|(arg, param)| (*arg, *param))
+            .collect::<Vec<_>>();
+        for (arg, _) in &moves {
+            self.line(format!("pushq {}", self.home(*arg)));
+        }
+        for (_, param) in moves.iter().rev() {
+            self.line(format!("popq {}", self.home(*param)));
+        }
+    }
+
+    fn jump(&mut self, target: &BlockCall) {
+        self.arguments(target);
+        let label = self.block_label(target.block);
+        self.line(format!("jmp {label}"));
+    }
+
+    fn terminator(&mut self, terminator: &Terminator, location: crate::parser::tokens::Location) {
+        match terminator {
+            Terminator::Jump(target) => self.jump(target),
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let label = self.label();
+                self.line(format!("cmpq $0, {}", self.home(*condition)));
+                self.line(format!("je {label}"));
+                self.jump(then);
+                self.place(&label);
+                self.jump(otherwise);
+            }
+            Terminator::Switch {
+                value,
+                cases,
+                default,
+            } => {
+                self.load(*value, "%rax");
+                let labels = cases.iter().map(|_| self.label()).collect::<Vec<_>>();
+                for ((case, _), label) in cases.iter().zip(&labels) {
+                    self.line(format!("movabsq ${}, %rcx", case.cast_signed()));
+                    self.line("cmpq %rcx, %rax");
+                    self.line(format!("je {label}"));
+                }
+                self.jump(default);
+                for ((_, target), label) in cases.iter().zip(&labels) {
+                    self.place(label);
+                    self.jump(target);
+                }
+            }
+            Terminator::Return(value) => {
+                if let Some(value) = value {
+                    self.load(*value, "%rax");
+                } else if self.function.name == "main" {
+                    self.line("xorl %eax, %eax");
+                }
+                for (index, register) in self.allocation.saved.clone().into_iter().enumerate() {
+                    self.line(format!("movq {}, {register}", self.save_area(index)));
+                }
+                self.line("leave");
+                self.line("ret");
+            }
+            Terminator::Trap(index) => {
+                let length = self.module.data[*index as usize].len();
+                self.line(format!("leaq .Ldata{index}(%rip), %rdi"));
+                self.line(format!("movq ${length}, %rsi"));
+                self.line(format!("leaq .Lfile{}(%rip), %rdx", location.fid));
+                self.line(format!("movq ${}, %rcx", location.l));
+                self.line(format!("movq ${}, %r8", location.c));
+                self.line("call cr.trap@PLT");
+            }
+            Terminator::Unreachable => self.line("ud2"),
+        }
+    }
+}
+
+fn to_i64(value: u64) -> i64 {
+    i64::try_from(value).expect("frames are small")
+}
+
+/// The condition code of a comparison.
+fn condition(op: CompareOp) -> &'static str {
+    match op {
+        CompareOp::Eq => "e",
+        CompareOp::Ne => "ne",
+        CompareOp::Slt => "l",
+        CompareOp::Sle => "le",
+        CompareOp::Sgt => "g",
+        CompareOp::Sge => "ge",
+        CompareOp::Ult => "b",
+        CompareOp::Ule => "be",
+        CompareOp::Ugt => "a",
+        CompareOp::Uge => "ae",
+    }
+}
@@ -0,0 +1,53 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+use log::warn;
+
+use crate::{Error, Warning};
+
+/// Errors and warnings collected by the semantic passes. A pass keeps going
+/// after an error so that one run reports as much as possible.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    pub errors: Vec<Error>,
+    pub warnings: Vec<Warning>,
+}
+
+impl Diagnostics {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn error(&mut self, error: Error) {
+        self.errors.push(error);
+    }
+
+    pub fn warning(&mut self, warning: Warning) {
+        self.warnings.push(warning);
+    }
+
+    #[must_use]
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    /// Logs and drains the warnings, then turns collected errors into an
+    /// [`Error`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the single collected error or [`Error::Multiple`].
+    pub fn check(&mut self) -> Result<(), Error> {
+        for warning in self.warnings.drain(..) {
+            warn!("{warning}");
+        }
+        let error = match self.errors.len() {
+            0 => return Ok(()),
+            1 => self.errors.remove(0),
+            _ => Error::Multiple(self.errors.drain(..).collect()),
+        };
+        Err(error)
+    }
+}
@@ -0,0 +1,458 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use log::info;
+
+use crate::{
+    Error,
+    bytecode::{self, vm::Vm},
+    codegen::{self, Architecture, link},
+    diagnostics::Diagnostics,
+    interpreter::{self, Interpreter},
+    ir::{
+        self,
+        opt::{Level, PassManager},
+    },
+    module::{DefId, ModuleTree},
+    parser::tokens::Location,
+    sema::{
+        assign, flow,
+        layout::{self, Layouts},
+        mono, patterns, resolve, typeck,
+        typed::{Field, TypedProgram},
+        types::Ty,
+    },
+    target::Target,
+};
+
+const USAGE: &str = "cringe-lang [--print-layout] [--hover <line>:<column>] [--disassemble] \
+                     [--emit=ir | --emit=asm | --emit=llvm | --emit=c | --emit=wat | --emit=wasm] [-O0 | -O1 | -O2] [--time-passes] [--print-live-ranges] [--target <triple>] [-o <output>] \
+                     [--run | --interpret] [--stack-depth <calls>] <file.cringe>";
+
+/// Stack of the thread compiling the program. The passes walk expressions
+/// and blocks recursively, up to [`crate::parser::MAX_NESTING`] levels deep.
+const COMPILER_STACK_SIZE: usize = 64 << 20;
+
+/// How `--run` executes the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    /// Compile to bytecode and run it on the VM.
+    Bytecode,
+    /// Walk the typed AST.
+    Ast,
+}
+
+/// What `--emit` prints instead of running the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Emit {
+    /// The SSA intermediate representation.
+    Ir,
+    /// x86-64 assembly.
+    Asm,
+    /// Textual LLVM IR.
+    Llvm,
+    /// C99 source of the checked program.
+    C,
+    /// A WebAssembly module as text.
+    Wat,
+    /// A binary WebAssembly module, written to the output.
+    Wasm,
+}
+
+#[derive(Debug, Clone, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Options {
+    pub input: PathBuf,
+    /// Print the type of the variable at this position of the input file.
+    pub hover: Option<Location>,
+    /// Print size, alignment and field offsets of every struct and enum.
+    pub print_layout: bool,
+    /// Print the bytecode of the program.
+    pub disassemble: bool,
+    /// Print the program in another representation.
+    pub emit: Option<Emit>,
+    /// How much to optimise the IR.
+    pub level: Level,
+    /// Print the time spent in every optimisation pass.
+    pub time_passes: bool,
+    /// Print the live range and place of every value the native backend
+    /// allocates.
+    pub print_live_ranges: bool,
+    /// The target the program is compiled for.
+    pub target: Target,
+    /// Build a native executable, or the binary WebAssembly module, at this
+    /// path.
+    pub output: Option<PathBuf>,
+    /// Run the program with the given engine.
+    pub run: Option<Engine>,
+    /// Nested calls the program may make.
+    pub stack_depth: usize,
+}
+
+impl Options {
+    /// Parses the command line arguments, without the program name.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Usage`] on unknown or missing arguments or a stack
+    /// depth above [`interpreter::LIMIT_MAX_DEPTH`] and [`Error::Target`] on
+    /// a triple of an unknown architecture.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, Error> {
+        let usage = || Error::Usage(USAGE.to_string());
+
+        let mut input = None;
+        let mut hover = None;
+        let mut print_layout = false;
+        let mut disassemble = false;
+        let mut emit = None;
+        let mut level = Level::O0;
+        let mut time_passes = false;
+        let mut print_live_ranges = false;
+        let mut target = Target::default();
+        let mut output = None;
+        let mut run = None;
+        let mut stack_depth = interpreter::DEFAULT_MAX_DEPTH;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--print-layout" => print_layout = true,
+                "--disassemble" => disassemble = true,
+                "--emit=ir" => emit = Some(Emit::Ir),
+                "--emit=asm" => emit = Some(Emit::Asm),
+                "--emit=llvm" => emit = Some(Emit::Llvm),
+                "--emit=c" => emit = Some(Emit::C),
+                "--emit=wat" => emit = Some(Emit::Wat),
+                "--emit=wasm" => emit = Some(Emit::Wasm),
+                "--time-passes" => time_passes = true,
+                "--print-live-ranges" => print_live_ranges = true,
+                "--target" => {
+                    let triple = args.next().ok_or_else(usage)?;
+                    target = triple.parse()?;
+                }
+                _ if arg.starts_with("-O") => level = arg[2..].parse().map_err(|()| usage())?,
+                "--run" => run = Some(Engine::Bytecode),
+                "--interpret" => run = Some(Engine::Ast),
+                "-o" => output = Some(PathBuf::from(args.next().ok_or_else(usage)?)),
+                "--stack-depth" => {
+                    let depth = args.next().ok_or_else(usage)?;
+                    stack_depth = depth.parse().map_err(|_| usage())?;
+                    if stack_depth > interpreter::LIMIT_MAX_DEPTH {
+                        return Err(usage());
+                    }
+                }
+                "--hover" => {
+                    let position = args.next().ok_or_else(usage)?;
+                    hover = Some(parse_position(&position).ok_or_else(usage)?);
+                }
+                _ if arg.starts_with('-') || input.is_some() => return Err(usage()),
+                _ => input = Some(PathBuf::from(arg)),
+            }
+        }
+
+        // a binary module is not printed
+        if emit == Some(Emit::Wasm) && output.is_none() {
+            return Err(usage());
+        }
+
+        let input = input.ok_or_else(usage)?;
+        // the position is in the root file, which has id 0
+        let hover = hover.map(|position: Location| Location {
+            l: position.l,
+            c: position.c,
+            ..Location::in_file(0, &input)
+        });
+        Ok(Self {
+            input,
+            hover,
+            print_layout,
+            disassemble,
+            emit,
+            level,
+            time_passes,
+            print_live_ranges,
+            target,
+            output,
+            run,
+            stack_depth,
+        })
+    }
+}
+
+/// Parses `line:column` into a location.
+fn parse_position(position: &str) -> Option<Location> {
+    let (line, column) = position.split_once(':')?;
+    Some(Location {
+        l: line.parse().ok()?,
+        c: column.parse().ok()?,
+        ..Location::new(0)
+    })
+}
+
+/// A checked, monomorphic program with the layouts of its types.
+#[derive(Debug)]
+pub struct Compilation {
+    pub tree: ModuleTree,
+    pub program: TypedProgram,
+    pub layouts: Layouts,
+}
+
+/// Loads and checks the program rooted at `input` for the default target.
+///
+/// # Errors
+///
+/// Returns the first error of any compiler stage.
+pub fn compile(input: &Path) -> Result<Compilation, Error> {
+    compile_for(input, &Target::default())
+}
+
+/// Loads and checks the program rooted at `input`, laying out its types for
+/// `target`.
+///
+/// # Errors
+///
+/// Returns the first error of any compiler stage.
+pub fn compile_for(input: &Path, target: &Target) -> Result<Compilation, Error> {
+    let tree = ModuleTree::load(input)?;
+    for (fid, file) in tree.files.iter().enumerate() {
+        info!("file {fid}: {}", file.display());
+    }
+    info!(
+        "{} namespaces, {} definitions, {} imports",
+        tree.modules.len(),
+        tree.definitions.len(),
+        tree.imports.len()
+    );
+
+    let mut diagnostics = Diagnostics::new();
+    let resolution = resolve::resolve(&tree, &mut diagnostics);
+    diagnostics.check()?;
+    info!(
+        "{} locals, {} resolved names",
+        resolution.locals.len(),
+        resolution.uses.len()
+    );
+
+    let program = typeck::check(&tree, &resolution, &mut diagnostics);
+    diagnostics.check()?;
+    patterns::check(&program, &mut diagnostics);
+    diagnostics.check()?;
+    flow::check(&program, &mut diagnostics);
+    diagnostics.check()?;
+    assign::check(&program, &mut diagnostics);
+    diagnostics.check()?;
+
+    let mut program = mono::monomorphise(&tree, program, &mut diagnostics);
+    diagnostics.check()?;
+    info!("{} function instances", program.functions.len());
+
+    let layouts = layout::compute(&mut program, target, &mut diagnostics);
+    diagnostics.check()?;
+    Ok(Compilation {
+        tree,
+        program,
+        layouts,
+    })
+}
+
+/// Runs the compiler with the given options. Returns the result of `main`
+/// if the program was run, 0 otherwise.
+///
+/// # Errors
+///
+/// Returns the first error of any compiler stage or of the program run, and
+/// [`Error::Io`] if the threads compiling or running the program cannot be
+/// spawned.
+pub fn run(options: &Options) -> Result<i64, Error> {
+    thread::scope(|scope| {
+        thread::Builder::new()
+            .stack_size(COMPILER_STACK_SIZE)
+            .spawn_scoped(scope, || run_on_thread(options))?
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+fn run_on_thread(options: &Options) -> Result<i64, Error> {
+    let lowered = options.emit.is_some_and(|emit| emit != Emit::C)
+        || options.output.is_some()
+        || options.print_live_ranges;
+    if (lowered || options.disassemble || options.run.is_some()) && !options.target.has_words() {
+        return Err(Error::Target {
+            triple: options.target.triple.clone(),
+            message: "the IR and the interpreters need eight byte pointers and int".to_string(),
+        });
+    }
+
+    let Compilation {
+        tree,
+        program,
+        layouts,
+    } = compile_for(&options.input, &options.target)?;
+    if options.print_layout {
+        println!("target {}", options.target);
+        print_layouts(&tree, &program, &layouts);
+    }
+
+    if let Some(location) = options.hover {
+        match program.binding_at(location) {
+            Some(local) => println!("{}: {}", local.name, local.ty.display(&tree)),
+            None => println!("no variable at {location}"),
+        }
+    }
+
+    if options.emit == Some(Emit::C) {
+        print!("{}", codegen::c::emit(&tree, &program, &layouts));
+    }
+
+    if lowered {
+        let mut module = ir::lower::lower(&tree, &program, &layouts);
+        ir::verify::verify(&module)?;
+        let mut passes = PassManager::new(options.level);
+        passes.run(&mut module)?;
+        ir::verify::verify(&module)?;
+        if options.time_passes {
+            for timing in passes.timings() {
+                eprintln!("{timing}");
+            }
+        }
+        if options.emit == Some(Emit::Ir) {
+            print!("{module:#}");
+        }
+        if options.print_live_ranges {
+            print!("{}", backend(&options.target)?.live_ranges(&module));
+        }
+        if options.emit == Some(Emit::Llvm) {
+            print!(
+                "{}",
+                codegen::llvm::emit(&module, &tree.files, &options.target)
+            );
+        }
+        if options.emit == Some(Emit::Wat) {
+            print!("{}", codegen::wasm::text(&module));
+        }
+        if options.emit == Some(Emit::Wasm) {
+            let output = options.output.as_ref().expect("checked by parse");
+            fs::write(output, codegen::wasm::binary(&module))?;
+        } else if options.emit == Some(Emit::Asm) || options.output.is_some() {
+            let architecture = backend(&options.target)?;
+            let assembly = architecture.emit(&module);
+            if options.emit == Some(Emit::Asm) {
+                print!("{assembly}");
+            }
+            if let Some(output) = &options.output {
+                let (assembler, compiler) = architecture.tools();
+                link::link_with(&assembly, output, &assembler, &compiler)?;
+            }
+        }
+    }
+
+    if options.disassemble || options.run == Some(Engine::Bytecode) {
+        let module = bytecode::compiler::compile(&tree, &program, &layouts);
+        if options.disassemble {
+            print!("{module}");
+        }
+        if options.run == Some(Engine::Bytecode) {
+            return Vm::new(&module, io::stdout().lock())
+                .with_max_depth(options.stack_depth)
+                .run();
+        }
+    }
+    if options.run != Some(Engine::Ast) {
+        return Ok(0);
+    }
+    // every interpreted call nests a few Rust frames, so the native stack
+    // has to grow with the depth the program may reach
+    let stack_size = options
+        .stack_depth
+        .saturating_add(16)
+        .saturating_mul(interpreter::NATIVE_FRAME_SIZE);
+    thread::scope(|scope| {
+        thread::Builder::new()
+            .stack_size(stack_size)
+            .spawn_scoped(scope, || {
+                Interpreter::new(&tree, &program, &layouts, io::stdout().lock())
+                    .with_max_depth(options.stack_depth)
+                    .run()
+            })?
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+/// The native backend of a target.
+fn backend(target: &Target) -> Result<Architecture, Error> {
+    target.architecture.ok_or_else(|| Error::Target {
+        triple: target.triple.clone(),
+        message: "no native backend".to_string(),
+    })
+}
+
+fn print_layouts(tree: &ModuleTree, program: &TypedProgram, layouts: &Layouts) {
+    for (ty, layout) in &layouts.structs {
+        let Ty::Struct(id, generics) = ty else {
+            continue;
+        };
+        println!(
+            "struct {}: size {}, align {}",
+            ty.display(tree),
+            layout.layout.size,
+            layout.layout.align
+        );
+        let fields = &program.structs[id].fields;
+        print_fields(tree, layouts, fields, &layout.offsets, *id, generics, "");
+    }
+
+    for (ty, layout) in &layouts.enums {
+        let Ty::Enum(id, generics) = ty else {
+            continue;
+        };
+        println!(
+            "enum {}: size {}, align {}, tag size {}",
+            ty.display(tree),
+            layout.layout.size,
+            layout.layout.align,
+            layout.tag.size
+        );
+        for (variant, offsets) in program.enums[id].variants.iter().zip(&layout.variants) {
+            println!("    {}", variant.name);
+            print_fields(
+                tree,
+                layouts,
+                &variant.fields,
+                offsets,
+                *id,
+                generics,
+                "    ",
+            );
+        }
+    }
+}
+
+fn print_fields(
+    tree: &ModuleTree,
+    layouts: &Layouts,
+    fields: &[Field],
+    offsets: &[u64],
+    owner: DefId,
+    generics: &[Ty],
+    indent: &str,
+) {
+    for (field, offset) in fields.iter().zip(offsets) {
+        let ty = field.ty.substitute(owner, generics);
+        let layout = layouts.of(&ty);
+        println!(
+            "{indent}    {offset:>4} {}: {} (size {}, align {})",
+            field.name,
+            ty.display(tree),
+            layout.size,
+            layout.align
+        );
+    }
+}
@@ -0,0 +1,907 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+use std::{collections::HashMap, io::Write, rc::Rc};
+
+use crate::{
+    Error,
+    module::{DefId, ModuleTree},
+    parser::{
+        ast::{BinaryOperator, UnaryOperator},
+        tokens::Location,
+    },
+    sema::{
+        layout::{LENGTH_OFFSET, Layout, Layouts},
+        resolve::LocalId,
+        typed::{
+            Arm, ArmBody, Expr, ExprKind, Instance, Iteration, Pattern, PatternKind, Stmt,
+            StmtKind, TypedFunction, TypedProgram,
+        },
+        types::Ty,
+    },
+};
+
+use memory::{Fault, Memory};
+
+pub mod memory;
+
+/// Nested calls allowed unless configured otherwise.
+pub const DEFAULT_MAX_DEPTH: usize = 512;
+
+/// The most nested calls a program may be allowed, so that the native stack
+/// for them stays within reach.
+pub const LIMIT_MAX_DEPTH: usize = 1 << 16;
+
+/// A generous bound on the native stack a single interpreted call uses,
+/// for sizing the stack of the thread the interpreter runs on.
+pub const NATIVE_FRAME_SIZE: usize = 64 * 1024;
+
+/// Runs a checked, monomorphic program by walking its typed AST.
+///
+/// Every value lives in an emulated [`Memory`] with the layouts the compiler
+/// computed, so pointers are plain addresses. Locals are slots in the frame
+/// of their call, literal strings and arrays as well as `std::alloc` live on
+/// the heap. Integer arithmetic wraps and shifts use the amount modulo 64.
+pub struct Interpreter<'p, W: Write> {
+    program: &'p TypedProgram,
+    layouts: &'p Layouts,
+    output: W,
+    memory: Memory,
+    max_depth: usize,
+    frames: Vec<Frame>,
+    /// Offset of every local in the frames of the functions called so far.
+    slots: HashMap<Instance, Rc<Slots>>,
+    /// Address of the bytes of every string literal.
+    strings: HashMap<String, u64>,
+    intrinsics: HashMap<DefId, Intrinsic>,
+}
+
+/// The `extern` functions of the namespace `std`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Intrinsic {
+    Print,
+    Println,
+    PrintInt,
+    PrintUInt,
+    Alloc,
+    Free,
+}
+
+impl Intrinsic {
+    pub(crate) const ALL: [(&'static str, Intrinsic); 6] = [
+        ("print", Intrinsic::Print),
+        ("println", Intrinsic::Println),
+        ("print_int", Intrinsic::PrintInt),
+        ("print_uint", Intrinsic::PrintUInt),
+        ("alloc", Intrinsic::Alloc),
+        ("free", Intrinsic::Free),
+    ];
+}
+
+#[derive(Debug)]
+struct Slots {
+    offsets: HashMap<LocalId, u64>,
+    layout: Layout,
+}
+
+struct Frame {
+    base: u64,
+    slots: Rc<Slots>,
+}
+
+/// Why evaluation stopped early: an error or control leaving a loop or the
+/// function.
+enum Exit {
+    Error(Error),
+    Break(Option<String>, Vec<u8>),
+    Continue(Option<String>),
+    Return(Vec<u8>),
+}
+
+impl From<Error> for Exit {
+    fn from(error: Error) -> Self {
+        Exit::Error(error)
+    }
+}
+
+type Eval<T> = Result<T, Exit>;
+
+impl<'p, W: Write> Interpreter<'p, W> {
+    /// Creates an interpreter for `program` that prints to `output`.
+    pub fn new(
+        tree: &ModuleTree,
+        program: &'p TypedProgram,
+        layouts: &'p Layouts,
+        output: W,
+    ) -> Self {
+        let intrinsics = Intrinsic::ALL
+            .iter()
+            .filter_map(|(name, intrinsic)| Some((tree.prelude(name)?, *intrinsic)))
+            .collect();
+        Self {
+            program,
+            layouts,
+            output,
+            memory: Memory::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            frames: Vec::new(),
+            slots: HashMap::new(),
+            strings: HashMap::new(),
+            intrinsics,
+        }
+    }
+
+    /// Limits the number of nested calls, exceeding it stops the program.
+    #[must_use]
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Calls `main` and returns its result, or 0 if it returns nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MissingMain`] if there is no `main` without
+    /// parameters, [`Error::Panic`] and [`Error::StackOverflow`] if the
+    /// program fails and I/O errors of the output.
+    pub fn run(&mut self) -> Result<i64, Error> {
+        let entry = self.program.functions.iter().find(|(instance, function)| {
+            function.name == "main"
+                && instance.generics.is_empty()
+                && function.parameters.is_empty()
+        });
+        let Some((instance, function)) = entry else {
+            return Err(Error::MissingMain);
+        };
+
+        let result = self.call(instance, Vec::new(), function.location);
+        self.output.flush()?;
+        let result = match result {
+            Ok(result) => result,
+            Err(Exit::Error(error)) => return Err(error),
+            Err(_) => unreachable!("calls catch returns and loops catch breaks"),
+        };
+        Ok(match function.return_type {
+            Ty::Int => int(&result),
+            _ => 0,
+        })
+    }
+
+    fn call(
+        &mut self,
+        instance: &Instance,
+        arguments: Vec<Vec<u8>>,
+        location: Location,
+    ) -> Eval<Vec<u8>> {
+        let program = self.program;
+        let function = &program.functions[instance];
+        if function.external {
+            return self.intrinsic(instance, function, &arguments, location);
+        }
+        if self.frames.len() >= self.max_depth {
+            return Err(Error::StackOverflow {
+                depth: self.max_depth,
+                location,
+            }
+            .into());
+        }
+
+        let slots = self.slots(instance, function);
+        let top = self.memory.top();
+        let base = self.memory.push(slots.layout);
+        self.frames.push(Frame { base, slots });
+        for (parameter, argument) in function.parameters.iter().zip(arguments) {
+            let address = self.local(*parameter);
+            self.write(address, &argument, location)?;
+        }
+
+        let result = match self.exec_block(&function.body) {
+            Ok(()) => Ok(Vec::new()),
+            Err(Exit::Return(value)) => Ok(value),
+            Err(exit) => Err(exit),
+        };
+        self.frames.pop();
+        self.memory.pop(top);
+        result
+    }
+
+    /// Lays out the frame of `function`, every local gets a slot of its own.
+    fn slots(&mut self, instance: &Instance, function: &TypedFunction) -> Rc<Slots> {
+        if let Some(slots) = self.slots.get(instance) {
+            return Rc::clone(slots);
+        }
+        let mut offsets = HashMap::new();
+        let mut layout = Layout::UNIT;
+        for local in function.locals() {
+            if offsets.contains_key(&local) {
+                continue;
+            }
+            let local_layout = self.layouts.of(&self.program.local(local).ty);
+            let offset = layout.size.next_multiple_of(local_layout.align);
+            offsets.insert(local, offset);
+            layout.size = offset + local_layout.size;
+            layout.align = layout.align.max(local_layout.align);
+        }
+        let slots = Rc::new(Slots { offsets, layout });
+        self.slots.insert(instance.clone(), Rc::clone(&slots));
+        slots
+    }
+
+    fn intrinsic(
+        &mut self,
+        instance: &Instance,
+        function: &TypedFunction,
+        arguments: &[Vec<u8>],
+        location: Location,
+    ) -> Eval<Vec<u8>> {
+        let Some(intrinsic) = self.intrinsics.get(&instance.id).copied() else {
+            return Err(panic(
+                format!("extern function {} is not available", function.name),
+                location,
+            ));
+        };
+        match intrinsic {
+            Intrinsic::Print | Intrinsic::Println => {
+                let text = self.string(&arguments[0], location)?;
+                self.output.write_all(&text).map_err(Error::from)?;
+                if matches!(intrinsic, Intrinsic::Println) {
+                    self.output.write_all(b"\n").map_err(Error::from)?;
+                }
+            }
+            Intrinsic::PrintInt => {
+                write!(self.output, "{}", int(&arguments[0])).map_err(Error::from)?;
+            }
+            Intrinsic::PrintUInt => {
+                write!(self.output, "{}", word(&arguments[0])).map_err(Error::from)?;
+            }
+            Intrinsic::Alloc => {
+                let element = self.layouts.of(&instance.generics[0]);
+                let size = element
+                    .size
+                    .checked_mul(word(&arguments[0]))
+                    .ok_or_else(|| panic("allocation too large".to_string(), location))?;
+                let address = self.memory.allocate(Layout {
+                    size,
+                    align: element.align,
+                });
+                return Ok(address.to_le_bytes().to_vec());
+            }
+            Intrinsic::Free => self
+                .memory
+                .free(word(&arguments[0]))
+                .map_err(|fault| fault_panic(fault, location))?,
+        }
+        Ok(Vec::new())
+    }
+
+    fn exec_block(&mut self, body: &[Stmt]) -> Eval<()> {
+        for statement in body {
+            self.exec(statement)?;
+        }
+        Ok(())
+    }
+
+    fn exec(&mut self, statement: &Stmt) -> Eval<()> {
+        match &statement.kind {
+            StmtKind::Block(body) => self.exec_block(body),
+            StmtKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                if self.eval(condition)?[0] != 0 {
+                    self.exec_block(then)
+                } else if let Some(otherwise) = otherwise {
+                    self.exec_block(otherwise)
+                } else {
+                    Ok(())
+                }
+            }
+            StmtKind::For {
+                label,
+                binding,
+                iteration,
+                body,
+            } => {
+                let top = self.memory.top();
+                let result = self.exec_for(label.as_ref(), *binding, iteration, body);
+                self.memory.pop(top);
+                result
+            }
+            StmtKind::Return(value) => {
+                let value = self.eval_optional(value.as_ref())?;
+                Err(Exit::Return(value))
+            }
+            StmtKind::Break { label, value } => {
+                let value = self.eval_optional(value.as_ref())?;
+                Err(Exit::Break(label.clone(), value))
+            }
+            StmtKind::Continue { label } => Err(Exit::Continue(label.clone())),
+            StmtKind::Let { local, value } => {
+                if let Some(value) = value {
+                    let bytes = self.eval(value)?;
+                    let address = self.local(*local);
+                    self.write(address, &bytes, value.location)?;
+                }
+                Ok(())
+            }
+            StmtKind::Assign { target, value } => {
+                let bytes = self.eval(value)?;
+                let address = self.place(target)?;
+                self.write(address, &bytes, target.location)
+            }
+            StmtKind::Expr(expression) => self.eval(expression).map(|_| ()),
+        }
+    }
+
+    fn eval_optional(&mut self, value: Option<&Expr>) -> Eval<Vec<u8>> {
+        value.map_or(Ok(Vec::new()), |value| self.eval(value))
+    }
+
+    /// Runs one pass of a loop body. Returns the value of a `break` that
+    /// leaves the loop, other exits of other loops are passed on.
+    fn pass(&mut self, label: Option<&String>, body: &[Stmt]) -> Eval<Option<Vec<u8>>> {
+        let ours = |target: &Option<String>| target.is_none() || target.as_ref() == label;
+        match self.exec_block(body) {
+            Ok(()) => Ok(None),
+            Err(Exit::Continue(target)) if ours(&target) => Ok(None),
+            Err(Exit::Break(target, value)) if ours(&target) => Ok(Some(value)),
+            Err(exit) => Err(exit),
+        }
+    }
+
+    fn exec_for(
+        &mut self,
+        label: Option<&String>,
+        binding: LocalId,
+        iteration: &Iteration,
+        body: &[Stmt],
+    ) -> Eval<()> {
+        let slot = self.local(binding);
+        match iteration {
+            Iteration::Range {
+                start,
+                end,
+                step,
+                inclusive,
+            } => {
+                let signed = start.ty == Ty::Int;
+                let (mut value, end, step) = self.range(start, end, step.as_ref())?;
+                let inclusive = *inclusive;
+                let more = |value: i128| match (step > 0, inclusive) {
+                    (true, true) => value <= end,
+                    (true, false) => value < end,
+                    (false, true) => value >= end,
+                    (false, false) => value > end,
+                };
+                while more(value) {
+                    // the value lies between start and end, so it fits
+                    let bytes = if signed {
+                        i64::try_from(value).map(i64::to_le_bytes)
+                    } else {
+                        u64::try_from(value).map(u64::to_le_bytes)
+                    }
+                    .expect("range values fit their type");
+                    self.write(slot, &bytes, start.location)?;
+                    if self.pass(label, body)?.is_some() {
+                        break;
+                    }
+                    value += step;
+                }
+            }
+            Iteration::Elements(iterable) => {
+                let (address, length) = fat(&self.eval(iterable)?);
+                let element = match &iterable.ty {
+                    Ty::Array(element) => self.layouts.of(element).size,
+                    _ => 1,
+                };
+                for index in 0..length {
+                    let mut bytes =
+                        self.read(address + index * element, element, iterable.location)?;
+                    if iterable.ty == Ty::Str {
+                        bytes = u64::from(bytes[0]).to_le_bytes().to_vec();
+                    }
+                    self.write(slot, &bytes, iterable.location)?;
+                    if self.pass(label, body)?.is_some() {
+                        break;
+                    }
+                }
+            }
+            Iteration::Iterator { iterator, next } => {
+                self.exec_iterator(label, binding, iterator, next, body)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluates start, end and step of a range.
+    fn range(&mut self, start: &Expr, end: &Expr, step: Option<&Expr>) -> Eval<(i128, i128, i128)> {
+        let signed = start.ty == Ty::Int;
+        let wide = |bytes: &[u8]| {
+            if signed {
+                i128::from(int(bytes))
+            } else {
+                i128::from(word(bytes))
+            }
+        };
+        let start_value = wide(&self.eval(start)?);
+        let end = wide(&self.eval(end)?);
+        let step = match step {
+            Some(step) => wide(&self.eval(step)?),
+            None => 1,
+        };
+        if step == 0 {
+            return Err(panic(
+                "range with a step of zero".to_string(),
+                start.location,
+            ));
+        }
+        Ok((start_value, end, step))
+    }
+
+    fn exec_iterator(
+        &mut self,
+        label: Option<&String>,
+        binding: LocalId,
+        iterator: &Expr,
+        next: &Expr,
+        body: &[Stmt],
+    ) -> Eval<()> {
+        let slot = self.local(binding);
+        let value = self.eval(iterator)?;
+        let pointer = if matches!(iterator.ty, Ty::Pointer(_)) {
+            value
+        } else {
+            let copy = self.memory.push(self.layouts.of(&iterator.ty));
+            self.write(copy, &value, iterator.location)?;
+            copy.to_le_bytes().to_vec()
+        };
+        let ExprKind::Call(instance, _) = &next.kind else {
+            unreachable!("next is a call")
+        };
+        let Ty::Enum(id, _) = &next.ty else {
+            unreachable!("next returns an option")
+        };
+        let layout = &self.layouts.enums[&next.ty];
+        let tag = layout.tag.size;
+        let some = self.program.enums[id]
+            .variants
+            .iter()
+            .position(|variant| variant.name == "Some")
+            .expect("Option has a variant Some");
+        let offset = index(layout.variants[some][0]);
+        let size = index(self.layouts.of(&self.program.local(binding).ty).size);
+        loop {
+            let option = self.call(instance, vec![pointer.clone()], next.location)?;
+            if tag_of(&option, tag) != some {
+                return Ok(());
+            }
+            self.write(slot, &option[offset..offset + size], next.location)?;
+            if self.pass(label, body)?.is_some() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Evaluates an expression to the bytes of its value. Every case with
+    /// locals of its own has a function of its own, which keeps the frame
+    /// of this recursion small.
+    fn eval(&mut self, expression: &Expr) -> Eval<Vec<u8>> {
+        let location = expression.location;
+        let ty = &expression.ty;
+        match &expression.kind {
+            ExprKind::Int(int) => Ok(int.to_le_bytes().to_vec()),
+            ExprKind::Bool(bool) => Ok(vec![u8::from(*bool)]),
+            ExprKind::Str(str) => Ok(self.eval_str(str)),
+            ExprKind::SizeOf(ty) => Ok(self.layouts.of(ty).size.to_le_bytes().to_vec()),
+            ExprKind::Local(_)
+            | ExprKind::Unary(UnaryOperator::Dereference, _)
+            | ExprKind::Index(..)
+                if expression.is_place() =>
+            {
+                self.eval_place(expression)
+            }
+            ExprKind::Unary(operator, operand) => self.eval_unary(*operator, operand),
+            ExprKind::Binary(operator, lhs, rhs) => self.eval_binary(*operator, lhs, rhs, location),
+            ExprKind::Call(instance, arguments) => self.eval_call(instance, arguments, location),
+            // only strings are indexed without being a place
+            ExprKind::Index(base, index) => self.eval_byte(base, index, location),
+            ExprKind::Field(base, field) => self.eval_field(base, *field, ty, location),
+            ExprKind::Len(base) => self.eval_len(base, location),
+            ExprKind::StructInit(_, fields) => {
+                let offsets = self.layouts.structs[ty].offsets.clone();
+                self.compose(self.layouts.of(ty), &[], fields, &offsets)
+            }
+            ExprKind::Variant(_, variant, fields) => {
+                let layout = &self.layouts.enums[ty];
+                let (whole, tag) = (layout.layout, index(layout.tag.size));
+                let offsets = layout.variants[*variant].clone();
+                self.compose(whole, &variant.to_le_bytes()[..tag], fields, &offsets)
+            }
+            ExprKind::ArrayInit(elements) => self.eval_array(elements, ty),
+            ExprKind::Loop { label, body } => loop {
+                if let Some(value) = self.pass(label.as_ref(), body)? {
+                    return Ok(value);
+                }
+            },
+            ExprKind::Match { scrutinee, arms } => self.eval_match(scrutinee, arms),
+            ExprKind::Local(_) => unreachable!("locals are places"),
+            ExprKind::InterfaceCall(..) | ExprKind::Error => {
+                unreachable!("only checked, monomorphic programs run")
+            }
+        }
+    }
+
+    fn eval_str(&mut self, str: &str) -> Vec<u8> {
+        let address = self.intern(str);
+        fat_pointer(address, str.len() as u64)
+    }
+
+    fn eval_place(&mut self, expression: &Expr) -> Eval<Vec<u8>> {
+        let address = self.place(expression)?;
+        let size = self.layouts.of(&expression.ty).size;
+        self.read(address, size, expression.location)
+    }
+
+    fn eval_unary(&mut self, operator: UnaryOperator, operand: &Expr) -> Eval<Vec<u8>> {
+        if operator == UnaryOperator::Address {
+            return Ok(self.place(operand)?.to_le_bytes().to_vec());
+        }
+        let value = self.eval(operand)?;
+        Ok(unary(operator, &operand.ty, &value))
+    }
+
+    fn eval_call(
+        &mut self,
+        instance: &Instance,
+        arguments: &[Expr],
+        location: Location,
+    ) -> Eval<Vec<u8>> {
+        let arguments = arguments
+            .iter()
+            .map(|argument| self.eval(argument))
+            .collect::<Eval<Vec<_>>>()?;
+        self.call(instance, arguments, location)
+    }
+
+    fn eval_byte(&mut self, base: &Expr, index: &Expr, location: Location) -> Eval<Vec<u8>> {
+        let (address, length) = fat(&self.eval(base)?);
+        let index = word(&self.eval(index)?);
+        check_bounds(index, length, location)?;
+        let byte = self.read(address + index, 1, location)?[0];
+        Ok(u64::from(byte).to_le_bytes().to_vec())
+    }
+
+    fn eval_field(
+        &mut self,
+        base: &Expr,
+        field: usize,
+        ty: &Ty,
+        location: Location,
+    ) -> Eval<Vec<u8>> {
+        let (structure, value) = match &base.ty {
+            Ty::Pointer(pointee) => {
+                let address = word(&self.eval(base)?);
+                let size = self.layouts.of(pointee).size;
+                (&**pointee, self.read(address, size, location)?)
+            }
+            structure => (structure, self.eval(base)?),
+        };
+        let offset = index(self.layouts.structs[structure].offsets[field]);
+        Ok(value[offset..offset + index(self.layouts.of(ty).size)].to_vec())
+    }
+
+    fn eval_len(&mut self, base: &Expr, location: Location) -> Eval<Vec<u8>> {
+        let mut value = self.eval(base)?;
+        if matches!(base.ty, Ty::Pointer(_)) {
+            value = self.read(word(&value), Layout::FAT_POINTER.size, location)?;
+        }
+        Ok(fat(&value).1.to_le_bytes().to_vec())
+    }
+
+    /// Builds a struct or variant of the given layout that starts with
+    /// `head` and has the values of `fields` at `offsets`.
+    fn compose(
+        &mut self,
+        layout: Layout,
+        head: &[u8],
+        fields: &[Expr],
+        offsets: &[u64],
+    ) -> Eval<Vec<u8>> {
+        let mut value = vec![0; index(layout.size)];
+        value[..head.len()].copy_from_slice(head);
+        for (field, offset) in fields.iter().zip(offsets) {
+            let bytes = self.eval(field)?;
+            let offset = index(*offset);
+            value[offset..offset + bytes.len()].copy_from_slice(&bytes);
+        }
+        Ok(value)
+    }
+
+    fn eval_array(&mut self, elements: &[Expr], ty: &Ty) -> Eval<Vec<u8>> {
+        let Ty::Array(element) = ty else {
+            unreachable!("array literals are arrays")
+        };
+        let layout = self.layouts.of(element);
+        let address = self.memory.allocate(Layout {
+            size: layout.size * elements.len() as u64,
+            align: layout.align,
+        });
+        let mut next = address;
+        for element in elements {
+            let bytes = self.eval(element)?;
+            self.write(next, &bytes, element.location)?;
+            next += layout.size;
+        }
+        Ok(fat_pointer(address, elements.len() as u64))
+    }
+
+    fn eval_match(&mut self, scrutinee: &Expr, arms: &[Arm]) -> Eval<Vec<u8>> {
+        let value = self.eval(scrutinee)?;
+        for arm in arms {
+            if !self.matches(&arm.pattern, &value)? {
+                continue;
+            }
+            if let Some(guard) = &arm.guard
+                && self.eval(guard)?[0] == 0
+            {
+                continue;
+            }
+            return match &arm.body {
+                ArmBody::Expr(value) => self.eval(value),
+                ArmBody::Block(body) => self.exec_block(body).map(|()| Vec::new()),
+            };
+        }
+        unreachable!("matches are exhaustive")
+    }
+
+    fn eval_binary(
+        &mut self,
+        operator: BinaryOperator,
+        lhs: &Expr,
+        rhs: &Expr,
+        location: Location,
+    ) -> Eval<Vec<u8>> {
+        use BinaryOperator as B;
+
+        let left = self.eval(lhs)?;
+        if let B::LogicalAnd | B::LogicalOr = operator {
+            // the right-hand side only runs if it decides the result
+            if (left[0] != 0) == (operator == B::LogicalOr) {
+                return Ok(left);
+            }
+            return self.eval(rhs);
+        }
+        let right = self.eval(rhs)?;
+
+        if lhs.ty == Ty::Str {
+            let equal = self.string(&left, location)? == self.string(&right, location)?;
+            return Ok(vec![u8::from(equal == (operator == B::Equal))]);
+        }
+        if matches!(operator, B::Div | B::Rem) && word(&right) == 0 {
+            return Err(panic("division by zero".to_string(), location));
+        }
+        Ok(binary(operator, &lhs.ty, &left, &right))
+    }
+
+    /// The address of a place expression.
+    fn place(&mut self, expression: &Expr) -> Eval<u64> {
+        let location = expression.location;
+        match &expression.kind {
+            ExprKind::Local(local) => Ok(self.local(*local)),
+            ExprKind::Unary(UnaryOperator::Dereference, pointer) => Ok(word(&self.eval(pointer)?)),
+            ExprKind::Field(base, field) => {
+                let (structure, address) = match &base.ty {
+                    Ty::Pointer(pointee) => (&**pointee, word(&self.eval(base)?)),
+                    structure => (structure, self.place(base)?),
+                };
+                Ok(address + self.layouts.structs[structure].offsets[*field])
+            }
+            ExprKind::Index(base, index) => {
+                let value = self.eval(base)?;
+                let position = word(&self.eval(index)?);
+                match &base.ty {
+                    Ty::Pointer(element) => {
+                        let size = self.layouts.of(element).size;
+                        Ok(word(&value).wrapping_add(position.wrapping_mul(size)))
+                    }
+                    Ty::Array(element) => {
+                        let (address, length) = fat(&value);
+                        check_bounds(position, length, location)?;
+                        Ok(address + position * self.layouts.of(element).size)
+                    }
+                    _ => unreachable!("strings are not places"),
+                }
+            }
+            _ => unreachable!("checked to be a place"),
+        }
+    }
+
+    /// Tests `value` against `pattern` and writes the bindings on a match.
+    fn matches(&mut self, pattern: &Pattern, value: &[u8]) -> Eval<bool> {
+        let location = pattern.location;
+        match &pattern.kind {
+            PatternKind::Wildcard => Ok(true),
+            PatternKind::Binding(local) => {
+                let address = self.local(*local);
+                self.write(address, value, location)?;
+                Ok(true)
+            }
+            PatternKind::Int(int) => Ok(word(value) == *int),
+            PatternKind::Bool(bool) => Ok((value[0] != 0) == *bool),
+            PatternKind::Str(str) => Ok(self.string(value, location)? == str.as_bytes()),
+            PatternKind::Variant(_, variant, fields) => {
+                let layout = &self.layouts.enums[&pattern.ty];
+                if tag_of(value, layout.tag.size) != *variant {
+                    return Ok(false);
+                }
+                let offsets = layout.variants[*variant].clone();
+                self.matches_fields(fields, &offsets, value)
+            }
+            PatternKind::Struct(_, fields) => {
+                let offsets = self.layouts.structs[&pattern.ty].offsets.clone();
+                self.matches_fields(fields, &offsets, value)
+            }
+        }
+    }
+
+    fn matches_fields(&mut self, fields: &[Pattern], offsets: &[u64], value: &[u8]) -> Eval<bool> {
+        for (field, offset) in fields.iter().zip(offsets) {
+            let offset = index(*offset);
+            let size = index(self.layouts.of(&field.ty).size);
+            if !self.matches(field, &value[offset..offset + size])? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// The address of a local of the current call.
+    fn local(&self, local: LocalId) -> u64 {
+        let frame = self.frames.last().expect("code only runs inside a call");
+        frame.base + frame.slots.offsets[&local]
+    }
+
+    fn intern(&mut self, str: &str) -> u64 {
+        if let Some(address) = self.strings.get(str) {
+            return *address;
+        }
+        let address = self.memory.allocate(Layout {
+            size: str.len() as u64,
+            align: 1,
+        });
+        self.memory
+            .write(address, str.as_bytes())
+            .expect("the block was just allocated");
+        self.strings.insert(str.to_string(), address);
+        address
+    }
+
+    /// The bytes a `str` value points to.
+    fn string(&self, value: &[u8], location: Location) -> Eval<Vec<u8>> {
+        let (address, length) = fat(value);
+        self.read(address, length, location)
+    }
+
+    fn read(&self, address: u64, size: u64, location: Location) -> Eval<Vec<u8>> {
+        self.memory
+            .read(address, size)
+            .map(<[u8]>::to_vec)
+            .map_err(|fault| fault_panic(fault, location))
+    }
+
+    fn write(&mut self, address: u64, bytes: &[u8], location: Location) -> Eval<()> {
+        self.memory
+            .write(address, bytes)
+            .map_err(|fault| fault_panic(fault, location))
+    }
+}
+
+fn unary(operator: UnaryOperator, ty: &Ty, value: &[u8]) -> Vec<u8> {
+    match (operator, ty) {
+        (UnaryOperator::Minus, _) => int(value).wrapping_neg().to_le_bytes().to_vec(),
+        (UnaryOperator::Not, Ty::Bool) => vec![u8::from(value[0] == 0)],
+        (UnaryOperator::Not, _) => (!word(value)).to_le_bytes().to_vec(),
+        _ => unreachable!("other unary operators are handled by eval"),
+    }
+}
+
+fn binary(operator: BinaryOperator, ty: &Ty, lhs: &[u8], rhs: &[u8]) -> Vec<u8> {
+    use BinaryOperator as B;
+
+    if *ty == Ty::Bool {
+        let (lhs, rhs) = (lhs[0] != 0, rhs[0] != 0);
+        let result = match operator {
+            B::And => lhs & rhs,
+            B::Or => lhs | rhs,
+            B::Xor | B::NotEqual => lhs ^ rhs,
+            B::Equal => lhs == rhs,
+            _ => unreachable!("no other operator applies to bool"),
+        };
+        return vec![u8::from(result)];
+    }
+
+    let (a, b) = (word(lhs), word(rhs));
+    let (x, y) = (int(lhs), int(rhs));
+    let signed = *ty == Ty::Int;
+    let shift = u32::try_from(b % 64).expect("below 64");
+    let value = match operator {
+        B::Add => a.wrapping_add(b).to_le_bytes(),
+        B::Sub => a.wrapping_sub(b).to_le_bytes(),
+        B::Mul => a.wrapping_mul(b).to_le_bytes(),
+        B::Div if signed => x.wrapping_div(y).to_le_bytes(),
+        B::Div => (a / b).to_le_bytes(),
+        B::Rem if signed => x.wrapping_rem(y).to_le_bytes(),
+        B::Rem => (a % b).to_le_bytes(),
+        B::ShiftLeft => a.wrapping_shl(shift).to_le_bytes(),
+        B::ShiftRight if signed => x.wrapping_shr(shift).to_le_bytes(),
+        B::ShiftRight => a.wrapping_shr(shift).to_le_bytes(),
+        B::And => (a & b).to_le_bytes(),
+        B::Xor => (a ^ b).to_le_bytes(),
+        B::Or => (a | b).to_le_bytes(),
+        B::Equal => return vec![u8::from(a == b)],
+        B::NotEqual => return vec![u8::from(a != b)],
+        B::Less | B::LessEqual | B::Greater | B::GreaterEqual => {
+            let ordering = if signed { x.cmp(&y) } else { a.cmp(&b) };
+            let result = match operator {
+                B::Less => ordering.is_lt(),
+                B::LessEqual => ordering.is_le(),
+                B::Greater => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            };
+            return vec![u8::from(result)];
+        }
+        B::LogicalAnd | B::LogicalOr => unreachable!("short circuits are handled by eval"),
+    };
+    value.to_vec()
+}
+
+fn check_bounds(index: u64, length: u64, location: Location) -> Eval<()> {
+    if index < length {
+        Ok(())
+    } else {
+        Err(panic(
+            format!("index {index} is out of bounds for length {length}"),
+            location,
+        ))
+    }
+}
+
+fn panic(message: String, location: Location) -> Exit {
+    Exit::Error(Error::Panic { message, location })
+}
+
+fn fault_panic(fault: Fault, location: Location) -> Exit {
+    panic(fault.to_string(), location)
+}
+
+/// An `unsigned int` or a pointer.
+fn word(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().expect("a word has 8 bytes"))
+}
+
+fn int(bytes: &[u8]) -> i64 {
+    i64::from_le_bytes(bytes[..8].try_into().expect("a word has 8 bytes"))
+}
+
+fn fat(bytes: &[u8]) -> (u64, u64) {
+    (word(bytes), word(&bytes[index(LENGTH_OFFSET)..]))
+}
+
+fn fat_pointer(address: u64, length: u64) -> Vec<u8> {
+    let mut bytes = address.to_le_bytes().to_vec();
+    bytes.extend_from_slice(&length.to_le_bytes());
+    bytes
+}
+
+fn tag_of(value: &[u8], size: u64) -> usize {
+    let mut tag = [0; 8];
+    tag[..index(size)].copy_from_slice(&value[..index(size)]);
+    index(u64::from_le_bytes(tag))
+}
+
+fn index(offset: u64) -> usize {
+    usize::try_from(offset).expect("offsets of values fit into the address space")
+}
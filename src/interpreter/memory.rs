@@ -0,0 +1,156 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+use std::{collections::BTreeMap, fmt};
+
+use crate::sema::layout::Layout;
+
+/// Addresses from here on belong to the heap, the ones below to the stack.
+pub const HEAP_BASE: u64 = 1 << 40;
+
+/// The emulated memory of a running program: a stack holding the frames of
+/// the active calls and a heap of blocks that are never reused. Address 0
+/// is never valid, so it serves as the null pointer.
+#[derive(Debug)]
+pub struct Memory {
+    stack: Vec<u8>,
+    heap: Vec<u8>,
+    /// Start and size of every heap block that was not freed.
+    blocks: BTreeMap<u64, u64>,
+}
+
+/// An invalid memory access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    Null,
+    Invalid { address: u64, size: u64 },
+    InvalidFree(u64),
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fault::Null => write!(f, "null pointer dereference"),
+            Fault::Invalid { address, size } => {
+                write!(f, "invalid access of {size} bytes at {address:#x}")
+            }
+            Fault::InvalidFree(address) => write!(f, "free of {address:#x}, which is not a block"),
+        }
+    }
+}
+
+impl Memory {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            // keeps the first stack slot away from address 0
+            stack: vec![0; 16],
+            heap: Vec::new(),
+            blocks: BTreeMap::new(),
+        }
+    }
+
+    /// The end of the stack, to be passed to [`Memory::pop`].
+    #[must_use]
+    pub fn top(&self) -> u64 {
+        self.stack.len() as u64
+    }
+
+    /// Reserves zeroed memory on the stack.
+    pub fn push(&mut self, layout: Layout) -> u64 {
+        let address = self.top().next_multiple_of(layout.align);
+        self.stack.resize(index(address + layout.size), 0);
+        address
+    }
+
+    /// Releases the stack above `top`.
+    pub fn pop(&mut self, top: u64) {
+        self.stack.truncate(index(top));
+    }
+
+    /// Allocates a zeroed block on the heap. Every block gets an address of
+    /// its own, even an empty one.
+    pub fn allocate(&mut self, layout: Layout) -> u64 {
+        let start = (HEAP_BASE + self.heap.len() as u64).next_multiple_of(layout.align.max(1));
+        let end = start + layout.size.max(1);
+        self.heap.resize(index(end - HEAP_BASE), 0);
+        self.blocks.insert(start, end - start);
+        start
+    }
+
+    /// Releases the heap block starting at `address`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Fault::InvalidFree`] if no block starts there.
+    pub fn free(&mut self, address: u64) -> Result<(), Fault> {
+        self.blocks
+            .remove(&address)
+            .map(|_| ())
+            .ok_or(Fault::InvalidFree(address))
+    }
+
+    /// # Errors
+    ///
+    /// Returns a fault if the bytes are not all on the stack or in one heap
+    /// block.
+    pub fn read(&self, address: u64, size: u64) -> Result<&[u8], Fault> {
+        let range = self.range(address, size)?;
+        Ok(if address < HEAP_BASE {
+            &self.stack[range]
+        } else {
+            &self.heap[range]
+        })
+    }
+
+    /// # Errors
+    ///
+    /// Returns a fault if the bytes are not all on the stack or in one heap
+    /// block.
+    pub fn write(&mut self, address: u64, bytes: &[u8]) -> Result<(), Fault> {
+        let range = self.range(address, bytes.len() as u64)?;
+        if address < HEAP_BASE {
+            self.stack[range].copy_from_slice(bytes);
+        } else {
+            self.heap[range].copy_from_slice(bytes);
+        }
+        Ok(())
+    }
+
+    /// The indices of an access in the stack or heap vector.
+    fn range(&self, address: u64, size: u64) -> Result<std::ops::Range<usize>, Fault> {
+        if size == 0 {
+            return Ok(0..0);
+        }
+        if address == 0 {
+            return Err(Fault::Null);
+        }
+        let invalid = Fault::Invalid { address, size };
+        let end = address.checked_add(size).ok_or(invalid)?;
+        if address < HEAP_BASE {
+            return if end <= self.top() {
+                Ok(index(address)..index(end))
+            } else {
+                Err(invalid)
+            };
+        }
+
+        let (start, length) = self.blocks.range(..=address).next_back().ok_or(invalid)?;
+        if end > start + length {
+            return Err(invalid);
+        }
+        Ok(index(address - HEAP_BASE)..index(end - HEAP_BASE))
+    }
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Addresses that reach this point lie within a vector, so they fit.
+fn index(address: u64) -> usize {
+    usize::try_from(address).expect("memory fits into the address space")
+}
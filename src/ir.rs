@@ -0,0 +1,964 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! The mid-level intermediate representation between the checked program
+//! and the backends.
+//!
+//! A function is a list of basic blocks in SSA form. Only scalars are
+//! values: integers, booleans and pointers. Structs, enums, strings and
+//! arrays live in memory and are handled through their address with
+//! explicit loads, stores and copies; a function takes them as a pointer to
+//! a copy owned by the caller and returns them by writing through a pointer
+//! that comes first among its parameters. Instead of phi nodes blocks take
+//! parameters, every jump passes their arguments. The entry block takes the
+//! parameters of the function.
+//!
+//! Integer arithmetic wraps, shifts use the amount modulo the width and
+//! division by zero is undefined; the lowering inserts the checks the
+//! language demands as explicit branches to `trap`.
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+};
+
+use crate::parser::tokens::Location;
+
+pub mod interpret;
+pub mod lower;
+pub mod opt;
+pub mod parse;
+pub mod verify;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Type {
+    I1,
+    I8,
+    I16,
+    I32,
+    I64,
+    Ptr,
+}
+
+impl Type {
+    /// Bytes a value of the type takes in memory.
+    #[must_use]
+    pub fn size(self) -> u64 {
+        match self {
+            Type::I1 | Type::I8 => 1,
+            Type::I16 => 2,
+            Type::I32 => 4,
+            Type::I64 | Type::Ptr => 8,
+        }
+    }
+
+    /// The unsigned integer of the given size in bytes.
+    #[must_use]
+    pub fn int(size: u64) -> Self {
+        match size {
+            1 => Type::I8,
+            2 => Type::I16,
+            4 => Type::I32,
+            8 => Type::I64,
+            _ => unreachable!("no integer has {size} bytes"),
+        }
+    }
+
+    /// The bits of a value of the type that are significant.
+    #[must_use]
+    pub fn mask(self) -> u64 {
+        match self {
+            Type::I1 => 1,
+            Type::I64 | Type::Ptr => u64::MAX,
+            ty => (1 << (8 * ty.size())) - 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Value(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockId(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FuncId(pub u32);
+
+/// An aggregate type of the module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TypeId(pub u32);
+
+impl Value {
+    #[must_use]
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl BlockId {
+    #[must_use]
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl FuncId {
+    #[must_use]
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl TypeId {
+    #[must_use]
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// An index as the number of a value, block or function.
+fn number(index: usize) -> u32 {
+    u32::try_from(index).expect("functions and modules are small")
+}
+
+/// The immediate dominator of a block that was already visited.
+fn processed(dominators: &[Option<BlockId>], block: BlockId) -> BlockId {
+    dominators[block.index()].expect("visited blocks have a dominator")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    SDiv,
+    UDiv,
+    SRem,
+    URem,
+    Shl,
+    LShr,
+    AShr,
+    And,
+    Or,
+    Xor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Slt,
+    Sle,
+    Sgt,
+    Sge,
+    Ult,
+    Ule,
+    Ugt,
+    Uge,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InstKind {
+    Const(u64),
+    Unary(UnaryOp, Value),
+    Binary(BinaryOp, Value, Value),
+    /// Compares two integers or pointers of the same type, the result is
+    /// an `i1`.
+    Compare(CompareOp, Value, Value),
+    /// The second operand if the first is true, else the third.
+    Select(Value, Value, Value),
+    /// Zero extension of an integer to the wider type of the result.
+    Extend(Value),
+    /// Memory in the frame of the call, valid until it returns, holding a
+    /// value of the aggregate type if there is one.
+    Slot {
+        size: u64,
+        align: u64,
+        ty: Option<TypeId>,
+    },
+    Load(Value),
+    Store {
+        value: Value,
+        address: Value,
+    },
+    /// A pointer plus an `i64` offset in bytes.
+    PtrAdd(Value, Value),
+    /// Copies `size` bytes, the ranges are either the same or disjoint.
+    Copy {
+        to: Value,
+        from: Value,
+        size: u64,
+    },
+    /// The address of the bytes of a string constant of the module.
+    Data(u32),
+    Call(FuncId, Vec<Value>),
+}
+
+impl InstKind {
+    /// The values the instruction uses, in order.
+    #[must_use]
+    pub fn operands(&self) -> Vec<Value> {
+        match self {
+            InstKind::Const(_) | InstKind::Slot { .. } | InstKind::Data(_) => Vec::new(),
+            InstKind::Unary(_, value) | InstKind::Extend(value) | InstKind::Load(value) => {
+                vec![*value]
+            }
+            InstKind::Binary(_, lhs, rhs)
+            | InstKind::Compare(_, lhs, rhs)
+            | InstKind::PtrAdd(lhs, rhs) => vec![*lhs, *rhs],
+            InstKind::Select(condition, then, otherwise) => vec![*condition, *then, *otherwise],
+            InstKind::Store { value, address } => vec![*value, *address],
+            InstKind::Copy { to, from, .. } => vec![*to, *from],
+            InstKind::Call(_, arguments) => arguments.clone(),
+        }
+    }
+
+    pub fn operands_mut(&mut self) -> Vec<&mut Value> {
+        match self {
+            InstKind::Const(_) | InstKind::Slot { .. } | InstKind::Data(_) => Vec::new(),
+            InstKind::Unary(_, value) | InstKind::Extend(value) | InstKind::Load(value) => {
+                vec![value]
+            }
+            InstKind::Binary(_, lhs, rhs)
+            | InstKind::Compare(_, lhs, rhs)
+            | InstKind::PtrAdd(lhs, rhs) => vec![lhs, rhs],
+            InstKind::Select(condition, then, otherwise) => vec![condition, then, otherwise],
+            InstKind::Store { value, address } => vec![value, address],
+            InstKind::Copy { to, from, .. } => vec![to, from],
+            InstKind::Call(_, arguments) => arguments.iter_mut().collect(),
+        }
+    }
+
+    /// Returns `true` if the instruction does more than compute its result.
+    #[must_use]
+    pub fn has_effects(&self) -> bool {
+        matches!(
+            self,
+            InstKind::Store { .. } | InstKind::Copy { .. } | InstKind::Call(..)
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inst {
+    pub result: Option<Value>,
+    pub kind: InstKind,
+    pub location: Location,
+}
+
+/// A jump to a block with the arguments for its parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockCall {
+    pub block: BlockId,
+    pub args: Vec<Value>,
+}
+
+impl BlockCall {
+    #[must_use]
+    pub fn new(block: BlockId, args: Vec<Value>) -> Self {
+        Self { block, args }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Terminator {
+    Jump(BlockCall),
+    Branch {
+        condition: Value,
+        then: BlockCall,
+        otherwise: BlockCall,
+    },
+    /// Jumps to the case equal to the integer, or to the default.
+    Switch {
+        value: Value,
+        cases: Vec<(u64, BlockCall)>,
+        default: BlockCall,
+    },
+    Return(Option<Value>),
+    /// Stops the program with the string constant with the given index as
+    /// message.
+    Trap(u32),
+    Unreachable,
+}
+
+impl Terminator {
+    #[must_use]
+    pub fn successors(&self) -> Vec<&BlockCall> {
+        match self {
+            Terminator::Jump(target) => vec![target],
+            Terminator::Branch {
+                then, otherwise, ..
+            } => vec![then, otherwise],
+            Terminator::Switch { cases, default, .. } => cases
+                .iter()
+                .map(|(_, target)| target)
+                .chain([default])
+                .collect(),
+            Terminator::Return(_) | Terminator::Trap(_) | Terminator::Unreachable => Vec::new(),
+        }
+    }
+
+    pub fn successors_mut(&mut self) -> Vec<&mut BlockCall> {
+        match self {
+            Terminator::Jump(target) => vec![target],
+            Terminator::Branch {
+                then, otherwise, ..
+            } => vec![then, otherwise],
+            Terminator::Switch { cases, default, .. } => cases
+                .iter_mut()
+                .map(|(_, target)| target)
+                .chain([default])
+                .collect(),
+            Terminator::Return(_) | Terminator::Trap(_) | Terminator::Unreachable => Vec::new(),
+        }
+    }
+
+    /// The values the terminator uses apart from block arguments.
+    #[must_use]
+    pub fn operands(&self) -> Vec<Value> {
+        match self {
+            Terminator::Branch { condition, .. } => vec![*condition],
+            Terminator::Switch { value, .. } => vec![*value],
+            Terminator::Return(value) => value.iter().copied().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn operands_mut(&mut self) -> Vec<&mut Value> {
+        match self {
+            Terminator::Branch { condition, .. } => vec![condition],
+            Terminator::Switch { value, .. } => vec![value],
+            Terminator::Return(value) => value.iter_mut().collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub params: Vec<Value>,
+    pub insts: Vec<Inst>,
+    pub terminator: Terminator,
+    /// The location of the terminator.
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub params: Vec<Type>,
+    pub result: Option<Type>,
+}
+
+/// A function with its body, or without one if it is `extern`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub name: String,
+    pub signature: Signature,
+    /// The entry comes first.
+    pub blocks: Vec<Block>,
+    /// The type of every value.
+    pub types: Vec<Type>,
+    pub location: Location,
+}
+
+impl Function {
+    #[must_use]
+    pub fn new(name: String, signature: Signature, location: Location) -> Self {
+        Self {
+            name,
+            signature,
+            blocks: Vec::new(),
+            types: Vec::new(),
+            location,
+        }
+    }
+
+    #[must_use]
+    pub fn is_extern(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn value(&mut self, ty: Type) -> Value {
+        self.types.push(ty);
+        Value(number(self.types.len() - 1))
+    }
+
+    #[must_use]
+    pub fn ty(&self, value: Value) -> Type {
+        self.types[value.index()]
+    }
+
+    #[must_use]
+    pub fn block(&self, id: BlockId) -> &Block {
+        &self.blocks[id.index()]
+    }
+
+    pub fn block_mut(&mut self, id: BlockId) -> &mut Block {
+        &mut self.blocks[id.index()]
+    }
+
+    /// Every block with its id, in order.
+    pub fn block_ids(&self) -> impl Iterator<Item = BlockId> + use<> {
+        (0..number(self.blocks.len())).map(BlockId)
+    }
+
+    /// The blocks jumping to every block, each once.
+    #[must_use]
+    pub fn predecessors(&self) -> Vec<Vec<BlockId>> {
+        let mut predecessors = vec![Vec::new(); self.blocks.len()];
+        for id in self.block_ids() {
+            for target in self.block(id).terminator.successors() {
+                let list: &mut Vec<BlockId> = &mut predecessors[target.block.index()];
+                if list.last() != Some(&id) {
+                    list.push(id);
+                }
+            }
+        }
+        predecessors
+    }
+
+    /// The blocks reachable from the entry in reverse postorder, so every
+    /// block comes before its successors apart from back edges.
+    #[must_use]
+    pub fn reverse_postorder(&self) -> Vec<BlockId> {
+        let mut order = Vec::new();
+        if self.is_extern() {
+            return order;
+        }
+        let mut visited = vec![false; self.blocks.len()];
+        // a block with the index of its next successor to visit
+        let mut stack = vec![(BlockId(0), 0)];
+        visited[0] = true;
+        while let Some((block, next)) = stack.pop() {
+            let successors = self.block(block).terminator.successors();
+            if let Some(target) = successors.get(next) {
+                stack.push((block, next + 1));
+                if !visited[target.block.index()] {
+                    visited[target.block.index()] = true;
+                    stack.push((target.block, 0));
+                }
+            } else {
+                order.push(block);
+            }
+        }
+        order.reverse();
+        order
+    }
+
+    /// The immediate dominator of every reachable block, `None` for the
+    /// entry and unreachable blocks. Computed as described by Cooper,
+    /// Harvey and Kennedy in "A Simple, Fast Dominance Algorithm".
+    #[must_use]
+    pub fn dominators(&self) -> Vec<Option<BlockId>> {
+        let order = self.reverse_postorder();
+        let mut position = vec![usize::MAX; self.blocks.len()];
+        for (index, block) in order.iter().enumerate() {
+            position[block.index()] = index;
+        }
+        let predecessors = self.predecessors();
+        let mut dominators = vec![None; self.blocks.len()];
+        if order.is_empty() {
+            return dominators;
+        }
+        dominators[0] = Some(BlockId(0));
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in &order[1..] {
+                let mut candidate: Option<BlockId> = None;
+                for &predecessor in &predecessors[block.index()] {
+                    if dominators[predecessor.index()].is_none() {
+                        continue;
+                    }
+                    candidate = Some(match candidate {
+                        None => predecessor,
+                        Some(mut other) => {
+                            let mut predecessor = predecessor;
+                            while predecessor != other {
+                                while position[predecessor.index()] > position[other.index()] {
+                                    predecessor = processed(&dominators, predecessor);
+                                }
+                                while position[other.index()] > position[predecessor.index()] {
+                                    other = processed(&dominators, other);
+                                }
+                            }
+                            other
+                        }
+                    });
+                }
+                if candidate.is_some() && dominators[block.index()] != candidate {
+                    dominators[block.index()] = candidate;
+                    changed = true;
+                }
+            }
+        }
+        dominators[0] = None;
+        dominators
+    }
+
+    /// Drops the blocks that cannot be reached from the entry.
+    pub fn remove_unreachable(&mut self) {
+        let mut reachable = vec![false; self.blocks.len()];
+        for block in self.reverse_postorder() {
+            reachable[block.index()] = true;
+        }
+        let mut ids = Vec::with_capacity(self.blocks.len());
+        let mut next = 0;
+        for keep in &reachable {
+            ids.push(BlockId(next));
+            next += u32::from(*keep);
+        }
+        let blocks = std::mem::take(&mut self.blocks);
+        self.blocks = blocks
+            .into_iter()
+            .zip(reachable)
+            .filter_map(|(block, keep)| keep.then_some(block))
+            .collect();
+        for block in &mut self.blocks {
+            for target in block.terminator.successors_mut() {
+                target.block = ids[target.block.index()];
+            }
+        }
+    }
+
+    /// Replaces every use of a value by the value it maps to, following
+    /// chains of replacements.
+    pub fn substitute(&mut self, replacements: &HashMap<Value, Value>) {
+        if replacements.is_empty() {
+            return;
+        }
+        let resolve = |value: &mut Value| {
+            while let Some(replacement) = replacements.get(value) {
+                *value = *replacement;
+            }
+        };
+        for block in &mut self.blocks {
+            for inst in &mut block.insts {
+                inst.kind.operands_mut().into_iter().for_each(resolve);
+            }
+            block
+                .terminator
+                .operands_mut()
+                .into_iter()
+                .for_each(resolve);
+            for target in block.terminator.successors_mut() {
+                target.args.iter_mut().for_each(resolve);
+            }
+        }
+    }
+
+    /// The block and instruction index defining every value, `None` as
+    /// index for block parameters.
+    #[must_use]
+    pub fn definitions(&self) -> Vec<Option<(BlockId, Option<usize>)>> {
+        let mut definitions = vec![None; self.types.len()];
+        for id in self.block_ids() {
+            let block = self.block(id);
+            for param in &block.params {
+                definitions[param.index()] = Some((id, None));
+            }
+            for (index, inst) in block.insts.iter().enumerate() {
+                if let Some(result) = inst.result {
+                    definitions[result.index()] = Some((id, Some(index)));
+                }
+            }
+        }
+        definitions
+    }
+
+    /// Returns `true` if every path from the entry to `block` passes
+    /// `dominator`, given the immediate dominators of all blocks.
+    #[must_use]
+    pub fn dominates(dominators: &[Option<BlockId>], dominator: BlockId, block: BlockId) -> bool {
+        let mut current = Some(block);
+        while let Some(id) = current {
+            if id == dominator {
+                return true;
+            }
+            current = dominators[id.index()];
+        }
+        false
+    }
+
+    /// Numbers the values in the order of their definitions and forgets
+    /// the ones that are not defined anywhere.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a value is used but not defined.
+    pub fn renumber(&mut self) {
+        let mut numbers = vec![None; self.types.len()];
+        let mut types = Vec::new();
+        for block in &self.blocks {
+            let results = block.insts.iter().filter_map(|inst| inst.result);
+            for value in block.params.iter().copied().chain(results) {
+                numbers[value.index()] = Some(Value(number(types.len())));
+                types.push(self.types[value.index()]);
+            }
+        }
+        let number = |value: &mut Value| {
+            *value = numbers[value.index()].expect("values are defined before renumbering");
+        };
+        for block in &mut self.blocks {
+            block.params.iter_mut().for_each(number);
+            for inst in &mut block.insts {
+                inst.result.iter_mut().for_each(number);
+                inst.kind.operands_mut().into_iter().for_each(number);
+            }
+            block.terminator.operands_mut().into_iter().for_each(number);
+            for target in block.terminator.successors_mut() {
+                target.args.iter_mut().for_each(number);
+            }
+        }
+        self.types = types;
+    }
+}
+
+/// A struct, enum, string or array of the program. Nothing in the IR
+/// depends on these, they describe slots to backends that keep types.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Aggregate {
+    /// The type in the source.
+    pub name: String,
+    pub size: u64,
+    pub align: u64,
+    /// The named parts in order of their offsets, the bytes between them
+    /// are padding or, for an enum, the fields of its variants.
+    pub fields: Vec<AggregateField>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregateField {
+    pub name: String,
+    pub offset: u64,
+    pub ty: FieldType,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    Scalar(Type),
+    /// An aggregate defined before the one containing it.
+    Aggregate(TypeId),
+}
+
+/// A program in the intermediate representation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Module {
+    pub functions: Vec<Function>,
+    /// String constants.
+    pub data: Vec<String>,
+    pub types: Vec<Aggregate>,
+}
+
+impl Module {
+    #[must_use]
+    pub fn function(&self, name: &str) -> Option<FuncId> {
+        self.functions
+            .iter()
+            .position(|function| function.name == name)
+            .map(|index| FuncId(number(index)))
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Type::I1 => "i1",
+            Type::I8 => "i8",
+            Type::I16 => "i16",
+            Type::I32 => "i32",
+            Type::I64 => "i64",
+            Type::Ptr => "ptr",
+        })
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "%{}", self.0)
+    }
+}
+
+impl Display for BlockId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "b{}", self.0)
+    }
+}
+
+impl Display for TypeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "${}", self.0)
+    }
+}
+
+impl Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldType::Scalar(ty) => write!(f, "{ty}"),
+            FieldType::Aggregate(id) => write!(f, "{id}"),
+        }
+    }
+}
+
+impl UnaryOp {
+    pub const ALL: [(&'static str, UnaryOp); 2] = [("neg", UnaryOp::Neg), ("not", UnaryOp::Not)];
+}
+
+impl BinaryOp {
+    pub const ALL: [(&'static str, BinaryOp); 13] = [
+        ("add", BinaryOp::Add),
+        ("sub", BinaryOp::Sub),
+        ("mul", BinaryOp::Mul),
+        ("sdiv", BinaryOp::SDiv),
+        ("udiv", BinaryOp::UDiv),
+        ("srem", BinaryOp::SRem),
+        ("urem", BinaryOp::URem),
+        ("shl", BinaryOp::Shl),
+        ("lshr", BinaryOp::LShr),
+        ("ashr", BinaryOp::AShr),
+        ("and", BinaryOp::And),
+        ("or", BinaryOp::Or),
+        ("xor", BinaryOp::Xor),
+    ];
+}
+
+impl CompareOp {
+    pub const ALL: [(&'static str, CompareOp); 10] = [
+        ("eq", CompareOp::Eq),
+        ("ne", CompareOp::Ne),
+        ("slt", CompareOp::Slt),
+        ("sle", CompareOp::Sle),
+        ("sgt", CompareOp::Sgt),
+        ("sge", CompareOp::Sge),
+        ("ult", CompareOp::Ult),
+        ("ule", CompareOp::Ule),
+        ("ugt", CompareOp::Ugt),
+        ("uge", CompareOp::Uge),
+    ];
+}
+
+/// The mnemonic of an operator in the text format.
+fn mnemonic<T: PartialEq>(table: &[(&'static str, T)], op: &T) -> &'static str {
+    table
+        .iter()
+        .find(|(_, candidate)| candidate == op)
+        .map(|(name, _)| *name)
+        .expect("every operator has a mnemonic")
+}
+
+/// Writes the name of a function, quoted unless it only consists of
+/// letters, digits, `_`, `:` and `.`.
+fn write_name(f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+    let plain = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | ':' | '.'));
+    if plain {
+        write!(f, "@{name}")
+    } else {
+        write!(f, "@{name:?}")
+    }
+}
+
+fn write_list<T: Display>(f: &mut fmt::Formatter<'_>, items: &[T]) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "{item}")?;
+    }
+    Ok(())
+}
+
+impl Display for BlockCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.block)?;
+        if !self.args.is_empty() {
+            f.write_str("(")?;
+            write_list(f, &self.args)?;
+            f.write_str(")")?;
+        }
+        Ok(())
+    }
+}
+
+impl Function {
+    fn write_inst(&self, f: &mut fmt::Formatter<'_>, module: &Module, inst: &Inst) -> fmt::Result {
+        f.write_str("  ")?;
+        if let Some(result) = inst.result {
+            write!(f, "{result}: {} = ", self.ty(result))?;
+        }
+        match &inst.kind {
+            InstKind::Const(value) => {
+                let ty = inst.result.map_or(Type::I64, |result| self.ty(result));
+                if ty == Type::I64 && value.cast_signed() < 0 {
+                    write!(f, "const {}", value.cast_signed())?;
+                } else {
+                    write!(f, "const {value}")?;
+                }
+            }
+            InstKind::Unary(op, value) => write!(f, "{} {value}", mnemonic(&UnaryOp::ALL, op))?,
+            InstKind::Binary(op, lhs, rhs) => {
+                write!(f, "{} {lhs}, {rhs}", mnemonic(&BinaryOp::ALL, op))?;
+            }
+            InstKind::Compare(op, lhs, rhs) => {
+                write!(f, "{} {lhs}, {rhs}", mnemonic(&CompareOp::ALL, op))?;
+            }
+            InstKind::Select(condition, then, otherwise) => {
+                write!(f, "select {condition}, {then}, {otherwise}")?;
+            }
+            InstKind::Extend(value) => write!(f, "extend {value}")?,
+            InstKind::Slot { size, align, ty } => {
+                write!(f, "slot {size}, {align}")?;
+                if let Some(ty) = ty {
+                    write!(f, ", {ty}")?;
+                }
+            }
+            InstKind::Load(address) => write!(f, "load {address}")?,
+            InstKind::Store { value, address } => write!(f, "store {value}, {address}")?,
+            InstKind::PtrAdd(address, offset) => write!(f, "ptradd {address}, {offset}")?,
+            InstKind::Copy { to, from, size } => write!(f, "copy {to}, {from}, {size}")?,
+            InstKind::Data(index) => write!(f, "data #{index}")?,
+            InstKind::Call(function, arguments) => {
+                f.write_str("call ")?;
+                write_name(f, &module.functions[function.index()].name)?;
+                f.write_str("(")?;
+                write_list(f, arguments)?;
+                f.write_str(")")?;
+            }
+        }
+        write_location(f, inst.location)
+    }
+
+    fn write_terminator(f: &mut fmt::Formatter<'_>, block: &Block) -> fmt::Result {
+        f.write_str("  ")?;
+        match &block.terminator {
+            Terminator::Jump(target) => write!(f, "jump {target}")?,
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => write!(f, "branch {condition}, {then}, {otherwise}")?,
+            Terminator::Switch {
+                value,
+                cases,
+                default,
+            } => {
+                write!(f, "switch {value}")?;
+                for (case, target) in cases {
+                    write!(f, ", {case}: {target}")?;
+                }
+                write!(f, ", default: {default}")?;
+            }
+            Terminator::Return(None) => f.write_str("ret")?,
+            Terminator::Return(Some(value)) => write!(f, "ret {value}")?,
+            Terminator::Trap(index) => write!(f, "trap #{index}")?,
+            Terminator::Unreachable => f.write_str("unreachable")?,
+        }
+        write_location(f, block.location)
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, module: &Module) -> fmt::Result {
+        f.write_str(if self.is_extern() {
+            "extern fn "
+        } else {
+            "fn "
+        })?;
+        write_name(f, &self.name)?;
+        f.write_str("(")?;
+        write_list(f, &self.signature.params)?;
+        f.write_str(")")?;
+        if let Some(result) = self.signature.result {
+            write!(f, " -> {result}")?;
+        }
+        if f.alternate() {
+            write!(f, " !{}", Position(self.location))?;
+        }
+        if self.is_extern() {
+            return writeln!(f);
+        }
+        writeln!(f, " {{")?;
+        for id in self.block_ids() {
+            let block = self.block(id);
+            write!(f, "{id}")?;
+            if !block.params.is_empty() {
+                f.write_str("(")?;
+                for (i, param) in block.params.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{param}: {}", self.ty(*param))?;
+                }
+                f.write_str(")")?;
+            }
+            writeln!(f, ":")?;
+            for inst in &block.insts {
+                self.write_inst(f, module, inst)?;
+            }
+            Self::write_terminator(f, block)?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+/// With the alternate flag every instruction and function is followed by
+/// its source location, `!file:line:column`.
+fn write_location(f: &mut fmt::Formatter<'_>, location: Location) -> fmt::Result {
+    if f.alternate() {
+        write!(f, " !{}", Position(location))?;
+    }
+    writeln!(f)
+}
+
+/// A location by the id of its file, which the text format reads back.
+pub struct Position(pub Location);
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Location { l, c, fid, .. } = self.0;
+        write!(f, "{fid}:{l}:{c}")
+    }
+}
+
+/// The text format, which [`parse::parse`] reads back.
+impl Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, aggregate) in self.types.iter().enumerate() {
+            write!(
+                f,
+                "type ${index} = {:?} ({}, {}) {{",
+                aggregate.name, aggregate.size, aggregate.align
+            )?;
+            for (i, field) in aggregate.fields.iter().enumerate() {
+                let separator = if i == 0 { " " } else { ", " };
+                write!(
+                    f,
+                    "{separator}{:?} {}: {}",
+                    field.name, field.offset, field.ty
+                )?;
+            }
+            writeln!(f, " }}")?;
+        }
+        for (index, data) in self.data.iter().enumerate() {
+            writeln!(f, "data #{index} = {data:?}")?;
+        }
+        // consecutive declarations stay together, everything else is
+        // separated by an empty line
+        let mut previous = (!self.data.is_empty() || !self.types.is_empty()).then_some(false);
+        for function in &self.functions {
+            if previous.is_some_and(|declaration| !declaration || !function.is_extern()) {
+                writeln!(f)?;
+            }
+            function.write(f, self)?;
+            previous = Some(function.is_extern());
+        }
+        Ok(())
+    }
+}
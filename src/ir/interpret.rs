@@ -0,0 +1,377 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! A reference evaluator of the IR on the emulated memory of the
+//! [`Interpreter`](crate::interpreter::Interpreter), so the lowering and
+//! the passes over the IR can be checked against the other engines.
+//!
+//! Division by zero, which the IR leaves undefined, panics.
+
+use std::io::Write;
+
+use crate::{
+    Error,
+    interpreter::{
+        DEFAULT_MAX_DEPTH,
+        memory::{Fault, Memory},
+    },
+    parser::tokens::Location,
+    sema::layout::Layout,
+};
+
+use super::{
+    BinaryOp, Block, BlockCall, BlockId, CompareOp, FuncId, Function, Inst, InstKind, Module,
+    Terminator, Type, UnaryOp, Value,
+};
+
+/// Larger heap blocks are refused instead of exhausting the host.
+const MAX_ALLOCATION: u64 = 1 << 32;
+
+pub struct Evaluator<'m, W: Write> {
+    module: &'m Module,
+    output: W,
+    memory: Memory,
+    max_depth: usize,
+    depth: usize,
+    /// Address of the bytes of every string constant.
+    data: Vec<u64>,
+}
+
+impl<'m, W: Write> Evaluator<'m, W> {
+    /// Creates an evaluator for `module` that prints to `output`.
+    pub fn new(module: &'m Module, output: W) -> Self {
+        let mut memory = Memory::new();
+        let data = module
+            .data
+            .iter()
+            .map(|string| constant(&mut memory, string))
+            .collect();
+        Self {
+            module,
+            output,
+            memory,
+            max_depth: DEFAULT_MAX_DEPTH,
+            depth: 0,
+            data,
+        }
+    }
+
+    /// Limits the number of nested calls, exceeding it stops the program.
+    #[must_use]
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Calls `main` and returns its result, or 0 if it returns nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MissingMain`] if the module has no `main` without
+    /// parameters, [`Error::Panic`] and [`Error::StackOverflow`] if the
+    /// program fails and I/O errors of the output.
+    pub fn run(&mut self) -> Result<i64, Error> {
+        let entry = self
+            .module
+            .function("main")
+            .filter(|id| {
+                self.module.functions[id.index()]
+                    .signature
+                    .params
+                    .is_empty()
+            })
+            .ok_or(Error::MissingMain)?;
+        let location = self.module.functions[entry.index()].location;
+        let result = self.call(entry, &[], location);
+        self.output.flush()?;
+        Ok(result?.map_or(0, u64::cast_signed))
+    }
+
+    fn call(&mut self, id: FuncId, args: &[u64], location: Location) -> Result<Option<u64>, Error> {
+        let function = &self.module.functions[id.index()];
+        if function.is_extern() {
+            return self.external(&function.name, args, location);
+        }
+        if self.depth >= self.max_depth {
+            return Err(Error::StackOverflow {
+                depth: self.max_depth,
+                location,
+            });
+        }
+        self.depth += 1;
+        let top = self.memory.top();
+        let result = self.execute(function, args);
+        self.memory.pop(top);
+        self.depth -= 1;
+        result
+    }
+
+    fn execute(&mut self, function: &Function, args: &[u64]) -> Result<Option<u64>, Error> {
+        let mut values = vec![0; function.types.len()];
+        let mut block = function.block(BlockId(0));
+        for (param, arg) in block.params.iter().zip(args) {
+            values[param.index()] = *arg;
+        }
+        loop {
+            for inst in &block.insts {
+                let value = self.inst(function, &values, inst)?;
+                if let (Some(result), Some(value)) = (inst.result, value) {
+                    values[result.index()] = value & function.ty(result).mask();
+                }
+            }
+            let target = match &block.terminator {
+                Terminator::Jump(target) => target,
+                Terminator::Branch {
+                    condition,
+                    then,
+                    otherwise,
+                } => {
+                    if values[condition.index()] != 0 {
+                        then
+                    } else {
+                        otherwise
+                    }
+                }
+                Terminator::Switch {
+                    value,
+                    cases,
+                    default,
+                } => {
+                    let value = values[value.index()];
+                    cases
+                        .iter()
+                        .find(|(case, _)| *case == value)
+                        .map_or(default, |(_, target)| target)
+                }
+                Terminator::Return(value) => return Ok(value.map(|value| values[value.index()])),
+                Terminator::Trap(index) => {
+                    return Err(Error::Panic {
+                        message: self.module.data[*index as usize].clone(),
+                        location: block.location,
+                    });
+                }
+                Terminator::Unreachable => {
+                    return Err(Error::Panic {
+                        message: "reached unreachable code".into(),
+                        location: block.location,
+                    });
+                }
+            };
+            block = enter(function, &mut values, target);
+        }
+    }
+
+    fn inst(
+        &mut self,
+        function: &Function,
+        values: &[u64],
+        inst: &Inst,
+    ) -> Result<Option<u64>, Error> {
+        let panic = |message: String| Error::Panic {
+            message,
+            location: inst.location,
+        };
+        let fault = |fault: Fault| panic(fault.to_string());
+        let value = |value: &Value| values[value.index()];
+        let ty = |value: &Value| function.ty(*value);
+        Ok(Some(match &inst.kind {
+            InstKind::Const(constant) => *constant,
+            InstKind::Unary(UnaryOp::Neg, operand) => value(operand).wrapping_neg(),
+            InstKind::Unary(UnaryOp::Not, operand) => !value(operand),
+            InstKind::Binary(op, lhs, rhs) => {
+                binary(*op, ty(lhs), value(lhs), value(rhs)).map_err(panic)?
+            }
+            InstKind::Compare(op, lhs, rhs) => {
+                u64::from(compare(*op, ty(lhs), value(lhs), value(rhs)))
+            }
+            InstKind::Select(condition, then, otherwise) => {
+                if value(condition) != 0 {
+                    value(then)
+                } else {
+                    value(otherwise)
+                }
+            }
+            InstKind::Extend(operand) => value(operand),
+            InstKind::Slot { size, align, .. } => self.memory.push(Layout {
+                size: *size,
+                align: *align,
+            }),
+            InstKind::Load(address) => {
+                let size = function
+                    .ty(inst.result.expect("loads have a result"))
+                    .size();
+                let bytes = self.memory.read(value(address), size).map_err(fault)?;
+                let mut word = [0; 8];
+                word[..bytes.len()].copy_from_slice(bytes);
+                u64::from_le_bytes(word)
+            }
+            InstKind::Store {
+                value: stored,
+                address,
+            } => {
+                let bytes = value(stored).to_le_bytes();
+                let size = usize::try_from(ty(stored).size()).expect("at most a word");
+                self.memory
+                    .write(value(address), &bytes[..size])
+                    .map_err(fault)?;
+                return Ok(None);
+            }
+            InstKind::PtrAdd(address, offset) => value(address).wrapping_add(value(offset)),
+            InstKind::Copy { to, from, size } => {
+                let bytes = self
+                    .memory
+                    .read(value(from), *size)
+                    .map_err(fault)?
+                    .to_vec();
+                self.memory.write(value(to), &bytes).map_err(fault)?;
+                return Ok(None);
+            }
+            InstKind::Data(index) => self.data[*index as usize],
+            InstKind::Call(callee, arguments) => {
+                let args = arguments.iter().map(value).collect::<Vec<_>>();
+                return self.call(*callee, &args, inst.location);
+            }
+        }))
+    }
+
+    fn external(
+        &mut self,
+        name: &str,
+        args: &[u64],
+        location: Location,
+    ) -> Result<Option<u64>, Error> {
+        let panic = |message: String| Error::Panic { message, location };
+        // instances of generic externs share the implementation
+        let base = name.split_once('<').map_or(name, |(base, _)| base);
+        match base {
+            "std::print" | "std::println" => {
+                let fat = self
+                    .memory
+                    .read(args[0], 16)
+                    .map_err(|error| panic(error.to_string()))?;
+                let address = u64::from_le_bytes(fat[..8].try_into().expect("8 bytes"));
+                let length = u64::from_le_bytes(fat[8..].try_into().expect("8 bytes"));
+                let bytes = self
+                    .memory
+                    .read(address, length)
+                    .map_err(|error| panic(error.to_string()))?;
+                self.output.write_all(bytes)?;
+                if name == "std::println" {
+                    self.output.write_all(b"\n")?;
+                }
+                Ok(None)
+            }
+            "std::print_int" => {
+                write!(self.output, "{}", args[0].cast_signed())?;
+                Ok(None)
+            }
+            "std::print_uint" => {
+                write!(self.output, "{}", args[0])?;
+                Ok(None)
+            }
+            "std::alloc" => {
+                if args[0] > MAX_ALLOCATION {
+                    return Err(panic("allocation too large".into()));
+                }
+                let layout = Layout {
+                    size: args[0],
+                    align: Layout::WORD.align,
+                };
+                Ok(Some(self.memory.allocate(layout)))
+            }
+            "std::free" => {
+                self.memory
+                    .free(args[0])
+                    .map_err(|error| panic(error.to_string()))?;
+                Ok(None)
+            }
+            _ => Err(panic(format!("extern function {name} is not available"))),
+        }
+    }
+}
+
+fn constant(memory: &mut Memory, string: &str) -> u64 {
+    let address = memory.allocate(Layout {
+        size: string.len() as u64,
+        align: 1,
+    });
+    memory
+        .write(address, string.as_bytes())
+        .expect("the block was just allocated");
+    address
+}
+
+/// Passes the arguments of a jump, all are read before any is written.
+fn enter<'f>(function: &'f Function, values: &mut [u64], target: &BlockCall) -> &'f Block {
+    let block = function.block(target.block);
+    let args = target
+        .args
+        .iter()
+        .map(|arg| values[arg.index()])
+        .collect::<Vec<_>>();
+    for (param, arg) in block.params.iter().zip(args) {
+        values[param.index()] = arg;
+    }
+    block
+}
+
+/// The value of an integer of type `ty` as a signed number.
+pub(crate) fn signed(ty: Type, value: u64) -> i64 {
+    let unused = 64 - bits(ty);
+    (value << unused).cast_signed() >> unused
+}
+
+fn bits(ty: Type) -> u32 {
+    match ty {
+        Type::I1 => 1,
+        ty => u32::try_from(8 * ty.size()).expect("types are small"),
+    }
+}
+
+pub(crate) fn binary(op: BinaryOp, ty: Type, lhs: u64, rhs: u64) -> Result<u64, String> {
+    let amount = u32::try_from(rhs % u64::from(bits(ty))).expect("below 64");
+    let (left, right) = (signed(ty, lhs), signed(ty, rhs));
+    if matches!(
+        op,
+        BinaryOp::SDiv | BinaryOp::UDiv | BinaryOp::SRem | BinaryOp::URem
+    ) && rhs == 0
+    {
+        return Err("division by zero".into());
+    }
+    Ok(match op {
+        BinaryOp::Add => lhs.wrapping_add(rhs),
+        BinaryOp::Sub => lhs.wrapping_sub(rhs),
+        BinaryOp::Mul => lhs.wrapping_mul(rhs),
+        BinaryOp::SDiv => left.wrapping_div(right).cast_unsigned(),
+        BinaryOp::UDiv => lhs / rhs,
+        BinaryOp::SRem => left.wrapping_rem(right).cast_unsigned(),
+        BinaryOp::URem => lhs % rhs,
+        BinaryOp::Shl => lhs << amount,
+        BinaryOp::LShr => lhs >> amount,
+        BinaryOp::AShr => (left >> amount).cast_unsigned(),
+        BinaryOp::And => lhs & rhs,
+        BinaryOp::Or => lhs | rhs,
+        BinaryOp::Xor => lhs ^ rhs,
+    })
+}
+
+pub(crate) fn compare(op: CompareOp, ty: Type, lhs: u64, rhs: u64) -> bool {
+    let (left, right) = if ty == Type::Ptr {
+        (lhs.cast_signed(), rhs.cast_signed())
+    } else {
+        (signed(ty, lhs), signed(ty, rhs))
+    };
+    match op {
+        CompareOp::Eq => lhs == rhs,
+        CompareOp::Ne => lhs != rhs,
+        CompareOp::Slt => left < right,
+        CompareOp::Sle => left <= right,
+        CompareOp::Sgt => left > right,
+        CompareOp::Sge => left >= right,
+        CompareOp::Ult => lhs < rhs,
+        CompareOp::Ule => lhs <= rhs,
+        CompareOp::Ugt => lhs > rhs,
+        CompareOp::Uge => lhs >= rhs,
+    }
+}
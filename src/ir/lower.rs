@@ -0,0 +1,1676 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! Translates a checked, monomorphic program into the IR.
+//!
+//! Locals of scalar type whose address is never taken become SSA values,
+//! constructed on the fly as described by Braun et al. in "Simple and
+//! Efficient Construction of Static Single Assignment Form": reading a
+//! local looks for its last definition in the current block and otherwise
+//! asks the predecessors, adding a block parameter where they may disagree.
+//! A block is sealed once all its predecessors are known, reads in it
+//! before that add parameters whose arguments are filled in then. Every
+//! other local lives in a slot of the entry block.
+//!
+//! An expression of aggregate type yields the address of its value, those
+//! that are not places in a slot of their own. Arguments are copied unless
+//! they already are such a temporary, so a callee may change its
+//! parameters.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    interpreter::Intrinsic,
+    module::{DefId, ModuleTree},
+    parser::{
+        ast::{BinaryOperator, UnaryOperator},
+        tokens::Location,
+    },
+    sema::{
+        layout::{LENGTH_OFFSET, Layout, Layouts},
+        resolve::LocalId,
+        typed::{
+            Arm, ArmBody, Expr, ExprKind, Instance, Iteration, Pattern, PatternKind, Stmt,
+            StmtKind, TypedFunction, TypedProgram, for_each_expr,
+        },
+        types::Ty,
+    },
+};
+
+use super::{
+    Aggregate, AggregateField, BinaryOp, Block, BlockCall, BlockId, CompareOp, FieldType, FuncId,
+    Function, Inst, InstKind, Module, Signature, Terminator, Type, TypeId, UnaryOp, Value, parse,
+};
+
+/// Compares the bytes of two strings given as address and length.
+const STR_EQ: &str = "
+fn @cringe.str_eq(ptr, i64, ptr, i64) -> i1 {
+b0(%a: ptr, %a.len: i64, %b: ptr, %b.len: i64):
+  %same: i1 = eq %a.len, %b.len
+  %false: i1 = const 0
+  %true: i1 = const 1
+  %zero: i64 = const 0
+  %one: i64 = const 1
+  branch %same, b1(%zero), b3(%false)
+b1(%i: i64):
+  %more: i1 = ult %i, %a.len
+  branch %more, b2, b3(%true)
+b2:
+  %x.address: ptr = ptradd %a, %i
+  %x: i8 = load %x.address
+  %y.address: ptr = ptradd %b, %i
+  %y: i8 = load %y.address
+  %equal: i1 = eq %x, %y
+  %next: i64 = add %i, %one
+  branch %equal, b1(%next), b3(%false)
+b3(%result: i1):
+  ret %result
+}
+";
+
+/// Lowers every function of `program`, `extern` ones become declarations.
+/// The intrinsics of `std` keep their names, `std::alloc` takes a size in
+/// bytes instead of a count.
+#[must_use]
+pub fn lower(tree: &ModuleTree, program: &TypedProgram, layouts: &Layouts) -> Module {
+    let intrinsics = Intrinsic::ALL
+        .iter()
+        .filter_map(|(name, intrinsic)| Some((tree.prelude(name)?, *intrinsic)))
+        .collect();
+    let mut lowering = Lowering {
+        tree,
+        program,
+        layouts,
+        intrinsics,
+        functions: HashMap::new(),
+        module: Module::default(),
+        interned: HashMap::new(),
+        types: HashMap::new(),
+    };
+
+    let mut bodies = Vec::new();
+    for (instance, function) in &program.functions {
+        let name = lowering.name(instance, function);
+        if let Some(id) = lowering.module.function(&name) {
+            lowering.functions.insert(instance, id);
+            continue;
+        }
+        let signature = lowering.signature(function);
+        let id = lowering.declare(Function::new(name, signature, function.location));
+        lowering.functions.insert(instance, id);
+        if !function.external {
+            bodies.push((id, function));
+        }
+    }
+    for (id, function) in bodies {
+        let lowered = Builder::lower(&mut lowering, id, function);
+        lowering.module.functions[id.index()] = lowered;
+    }
+    lowering.module
+}
+
+struct Lowering<'p> {
+    tree: &'p ModuleTree,
+    program: &'p TypedProgram,
+    layouts: &'p Layouts,
+    intrinsics: HashMap<DefId, Intrinsic>,
+    functions: HashMap<&'p Instance, FuncId>,
+    module: Module,
+    interned: HashMap<String, u32>,
+    types: HashMap<Ty, TypeId>,
+}
+
+/// How a value of a type is represented.
+#[derive(Clone, Copy)]
+enum Repr {
+    Unit,
+    Scalar(Type),
+    /// In memory, handled by its address.
+    Aggregate(Layout),
+}
+
+impl Lowering<'_> {
+    fn repr(&self, ty: &Ty) -> Repr {
+        match ty {
+            Ty::Unit => Repr::Unit,
+            Ty::Bool => Repr::Scalar(Type::I1),
+            Ty::Int | Ty::UInt => Repr::Scalar(Type::I64),
+            Ty::Pointer(_) => Repr::Scalar(Type::Ptr),
+            ty => Repr::Aggregate(self.layouts.of(ty)),
+        }
+    }
+
+    /// Generic instances carry their type arguments, intrinsics are shared
+    /// by all of theirs.
+    fn name(&self, instance: &Instance, function: &TypedFunction) -> String {
+        if instance.generics.is_empty() || self.intrinsics.contains_key(&instance.id) {
+            return function.name.clone();
+        }
+        let generics = instance
+            .generics
+            .iter()
+            .map(|ty| ty.display(self.tree).to_string())
+            .collect::<Vec<_>>();
+        format!("{}<{}>", function.name, generics.join(", "))
+    }
+
+    fn signature(&self, function: &TypedFunction) -> Signature {
+        let (mut params, result) = match self.repr(&function.return_type) {
+            Repr::Unit => (Vec::new(), None),
+            Repr::Scalar(ty) => (Vec::new(), Some(ty)),
+            Repr::Aggregate(_) => (vec![Type::Ptr], None),
+        };
+        for parameter in &function.parameters {
+            match self.repr(&self.program.local(*parameter).ty) {
+                Repr::Unit => {}
+                Repr::Scalar(ty) => params.push(ty),
+                Repr::Aggregate(_) => params.push(Type::Ptr),
+            }
+        }
+        Signature { params, result }
+    }
+
+    fn declare(&mut self, function: Function) -> FuncId {
+        self.module.functions.push(function);
+        FuncId(u32::try_from(self.module.functions.len() - 1).expect("modules are small"))
+    }
+
+    /// The function with the given name, declared as `extern` if the
+    /// program does not have it yet.
+    fn runtime(&mut self, name: &str, params: Vec<Type>, result: Option<Type>) -> FuncId {
+        if let Some(id) = self.module.function(name) {
+            return id;
+        }
+        let signature = Signature { params, result };
+        self.declare(Function::new(name.to_string(), signature, Location::new(0)))
+    }
+
+    /// The aggregate type describing values of `ty` in memory, defined
+    /// after the ones it contains.
+    fn aggregate(&mut self, ty: &Ty) -> Option<TypeId> {
+        if let Some(id) = self.types.get(ty) {
+            return Some(*id);
+        }
+        let fields = match ty {
+            Ty::Str | Ty::Array(_) => vec![
+                ("ptr".to_string(), 0, Ty::pointer(Ty::UInt)),
+                ("len".to_string(), LENGTH_OFFSET, Ty::UInt),
+            ],
+            Ty::Struct(id, generics) => self.program.structs[id]
+                .fields
+                .iter()
+                .zip(&self.layouts.structs[ty].offsets)
+                .map(|(field, offset)| {
+                    (
+                        field.name.clone(),
+                        *offset,
+                        field.ty.substitute(*id, generics),
+                    )
+                })
+                .collect(),
+            Ty::Enum(..) => {
+                let tag = Type::int(self.layouts.enums[ty].tag.size);
+                let field = AggregateField {
+                    name: "tag".to_string(),
+                    offset: 0,
+                    ty: FieldType::Scalar(tag),
+                };
+                return Some(self.define(ty, vec![field]));
+            }
+            _ => return None,
+        };
+        let fields = fields
+            .into_iter()
+            .filter_map(|(name, offset, field)| {
+                let ty = match self.repr(&field) {
+                    Repr::Unit => return None,
+                    Repr::Scalar(scalar) => FieldType::Scalar(scalar),
+                    Repr::Aggregate(_) => FieldType::Aggregate(self.aggregate(&field)?),
+                };
+                Some(AggregateField { name, offset, ty })
+            })
+            .collect();
+        Some(self.define(ty, fields))
+    }
+
+    fn define(&mut self, ty: &Ty, fields: Vec<AggregateField>) -> TypeId {
+        let layout = self.layouts.of(ty);
+        self.module.types.push(Aggregate {
+            name: ty.display(self.tree).to_string(),
+            size: layout.size,
+            align: layout.align,
+            fields,
+        });
+        let id = TypeId(u32::try_from(self.module.types.len() - 1).expect("few types"));
+        self.types.insert(ty.clone(), id);
+        id
+    }
+
+    fn str_eq(&mut self) -> FuncId {
+        if let Some(id) = self.module.function("cringe.str_eq") {
+            return id;
+        }
+        let mut helper = parse::parse(STR_EQ).expect("the helper is valid IR");
+        self.declare(helper.functions.remove(0))
+    }
+
+    fn data(&mut self, string: &str) -> u32 {
+        if let Some(index) = self.interned.get(string) {
+            return *index;
+        }
+        let index = u32::try_from(self.module.data.len()).expect("few strings");
+        self.module.data.push(string.to_string());
+        self.interned.insert(string.to_string(), index);
+        index
+    }
+}
+
+/// A local or a temporary of the lowering that is an SSA variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Var {
+    Local(LocalId),
+    Temporary(usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Storage {
+    Unit,
+    Variable(Type),
+    /// The address of the value.
+    Memory(Value),
+}
+
+#[derive(Debug, Default)]
+struct BlockState {
+    predecessors: Vec<BlockId>,
+    /// Parameters added before the block was sealed.
+    incomplete: Vec<(Var, Value)>,
+    sealed: bool,
+    terminated: bool,
+}
+
+/// Where a loop leaves its value.
+#[derive(Debug, Clone, Copy)]
+enum Join {
+    None,
+    /// In the only parameter of the exit.
+    Parameter,
+    Memory(Value),
+}
+
+struct Loop {
+    label: Option<String>,
+    /// The target of `continue`.
+    next: BlockId,
+    exit: BlockId,
+    result: Join,
+}
+
+/// The lowering of a single function.
+struct Builder<'l, 'p> {
+    lowering: &'l mut Lowering<'p>,
+    function: Function,
+    current: BlockId,
+    blocks: Vec<BlockState>,
+    /// The slots, they are placed at the start of the entry.
+    slots: Vec<Inst>,
+    locals: HashMap<LocalId, Storage>,
+    temporaries: Vec<Type>,
+    definitions: HashMap<(BlockId, Var), Value>,
+    loops: Vec<Loop>,
+    /// Where an aggregate result is written.
+    out: Option<Value>,
+}
+
+impl<'l, 'p> Builder<'l, 'p> {
+    fn lower(lowering: &'l mut Lowering<'p>, id: FuncId, function: &TypedFunction) -> Function {
+        let declaration = &lowering.module.functions[id.index()];
+        let lowered = Function::new(
+            declaration.name.clone(),
+            declaration.signature.clone(),
+            function.location,
+        );
+        let mut builder = Builder {
+            lowering,
+            function: lowered,
+            current: BlockId(0),
+            blocks: Vec::new(),
+            slots: Vec::new(),
+            locals: HashMap::new(),
+            temporaries: Vec::new(),
+            definitions: HashMap::new(),
+            loops: Vec::new(),
+            out: None,
+        };
+        let entry = builder.block();
+        builder.seal(entry);
+        builder.parameters(function);
+
+        builder.body(&function.body);
+        if builder.is_open() {
+            // flow checking guarantees that only functions without a
+            // result reach their end
+            let end = if function.return_type == Ty::Unit {
+                Terminator::Return(None)
+            } else {
+                Terminator::Unreachable
+            };
+            builder.terminate(end, function.location);
+        }
+        debug_assert!(builder.blocks.iter().all(|block| block.sealed));
+
+        let mut lowered = builder.function;
+        let entry = &mut lowered.blocks[0].insts;
+        let body = std::mem::replace(entry, builder.slots);
+        entry.extend(body);
+        lowered.remove_unreachable();
+        lowered.renumber();
+        lowered
+    }
+
+    /// Binds the parameters and gives every local its storage.
+    fn parameters(&mut self, function: &TypedFunction) {
+        let program = self.lowering.program;
+        let mut addressed = HashSet::new();
+        for_each_expr(&function.body, &mut |expression| {
+            if let ExprKind::Unary(UnaryOperator::Address, operand) = &expression.kind
+                && let ExprKind::Local(local) = operand.kind
+            {
+                addressed.insert(local);
+            }
+        });
+
+        let location = function.location;
+        if let Repr::Aggregate(_) = self.lowering.repr(&function.return_type) {
+            self.out = Some(self.parameter(BlockId(0), Type::Ptr));
+        }
+        for local in function.locals() {
+            if self.locals.contains_key(&local) {
+                continue;
+            }
+            let is_parameter = function.parameters.contains(&local);
+            let storage = match self.lowering.repr(&program.local(local).ty) {
+                Repr::Unit => Storage::Unit,
+                Repr::Scalar(ty) if !addressed.contains(&local) => {
+                    if is_parameter {
+                        let value = self.parameter(BlockId(0), ty);
+                        self.write(Var::Local(local), BlockId(0), value);
+                    }
+                    Storage::Variable(ty)
+                }
+                Repr::Scalar(ty) => {
+                    let slot = self.slot(&program.local(local).ty, location);
+                    if is_parameter {
+                        let value = self.parameter(BlockId(0), ty);
+                        self.effect(
+                            InstKind::Store {
+                                value,
+                                address: slot,
+                            },
+                            location,
+                        );
+                    }
+                    Storage::Memory(slot)
+                }
+                // the caller passes a copy that belongs to the callee
+                Repr::Aggregate(_) if is_parameter => {
+                    Storage::Memory(self.parameter(BlockId(0), Type::Ptr))
+                }
+                Repr::Aggregate(_) => {
+                    Storage::Memory(self.slot(&program.local(local).ty, location))
+                }
+            };
+            self.locals.insert(local, storage);
+        }
+    }
+
+    fn block(&mut self) -> BlockId {
+        self.function.blocks.push(Block {
+            params: Vec::new(),
+            insts: Vec::new(),
+            terminator: Terminator::Unreachable,
+            location: self.function.location,
+        });
+        self.blocks.push(BlockState::default());
+        BlockId(u32::try_from(self.blocks.len() - 1).expect("functions are small"))
+    }
+
+    fn parameter(&mut self, block: BlockId, ty: Type) -> Value {
+        let value = self.function.value(ty);
+        self.function.block_mut(block).params.push(value);
+        value
+    }
+
+    fn switch_to(&mut self, block: BlockId) {
+        self.current = block;
+    }
+
+    fn is_open(&self) -> bool {
+        !self.blocks[self.current.index()].terminated
+    }
+
+    fn terminate(&mut self, terminator: Terminator, location: Location) {
+        let current = self.current;
+        debug_assert!(self.is_open(), "{current} is already terminated");
+        for target in terminator.successors() {
+            let state = &mut self.blocks[target.block.index()];
+            debug_assert!(!state.sealed, "{} is already sealed", target.block);
+            if state.predecessors.last() != Some(&current) {
+                state.predecessors.push(current);
+            }
+        }
+        let block = self.function.block_mut(current);
+        block.terminator = terminator;
+        block.location = location;
+        self.blocks[current.index()].terminated = true;
+    }
+
+    fn jump(&mut self, target: BlockId, args: Vec<Value>, location: Location) {
+        self.terminate(Terminator::Jump(BlockCall::new(target, args)), location);
+    }
+
+    fn branch(
+        &mut self,
+        condition: Value,
+        then: BlockCall,
+        otherwise: BlockCall,
+        location: Location,
+    ) {
+        let terminator = Terminator::Branch {
+            condition,
+            then,
+            otherwise,
+        };
+        self.terminate(terminator, location);
+    }
+
+    /// Continues in a new block if `condition` holds, else jumps to `fail`.
+    fn test(&mut self, condition: Value, fail: BlockId, location: Location) {
+        let pass = self.block();
+        let (then, otherwise) = (
+            BlockCall::new(pass, Vec::new()),
+            BlockCall::new(fail, Vec::new()),
+        );
+        self.branch(condition, then, otherwise, location);
+        self.seal(pass);
+        self.switch_to(pass);
+    }
+
+    /// Panics with `message` unless `condition` holds.
+    fn check(&mut self, condition: Value, message: &str, location: Location) {
+        let fail = self.block();
+        self.test(condition, fail, location);
+        self.seal(fail);
+        let pass = self.current;
+        self.switch_to(fail);
+        let message = self.lowering.data(message);
+        self.terminate(Terminator::Trap(message), location);
+        self.switch_to(pass);
+    }
+
+    fn seal(&mut self, block: BlockId) {
+        let incomplete = std::mem::take(&mut self.blocks[block.index()].incomplete);
+        for (var, parameter) in incomplete {
+            self.arguments(var, block, parameter);
+        }
+        self.blocks[block.index()].sealed = true;
+    }
+
+    fn write(&mut self, var: Var, block: BlockId, value: Value) {
+        self.definitions.insert((block, var), value);
+    }
+
+    fn var_type(&self, var: Var) -> Type {
+        match var {
+            Var::Local(local) => match self.locals[&local] {
+                Storage::Variable(ty) => ty,
+                storage => unreachable!("{storage:?} is not a variable"),
+            },
+            Var::Temporary(index) => self.temporaries[index],
+        }
+    }
+
+    /// The value of `var` at the end of `block`.
+    fn read(&mut self, var: Var, block: BlockId) -> Value {
+        // chains of blocks with a single predecessor are followed without
+        // recursion
+        let mut chain = Vec::new();
+        let mut current = block;
+        let value = loop {
+            if let Some(value) = self.definitions.get(&(current, var)) {
+                break *value;
+            }
+            let state = &self.blocks[current.index()];
+            if state.sealed && state.predecessors.len() == 1 {
+                chain.push(current);
+                current = state.predecessors[0];
+                continue;
+            }
+            break self.read_at_join(var, current);
+        };
+        for block in chain {
+            self.write(var, block, value);
+        }
+        value
+    }
+
+    fn read_at_join(&mut self, var: Var, block: BlockId) -> Value {
+        let ty = self.var_type(var);
+        let state = &self.blocks[block.index()];
+        if !state.sealed {
+            let parameter = self.parameter(block, ty);
+            self.blocks[block.index()].incomplete.push((var, parameter));
+            self.write(var, block, parameter);
+            return parameter;
+        }
+        if state.predecessors.is_empty() {
+            // only unreachable code or the entry reads a variable before
+            // it is defined
+            let undefined = self.function.value(ty);
+            let inst = Inst {
+                result: Some(undefined),
+                kind: InstKind::Const(0),
+                location: self.function.location,
+            };
+            self.function.block_mut(block).insts.insert(0, inst);
+            self.write(var, block, undefined);
+            return undefined;
+        }
+        let parameter = self.parameter(block, ty);
+        self.write(var, block, parameter);
+        self.arguments(var, block, parameter);
+        parameter
+    }
+
+    /// Passes the value of `var` from every predecessor of `block`.
+    fn arguments(&mut self, var: Var, block: BlockId, parameter: Value) {
+        let predecessors = self.blocks[block.index()].predecessors.clone();
+        for predecessor in predecessors {
+            let value = self.read(var, predecessor);
+            let terminator = &mut self.function.block_mut(predecessor).terminator;
+            for target in terminator.successors_mut() {
+                if target.block == block {
+                    target.args.push(value);
+                }
+            }
+        }
+        debug_assert!(self.function.block(block).params.contains(&parameter));
+    }
+
+    /// A new variable that is not a local.
+    fn temporary(&mut self, ty: Type) -> Var {
+        self.temporaries.push(ty);
+        Var::Temporary(self.temporaries.len() - 1)
+    }
+
+    fn value(&mut self, kind: InstKind, ty: Type, location: Location) -> Value {
+        let result = self.function.value(ty);
+        let inst = Inst {
+            result: Some(result),
+            kind,
+            location,
+        };
+        self.function.block_mut(self.current).insts.push(inst);
+        result
+    }
+
+    fn effect(&mut self, kind: InstKind, location: Location) {
+        let inst = Inst {
+            result: None,
+            kind,
+            location,
+        };
+        self.function.block_mut(self.current).insts.push(inst);
+    }
+
+    fn constant(&mut self, ty: Type, value: u64, location: Location) -> Value {
+        self.value(InstKind::Const(value), ty, location)
+    }
+
+    /// A slot for a value of type `ty`, a word for scalars.
+    fn slot(&mut self, ty: &Ty, location: Location) -> Value {
+        let layout = match self.lowering.repr(ty) {
+            Repr::Aggregate(layout) => layout,
+            Repr::Unit | Repr::Scalar(_) => Layout::WORD,
+        };
+        let aggregate = self.lowering.aggregate(ty);
+        let result = self.function.value(Type::Ptr);
+        self.slots.push(Inst {
+            result: Some(result),
+            kind: InstKind::Slot {
+                size: layout.size,
+                align: layout.align,
+                ty: aggregate,
+            },
+            location,
+        });
+        result
+    }
+
+    fn offset(&mut self, address: Value, offset: u64, location: Location) -> Value {
+        if offset == 0 {
+            return address;
+        }
+        let offset = self.constant(Type::I64, offset, location);
+        self.value(InstKind::PtrAdd(address, offset), Type::Ptr, location)
+    }
+
+    /// Reads a value of type `ty`, an aggregate is referred to by the
+    /// address.
+    fn load(&mut self, address: Value, ty: &Ty, location: Location) -> Option<Value> {
+        match self.lowering.repr(ty) {
+            Repr::Unit => None,
+            Repr::Scalar(scalar) => Some(self.value(InstKind::Load(address), scalar, location)),
+            Repr::Aggregate(_) => Some(address),
+        }
+    }
+
+    /// Writes a value of type `ty`, an aggregate is copied.
+    fn store(&mut self, value: Option<Value>, address: Value, ty: &Ty, location: Location) {
+        let kind = match self.lowering.repr(ty) {
+            Repr::Unit => return,
+            Repr::Aggregate(layout) if layout.size == 0 => return,
+            Repr::Scalar(_) => InstKind::Store {
+                value: value.expect("scalars have a value"),
+                address,
+            },
+            Repr::Aggregate(layout) => InstKind::Copy {
+                to: address,
+                from: value.expect("aggregates have an address"),
+                size: layout.size,
+            },
+        };
+        self.effect(kind, location);
+    }
+
+    /// The address and the length of a string or an array.
+    fn fat(&mut self, address: Value, location: Location) -> (Value, Value) {
+        let data = self.value(InstKind::Load(address), Type::Ptr, location);
+        let length = self.offset(address, LENGTH_OFFSET, location);
+        let length = self.value(InstKind::Load(length), Type::I64, location);
+        (data, length)
+    }
+
+    fn assign(&mut self, local: LocalId, value: Option<Value>, ty: &Ty, location: Location) {
+        match self.locals[&local] {
+            Storage::Unit => {}
+            Storage::Variable(_) => {
+                let value = value.expect("variables have a value");
+                self.write(Var::Local(local), self.current, value);
+            }
+            Storage::Memory(address) => self.store(value, address, ty, location),
+        }
+    }
+
+    fn body(&mut self, body: &[Stmt]) {
+        for statement in body {
+            // the rest is unreachable
+            if !self.is_open() {
+                return;
+            }
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &Stmt) {
+        let location = statement.location;
+        match &statement.kind {
+            StmtKind::Block(body) => self.body(body),
+            StmtKind::If {
+                condition,
+                then,
+                otherwise,
+            } => self.if_statement(condition, then, otherwise.as_deref(), location),
+            StmtKind::For {
+                label,
+                binding,
+                iteration,
+                body,
+            } => match &**iteration {
+                Iteration::Range {
+                    start,
+                    end,
+                    step,
+                    inclusive,
+                } => {
+                    let range = (start, end, step.as_ref(), *inclusive);
+                    self.range_loop(label.as_ref(), *binding, range, body, location);
+                }
+                Iteration::Elements(iterable) => {
+                    self.elements_loop(label.as_ref(), *binding, iterable, body, location);
+                }
+                Iteration::Iterator { iterator, next } => {
+                    self.iterator_loop(label.as_ref(), *binding, iterator, next, body, location);
+                }
+            },
+            StmtKind::Return(value) => {
+                let value = value.as_ref().and_then(|value| {
+                    let result = self.expression(value);
+                    if let Some(out) = self.out {
+                        self.store(result, out, &value.ty, location);
+                        return None;
+                    }
+                    result
+                });
+                self.terminate(Terminator::Return(value), location);
+            }
+            StmtKind::Break { label, value } => {
+                let target = self.target(label.as_ref());
+                let mut args = Vec::new();
+                if let Some(value) = value {
+                    let result = self.expression(value);
+                    match self.loops[target].result {
+                        Join::None => {}
+                        Join::Parameter => args.extend(result),
+                        Join::Memory(address) => {
+                            self.store(result, address, &value.ty, location);
+                        }
+                    }
+                }
+                self.jump(self.loops[target].exit, args, location);
+            }
+            StmtKind::Continue { label } => {
+                let target = self.target(label.as_ref());
+                self.jump(self.loops[target].next, Vec::new(), location);
+            }
+            StmtKind::Let { local, value } => {
+                if let Some(value) = value {
+                    let result = self.expression(value);
+                    self.assign(*local, result, &value.ty, location);
+                }
+            }
+            StmtKind::Assign { target, value } => {
+                let result = self.expression(value);
+                if let ExprKind::Local(local) = &target.kind {
+                    self.assign(*local, result, &value.ty, location);
+                } else {
+                    let address = self.place(target);
+                    self.store(result, address, &value.ty, location);
+                }
+            }
+            StmtKind::Expr(expression) => _ = self.expression(expression),
+        }
+    }
+
+    fn if_statement(
+        &mut self,
+        condition: &Expr,
+        then: &[Stmt],
+        otherwise: Option<&[Stmt]>,
+        location: Location,
+    ) {
+        let condition = self.operand(condition);
+        let then_block = self.block();
+        let end = self.block();
+        let otherwise_block = if otherwise.is_some() {
+            self.block()
+        } else {
+            end
+        };
+        self.branch(
+            condition,
+            BlockCall::new(then_block, Vec::new()),
+            BlockCall::new(otherwise_block, Vec::new()),
+            location,
+        );
+        self.seal(then_block);
+        self.switch_to(then_block);
+        self.body(then);
+        if self.is_open() {
+            self.jump(end, Vec::new(), location);
+        }
+        if let Some(otherwise) = otherwise {
+            self.seal(otherwise_block);
+            self.switch_to(otherwise_block);
+            self.body(otherwise);
+            if self.is_open() {
+                self.jump(end, Vec::new(), location);
+            }
+        }
+        self.seal(end);
+        self.switch_to(end);
+    }
+
+    /// The index of the loop a `break` or `continue` refers to.
+    fn target(&self, label: Option<&String>) -> usize {
+        self.loops
+            .iter()
+            .rposition(|candidate| label.is_none() || candidate.label.as_ref() == label)
+            .expect("checked to be inside the loop")
+    }
+
+    /// Jumps to a new block that is the head of a loop, it is sealed by
+    /// [`Builder::leave_loop`].
+    fn enter_loop(&mut self, location: Location) -> BlockId {
+        let head = self.block();
+        self.jump(head, Vec::new(), location);
+        self.switch_to(head);
+        head
+    }
+
+    /// Lowers the body of a loop, `continue` and its end jump to `next`.
+    fn loop_body(
+        &mut self,
+        label: Option<&String>,
+        (next, exit, result): (BlockId, BlockId, Join),
+        body: &[Stmt],
+        location: Location,
+    ) {
+        self.loops.push(Loop {
+            label: label.cloned(),
+            next,
+            exit,
+            result,
+        });
+        self.body(body);
+        if self.is_open() {
+            self.jump(next, Vec::new(), location);
+        }
+        self.loops.pop();
+    }
+
+    /// Enters the body of a loop if `condition` holds, else leaves it for
+    /// the returned exit.
+    fn loop_test(&mut self, condition: Value, location: Location) -> BlockId {
+        let exit = self.block();
+        let body = self.block();
+        let (then, otherwise) = (
+            BlockCall::new(body, Vec::new()),
+            BlockCall::new(exit, Vec::new()),
+        );
+        self.branch(condition, then, otherwise, location);
+        self.seal(body);
+        self.switch_to(body);
+        exit
+    }
+
+    fn range_loop(
+        &mut self,
+        label: Option<&String>,
+        binding: LocalId,
+        (start, end, step, inclusive): (&Expr, &Expr, Option<&Expr>, bool),
+        body: &[Stmt],
+        location: Location,
+    ) {
+        let first = self.operand(start);
+        let last = self.operand(end);
+        let zero = self.constant(Type::I64, 0, location);
+        let step = match step {
+            Some(step) => {
+                let value = self.operand(step);
+                let valid = self.value(
+                    InstKind::Compare(CompareOp::Ne, value, zero),
+                    Type::I1,
+                    location,
+                );
+                self.check(valid, "range with a step of zero", start.location);
+                value
+            }
+            None => self.constant(Type::I64, 1, location),
+        };
+        // a negative step counts down
+        let upwards = (start.ty == Ty::Int).then(|| {
+            self.value(
+                InstKind::Compare(CompareOp::Sgt, step, zero),
+                Type::I1,
+                location,
+            )
+        });
+        let counter = self.temporary(Type::I64);
+        self.write(counter, self.current, first);
+
+        let head = self.enter_loop(location);
+        let value = self.read(counter, head);
+        let (up, down) = if inclusive {
+            (CompareOp::Sle, CompareOp::Sge)
+        } else {
+            (CompareOp::Slt, CompareOp::Sgt)
+        };
+        let within = if let Some(upwards) = upwards {
+            self.choose(upwards, (up, down), (value, last), location)
+        } else {
+            let op = if inclusive {
+                CompareOp::Ule
+            } else {
+                CompareOp::Ult
+            };
+            self.value(InstKind::Compare(op, value, last), Type::I1, location)
+        };
+        let exit = self.loop_test(within, location);
+        self.assign(binding, Some(value), &start.ty, location);
+        let next = self.block();
+        self.loop_body(label, (next, exit, Join::None), body, location);
+
+        self.seal(next);
+        self.switch_to(next);
+        let value = self.read(counter, next);
+        let advanced = self.value(
+            InstKind::Binary(BinaryOp::Add, value, step),
+            Type::I64,
+            location,
+        );
+        self.write(counter, next, advanced);
+        // the loop also ends when the counter leaves the range of its type
+        let wrapped = match upwards {
+            Some(upwards) => self.choose(
+                upwards,
+                (CompareOp::Slt, CompareOp::Sgt),
+                (advanced, value),
+                location,
+            ),
+            None => self.value(
+                InstKind::Compare(CompareOp::Ult, advanced, value),
+                Type::I1,
+                location,
+            ),
+        };
+        self.branch(
+            wrapped,
+            BlockCall::new(exit, Vec::new()),
+            BlockCall::new(head, Vec::new()),
+            location,
+        );
+        self.seal(head);
+        self.seal(exit);
+        self.switch_to(exit);
+    }
+
+    /// One comparison if `condition` holds, else the other.
+    fn choose(
+        &mut self,
+        condition: Value,
+        (then, otherwise): (CompareOp, CompareOp),
+        (lhs, rhs): (Value, Value),
+        location: Location,
+    ) -> Value {
+        let then = self.value(InstKind::Compare(then, lhs, rhs), Type::I1, location);
+        let otherwise = self.value(InstKind::Compare(otherwise, lhs, rhs), Type::I1, location);
+        self.value(
+            InstKind::Select(condition, then, otherwise),
+            Type::I1,
+            location,
+        )
+    }
+
+    fn elements_loop(
+        &mut self,
+        label: Option<&String>,
+        binding: LocalId,
+        iterable: &Expr,
+        body: &[Stmt],
+        location: Location,
+    ) {
+        let element = match &iterable.ty {
+            Ty::Array(element) => Some(&**element),
+            _ => None,
+        };
+        let size = element.map_or(1, |element| self.lowering.layouts.of(element).size);
+        let address = self.operand(iterable);
+        let (data, length) = self.fat(address, location);
+        let index = self.temporary(Type::I64);
+        let zero = self.constant(Type::I64, 0, location);
+        self.write(index, self.current, zero);
+
+        let head = self.enter_loop(location);
+        let value = self.read(index, head);
+        let more = self.value(
+            InstKind::Compare(CompareOp::Ult, value, length),
+            Type::I1,
+            location,
+        );
+        let exit = self.loop_test(more, location);
+        let size = self.constant(Type::I64, size, location);
+        let offset = self.value(
+            InstKind::Binary(BinaryOp::Mul, value, size),
+            Type::I64,
+            location,
+        );
+        let address = self.value(InstKind::PtrAdd(data, offset), Type::Ptr, location);
+        let program = self.lowering.program;
+        let ty = &program.local(binding).ty;
+        let element = match element {
+            Some(element) => self.load(address, element, iterable.location),
+            None => Some(self.byte(address, iterable.location)),
+        };
+        self.assign(binding, element, ty, location);
+        let next = self.block();
+        self.loop_body(label, (next, exit, Join::None), body, location);
+
+        self.seal(next);
+        self.switch_to(next);
+        let value = self.read(index, next);
+        let one = self.constant(Type::I64, 1, location);
+        let advanced = self.value(
+            InstKind::Binary(BinaryOp::Add, value, one),
+            Type::I64,
+            location,
+        );
+        self.write(index, next, advanced);
+        self.jump(head, Vec::new(), location);
+        self.seal(head);
+        self.seal(exit);
+        self.switch_to(exit);
+    }
+
+    fn iterator_loop(
+        &mut self,
+        label: Option<&String>,
+        binding: LocalId,
+        iterator: &Expr,
+        next: &Expr,
+        body: &[Stmt],
+        location: Location,
+    ) {
+        let ExprKind::Call(instance, _) = &next.kind else {
+            unreachable!("next is a call")
+        };
+        let Ty::Enum(id, _) = &next.ty else {
+            unreachable!("next returns an option")
+        };
+        let layouts = self.lowering.layouts;
+        let layout = &layouts.enums[&next.ty];
+        let some = self.lowering.program.enums[id]
+            .variants
+            .iter()
+            .position(|variant| variant.name == "Some")
+            .expect("Option has a variant Some");
+        let payload = layout.variants[some][0];
+
+        // a struct value is iterated as a copy
+        let iterator = if matches!(iterator.ty, Ty::Pointer(_)) {
+            self.operand(iterator)
+        } else {
+            self.owned(iterator).expect("structs have an address")
+        };
+        let option = self.slot(&next.ty, location);
+        let function = self.lowering.functions[instance];
+
+        let head = self.enter_loop(location);
+        self.effect(
+            InstKind::Call(function, vec![option, iterator]),
+            next.location,
+        );
+        let tag_type = Type::int(layout.tag.size);
+        let tag = self.value(InstKind::Load(option), tag_type, location);
+        let expected = self.constant(tag_type, some as u64, location);
+        let is_some = self.value(
+            InstKind::Compare(CompareOp::Eq, tag, expected),
+            Type::I1,
+            location,
+        );
+        let exit = self.loop_test(is_some, location);
+        let program = self.lowering.program;
+        let ty = &program.local(binding).ty;
+        let address = self.offset(option, payload, location);
+        let element = self.load(address, ty, location);
+        self.assign(binding, element, ty, location);
+        self.loop_body(label, (head, exit, Join::None), body, location);
+
+        self.seal(head);
+        self.seal(exit);
+        self.switch_to(exit);
+    }
+
+    /// The value of an expression that has one.
+    fn operand(&mut self, expression: &Expr) -> Value {
+        self.expression(expression)
+            .expect("operands are not of type unit")
+    }
+
+    /// The value of an expression, an aggregate in a temporary that nothing
+    /// else refers to.
+    fn owned(&mut self, expression: &Expr) -> Option<Value> {
+        let value = self.expression(expression);
+        match self.lowering.repr(&expression.ty) {
+            Repr::Aggregate(_) if expression.is_place() => {
+                let copy = self.slot(&expression.ty, expression.location);
+                self.store(value, copy, &expression.ty, expression.location);
+                Some(copy)
+            }
+            _ => value,
+        }
+    }
+
+    /// The value of an expression, or its address if it is an aggregate.
+    fn expression(&mut self, expression: &Expr) -> Option<Value> {
+        let location = expression.location;
+        let ty = &expression.ty;
+        match &expression.kind {
+            ExprKind::Int(int) => Some(self.constant(Type::I64, *int, location)),
+            ExprKind::Bool(bool) => Some(self.constant(Type::I1, u64::from(*bool), location)),
+            ExprKind::Str(str) => Some(self.string(str, location)),
+            ExprKind::SizeOf(ty) => {
+                let size = self.lowering.layouts.of(ty).size;
+                Some(self.constant(Type::I64, size, location))
+            }
+            ExprKind::Local(local) => match self.locals[local] {
+                Storage::Unit => None,
+                Storage::Variable(_) => Some(self.read(Var::Local(*local), self.current)),
+                Storage::Memory(address) => self.load(address, ty, location),
+            },
+            ExprKind::Unary(UnaryOperator::Dereference, _)
+            | ExprKind::Index(..)
+            | ExprKind::Field(..)
+                if expression.is_place() =>
+            {
+                let address = self.place(expression);
+                self.load(address, ty, location)
+            }
+            ExprKind::Unary(UnaryOperator::Address, operand) => Some(self.place(operand)),
+            ExprKind::Unary(operator, operand) => {
+                let value = self.operand(operand);
+                let op = if *operator == UnaryOperator::Minus {
+                    UnaryOp::Neg
+                } else {
+                    UnaryOp::Not
+                };
+                let ty = self.function.ty(value);
+                Some(self.value(InstKind::Unary(op, value), ty, location))
+            }
+            ExprKind::Binary(operator, lhs, rhs) => {
+                Some(self.binary(*operator, lhs, rhs, location))
+            }
+            ExprKind::Call(instance, arguments) => self.call(instance, arguments, ty, location),
+            // only strings are indexed without being a place
+            ExprKind::Index(base, index) => {
+                let base = self.operand(base);
+                let (data, length) = self.fat(base, location);
+                let index = self.operand(index);
+                self.bounds(index, length, location);
+                let address = self.value(InstKind::PtrAdd(data, index), Type::Ptr, location);
+                Some(self.byte(address, location))
+            }
+            ExprKind::Field(base, field) => {
+                let offset = self.lowering.layouts.structs[&base.ty].offsets[*field];
+                let base = self.operand(base);
+                let address = self.offset(base, offset, location);
+                self.load(address, ty, location)
+            }
+            ExprKind::Len(base) => {
+                let base = self.operand(base);
+                let address = self.offset(base, LENGTH_OFFSET, location);
+                Some(self.value(InstKind::Load(address), Type::I64, location))
+            }
+            ExprKind::StructInit(_, fields) => {
+                let layouts = self.lowering.layouts;
+                let offsets = &layouts.structs[ty].offsets;
+                Some(self.compose(ty, fields, offsets, location))
+            }
+            ExprKind::Variant(_, variant, fields) => {
+                Some(self.variant(ty, *variant, fields, location))
+            }
+            ExprKind::ArrayInit(elements) => Some(self.array(ty, elements, location)),
+            ExprKind::Loop { label, body } => {
+                self.loop_expression(label.as_ref(), body, ty, location)
+            }
+            ExprKind::Match { scrutinee, arms } => {
+                self.match_expression(scrutinee, arms, ty, location)
+            }
+            ExprKind::InterfaceCall(..) | ExprKind::Error => {
+                unreachable!("only checked, monomorphic programs are lowered")
+            }
+        }
+    }
+
+    /// A string constant in a temporary.
+    fn string(&mut self, string: &str, location: Location) -> Value {
+        let index = self.lowering.data(string);
+        let data = self.value(InstKind::Data(index), Type::Ptr, location);
+        let length = self.constant(Type::I64, string.len() as u64, location);
+        let address = self.slot(&Ty::Str, location);
+        self.effect(
+            InstKind::Store {
+                value: data,
+                address,
+            },
+            location,
+        );
+        let at = self.offset(address, LENGTH_OFFSET, location);
+        self.effect(
+            InstKind::Store {
+                value: length,
+                address: at,
+            },
+            location,
+        );
+        address
+    }
+
+    /// The byte at `address` as an `unsigned int`.
+    fn byte(&mut self, address: Value, location: Location) -> Value {
+        let byte = self.value(InstKind::Load(address), Type::I8, location);
+        self.value(InstKind::Extend(byte), Type::I64, location)
+    }
+
+    fn bounds(&mut self, index: Value, length: Value, location: Location) {
+        let within = self.value(
+            InstKind::Compare(CompareOp::Ult, index, length),
+            Type::I1,
+            location,
+        );
+        self.check(within, "index out of bounds", location);
+    }
+
+    fn compose(&mut self, ty: &Ty, fields: &[Expr], offsets: &[u64], location: Location) -> Value {
+        let address = self.slot(ty, location);
+        self.fields(address, fields, offsets, location);
+        address
+    }
+
+    fn fields(&mut self, address: Value, fields: &[Expr], offsets: &[u64], location: Location) {
+        for (field, offset) in fields.iter().zip(offsets) {
+            let value = self.expression(field);
+            let at = self.offset(address, *offset, location);
+            self.store(value, at, &field.ty, location);
+        }
+    }
+
+    fn variant(&mut self, ty: &Ty, variant: usize, fields: &[Expr], location: Location) -> Value {
+        let layouts = self.lowering.layouts;
+        let layout = &layouts.enums[ty];
+        let address = self.slot(ty, location);
+        let tag = self.constant(Type::int(layout.tag.size), variant as u64, location);
+        self.effect(
+            InstKind::Store {
+                value: tag,
+                address,
+            },
+            location,
+        );
+        self.fields(address, fields, &layout.variants[variant], location);
+        address
+    }
+
+    /// An array literal, its elements in a new heap block.
+    fn array(&mut self, ty: &Ty, elements: &[Expr], location: Location) -> Value {
+        let Ty::Array(element) = ty else {
+            unreachable!("array literals are arrays")
+        };
+        let values = elements
+            .iter()
+            .map(|element| self.expression(element))
+            .collect::<Vec<_>>();
+        let size = self.lowering.layouts.of(element).size;
+        let count = elements.len() as u64;
+        let bytes = self.constant(Type::I64, count * size, location);
+        let alloc = self.alloc();
+        let data = self.value(InstKind::Call(alloc, vec![bytes]), Type::Ptr, location);
+        for (index, value) in (0..).zip(values) {
+            let at = self.offset(data, index * size, location);
+            self.store(value, at, element, location);
+        }
+        let address = self.slot(ty, location);
+        self.effect(
+            InstKind::Store {
+                value: data,
+                address,
+            },
+            location,
+        );
+        let length = self.constant(Type::I64, count, location);
+        let at = self.offset(address, LENGTH_OFFSET, location);
+        self.effect(
+            InstKind::Store {
+                value: length,
+                address: at,
+            },
+            location,
+        );
+        address
+    }
+
+    fn alloc(&mut self) -> FuncId {
+        self.lowering
+            .runtime("std::alloc", vec![Type::I64], Some(Type::Ptr))
+    }
+
+    fn loop_expression(
+        &mut self,
+        label: Option<&String>,
+        body: &[Stmt],
+        ty: &Ty,
+        location: Location,
+    ) -> Option<Value> {
+        let head = self.enter_loop(location);
+        let exit = self.block();
+        let (result, value) = self.result(exit, ty, location);
+        self.loop_body(label, (head, exit, result), body, location);
+        self.seal(head);
+        self.seal(exit);
+        self.switch_to(exit);
+        value
+    }
+
+    /// Where the paths of a loop or a match leave a value of type `ty` for
+    /// `join`, and the value there.
+    fn result(&mut self, join: BlockId, ty: &Ty, location: Location) -> (Join, Option<Value>) {
+        match self.lowering.repr(ty) {
+            Repr::Unit => (Join::None, None),
+            Repr::Scalar(scalar) => (Join::Parameter, Some(self.parameter(join, scalar))),
+            Repr::Aggregate(_) => {
+                let address = self.slot(ty, location);
+                (Join::Memory(address), Some(address))
+            }
+        }
+    }
+
+    /// The address of a place expression.
+    fn place(&mut self, expression: &Expr) -> Value {
+        let location = expression.location;
+        match &expression.kind {
+            ExprKind::Local(local) => match self.locals[local] {
+                Storage::Memory(address) => address,
+                storage => unreachable!("{storage:?} has no address"),
+            },
+            ExprKind::Unary(UnaryOperator::Dereference, pointer) => self.operand(pointer),
+            ExprKind::Field(base, field) => {
+                let (address, structure) = match &base.ty {
+                    Ty::Pointer(pointee) => (self.operand(base), &**pointee),
+                    structure => (self.place(base), structure),
+                };
+                let offset = self.lowering.layouts.structs[structure].offsets[*field];
+                self.offset(address, offset, location)
+            }
+            ExprKind::Index(base, index) => {
+                let (data, element) = match &base.ty {
+                    Ty::Pointer(element) => {
+                        let data = self.operand(base);
+                        (data, &**element)
+                    }
+                    Ty::Array(element) => {
+                        let array = self.operand(base);
+                        let (data, length) = self.fat(array, location);
+                        let index = self.operand(index);
+                        self.bounds(index, length, location);
+                        return self.element(data, index, element, location);
+                    }
+                    _ => unreachable!("strings are not places"),
+                };
+                let index = self.operand(index);
+                self.element(data, index, element, location)
+            }
+            _ => unreachable!("checked to be a place"),
+        }
+    }
+
+    fn element(&mut self, data: Value, index: Value, element: &Ty, location: Location) -> Value {
+        let size = self.lowering.layouts.of(element).size;
+        let size = self.constant(Type::I64, size, location);
+        let offset = self.value(
+            InstKind::Binary(BinaryOp::Mul, index, size),
+            Type::I64,
+            location,
+        );
+        self.value(InstKind::PtrAdd(data, offset), Type::Ptr, location)
+    }
+
+    fn binary(
+        &mut self,
+        operator: BinaryOperator,
+        lhs: &Expr,
+        rhs: &Expr,
+        location: Location,
+    ) -> Value {
+        use BinaryOperator as B;
+
+        if let B::LogicalAnd | B::LogicalOr = operator {
+            return self.short_circuit(operator == B::LogicalOr, lhs, rhs, location);
+        }
+        let left = self.operand(lhs);
+        let right = self.operand(rhs);
+        if lhs.ty == Ty::Str {
+            let equal = self.str_eq(left, right, location);
+            return if operator == B::NotEqual {
+                self.value(InstKind::Unary(UnaryOp::Not, equal), Type::I1, location)
+            } else {
+                equal
+            };
+        }
+
+        let signed = lhs.ty == Ty::Int;
+        let compare = match operator {
+            B::Equal => Some(CompareOp::Eq),
+            B::NotEqual => Some(CompareOp::Ne),
+            B::Less => Some(if signed {
+                CompareOp::Slt
+            } else {
+                CompareOp::Ult
+            }),
+            B::LessEqual => Some(if signed {
+                CompareOp::Sle
+            } else {
+                CompareOp::Ule
+            }),
+            B::Greater => Some(if signed {
+                CompareOp::Sgt
+            } else {
+                CompareOp::Ugt
+            }),
+            B::GreaterEqual => Some(if signed {
+                CompareOp::Sge
+            } else {
+                CompareOp::Uge
+            }),
+            _ => None,
+        };
+        if let Some(op) = compare {
+            return self.value(InstKind::Compare(op, left, right), Type::I1, location);
+        }
+        let op = match operator {
+            B::Add => BinaryOp::Add,
+            B::Sub => BinaryOp::Sub,
+            B::Mul => BinaryOp::Mul,
+            B::Div if signed => BinaryOp::SDiv,
+            B::Div => BinaryOp::UDiv,
+            B::Rem if signed => BinaryOp::SRem,
+            B::Rem => BinaryOp::URem,
+            B::ShiftLeft => BinaryOp::Shl,
+            B::ShiftRight if signed => BinaryOp::AShr,
+            B::ShiftRight => BinaryOp::LShr,
+            B::And => BinaryOp::And,
+            B::Or => BinaryOp::Or,
+            B::Xor => BinaryOp::Xor,
+            _ => unreachable!("handled above"),
+        };
+        if let BinaryOp::SDiv | BinaryOp::UDiv | BinaryOp::SRem | BinaryOp::URem = op {
+            let zero = self.constant(Type::I64, 0, location);
+            let valid = self.value(
+                InstKind::Compare(CompareOp::Ne, right, zero),
+                Type::I1,
+                location,
+            );
+            self.check(valid, "division by zero", location);
+        }
+        let ty = self.function.ty(left);
+        self.value(InstKind::Binary(op, left, right), ty, location)
+    }
+
+    /// `&&` and `||`, the right-hand side only runs if it decides the
+    /// result.
+    fn short_circuit(&mut self, or: bool, lhs: &Expr, rhs: &Expr, location: Location) -> Value {
+        let left = self.operand(lhs);
+        let right_block = self.block();
+        let join = self.block();
+        let result = self.parameter(join, Type::I1);
+        let decided = BlockCall::new(join, vec![left]);
+        let undecided = BlockCall::new(right_block, Vec::new());
+        if or {
+            self.branch(left, decided, undecided, location);
+        } else {
+            self.branch(left, undecided, decided, location);
+        }
+        self.seal(right_block);
+        self.switch_to(right_block);
+        let right = self.operand(rhs);
+        if self.is_open() {
+            self.jump(join, vec![right], location);
+        }
+        self.seal(join);
+        self.switch_to(join);
+        result
+    }
+
+    /// Compares two strings given by their addresses.
+    fn str_eq(&mut self, lhs: Value, rhs: Value, location: Location) -> Value {
+        let (left, left_length) = self.fat(lhs, location);
+        let (right, right_length) = self.fat(rhs, location);
+        let function = self.lowering.str_eq();
+        let arguments = vec![left, left_length, right, right_length];
+        self.value(InstKind::Call(function, arguments), Type::I1, location)
+    }
+
+    fn call(
+        &mut self,
+        instance: &Instance,
+        arguments: &[Expr],
+        ty: &Ty,
+        location: Location,
+    ) -> Option<Value> {
+        if let Some(Intrinsic::Alloc) = self.lowering.intrinsics.get(&instance.id) {
+            let count = self.operand(&arguments[0]);
+            let size = self.lowering.layouts.of(&instance.generics[0]).size;
+            let size = self.constant(Type::I64, size, location);
+            let bytes = self.value(
+                InstKind::Binary(BinaryOp::Mul, count, size),
+                Type::I64,
+                location,
+            );
+            let alloc = self.alloc();
+            return Some(self.value(InstKind::Call(alloc, vec![bytes]), Type::Ptr, location));
+        }
+
+        let repr = self.lowering.repr(ty);
+        let mut values = Vec::new();
+        let out = if let Repr::Aggregate(_) = repr {
+            let out = self.slot(ty, location);
+            values.push(out);
+            Some(out)
+        } else {
+            None
+        };
+        for argument in arguments {
+            values.extend(self.owned(argument));
+        }
+        let kind = InstKind::Call(self.lowering.functions[instance], values);
+        match repr {
+            Repr::Scalar(scalar) => Some(self.value(kind, scalar, location)),
+            Repr::Unit | Repr::Aggregate(_) => {
+                self.effect(kind, location);
+                out
+            }
+        }
+    }
+
+    fn match_expression(
+        &mut self,
+        scrutinee: &Expr,
+        arms: &[Arm],
+        ty: &Ty,
+        location: Location,
+    ) -> Option<Value> {
+        let value = self.owned(scrutinee);
+        let join = self.block();
+        let (result, joined) = self.result(join, ty, location);
+        for arm in arms {
+            let next = self.block();
+            self.pattern(&arm.pattern, value, next);
+            if let Some(guard) = &arm.guard {
+                let condition = self.operand(guard);
+                self.test(condition, next, guard.location);
+            }
+            let value = match &arm.body {
+                ArmBody::Expr(value) => {
+                    let result = self.expression(value);
+                    (result, &value.ty)
+                }
+                ArmBody::Block(body) => {
+                    self.body(body);
+                    (None, ty)
+                }
+            };
+            if self.is_open() {
+                let args = match result {
+                    Join::None => Vec::new(),
+                    Join::Parameter => value.0.into_iter().collect(),
+                    Join::Memory(address) => {
+                        self.store(value.0, address, value.1, arm.location);
+                        Vec::new()
+                    }
+                };
+                self.jump(join, args, arm.location);
+            }
+            self.seal(next);
+            self.switch_to(next);
+        }
+        // the arms are exhaustive
+        self.terminate(Terminator::Unreachable, location);
+        self.seal(join);
+        self.switch_to(join);
+        joined
+    }
+
+    /// Tests `value` against `pattern`, binds its locals and jumps to
+    /// `fail` if it does not match.
+    fn pattern(&mut self, pattern: &Pattern, value: Option<Value>, fail: BlockId) {
+        let location = pattern.location;
+        let (constant, ty) = match &pattern.kind {
+            PatternKind::Wildcard => return,
+            PatternKind::Binding(local) => {
+                self.assign(*local, value, &pattern.ty, location);
+                return;
+            }
+            PatternKind::Int(int) => (*int, Type::I64),
+            PatternKind::Bool(bool) => (u64::from(*bool), Type::I1),
+            PatternKind::Str(str) => {
+                let expected = self.string(str, location);
+                let value = value.expect("strings have an address");
+                let equal = self.str_eq(value, expected, location);
+                self.test(equal, fail, location);
+                return;
+            }
+            PatternKind::Variant(_, variant, fields) => {
+                let layouts = self.lowering.layouts;
+                let layout = &layouts.enums[&pattern.ty];
+                let address = value.expect("enums have an address");
+                let tag_type = Type::int(layout.tag.size);
+                let tag = self.value(InstKind::Load(address), tag_type, location);
+                let expected = self.constant(tag_type, *variant as u64, location);
+                let equal = self.value(
+                    InstKind::Compare(CompareOp::Eq, tag, expected),
+                    Type::I1,
+                    location,
+                );
+                self.test(equal, fail, location);
+                self.subpatterns(fields, &layout.variants[*variant], address, fail);
+                return;
+            }
+            PatternKind::Struct(_, fields) => {
+                let layouts = self.lowering.layouts;
+                let offsets = &layouts.structs[&pattern.ty].offsets;
+                let address = value.expect("structs have an address");
+                self.subpatterns(fields, offsets, address, fail);
+                return;
+            }
+        };
+        let value = value.expect("scalars have a value");
+        let expected = self.constant(ty, constant, location);
+        let equal = self.value(
+            InstKind::Compare(CompareOp::Eq, value, expected),
+            Type::I1,
+            location,
+        );
+        self.test(equal, fail, location);
+    }
+
+    fn subpatterns(&mut self, fields: &[Pattern], offsets: &[u64], address: Value, fail: BlockId) {
+        for (field, offset) in fields.iter().zip(offsets) {
+            if let PatternKind::Wildcard = field.kind {
+                continue;
+            }
+            let at = self.offset(address, *offset, field.location);
+            let value = self.load(at, &field.ty, field.location);
+            self.pattern(field, value, fail);
+        }
+    }
+}
@@ -0,0 +1,230 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! Optimisations on the IR and the pass manager running them.
+//!
+//! Every pass keeps the module valid and reports whether it changed
+//! anything. The passes of a level run in order, repeatedly until a round
+//! changes nothing or the rounds are used up.
+
+pub mod cse;
+pub mod dce;
+pub mod fold;
+pub mod inline;
+pub mod licm;
+pub mod simplify;
+
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use crate::Error;
+
+use super::{Module, verify};
+
+/// Rounds of the passes before the pass manager gives up on reaching a
+/// fixpoint.
+const MAX_ROUNDS: usize = 8;
+
+/// An optimisation over a whole module.
+#[derive(Debug, Clone, Copy)]
+pub struct Pass {
+    pub name: &'static str,
+    /// Returns `true` if the module changed.
+    pub run: fn(&mut Module) -> bool,
+}
+
+impl Pass {
+    pub const FOLD: Pass = Pass {
+        name: "fold",
+        run: fold::run,
+    };
+    pub const DCE: Pass = Pass {
+        name: "dce",
+        run: dce::run,
+    };
+    pub const CSE: Pass = Pass {
+        name: "cse",
+        run: cse::run,
+    };
+    pub const INLINE: Pass = Pass {
+        name: "inline",
+        run: inline::run,
+    };
+    pub const LICM: Pass = Pass {
+        name: "licm",
+        run: licm::run,
+    };
+    pub const SIMPLIFY: Pass = Pass {
+        name: "simplify",
+        run: simplify::run,
+    };
+
+    pub const ALL: [Pass; 6] = [
+        Pass::FOLD,
+        Pass::DCE,
+        Pass::CSE,
+        Pass::INLINE,
+        Pass::LICM,
+        Pass::SIMPLIFY,
+    ];
+}
+
+/// How hard to optimise, as `-O0`, `-O1` and `-O2` on the command line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// No optimisation, the IR as lowered.
+    #[default]
+    O0,
+    /// The scalar optimisations within every function.
+    O1,
+    /// Inlining and loop-invariant code motion on top.
+    O2,
+}
+
+impl Level {
+    /// The passes of the level in the order they run in each round.
+    #[must_use]
+    pub fn passes(self) -> Vec<Pass> {
+        match self {
+            Level::O0 => Vec::new(),
+            Level::O1 => vec![Pass::FOLD, Pass::CSE, Pass::DCE, Pass::SIMPLIFY],
+            Level::O2 => vec![
+                Pass::INLINE,
+                Pass::FOLD,
+                Pass::CSE,
+                Pass::LICM,
+                Pass::DCE,
+                Pass::SIMPLIFY,
+            ],
+        }
+    }
+}
+
+impl FromStr for Level {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "0" => Ok(Level::O0),
+            "1" => Ok(Level::O1),
+            "2" => Ok(Level::O2),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The time spent in a pass over all rounds.
+#[derive(Debug, Clone, Copy)]
+pub struct Timing {
+    pub pass: &'static str,
+    pub runs: usize,
+    /// How many of the runs changed the module.
+    pub changes: usize,
+    pub time: Duration,
+}
+
+impl Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>10.3?}  {:<10} {} runs, {} changed",
+            self.time, self.pass, self.runs, self.changes
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PassManager {
+    passes: Vec<Pass>,
+    max_rounds: usize,
+    verify_each: bool,
+    timings: Vec<Timing>,
+}
+
+impl PassManager {
+    /// Creates a pass manager running the passes of `level`.
+    #[must_use]
+    pub fn new(level: Level) -> Self {
+        Self::with_passes(level.passes())
+    }
+
+    /// Creates a pass manager running the given passes in order.
+    #[must_use]
+    pub fn with_passes(passes: Vec<Pass>) -> Self {
+        let timings = passes
+            .iter()
+            .map(|pass| Timing {
+                pass: pass.name,
+                runs: 0,
+                changes: 0,
+                time: Duration::ZERO,
+            })
+            .collect();
+        Self {
+            passes,
+            max_rounds: MAX_ROUNDS,
+            verify_each: cfg!(debug_assertions),
+            timings,
+        }
+    }
+
+    /// Limits how often the passes run, 1 runs each of them once.
+    #[must_use]
+    pub fn with_max_rounds(mut self, max_rounds: usize) -> Self {
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    /// Verifies the module after every pass, the default in debug builds.
+    #[must_use]
+    pub fn verify_each(mut self, verify_each: bool) -> Self {
+        self.verify_each = verify_each;
+        self
+    }
+
+    /// Optimises the module and numbers the values of every function anew.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidIr`] if verifying after a pass fails, with
+    /// the name of the pass in the message.
+    pub fn run(&mut self, module: &mut Module) -> Result<(), Error> {
+        for _ in 0..self.max_rounds {
+            let mut changed = false;
+            for (pass, timing) in self.passes.iter().zip(&mut self.timings) {
+                let start = Instant::now();
+                let modified = (pass.run)(module);
+                timing.time += start.elapsed();
+                timing.runs += 1;
+                timing.changes += usize::from(modified);
+                changed |= modified;
+                if modified && self.verify_each {
+                    verify::verify(module).map_err(|error| match error {
+                        Error::InvalidIr { function, message } => Error::InvalidIr {
+                            function,
+                            message: format!("after {}: {message}", pass.name),
+                        },
+                        error => error,
+                    })?;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        for function in &mut module.functions {
+            function.renumber();
+        }
+        Ok(())
+    }
+
+    /// The time spent in each pass, in the order they run.
+    #[must_use]
+    pub fn timings(&self) -> &[Timing] {
+        &self.timings
+    }
+}
@@ -0,0 +1,110 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! Common subexpression elimination. An instruction without effects that
+//! does not read memory is replaced by an equal one in a dominating block,
+//! with the operands of commutative operations in a canonical order.
+
+use std::collections::HashMap;
+
+use crate::ir::{BinaryOp, BlockId, CompareOp, Function, InstKind, Module, Type, Value};
+
+pub fn run(module: &mut Module) -> bool {
+    let mut changed = false;
+    for function in module
+        .functions
+        .iter_mut()
+        .filter(|function| !function.is_extern())
+    {
+        changed |= eliminate(function);
+    }
+    changed
+}
+
+/// An instruction computing the same value wherever it is.
+type Key = (InstKind, Type);
+
+fn key(kind: &InstKind, ty: Type) -> Option<Key> {
+    let mut kind = kind.clone();
+    match &mut kind {
+        InstKind::Binary(
+            BinaryOp::Add | BinaryOp::Mul | BinaryOp::And | BinaryOp::Or | BinaryOp::Xor,
+            lhs,
+            rhs,
+        )
+        | InstKind::Compare(CompareOp::Eq | CompareOp::Ne, lhs, rhs) => {
+            if rhs < lhs {
+                std::mem::swap(lhs, rhs);
+            }
+        }
+        InstKind::Const(_)
+        | InstKind::Unary(..)
+        | InstKind::Binary(..)
+        | InstKind::Compare(..)
+        | InstKind::Select(..)
+        | InstKind::Extend(_)
+        | InstKind::PtrAdd(..)
+        | InstKind::Data(_) => {}
+        InstKind::Slot { .. }
+        | InstKind::Load(_)
+        | InstKind::Store { .. }
+        | InstKind::Copy { .. }
+        | InstKind::Call(..) => return None,
+    }
+    Some((kind, ty))
+}
+
+fn eliminate(function: &mut Function) -> bool {
+    let dominators = function.dominators();
+    let mut children = vec![Vec::new(); function.blocks.len()];
+    for block in function.block_ids() {
+        if let Some(dominator) = dominators[block.index()] {
+            children[dominator.index()].push(block);
+        }
+    }
+
+    let mut available: HashMap<Key, Value> = HashMap::new();
+    let mut replacements = HashMap::new();
+    // blocks to visit, `None` leaves the block on top of `scopes`
+    let mut work = vec![Some(BlockId(0))];
+    let mut scopes: Vec<Vec<Key>> = Vec::new();
+    while let Some(next) = work.pop() {
+        let Some(id) = next else {
+            for key in scopes.pop().expect("entered before") {
+                available.remove(&key);
+            }
+            continue;
+        };
+        let mut added = Vec::new();
+        let types = &function.types;
+        let block = &mut function.blocks[id.index()];
+        block.insts.retain_mut(|inst| {
+            for operand in inst.kind.operands_mut() {
+                while let Some(replacement) = replacements.get(operand) {
+                    *operand = *replacement;
+                }
+            }
+            let Some(result) = inst.result else {
+                return true;
+            };
+            let Some(key) = key(&inst.kind, types[result.index()]) else {
+                return true;
+            };
+            if let Some(existing) = available.get(&key) {
+                replacements.insert(result, *existing);
+                false
+            } else {
+                available.insert(key.clone(), result);
+                added.push(key);
+                true
+            }
+        });
+        scopes.push(added);
+        work.push(None);
+        work.extend(children[id.index()].iter().rev().map(|child| Some(*child)));
+    }
+    let changed = !replacements.is_empty();
+    function.substitute(&replacements);
+    changed
+}
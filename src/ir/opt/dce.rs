@@ -0,0 +1,172 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! Dead code elimination. Removes stores to stack slots that are never
+//! read, instructions without effects whose results are unused and block
+//! parameters whose values only flow into other dead parameters.
+
+use std::collections::HashMap;
+
+use crate::ir::{BlockId, Function, InstKind, Module, Value};
+
+pub fn run(module: &mut Module) -> bool {
+    let mut changed = false;
+    for function in module
+        .functions
+        .iter_mut()
+        .filter(|function| !function.is_extern())
+    {
+        changed |= write_only(function);
+        changed |= unused(function);
+    }
+    changed
+}
+
+/// Removes the stores and copies into slots that are never read. Pointers
+/// into a slot may only be offset and written through, any other use
+/// counts as a read.
+fn write_only(function: &mut Function) -> bool {
+    // the slot every pointer into a slot points into, definitions come
+    // before their uses in reverse postorder
+    let mut slots = HashMap::new();
+    for id in function.reverse_postorder() {
+        for inst in &function.block(id).insts {
+            let Some(result) = inst.result else {
+                continue;
+            };
+            match inst.kind {
+                InstKind::Slot { .. } => {
+                    slots.insert(result, result);
+                }
+                InstKind::PtrAdd(address, _) => {
+                    if let Some(slot) = slots.get(&address).copied() {
+                        slots.insert(result, slot);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    let mut read = vec![false; function.types.len()];
+    let mut mark = |value: &Value| {
+        if let Some(slot) = slots.get(value) {
+            read[slot.index()] = true;
+        }
+    };
+    for block in &function.blocks {
+        for inst in &block.insts {
+            match &inst.kind {
+                InstKind::Store { value, .. } | InstKind::PtrAdd(_, value) => mark(value),
+                InstKind::Copy { from, .. } => mark(from),
+                kind => kind.operands().iter().for_each(&mut mark),
+            }
+        }
+        block.terminator.operands().iter().for_each(&mut mark);
+        for target in block.terminator.successors() {
+            target.args.iter().for_each(&mut mark);
+        }
+    }
+    let unread = |address: &Value| slots.get(address).is_some_and(|slot| !read[slot.index()]);
+    let mut changed = false;
+    for block in &mut function.blocks {
+        let count = block.insts.len();
+        block.insts.retain(|inst| match &inst.kind {
+            InstKind::Store { address, .. } | InstKind::Copy { to: address, .. } => {
+                !unread(address)
+            }
+            _ => true,
+        });
+        changed |= block.insts.len() != count;
+    }
+    changed
+}
+
+/// Removes the instructions and block parameters that do not contribute to
+/// an effect, a branch or a returned value.
+fn unused(function: &mut Function) -> bool {
+    let definitions = function.definitions();
+    // the arguments passed to every parameter of every block
+    let mut incoming: HashMap<(BlockId, usize), Vec<Value>> = HashMap::new();
+    let mut live = vec![false; function.types.len()];
+    let mut work = Vec::new();
+    for block in &function.blocks {
+        for inst in &block.insts {
+            if inst.kind.has_effects() {
+                work.extend(inst.kind.operands());
+            }
+        }
+        work.extend(block.terminator.operands());
+        for target in block.terminator.successors() {
+            for (index, arg) in target.args.iter().enumerate() {
+                incoming
+                    .entry((target.block, index))
+                    .or_default()
+                    .push(*arg);
+            }
+        }
+    }
+    while let Some(value) = work.pop() {
+        if std::mem::replace(&mut live[value.index()], true) {
+            continue;
+        }
+        match definitions[value.index()] {
+            Some((block, Some(index))) => {
+                work.extend(function.block(block).insts[index].kind.operands());
+            }
+            Some((block, None)) => {
+                let index = function
+                    .block(block)
+                    .params
+                    .iter()
+                    .position(|param| *param == value)
+                    .expect("the parameter is defined in the block");
+                work.extend(incoming.get(&(block, index)).into_iter().flatten());
+            }
+            None => {}
+        }
+    }
+
+    let mut changed = false;
+    for block in &mut function.blocks {
+        let count = block.insts.len();
+        block.insts.retain(|inst| {
+            inst.kind.has_effects() || inst.result.is_some_and(|result| live[result.index()])
+        });
+        changed |= block.insts.len() != count;
+    }
+    // the entry takes the parameters of the function
+    let dead = function
+        .block_ids()
+        .skip(1)
+        .map(|id| {
+            let params = &function.block(id).params;
+            params.iter().map(|param| !live[param.index()]).collect()
+        })
+        .collect::<Vec<Vec<bool>>>();
+    if dead.iter().flatten().any(|dead| *dead) {
+        changed = true;
+        for block in &mut function.blocks {
+            for target in block.terminator.successors_mut() {
+                if let Some(dead) = target
+                    .block
+                    .index()
+                    .checked_sub(1)
+                    .map(|index| &dead[index])
+                {
+                    let mut dead = dead.iter();
+                    target
+                        .args
+                        .retain(|_| !dead.next().expect("one per parameter"));
+                }
+            }
+        }
+        for (block, dead) in function.blocks.iter_mut().skip(1).zip(&dead) {
+            let mut dead = dead.iter();
+            block
+                .params
+                .retain(|_| !dead.next().expect("one per parameter"));
+        }
+    }
+    changed
+}
@@ -0,0 +1,329 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! Constant folding and propagation. Instructions on constants become
+//! constants, including comparisons of string literals, simple identities
+//! collapse to one of their operands, branches on constants become jumps,
+//! and a load from a stack slot sees the value stored there last on the
+//! way from a dominating block without calls or unknown stores between.
+
+use std::collections::HashMap;
+
+use crate::ir::{
+    BinaryOp, BlockCall, CompareOp, FuncId, Function, InstKind, Module, Terminator, Type, UnaryOp,
+    Value, interpret,
+};
+
+/// Name of the helper comparing strings, see [`crate::ir::lower`].
+const STR_EQ: &str = "cringe.str_eq";
+
+pub fn run(module: &mut Module) -> bool {
+    let str_eq = module.function(STR_EQ);
+    let Module {
+        functions, data, ..
+    } = module;
+    let mut changed = false;
+    for function in functions
+        .iter_mut()
+        .filter(|function| !function.is_extern())
+    {
+        changed |= Folder::new(function, data, str_eq).function(function);
+    }
+    changed
+}
+
+/// What an instruction turned out to compute.
+enum Folded {
+    Const(u64),
+    Value(Value),
+}
+
+/// A location in a stack slot: the slot and an offset in bytes.
+type Place = (Value, u64);
+
+struct Folder<'d> {
+    data: &'d [String],
+    str_eq: Option<FuncId>,
+    types: Vec<Type>,
+    constants: Vec<Option<u64>>,
+    /// The index of the string constant every `data` value addresses.
+    strings: Vec<Option<u32>>,
+    /// The place every pointer into a slot at a known offset points to.
+    places: Vec<Option<Place>>,
+    replacements: HashMap<Value, Value>,
+}
+
+impl<'d> Folder<'d> {
+    fn new(function: &Function, data: &'d [String], str_eq: Option<FuncId>) -> Self {
+        let count = function.types.len();
+        Self {
+            data,
+            str_eq,
+            types: function.types.clone(),
+            constants: vec![None; count],
+            strings: vec![None; count],
+            places: vec![None; count],
+            replacements: HashMap::new(),
+        }
+    }
+
+    fn resolve(&self, mut value: Value) -> Value {
+        while let Some(replacement) = self.replacements.get(&value) {
+            value = *replacement;
+        }
+        value
+    }
+
+    fn constant(&self, value: Value) -> Option<u64> {
+        self.constants[self.resolve(value).index()]
+    }
+
+    fn function(mut self, function: &mut Function) -> bool {
+        let mut changed = false;
+        let predecessors = function.predecessors();
+        // the values in slots when leaving every block
+        let mut exits: Vec<Option<HashMap<Place, Value>>> = vec![None; function.blocks.len()];
+        for id in function.reverse_postorder() {
+            // only a single predecessor, visited before, passes its stores on
+            let mut stored = match predecessors[id.index()].as_slice() {
+                [predecessor] => exits[predecessor.index()].clone().unwrap_or_default(),
+                _ => HashMap::new(),
+            };
+            let block = function.block_mut(id);
+            for inst in &mut block.insts {
+                for operand in inst.kind.operands_mut() {
+                    *operand = self.resolve(*operand);
+                }
+                let folded = self.memory(&inst.kind, &mut stored, inst.result);
+                let Some(result) = inst.result else {
+                    continue;
+                };
+                match folded.or_else(|| self.fold(&inst.kind)) {
+                    Some(Folded::Const(value)) => {
+                        let value = value & self.types[result.index()].mask();
+                        if inst.kind != InstKind::Const(value) {
+                            inst.kind = InstKind::Const(value);
+                            changed = true;
+                        }
+                        self.constants[result.index()] = Some(value);
+                    }
+                    Some(Folded::Value(value)) => {
+                        self.replacements.insert(result, value);
+                        changed = true;
+                    }
+                    None => {
+                        if let InstKind::Data(index) = inst.kind {
+                            self.strings[result.index()] = Some(index);
+                        }
+                    }
+                }
+            }
+            for operand in block.terminator.operands_mut() {
+                *operand = self.resolve(*operand);
+            }
+            if let Some(target) = self.branch(&block.terminator) {
+                block.terminator = Terminator::Jump(target);
+                changed = true;
+            }
+            exits[id.index()] = Some(stored);
+        }
+        function.substitute(&self.replacements);
+        changed
+    }
+
+    /// Tracks the stores to slots and forwards stored values to loads.
+    fn memory(
+        &mut self,
+        kind: &InstKind,
+        stored: &mut HashMap<Place, Value>,
+        result: Option<Value>,
+    ) -> Option<Folded> {
+        match kind {
+            InstKind::Slot { .. } => {
+                let result = result.expect("slots have a result");
+                self.places[result.index()] = Some((result, 0));
+            }
+            InstKind::PtrAdd(address, offset) => {
+                if let (Some((slot, base)), Some(offset)) =
+                    (self.places[address.index()], self.constant(*offset))
+                {
+                    let result = result.expect("ptradd has a result");
+                    self.places[result.index()] = Some((slot, base.wrapping_add(offset)));
+                }
+            }
+            InstKind::Load(address) => {
+                let result = result.expect("loads have a result");
+                let value = *stored.get(&self.places[address.index()]?)?;
+                return (self.types[value.index()] == self.types[result.index()])
+                    .then_some(Folded::Value(value));
+            }
+            InstKind::Store { value, address } => {
+                let size = self.types[value.index()].size();
+                if let Some(place) = self.places[address.index()] {
+                    self.clobber(stored, place, size);
+                    stored.insert(place, *value);
+                } else {
+                    stored.clear();
+                }
+            }
+            InstKind::Copy { to, size, .. } => {
+                if let Some(place) = self.places[to.index()] {
+                    self.clobber(stored, place, *size);
+                } else {
+                    stored.clear();
+                }
+            }
+            InstKind::Call(..) => stored.clear(),
+            _ => {}
+        }
+        None
+    }
+
+    /// Forgets the values stored in slots that overlap the given bytes.
+    fn clobber(&self, stored: &mut HashMap<Place, Value>, (slot, start): Place, size: u64) {
+        stored.retain(|(other, offset), value| {
+            let end = offset.saturating_add(self.types[value.index()].size());
+            *other != slot || end <= start || start.saturating_add(size) <= *offset
+        });
+    }
+
+    fn fold(&self, kind: &InstKind) -> Option<Folded> {
+        let constant = |value: &Value| self.constant(*value);
+        match kind {
+            InstKind::Const(value) => Some(Folded::Const(*value)),
+            InstKind::Unary(op, value) => {
+                let value = constant(value)?;
+                Some(Folded::Const(match op {
+                    UnaryOp::Neg => value.wrapping_neg(),
+                    UnaryOp::Not => !value,
+                }))
+            }
+            InstKind::Binary(op, lhs, rhs) => {
+                let operands = self.types[lhs.index()];
+                match (constant(lhs), constant(rhs)) {
+                    (Some(left), Some(right)) => interpret::binary(*op, operands, left, right)
+                        .ok()
+                        .map(Folded::Const),
+                    (left, right) => identity(*op, (*lhs, left), (*rhs, right), operands.mask()),
+                }
+            }
+            InstKind::Compare(op, lhs, rhs) => {
+                if lhs == rhs {
+                    return Some(Folded::Const(u64::from(matches!(
+                        op,
+                        CompareOp::Eq
+                            | CompareOp::Sle
+                            | CompareOp::Sge
+                            | CompareOp::Ule
+                            | CompareOp::Uge
+                    ))));
+                }
+                let operands = self.types[lhs.index()];
+                let (left, right) = (constant(lhs)?, constant(rhs)?);
+                Some(Folded::Const(u64::from(interpret::compare(
+                    *op, operands, left, right,
+                ))))
+            }
+            InstKind::Select(condition, then, otherwise) => {
+                if then == otherwise {
+                    return Some(Folded::Value(*then));
+                }
+                let chosen = if constant(condition)? != 0 {
+                    then
+                } else {
+                    otherwise
+                };
+                Some(match constant(chosen) {
+                    Some(value) => Folded::Const(value),
+                    None => Folded::Value(*chosen),
+                })
+            }
+            InstKind::Extend(value) => constant(value).map(Folded::Const),
+            InstKind::PtrAdd(address, offset) => {
+                (constant(offset)? == 0).then_some(Folded::Value(*address))
+            }
+            InstKind::Call(function, arguments) if Some(*function) == self.str_eq => {
+                let [lhs, lhs_length, rhs, rhs_length] = arguments.as_slice() else {
+                    return None;
+                };
+                let lhs = self.literal(*lhs, constant(lhs_length)?)?;
+                let rhs = self.literal(*rhs, constant(rhs_length)?)?;
+                Some(Folded::Const(u64::from(lhs == rhs)))
+            }
+            _ => None,
+        }
+    }
+
+    /// The first `length` bytes of the string constant `value` points to.
+    fn literal(&self, value: Value, length: u64) -> Option<&'d [u8]> {
+        let string = self.data[self.strings[value.index()]? as usize].as_bytes();
+        string.get(..usize::try_from(length).ok()?)
+    }
+
+    /// The only target of a branch or switch on a constant, or of a branch
+    /// to the same block with the same arguments either way.
+    fn branch(&self, terminator: &Terminator) -> Option<BlockCall> {
+        match terminator {
+            Terminator::Branch {
+                then, otherwise, ..
+            } if then == otherwise => Some(then.clone()),
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => Some(if self.constant(*condition)? != 0 {
+                then.clone()
+            } else {
+                otherwise.clone()
+            }),
+            Terminator::Switch {
+                value,
+                cases,
+                default,
+            } => {
+                let value = self.constant(*value)?;
+                let target = cases
+                    .iter()
+                    .find(|(case, _)| *case == value)
+                    .map_or(default, |(_, target)| target);
+                Some(target.clone())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Folds an operation with at most one constant operand where the result
+/// does not depend on the other one.
+fn identity(
+    op: BinaryOp,
+    (lhs, left): (Value, Option<u64>),
+    (rhs, right): (Value, Option<u64>),
+    mask: u64,
+) -> Option<Folded> {
+    use Folded::{Const, Value};
+    match (op, left, right) {
+        (BinaryOp::Add | BinaryOp::Or | BinaryOp::Xor, Some(0), _)
+        | (BinaryOp::Mul, Some(1), _) => Some(Value(rhs)),
+        (
+            BinaryOp::Add
+            | BinaryOp::Sub
+            | BinaryOp::Or
+            | BinaryOp::Xor
+            | BinaryOp::Shl
+            | BinaryOp::LShr
+            | BinaryOp::AShr,
+            _,
+            Some(0),
+        )
+        | (BinaryOp::Mul | BinaryOp::SDiv | BinaryOp::UDiv, _, Some(1)) => Some(Value(lhs)),
+        (BinaryOp::And, Some(all), _) if all == mask => Some(Value(rhs)),
+        (BinaryOp::And, _, Some(all)) if all == mask => Some(Value(lhs)),
+        (BinaryOp::Mul | BinaryOp::And, Some(0), _)
+        | (BinaryOp::Mul | BinaryOp::And, _, Some(0)) => Some(Const(0)),
+        (BinaryOp::Sub | BinaryOp::Xor, _, _) if lhs == rhs => Some(Const(0)),
+        (BinaryOp::And | BinaryOp::Or, _, _) if lhs == rhs => Some(Value(lhs)),
+        _ => None,
+    }
+}
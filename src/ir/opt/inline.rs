@@ -0,0 +1,119 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! Inlining of calls to small functions. The block with the call is split
+//! after it, the rest takes the result as parameter and the returns of the
+//! copied body jump there. The slots of the callee move to the entry of the
+//! caller, so they are allocated once however often the copy runs.
+//!
+//! Only the calls in the body before inlining are replaced, and never calls
+//! of a function to itself, so every run ends even with recursion.
+
+use crate::ir::{Block, BlockCall, BlockId, Function, InstKind, Module, Terminator, Value};
+
+/// Functions with more instructions than this are not inlined.
+pub const MAX_INSTS: usize = 40;
+
+pub fn run(module: &mut Module) -> bool {
+    let candidates = module
+        .functions
+        .iter()
+        .map(|function| {
+            let size = function
+                .blocks
+                .iter()
+                .map(|block| block.insts.len() + 1)
+                .sum::<usize>();
+            (!function.is_extern() && size <= MAX_INSTS).then(|| function.clone())
+        })
+        .collect::<Vec<_>>();
+    let mut changed = false;
+    for (id, function) in module.functions.iter_mut().enumerate() {
+        if function.is_extern() {
+            continue;
+        }
+        let mut work = function.block_ids().collect::<Vec<_>>();
+        while let Some(block) = work.pop() {
+            let call = function
+                .block(block)
+                .insts
+                .iter()
+                .enumerate()
+                .find_map(|(index, inst)| match inst.kind {
+                    InstKind::Call(callee, _) if callee.index() != id => {
+                        Some((index, candidates[callee.index()].as_ref()?))
+                    }
+                    _ => None,
+                });
+            if let Some((index, callee)) = call {
+                work.push(inline(function, block, index, callee));
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+/// Replaces the call at `index` in `block` by a copy of the body of
+/// `inlined` and returns the block with the instructions after the call.
+fn inline(caller: &mut Function, block: BlockId, index: usize, inlined: &Function) -> BlockId {
+    let entry = BlockId(u32::try_from(caller.blocks.len() + 1).expect("functions are small"));
+    let split = caller.block_mut(block);
+    let rest = split.insts.split_off(index + 1);
+    let call = split.insts.pop().expect("the call is the last instruction");
+    let InstKind::Call(_, args) = call.kind else {
+        unreachable!("inlining a call")
+    };
+    let terminator = std::mem::replace(
+        &mut split.terminator,
+        Terminator::Jump(BlockCall::new(entry, args)),
+    );
+    let location = std::mem::replace(&mut split.location, call.location);
+    let continuation = BlockId(entry.0 - 1);
+    caller.blocks.push(Block {
+        params: call.result.into_iter().collect(),
+        insts: rest,
+        terminator,
+        location,
+    });
+
+    let values = inlined
+        .types
+        .iter()
+        .map(|ty| caller.value(*ty))
+        .collect::<Vec<_>>();
+    let map = |value: &mut Value| *value = values[value.index()];
+    let mut slots = Vec::new();
+    for original in &inlined.blocks {
+        let mut copy = original.clone();
+        copy.params.iter_mut().for_each(map);
+        let mut insts = Vec::with_capacity(copy.insts.len());
+        for mut inst in copy.insts {
+            inst.result.iter_mut().for_each(map);
+            inst.kind.operands_mut().into_iter().for_each(map);
+            if matches!(inst.kind, InstKind::Slot { .. }) {
+                slots.push(inst);
+            } else {
+                insts.push(inst);
+            }
+        }
+        copy.insts = insts;
+        copy.terminator.operands_mut().into_iter().for_each(map);
+        for target in copy.terminator.successors_mut() {
+            target.block = BlockId(target.block.0 + entry.0);
+            target.args.iter_mut().for_each(map);
+        }
+        if let Terminator::Return(value) = copy.terminator {
+            // the result of the call may be ignored
+            let args = value
+                .filter(|_| call.result.is_some())
+                .into_iter()
+                .collect();
+            copy.terminator = Terminator::Jump(BlockCall::new(continuation, args));
+        }
+        caller.blocks.push(copy);
+    }
+    caller.blocks[0].insts.splice(0..0, slots);
+    continuation
+}
@@ -0,0 +1,187 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! Loop-invariant code motion. Instructions of a natural loop without
+//! effects whose operands are all defined outside the loop move to the
+//! block before its header, created if the header has several entries
+//! from outside. Loads stay, and so do divisions unless the divisor is a
+//! constant other than zero: a division guarded by a check in the loop
+//! must not run before the check.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::ir::{
+    BinaryOp, Block, BlockCall, BlockId, Function, InstKind, Module, Terminator, Value,
+};
+
+pub fn run(module: &mut Module) -> bool {
+    let mut changed = false;
+    for function in module
+        .functions
+        .iter_mut()
+        .filter(|function| !function.is_extern())
+    {
+        let mut done = HashSet::new();
+        // innermost loops first, so invariants move out step by step
+        while let Some((header, body)) = innermost(function, &done) {
+            done.insert(header);
+            changed |= hoist(function, header, &body);
+        }
+    }
+    changed
+}
+
+/// The smallest natural loop whose header is not in `done`, with the
+/// blocks of its body.
+fn innermost(function: &Function, done: &HashSet<BlockId>) -> Option<(BlockId, Vec<bool>)> {
+    let dominators = function.dominators();
+    let predecessors = function.predecessors();
+    let mut loops: Vec<(BlockId, Vec<bool>)> = Vec::new();
+    for latch in function.block_ids() {
+        let reachable = latch == BlockId(0) || dominators[latch.index()].is_some();
+        for target in function.block(latch).terminator.successors() {
+            let header = target.block;
+            if !reachable
+                || done.contains(&header)
+                || !Function::dominates(&dominators, header, latch)
+            {
+                continue;
+            }
+            let index = if let Some(index) = loops.iter().position(|(other, _)| *other == header) {
+                index
+            } else {
+                let mut body = vec![false; function.blocks.len()];
+                body[header.index()] = true;
+                loops.push((header, body));
+                loops.len() - 1
+            };
+            let body = &mut loops[index].1;
+            let mut work = vec![latch];
+            while let Some(block) = work.pop() {
+                if !std::mem::replace(&mut body[block.index()], true) {
+                    work.extend(&predecessors[block.index()]);
+                }
+            }
+        }
+    }
+    loops
+        .into_iter()
+        .min_by_key(|(_, body)| body.iter().filter(|inside| **inside).count())
+}
+
+/// Returns `true` if the instruction may run before the checks of the loop.
+fn movable(kind: &InstKind, constant: impl Fn(Value) -> Option<u64>) -> bool {
+    match kind {
+        InstKind::Binary(
+            BinaryOp::SDiv | BinaryOp::UDiv | BinaryOp::SRem | BinaryOp::URem,
+            _,
+            divisor,
+        ) => constant(*divisor).is_some_and(|divisor| divisor != 0),
+        InstKind::Const(_)
+        | InstKind::Unary(..)
+        | InstKind::Binary(..)
+        | InstKind::Compare(..)
+        | InstKind::Select(..)
+        | InstKind::Extend(_)
+        | InstKind::PtrAdd(..)
+        | InstKind::Data(_) => true,
+        InstKind::Slot { .. }
+        | InstKind::Load(_)
+        | InstKind::Store { .. }
+        | InstKind::Copy { .. }
+        | InstKind::Call(..) => false,
+    }
+}
+
+fn hoist(function: &mut Function, header: BlockId, body: &[bool]) -> bool {
+    let definitions = function.definitions();
+    let constants = function
+        .blocks
+        .iter()
+        .flat_map(|block| &block.insts)
+        .filter_map(|inst| match inst.kind {
+            InstKind::Const(value) => Some((inst.result?, value)),
+            _ => None,
+        })
+        .collect::<HashMap<_, _>>();
+    let mut invariant = vec![false; function.types.len()];
+    for (value, definition) in definitions.iter().enumerate() {
+        invariant[value] = definition.is_some_and(|(block, _)| !body[block.index()]);
+    }
+    let mut moved = Vec::new();
+    for id in function.reverse_postorder() {
+        if !body[id.index()] {
+            continue;
+        }
+        for (index, inst) in function.block(id).insts.iter().enumerate() {
+            let Some(result) = inst.result else {
+                continue;
+            };
+            let operands = inst.kind.operands();
+            if movable(&inst.kind, |value| constants.get(&value).copied())
+                && operands.iter().all(|operand| invariant[operand.index()])
+            {
+                invariant[result.index()] = true;
+                moved.push((id, index));
+            }
+        }
+    }
+    if moved.is_empty() {
+        return false;
+    }
+
+    let preheader = preheader(function, header, body);
+    let mut insts = Vec::with_capacity(moved.len());
+    for (block, index) in &moved {
+        insts.push(function.block(*block).insts[*index].clone());
+    }
+    for block in function.block_ids() {
+        let mut index = 0;
+        function.block_mut(block).insts.retain(|_| {
+            index += 1;
+            !moved.contains(&(block, index - 1))
+        });
+    }
+    function.block_mut(preheader).insts.extend(insts);
+    true
+}
+
+/// The only block entering the loop from outside with a jump, created if
+/// there is none.
+fn preheader(function: &mut Function, header: BlockId, body: &[bool]) -> BlockId {
+    let predecessors = function.predecessors();
+    let entries = predecessors[header.index()]
+        .iter()
+        .copied()
+        .filter(|block| !body[block.index()])
+        .collect::<Vec<_>>();
+    if let [entry] = entries.as_slice()
+        && matches!(function.block(*entry).terminator, Terminator::Jump(_))
+    {
+        return *entry;
+    }
+    let id = BlockId(u32::try_from(function.blocks.len()).expect("functions are small"));
+    let params = function
+        .block(header)
+        .params
+        .clone()
+        .into_iter()
+        .map(|param| function.value(function.ty(param)))
+        .collect::<Vec<_>>();
+    for entry in entries {
+        for target in function.block_mut(entry).terminator.successors_mut() {
+            if target.block == header {
+                target.block = id;
+            }
+        }
+    }
+    let location = function.block(header).location;
+    function.blocks.push(Block {
+        terminator: Terminator::Jump(BlockCall::new(header, params.clone())),
+        params,
+        insts: Vec::new(),
+        location,
+    });
+    id
+}
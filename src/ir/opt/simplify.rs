@@ -0,0 +1,154 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! Cleans up the control flow the other passes leave behind: removes
+//! unreachable blocks, replaces block parameters that always receive the
+//! same value, skips empty blocks that only jump on and merges a block into
+//! its only predecessor.
+
+use std::collections::HashMap;
+
+use crate::ir::{Block, BlockId, Function, Module, Terminator};
+
+pub fn run(module: &mut Module) -> bool {
+    let mut changed = false;
+    for function in module
+        .functions
+        .iter_mut()
+        .filter(|function| !function.is_extern())
+    {
+        let blocks = function.blocks.len();
+        function.remove_unreachable();
+        changed |= function.blocks.len() != blocks;
+        changed |= parameters(function);
+        changed |= forward(function);
+        changed |= merge(function);
+        if changed {
+            function.remove_unreachable();
+        }
+    }
+    changed
+}
+
+/// Replaces the parameters that receive a single value, apart from
+/// themselves, by that value.
+fn parameters(function: &mut Function) -> bool {
+    let mut replacements = HashMap::new();
+    for id in function.block_ids().skip(1) {
+        let params = &function.block(id).params;
+        let mut incoming = vec![Vec::new(); params.len()];
+        for block in &function.blocks {
+            for target in block.terminator.successors() {
+                if target.block == id {
+                    for (values, arg) in incoming.iter_mut().zip(&target.args) {
+                        values.push(*arg);
+                    }
+                }
+            }
+        }
+        for (param, values) in params.iter().zip(incoming) {
+            let mut values = values.into_iter().filter(|value| value != param);
+            if let Some(value) = values.next()
+                && values.all(|other| other == value)
+            {
+                replacements.insert(*param, value);
+            }
+        }
+    }
+    if replacements.is_empty() {
+        return false;
+    }
+    for id in function.block_ids().skip(1) {
+        let keep = function
+            .block(id)
+            .params
+            .iter()
+            .map(|param| !replacements.contains_key(param))
+            .collect::<Vec<_>>();
+        function
+            .block_mut(id)
+            .params
+            .retain(|param| !replacements.contains_key(param));
+        for block in &mut function.blocks {
+            for target in block.terminator.successors_mut() {
+                if target.block == id {
+                    let mut keep = keep.iter();
+                    target
+                        .args
+                        .retain(|_| *keep.next().expect("one per parameter"));
+                }
+            }
+        }
+    }
+    function.substitute(&replacements);
+    true
+}
+
+/// Lets jumps to blocks without parameters and instructions that only jump
+/// on go to the final target directly.
+fn forward(function: &mut Function) -> bool {
+    let mut changed = false;
+    for id in function.block_ids() {
+        for index in 0..function.block(id).terminator.successors().len() {
+            let target = function.block(id).terminator.successors()[index].block;
+            let skipped = function.block(target);
+            let Terminator::Jump(next) = &skipped.terminator else {
+                continue;
+            };
+            if target == id
+                || next.block == target
+                || !skipped.params.is_empty()
+                || !skipped.insts.is_empty()
+            {
+                continue;
+            }
+            let next = next.clone();
+            *function.block_mut(id).terminator.successors_mut()[index] = next;
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Appends a block to its only predecessor if that ends in a jump to it.
+fn merge(function: &mut Function) -> bool {
+    let mut changed = false;
+    let mut predecessors = function.predecessors();
+    for id in function.block_ids() {
+        while let Terminator::Jump(target) = &function.block(id).terminator {
+            let next = target.block;
+            if next == id || next == BlockId(0) || predecessors[next.index()] != [id] {
+                break;
+            }
+            let args = target.args.clone();
+            let location = function.block(next).location;
+            let merged = std::mem::replace(
+                function.block_mut(next),
+                Block {
+                    params: Vec::new(),
+                    insts: Vec::new(),
+                    terminator: Terminator::Unreachable,
+                    location,
+                },
+            );
+            let replacements = merged.params.iter().copied().zip(args).collect();
+            for successor in merged.terminator.successors() {
+                let list = &mut predecessors[successor.block.index()];
+                for predecessor in list.iter_mut() {
+                    if *predecessor == next {
+                        *predecessor = id;
+                    }
+                }
+            }
+            predecessors[next.index()].clear();
+            let block = function.block_mut(id);
+            block.insts.extend(merged.insts);
+            block.terminator = merged.terminator;
+            block.location = merged.location;
+            function.substitute(&replacements);
+            changed = true;
+        }
+    }
+    changed
+}
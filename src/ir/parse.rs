@@ -0,0 +1,821 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! Reads the text format of the IR that the `Display` of [`Module`]
+//! writes. Values and blocks may have any name, they are numbered in the
+//! order of their definitions. Locations are optional and `;` starts a
+//! comment.
+
+use std::{collections::HashMap, iter::Peekable, str::CharIndices};
+
+use crate::{Error, parser::tokens::Location};
+
+use super::{
+    Aggregate, AggregateField, BinaryOp, Block, BlockCall, BlockId, CompareOp, FieldType, FuncId,
+    Function, Inst, InstKind, Module, Position, Signature, Terminator, Type, TypeId, UnaryOp,
+    Value,
+};
+
+/// Parses a module, it is not verified.
+///
+/// # Errors
+///
+/// Returns [`Error::IrSyntax`] for the first malformed line.
+pub fn parse(source: &str) -> Result<Module, Error> {
+    let tokens = lex(source)?;
+    Parser {
+        tokens,
+        position: 0,
+        calls: Vec::new(),
+    }
+    .module()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Value(String),
+    Function(String),
+    Data(u32),
+    Type(u32),
+    Int(i128),
+    Str(String),
+    Location(Location),
+    Punct(&'static str),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Ident(name) => name.clone(),
+            Token::Value(name) => format!("%{name}"),
+            Token::Function(name) => format!("@{name}"),
+            Token::Data(index) => format!("#{index}"),
+            Token::Type(index) => format!("${index}"),
+            Token::Int(int) => int.to_string(),
+            Token::Str(string) => format!("{string:?}"),
+            Token::Location(location) => format!("!{}", Position(*location)),
+            Token::Punct(punct) => (*punct).to_string(),
+        }
+    }
+}
+
+fn syntax(line: usize, message: impl Into<String>) -> Error {
+    Error::IrSyntax {
+        line,
+        message: message.into(),
+    }
+}
+
+fn lex(source: &str) -> Result<Vec<(Token, usize)>, Error> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    let mut line = 1;
+    while let Some((_, c)) = chars.next() {
+        let token = match c {
+            '\n' => {
+                line += 1;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            ';' => {
+                while chars.next_if(|(_, c)| *c != '\n').is_some() {}
+                continue;
+            }
+            '(' => Token::Punct("("),
+            ')' => Token::Punct(")"),
+            '{' => Token::Punct("{"),
+            '}' => Token::Punct("}"),
+            ',' => Token::Punct(","),
+            ':' => Token::Punct(":"),
+            '=' => Token::Punct("="),
+            '-' if chars.next_if(|(_, c)| *c == '>').is_some() => Token::Punct("->"),
+            '-' | '0'..='9' => {
+                let mut digits = c.to_string();
+                digits.push_str(&word(&mut chars, |c| c.is_ascii_digit()));
+                let int = digits
+                    .parse()
+                    .map_err(|_| syntax(line, format!("invalid integer {digits}")))?;
+                Token::Int(int)
+            }
+            '%' => Token::Value(name(&mut chars, line, "value", |c| {
+                c.is_ascii_alphanumeric() || matches!(c, '_' | '.')
+            })?),
+            '@' if chars.next_if(|(_, c)| *c == '"').is_some() => {
+                Token::Function(string(&mut chars, line)?)
+            }
+            '@' => Token::Function(name(&mut chars, line, "function", |c| {
+                c.is_ascii_alphanumeric() || matches!(c, '_' | ':' | '.')
+            })?),
+            '#' => {
+                let digits = word(&mut chars, |c| c.is_ascii_digit());
+                Token::Data(
+                    digits
+                        .parse()
+                        .map_err(|_| syntax(line, "expected a number after #"))?,
+                )
+            }
+            '$' => {
+                let digits = word(&mut chars, |c| c.is_ascii_digit());
+                Token::Type(
+                    digits
+                        .parse()
+                        .map_err(|_| syntax(line, "expected a number after $"))?,
+                )
+            }
+            '!' => {
+                let text = word(&mut chars, |c| c.is_ascii_digit() || c == ':');
+                let parts = text
+                    .split(':')
+                    .map(str::parse)
+                    .collect::<Result<Vec<usize>, _>>()
+                    .ok()
+                    .filter(|parts| parts.len() == 3)
+                    .ok_or_else(|| syntax(line, format!("invalid location !{text}")))?;
+                Token::Location(Location {
+                    l: parts[1],
+                    c: parts[2],
+                    ..Location::new(parts[0])
+                })
+            }
+            '"' => Token::Str(string(&mut chars, line)?),
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut ident = c.to_string();
+                ident.push_str(&word(&mut chars, |c| {
+                    c.is_ascii_alphanumeric() || matches!(c, '_' | '.')
+                }));
+                Token::Ident(ident)
+            }
+            c => return Err(syntax(line, format!("unexpected character {c:?}"))),
+        };
+        tokens.push((token, line));
+    }
+    Ok(tokens)
+}
+
+fn word(chars: &mut Peekable<CharIndices<'_>>, accept: impl Fn(char) -> bool) -> String {
+    let mut word = String::new();
+    while let Some((_, c)) = chars.next_if(|(_, c)| accept(*c)) {
+        word.push(c);
+    }
+    word
+}
+
+fn name(
+    chars: &mut Peekable<CharIndices<'_>>,
+    line: usize,
+    what: &str,
+    accept: impl Fn(char) -> bool,
+) -> Result<String, Error> {
+    let name = word(chars, accept);
+    if name.is_empty() {
+        return Err(syntax(line, format!("expected the name of a {what}")));
+    }
+    Ok(name)
+}
+
+/// The rest of a string literal with the escapes of Rust's `Debug`.
+fn string(chars: &mut Peekable<CharIndices<'_>>, line: usize) -> Result<String, Error> {
+    let mut string = String::new();
+    let unterminated = || syntax(line, "unterminated string");
+    loop {
+        let (_, c) = chars.next().ok_or_else(unterminated)?;
+        match c {
+            '"' => return Ok(string),
+            '\\' => {
+                let (_, escape) = chars.next().ok_or_else(unterminated)?;
+                string.push(match escape {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    '0' => '\0',
+                    '\\' | '"' | '\'' => escape,
+                    'u' => {
+                        let digits = word(chars, |c| c != '}');
+                        chars.next();
+                        digits
+                            .strip_prefix('{')
+                            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| syntax(line, format!("invalid escape \\u{digits}}}")))?
+                    }
+                    escape => return Err(syntax(line, format!("invalid escape \\{escape}"))),
+                });
+            }
+            c => string.push(c),
+        }
+    }
+}
+
+/// A call whose callee is resolved once every function is known.
+struct Call {
+    function: usize,
+    block: usize,
+    inst: usize,
+    name: String,
+    line: usize,
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    calls: Vec<Call>,
+}
+
+/// The names of a function body.
+#[derive(Default)]
+struct Names {
+    values: HashMap<String, Value>,
+    /// Name and type of every value, the type is known once it is defined.
+    types: Vec<(String, Option<Type>)>,
+    blocks: HashMap<String, usize>,
+    /// Every block by the order of the first mention of its name.
+    definitions: Vec<(String, Option<Block>)>,
+    /// The blocks in the order of their definitions.
+    order: Vec<usize>,
+}
+
+impl Names {
+    fn value(&mut self, name: &str) -> Value {
+        if let Some(value) = self.values.get(name) {
+            return *value;
+        }
+        let value = Value(u32::try_from(self.types.len()).expect("functions are small"));
+        self.types.push((name.to_string(), None));
+        self.values.insert(name.to_string(), value);
+        value
+    }
+
+    fn block(&mut self, name: &str) -> usize {
+        if let Some(block) = self.blocks.get(name) {
+            return *block;
+        }
+        self.definitions.push((name.to_string(), None));
+        self.blocks
+            .insert(name.to_string(), self.definitions.len() - 1);
+        self.definitions.len() - 1
+    }
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map_or(1, |(_, line)| *line)
+    }
+
+    fn error(&self, message: impl Into<String>) -> Error {
+        syntax(self.line(), message)
+    }
+
+    fn expected(&self, what: &str) -> Error {
+        match self.peek() {
+            Some(token) => self.error(format!("expected {what}, found {}", token.describe())),
+            None => self.error(format!("expected {what}, found the end")),
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .map(|(token, _)| token.clone());
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Punct(candidate)) if *candidate == punct) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, punct: &'static str) -> Result<(), Error> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(self.expected(&format!("`{punct}`")))
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(ident)) if ident == keyword) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn ident(&mut self, what: &str) -> Result<String, Error> {
+        match self.peek() {
+            Some(Token::Ident(ident)) => {
+                let ident = ident.clone();
+                self.position += 1;
+                Ok(ident)
+            }
+            _ => Err(self.expected(what)),
+        }
+    }
+
+    fn int(&mut self) -> Result<i128, Error> {
+        match self.peek() {
+            Some(Token::Int(int)) => {
+                let int = *int;
+                self.position += 1;
+                Ok(int)
+            }
+            _ => Err(self.expected("an integer")),
+        }
+    }
+
+    fn unsigned(&mut self) -> Result<u64, Error> {
+        let int = self.int()?;
+        u64::try_from(int).map_err(|_| self.error(format!("{int} is out of range")))
+    }
+
+    fn data(&mut self) -> Result<u32, Error> {
+        match self.peek() {
+            Some(Token::Data(index)) => {
+                let index = *index;
+                self.position += 1;
+                Ok(index)
+            }
+            _ => Err(self.expected("a string constant")),
+        }
+    }
+
+    fn type_id(&mut self) -> Result<TypeId, Error> {
+        match self.peek() {
+            Some(Token::Type(index)) => {
+                let index = *index;
+                self.position += 1;
+                Ok(TypeId(index))
+            }
+            _ => Err(self.expected("an aggregate type")),
+        }
+    }
+
+    fn string(&mut self, what: &str) -> Result<String, Error> {
+        match self.peek() {
+            Some(Token::Str(string)) => {
+                let string = string.clone();
+                self.position += 1;
+                Ok(string)
+            }
+            _ => Err(self.expected(what)),
+        }
+    }
+
+    /// `$n = "name" (size, align) { "field" offset: type, ... }` after
+    /// `type`.
+    fn aggregate(&mut self, index: usize) -> Result<Aggregate, Error> {
+        if self.type_id()?.index() != index {
+            self.position -= 1;
+            return Err(self.error(format!("expected ${index}")));
+        }
+        self.expect("=")?;
+        let name = self.string("a name")?;
+        self.expect("(")?;
+        let size = self.unsigned()?;
+        self.expect(",")?;
+        let align = self.unsigned()?;
+        self.expect(")")?;
+        self.expect("{")?;
+        let mut fields = Vec::new();
+        while !self.eat("}") {
+            if !fields.is_empty() {
+                self.expect(",")?;
+            }
+            let name = self.string("the name of a field")?;
+            let offset = self.unsigned()?;
+            self.expect(":")?;
+            let ty = if let Some(Token::Type(_)) = self.peek() {
+                FieldType::Aggregate(self.type_id()?)
+            } else {
+                FieldType::Scalar(self.ty()?)
+            };
+            fields.push(AggregateField { name, offset, ty });
+        }
+        Ok(Aggregate {
+            name,
+            size,
+            align,
+            fields,
+        })
+    }
+
+    fn ty(&mut self) -> Result<Type, Error> {
+        let ty = match self.peek() {
+            Some(Token::Ident(ident)) => match ident.as_str() {
+                "i1" => Type::I1,
+                "i8" => Type::I8,
+                "i16" => Type::I16,
+                "i32" => Type::I32,
+                "i64" => Type::I64,
+                "ptr" => Type::Ptr,
+                _ => return Err(self.expected("a type")),
+            },
+            _ => return Err(self.expected("a type")),
+        };
+        self.position += 1;
+        Ok(ty)
+    }
+
+    fn location(&mut self) -> Option<Location> {
+        if let Some(Token::Location(location)) = self.peek() {
+            let location = *location;
+            self.position += 1;
+            return Some(location);
+        }
+        None
+    }
+
+    fn module(mut self) -> Result<Module, Error> {
+        let mut module = Module::default();
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Ident(ident) if ident == "type" => {
+                    self.position += 1;
+                    let aggregate = self.aggregate(module.types.len())?;
+                    module.types.push(aggregate);
+                }
+                Token::Ident(ident) if ident == "data" => {
+                    self.position += 1;
+                    let index = self.data()?;
+                    if index as usize != module.data.len() {
+                        return Err(self.error(format!("expected #{}", module.data.len())));
+                    }
+                    self.expect("=")?;
+                    if let Some(Token::Str(string)) = self.next() {
+                        module.data.push(string);
+                    } else {
+                        self.position -= 1;
+                        return Err(self.expected("a string"));
+                    }
+                }
+                Token::Ident(ident) if ident == "extern" => {
+                    self.position += 1;
+                    if !self.keyword("fn") {
+                        return Err(self.expected("`fn`"));
+                    }
+                    module.functions.push(self.header()?);
+                }
+                Token::Ident(ident) if ident == "fn" => {
+                    self.position += 1;
+                    let header = self.header()?;
+                    let function = self.body(header, module.functions.len())?;
+                    module.functions.push(function);
+                }
+                _ => return Err(self.expected("`type`, `data`, `fn` or `extern fn`")),
+            }
+        }
+
+        for call in self.calls {
+            let id = module
+                .function(&call.name)
+                .ok_or_else(|| syntax(call.line, format!("unknown function @{}", call.name)))?;
+            let inst = &mut module.functions[call.function].blocks[call.block].insts[call.inst];
+            if let InstKind::Call(function, _) = &mut inst.kind {
+                *function = id;
+            }
+        }
+        Ok(module)
+    }
+
+    fn header(&mut self) -> Result<Function, Error> {
+        let Some(Token::Function(name)) = self.peek().cloned() else {
+            return Err(self.expected("the name of a function"));
+        };
+        self.position += 1;
+        self.expect("(")?;
+        let mut params = Vec::new();
+        if !self.eat(")") {
+            loop {
+                params.push(self.ty()?);
+                if self.eat(")") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+        let result = if self.eat("->") {
+            Some(self.ty()?)
+        } else {
+            None
+        };
+        let location = self.location().unwrap_or(Location::new(0));
+        Ok(Function::new(name, Signature { params, result }, location))
+    }
+
+    fn body(&mut self, mut function: Function, index: usize) -> Result<Function, Error> {
+        self.expect("{")?;
+        let mut names = Names::default();
+        while !self.eat("}") {
+            let line = self.line();
+            let label = self.ident("a block label or `}`")?;
+            let block = names.block(&label);
+            if names.definitions[block].1.is_some() {
+                return Err(syntax(line, format!("block {label} is defined twice")));
+            }
+            let mut params = Vec::new();
+            if self.eat("(") {
+                loop {
+                    params.push(self.definition(&mut names)?);
+                    if self.eat(")") {
+                        break;
+                    }
+                    self.expect(",")?;
+                }
+            }
+            self.expect(":")?;
+            let position = names.order.len();
+            names.order.push(block);
+            let (insts, terminator, location) = self.block(&mut names, index, position)?;
+            names.definitions[block].1 = Some(Block {
+                params,
+                insts,
+                terminator,
+                location: location.unwrap_or(function.location),
+            });
+        }
+
+        let mut ids = vec![BlockId(0); names.definitions.len()];
+        for (position, block) in names.order.iter().enumerate() {
+            ids[*block] = BlockId(u32::try_from(position).expect("functions are small"));
+        }
+        let mut blocks = names
+            .definitions
+            .into_iter()
+            .map(|(label, block)| {
+                block.ok_or_else(|| self.error(format!("undefined block {label}")))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .zip(&ids)
+            .collect::<Vec<_>>();
+        blocks.sort_by_key(|(_, id)| **id);
+        function.blocks = blocks.into_iter().map(|(block, _)| block).collect();
+        for block in &mut function.blocks {
+            for target in block.terminator.successors_mut() {
+                target.block = ids[target.block.index()];
+            }
+        }
+        function.types = names
+            .types
+            .into_iter()
+            .map(|(name, ty)| ty.ok_or_else(|| self.error(format!("undefined value %{name}"))))
+            .collect::<Result<_, _>>()?;
+        function.renumber();
+        Ok(function)
+    }
+
+    /// `%name: type` of a block parameter or an instruction.
+    fn definition(&mut self, names: &mut Names) -> Result<Value, Error> {
+        let Some(Token::Value(name)) = self.peek().cloned() else {
+            return Err(self.expected("a value"));
+        };
+        self.position += 1;
+        self.expect(":")?;
+        let ty = self.ty()?;
+        let value = names.value(&name);
+        let defined = &mut names.types[value.index()].1;
+        if defined.is_some() {
+            return Err(self.error(format!("%{name} is defined twice")));
+        }
+        *defined = Some(ty);
+        Ok(value)
+    }
+
+    fn value(&mut self, names: &mut Names) -> Result<Value, Error> {
+        match self.peek() {
+            Some(Token::Value(name)) => {
+                let value = names.value(&name.clone());
+                self.position += 1;
+                Ok(value)
+            }
+            _ => Err(self.expected("a value")),
+        }
+    }
+
+    fn values(&mut self, names: &mut Names, count: usize) -> Result<Vec<Value>, Error> {
+        let mut values = Vec::with_capacity(count);
+        for i in 0..count {
+            if i > 0 {
+                self.expect(",")?;
+            }
+            values.push(self.value(names)?);
+        }
+        Ok(values)
+    }
+
+    /// `(%a, %b)` or nothing.
+    fn arguments(&mut self, names: &mut Names) -> Result<Vec<Value>, Error> {
+        let mut args = Vec::new();
+        if self.eat("(") && !self.eat(")") {
+            loop {
+                args.push(self.value(names)?);
+                if self.eat(")") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+        Ok(args)
+    }
+
+    fn target(&mut self, names: &mut Names) -> Result<BlockCall, Error> {
+        let label = self.ident("a block label")?;
+        let block = names.block(&label);
+        let args = self.arguments(names)?;
+        Ok(BlockCall::new(
+            BlockId(u32::try_from(block).expect("functions are small")),
+            args,
+        ))
+    }
+
+    /// The instructions and the terminator of a block.
+    fn block(
+        &mut self,
+        names: &mut Names,
+        function: usize,
+        block: usize,
+    ) -> Result<(Vec<Inst>, Terminator, Option<Location>), Error> {
+        let mut insts = Vec::new();
+        loop {
+            let result = if let Some(Token::Value(_)) = self.peek() {
+                let result = self.definition(names)?;
+                self.expect("=")?;
+                Some(result)
+            } else {
+                None
+            };
+            let line = self.line();
+            let op = self.ident("an instruction")?;
+            if result.is_none()
+                && let Some(terminator) = self.terminator(&op, names)?
+            {
+                return Ok((insts, terminator, self.location()));
+            }
+            let (kind, callee) = self.instruction(&op, result.is_some(), names)?;
+            if let Some(name) = callee {
+                self.calls.push(Call {
+                    function,
+                    block,
+                    inst: insts.len(),
+                    name,
+                    line,
+                });
+            }
+            let location = self.location().unwrap_or(Location::new(0));
+            insts.push(Inst {
+                result,
+                kind,
+                location,
+            });
+        }
+    }
+
+    fn terminator(&mut self, op: &str, names: &mut Names) -> Result<Option<Terminator>, Error> {
+        Ok(Some(match op {
+            "jump" => Terminator::Jump(self.target(names)?),
+            "branch" => {
+                let condition = self.value(names)?;
+                self.expect(",")?;
+                let then = self.target(names)?;
+                self.expect(",")?;
+                let otherwise = self.target(names)?;
+                Terminator::Branch {
+                    condition,
+                    then,
+                    otherwise,
+                }
+            }
+            "switch" => {
+                let value = self.value(names)?;
+                let mut cases = Vec::new();
+                loop {
+                    self.expect(",")?;
+                    if self.keyword("default") {
+                        self.expect(":")?;
+                        let default = self.target(names)?;
+                        break Terminator::Switch {
+                            value,
+                            cases,
+                            default,
+                        };
+                    }
+                    let case = self.unsigned()?;
+                    self.expect(":")?;
+                    cases.push((case, self.target(names)?));
+                }
+            }
+            "ret" => match self.peek() {
+                Some(Token::Value(_)) => Terminator::Return(Some(self.value(names)?)),
+                _ => Terminator::Return(None),
+            },
+            "trap" => Terminator::Trap(self.data()?),
+            "unreachable" => Terminator::Unreachable,
+            _ => return Ok(None),
+        }))
+    }
+
+    /// An instruction and the name of the function it calls.
+    fn instruction(
+        &mut self,
+        op: &str,
+        has_result: bool,
+        names: &mut Names,
+    ) -> Result<(InstKind, Option<String>), Error> {
+        if let Some((_, op)) = UnaryOp::ALL.iter().find(|(name, _)| *name == op) {
+            return Ok((InstKind::Unary(*op, self.value(names)?), None));
+        }
+        if let Some((_, op)) = BinaryOp::ALL.iter().find(|(name, _)| *name == op) {
+            let operands = self.values(names, 2)?;
+            return Ok((InstKind::Binary(*op, operands[0], operands[1]), None));
+        }
+        if let Some((_, op)) = CompareOp::ALL.iter().find(|(name, _)| *name == op) {
+            let operands = self.values(names, 2)?;
+            return Ok((InstKind::Compare(*op, operands[0], operands[1]), None));
+        }
+        let kind = match op {
+            // negative constants are written as such for i64 and wrap
+            "const" => InstKind::Const(match self.int()? {
+                int if int < 0 => {
+                    let int = i64::try_from(int)
+                        .map_err(|_| self.error(format!("{int} is out of range")))?;
+                    int.cast_unsigned()
+                }
+                int => {
+                    u64::try_from(int).map_err(|_| self.error(format!("{int} is out of range")))?
+                }
+            }),
+            "select" => {
+                let operands = self.values(names, 3)?;
+                InstKind::Select(operands[0], operands[1], operands[2])
+            }
+            "extend" => InstKind::Extend(self.value(names)?),
+            "slot" => {
+                let size = self.unsigned()?;
+                self.expect(",")?;
+                let align = self.unsigned()?;
+                let ty = if self.eat(",") {
+                    Some(self.type_id()?)
+                } else {
+                    None
+                };
+                InstKind::Slot { size, align, ty }
+            }
+            "load" => InstKind::Load(self.value(names)?),
+            "store" => {
+                let operands = self.values(names, 2)?;
+                InstKind::Store {
+                    value: operands[0],
+                    address: operands[1],
+                }
+            }
+            "ptradd" => {
+                let operands = self.values(names, 2)?;
+                InstKind::PtrAdd(operands[0], operands[1])
+            }
+            "copy" => {
+                let operands = self.values(names, 2)?;
+                self.expect(",")?;
+                InstKind::Copy {
+                    to: operands[0],
+                    from: operands[1],
+                    size: self.unsigned()?,
+                }
+            }
+            "data" => InstKind::Data(self.data()?),
+            "call" => {
+                let Some(Token::Function(name)) = self.peek().cloned() else {
+                    return Err(self.expected("the name of a function"));
+                };
+                self.position += 1;
+                if !matches!(self.peek(), Some(Token::Punct("("))) {
+                    return Err(self.expected("`(`"));
+                }
+                let arguments = self.arguments(names)?;
+                // the callee is resolved once every function is known
+                return Ok((InstKind::Call(FuncId(u32::MAX), arguments), Some(name)));
+            }
+            _ if has_result => {
+                self.position -= 1;
+                return Err(self.expected("an instruction"));
+            }
+            _ => {
+                self.position -= 1;
+                return Err(self.expected("an instruction or a terminator"));
+            }
+        };
+        Ok((kind, None))
+    }
+}
@@ -0,0 +1,408 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! Checks the invariants every pass may rely on: jumps pass as many
+//! arguments as their targets take and of the same types, every value is
+//! defined once and before its uses on every path that reaches them,
+//! operands have the types their instructions demand and calls match the
+//! signatures of their callees. Aggregate types only contain earlier ones
+//! and slots of such a type have its size.
+
+use crate::Error;
+
+use super::{
+    BlockCall, BlockId, FieldType, FuncId, Function, Inst, InstKind, Module, Terminator, Type,
+    TypeId, UnaryOp, Value,
+};
+
+/// Verifies every function of the module.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidIr`] for the first violation.
+pub fn verify(module: &Module) -> Result<(), Error> {
+    for (index, aggregate) in module.types.iter().enumerate() {
+        let error = |message: String| Error::InvalidIr {
+            function: format!("${index}"),
+            message,
+        };
+        let mut end = 0;
+        for field in &aggregate.fields {
+            let (size, align) = match field.ty {
+                FieldType::Scalar(ty) => (ty.size(), ty.size()),
+                FieldType::Aggregate(id) if id.index() < index => {
+                    let inner = &module.types[id.index()];
+                    (inner.size, inner.align)
+                }
+                FieldType::Aggregate(id) => {
+                    return Err(error(format!("{id} is not defined before")));
+                }
+            };
+            if field.offset < end || field.offset % align != 0 {
+                return Err(error(format!("{:?} is misplaced", field.name)));
+            }
+            end = field.offset + size;
+        }
+        if end > aggregate.size || !aggregate.align.is_power_of_two() {
+            return Err(error("the size does not fit the fields".into()));
+        }
+    }
+    for function in &module.functions {
+        Verifier { module, function }.function()?;
+    }
+    Ok(())
+}
+
+struct Verifier<'m> {
+    module: &'m Module,
+    function: &'m Function,
+}
+
+/// Where a value is defined: the block and the index of the instruction,
+/// `None` for a parameter of the block.
+type Definition = (BlockId, Option<usize>);
+
+impl Verifier<'_> {
+    fn error(&self, message: String) -> Error {
+        Error::InvalidIr {
+            function: self.function.name.clone(),
+            message,
+        }
+    }
+
+    fn function(&self) -> Result<(), Error> {
+        let function = self.function;
+        if function.is_extern() {
+            return Ok(());
+        }
+        let entry = function.block(BlockId(0));
+        let params = entry
+            .params
+            .iter()
+            .map(|param| self.ty(*param))
+            .collect::<Result<Vec<_>, _>>()?;
+        if params != function.signature.params {
+            return Err(self.error("the entry takes other parameters than the function".into()));
+        }
+        if !function.predecessors()[0].is_empty() {
+            return Err(self.error("b0 is the entry, no block may jump to it".into()));
+        }
+
+        let definitions = self.definitions()?;
+        let dominators = function.dominators();
+        for id in function.block_ids() {
+            let block = function.block(id);
+            for (index, inst) in block.insts.iter().enumerate() {
+                self.inst(inst)
+                    .map_err(|message| self.error(format!("{id}: {message}")))?;
+                for operand in inst.kind.operands() {
+                    self.dominates(&definitions, &dominators, operand, (id, Some(index)))?;
+                }
+            }
+            self.terminator(&block.terminator)
+                .map_err(|message| self.error(format!("{id}: {message}")))?;
+            let args = block
+                .terminator
+                .successors()
+                .into_iter()
+                .flat_map(|target| target.args.iter().copied());
+            for operand in block.terminator.operands().into_iter().chain(args) {
+                self.dominates(
+                    &definitions,
+                    &dominators,
+                    operand,
+                    (id, Some(block.insts.len())),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn ty(&self, value: Value) -> Result<Type, Error> {
+        self.function
+            .types
+            .get(value.index())
+            .copied()
+            .ok_or_else(|| self.error(format!("{value} has no type")))
+    }
+
+    /// Where every value is defined, each at most once.
+    fn definitions(&self) -> Result<Vec<Option<Definition>>, Error> {
+        let mut definitions = vec![None; self.function.types.len()];
+        for id in self.function.block_ids() {
+            let block = self.function.block(id);
+            let results = block
+                .insts
+                .iter()
+                .enumerate()
+                .filter_map(|(index, inst)| Some((inst.result?, Some(index))));
+            let params = block.params.iter().map(|param| (*param, None));
+            for (value, index) in params.chain(results) {
+                self.ty(value)?;
+                if definitions[value.index()].replace((id, index)).is_some() {
+                    return Err(self.error(format!("{value} is defined twice")));
+                }
+            }
+        }
+        Ok(definitions)
+    }
+
+    /// Checks that the definition of `value` dominates a use. Uses in
+    /// unreachable blocks only need a definition.
+    fn dominates(
+        &self,
+        definitions: &[Option<Definition>],
+        dominators: &[Option<BlockId>],
+        value: Value,
+        (block, index): Definition,
+    ) -> Result<(), Error> {
+        let Some((defined, position)) = definitions.get(value.index()).copied().flatten() else {
+            return Err(self.error(format!("{block}: {value} is used but never defined")));
+        };
+        let reachable = block == BlockId(0) || dominators[block.index()].is_some();
+        if !reachable {
+            return Ok(());
+        }
+        let dominated = if defined == block {
+            position < index || position.is_none()
+        } else {
+            let mut current = block;
+            loop {
+                match dominators[current.index()] {
+                    Some(dominator) if dominator == defined => break true,
+                    Some(dominator) => current = dominator,
+                    None => break false,
+                }
+            }
+        };
+        if dominated {
+            Ok(())
+        } else {
+            Err(self.error(format!(
+                "{block}: {value} is used where its definition does not dominate"
+            )))
+        }
+    }
+
+    fn slot(&self, size: u64, align: u64, ty: Option<TypeId>) -> Result<(), String> {
+        if !align.is_power_of_two() {
+            return Err(format!("alignment {align} is not a power of two"));
+        }
+        if let Some(ty) = ty {
+            let aggregate = self
+                .module
+                .types
+                .get(ty.index())
+                .ok_or_else(|| format!("unknown type {ty}"))?;
+            if aggregate.size != size || aggregate.align != align {
+                return Err(format!("the slot does not fit {ty}"));
+            }
+        }
+        Ok(())
+    }
+
+    fn inst(&self, inst: &Inst) -> Result<(), String> {
+        let ty = |value: Value| self.function.types.get(value.index()).copied().ok_or(value);
+        let ty = |value: Value| ty(value).map_err(|value| format!("{value} has no type"));
+        let result = inst.result.map(ty).transpose()?;
+        let integer = |value: Value| -> Result<Type, String> {
+            match ty(value)? {
+                Type::Ptr => Err(format!("{value} is a pointer, not an integer")),
+                integer => Ok(integer),
+            }
+        };
+        let expect = |value: Value, expected: Type| -> Result<(), String> {
+            let found = ty(value)?;
+            if found == expected {
+                Ok(())
+            } else {
+                Err(format!("{value} is {found}, expected {expected}"))
+            }
+        };
+
+        // the type of the result, `None` if there is none
+        let produced = match &inst.kind {
+            InstKind::Const(value) => {
+                let ty = result.ok_or("const has no result")?;
+                if value & !ty.mask() != 0 {
+                    return Err(format!("{value} does not fit into {ty}"));
+                }
+                Some(ty)
+            }
+            InstKind::Unary(op, value) => {
+                let ty = integer(*value)?;
+                if *op == UnaryOp::Neg && ty == Type::I1 {
+                    return Err("neg of an i1".into());
+                }
+                Some(ty)
+            }
+            InstKind::Binary(_, lhs, rhs) => {
+                let ty = integer(*lhs)?;
+                expect(*rhs, ty)?;
+                Some(ty)
+            }
+            InstKind::Compare(_, lhs, rhs) => {
+                expect(*rhs, ty(*lhs)?)?;
+                Some(Type::I1)
+            }
+            InstKind::Select(condition, then, otherwise) => {
+                expect(*condition, Type::I1)?;
+                let ty = ty(*then)?;
+                expect(*otherwise, ty)?;
+                Some(ty)
+            }
+            InstKind::Extend(value) => {
+                let from = integer(*value)?;
+                let to = result.ok_or("extend has no result")?;
+                if to == Type::Ptr || to == from || (to.size() <= from.size() && from != Type::I1) {
+                    return Err(format!("cannot extend {from} to {to}"));
+                }
+                Some(to)
+            }
+            InstKind::Slot { size, align, ty } => {
+                self.slot(*size, *align, *ty)?;
+                Some(Type::Ptr)
+            }
+            InstKind::Load(address) => {
+                expect(*address, Type::Ptr)?;
+                Some(result.ok_or("load has no result")?)
+            }
+            InstKind::Store { value, address } => {
+                ty(*value)?;
+                expect(*address, Type::Ptr)?;
+                None
+            }
+            InstKind::PtrAdd(address, offset) => {
+                expect(*address, Type::Ptr)?;
+                expect(*offset, Type::I64)?;
+                Some(Type::Ptr)
+            }
+            InstKind::Copy { to, from, .. } => {
+                expect(*to, Type::Ptr)?;
+                expect(*from, Type::Ptr)?;
+                None
+            }
+            InstKind::Data(index) => {
+                if *index as usize >= self.module.data.len() {
+                    return Err(format!("there is no string constant #{index}"));
+                }
+                Some(Type::Ptr)
+            }
+            InstKind::Call(function, arguments) => {
+                // the result of a call may be ignored
+                match (result, self.call(*function, arguments, expect)?) {
+                    (None, _) => return Ok(()),
+                    (Some(_), result) => result,
+                }
+            }
+        };
+        self.yields(inst, produced)
+    }
+
+    /// Checks the result of an instruction against the type it yields.
+    fn yields(&self, inst: &Inst, produced: Option<Type>) -> Result<(), String> {
+        match (inst.result, produced) {
+            (Some(result), Some(produced)) if self.function.ty(result) != produced => Err(format!(
+                "{result} is {}, but the instruction yields {produced}",
+                self.function.ty(result)
+            )),
+            (Some(_), None) => Err("the instruction has no result".into()),
+            (None, Some(_)) => Err("the result of the instruction is missing".into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the arguments of a call and returns the type of its result.
+    fn call(
+        &self,
+        function: FuncId,
+        arguments: &[Value],
+        expect: impl Fn(Value, Type) -> Result<(), String>,
+    ) -> Result<Option<Type>, String> {
+        let callee = self
+            .module
+            .functions
+            .get(function.index())
+            .ok_or_else(|| format!("there is no function {}", function.0))?;
+        let signature = &callee.signature;
+        if arguments.len() != signature.params.len() {
+            return Err(format!(
+                "{} takes {} arguments, {} given",
+                callee.name,
+                signature.params.len(),
+                arguments.len()
+            ));
+        }
+        for (argument, param) in arguments.iter().zip(&signature.params) {
+            expect(*argument, *param)?;
+        }
+        Ok(signature.result)
+    }
+
+    fn target(&self, target: &BlockCall) -> Result<(), String> {
+        let Some(block) = self.function.blocks.get(target.block.index()) else {
+            return Err(format!("{} does not exist", target.block));
+        };
+        if block.params.len() != target.args.len() {
+            return Err(format!(
+                "{} takes {} arguments, {} given",
+                target.block,
+                block.params.len(),
+                target.args.len()
+            ));
+        }
+        for (arg, param) in target.args.iter().zip(&block.params) {
+            let (found, expected) = (
+                self.function.types.get(arg.index()),
+                self.function.ty(*param),
+            );
+            if found != Some(&expected) {
+                return Err(format!(
+                    "{arg} is passed to {param} of {}, which is {expected}",
+                    target.block
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn terminator(&self, terminator: &Terminator) -> Result<(), String> {
+        for target in terminator.successors() {
+            self.target(target)?;
+        }
+        let ty = |value: &Value| self.function.types.get(value.index()).copied();
+        match terminator {
+            Terminator::Branch { condition, .. } if ty(condition) != Some(Type::I1) => {
+                Err(format!("the condition {condition} is not an i1"))
+            }
+            Terminator::Switch { value, cases, .. } => {
+                let Some(switched) = ty(value).filter(|ty| *ty != Type::Ptr) else {
+                    return Err(format!("{value} is not an integer"));
+                };
+                for (i, (case, _)) in cases.iter().enumerate() {
+                    if case & !switched.mask() != 0 {
+                        return Err(format!("case {case} does not fit into {switched}"));
+                    }
+                    if cases[..i].iter().any(|(other, _)| other == case) {
+                        return Err(format!("case {case} appears twice"));
+                    }
+                }
+                Ok(())
+            }
+            Terminator::Return(value) => {
+                let returned = value.map(|value| ty(&value).ok_or(value)).transpose();
+                let returned = returned.map_err(|value| format!("{value} has no type"))?;
+                if returned == self.function.signature.result {
+                    Ok(())
+                } else {
+                    Err("the returned value does not match the signature".into())
+                }
+            }
+            Terminator::Trap(index) if *index as usize >= self.module.data.len() => {
+                Err(format!("there is no string constant #{index}"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -7,12 +7,23 @@
 // #![warn(clippy::missing_docs_in_private_items)]
 #![cfg_attr(debug_assertions, allow(dead_code, unused_imports))]
 
-use std::{io, num::ParseIntError};
+use std::{io, num::ParseIntError, path::PathBuf};
 
 use thiserror::Error;
 
+use parser::tokens::Location;
+
 pub mod build_info;
+pub mod bytecode;
+pub mod codegen;
+pub mod diagnostics;
+pub mod driver;
+pub mod interpreter;
+pub mod ir;
+pub mod module;
 pub mod parser;
+pub mod sema;
+pub mod target;
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -20,12 +31,282 @@ pub enum Error {
     Io(#[from] io::Error),
     #[error(transparent)]
     ParseInt(#[from] ParseIntError),
-    #[error("Unexpected symbol {0}")]
-    UnexpectedSymbol(char),
-    #[error("Unexpected EOF")]
-    UnexpectedEOF,
-    #[error("Unexpected LF")]
-    UnexpectedLF,
-    #[error("Invalid identifier: {0}")]
-    InvalidID(String),
+    #[error("{location}: unexpected symbol {symbol}")]
+    UnexpectedSymbol { symbol: char, location: Location },
+    #[error("{location}: unexpected end of file")]
+    UnexpectedEOF { location: Location },
+    #[error("{location}: unexpected line break in a string literal")]
+    UnexpectedLF { location: Location },
+    #[error("{location}: invalid identifier {identifier}")]
+    InvalidID {
+        identifier: String,
+        location: Location,
+    },
+    #[error("{location}: invalid integer literal {literal}, {error}")]
+    InvalidInt {
+        literal: String,
+        error: ParseIntError,
+        location: Location,
+    },
+    #[error("{location}: expected {expected}, found {found}")]
+    UnexpectedToken {
+        expected: String,
+        found: String,
+        location: Location,
+    },
+    #[error("{location}: expressions and blocks nest deeper than {limit} levels")]
+    NestingTooDeep { limit: usize, location: Location },
+    #[error("{location}: no source for namespace {name}, expected {}", path.display())]
+    ModuleNotFound {
+        name: String,
+        path: PathBuf,
+        location: Location,
+    },
+    #[error("{location}: namespace {name} is defined by both {} and {}", first.display(), second.display())]
+    AmbiguousModule {
+        name: String,
+        first: PathBuf,
+        second: PathBuf,
+        location: Location,
+    },
+    #[error("Namespace cycle: {}", .0.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(" -> "))]
+    ModuleCycle(Vec<PathBuf>),
+    #[error("{location}: {name} is defined multiple times")]
+    DuplicateDefinition { name: String, location: Location },
+    #[error("{location}: cannot resolve {path}")]
+    UnresolvedPath { path: String, location: Location },
+    #[error("{location}: {path} is private")]
+    PrivateItem { path: String, location: Location },
+    #[error("{location}: import cycle while resolving {path}")]
+    ImportCycle { path: String, location: Location },
+    #[error("{location}: {path} is not a namespace")]
+    NotANamespace { path: String, location: Location },
+    #[error("{location}: cannot find {name}{}", suggestion.as_ref().map(|s| format!(", did you mean {s}?")).unwrap_or_default())]
+    UndeclaredName {
+        name: String,
+        suggestion: Option<String>,
+        location: Location,
+    },
+    #[error("{location}: {path} is not {expected}")]
+    WrongKind {
+        path: String,
+        expected: &'static str,
+        location: Location,
+    },
+    #[error("{location}: mismatched types, expected {expected}, found {found}")]
+    TypeMismatch {
+        expected: String,
+        found: String,
+        location: Location,
+    },
+    #[error("{location}: cannot apply {operator} to {operand}")]
+    InvalidOperand {
+        operator: String,
+        operand: String,
+        location: Location,
+    },
+    #[error("{location}: cannot apply {operator} to {lhs} and {rhs}")]
+    InvalidOperands {
+        operator: String,
+        lhs: String,
+        rhs: String,
+        location: Location,
+    },
+    #[error("{location}: {function} takes {expected} arguments but {found} were given")]
+    ArgumentCount {
+        function: String,
+        expected: usize,
+        found: usize,
+        location: Location,
+    },
+    #[error("{location}: {callee} is not a function")]
+    NotCallable { callee: String, location: Location },
+    #[error("{location}: {ty} has no field {field}")]
+    UnknownField {
+        ty: String,
+        field: String,
+        location: Location,
+    },
+    #[error("{location}: missing field {field} in initialiser of {ty}")]
+    MissingField {
+        ty: String,
+        field: String,
+        location: Location,
+    },
+    #[error("{location}: field {field} of {ty} is initialised twice")]
+    DuplicateField {
+        ty: String,
+        field: String,
+        location: Location,
+    },
+    #[error("{location}: {name} has {expected} fields but the pattern has {found}")]
+    PatternFieldCount {
+        name: String,
+        expected: usize,
+        found: usize,
+        location: Location,
+    },
+    #[error("{location}: match does not cover {missing}")]
+    NonExhaustive { missing: String, location: Location },
+    #[error("{location}: match arm ends without a value")]
+    ArmWithoutValue { location: Location },
+    #[error("{location}: index {index} is negative")]
+    NegativeIndex { index: i128, location: Location },
+    #[error("{location}: index {index} is out of bounds for length {length}")]
+    IndexOutOfBounds {
+        index: i128,
+        length: usize,
+        location: Location,
+    },
+    #[error("{location}: panicked: {message}")]
+    Panic { message: String, location: Location },
+    #[error("{location}: stack overflow, more than {depth} nested calls")]
+    StackOverflow { depth: usize, location: Location },
+    #[error("no function main without parameters in the root namespace")]
+    MissingMain,
+    #[error("{location}: ranges can only be iterated by for")]
+    RangeOutsideFor { location: Location },
+    #[error("{location}: the step of a range must not be zero")]
+    ZeroStep { location: Location },
+    #[error(
+        "{location}: {ty} cannot be iterated, it is not a range, an array, a string or a struct with a next method"
+    )]
+    NotIterable { ty: String, location: Location },
+    #[error("{location}: {ty} is not an iterator, next must take self: *{ty} and return Option")]
+    InvalidIterator { ty: String, location: Location },
+    #[error("{location}: expression is not a place that can be {usage}")]
+    NotAPlace {
+        usage: &'static str,
+        location: Location,
+    },
+    #[error("{location}: type annotations needed for {name}")]
+    AnnotationNeeded { name: String, location: Location },
+    #[error("{location}: cannot assign to {name}, it is immutable")]
+    ImmutableAssignment { name: String, location: Location },
+    #[error("{location}: cannot take the address of {name}, it is immutable")]
+    ImmutableBorrow { name: String, location: Location },
+    #[error("{location}: literal {value} does not fit in {ty}")]
+    LiteralOutOfRange {
+        value: i128,
+        ty: String,
+        location: Location,
+    },
+    #[error("{location}: val {name} may already be initialised")]
+    ValReassignment { name: String, location: Location },
+    #[error("{location}: {name} may be used before it is initialised")]
+    Uninitialised { name: String, location: Location },
+    #[error("{location}: no enclosing loop is labelled '{label}")]
+    UnknownLabel { label: String, location: Location },
+    #[error("{location}: {keyword} outside of a loop")]
+    OutsideLoop {
+        keyword: &'static str,
+        location: Location,
+    },
+    #[error("{location}: break with a value is only allowed in loop")]
+    BreakValueInFor { location: Location },
+    #[error("{location}: {function} does not return a value on every path")]
+    MissingReturn {
+        function: String,
+        location: Location,
+    },
+    #[error("{location}: {name} takes {expected} type arguments but {found} were given")]
+    GenericCount {
+        name: String,
+        expected: usize,
+        found: usize,
+        location: Location,
+    },
+    #[error("{location}: instantiating {name} nests type arguments without end")]
+    InstantiationDepth { name: String, location: Location },
+    #[error("{location}: {ty} has no method {method}")]
+    UnknownMethod {
+        ty: String,
+        method: String,
+        location: Location,
+    },
+    #[error("{location}: receiver must be a struct or a pointer to one, found {ty}")]
+    InvalidReceiver { ty: String, location: Location },
+    #[error("{location}: {function} must take self as its first parameter")]
+    MissingReceiver {
+        function: String,
+        location: Location,
+    },
+    #[error("{location}: receiver of an interface method must be Self or *Self, found {ty}")]
+    InterfaceReceiver { ty: String, location: Location },
+    #[error("{location}: methods of interfaces cannot have type parameters")]
+    GenericInterfaceMethod { location: Location },
+    #[error("{location}: Self is only allowed in interfaces and impl blocks")]
+    SelfOutsideImpl { location: Location },
+    #[error(
+        "{location}: interfaces can only be implemented for structs without type parameters, found {ty}"
+    )]
+    InvalidImplTarget { ty: String, location: Location },
+    #[error("{location}: {interface} is already implemented for {ty}")]
+    DuplicateImpl {
+        interface: String,
+        ty: String,
+        location: Location,
+    },
+    #[error("{location}: {method} is not a method of {interface}")]
+    NotInInterface {
+        method: String,
+        interface: String,
+        location: Location,
+    },
+    #[error(
+        "{location}: {method} does not match its declaration in {interface}, expected {expected}"
+    )]
+    ImplSignature {
+        method: String,
+        interface: String,
+        expected: String,
+        location: Location,
+    },
+    #[error("{location}: impl of {interface} for {ty} is missing {methods}")]
+    MissingMethods {
+        interface: String,
+        ty: String,
+        methods: String,
+        location: Location,
+    },
+    #[error("{location}: {ty} does not implement {interface}")]
+    UnsatisfiedBound {
+        ty: String,
+        interface: String,
+        location: Location,
+    },
+    #[error("{location}: {method} of {ty} is declared by more than one interface")]
+    AmbiguousMethod {
+        method: String,
+        ty: String,
+        location: Location,
+    },
+    #[error("{location}: {name} contains itself and has no finite size")]
+    RecursiveType { name: String, location: Location },
+    #[error("{}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n"))]
+    Multiple(Vec<Error>),
+    #[error("invalid IR in {function}: {message}")]
+    InvalidIr { function: String, message: String },
+    #[error("line {line}: {message}")]
+    IrSyntax { line: usize, message: String },
+    #[error("{tool} failed: {message}")]
+    Tool { tool: String, message: String },
+    #[error("target {triple}: {message}")]
+    Target { triple: String, message: String },
+    #[error("Usage: {0}")]
+    Usage(String),
+}
+
+#[derive(Debug, Error)]
+pub enum Warning {
+    #[error("{location}: {name} shadows the declaration at {previous}")]
+    Shadowing {
+        name: String,
+        location: Location,
+        previous: Location,
+    },
+    #[error("{location}: unreachable statement")]
+    Unreachable { location: Location },
+    #[error("{location}: unreachable pattern, earlier arms already match")]
+    UnreachablePattern { location: Location },
 }
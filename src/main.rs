@@ -7,11 +7,25 @@
 // #![warn(clippy::missing_docs_in_private_items)]
 // #![cfg_attr(debug_assertions, allow(dead_code, unused_imports))]
 
-use log::info;
+use std::{env, process::ExitCode};
 
-use cringe_lang::build_info::build_info;
+use log::{error, info};
 
-pub fn main() {
+use cringe_lang::{
+    build_info::build_info,
+    driver::{self, Options},
+};
+
+fn main() -> ExitCode {
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
     info!("{}", build_info());
+
+    match Options::parse(env::args().skip(1)).and_then(|options| driver::run(&options)) {
+        // like a process exit status, only the low byte is kept
+        Ok(status) => ExitCode::from(status.to_le_bytes()[0]),
+        Err(e) => {
+            error!("{e}");
+            ExitCode::FAILURE
+        }
+    }
 }
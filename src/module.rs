@@ -0,0 +1,437 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path as FsPath, PathBuf},
+};
+
+use crate::{
+    Error,
+    parser::{
+        Parser,
+        ast::{self, AstRoot, Item, ItemKind, Path, Visibility},
+        tokens::Location,
+    },
+};
+
+pub use loader::Loader;
+
+mod loader;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ModuleId(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DefId(pub usize);
+
+/// All namespaces of a program together with the items they define and the
+/// resolved `use` imports.
+#[derive(Debug)]
+pub struct ModuleTree {
+    pub files: Vec<PathBuf>,
+    pub modules: Vec<Module>,
+    pub definitions: Vec<Definition>,
+    pub imports: Vec<Import>,
+    pub impls: Vec<Impl>,
+}
+
+#[derive(Debug)]
+pub struct Module {
+    pub name: String,
+    pub parent: Option<ModuleId>,
+    pub items: HashMap<String, DefId>,
+    pub imports: HashMap<String, usize>,
+}
+
+#[derive(Debug)]
+pub struct Definition {
+    pub name: String,
+    pub module: ModuleId,
+    pub visibility: Visibility,
+    pub location: Location,
+    pub kind: DefKind,
+}
+
+impl Definition {
+    /// Type parameters of a generic struct, enum or function.
+    #[must_use]
+    pub fn generics(&self) -> &[ast::GenericParam] {
+        match &self.kind {
+            DefKind::Struct(structure) => &structure.generics,
+            DefKind::Enum(enumeration) => &enumeration.generics,
+            DefKind::Function(function) => &function.generics,
+            DefKind::Namespace(_) | DefKind::Interface(_) => &[],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DefKind {
+    Namespace(ModuleId),
+    Struct(ast::Struct),
+    Enum(ast::Enum),
+    Interface(ast::Interface),
+    Function(ast::Function),
+}
+
+#[derive(Debug)]
+pub struct Import {
+    pub module: ModuleId,
+    pub path: Path,
+    pub visibility: Visibility,
+    pub target: Option<DefId>,
+}
+
+/// An `impl` block, its methods are definitions of its namespace that are
+/// not visible by name.
+#[derive(Debug)]
+pub struct Impl {
+    pub module: ModuleId,
+    pub interface: Path,
+    pub target: ast::Type,
+    pub methods: Vec<DefId>,
+    pub location: Location,
+}
+
+/// Built into every program as the namespace `std`. Its imports into the
+/// root namespace are skipped for names the program defines there itself.
+/// The `extern` functions are provided by the interpreter and the backends.
+const PRELUDE: &str = "
+pub namespace std {
+    pub enum Option<T> {
+        Some(T),
+        None,
+    }
+
+    pub extern fn print(text: str);
+    pub extern fn println(text: str);
+    pub extern fn print_int(value: int);
+    pub extern fn print_uint(value: unsigned int);
+    pub extern fn alloc<T>(count: unsigned int) -> *T;
+    pub extern fn free<T>(pointer: *T);
+}
+
+use std::Option;
+";
+
+/// Name of the prelude in [`ModuleTree::files`].
+pub const PRELUDE_FILE: &str = "<prelude>";
+
+enum Lookup {
+    Found(DefId),
+    Blocked(usize),
+}
+
+impl ModuleTree {
+    pub const ROOT: ModuleId = ModuleId(0);
+
+    /// Loads the program rooted at `path` from disk.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if loading fails or the module tree is inconsistent.
+    pub fn load(path: &FsPath) -> Result<Self, Error> {
+        let (root, files) = Loader::new().load(path)?;
+        Self::build(root, files)
+    }
+
+    /// Builds the module tree from an already loaded AST and resolves all
+    /// imports.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error on duplicate definitions, unresolvable or private
+    /// imports and import cycles.
+    pub fn build(mut root: AstRoot, mut files: Vec<PathBuf>) -> Result<Self, Error> {
+        let mut prelude = Parser::new(PRELUDE.as_bytes(), files.len())
+            .with_file(FsPath::new(PRELUDE_FILE))
+            .parse()?;
+        files.push(PathBuf::from(PRELUDE_FILE));
+        let defined: HashSet<&str> = root
+            .items
+            .iter()
+            .filter_map(|item| item.kind.name())
+            .collect();
+        prelude.items.retain(|item| match &item.kind {
+            ItemKind::Use(import) => !defined.contains(import.path.name()),
+            _ => true,
+        });
+        root.items.append(&mut prelude.items);
+
+        let mut tree = Self {
+            files,
+            modules: vec![Module {
+                name: String::new(),
+                parent: None,
+                items: HashMap::new(),
+                imports: HashMap::new(),
+            }],
+            definitions: Vec::new(),
+            imports: Vec::new(),
+            impls: Vec::new(),
+        };
+        tree.collect(Self::ROOT, root.items)?;
+        tree.resolve_imports()?;
+        Ok(tree)
+    }
+
+    #[must_use]
+    pub fn module(&self, id: ModuleId) -> &Module {
+        &self.modules[id.0]
+    }
+
+    #[must_use]
+    pub fn definition(&self, id: DefId) -> &Definition {
+        &self.definitions[id.0]
+    }
+
+    /// Fully qualified name of a module, e.g. `a::b`.
+    #[must_use]
+    pub fn module_path(&self, id: ModuleId) -> String {
+        let mut segments = Vec::new();
+        let mut current = Some(id);
+        while let Some(id) = current {
+            let module = self.module(id);
+            if module.parent.is_some() {
+                segments.push(module.name.as_str());
+            }
+            current = module.parent;
+        }
+        segments.reverse();
+        segments.join("::")
+    }
+
+    /// Fully qualified name of a definition, e.g. `a::b::Point`.
+    #[must_use]
+    pub fn qualified_name(&self, id: DefId) -> String {
+        let definition = self.definition(id);
+        let module = self.module_path(definition.module);
+        if module.is_empty() {
+            definition.name.clone()
+        } else {
+            format!("{module}::{}", definition.name)
+        }
+    }
+
+    /// Returns `true` if `module` is `ancestor` or nested inside of it.
+    #[must_use]
+    pub fn is_within(&self, module: ModuleId, ancestor: ModuleId) -> bool {
+        let mut current = Some(module);
+        while let Some(id) = current {
+            if id == ancestor {
+                return true;
+            }
+            current = self.module(id).parent;
+        }
+        false
+    }
+
+    /// The item `name` of the prelude namespace `std`.
+    #[must_use]
+    pub fn prelude(&self, name: &str) -> Option<DefId> {
+        let std = self.module(Self::ROOT).items.get("std")?;
+        let DefKind::Namespace(module) = self.definition(*std).kind else {
+            return None;
+        };
+        self.module(module).items.get(name).copied()
+    }
+
+    /// Resolves a qualified path as seen from `from`.
+    ///
+    /// The first segment is looked up in `from` and then in its enclosing
+    /// namespaces, every further segment must name an item that is visible
+    /// from `from`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path does not resolve or crosses a private item.
+    pub fn resolve_path(&self, from: ModuleId, path: &Path) -> Result<DefId, Error> {
+        match self.lookup(from, path)? {
+            Lookup::Found(id) => Ok(id),
+            Lookup::Blocked(_) => unreachable!("imports are resolved on construction"),
+        }
+    }
+
+    fn collect(&mut self, module: ModuleId, items: Vec<Item>) -> Result<(), Error> {
+        for item in items {
+            if let ItemKind::Impl(block) = item.kind {
+                self.collect_impl(module, block, item.location);
+                continue;
+            }
+            let name = match &item.kind {
+                ItemKind::Namespace(namespace) => namespace.name.clone(),
+                ItemKind::Struct(structure) => structure.name.clone(),
+                ItemKind::Enum(enumeration) => enumeration.name.clone(),
+                ItemKind::Interface(interface) => interface.name.clone(),
+                ItemKind::Function(function) => function.name.clone(),
+                ItemKind::Use(import) => import.path.name().to_string(),
+                ItemKind::Impl(_) => unreachable!("impl blocks are collected above"),
+            };
+
+            // methods belong to their receiver's struct, not to the namespace
+            let method =
+                matches!(&item.kind, ItemKind::Function(function) if function.receiver.is_some());
+            let current = &self.modules[module.0];
+            if !method && (current.items.contains_key(&name) || current.imports.contains_key(&name))
+            {
+                return Err(Error::DuplicateDefinition {
+                    name,
+                    location: item.location,
+                });
+            }
+
+            let kind = match item.kind {
+                ItemKind::Namespace(namespace) => {
+                    let id = ModuleId(self.modules.len());
+                    self.modules.push(Module {
+                        name: namespace.name,
+                        parent: Some(module),
+                        items: HashMap::new(),
+                        imports: HashMap::new(),
+                    });
+                    self.collect(id, namespace.items)?;
+                    DefKind::Namespace(id)
+                }
+                ItemKind::Struct(structure) => DefKind::Struct(structure),
+                ItemKind::Enum(enumeration) => DefKind::Enum(enumeration),
+                ItemKind::Interface(interface) => DefKind::Interface(interface),
+                ItemKind::Function(function) => DefKind::Function(function),
+                ItemKind::Use(import) => {
+                    let index = self.imports.len();
+                    self.imports.push(Import {
+                        module,
+                        path: import.path,
+                        visibility: item.visibility,
+                        target: None,
+                    });
+                    self.modules[module.0].imports.insert(name, index);
+                    continue;
+                }
+                ItemKind::Impl(_) => unreachable!("impl blocks are collected above"),
+            };
+
+            let id = DefId(self.definitions.len());
+            self.definitions.push(Definition {
+                name: name.clone(),
+                module,
+                visibility: item.visibility,
+                location: item.location,
+                kind,
+            });
+            if !method {
+                self.modules[module.0].items.insert(name, id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the methods of an `impl` block as public definitions, they are
+    /// reached through the struct they belong to.
+    fn collect_impl(&mut self, module: ModuleId, block: ast::Impl, location: Location) {
+        let mut methods = Vec::new();
+        for method in block.methods {
+            methods.push(DefId(self.definitions.len()));
+            self.definitions.push(Definition {
+                name: method.function.name.clone(),
+                module,
+                visibility: Visibility::Public,
+                location: method.location,
+                kind: DefKind::Function(method.function),
+            });
+        }
+        self.impls.push(Impl {
+            module,
+            interface: block.interface,
+            target: block.target,
+            methods,
+            location,
+        });
+    }
+
+    fn resolve_imports(&mut self) -> Result<(), Error> {
+        for index in 0..self.imports.len() {
+            let mut stack = vec![index];
+            while let Some(&top) = stack.last() {
+                if self.imports[top].target.is_some() {
+                    stack.pop();
+                    continue;
+                }
+
+                let import = &self.imports[top];
+                match self.lookup(import.module, &import.path)? {
+                    Lookup::Found(id) => {
+                        self.imports[top].target = Some(id);
+                        stack.pop();
+                    }
+                    Lookup::Blocked(next) if stack.contains(&next) => {
+                        return Err(Error::ImportCycle {
+                            path: import.path.to_string(),
+                            location: import.path.location,
+                        });
+                    }
+                    Lookup::Blocked(next) => stack.push(next),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn lookup(&self, from: ModuleId, path: &Path) -> Result<Lookup, Error> {
+        let unresolved = || Error::UnresolvedPath {
+            path: path.to_string(),
+            location: path.location,
+        };
+
+        let mut segments = path.segments.iter();
+        let first = segments.next().ok_or_else(unresolved)?;
+
+        let mut scope = Some(from);
+        let mut current = loop {
+            let Some(module) = scope else {
+                return Err(unresolved());
+            };
+            match self.lookup_in(module, first) {
+                Some((entry, _)) => break entry,
+                None => scope = self.module(module).parent,
+            }
+        };
+
+        for segment in segments {
+            let id = match current {
+                Lookup::Found(id) => id,
+                blocked @ Lookup::Blocked(_) => return Ok(blocked),
+            };
+            let DefKind::Namespace(module) = self.definition(id).kind else {
+                return Err(Error::NotANamespace {
+                    path: self.qualified_name(id),
+                    location: path.location,
+                });
+            };
+
+            let (entry, visibility) = self.lookup_in(module, segment).ok_or_else(unresolved)?;
+            if visibility == Visibility::Private && !self.is_within(from, module) {
+                return Err(Error::PrivateItem {
+                    path: path.to_string(),
+                    location: path.location,
+                });
+            }
+            current = entry;
+        }
+
+        Ok(current)
+    }
+
+    fn lookup_in(&self, module: ModuleId, name: &str) -> Option<(Lookup, Visibility)> {
+        let module = self.module(module);
+        if let Some(&id) = module.items.get(name) {
+            return Some((Lookup::Found(id), self.definition(id).visibility));
+        }
+        module.imports.get(name).map(|&index| {
+            let import = &self.imports[index];
+            let entry = import.target.map_or(Lookup::Blocked(index), Lookup::Found);
+            (entry, import.visibility)
+        })
+    }
+}
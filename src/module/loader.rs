@@ -0,0 +1,122 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use log::debug;
+
+use crate::{
+    Error,
+    parser::{
+        Parser,
+        ast::{AstRoot, Item, ItemKind},
+    },
+};
+
+pub const SOURCE_EXTENSION: &str = "cringe";
+const MODULE_FILE: &str = "mod.cringe";
+
+/// Loads a source file and every file it pulls in via `namespace name;`.
+///
+/// A declaration `namespace foo;` in a file whose submodules live in `dir`
+/// loads either `dir/foo.cringe` or `dir/foo/mod.cringe`. The root file and
+/// `mod.cringe` files keep their submodules next to them, every other file
+/// `bar.cringe` keeps them in `bar/`.
+pub struct Loader {
+    files: Vec<PathBuf>,
+    stack: Vec<PathBuf>,
+}
+
+impl Loader {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            files: Vec::new(),
+            stack: Vec::new(),
+        }
+    }
+
+    /// Loads `root` and all namespaces declared in external files.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be read or parsed, a namespace file
+    /// is missing or ambiguous, or files include each other.
+    pub fn load(mut self, root: &Path) -> Result<(AstRoot, Vec<PathBuf>), Error> {
+        let dir = root.parent().map(Path::to_path_buf).unwrap_or_default();
+        let items = self.load_file(root, &dir)?;
+        Ok((AstRoot { items }, self.files))
+    }
+
+    fn load_file(&mut self, path: &Path, dir: &Path) -> Result<Vec<Item>, Error> {
+        let canonical = fs::canonicalize(path)?;
+        if let Some(start) = self.stack.iter().position(|p| *p == canonical) {
+            let mut cycle = self.stack[start..].to_vec();
+            cycle.push(canonical);
+            return Err(Error::ModuleCycle(cycle));
+        }
+
+        let fid = self.files.len();
+        self.files.push(path.to_path_buf());
+        debug!("loading {} as file {fid}", path.display());
+
+        let mut parser = Parser::new(BufReader::new(File::open(path)?), fid).with_file(path);
+        let mut items = parser.parse()?.items;
+
+        self.stack.push(canonical);
+        self.load_items(&mut items, dir)?;
+        self.stack.pop();
+
+        Ok(items)
+    }
+
+    fn load_items(&mut self, items: &mut [Item], dir: &Path) -> Result<(), Error> {
+        for item in items {
+            let ItemKind::Namespace(namespace) = &mut item.kind else {
+                continue;
+            };
+
+            let sub_dir = dir.join(&namespace.name);
+            if !namespace.external {
+                self.load_items(&mut namespace.items, &sub_dir)?;
+                continue;
+            }
+
+            let file = dir.join(format!("{}.{SOURCE_EXTENSION}", namespace.name));
+            let module_file = sub_dir.join(MODULE_FILE);
+            let path = match (file.is_file(), module_file.is_file()) {
+                (true, false) => file,
+                (false, true) => module_file,
+                (true, true) => {
+                    return Err(Error::AmbiguousModule {
+                        name: namespace.name.clone(),
+                        first: file,
+                        second: module_file,
+                        location: item.location,
+                    });
+                }
+                (false, false) => {
+                    return Err(Error::ModuleNotFound {
+                        name: namespace.name.clone(),
+                        path: file,
+                        location: item.location,
+                    });
+                }
+            };
+
+            namespace.items = self.load_file(&path, &sub_dir)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Loader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -2,135 +2,1060 @@
  * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
  */
 
-use std::{collections::VecDeque, io::BufRead};
+use std::{collections::VecDeque, io::BufRead, path::Path as FsPath};
 
-use ast::AstRoot;
+use ast::{
+    ArmBody, AstRoot, BaseType, Declaration, Enum, Expression, ExpressionKind, FieldInit,
+    FieldPattern, For, Function, GenericParam, If, Impl, Interface, Item, ItemKind, Literal,
+    MatchArm, Member, Method, Namespace, Path, Pattern, PatternKind, Statement, StatementKind,
+    Struct, Type, Use, Variant, VariantFields, Visibility,
+};
 use lexer::Lexer;
-use tokens::Token;
+use precedence::{Fixity, PostfixOperator};
+use tokens::{Location, RichToken, Token};
 
 use crate::Error;
 
 pub mod ast;
 mod lexer;
+pub mod precedence;
 pub mod tokens;
 
+/// How deeply expressions and blocks may nest. The passes after the parser
+/// walk the tree recursively and need a stack frame per level.
+pub const MAX_NESTING: usize = 512;
+
 pub struct Parser<R: BufRead> {
     lexer: Lexer<R>,
-    token_buffer: VecDeque<Token>,
-    root: AstRoot,
+    token_buffer: VecDeque<RichToken>,
+    eof: bool,
+    restrict_struct_init: bool,
+    /// Expressions and blocks around the current token.
+    depth: usize,
 }
 
 impl<R: BufRead> Parser<R> {
-    pub fn parse(&mut self) -> Result<(), Error> {
-        todo!()
+    pub fn new(source: R, fid: usize) -> Self {
+        Self {
+            lexer: Lexer::new(source, fid),
+            token_buffer: VecDeque::new(),
+            eof: false,
+            restrict_struct_init: false,
+            depth: 0,
+        }
     }
 
-    fn parse_scope(&mut self) -> Result<(), Error> {
-        todo!()
+    /// Shows the locations of the parsed program with `path`.
+    #[must_use]
+    pub fn with_file(self, path: &FsPath) -> Self {
+        Self {
+            lexer: self.lexer.with_file(path),
+            ..self
+        }
     }
 
-    fn parse_struct(&mut self) -> Result<(), Error> {
-        todo!()
+    /// Parses a whole source file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source cannot be read or is not a valid program.
+    pub fn parse(&mut self) -> Result<AstRoot, Error> {
+        let mut items = Vec::new();
+        while self.peek()?.is_some() {
+            items.push(self.parse_item()?);
+        }
+        Ok(AstRoot { items })
     }
 
-    fn parse_function(&mut self) -> Result<(), Error> {
-        todo!()
+    fn parse_item(&mut self) -> Result<Item, Error> {
+        let location = self.location()?;
+        let visibility = if self.eat(&Token::Pub)? {
+            Visibility::Public
+        } else {
+            Visibility::Private
+        };
+
+        let kind = match self.peek()? {
+            Some(Token::Namespace) => ItemKind::Namespace(self.parse_scope()?),
+            Some(Token::Struct) => ItemKind::Struct(self.parse_struct()?),
+            Some(Token::Enum) => ItemKind::Enum(self.parse_enum()?),
+            Some(Token::Interface) => ItemKind::Interface(self.parse_interface()?),
+            Some(Token::Impl) => ItemKind::Impl(self.parse_impl()?),
+            Some(Token::Fn) => ItemKind::Function(self.parse_function(true)?),
+            Some(Token::Extern) => ItemKind::Function(self.parse_extern()?),
+            Some(Token::Use) => ItemKind::Use(self.parse_use()?),
+            _ => return Err(self.unexpected("item")?),
+        };
+
+        Ok(Item {
+            visibility,
+            kind,
+            location,
+        })
     }
 
-    fn parse_member(&mut self) -> Result<(), Error> {
-        todo!()
+    fn parse_scope(&mut self) -> Result<Namespace, Error> {
+        self.expect(&Token::Namespace)?;
+        let name = self.parse_identifier()?;
+
+        if self.eat(&Token::SemiColon)? {
+            return Ok(Namespace {
+                name,
+                items: Vec::new(),
+                external: true,
+            });
+        }
+
+        self.expect(&Token::LBrace)?;
+        let mut items = Vec::new();
+        while !self.eat(&Token::RBrace)? {
+            items.push(self.parse_item()?);
+        }
+
+        Ok(Namespace {
+            name,
+            items,
+            external: false,
+        })
     }
 
-    fn parse_type(&mut self) -> Result<(), Error> {
-        todo!()
+    fn parse_struct(&mut self) -> Result<Struct, Error> {
+        self.expect(&Token::Struct)?;
+        let name = self.parse_identifier()?;
+        let generics = self.parse_generic_params(false)?;
+        self.expect(&Token::LBrace)?;
+
+        let mut members = vec![self.parse_member()?];
+        while self.eat(&Token::Comma)? {
+            if self.peek()? == Some(&Token::RBrace) {
+                break;
+            }
+            members.push(self.parse_member()?);
+        }
+        self.expect(&Token::RBrace)?;
+
+        Ok(Struct {
+            name,
+            generics,
+            members,
+        })
     }
 
-    fn parse_identifier(&mut self) -> Result<(), Error> {
-        todo!()
+    fn parse_enum(&mut self) -> Result<Enum, Error> {
+        self.expect(&Token::Enum)?;
+        let name = self.parse_identifier()?;
+        let generics = self.parse_generic_params(false)?;
+        self.expect(&Token::LBrace)?;
+
+        let mut variants = Vec::new();
+        while !self.eat(&Token::RBrace)? {
+            variants.push(self.parse_variant()?);
+            if !self.eat(&Token::Comma)? {
+                self.expect(&Token::RBrace)?;
+                break;
+            }
+        }
+
+        Ok(Enum {
+            name,
+            generics,
+            variants,
+        })
     }
 
-    fn parse_literal(&mut self) -> Result<(), Error> {
-        todo!()
+    fn parse_variant(&mut self) -> Result<Variant, Error> {
+        let location = self.location()?;
+        let name = self.parse_identifier()?;
+        let fields = if self.eat(&Token::LPar)? {
+            let mut types = vec![self.parse_type()?];
+            while self.eat(&Token::Comma)? {
+                if self.peek()? == Some(&Token::RPar) {
+                    break;
+                }
+                types.push(self.parse_type()?);
+            }
+            self.expect(&Token::RPar)?;
+            VariantFields::Tuple(types)
+        } else if self.eat(&Token::LBrace)? {
+            let mut members = vec![self.parse_member()?];
+            while self.eat(&Token::Comma)? {
+                if self.peek()? == Some(&Token::RBrace) {
+                    break;
+                }
+                members.push(self.parse_member()?);
+            }
+            self.expect(&Token::RBrace)?;
+            VariantFields::Named(members)
+        } else {
+            VariantFields::Unit
+        };
+
+        Ok(Variant {
+            name,
+            fields,
+            location,
+        })
     }
-    fn parse_body(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_interface(&mut self) -> Result<Interface, Error> {
+        self.expect(&Token::Interface)?;
+        let name = self.parse_identifier()?;
+        self.expect(&Token::LBrace)?;
+
+        let mut methods = Vec::new();
+        while !self.eat(&Token::RBrace)? {
+            let location = self.location()?;
+            let function = self.parse_function(false)?;
+            self.expect(&Token::SemiColon)?;
+            methods.push(Method { function, location });
+        }
+
+        Ok(Interface { name, methods })
     }
-    fn parse_statement(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_impl(&mut self) -> Result<Impl, Error> {
+        self.expect(&Token::Impl)?;
+        let interface = self.parse_path()?;
+        self.expect(&Token::For)?;
+        let target = self.parse_type()?;
+        self.expect(&Token::LBrace)?;
+
+        let mut methods = Vec::new();
+        while !self.eat(&Token::RBrace)? {
+            let location = self.location()?;
+            let function = self.parse_function(true)?;
+            methods.push(Method { function, location });
+        }
+
+        Ok(Impl {
+            interface,
+            target,
+            methods,
+        })
     }
-    fn parse_compound(&mut self) -> Result<(), Error> {
-        todo!()
+
+    /// Parses a function, without `body` only up to its return type.
+    fn parse_function(&mut self, body: bool) -> Result<Function, Error> {
+        self.expect(&Token::Fn)?;
+        let name = self.parse_identifier()?;
+        let generics = self.parse_generic_params(true)?;
+        self.expect(&Token::LPar)?;
+
+        let mut receiver = None;
+        let mut parameters = Vec::new();
+        if self.peek()? == Some(&Token::SelfKey) {
+            let location = self.expect(&Token::SelfKey)?;
+            self.expect(&Token::Colon)?;
+            receiver = Some(Box::new(Member {
+                name: "self".to_string(),
+                ty: self.parse_type()?,
+                location,
+            }));
+        } else if self.peek()? != Some(&Token::RPar) {
+            parameters.push(self.parse_member()?);
+        }
+        while self.eat(&Token::Comma)? {
+            parameters.push(self.parse_member()?);
+        }
+        self.expect(&Token::RPar)?;
+
+        let return_type = if self.eat(&Token::Arrow)? {
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+
+        let body = if body { self.parse_body()? } else { Vec::new() };
+
+        Ok(Function {
+            name,
+            generics,
+            receiver,
+            parameters,
+            return_type,
+            body,
+            external: false,
+        })
     }
-    fn parse_if(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_extern(&mut self) -> Result<Function, Error> {
+        self.expect(&Token::Extern)?;
+        let mut function = self.parse_function(false)?;
+        self.expect(&Token::SemiColon)?;
+        function.external = true;
+        Ok(function)
     }
-    fn parse_loop(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_use(&mut self) -> Result<Use, Error> {
+        self.expect(&Token::Use)?;
+        let path = self.parse_path()?;
+        self.expect(&Token::SemiColon)?;
+        Ok(Use { path })
     }
-    fn parse_for(&mut self) -> Result<(), Error> {
-        todo!()
+
+    /// Parses `<A, B>`, with `bounds` also `<A: Interface + Other>`.
+    fn parse_generic_params(&mut self, bounds: bool) -> Result<Vec<GenericParam>, Error> {
+        let mut generics = Vec::new();
+        if !self.eat(&Token::LAngle)? {
+            return Ok(generics);
+        }
+        loop {
+            let location = self.location()?;
+            let name = self.parse_identifier()?;
+            let mut paths = Vec::new();
+            if bounds && self.eat(&Token::Colon)? {
+                paths.push(self.parse_path()?);
+                while self.eat(&Token::Plus)? {
+                    paths.push(self.parse_path()?);
+                }
+            }
+            generics.push(GenericParam {
+                name,
+                bounds: paths,
+                location,
+            });
+            if !self.eat(&Token::Comma)? {
+                break;
+            }
+        }
+        self.expect_closing_angle()?;
+        Ok(generics)
     }
-    fn parse_return(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_generic_args(&mut self) -> Result<Vec<Type>, Error> {
+        self.expect(&Token::LAngle)?;
+        let mut generics = vec![self.parse_type()?];
+        while self.eat(&Token::Comma)? {
+            generics.push(self.parse_type()?);
+        }
+        self.expect_closing_angle()?;
+        Ok(generics)
     }
-    fn parse_break(&mut self) -> Result<(), Error> {
-        todo!()
+
+    /// Expects a `>`, splitting `>>` and `>=` as in `Vec<Vec<int>>`.
+    fn expect_closing_angle(&mut self) -> Result<(), Error> {
+        let rest = match self.peek()? {
+            Some(Token::ShiftRight) => Token::RAngle,
+            Some(Token::Geq) => Token::Equal,
+            _ => return self.expect(&Token::RAngle).map(|_| ()),
+        };
+        let front = self
+            .token_buffer
+            .front_mut()
+            .expect("peek filled the buffer");
+        front.token = rest;
+        front.location.c += 1;
+        Ok(())
     }
-    fn parse_continue(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_member(&mut self) -> Result<Member, Error> {
+        let location = self.location()?;
+        let name = self.parse_identifier()?;
+        self.expect(&Token::Colon)?;
+        let ty = self.parse_type()?;
+        Ok(Member { name, ty, location })
     }
-    fn parse_var_decl(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_type(&mut self) -> Result<Type, Error> {
+        let location = self.location()?;
+
+        let mut pointer = 0;
+        while self.eat(&Token::Asterix)? {
+            pointer += 1;
+        }
+
+        let base = match self.peek()? {
+            Some(Token::Identifier(_)) => {
+                let mut path = self.parse_path()?;
+                if path.generics.is_empty() && self.peek()? == Some(&Token::LAngle) {
+                    path.generics = self.parse_generic_args()?;
+                }
+                BaseType::Named(path)
+            }
+            Some(Token::Unsigned) => {
+                self.next()?;
+                self.expect(&Token::IntKey)?;
+                BaseType::Int { unsigned: true }
+            }
+            Some(Token::IntKey) => {
+                self.next()?;
+                BaseType::Int { unsigned: false }
+            }
+            Some(Token::StrKey) => {
+                self.next()?;
+                BaseType::Str
+            }
+            Some(Token::BoolKey) => {
+                self.next()?;
+                BaseType::Bool
+            }
+            Some(Token::SelfType) => {
+                self.next()?;
+                BaseType::SelfType
+            }
+            _ => return Err(self.unexpected("type")?),
+        };
+
+        let mut array = 0;
+        while self.eat(&Token::LBracket)? {
+            self.expect(&Token::RBracket)?;
+            array += 1;
+        }
+
+        Ok(Type {
+            pointer,
+            base,
+            array,
+            location,
+        })
     }
-    fn parse_val_decl(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_path(&mut self) -> Result<Path, Error> {
+        let location = self.location()?;
+        let mut segments = vec![self.parse_identifier()?];
+        let mut generics = Vec::new();
+        while self.eat(&Token::PathSeparator)? {
+            // `::<` starts the type arguments, so that `a < b` stays a comparison
+            if self.peek()? == Some(&Token::LAngle) {
+                generics = self.parse_generic_args()?;
+                break;
+            }
+            segments.push(self.parse_identifier()?);
+        }
+        Ok(Path {
+            segments,
+            generics,
+            location,
+        })
     }
-    fn parse_assing(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_identifier(&mut self) -> Result<String, Error> {
+        match self.peek()? {
+            Some(Token::Identifier(_)) => {
+                let Token::Identifier(id) = self.next()?.token else {
+                    unreachable!()
+                };
+                Ok(id)
+            }
+            _ => Err(self.unexpected("identifier")?),
+        }
     }
-    fn parse_expression(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_label(&mut self) -> Result<Option<String>, Error> {
+        if self.eat(&Token::LableDecl)? {
+            Ok(Some(self.parse_identifier()?))
+        } else {
+            Ok(None)
+        }
     }
-    fn parse_logical_or(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_literal(&mut self) -> Result<Literal, Error> {
+        let literal = match self.peek()? {
+            Some(Token::Int(_) | Token::Str(_) | Token::Bool(_)) => self.next()?.token,
+            _ => return Err(self.unexpected("literal")?),
+        };
+        Ok(match literal {
+            Token::Int(int) => Literal::Int(int),
+            Token::Str(str) => Literal::Str(str),
+            Token::Bool(bool) => Literal::Bool(bool),
+            _ => unreachable!(),
+        })
     }
-    fn parse_logical_and(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_body(&mut self) -> Result<Vec<Statement>, Error> {
+        let location = self.expect(&Token::LBrace)?;
+        self.nest(location)?;
+        let restrict = std::mem::replace(&mut self.restrict_struct_init, false);
+        let mut body = Vec::new();
+        while !self.eat(&Token::RBrace)? {
+            body.push(self.parse_statement()?);
+        }
+        self.restrict_struct_init = restrict;
+        self.depth -= 1;
+        Ok(body)
     }
-    fn parse_binary_or(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_statement(&mut self) -> Result<Statement, Error> {
+        let location = self.location()?;
+        let kind = match self.peek()? {
+            Some(Token::LBrace) => self.parse_compound()?,
+            Some(Token::If) => StatementKind::If(self.parse_if()?),
+            Some(Token::For) => StatementKind::For(self.parse_for(None)?),
+            Some(Token::Loop) => {
+                let expression = self.parse_loop(None)?;
+                self.eat(&Token::SemiColon)?;
+                StatementKind::Expression(expression)
+            }
+            Some(Token::Match) => {
+                let expression = self.parse_match()?;
+                self.eat(&Token::SemiColon)?;
+                StatementKind::Expression(expression)
+            }
+            Some(Token::LableDecl) => {
+                let label = self.parse_label()?;
+                self.expect(&Token::Colon)?;
+                if self.peek()? == Some(&Token::For) {
+                    StatementKind::For(self.parse_for(label)?)
+                } else {
+                    let expression = self.parse_loop(label)?;
+                    self.eat(&Token::SemiColon)?;
+                    StatementKind::Expression(expression)
+                }
+            }
+            Some(Token::Return) => self.parse_return()?,
+            Some(Token::Break) => self.parse_break()?,
+            Some(Token::Continue) => self.parse_continue()?,
+            Some(Token::Var) => self.parse_var_decl()?,
+            Some(Token::Val) => self.parse_val_decl()?,
+            _ => self.parse_assing()?,
+        };
+        Ok(Statement { kind, location })
     }
-    fn parse_binary_xor(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_compound(&mut self) -> Result<StatementKind, Error> {
+        Ok(StatementKind::Compound(self.parse_body()?))
     }
-    fn parse_binary_and(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_if(&mut self) -> Result<If, Error> {
+        self.expect(&Token::If)?;
+        let condition = self.parse_restricted_expression()?;
+        let then = self.parse_body()?;
+
+        let otherwise = if self.eat(&Token::Else)? {
+            if self.peek()? == Some(&Token::If) {
+                let location = self.location()?;
+                let kind = StatementKind::If(self.parse_if()?);
+                Some(vec![Statement { kind, location }])
+            } else {
+                Some(self.parse_body()?)
+            }
+        } else {
+            None
+        };
+
+        Ok(If {
+            condition,
+            then,
+            otherwise,
+        })
     }
-    fn parse_equal_rel(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_loop(&mut self, label: Option<String>) -> Result<Expression, Error> {
+        let location = self.expect(&Token::Loop)?;
+        let body = self.parse_body()?;
+        Ok(Expression {
+            kind: ExpressionKind::Loop { label, body },
+            location,
+        })
     }
-    fn parse_ord_rel(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_match(&mut self) -> Result<Expression, Error> {
+        let location = self.expect(&Token::Match)?;
+        let scrutinee = self.parse_restricted_expression()?;
+        self.expect(&Token::LBrace)?;
+        let restrict = std::mem::replace(&mut self.restrict_struct_init, false);
+
+        let mut arms = Vec::new();
+        while !self.eat(&Token::RBrace)? {
+            let location = self.location()?;
+            let pattern = self.parse_pattern()?;
+            let guard = if self.eat(&Token::If)? {
+                Some(self.parse_expression()?)
+            } else {
+                None
+            };
+            self.expect(&Token::FatArrow)?;
+
+            // a block needs no comma, an expression does unless it is last
+            let body = if self.peek()? == Some(&Token::LBrace) {
+                let body = ArmBody::Block(self.parse_body()?);
+                self.eat(&Token::Comma)?;
+                body
+            } else {
+                let body = ArmBody::Expression(self.parse_expression()?);
+                if !self.eat(&Token::Comma)? && self.peek()? != Some(&Token::RBrace) {
+                    return Err(self.unexpected(",")?);
+                }
+                body
+            };
+            arms.push(MatchArm {
+                pattern,
+                guard,
+                body,
+                location,
+            });
+        }
+
+        self.restrict_struct_init = restrict;
+        Ok(Expression {
+            kind: ExpressionKind::Match {
+                scrutinee: Box::new(scrutinee),
+                arms,
+            },
+            location,
+        })
     }
-    fn parse_bit_op(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_pattern(&mut self) -> Result<Pattern, Error> {
+        let location = self.location()?;
+        let path = matches!(
+            self.peek_nth(1)?,
+            Some(Token::PathSeparator | Token::LPar | Token::LBrace)
+        );
+        let kind = match self.peek()? {
+            Some(Token::Identifier(_)) if !path => {
+                let name = self.parse_identifier()?;
+                if name == "_" {
+                    PatternKind::Wildcard
+                } else {
+                    PatternKind::Binding(name)
+                }
+            }
+            Some(Token::Identifier(_)) => {
+                let path = self.parse_path()?;
+                if self.eat(&Token::LPar)? {
+                    let mut patterns = Vec::new();
+                    while !self.eat(&Token::RPar)? {
+                        patterns.push(self.parse_pattern()?);
+                        if !self.eat(&Token::Comma)? {
+                            self.expect(&Token::RPar)?;
+                            break;
+                        }
+                    }
+                    PatternKind::Tuple(path, patterns)
+                } else if self.eat(&Token::LBrace)? {
+                    self.parse_field_patterns(path)?
+                } else {
+                    PatternKind::Tuple(path, Vec::new())
+                }
+            }
+            Some(Token::Dash) => {
+                self.next()?;
+                let literal @ Literal::Int(_) = self.parse_literal()? else {
+                    return Err(Error::UnexpectedToken {
+                        expected: "integer".to_string(),
+                        found: "literal".to_string(),
+                        location,
+                    });
+                };
+                PatternKind::Literal {
+                    literal,
+                    negative: true,
+                }
+            }
+            _ => PatternKind::Literal {
+                literal: self.parse_literal()?,
+                negative: false,
+            },
+        };
+        Ok(Pattern { kind, location })
     }
-    fn parse_sub_add(&mut self) -> Result<(), Error> {
-        todo!()
+
+    /// Parses the fields of a struct pattern after the `{`.
+    fn parse_field_patterns(&mut self, path: Path) -> Result<PatternKind, Error> {
+        let mut fields = Vec::new();
+        let mut rest = false;
+        while !self.eat(&Token::RBrace)? {
+            if self.eat(&Token::DotDot)? {
+                rest = true;
+                self.expect(&Token::RBrace)?;
+                break;
+            }
+            let location = self.location()?;
+            let name = self.parse_identifier()?;
+            let pattern = if self.eat(&Token::Colon)? {
+                self.parse_pattern()?
+            } else {
+                Pattern {
+                    kind: PatternKind::Binding(name.clone()),
+                    location,
+                }
+            };
+            fields.push(FieldPattern { name, pattern });
+            if !self.eat(&Token::Comma)? {
+                self.expect(&Token::RBrace)?;
+                break;
+            }
+        }
+        Ok(PatternKind::Struct { path, fields, rest })
     }
-    fn parse_mul_div(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_for(&mut self, label: Option<String>) -> Result<For, Error> {
+        self.expect(&Token::For)?;
+        let binding_location = self.location()?;
+        let binding = self.parse_identifier()?;
+        self.expect(&Token::In)?;
+        let iterable = self.parse_restricted_expression()?;
+        let body = self.parse_body()?;
+
+        Ok(For {
+            label,
+            binding,
+            binding_location,
+            iterable,
+            body,
+        })
     }
-    fn parse_unary_op(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_return(&mut self) -> Result<StatementKind, Error> {
+        self.expect(&Token::Return)?;
+        let value = self.parse_optional_expression()?;
+        self.expect(&Token::SemiColon)?;
+        Ok(StatementKind::Return(value))
+    }
+
+    fn parse_break(&mut self) -> Result<StatementKind, Error> {
+        self.expect(&Token::Break)?;
+        let label = self.parse_label()?;
+        let value = self.parse_optional_expression()?;
+        self.expect(&Token::SemiColon)?;
+        Ok(StatementKind::Break { label, value })
+    }
+
+    fn parse_continue(&mut self) -> Result<StatementKind, Error> {
+        self.expect(&Token::Continue)?;
+        let label = self.parse_label()?;
+        self.expect(&Token::SemiColon)?;
+        Ok(StatementKind::Continue { label })
+    }
+
+    fn parse_var_decl(&mut self) -> Result<StatementKind, Error> {
+        self.expect(&Token::Var)?;
+        let location = self.location()?;
+        let name = self.parse_identifier()?;
+        let ty = if self.eat(&Token::Colon)? {
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+        let value = self.parse_initializer()?;
+        self.expect(&Token::SemiColon)?;
+        Ok(StatementKind::VarDecl(Declaration {
+            name,
+            location,
+            ty,
+            value,
+        }))
+    }
+
+    fn parse_val_decl(&mut self) -> Result<StatementKind, Error> {
+        self.expect(&Token::Val)?;
+        let location = self.location()?;
+        let name = self.parse_identifier()?;
+        self.expect(&Token::Colon)?;
+        let ty = Some(self.parse_type()?);
+        let value = self.parse_initializer()?;
+        self.expect(&Token::SemiColon)?;
+        Ok(StatementKind::ValDecl(Declaration {
+            name,
+            location,
+            ty,
+            value,
+        }))
+    }
+
+    fn parse_initializer(&mut self) -> Result<Option<Expression>, Error> {
+        if self.eat(&Token::Equal)? {
+            Ok(Some(self.parse_expression()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_assing(&mut self) -> Result<StatementKind, Error> {
+        let target = self.parse_expression()?;
+        let kind = if self.eat(&Token::Equal)? {
+            let value = self.parse_expression()?;
+            StatementKind::Assign { target, value }
+        } else {
+            StatementKind::Expression(target)
+        };
+        self.expect(&Token::SemiColon)?;
+        Ok(kind)
     }
-    fn parse_access_op(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_optional_expression(&mut self) -> Result<Option<Expression>, Error> {
+        if self.peek()? == Some(&Token::SemiColon) {
+            Ok(None)
+        } else {
+            Ok(Some(self.parse_expression()?))
+        }
+    }
+
+    /// Parses an expression in front of a `{ body }`, where `name {` must not
+    /// be read as a struct initialisation.
+    fn parse_restricted_expression(&mut self) -> Result<Expression, Error> {
+        let restrict = std::mem::replace(&mut self.restrict_struct_init, true);
+        let expression = self.parse_expression();
+        self.restrict_struct_init = restrict;
+        expression
     }
-    fn parse_atom(&mut self) -> Result<(), Error> {
-        todo!()
+
+    /// Parses an expression, which may be a range. Ranges bind weaker than
+    /// every operator and `step` is only a keyword right after one.
+    fn parse_expression(&mut self) -> Result<Expression, Error> {
+        let location = self.location()?;
+        let start = self.parse_expression_bp(0)?;
+        let inclusive = match self.peek()? {
+            Some(Token::DotDot) => false,
+            Some(Token::DotDotEqual) => true,
+            _ => return Ok(start),
+        };
+        self.next()?;
+        let end = self.parse_expression_bp(0)?;
+        let step = if matches!(self.peek()?, Some(Token::Identifier(name)) if name == "step") {
+            self.next()?;
+            Some(Box::new(self.parse_expression_bp(0)?))
+        } else {
+            None
+        };
+
+        Ok(Expression {
+            kind: ExpressionKind::Range {
+                start: Box::new(start),
+                end: Box::new(end),
+                inclusive,
+                step,
+            },
+            location,
+        })
+    }
+
+    /// Pratt parser over the operator table in [`precedence::OPERATORS`].
+    fn parse_expression_bp(&mut self, min_power: u8) -> Result<Expression, Error> {
+        let depth = self.depth;
+        let expression = self.parse_operators(min_power);
+        self.depth = depth;
+        expression
+    }
+
+    /// Parses the operators of [`Self::parse_expression_bp`], nesting once
+    /// for every operator applied.
+    fn parse_operators(&mut self, min_power: u8) -> Result<Expression, Error> {
+        let location = self.location()?;
+        self.nest(location)?;
+        let prefix = match self.peek()? {
+            Some(token) => precedence::prefix(token),
+            None => None,
+        };
+
+        let mut lhs = if let Some(operator) = prefix {
+            let Fixity::Prefix(unary) = operator.fixity else {
+                unreachable!()
+            };
+            self.next()?;
+            let operand = self.parse_expression_bp(operator.binding_power().1)?;
+            Expression {
+                kind: ExpressionKind::Unary(unary, Box::new(operand)),
+                location,
+            }
+        } else {
+            self.parse_atom()?
+        };
+
+        loop {
+            let operator = match self.peek()? {
+                Some(token) => precedence::trailing(token),
+                None => None,
+            };
+            let Some(operator) = operator else {
+                return Ok(lhs);
+            };
+
+            let (left_power, right_power) = operator.binding_power();
+            if left_power < min_power {
+                return Ok(lhs);
+            }
+
+            let mut location = self.next()?.location;
+            self.nest(location)?;
+            let kind = match operator.fixity {
+                Fixity::Infix(binary) => {
+                    let rhs = self.parse_expression_bp(right_power)?;
+                    location = lhs.location;
+                    ExpressionKind::Binary(binary, Box::new(lhs), Box::new(rhs))
+                }
+                Fixity::Postfix(PostfixOperator::Call) => {
+                    let arguments = self.parse_arguments(&Token::RPar)?;
+                    ExpressionKind::Call(Box::new(lhs), arguments)
+                }
+                Fixity::Postfix(PostfixOperator::Index) => {
+                    let index = self.parse_expression()?;
+                    self.expect(&Token::RBracket)?;
+                    ExpressionKind::Index(Box::new(lhs), Box::new(index))
+                }
+                Fixity::Postfix(PostfixOperator::Member) => {
+                    let member = self.parse_identifier()?;
+                    ExpressionKind::Member(Box::new(lhs), member)
+                }
+                Fixity::Prefix(_) => unreachable!(),
+            };
+            lhs = Expression { kind, location };
+        }
     }
-    fn parse_initialisation(&mut self) -> Result<(), Error> {
-        todo!()
+
+    fn parse_arguments(&mut self, close: &Token) -> Result<Vec<Expression>, Error> {
+        let mut arguments = Vec::new();
+        while !self.eat(close)? {
+            arguments.push(self.parse_expression()?);
+            if !self.eat(&Token::Comma)? {
+                self.expect(close)?;
+                break;
+            }
+        }
+        Ok(arguments)
+    }
+
+    fn parse_atom(&mut self) -> Result<Expression, Error> {
+        let location = self.location()?;
+        let kind = match self.peek()? {
+            Some(Token::Identifier(_)) => {
+                let path = self.parse_path()?;
+                if !self.restrict_struct_init && self.peek()? == Some(&Token::LBrace) {
+                    return self.parse_initialisation(Some(path));
+                }
+                ExpressionKind::Identifier(path)
+            }
+            Some(Token::SelfKey) => {
+                self.next()?;
+                ExpressionKind::Identifier(Path {
+                    segments: vec!["self".to_string()],
+                    generics: Vec::new(),
+                    location,
+                })
+            }
+            Some(Token::LPar) => {
+                self.next()?;
+                let restrict = std::mem::replace(&mut self.restrict_struct_init, false);
+                let expression = self.parse_expression()?;
+                self.restrict_struct_init = restrict;
+                self.expect(&Token::RPar)?;
+                return Ok(expression);
+            }
+            Some(Token::LBracket) => return self.parse_initialisation(None),
+            Some(Token::Loop) => return self.parse_loop(None),
+            Some(Token::Match) => return self.parse_match(),
+            Some(Token::LableDecl) => {
+                let label = self.parse_label()?;
+                self.expect(&Token::Colon)?;
+                return self.parse_loop(label);
+            }
+            _ => ExpressionKind::Literal(self.parse_literal()?),
+        };
+        Ok(Expression { kind, location })
+    }
+
+    fn parse_initialisation(&mut self, path: Option<Path>) -> Result<Expression, Error> {
+        let location = self.location()?;
+        let Some(path) = path else {
+            self.expect(&Token::LBracket)?;
+            let elements = self.parse_arguments(&Token::RBracket)?;
+            return Ok(Expression {
+                kind: ExpressionKind::ArrayInit(elements),
+                location,
+            });
+        };
+
+        self.expect(&Token::LBrace)?;
+        let mut fields = Vec::new();
+        while !self.eat(&Token::RBrace)? {
+            let named = matches!(self.peek_nth(0)?, Some(Token::Identifier(_)))
+                && self.peek_nth(1)? == Some(&Token::Colon);
+            let name = if named {
+                let name = self.parse_identifier()?;
+                self.expect(&Token::Colon)?;
+                Some(name)
+            } else {
+                None
+            };
+            let value = self.parse_expression()?;
+            fields.push(FieldInit { name, value });
+            if !self.eat(&Token::Comma)? {
+                self.expect(&Token::RBrace)?;
+                break;
+            }
+        }
+
+        Ok(Expression {
+            kind: ExpressionKind::StructInit(path.clone(), fields),
+            location: path.location,
+        })
+    }
+
+    fn nest(&mut self, location: Location) -> Result<(), Error> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING {
+            return Err(Error::NestingTooDeep {
+                limit: MAX_NESTING,
+                location,
+            });
+        }
+        Ok(())
+    }
+
+    fn fill(&mut self, n: usize) -> Result<(), Error> {
+        while self.token_buffer.len() <= n && !self.eof {
+            match self.lexer.scan()? {
+                Some(tokens) => self.token_buffer.extend(tokens),
+                None => self.eof = true,
+            }
+        }
+        Ok(())
+    }
+
+    fn peek_nth(&mut self, n: usize) -> Result<Option<&Token>, Error> {
+        self.fill(n)?;
+        Ok(self.token_buffer.get(n).map(|token| &token.token))
+    }
+
+    fn peek(&mut self) -> Result<Option<&Token>, Error> {
+        self.peek_nth(0)
+    }
+
+    fn next(&mut self) -> Result<RichToken, Error> {
+        self.fill(0)?;
+        let location = self.lexer.location();
+        self.token_buffer
+            .pop_front()
+            .ok_or(Error::UnexpectedEOF { location })
+    }
+
+    fn eat(&mut self, token: &Token) -> Result<bool, Error> {
+        if self.peek()? == Some(token) {
+            self.next()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<Location, Error> {
+        if self.peek()? == Some(token) {
+            Ok(self.next()?.location)
+        } else {
+            Err(self.unexpected(&token.to_debug_string())?)
+        }
+    }
+
+    fn location(&mut self) -> Result<Location, Error> {
+        self.fill(0)?;
+        Ok(self
+            .token_buffer
+            .front()
+            .map_or_else(|| self.lexer.location(), |token| token.location))
+    }
+
+    fn unexpected(&mut self, expected: &str) -> Result<Error, Error> {
+        self.fill(0)?;
+        Ok(match self.token_buffer.front() {
+            Some(found) => Error::UnexpectedToken {
+                expected: expected.to_string(),
+                found: found.token.to_debug_string(),
+                location: found.location,
+            },
+            None => Error::UnexpectedEOF {
+                location: self.lexer.location(),
+            },
+        })
     }
 }
@@ -2,10 +2,457 @@
  * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
  */
 
+use std::fmt::{self, Display};
+
+use crate::parser::tokens::Location;
+
 mod visitor;
 
-pub trait AstNode {}
+pub trait AstNode {
+    fn location(&self) -> Location;
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AstRoot {
+    pub items: Vec<Item>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Visibility {
+    #[default]
+    Private,
+    Public,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub visibility: Visibility,
+    pub kind: ItemKind,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ItemKind {
+    Namespace(Namespace),
+    Struct(Struct),
+    Enum(Enum),
+    Interface(Interface),
+    Impl(Impl),
+    Function(Function),
+    Use(Use),
+}
+
+impl ItemKind {
+    /// The name the item adds to its namespace. `impl` blocks and methods
+    /// add none.
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Self::Namespace(namespace) => Some(&namespace.name),
+            Self::Struct(structure) => Some(&structure.name),
+            Self::Enum(enumeration) => Some(&enumeration.name),
+            Self::Interface(interface) => Some(&interface.name),
+            Self::Function(function) if function.receiver.is_none() => Some(&function.name),
+            Self::Use(import) => Some(import.path.name()),
+            Self::Function(_) | Self::Impl(_) => None,
+        }
+    }
+}
+
+/// `namespace name { ... }` or `namespace name;`, the latter is loaded from
+/// `name.cringe` or `name/mod.cringe` by the module loader.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Namespace {
+    pub name: String,
+    pub items: Vec<Item>,
+    pub external: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Struct {
+    pub name: String,
+    pub generics: Vec<GenericParam>,
+    pub members: Vec<Member>,
+}
+
+/// `enum name { A, B(int), C { x: int } }`, a tagged union.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Enum {
+    pub name: String,
+    pub generics: Vec<GenericParam>,
+    pub variants: Vec<Variant>,
+}
+
+/// `interface name { fn method(self: *Self); }`, a set of methods that
+/// structs can implement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interface {
+    pub name: String,
+    /// Declarations only, their bodies are empty.
+    pub methods: Vec<Method>,
+}
+
+/// `impl Interface for Struct { ... }`, the methods of an interface for a
+/// struct.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Impl {
+    pub interface: Path,
+    pub target: Type,
+    pub methods: Vec<Method>,
+}
+
+/// A function declared in an interface or defined in an `impl` block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Method {
+    pub function: Function,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    pub name: String,
+    pub fields: VariantFields,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VariantFields {
+    Unit,
+    Tuple(Vec<Type>),
+    Named(Vec<Member>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub name: String,
+    pub generics: Vec<GenericParam>,
+    /// `self: type`, makes the function a method of the receiver's struct.
+    pub receiver: Option<Box<Member>>,
+    pub parameters: Vec<Member>,
+    pub return_type: Option<Type>,
+    pub body: Vec<Statement>,
+    /// `extern fn`, implemented outside of the program and without a body.
+    pub external: bool,
+}
+
+/// Type parameter of a generic struct or function, e.g. the `T` in
+/// `struct Vec<T>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenericParam {
+    pub name: String,
+    /// Interfaces the type argument must implement, only for functions.
+    pub bounds: Vec<Path>,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Use {
+    pub path: Path,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    pub name: String,
+    pub ty: Type,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Path {
+    pub segments: Vec<String>,
+    /// Type arguments of the last segment, `Vec<int>` in a type or
+    /// `max::<int>` in an expression.
+    pub generics: Vec<Type>,
+    pub location: Location,
+}
+
+impl Path {
+    #[must_use]
+    pub fn name(&self) -> &str {
+        self.segments.last().map_or("", String::as_str)
+    }
+}
+
+impl Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.segments.join("::"))?;
+        if !self.generics.is_empty() {
+            let generics: Vec<String> = self.generics.iter().map(ToString::to_string).collect();
+            write!(f, "<{}>", generics.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Type {
+    pub pointer: usize,
+    pub base: BaseType,
+    pub array: usize,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BaseType {
+    Named(Path),
+    Int {
+        unsigned: bool,
+    },
+    Str,
+    Bool,
+    /// `Self`, the implementing type inside an interface or `impl` block.
+    SelfType,
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", "*".repeat(self.pointer))?;
+        match &self.base {
+            BaseType::Named(path) => write!(f, "{path}")?,
+            BaseType::Int { unsigned: true } => write!(f, "unsigned int")?,
+            BaseType::Int { unsigned: false } => write!(f, "int")?,
+            BaseType::Str => write!(f, "str")?,
+            BaseType::Bool => write!(f, "bool")?,
+            BaseType::SelfType => write!(f, "Self")?,
+        }
+        write!(f, "{}", "[]".repeat(self.array))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatementKind {
+    Compound(Vec<Statement>),
+    If(If),
+    For(For),
+    Return(Option<Expression>),
+    Break {
+        label: Option<String>,
+        value: Option<Expression>,
+    },
+    Continue {
+        label: Option<String>,
+    },
+    VarDecl(Declaration),
+    ValDecl(Declaration),
+    Assign {
+        target: Expression,
+        value: Expression,
+    },
+    Expression(Expression),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct If {
+    pub condition: Expression,
+    pub then: Vec<Statement>,
+    pub otherwise: Option<Vec<Statement>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct For {
+    pub label: Option<String>,
+    pub binding: String,
+    pub binding_location: Location,
+    pub iterable: Expression,
+    pub body: Vec<Statement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+    pub name: String,
+    pub location: Location,
+    pub ty: Option<Type>,
+    pub value: Option<Expression>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpressionKind {
+    Identifier(Path),
+    Literal(Literal),
+    Unary(UnaryOperator, Box<Expression>),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+    Call(Box<Expression>, Vec<Expression>),
+    Index(Box<Expression>, Box<Expression>),
+    Member(Box<Expression>, String),
+    StructInit(Path, Vec<FieldInit>),
+    ArrayInit(Vec<Expression>),
+    Loop {
+        label: Option<String>,
+        body: Vec<Statement>,
+    },
+    Match {
+        scrutinee: Box<Expression>,
+        arms: Vec<MatchArm>,
+    },
+    /// `start..end` or `start..=end`, optionally followed by `step value`.
+    Range {
+        start: Box<Expression>,
+        end: Box<Expression>,
+        inclusive: bool,
+        step: Option<Box<Expression>>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldInit {
+    pub name: Option<String>,
+    pub value: Expression,
+}
+
+/// `pattern if guard => body`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expression>,
+    pub body: ArmBody,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArmBody {
+    /// The value of the match.
+    Expression(Expression),
+    /// Statements without a value, used when the match has none or the
+    /// block leaves it with `return`, `break` or `continue`.
+    Block(Vec<Statement>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternKind {
+    /// `_`
+    Wildcard,
+    /// A single identifier, binds the matched value.
+    Binding(String),
+    Literal {
+        literal: Literal,
+        negative: bool,
+    },
+    /// `path` or `path(patterns)`, a variant with positional fields.
+    Tuple(Path, Vec<Pattern>),
+    /// `path { field: pattern, field, .. }`, a struct or a variant with
+    /// named fields. Without `..` every field must be listed.
+    Struct {
+        path: Path,
+        fields: Vec<FieldPattern>,
+        rest: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldPattern {
+    pub name: String,
+    pub pattern: Pattern,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Literal {
+    Int(u64),
+    Str(String),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnaryOperator {
+    Minus,
+    Not,
+    Dereference,
+    Address,
+    Sizeof,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOperator {
+    Mul,
+    Div,
+    Rem,
+    Add,
+    Sub,
+    ShiftLeft,
+    ShiftRight,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+    And,
+    Xor,
+    Or,
+    LogicalAnd,
+    LogicalOr,
+}
+
+impl Display for UnaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            UnaryOperator::Minus => "-",
+            UnaryOperator::Not => "!",
+            UnaryOperator::Dereference => "*",
+            UnaryOperator::Address => "&",
+            UnaryOperator::Sizeof => "sizeof",
+        };
+        write!(f, "{symbol}")
+    }
+}
+
+impl Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            BinaryOperator::Mul => "*",
+            BinaryOperator::Div => "/",
+            BinaryOperator::Rem => "%",
+            BinaryOperator::Add => "+",
+            BinaryOperator::Sub => "-",
+            BinaryOperator::ShiftLeft => "<<",
+            BinaryOperator::ShiftRight => ">>",
+            BinaryOperator::Less => "<",
+            BinaryOperator::LessEqual => "<=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::GreaterEqual => ">=",
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::And => "&",
+            BinaryOperator::Xor => "^",
+            BinaryOperator::Or => "|",
+            BinaryOperator::LogicalAnd => "&&",
+            BinaryOperator::LogicalOr => "||",
+        };
+        write!(f, "{symbol}")
+    }
+}
+
+impl AstNode for Item {
+    fn location(&self) -> Location {
+        self.location
+    }
+}
 
-pub struct AstRoot {}
+impl AstNode for Statement {
+    fn location(&self) -> Location {
+        self.location
+    }
+}
 
-pub enum AstNodeType {}
+impl AstNode for Expression {
+    fn location(&self) -> Location {
+        self.location
+    }
+}
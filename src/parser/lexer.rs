@@ -2,14 +2,14 @@
  * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
  */
 
-use std::{collections::VecDeque, io::BufRead};
+use std::{collections::VecDeque, io::BufRead, path::Path};
 
 use lexer_gut::scan_line;
 use lexer_state::LexerState;
 
 use crate::{
     Error,
-    parser::tokens::{Location, Token},
+    parser::tokens::{Location, RichToken},
 };
 
 mod lexer_gut;
@@ -18,17 +18,29 @@ mod lexer_state;
 pub struct Lexer<R: BufRead> {
     source: R,
     location: Location,
-    current_location: Location,
-    token_location: Location,
 }
 
 impl<R: BufRead> Lexer<R> {
-    pub fn scan(&mut self) -> Result<VecDeque<Token>, Error> {
+    pub fn new(source: R, fid: usize) -> Self {
+        Self {
+            source,
+            location: Location::new(fid),
+        }
+    }
+
+    /// Shows the locations of the tokens with `path`.
+    pub fn with_file(mut self, path: &Path) -> Self {
+        self.location = Location::in_file(self.location.fid, path);
+        self
+    }
+
+    /// Scans the next line of the source. Returns `None` at EOF.
+    pub fn scan(&mut self) -> Result<Option<VecDeque<RichToken>>, Error> {
         let mut line_buffer = String::new();
 
         // EOF return
         if self.source.read_line(&mut line_buffer)? == 0 {
-            return Ok(VecDeque::new());
+            return Ok(None);
         }
 
         let iter = line_buffer.chars().peekable();
@@ -36,8 +48,13 @@ impl<R: BufRead> Lexer<R> {
 
         scan_line(&mut lexer_state)?;
 
-        let (tokens, location) = lexer_state.into();
-        self.location = location;
-        Ok(tokens)
+        let (tokens, _) = lexer_state.into();
+        self.location.l += 1;
+        self.location.c = 1;
+        Ok(Some(tokens))
+    }
+
+    pub fn location(&self) -> Location {
+        self.location
     }
 }
@@ -10,6 +10,10 @@ pub fn scan_line(state: &mut LexerState) -> Result<(), Error> {
     while let Some(c) = state.peek() {
         let c = *c; // TODO make it clean
 
+        if state.is_string_buffer_empty() {
+            state.mark();
+        }
+
         // Skip whitespace
         if c.is_whitespace() {
             scan_keyword_or_identifier(state)?;
@@ -18,10 +22,17 @@ pub fn scan_line(state: &mut LexerState) -> Result<(), Error> {
         // Scan string literal
         if c == '"' {
             scan_keyword_or_identifier(state)?;
+            state.mark();
             scan_string_literal(state)?;
         } else
+        // Continue identifier
+        if !state.is_string_buffer_empty() && (c == '_' || c == '-') {
+            state.buffer();
+        } else
         // Scan punctuation
-        if state.is_string_buffer_empty() && c.is_ascii_punctuation() {
+        if c != '_' && c.is_ascii_punctuation() {
+            scan_keyword_or_identifier(state)?;
+            state.mark();
             scan_punctuation(state)?;
         } else
         // Scan int literal
@@ -64,13 +75,17 @@ fn scan_string_literal(state: &mut LexerState) -> Result<(), Error> {
             state.accept(Token::Str(buffer));
             return Ok(());
         } else if *c == '\n' {
-            return Err(Error::UnexpectedLF);
+            return Err(Error::UnexpectedLF {
+                location: state.token_location(),
+            });
         } else {
             state.buffer();
         }
     }
 
-    Err(Error::UnexpectedEOF)
+    Err(Error::UnexpectedEOF {
+        location: state.token_location(),
+    })
 }
 
 fn scan_punctuation(state: &mut LexerState) -> Result<(), Error> {
@@ -82,27 +97,32 @@ fn scan_punctuation(state: &mut LexerState) -> Result<(), Error> {
             state.skip();
             state.accept(Token::Comma);
         }
-        ':' => {
-            state.skip();
-            state.accept(Token::Colon);
-        }
         ';' => {
             state.skip();
             state.accept(Token::SemiColon);
         }
         '.' => {
             state.skip();
-            state.accept(Token::Dot);
+            let mut token = Token::Dot;
+            if state.peek() == Some(&'.') {
+                state.skip();
+                token = Token::DotDot;
+                if state.peek() == Some(&'=') {
+                    state.skip();
+                    token = Token::DotDotEqual;
+                }
+            }
+            state.accept(token);
+        }
+        '\'' => {
+            state.skip();
+            state.accept(Token::LableDecl);
         }
 
         '+' => {
             state.skip();
             state.accept(Token::Plus);
         }
-        '-' => {
-            state.skip();
-            state.accept(Token::Dash);
-        }
         '*' => {
             state.skip();
             state.accept(Token::Asterix);
@@ -144,54 +164,67 @@ fn scan_punctuation(state: &mut LexerState) -> Result<(), Error> {
             state.accept(Token::RBracket);
         }
         _ => scan_composed_punctuation(state)?,
-    };
+    }
 
     Ok(())
 }
 
 fn scan_composed_punctuation(state: &mut LexerState) -> Result<(), Error> {
     match state.peek().unwrap() {
+        ':' => {
+            state.skip();
+            let mut token = Token::Colon;
+            if state.peek() == Some(&':') {
+                state.skip();
+                token = Token::PathSeparator;
+            }
+            state.accept(token);
+        }
+        '-' => {
+            state.skip();
+            let mut token = Token::Dash;
+            if state.peek() == Some(&'>') {
+                state.skip();
+                token = Token::Arrow;
+            }
+            state.accept(token);
+        }
         '!' => {
             state.skip();
             let mut token = Token::Exclamation;
-            if let Some(c) = state.peek() {
-                if *c == '=' {
-                    state.skip();
-                    token = Token::NotEqualOperator;
-                }
+            if state.peek() == Some(&'=') {
+                state.skip();
+                token = Token::NotEqualOperator;
             }
             state.accept(token);
         }
         '=' => {
             state.skip();
             let mut token = Token::Equal;
-            if let Some(c) = state.peek() {
-                if *c == '=' {
-                    state.skip();
-                    token = Token::EqualOperator;
-                }
+            if state.peek() == Some(&'=') {
+                state.skip();
+                token = Token::EqualOperator;
+            } else if state.peek() == Some(&'>') {
+                state.skip();
+                token = Token::FatArrow;
             }
             state.accept(token);
         }
         '&' => {
             state.skip();
             let mut token = Token::Ampersand;
-            if let Some(c) = state.peek() {
-                if *c == '&' {
-                    state.skip();
-                    token = Token::LogicalAnd;
-                }
+            if state.peek() == Some(&'&') {
+                state.skip();
+                token = Token::LogicalAnd;
             }
             state.accept(token);
         }
         '|' => {
             state.skip();
             let mut token = Token::Pipe;
-            if let Some(c) = state.peek() {
-                if *c == '|' {
-                    state.skip();
-                    token = Token::LogicalOr;
-                }
+            if state.peek() == Some(&'|') {
+                state.skip();
+                token = Token::LogicalOr;
             }
             state.accept(token);
         }
@@ -223,50 +256,55 @@ fn scan_composed_punctuation(state: &mut LexerState) -> Result<(), Error> {
             }
             state.accept(token);
         }
-        c => return Err(Error::UnexpectedSymbol(*c)),
-    };
+        c => {
+            return Err(Error::UnexpectedSymbol {
+                symbol: *c,
+                location: state.token_location(),
+            });
+        }
+    }
     Ok(())
 }
 
 fn scan_int_literal(state: &mut LexerState) -> Result<(), Error> {
-    let mut radix = None;
-    if let Some(c) = state.peek() {
-        if *c == '0' {
+    let mut radix = 10;
+    let mut prefix = "";
+    if state.peek() == Some(&'0') {
+        state.buffer();
+        let base = match state.peek() {
+            Some('x' | 'X') => Some((16, "0x")),
+            Some('o' | 'O') => Some((8, "0o")),
+            Some('b' | 'B') => Some((2, "0b")),
+            _ => None,
+        };
+        if let Some(base) = base {
+            (radix, prefix) = base;
             state.skip();
-        } else {
-            radix = Some(10);
-            state.buffer();
+            state.clear_string_buffer();
         }
     }
 
     while let Some(c) = state.peek() {
-        if let Some(radix) = radix {
-            if *c == '_' {
-                state.skip();
-            } else if c.is_digit(radix) {
-                state.buffer();
-            } else {
-                break;
-            }
-        } else if *c == 'x' || *c == 'X' {
-            radix = Some(16);
-            state.skip();
-        } else if *c == 'o' || *c == 'O' {
-            radix = Some(8);
+        if *c == '_' {
             state.skip();
-        } else if *c == 'b' || *c == 'B' {
-            radix = Some(2);
-            state.skip();
-        } else if c.is_ascii_digit() {
-            radix = Some(10);
+        } else if c.is_digit(radix) {
             state.buffer();
+        } else if c.is_ascii_alphanumeric() {
+            return Err(Error::UnexpectedSymbol {
+                symbol: *c,
+                location: state.token_location(),
+            });
         } else {
-            return Err(Error::UnexpectedSymbol(*c));
+            break;
         }
     }
 
     let buffer = state.string_buffer();
-    let int = u64::from_str_radix(buffer, radix.unwrap())?;
+    let int = u64::from_str_radix(buffer, radix).map_err(|error| Error::InvalidInt {
+        literal: format!("{prefix}{buffer}"),
+        error,
+        location: state.token_location(),
+    })?;
 
     state.accept(Token::Int(int));
 
@@ -274,9 +312,18 @@ fn scan_int_literal(state: &mut LexerState) -> Result<(), Error> {
 }
 
 fn scan_keyword_or_identifier(state: &mut LexerState) -> Result<(), Error> {
+    if state.is_string_buffer_empty() {
+        return Ok(());
+    }
+
     let token = match state.string_buffer() {
         "namespace" => Token::Namespace,
         "struct" => Token::Struct,
+        "enum" => Token::Enum,
+        "interface" => Token::Interface,
+        "impl" => Token::Impl,
+        "extern" => Token::Extern,
+        "match" => Token::Match,
         "fn" => Token::Fn,
         "if" => Token::If,
         "else" => Token::Else,
@@ -295,6 +342,10 @@ fn scan_keyword_or_identifier(state: &mut LexerState) -> Result<(), Error> {
         "bool" => Token::BoolKey,
         "true" => Token::Bool(true),
         "false" => Token::Bool(false),
+        "self" => Token::SelfKey,
+        "Self" => Token::SelfType,
+        "pub" => Token::Pub,
+        "use" => Token::Use,
         _ => scan_identifier(state)?,
     };
 
@@ -304,11 +355,15 @@ fn scan_keyword_or_identifier(state: &mut LexerState) -> Result<(), Error> {
 }
 
 fn scan_identifier(state: &mut LexerState) -> Result<Token, Error> {
-    for c in state.string_buffer().chars() {
-        if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
-        } else {
-            return Err(Error::InvalidID(state.string_buffer().to_string()));
-        }
+    if !state
+        .string_buffer()
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(Error::InvalidID {
+            identifier: state.string_buffer().to_string(),
+            location: state.token_location(),
+        });
     }
     Ok(Token::Identifier(state.string_buffer().to_string()))
 }
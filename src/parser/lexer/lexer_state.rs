@@ -4,10 +4,10 @@
 
 use std::{collections::VecDeque, iter::Peekable, str::Chars};
 
-use crate::parser::tokens::{Location, Token};
+use crate::parser::tokens::{Location, RichToken, Token};
 
 pub struct LexerState<'a> {
-    token_stream: VecDeque<Token>,
+    token_stream: VecDeque<RichToken>,
     current_location: Location,
     token_location: Location,
     buffer_line: Peekable<Chars<'a>>,
@@ -18,8 +18,8 @@ impl<'a> LexerState<'a> {
     pub fn new(location: &Location, line: Peekable<Chars<'a>>) -> Self {
         Self {
             token_stream: VecDeque::new(),
-            current_location: location.clone(), // TODO Update Line
-            token_location: location.clone(),   // TODO same here
+            current_location: *location,
+            token_location: *location,
             buffer_line: line,
             buffer: String::new(),
         }
@@ -34,7 +34,7 @@ impl<'a> LexerState<'a> {
     }
 
     pub fn buffer(&mut self) -> bool {
-        if let Some(c) = self.buffer_line.next() {
+        if let Some(c) = self.advance() {
             self.buffer.push(c);
             true
         } else {
@@ -43,11 +43,11 @@ impl<'a> LexerState<'a> {
     }
 
     pub fn take(&mut self) -> Option<char> {
-        self.buffer_line.next()
+        self.advance()
     }
 
     pub fn skip(&mut self) -> bool {
-        self.buffer_line.next().is_some()
+        self.advance().is_some()
     }
 
     pub fn clear_string_buffer(&mut self) {
@@ -62,14 +62,35 @@ impl<'a> LexerState<'a> {
         self.buffer.as_str()
     }
 
+    /// Where the token being scanned starts.
+    pub fn token_location(&self) -> Location {
+        self.token_location
+    }
+
+    /// Marks the current position as the start of the next token.
+    pub fn mark(&mut self) {
+        self.token_location = self.current_location;
+    }
+
     pub fn accept(&mut self, token: Token) {
         self.buffer.clear();
-        self.token_stream.push_back(token);
-        self.token_location = self.current_location.clone();
+        self.token_stream.push_back(RichToken {
+            token,
+            location: self.token_location,
+        });
+        self.token_location = self.current_location;
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.buffer_line.next();
+        if c.is_some() {
+            self.current_location.c += 1;
+        }
+        c
     }
 }
 
-impl From<LexerState<'_>> for (VecDeque<Token>, Location) {
+impl From<LexerState<'_>> for (VecDeque<RichToken>, Location) {
     fn from(value: LexerState<'_>) -> Self {
         (value.token_stream, value.current_location)
     }
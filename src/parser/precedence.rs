@@ -0,0 +1,110 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+use crate::parser::{
+    ast::{BinaryOperator, UnaryOperator},
+    tokens::Token,
+};
+
+use Associativity::{Left, Right};
+
+/// Operator table, one entry per operator of doc/operator.txt. Level 1 binds
+/// tightest.
+#[rustfmt::skip]
+pub const OPERATORS: &[Operator] = &[
+    Operator::new(Token::LPar, Fixity::Postfix(PostfixOperator::Call), 1, Left),
+    Operator::new(Token::LBracket, Fixity::Postfix(PostfixOperator::Index), 1, Left),
+    Operator::new(Token::Dot, Fixity::Postfix(PostfixOperator::Member), 1, Left),
+    Operator::new(Token::Dash, Fixity::Prefix(UnaryOperator::Minus), 2, Right),
+    Operator::new(Token::Exclamation, Fixity::Prefix(UnaryOperator::Not), 2, Right),
+    Operator::new(Token::Asterix, Fixity::Prefix(UnaryOperator::Dereference), 2, Right),
+    Operator::new(Token::Ampersand, Fixity::Prefix(UnaryOperator::Address), 2, Right),
+    Operator::new(Token::Sizeof, Fixity::Prefix(UnaryOperator::Sizeof), 2, Right),
+    Operator::new(Token::Asterix, Fixity::Infix(BinaryOperator::Mul), 3, Left),
+    Operator::new(Token::Slash, Fixity::Infix(BinaryOperator::Div), 3, Left),
+    Operator::new(Token::Percent, Fixity::Infix(BinaryOperator::Rem), 3, Left),
+    Operator::new(Token::Plus, Fixity::Infix(BinaryOperator::Add), 4, Left),
+    Operator::new(Token::Dash, Fixity::Infix(BinaryOperator::Sub), 4, Left),
+    Operator::new(Token::ShiftLeft, Fixity::Infix(BinaryOperator::ShiftLeft), 5, Left),
+    Operator::new(Token::ShiftRight, Fixity::Infix(BinaryOperator::ShiftRight), 5, Left),
+    Operator::new(Token::LAngle, Fixity::Infix(BinaryOperator::Less), 6, Left),
+    Operator::new(Token::Leq, Fixity::Infix(BinaryOperator::LessEqual), 6, Left),
+    Operator::new(Token::RAngle, Fixity::Infix(BinaryOperator::Greater), 6, Left),
+    Operator::new(Token::Geq, Fixity::Infix(BinaryOperator::GreaterEqual), 6, Left),
+    Operator::new(Token::EqualOperator, Fixity::Infix(BinaryOperator::Equal), 7, Left),
+    Operator::new(Token::NotEqualOperator, Fixity::Infix(BinaryOperator::NotEqual), 7, Left),
+    Operator::new(Token::Ampersand, Fixity::Infix(BinaryOperator::And), 8, Left),
+    Operator::new(Token::Hat, Fixity::Infix(BinaryOperator::Xor), 9, Left),
+    Operator::new(Token::Pipe, Fixity::Infix(BinaryOperator::Or), 10, Left),
+    Operator::new(Token::LogicalAnd, Fixity::Infix(BinaryOperator::LogicalAnd), 11, Left),
+    Operator::new(Token::LogicalOr, Fixity::Infix(BinaryOperator::LogicalOr), 12, Left),
+];
+
+const LOWEST_LEVEL: u8 = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostfixOperator {
+    Call,
+    Index,
+    Member,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fixity {
+    Prefix(UnaryOperator),
+    Infix(BinaryOperator),
+    Postfix(PostfixOperator),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operator {
+    pub token: Token,
+    pub fixity: Fixity,
+    pub level: u8,
+    pub associativity: Associativity,
+}
+
+impl Operator {
+    const fn new(token: Token, fixity: Fixity, level: u8, associativity: Associativity) -> Self {
+        Self {
+            token,
+            fixity,
+            level,
+            associativity,
+        }
+    }
+
+    /// Left and right binding power. The left power decides whether the
+    /// operator may take the expression parsed so far as its operand, the
+    /// right power is the minimum for the operand that follows.
+    #[must_use]
+    pub const fn binding_power(&self) -> (u8, u8) {
+        let power = (LOWEST_LEVEL + 1 - self.level) * 2;
+        match self.associativity {
+            Left => (power, power + 1),
+            Right => (power + 1, power),
+        }
+    }
+}
+
+#[must_use]
+pub fn prefix(token: &Token) -> Option<&'static Operator> {
+    OPERATORS
+        .iter()
+        .find(|op| op.token == *token && matches!(op.fixity, Fixity::Prefix(_)))
+}
+
+/// Infix or postfix operator for a token following an operand.
+#[must_use]
+pub fn trailing(token: &Token) -> Option<&'static Operator> {
+    OPERATORS
+        .iter()
+        .find(|op| op.token == *token && !matches!(op.fixity, Fixity::Prefix(_)))
+}
@@ -2,17 +2,71 @@
  * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
  */
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Display},
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+
+/// A position in a source file. `fid` indexes the files of the module tree,
+/// `file` is the path the position is shown with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Location {
     pub l: usize,
     pub c: usize,
     pub fid: usize,
+    pub file: Option<&'static PathBuf>,
+}
+
+impl Location {
+    /// The start of the file `fid`, without a path to show.
+    #[must_use]
+    pub fn new(fid: usize) -> Self {
+        Self {
+            l: 1,
+            c: 1,
+            fid,
+            file: None,
+        }
+    }
+
+    /// The start of the file `fid` at `path`.
+    #[must_use]
+    pub fn in_file(fid: usize, path: &Path) -> Self {
+        Self {
+            file: Some(intern(path)),
+            ..Self::new(fid)
+        }
+    }
+}
+
+/// Keeps every path a location is shown with for the rest of the run, once,
+/// so that locations stay `Copy`.
+fn intern(path: &Path) -> &'static PathBuf {
+    static PATHS: Mutex<BTreeSet<&'static PathBuf>> = Mutex::new(BTreeSet::new());
+    let mut paths = PATHS.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(interned) = paths.get(&path.to_path_buf()) {
+        return interned;
+    }
+    let interned: &'static PathBuf = Box::leak(Box::new(path.to_path_buf()));
+    paths.insert(interned);
+    interned
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.file {
+            Some(file) => write!(f, "{}:{}:{}", file.display(), self.l, self.c),
+            None => write!(f, "{}:{}:{}", self.fid, self.l, self.c),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RichToken {
-    token: Token,
-    location: Location,
+    pub token: Token,
+    pub location: Location,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -23,8 +77,13 @@ pub enum Token {
     Colon,
     SemiColon,
     Dot,
+    DotDot,
+    DotDotEqual,
     Exclamation,
     LableDecl,
+    Arrow,
+    FatArrow,
+    PathSeparator,
     // Single arithmetic symbol
     Plus,
     Dash,
@@ -63,11 +122,17 @@ pub enum Token {
     Sizeof,
     Namespace,
     Struct,
+    Enum,
+    Interface,
+    Impl,
+    Extern,
     Fn,
     SelfKey,
+    SelfType,
     If,
     Else,
     Loop,
+    Match,
     For,
     In,
     Break,
@@ -79,9 +144,12 @@ pub enum Token {
     IntKey,
     StrKey,
     BoolKey,
+    Pub,
+    Use,
 }
 
 impl Token {
+    #[must_use]
     pub fn to_debug_string(&self) -> String {
         // TODO create string at the end
         match self {
@@ -90,8 +158,13 @@ impl Token {
             Token::Colon => ":".to_string(),
             Token::SemiColon => ";".to_string(),
             Token::Dot => ".".to_string(),
+            Token::DotDot => "..".to_string(),
+            Token::DotDotEqual => "..=".to_string(),
             Token::Exclamation => "!".to_string(),
             Token::LableDecl => "'".to_string(),
+            Token::Arrow => "->".to_string(),
+            Token::FatArrow => "=>".to_string(),
+            Token::PathSeparator => "::".to_string(),
             Token::Plus => "+".to_string(),
             Token::Dash => "-".to_string(),
             Token::Asterix => "*".to_string(),
@@ -130,11 +203,17 @@ impl Token {
             Token::Sizeof => "sizeof".to_string(),
             Token::Namespace => "namespace".to_string(),
             Token::Struct => "struct".to_string(),
+            Token::Enum => "enum".to_string(),
+            Token::Interface => "interface".to_string(),
+            Token::Impl => "impl".to_string(),
+            Token::Extern => "extern".to_string(),
             Token::Fn => "fn".to_string(),
             Token::SelfKey => "self".to_string(),
+            Token::SelfType => "Self".to_string(),
             Token::If => "if".to_string(),
             Token::Else => "else".to_string(),
             Token::Loop => "loop".to_string(),
+            Token::Match => "match".to_string(),
             Token::For => "for".to_string(),
             Token::In => "in".to_string(),
             Token::Break => "break".to_string(),
@@ -146,6 +225,8 @@ impl Token {
             Token::IntKey => "int".to_string(),
             Token::StrKey => "str".to_string(),
             Token::BoolKey => "bool".to_string(),
+            Token::Pub => "pub".to_string(),
+            Token::Use => "use".to_string(),
         }
     }
 }
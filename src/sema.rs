@@ -0,0 +1,14 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+pub mod assign;
+pub mod flow;
+pub mod infer;
+pub mod layout;
+pub mod mono;
+pub mod patterns;
+pub mod resolve;
+pub mod typeck;
+pub mod typed;
+pub mod types;
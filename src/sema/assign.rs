@@ -0,0 +1,382 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+use std::collections::{BTreeSet, HashSet};
+
+use crate::{
+    Error,
+    diagnostics::Diagnostics,
+    parser::{
+        ast::{BinaryOperator, UnaryOperator},
+        tokens::Location,
+    },
+    sema::{
+        resolve::{LocalId, LocalKind},
+        typed::{ArmBody, Expr, ExprKind, Pattern, PatternKind, Stmt, StmtKind, TypedProgram},
+        types::Ty,
+    },
+};
+
+/// Checks that every local is initialised before it is read, that a `val`
+/// declared without a value is written exactly once and that `val`s with an
+/// initialiser, `for` bindings and pattern bindings are never assigned.
+/// The address of a `val` or binding is never taken either.
+pub fn check(program: &TypedProgram, diagnostics: &mut Diagnostics) {
+    let mut checker = Checker {
+        program,
+        diagnostics,
+        initialised: HashSet::new(),
+        loops: Vec::new(),
+        reported: HashSet::new(),
+    };
+    for function in program.functions.values() {
+        checker.check_block(&function.body, Some(Flow::default()));
+    }
+}
+
+/// What is known about the locals at one point of a function. `None` in
+/// place of a flow means the point is unreachable.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Flow {
+    /// Written on every path.
+    assigned: BTreeSet<LocalId>,
+    /// Written on some path.
+    maybe: BTreeSet<LocalId>,
+}
+
+impl Flow {
+    fn assign(&mut self, local: LocalId) {
+        self.assigned.insert(local);
+        self.maybe.insert(local);
+    }
+}
+
+fn join(a: Option<Flow>, b: Option<Flow>) -> Option<Flow> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(Flow {
+            assigned: a.assigned.intersection(&b.assigned).copied().collect(),
+            maybe: a.maybe.union(&b.maybe).copied().collect(),
+        }),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Marks the bindings of `pattern` as initialised.
+fn bind(pattern: &Pattern, flow: &mut Flow) {
+    match &pattern.kind {
+        PatternKind::Binding(local) => flow.assign(*local),
+        PatternKind::Variant(_, _, fields) | PatternKind::Struct(_, fields) => {
+            for field in fields {
+                bind(field, flow);
+            }
+        }
+        PatternKind::Wildcard
+        | PatternKind::Int(_)
+        | PatternKind::Bool(_)
+        | PatternKind::Str(_) => {}
+    }
+}
+
+struct LoopFrame {
+    label: Option<String>,
+    breaks: Option<Flow>,
+    continues: Option<Flow>,
+}
+
+struct Checker<'p, 'd> {
+    program: &'p TypedProgram,
+    diagnostics: &'d mut Diagnostics,
+    /// `val`s declared with a value.
+    initialised: HashSet<LocalId>,
+    loops: Vec<LoopFrame>,
+    /// Loop bodies are visited until their flow is stable, so the same
+    /// mistake may be found more than once.
+    reported: HashSet<Location>,
+}
+
+impl Checker<'_, '_> {
+    fn check_block(&mut self, body: &[Stmt], mut flow: Option<Flow>) -> Option<Flow> {
+        for statement in body {
+            flow = self.check_statement(statement, flow);
+        }
+        flow
+    }
+
+    fn check_statement(&mut self, statement: &Stmt, mut flow: Option<Flow>) -> Option<Flow> {
+        flow.as_ref()?;
+
+        match &statement.kind {
+            StmtKind::Block(body) => self.check_block(body, flow),
+            StmtKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.check_expression(condition, &mut flow);
+                let then = self.check_block(then, flow.clone());
+                let otherwise = match otherwise {
+                    Some(otherwise) => self.check_block(otherwise, flow),
+                    None => flow,
+                };
+                join(then, otherwise)
+            }
+            StmtKind::For {
+                label,
+                binding,
+                iteration,
+                body,
+            } => {
+                for operand in iteration.operands() {
+                    self.check_expression(operand, &mut flow);
+                }
+                self.check_loop(label.as_deref(), body, Some(*binding), flow.as_ref())
+            }
+            StmtKind::Return(value) => {
+                if let Some(value) = value {
+                    self.check_expression(value, &mut flow);
+                }
+                None
+            }
+            StmtKind::Break { label, value } => {
+                if let Some(value) = value {
+                    self.check_expression(value, &mut flow);
+                }
+                if let Some(frame) = self.frame(label.as_deref()) {
+                    frame.breaks = join(frame.breaks.take(), flow);
+                }
+                None
+            }
+            StmtKind::Continue { label } => {
+                if let Some(frame) = self.frame(label.as_deref()) {
+                    frame.continues = join(frame.continues.take(), flow);
+                }
+                None
+            }
+            StmtKind::Let { local, value } => {
+                if let Some(value) = value {
+                    self.check_expression(value, &mut flow);
+                }
+                if let Some(flow) = &mut flow {
+                    // a declaration inside a loop starts fresh on every pass
+                    flow.assigned.remove(local);
+                    flow.maybe.remove(local);
+                    if value.is_some() {
+                        flow.assign(*local);
+                        if self.program.local(*local).kind == LocalKind::Val {
+                            self.initialised.insert(*local);
+                        }
+                    }
+                }
+                flow
+            }
+            StmtKind::Assign { target, value } => {
+                self.check_expression(value, &mut flow);
+                self.check_target(target, &mut flow);
+                flow
+            }
+            StmtKind::Expr(expression) => {
+                self.check_expression(expression, &mut flow);
+                flow
+            }
+        }
+    }
+
+    /// Visits a loop body until the flow at its head no longer changes.
+    /// `binding` is set for `for` loops, which may also run zero times.
+    fn check_loop(
+        &mut self,
+        label: Option<&str>,
+        body: &[Stmt],
+        binding: Option<LocalId>,
+        entry: Option<&Flow>,
+    ) -> Option<Flow> {
+        let mut head = entry.cloned();
+        loop {
+            self.loops.push(LoopFrame {
+                label: label.map(str::to_string),
+                breaks: None,
+                continues: None,
+            });
+            let mut flow = head.clone();
+            if let (Some(flow), Some(binding)) = (&mut flow, binding) {
+                flow.assign(binding);
+            }
+            let end = self.check_block(body, flow);
+            let frame = self.loops.pop().expect("loop frame was pushed above");
+
+            let next = join(join(entry.cloned(), end), frame.continues);
+            if next == head {
+                return match binding {
+                    Some(_) => join(head, frame.breaks),
+                    None => frame.breaks,
+                };
+            }
+            head = next;
+        }
+    }
+
+    fn check_expression(&mut self, expression: &Expr, flow: &mut Option<Flow>) {
+        match &expression.kind {
+            ExprKind::Int(_)
+            | ExprKind::Bool(_)
+            | ExprKind::Str(_)
+            | ExprKind::SizeOf(_)
+            | ExprKind::Error => {}
+            ExprKind::Local(local) => self.read(*local, expression.location, flow.as_ref()),
+            ExprKind::Unary(UnaryOperator::Address, operand) => self.check_borrow(operand, flow),
+            ExprKind::Unary(_, operand) | ExprKind::Field(operand, _) | ExprKind::Len(operand) => {
+                self.check_expression(operand, flow);
+            }
+            ExprKind::Binary(BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr, lhs, rhs) => {
+                self.check_expression(lhs, flow);
+                let mut short = flow.clone();
+                self.check_expression(rhs, &mut short);
+                *flow = join(flow.take(), short);
+            }
+            ExprKind::Binary(_, lhs, rhs) | ExprKind::Index(lhs, rhs) => {
+                self.check_expression(lhs, flow);
+                self.check_expression(rhs, flow);
+            }
+            ExprKind::Call(_, elements)
+            | ExprKind::InterfaceCall(_, _, _, elements)
+            | ExprKind::StructInit(_, elements)
+            | ExprKind::Variant(_, _, elements)
+            | ExprKind::ArrayInit(elements) => {
+                for element in elements {
+                    self.check_expression(element, flow);
+                }
+            }
+            ExprKind::Loop { label, body } => {
+                *flow = self.check_loop(label.as_deref(), body, None, flow.as_ref());
+            }
+            ExprKind::Match { scrutinee, arms } => {
+                self.check_expression(scrutinee, flow);
+                let mut result = None;
+                for arm in arms {
+                    let mut arm_flow = flow.clone();
+                    if let Some(arm_flow) = &mut arm_flow {
+                        bind(&arm.pattern, arm_flow);
+                    }
+                    if let Some(guard) = &arm.guard {
+                        self.check_expression(guard, &mut arm_flow);
+                    }
+                    match &arm.body {
+                        ArmBody::Expr(value) => self.check_expression(value, &mut arm_flow),
+                        ArmBody::Block(body) => arm_flow = self.check_block(body, arm_flow),
+                    }
+                    result = join(result, arm_flow);
+                }
+                *flow = result;
+            }
+        }
+    }
+
+    /// Checks the left-hand side of an assignment.
+    fn check_target(&mut self, target: &Expr, flow: &mut Option<Flow>) {
+        match &target.kind {
+            ExprKind::Local(local) => self.write(*local, target.location, flow),
+            _ => self.check_mutation(target, flow),
+        }
+    }
+
+    /// Checks a place of which only a part is written. The place must
+    /// already be initialised and must not belong to an immutable local.
+    fn check_mutation(&mut self, place: &Expr, flow: &mut Option<Flow>) {
+        if let Some(local) = self.check_place(place, flow)
+            && self.is_immutable(local)
+        {
+            self.immutable(local, place.location);
+        }
+    }
+
+    /// Checks a place whose address is taken. Writes through the pointer
+    /// cannot be followed, so the place must not belong to any `val`.
+    fn check_borrow(&mut self, place: &Expr, flow: &mut Option<Flow>) {
+        if let Some(local) = self.check_place(place, flow)
+            && (self.is_immutable(local) || self.program.local(local).kind == LocalKind::Val)
+        {
+            let name = self.program.local(local).name.clone();
+            let location = place.location;
+            self.report(Error::ImmutableBorrow { name, location }, location);
+        }
+    }
+
+    /// Checks the expressions of a place and returns the local it belongs
+    /// to, `None` if it is behind a pointer or not a place.
+    fn check_place(&mut self, place: &Expr, flow: &mut Option<Flow>) -> Option<LocalId> {
+        match &place.kind {
+            ExprKind::Local(local) => {
+                self.read(*local, place.location, flow.as_ref());
+                Some(*local)
+            }
+            ExprKind::Field(base, _) if !matches!(base.ty, Ty::Pointer(_)) => {
+                self.check_place(base, flow)
+            }
+            ExprKind::Index(base, index) if !matches!(base.ty, Ty::Pointer(_)) => {
+                self.check_expression(index, flow);
+                self.check_place(base, flow)
+            }
+            // writes through a pointer do not change the pointer itself
+            _ => {
+                self.check_expression(place, flow);
+                None
+            }
+        }
+    }
+
+    fn read(&mut self, local: LocalId, location: Location, flow: Option<&Flow>) {
+        let Some(flow) = flow else {
+            return;
+        };
+        let kind = self.program.local(local).kind;
+        if matches!(kind, LocalKind::Var | LocalKind::Val) && !flow.assigned.contains(&local) {
+            let name = self.program.local(local).name.clone();
+            self.report(Error::Uninitialised { name, location }, location);
+        }
+    }
+
+    fn write(&mut self, local: LocalId, location: Location, flow: &mut Option<Flow>) {
+        let Some(flow) = flow else {
+            return;
+        };
+        if self.is_immutable(local) {
+            self.immutable(local, location);
+        } else if self.program.local(local).kind == LocalKind::Val && flow.maybe.contains(&local) {
+            let name = self.program.local(local).name.clone();
+            self.report(Error::ValReassignment { name, location }, location);
+        }
+        flow.assign(local);
+    }
+
+    fn is_immutable(&self, local: LocalId) -> bool {
+        match self.program.local(local).kind {
+            LocalKind::ForBinding | LocalKind::PatternBinding => true,
+            LocalKind::Val => self.initialised.contains(&local),
+            LocalKind::Receiver | LocalKind::Parameter | LocalKind::Var => false,
+        }
+    }
+
+    fn immutable(&mut self, local: LocalId, location: Location) {
+        let name = self.program.local(local).name.clone();
+        self.report(Error::ImmutableAssignment { name, location }, location);
+    }
+
+    /// The frame a `break` or `continue` with `label` refers to.
+    fn frame(&mut self, label: Option<&str>) -> Option<&mut LoopFrame> {
+        match label {
+            Some(label) => self
+                .loops
+                .iter_mut()
+                .rev()
+                .find(|frame| frame.label.as_deref() == Some(label)),
+            None => self.loops.last_mut(),
+        }
+    }
+
+    fn report(&mut self, error: Error, location: Location) {
+        if self.reported.insert(location) {
+            self.diagnostics.error(error);
+        }
+    }
+}
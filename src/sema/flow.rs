@@ -0,0 +1,232 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+use crate::{
+    Error, Warning,
+    diagnostics::Diagnostics,
+    parser::{ast::BinaryOperator, tokens::Location},
+    sema::{
+        typed::{ArmBody, Expr, ExprKind, Stmt, StmtKind, TypedFunction, TypedProgram},
+        types::Ty,
+    },
+};
+
+/// Checks that `break` and `continue` refer to an enclosing loop, that only
+/// `loop` is left with a value, that functions with a result return on every
+/// path and that block arms of a `match` with a value do not finish.
+/// Statements that can never run are reported as warnings.
+pub fn check(program: &TypedProgram, diagnostics: &mut Diagnostics) {
+    let mut checker = Checker {
+        diagnostics,
+        loops: Vec::new(),
+    };
+    for function in program.functions.values() {
+        checker.check_function(function);
+    }
+}
+
+struct LoopFrame {
+    label: Option<String>,
+    is_loop: bool,
+    broken: bool,
+}
+
+struct Checker<'d> {
+    diagnostics: &'d mut Diagnostics,
+    loops: Vec<LoopFrame>,
+}
+
+impl Checker<'_> {
+    fn check_function(&mut self, function: &TypedFunction) {
+        let completes = self.check_block(&function.body);
+        if completes && !function.external && !matches!(function.return_type, Ty::Unit | Ty::Error)
+        {
+            self.diagnostics.error(Error::MissingReturn {
+                function: function.name.clone(),
+                location: function.location,
+            });
+        }
+    }
+
+    /// Returns `true` if control can reach the end of `body`.
+    fn check_block(&mut self, body: &[Stmt]) -> bool {
+        let mut completes = true;
+        let mut warned = false;
+        for statement in body {
+            if !completes && !warned {
+                self.diagnostics.warning(Warning::Unreachable {
+                    location: statement.location,
+                });
+                warned = true;
+            }
+            completes &= self.check_statement(statement);
+        }
+        completes
+    }
+
+    fn check_statement(&mut self, statement: &Stmt) -> bool {
+        match &statement.kind {
+            StmtKind::Block(body) => self.check_block(body),
+            StmtKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let condition = self.check_expression(condition);
+                let then = self.check_block(then);
+                let otherwise = otherwise
+                    .as_ref()
+                    .is_none_or(|otherwise| self.check_block(otherwise));
+                condition && (then || otherwise)
+            }
+            StmtKind::For {
+                label,
+                iteration,
+                body,
+                ..
+            } => {
+                let mut completes = true;
+                for operand in iteration.operands() {
+                    completes &= self.check_expression(operand);
+                }
+                self.loops.push(LoopFrame {
+                    label: label.clone(),
+                    is_loop: false,
+                    broken: false,
+                });
+                self.check_block(body);
+                self.loops.pop();
+                completes
+            }
+            StmtKind::Return(value) => {
+                if let Some(value) = value {
+                    self.check_expression(value);
+                }
+                false
+            }
+            StmtKind::Break { label, value } => {
+                if let Some(value) = value {
+                    self.check_expression(value);
+                }
+                let location = statement.location;
+                if let Some(frame) = self.frame("break", label.as_deref(), location) {
+                    frame.broken = true;
+                    if value.is_some() && !frame.is_loop {
+                        self.diagnostics.error(Error::BreakValueInFor { location });
+                    }
+                }
+                false
+            }
+            StmtKind::Continue { label } => {
+                self.frame("continue", label.as_deref(), statement.location);
+                false
+            }
+            StmtKind::Let { value, .. } => value
+                .as_ref()
+                .is_none_or(|value| self.check_expression(value)),
+            StmtKind::Assign { target, value } => {
+                let value = self.check_expression(value);
+                self.check_expression(target) && value
+            }
+            StmtKind::Expr(expression) => self.check_expression(expression),
+        }
+    }
+
+    /// Returns `true` if evaluating `expression` can finish.
+    fn check_expression(&mut self, expression: &Expr) -> bool {
+        match &expression.kind {
+            ExprKind::Int(_)
+            | ExprKind::Bool(_)
+            | ExprKind::Str(_)
+            | ExprKind::Local(_)
+            | ExprKind::SizeOf(_)
+            | ExprKind::Error => true,
+            ExprKind::Unary(_, operand) | ExprKind::Field(operand, _) | ExprKind::Len(operand) => {
+                self.check_expression(operand)
+            }
+            // the right-hand side may be skipped
+            ExprKind::Binary(BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr, lhs, rhs) => {
+                let lhs = self.check_expression(lhs);
+                self.check_expression(rhs);
+                lhs
+            }
+            ExprKind::Binary(_, lhs, rhs) | ExprKind::Index(lhs, rhs) => {
+                let lhs = self.check_expression(lhs);
+                self.check_expression(rhs) && lhs
+            }
+            ExprKind::Call(_, elements)
+            | ExprKind::InterfaceCall(_, _, _, elements)
+            | ExprKind::StructInit(_, elements)
+            | ExprKind::Variant(_, _, elements)
+            | ExprKind::ArrayInit(elements) => {
+                let mut completes = true;
+                for element in elements {
+                    completes &= self.check_expression(element);
+                }
+                completes
+            }
+            // a loop is only left through a break
+            ExprKind::Loop { label, body } => {
+                self.loops.push(LoopFrame {
+                    label: label.clone(),
+                    is_loop: true,
+                    broken: false,
+                });
+                self.check_block(body);
+                self.loops.pop().is_some_and(|frame| frame.broken)
+            }
+            // a match without arms only matches an enum without variants
+            ExprKind::Match { scrutinee, arms } => {
+                let needs_value = !matches!(expression.ty, Ty::Unit | Ty::Error);
+                let scrutinee = self.check_expression(scrutinee);
+                let mut completes = false;
+                for arm in arms {
+                    if let Some(guard) = &arm.guard {
+                        self.check_expression(guard);
+                    }
+                    completes |= match &arm.body {
+                        ArmBody::Expr(value) => self.check_expression(value),
+                        ArmBody::Block(body) => {
+                            let completes = self.check_block(body);
+                            if completes && needs_value {
+                                self.diagnostics.error(Error::ArmWithoutValue {
+                                    location: arm.location,
+                                });
+                            }
+                            completes
+                        }
+                    };
+                }
+                scrutinee && completes
+            }
+        }
+    }
+
+    /// The loop a `break` or `continue` refers to, reporting it if there is
+    /// none.
+    fn frame(
+        &mut self,
+        keyword: &'static str,
+        label: Option<&str>,
+        location: Location,
+    ) -> Option<&mut LoopFrame> {
+        let index = if let Some(label) = label {
+            self.loops
+                .iter()
+                .rposition(|frame| frame.label.as_deref() == Some(label))
+        } else {
+            self.loops.len().checked_sub(1)
+        };
+        if index.is_none() {
+            self.diagnostics.error(match label {
+                Some(label) => Error::UnknownLabel {
+                    label: label.to_string(),
+                    location,
+                },
+                None => Error::OutsideLoop { keyword, location },
+            });
+        }
+        index.map(|index| &mut self.loops[index])
+    }
+}
@@ -0,0 +1,136 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+use crate::sema::types::Ty;
+
+#[derive(Debug, Clone)]
+enum Variable {
+    /// `integer` variables stand for the type of an integer literal and may
+    /// only become `int` or `unsigned int`.
+    Unbound {
+        integer: bool,
+    },
+    Bound(Ty),
+}
+
+/// Type variables of one function body, solved by unification.
+#[derive(Debug, Default)]
+pub struct InferenceTable {
+    variables: Vec<Variable>,
+}
+
+impl InferenceTable {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn fresh(&mut self) -> Ty {
+        self.push(false)
+    }
+
+    pub fn fresh_integer(&mut self) -> Ty {
+        self.push(true)
+    }
+
+    /// Follows bound variables until the outermost type constructor is known
+    /// or an unbound variable is reached.
+    #[must_use]
+    pub fn shallow(&self, ty: &Ty) -> Ty {
+        let mut ty = ty.clone();
+        while let Ty::Var(var) = ty {
+            match &self.variables[var] {
+                Variable::Bound(bound) => ty = bound.clone(),
+                Variable::Unbound { .. } => break,
+            }
+        }
+        ty
+    }
+
+    /// Substitutes all bound variables. Unbound variables are kept.
+    #[must_use]
+    pub fn resolve(&self, ty: &Ty) -> Ty {
+        match self.shallow(ty) {
+            Ty::Pointer(pointee) => Ty::pointer(self.resolve(&pointee)),
+            Ty::Array(element) => Ty::array(self.resolve(&element)),
+            Ty::Struct(id, generics) => {
+                Ty::Struct(id, generics.iter().map(|ty| self.resolve(ty)).collect())
+            }
+            Ty::Enum(id, generics) => {
+                Ty::Enum(id, generics.iter().map(|ty| self.resolve(ty)).collect())
+            }
+            ty => ty,
+        }
+    }
+
+    #[must_use]
+    pub fn is_integer(&self, ty: &Ty) -> bool {
+        match self.shallow(ty) {
+            Ty::Var(var) => matches!(self.variables[var], Variable::Unbound { integer: true }),
+            ty => ty.is_integer(),
+        }
+    }
+
+    /// Makes `a` and `b` equal, binding variables as needed. Returns `false`
+    /// if they cannot be unified.
+    pub fn unify(&mut self, a: &Ty, b: &Ty) -> bool {
+        match (self.shallow(a), self.shallow(b)) {
+            (Ty::Error, _) | (_, Ty::Error) => true,
+            (Ty::Var(a), Ty::Var(b)) if a == b => true,
+            (Ty::Var(a), Ty::Var(b)) => {
+                if self.is_integer_var(a) {
+                    self.variables[b] = Variable::Unbound { integer: true };
+                }
+                self.variables[a] = Variable::Bound(Ty::Var(b));
+                true
+            }
+            (Ty::Var(var), ty) | (ty, Ty::Var(var)) => self.bind(var, ty),
+            (Ty::Pointer(a), Ty::Pointer(b)) | (Ty::Array(a), Ty::Array(b)) => self.unify(&a, &b),
+            (Ty::Struct(a, xs), Ty::Struct(b, ys)) | (Ty::Enum(a, xs), Ty::Enum(b, ys)) => {
+                a == b && xs.len() == ys.len() && xs.iter().zip(&ys).all(|(x, y)| self.unify(x, y))
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    /// Gives every integer literal that is still unconstrained the type `int`.
+    pub fn default_integers(&mut self) {
+        for variable in &mut self.variables {
+            if let Variable::Unbound { integer: true } = variable {
+                *variable = Variable::Bound(Ty::Int);
+            }
+        }
+    }
+
+    fn push(&mut self, integer: bool) -> Ty {
+        self.variables.push(Variable::Unbound { integer });
+        Ty::Var(self.variables.len() - 1)
+    }
+
+    fn is_integer_var(&self, var: usize) -> bool {
+        matches!(self.variables[var], Variable::Unbound { integer: true })
+    }
+
+    fn bind(&mut self, var: usize, ty: Ty) -> bool {
+        if self.is_integer_var(var) && !matches!(ty, Ty::Int | Ty::UInt) {
+            return false;
+        }
+        if self.occurs(var, &ty) {
+            return false;
+        }
+        self.variables[var] = Variable::Bound(ty);
+        true
+    }
+
+    fn occurs(&self, var: usize, ty: &Ty) -> bool {
+        match self.shallow(ty) {
+            Ty::Var(other) => other == var,
+            Ty::Pointer(inner) | Ty::Array(inner) => self.occurs(var, &inner),
+            Ty::Struct(_, generics) | Ty::Enum(_, generics) => {
+                generics.iter().any(|ty| self.occurs(var, ty))
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,302 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+use std::collections::BTreeMap;
+
+use crate::{
+    Error,
+    diagnostics::Diagnostics,
+    module::DefId,
+    parser::tokens::Location,
+    sema::{
+        typed::{self, ExprKind, Field, TypedEnum, TypedProgram, TypedStruct},
+        types::Ty,
+    },
+    target::Target,
+};
+
+/// Size and alignment of `int`, `unsigned int` and pointers in the IR and
+/// the interpreters.
+pub const WORD: u64 = 8;
+
+/// Offset of the length in a fat pointer of eight byte words, which starts
+/// with the address of the first element.
+pub const LENGTH_OFFSET: u64 = WORD;
+
+/// Size and alignment of a type in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    pub size: u64,
+    pub align: u64,
+}
+
+impl Layout {
+    pub const UNIT: Layout = Layout { size: 0, align: 1 };
+    pub const BYTE: Layout = Layout { size: 1, align: 1 };
+    pub const WORD: Layout = Layout {
+        size: WORD,
+        align: WORD,
+    };
+    /// A pointer and a length, used for `str` and arrays.
+    pub const FAT_POINTER: Layout = Layout {
+        size: 2 * WORD,
+        align: WORD,
+    };
+}
+
+/// Layout of a struct, with the offset of every field in declaration order.
+/// Fields are laid out like in C: in order, each at the next offset that
+/// satisfies its alignment, and the size is rounded up to the alignment.
+#[derive(Debug, Clone)]
+pub struct StructLayout {
+    pub layout: Layout,
+    pub offsets: Vec<u64>,
+}
+
+/// Layout of an enum: a tag holding the index of the variant, followed by
+/// the fields of the variant. The variants share the memory after the tag,
+/// each one is laid out like a struct that starts with the tag.
+#[derive(Debug, Clone)]
+pub struct EnumLayout {
+    pub layout: Layout,
+    pub tag: Layout,
+    /// Offsets of the fields of every variant.
+    pub variants: Vec<Vec<u64>>,
+}
+
+impl EnumLayout {
+    /// The smallest unsigned integer that can number `variants`.
+    #[must_use]
+    pub fn tag(variants: usize) -> Layout {
+        let size = match variants {
+            0..=0x100 => 1,
+            0x101..=0x1_0000 => 2,
+            _ => 4,
+        };
+        Layout { size, align: size }
+    }
+}
+
+/// Layouts of all struct and enum types used by a monomorphic program, keyed
+/// by the type with its type arguments, on a target.
+#[derive(Debug, Default)]
+pub struct Layouts {
+    pub target: Target,
+    pub structs: BTreeMap<Ty, StructLayout>,
+    pub enums: BTreeMap<Ty, EnumLayout>,
+}
+
+impl Layouts {
+    #[must_use]
+    pub fn of(&self, ty: &Ty) -> Layout {
+        match ty {
+            Ty::Unit | Ty::Var(_) | Ty::Param(..) | Ty::Error => Layout::UNIT,
+            Ty::Bool => Layout::BYTE,
+            Ty::Int | Ty::UInt => self.target.int(),
+            Ty::Pointer(_) => self.target.pointer(),
+            Ty::Str | Ty::Array(_) => self.fat_pointer(),
+            Ty::Struct(..) => self
+                .structs
+                .get(ty)
+                .map_or(Layout::UNIT, |structure| structure.layout),
+            Ty::Enum(..) => self
+                .enums
+                .get(ty)
+                .map_or(Layout::UNIT, |enumeration| enumeration.layout),
+        }
+    }
+
+    /// Layout of a pointer followed by its length, for `str` and arrays.
+    #[must_use]
+    pub fn fat_pointer(&self) -> Layout {
+        let pointer = self.target.pointer();
+        let length = self.target.int();
+        let align = pointer.align.max(length.align);
+        Layout {
+            size: (self.length_offset() + length.size).next_multiple_of(align),
+            align,
+        }
+    }
+
+    /// Offset of the length in a fat pointer.
+    #[must_use]
+    pub fn length_offset(&self) -> u64 {
+        let pointer = self.target.pointer();
+        pointer.size.next_multiple_of(self.target.int().align)
+    }
+}
+
+/// Computes the layout on `target` of every struct and enum type in the
+/// monomorphic `program` and replaces every `sizeof` by its value. A type
+/// that contains itself by value is reported and gets an empty layout.
+pub fn compute(
+    program: &mut TypedProgram,
+    target: &Target,
+    diagnostics: &mut Diagnostics,
+) -> Layouts {
+    let mut builder = Builder {
+        structs: &program.structs,
+        enums: &program.enums,
+        diagnostics,
+        layouts: Layouts {
+            target: target.clone(),
+            ..Layouts::default()
+        },
+        stack: Vec::new(),
+    };
+    for (id, structure) in &program.structs {
+        if structure.generics.is_empty() {
+            builder.layout(&Ty::structure(*id));
+        }
+    }
+    for (id, enumeration) in &program.enums {
+        if enumeration.generics.is_empty() {
+            builder.layout(&Ty::Enum(*id, Vec::new()));
+        }
+    }
+    for local in &program.locals {
+        builder.layout(&local.ty);
+    }
+    for function in program.functions.values_mut() {
+        typed::for_each_expr_mut(&mut function.body, &mut |expression| {
+            builder.layout(&expression.ty);
+            if let ExprKind::SizeOf(ty) = &expression.kind {
+                expression.kind = ExprKind::Int(builder.layout(ty).size);
+            }
+        });
+    }
+    builder.layouts
+}
+
+struct Builder<'p, 'd> {
+    structs: &'p BTreeMap<DefId, TypedStruct>,
+    enums: &'p BTreeMap<DefId, TypedEnum>,
+    diagnostics: &'d mut Diagnostics,
+    layouts: Layouts,
+    /// Struct and enum types whose layout is being computed.
+    stack: Vec<Ty>,
+}
+
+impl Builder<'_, '_> {
+    fn layout(&mut self, ty: &Ty) -> Layout {
+        match ty {
+            _ if ty.has_params() => self.layouts.of(ty),
+            Ty::Struct(id, generics) => self.layout_struct(ty, *id, generics),
+            Ty::Enum(id, generics) => self.layout_enum(ty, *id, generics),
+            _ => self.layouts.of(ty),
+        }
+    }
+
+    fn layout_struct(&mut self, ty: &Ty, id: DefId, generics: &[Ty]) -> Layout {
+        if let Some(structure) = self.layouts.structs.get(ty) {
+            return structure.layout;
+        }
+        let structure = &self.structs[&id];
+        if self.recursive(ty, &structure.name, structure.location) {
+            self.layouts.structs.insert(
+                ty.clone(),
+                StructLayout {
+                    layout: Layout::UNIT,
+                    offsets: vec![0; structure.fields.len()],
+                },
+            );
+            return Layout::UNIT;
+        }
+
+        self.stack.push(ty.clone());
+        let (offsets, layout) = self.fields(Layout::UNIT, &structure.fields, id, generics);
+        self.stack.pop();
+
+        // a cycle through this struct already stored a placeholder
+        self.layouts
+            .structs
+            .entry(ty.clone())
+            .or_insert(StructLayout { layout, offsets })
+            .layout
+    }
+
+    fn layout_enum(&mut self, ty: &Ty, id: DefId, generics: &[Ty]) -> Layout {
+        if let Some(enumeration) = self.layouts.enums.get(ty) {
+            return enumeration.layout;
+        }
+        let enumeration = &self.enums[&id];
+        let tag = EnumLayout::tag(enumeration.variants.len());
+        if self.recursive(ty, &enumeration.name, enumeration.location) {
+            let variants = enumeration
+                .variants
+                .iter()
+                .map(|variant| vec![0; variant.fields.len()])
+                .collect();
+            self.layouts.enums.insert(
+                ty.clone(),
+                EnumLayout {
+                    layout: Layout::UNIT,
+                    tag,
+                    variants,
+                },
+            );
+            return Layout::UNIT;
+        }
+
+        self.stack.push(ty.clone());
+        let mut layout = tag;
+        let mut variants = Vec::with_capacity(enumeration.variants.len());
+        for variant in &enumeration.variants {
+            let (offsets, variant) = self.fields(tag, &variant.fields, id, generics);
+            layout.size = layout.size.max(variant.size);
+            layout.align = layout.align.max(variant.align);
+            variants.push(offsets);
+        }
+        self.stack.pop();
+
+        layout.size = layout.size.next_multiple_of(layout.align);
+        self.layouts
+            .enums
+            .entry(ty.clone())
+            .or_insert(EnumLayout {
+                layout,
+                tag,
+                variants,
+            })
+            .layout
+    }
+
+    /// Lays out `fields` like a C struct that starts with `head`. Returns
+    /// the offset of every field and the layout of the whole.
+    fn fields(
+        &mut self,
+        head: Layout,
+        fields: &[Field],
+        owner: DefId,
+        generics: &[Ty],
+    ) -> (Vec<u64>, Layout) {
+        let mut size = head.size;
+        let mut align = head.align;
+        let mut offsets = Vec::with_capacity(fields.len());
+        for field in fields {
+            let layout = self.layout(&field.ty.substitute(owner, generics));
+            size = size.next_multiple_of(layout.align);
+            offsets.push(size);
+            size += layout.size;
+            align = align.max(layout.align);
+        }
+        let layout = Layout {
+            size: size.next_multiple_of(align),
+            align,
+        };
+        (offsets, layout)
+    }
+
+    /// Reports `ty` if its layout is already being computed.
+    fn recursive(&mut self, ty: &Ty, name: &str, location: Location) -> bool {
+        if !self.stack.contains(ty) {
+            return false;
+        }
+        self.diagnostics.error(Error::RecursiveType {
+            name: name.to_string(),
+            location,
+        });
+        true
+    }
+}
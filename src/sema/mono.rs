@@ -0,0 +1,308 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use crate::{
+    Error,
+    diagnostics::Diagnostics,
+    module::{DefId, ModuleTree},
+    sema::{
+        resolve::LocalId,
+        typed::{
+            ArmBody, Expr, ExprKind, Instance, Iteration, PatternKind, Stmt, StmtKind,
+            TypedFunction, TypedInterface, TypedLocal, TypedProgram, TypedStruct,
+        },
+        types::Ty,
+    },
+};
+
+/// Type arguments nested deeper than this stop the instantiation, as they
+/// only come from a generic function calling itself with ever larger types.
+const MAX_DEPTH: usize = 32;
+
+/// Instantiates generic functions for the type arguments they are called
+/// with, starting from the functions without type parameters. The result
+/// only contains functions without type parameters; generic functions that
+/// are never called are dropped.
+///
+/// Operators applied to values of a type parameter are checked here, once
+/// the actual type is known, and interface methods called on them are
+/// replaced by the implementing methods.
+#[must_use]
+pub fn monomorphise(
+    tree: &ModuleTree,
+    program: TypedProgram,
+    diagnostics: &mut Diagnostics,
+) -> TypedProgram {
+    let mut queue = VecDeque::new();
+    let mut templates = BTreeMap::new();
+    for (instance, function) in program.functions {
+        if !instance.is_generic() {
+            queue.push_back(instance.clone());
+        }
+        templates.insert(instance.id, function);
+    }
+
+    let mut mono = Mono {
+        tree,
+        structs: &program.structs,
+        interfaces: &program.interfaces,
+        diagnostics,
+        locals: program.locals,
+        queue,
+        owner: DefId(0),
+        generics: Vec::new(),
+        renamed: HashMap::new(),
+    };
+    let mut functions = BTreeMap::new();
+    while let Some(instance) = mono.queue.pop_front() {
+        if functions.contains_key(&instance) {
+            continue;
+        }
+        let function = mono.instantiate(&templates[&instance.id], &instance);
+        functions.insert(instance, function);
+    }
+
+    let locals = mono.locals;
+    TypedProgram {
+        structs: program.structs,
+        enums: program.enums,
+        interfaces: program.interfaces,
+        functions,
+        locals,
+        bindings: program.bindings,
+    }
+}
+
+struct Mono<'t, 'd> {
+    tree: &'t ModuleTree,
+    structs: &'t BTreeMap<DefId, TypedStruct>,
+    interfaces: &'t BTreeMap<DefId, TypedInterface>,
+    diagnostics: &'d mut Diagnostics,
+    locals: Vec<TypedLocal>,
+    queue: VecDeque<Instance>,
+    /// The function being instantiated and its type arguments.
+    owner: DefId,
+    generics: Vec<Ty>,
+    /// Copies of the template's locals for the current instance.
+    renamed: HashMap<LocalId, LocalId>,
+}
+
+impl Mono<'_, '_> {
+    fn instantiate(&mut self, template: &TypedFunction, instance: &Instance) -> TypedFunction {
+        self.owner = instance.id;
+        self.generics.clone_from(&instance.generics);
+        self.renamed.clear();
+
+        let mut function = template.clone();
+        if instance.generics.is_empty() {
+            self.walk_block(&mut function.body);
+            return function;
+        }
+
+        let generics: Vec<String> = instance
+            .generics
+            .iter()
+            .map(|ty| ty.display(self.tree).to_string())
+            .collect();
+        function.name = format!("{}<{}>", template.name, generics.join(", "));
+        if instance.generics.iter().any(|ty| depth(ty) > MAX_DEPTH) {
+            self.diagnostics.error(Error::InstantiationDepth {
+                name: template.name.clone(),
+                location: template.location,
+            });
+            function.body.clear();
+            return function;
+        }
+
+        function.return_type = self.substitute(&function.return_type);
+        for parameter in &mut function.parameters {
+            *parameter = self.local(*parameter);
+        }
+        self.walk_block(&mut function.body);
+        function
+    }
+
+    fn substitute(&self, ty: &Ty) -> Ty {
+        ty.substitute(self.owner, &self.generics)
+    }
+
+    /// The copy of a template local for the current instance.
+    fn local(&mut self, local: LocalId) -> LocalId {
+        if self.generics.is_empty() {
+            return local;
+        }
+        if let Some(renamed) = self.renamed.get(&local) {
+            return *renamed;
+        }
+        let mut copy = self.locals[local.0].clone();
+        copy.ty = self.substitute(&copy.ty);
+        let renamed = LocalId(self.locals.len());
+        self.locals.push(copy);
+        self.renamed.insert(local, renamed);
+        renamed
+    }
+
+    fn walk_block(&mut self, body: &mut [Stmt]) {
+        for statement in body {
+            self.walk_statement(statement);
+        }
+    }
+
+    fn walk_statement(&mut self, statement: &mut Stmt) {
+        match &mut statement.kind {
+            StmtKind::Block(body) => self.walk_block(body),
+            StmtKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.walk_expression(condition);
+                self.walk_block(then);
+                if let Some(otherwise) = otherwise {
+                    self.walk_block(otherwise);
+                }
+            }
+            StmtKind::For {
+                binding,
+                iteration,
+                body,
+                ..
+            } => {
+                *binding = self.local(*binding);
+                match &mut **iteration {
+                    Iteration::Range {
+                        start, end, step, ..
+                    } => {
+                        self.walk_expression(start);
+                        self.walk_expression(end);
+                        if let Some(step) = step {
+                            self.walk_expression(step);
+                        }
+                    }
+                    Iteration::Elements(iterable) => self.walk_expression(iterable),
+                    Iteration::Iterator { iterator, next } => {
+                        self.walk_expression(iterator);
+                        self.walk_expression(next);
+                    }
+                }
+                self.walk_block(body);
+            }
+            StmtKind::Return(value) | StmtKind::Break { value, .. } => {
+                if let Some(value) = value {
+                    self.walk_expression(value);
+                }
+            }
+            StmtKind::Continue { .. } => {}
+            StmtKind::Let { local, value } => {
+                *local = self.local(*local);
+                if let Some(value) = value {
+                    self.walk_expression(value);
+                }
+            }
+            StmtKind::Assign { target, value } => {
+                self.walk_expression(target);
+                self.walk_expression(value);
+            }
+            StmtKind::Expr(expression) => self.walk_expression(expression),
+        }
+    }
+
+    fn walk_expression(&mut self, expression: &mut Expr) {
+        let location = expression.location;
+        match &mut expression.kind {
+            ExprKind::Int(_) | ExprKind::Bool(_) | ExprKind::Str(_) | ExprKind::Error => {}
+            ExprKind::Local(local) => *local = self.local(*local),
+            ExprKind::SizeOf(ty) => *ty = self.substitute(ty),
+            ExprKind::Unary(operator, operand) => {
+                let deferred = operand.ty.has_params();
+                self.walk_expression(operand);
+                if deferred && !operand.ty.supports_unary(*operator) {
+                    self.diagnostics.error(Error::InvalidOperand {
+                        operator: operator.to_string(),
+                        operand: operand.ty.display(self.tree).to_string(),
+                        location,
+                    });
+                }
+            }
+            ExprKind::Binary(operator, lhs, rhs) => {
+                let deferred = lhs.ty.has_params();
+                self.walk_expression(lhs);
+                self.walk_expression(rhs);
+                if deferred && !lhs.ty.supports_binary(*operator) {
+                    self.diagnostics.error(Error::InvalidOperands {
+                        operator: operator.to_string(),
+                        lhs: lhs.ty.display(self.tree).to_string(),
+                        rhs: rhs.ty.display(self.tree).to_string(),
+                        location,
+                    });
+                }
+            }
+            ExprKind::Field(base, _) | ExprKind::Len(base) => self.walk_expression(base),
+            ExprKind::Index(base, index) => {
+                self.walk_expression(base);
+                self.walk_expression(index);
+            }
+            ExprKind::Call(instance, arguments) => {
+                for generic in &mut instance.generics {
+                    *generic = self.substitute(generic);
+                }
+                self.queue.push_back(instance.clone());
+                for argument in arguments {
+                    self.walk_expression(argument);
+                }
+            }
+            ExprKind::InterfaceCall(interface, method, ty, arguments) => {
+                let Ty::Struct(id, _) = self.substitute(ty) else {
+                    unreachable!("only structs implement interfaces")
+                };
+                let name = &self.interfaces[interface].methods[*method].name;
+                let function = self.structs[&id].methods[name];
+                expression.kind = ExprKind::Call(
+                    Instance::new(function, Vec::new()),
+                    std::mem::take(arguments),
+                );
+                return self.walk_expression(expression);
+            }
+            ExprKind::StructInit(_, elements)
+            | ExprKind::Variant(_, _, elements)
+            | ExprKind::ArrayInit(elements) => {
+                for element in elements {
+                    self.walk_expression(element);
+                }
+            }
+            ExprKind::Loop { body, .. } => self.walk_block(body),
+            ExprKind::Match { scrutinee, arms } => {
+                self.walk_expression(scrutinee);
+                for arm in arms {
+                    arm.pattern.for_each_mut(&mut |pattern| {
+                        pattern.ty = self.substitute(&pattern.ty);
+                        if let PatternKind::Binding(local) = &mut pattern.kind {
+                            *local = self.local(*local);
+                        }
+                    });
+                    if let Some(guard) = &mut arm.guard {
+                        self.walk_expression(guard);
+                    }
+                    match &mut arm.body {
+                        ArmBody::Expr(value) => self.walk_expression(value),
+                        ArmBody::Block(body) => self.walk_block(body),
+                    }
+                }
+            }
+        }
+        expression.ty = self.substitute(&expression.ty);
+    }
+}
+
+fn depth(ty: &Ty) -> usize {
+    match ty {
+        Ty::Pointer(inner) | Ty::Array(inner) => 1 + depth(inner),
+        Ty::Struct(_, generics) | Ty::Enum(_, generics) => {
+            1 + generics.iter().map(depth).max().unwrap_or(0)
+        }
+        _ => 0,
+    }
+}
@@ -0,0 +1,331 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+use crate::{
+    Error, Warning,
+    diagnostics::Diagnostics,
+    sema::{
+        typed::{Arm, ArmBody, Expr, ExprKind, Pattern, PatternKind, Stmt, StmtKind, TypedProgram},
+        types::Ty,
+    },
+};
+
+/// Checks that every `match` covers all values of its scrutinee and warns
+/// about arms that can never be reached. Arms with a guard are not counted
+/// as covering anything.
+///
+/// Uses the usefulness algorithm of Maranget, "Warnings for pattern
+/// matching": an arm is reachable if it is useful with respect to the arms
+/// before it, and a match is exhaustive if `_` is not useful after all arms.
+pub fn check(program: &TypedProgram, diagnostics: &mut Diagnostics) {
+    let mut checker = Checker {
+        program,
+        diagnostics,
+    };
+    for function in program.functions.values() {
+        checker.check_block(&function.body);
+    }
+}
+
+/// A pattern reduced to what matters for coverage, bindings are wildcards.
+#[derive(Debug, Clone)]
+enum Pat {
+    Wild,
+    Ctor(Ctor, Vec<Pat>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Ctor {
+    Variant(usize),
+    Struct,
+    Bool(bool),
+    Int(u64),
+    Str(String),
+}
+
+impl Pat {
+    fn lower(pattern: &Pattern) -> Self {
+        let fields = |fields: &[Pattern]| fields.iter().map(Self::lower).collect();
+        match &pattern.kind {
+            PatternKind::Wildcard | PatternKind::Binding(_) => Self::Wild,
+            PatternKind::Int(value) => Self::Ctor(Ctor::Int(*value), Vec::new()),
+            PatternKind::Bool(value) => Self::Ctor(Ctor::Bool(*value), Vec::new()),
+            PatternKind::Str(value) => Self::Ctor(Ctor::Str(value.clone()), Vec::new()),
+            PatternKind::Variant(_, index, subpatterns) => {
+                Self::Ctor(Ctor::Variant(*index), fields(subpatterns))
+            }
+            PatternKind::Struct(_, subpatterns) => Self::Ctor(Ctor::Struct, fields(subpatterns)),
+        }
+    }
+}
+
+type Row = Vec<Pat>;
+
+struct Checker<'p, 'd> {
+    program: &'p TypedProgram,
+    diagnostics: &'d mut Diagnostics,
+}
+
+impl Checker<'_, '_> {
+    fn check_block(&mut self, body: &[Stmt]) {
+        for statement in body {
+            self.check_statement(statement);
+        }
+    }
+
+    fn check_statement(&mut self, statement: &Stmt) {
+        match &statement.kind {
+            StmtKind::Block(body) => self.check_block(body),
+            StmtKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.check_expression(condition);
+                self.check_block(then);
+                if let Some(otherwise) = otherwise {
+                    self.check_block(otherwise);
+                }
+            }
+            StmtKind::For {
+                iteration, body, ..
+            } => {
+                for operand in iteration.operands() {
+                    self.check_expression(operand);
+                }
+                self.check_block(body);
+            }
+            StmtKind::Return(value)
+            | StmtKind::Break { value, .. }
+            | StmtKind::Let { value, .. } => {
+                if let Some(value) = value {
+                    self.check_expression(value);
+                }
+            }
+            StmtKind::Continue { .. } => {}
+            StmtKind::Assign { target, value } => {
+                self.check_expression(target);
+                self.check_expression(value);
+            }
+            StmtKind::Expr(expression) => self.check_expression(expression),
+        }
+    }
+
+    fn check_expression(&mut self, expression: &Expr) {
+        match &expression.kind {
+            ExprKind::Int(_)
+            | ExprKind::Bool(_)
+            | ExprKind::Str(_)
+            | ExprKind::Local(_)
+            | ExprKind::SizeOf(_)
+            | ExprKind::Error => {}
+            ExprKind::Unary(_, operand) | ExprKind::Field(operand, _) | ExprKind::Len(operand) => {
+                self.check_expression(operand);
+            }
+            ExprKind::Binary(_, lhs, rhs) | ExprKind::Index(lhs, rhs) => {
+                self.check_expression(lhs);
+                self.check_expression(rhs);
+            }
+            ExprKind::Call(_, elements)
+            | ExprKind::InterfaceCall(_, _, _, elements)
+            | ExprKind::StructInit(_, elements)
+            | ExprKind::Variant(_, _, elements)
+            | ExprKind::ArrayInit(elements) => {
+                for element in elements {
+                    self.check_expression(element);
+                }
+            }
+            ExprKind::Loop { body, .. } => self.check_block(body),
+            ExprKind::Match { scrutinee, arms } => {
+                self.check_expression(scrutinee);
+                for arm in arms {
+                    if let Some(guard) = &arm.guard {
+                        self.check_expression(guard);
+                    }
+                    match &arm.body {
+                        ArmBody::Expr(value) => self.check_expression(value),
+                        ArmBody::Block(body) => self.check_block(body),
+                    }
+                }
+                self.check_match(expression, &scrutinee.ty, arms);
+            }
+        }
+    }
+
+    fn check_match(&mut self, expression: &Expr, ty: &Ty, arms: &[Arm]) {
+        let tys = [ty.clone()];
+        let mut rows = Vec::new();
+        for arm in arms {
+            let row = vec![Pat::lower(&arm.pattern)];
+            if self.useful(&rows, &row, &tys).is_none() {
+                self.diagnostics.warning(Warning::UnreachablePattern {
+                    location: arm.pattern.location,
+                });
+            }
+            if arm.guard.is_none() {
+                rows.push(row);
+            }
+        }
+
+        if let Some(witness) = self.useful(&rows, &[Pat::Wild], &tys) {
+            self.diagnostics.error(Error::NonExhaustive {
+                missing: self.render(&witness[0], ty),
+                location: expression.location,
+            });
+        }
+    }
+
+    /// Returns a list of values matched by `vector` but by none of `rows`,
+    /// with one pattern per column of `tys`, or `None` if there is none.
+    fn useful(&self, rows: &[Row], vector: &[Pat], tys: &[Ty]) -> Option<Row> {
+        let Some((head, tail)) = vector.split_first() else {
+            return rows.is_empty().then(Vec::new);
+        };
+
+        match head {
+            Pat::Ctor(ctor, fields) => {
+                let mut specialised = fields.clone();
+                specialised.extend_from_slice(tail);
+                self.useful_ctor(rows, ctor, &specialised, tys)
+            }
+            Pat::Wild => {
+                let used: Vec<&Ctor> = rows
+                    .iter()
+                    .filter_map(|row| match &row[0] {
+                        Pat::Ctor(ctor, _) => Some(ctor),
+                        Pat::Wild => None,
+                    })
+                    .collect();
+                let all = self.constructors(&tys[0]);
+                let missing = all
+                    .as_ref()
+                    .map(|all| all.iter().find(|ctor| !used.contains(ctor)));
+
+                if let Some(None) = missing {
+                    // every constructor appears, so try each of them
+                    return all.into_iter().flatten().find_map(|ctor| {
+                        let mut specialised = vec![Pat::Wild; self.fields(&tys[0], &ctor).len()];
+                        specialised.extend_from_slice(tail);
+                        self.useful_ctor(rows, &ctor, &specialised, tys)
+                    });
+                }
+
+                let default: Vec<Row> = rows
+                    .iter()
+                    .filter(|row| matches!(row[0], Pat::Wild))
+                    .map(|row| row[1..].to_vec())
+                    .collect();
+                let mut witness = self.useful(&default, tail, &tys[1..])?;
+                let head = match missing.flatten() {
+                    Some(ctor) => {
+                        let arity = self.fields(&tys[0], ctor).len();
+                        Pat::Ctor(ctor.clone(), vec![Pat::Wild; arity])
+                    }
+                    None => Pat::Wild,
+                };
+                witness.insert(0, head);
+                Some(witness)
+            }
+        }
+    }
+
+    /// Usefulness of a vector starting with `ctor`, whose fields are already
+    /// spliced into `vector`.
+    fn useful_ctor(&self, rows: &[Row], ctor: &Ctor, vector: &[Pat], tys: &[Ty]) -> Option<Row> {
+        let fields = self.fields(&tys[0], ctor);
+        let arity = fields.len();
+        let specialised: Vec<Row> = rows
+            .iter()
+            .filter_map(|row| {
+                let mut specialised = match &row[0] {
+                    Pat::Ctor(other, subpatterns) if other == ctor => subpatterns.clone(),
+                    Pat::Ctor(..) => return None,
+                    Pat::Wild => vec![Pat::Wild; arity],
+                };
+                specialised.extend_from_slice(&row[1..]);
+                Some(specialised)
+            })
+            .collect();
+
+        let mut column_tys = fields;
+        column_tys.extend_from_slice(&tys[1..]);
+        let mut witness = self.useful(&specialised, vector, &column_tys)?;
+        let rest = witness.split_off(arity);
+        let mut result = vec![Pat::Ctor(ctor.clone(), witness)];
+        result.extend(rest);
+        Some(result)
+    }
+
+    /// Every constructor of `ty`, or `None` if there are too many to list.
+    fn constructors(&self, ty: &Ty) -> Option<Vec<Ctor>> {
+        match ty {
+            Ty::Bool => Some(vec![Ctor::Bool(false), Ctor::Bool(true)]),
+            Ty::Struct(..) => Some(vec![Ctor::Struct]),
+            Ty::Enum(id, _) => {
+                let count = self.program.enums[id].variants.len();
+                Some((0..count).map(Ctor::Variant).collect())
+            }
+            _ => None,
+        }
+    }
+
+    /// Types of the fields of `ctor` as a constructor of `ty`.
+    fn fields(&self, ty: &Ty, ctor: &Ctor) -> Vec<Ty> {
+        let (id, generics, fields) = match (ty, ctor) {
+            (Ty::Enum(id, generics), Ctor::Variant(index)) => {
+                let variant = &self.program.enums[id].variants[*index];
+                (id, generics, &variant.fields)
+            }
+            (Ty::Struct(id, generics), Ctor::Struct) => {
+                (id, generics, &self.program.structs[id].fields)
+            }
+            _ => return Vec::new(),
+        };
+        fields
+            .iter()
+            .map(|field| field.ty.substitute(*id, generics))
+            .collect()
+    }
+
+    /// Writes a missing pattern the way it would appear in an arm.
+    fn render(&self, pattern: &Pat, ty: &Ty) -> String {
+        let Pat::Ctor(ctor, subpatterns) = pattern else {
+            return "_".to_string();
+        };
+        let (name, fields, named) = match (ty, ctor) {
+            (Ty::Enum(id, _), Ctor::Variant(index)) => {
+                let enumeration = &self.program.enums[id];
+                let variant = &enumeration.variants[*index];
+                let name = format!("{}::{}", enumeration.name, variant.name);
+                (name, &variant.fields, variant.named)
+            }
+            (Ty::Struct(id, _), Ctor::Struct) => {
+                let structure = &self.program.structs[id];
+                (structure.name.clone(), &structure.fields, true)
+            }
+            (_, Ctor::Bool(value)) => return value.to_string(),
+            (_, Ctor::Int(value)) => return value.to_string(),
+            (_, Ctor::Str(value)) => return format!("{value:?}"),
+            _ => return "_".to_string(),
+        };
+
+        let tys = self.fields(ty, ctor);
+        let rendered = subpatterns
+            .iter()
+            .zip(&tys)
+            .map(|(subpattern, ty)| self.render(subpattern, ty));
+        if fields.is_empty() {
+            name
+        } else if named {
+            let fields: Vec<String> = fields
+                .iter()
+                .zip(rendered)
+                .map(|(field, pattern)| format!("{}: {pattern}", field.name))
+                .collect();
+            format!("{name} {{ {} }}", fields.join(", "))
+        } else {
+            format!("{name}({})", rendered.collect::<Vec<_>>().join(", "))
+        }
+    }
+}
@@ -0,0 +1,612 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+use std::collections::HashMap;
+
+use crate::{
+    Error, Warning,
+    diagnostics::Diagnostics,
+    module::{DefId, DefKind, ModuleId, ModuleTree},
+    parser::{
+        ast::{
+            ArmBody, BaseType, Declaration, Expression, ExpressionKind, Function, GenericParam,
+            Member, Path, Pattern, PatternKind, Statement, StatementKind, Type, UnaryOperator,
+            VariantFields,
+        },
+        tokens::Location,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LocalId(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Symbol {
+    Local(LocalId),
+    Definition(DefId),
+    /// Type parameter with the given index of a generic struct, enum or
+    /// function.
+    Param(DefId, usize),
+    /// Variant with the given index of an enum.
+    Variant(DefId, usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalKind {
+    Receiver,
+    Parameter,
+    Var,
+    Val,
+    ForBinding,
+    /// Bound by a pattern of a `match` arm.
+    PatternBinding,
+}
+
+#[derive(Debug, Clone)]
+pub struct Local {
+    pub name: String,
+    pub kind: LocalKind,
+    pub location: Location,
+    pub function: DefId,
+}
+
+/// Links every name in the program to its declaration.
+///
+/// `uses` is keyed by the location of the path that names something, be it
+/// in an expression, a type, a struct initialisation or a pattern.
+/// `declarations` maps the location of a parameter, `var`/`val` name, `for`
+/// binding or pattern binding to the local it introduces.
+#[derive(Debug, Default)]
+pub struct Resolution {
+    pub locals: Vec<Local>,
+    pub uses: HashMap<Location, Symbol>,
+    pub declarations: HashMap<Location, LocalId>,
+}
+
+impl Resolution {
+    #[must_use]
+    pub fn local(&self, id: LocalId) -> &Local {
+        &self.locals[id.0]
+    }
+
+    #[must_use]
+    pub fn symbol(&self, location: Location) -> Option<Symbol> {
+        self.uses.get(&location).copied()
+    }
+}
+
+/// Resolves all names in `tree`, reporting problems to `diagnostics`.
+#[must_use]
+pub fn resolve(tree: &ModuleTree, diagnostics: &mut Diagnostics) -> Resolution {
+    let mut resolver = Resolver {
+        tree,
+        diagnostics,
+        resolution: Resolution::default(),
+        scopes: Vec::new(),
+        generics: Vec::new(),
+        module: ModuleTree::ROOT,
+        function: DefId(0),
+    };
+
+    for (index, definition) in tree.definitions.iter().enumerate() {
+        resolver.module = definition.module;
+        match &definition.kind {
+            DefKind::Struct(structure) => {
+                resolver.enter_generics(DefId(index), &structure.generics);
+                for member in &structure.members {
+                    resolver.resolve_type(&member.ty);
+                }
+            }
+            DefKind::Enum(enumeration) => {
+                resolver.enter_generics(DefId(index), &enumeration.generics);
+                for variant in &enumeration.variants {
+                    match &variant.fields {
+                        VariantFields::Unit => {}
+                        VariantFields::Tuple(types) => {
+                            for ty in types {
+                                resolver.resolve_type(ty);
+                            }
+                        }
+                        VariantFields::Named(members) => {
+                            for member in members {
+                                resolver.resolve_type(&member.ty);
+                            }
+                        }
+                    }
+                }
+            }
+            DefKind::Interface(interface) => {
+                resolver.enter_generics(DefId(index), &[]);
+                for method in &interface.methods {
+                    resolver.resolve_signature(&method.function);
+                }
+            }
+            DefKind::Function(function) => resolver.resolve_function(DefId(index), function),
+            DefKind::Namespace(_) => {}
+        }
+    }
+
+    for block in &tree.impls {
+        resolver.module = block.module;
+        resolver.generics.clear();
+        resolver.resolve_interface(&block.interface);
+        resolver.resolve_type(&block.target);
+    }
+
+    resolver.resolution
+}
+
+struct Resolver<'t, 'd> {
+    tree: &'t ModuleTree,
+    diagnostics: &'d mut Diagnostics,
+    resolution: Resolution,
+    scopes: Vec<HashMap<String, LocalId>>,
+    /// Type parameters of the struct or function being resolved.
+    generics: Vec<String>,
+    module: ModuleId,
+    function: DefId,
+}
+
+impl Resolver<'_, '_> {
+    fn enter_generics(&mut self, owner: DefId, generics: &[GenericParam]) {
+        self.function = owner;
+        self.generics.clear();
+        for generic in generics {
+            if self.generics.contains(&generic.name) {
+                self.diagnostics.error(Error::DuplicateDefinition {
+                    name: generic.name.clone(),
+                    location: generic.location,
+                });
+            }
+            self.generics.push(generic.name.clone());
+        }
+    }
+
+    fn resolve_function(&mut self, id: DefId, function: &Function) {
+        self.enter_generics(id, &function.generics);
+        for generic in &function.generics {
+            for bound in &generic.bounds {
+                self.resolve_interface(bound);
+            }
+        }
+        self.scopes.push(HashMap::new());
+
+        if let Some(receiver) = &function.receiver {
+            self.resolve_type(&receiver.ty);
+            self.declare("self", LocalKind::Receiver, receiver.location);
+        }
+        for Member { name, ty, location } in &function.parameters {
+            self.resolve_type(ty);
+            self.declare(name, LocalKind::Parameter, *location);
+        }
+        if let Some(ty) = &function.return_type {
+            self.resolve_type(ty);
+        }
+
+        self.resolve_block(&function.body);
+        self.scopes.pop();
+    }
+
+    /// Resolves the types of an interface method, which declares no locals.
+    fn resolve_signature(&mut self, function: &Function) {
+        let receiver = function.receiver.iter().map(|receiver| &receiver.ty);
+        let parameters = function.parameters.iter().map(|parameter| &parameter.ty);
+        for ty in receiver.chain(parameters).chain(&function.return_type) {
+            self.resolve_type(ty);
+        }
+    }
+
+    fn resolve_block(&mut self, body: &[Statement]) {
+        self.scopes.push(HashMap::new());
+        for statement in body {
+            self.resolve_statement(statement);
+        }
+        self.scopes.pop();
+    }
+
+    fn resolve_statement(&mut self, statement: &Statement) {
+        match &statement.kind {
+            StatementKind::Compound(body) => self.resolve_block(body),
+            StatementKind::If(branch) => {
+                self.resolve_expression(&branch.condition);
+                self.resolve_block(&branch.then);
+                if let Some(otherwise) = &branch.otherwise {
+                    self.resolve_block(otherwise);
+                }
+            }
+            StatementKind::For(for_loop) => {
+                self.resolve_expression(&for_loop.iterable);
+                self.scopes.push(HashMap::new());
+                self.declare(
+                    &for_loop.binding,
+                    LocalKind::ForBinding,
+                    for_loop.binding_location,
+                );
+                self.resolve_block(&for_loop.body);
+                self.scopes.pop();
+            }
+            StatementKind::Return(value) | StatementKind::Break { value, .. } => {
+                if let Some(value) = value {
+                    self.resolve_expression(value);
+                }
+            }
+            StatementKind::Continue { .. } => {}
+            StatementKind::VarDecl(declaration) => {
+                self.resolve_declaration(declaration, LocalKind::Var);
+            }
+            StatementKind::ValDecl(declaration) => {
+                self.resolve_declaration(declaration, LocalKind::Val);
+            }
+            StatementKind::Assign { target, value } => {
+                self.resolve_expression(target);
+                self.resolve_expression(value);
+            }
+            StatementKind::Expression(expression) => self.resolve_expression(expression),
+        }
+    }
+
+    fn resolve_declaration(&mut self, declaration: &Declaration, kind: LocalKind) {
+        if let Some(ty) = &declaration.ty {
+            self.resolve_type(ty);
+        }
+        // the initialiser cannot see the name it initialises
+        if let Some(value) = &declaration.value {
+            self.resolve_expression(value);
+        }
+        self.declare(&declaration.name, kind, declaration.location);
+    }
+
+    fn resolve_expression(&mut self, expression: &Expression) {
+        match &expression.kind {
+            ExpressionKind::Identifier(path) => self.resolve_value(path, false),
+            ExpressionKind::Literal(_) => {}
+            ExpressionKind::Unary(UnaryOperator::Sizeof, operand) => {
+                if let ExpressionKind::Identifier(path) = &operand.kind {
+                    self.resolve_value(path, true);
+                } else {
+                    self.resolve_expression(operand);
+                }
+            }
+            ExpressionKind::Unary(_, operand) | ExpressionKind::Member(operand, _) => {
+                self.resolve_expression(operand);
+            }
+            ExpressionKind::Binary(_, lhs, rhs) | ExpressionKind::Index(lhs, rhs) => {
+                self.resolve_expression(lhs);
+                self.resolve_expression(rhs);
+            }
+            ExpressionKind::Call(callee, arguments) => {
+                self.resolve_expression(callee);
+                for argument in arguments {
+                    self.resolve_expression(argument);
+                }
+            }
+            ExpressionKind::StructInit(path, fields) => {
+                self.resolve_constructor(path);
+                for field in fields {
+                    self.resolve_expression(&field.value);
+                }
+            }
+            ExpressionKind::ArrayInit(elements) => {
+                for element in elements {
+                    self.resolve_expression(element);
+                }
+            }
+            ExpressionKind::Loop { body, .. } => self.resolve_block(body),
+            ExpressionKind::Match { scrutinee, arms } => {
+                self.resolve_expression(scrutinee);
+                for arm in arms {
+                    self.scopes.push(HashMap::new());
+                    self.resolve_pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.resolve_expression(guard);
+                    }
+                    match &arm.body {
+                        ArmBody::Expression(value) => self.resolve_expression(value),
+                        ArmBody::Block(body) => self.resolve_block(body),
+                    }
+                    self.scopes.pop();
+                }
+            }
+            ExpressionKind::Range {
+                start, end, step, ..
+            } => {
+                self.resolve_expression(start);
+                self.resolve_expression(end);
+                if let Some(step) = step {
+                    self.resolve_expression(step);
+                }
+            }
+        }
+    }
+
+    fn resolve_pattern(&mut self, pattern: &Pattern) {
+        match &pattern.kind {
+            PatternKind::Wildcard | PatternKind::Literal { .. } => {}
+            PatternKind::Binding(name) => {
+                self.declare(name, LocalKind::PatternBinding, pattern.location);
+            }
+            PatternKind::Tuple(path, patterns) => {
+                for generic in &path.generics {
+                    self.resolve_type(generic);
+                }
+                if let Some(id) = self.enum_prefix(path) {
+                    self.resolve_variant(id, path);
+                } else if self.resolve_item(path).is_some() {
+                    self.wrong_kind(path, "a variant");
+                }
+                for pattern in patterns {
+                    self.resolve_pattern(pattern);
+                }
+            }
+            PatternKind::Struct { path, fields, .. } => {
+                self.resolve_constructor(path);
+                for field in fields {
+                    self.resolve_pattern(&field.pattern);
+                }
+            }
+        }
+    }
+
+    fn resolve_type(&mut self, ty: &Type) {
+        let BaseType::Named(path) = &ty.base else {
+            return;
+        };
+        if let [name] = path.segments.as_slice()
+            && let Some(index) = self.generics.iter().position(|generic| generic == name)
+        {
+            self.resolution
+                .uses
+                .insert(path.location, Symbol::Param(self.function, index));
+            for generic in &path.generics {
+                self.resolve_type(generic);
+            }
+            return;
+        }
+        self.resolve_struct(path);
+    }
+
+    /// Resolves the name of a struct or enum type.
+    fn resolve_struct(&mut self, path: &Path) {
+        for generic in &path.generics {
+            self.resolve_type(generic);
+        }
+        if let Some(id) = self.resolve_item(path) {
+            if matches!(
+                self.tree.definition(id).kind,
+                DefKind::Struct(_) | DefKind::Enum(_)
+            ) {
+                self.resolution
+                    .uses
+                    .insert(path.location, Symbol::Definition(id));
+            } else {
+                self.wrong_kind(path, "a type");
+            }
+        }
+    }
+
+    fn resolve_interface(&mut self, path: &Path) {
+        if let Some(id) = self.resolve_item(path) {
+            if matches!(self.tree.definition(id).kind, DefKind::Interface(_)) {
+                self.resolution
+                    .uses
+                    .insert(path.location, Symbol::Definition(id));
+            } else {
+                self.wrong_kind(path, "an interface");
+            }
+        }
+    }
+
+    /// Resolves the path of a struct initialisation or struct pattern, which
+    /// names a struct or an enum variant with named fields.
+    fn resolve_constructor(&mut self, path: &Path) {
+        for generic in &path.generics {
+            self.resolve_type(generic);
+        }
+        if let Some(id) = self.enum_prefix(path) {
+            self.resolve_variant(id, path);
+        } else if let Some(id) = self.resolve_item(path) {
+            if matches!(self.tree.definition(id).kind, DefKind::Struct(_)) {
+                self.resolution
+                    .uses
+                    .insert(path.location, Symbol::Definition(id));
+            } else {
+                self.wrong_kind(path, "a struct");
+            }
+        }
+    }
+
+    /// The enum named by all but the last segment of `path`, if any.
+    fn enum_prefix(&self, path: &Path) -> Option<DefId> {
+        let (_, prefix) = path.segments.split_last()?;
+        if prefix.is_empty() {
+            return None;
+        }
+        let prefix = Path {
+            segments: prefix.to_vec(),
+            generics: Vec::new(),
+            location: path.location,
+        };
+        let id = self.tree.resolve_path(self.module, &prefix).ok()?;
+        matches!(self.tree.definition(id).kind, DefKind::Enum(_)).then_some(id)
+    }
+
+    /// Resolves the last segment of `path` to a variant of `enumeration`.
+    fn resolve_variant(&mut self, enumeration: DefId, path: &Path) {
+        let DefKind::Enum(definition) = &self.tree.definition(enumeration).kind else {
+            unreachable!("enum_prefix only returns enums")
+        };
+        match definition
+            .variants
+            .iter()
+            .position(|variant| variant.name == path.name())
+        {
+            Some(index) => {
+                self.resolution
+                    .uses
+                    .insert(path.location, Symbol::Variant(enumeration, index));
+            }
+            None => self.diagnostics.error(Error::UnresolvedPath {
+                path: path.to_string(),
+                location: path.location,
+            }),
+        }
+    }
+
+    /// Resolves a path in value position. `sizeof` also accepts a type name.
+    fn resolve_value(&mut self, path: &Path, allow_type: bool) {
+        for generic in &path.generics {
+            self.resolve_type(generic);
+        }
+        if allow_type
+            && let [name] = path.segments.as_slice()
+            && let Some(index) = self.generics.iter().position(|generic| generic == name)
+        {
+            self.resolution
+                .uses
+                .insert(path.location, Symbol::Param(self.function, index));
+            return;
+        }
+        if let [name] = path.segments.as_slice()
+            && let Some(local) = self.lookup_local(name)
+        {
+            self.resolution
+                .uses
+                .insert(path.location, Symbol::Local(local));
+            return;
+        }
+        if let Some(id) = self.enum_prefix(path) {
+            return self.resolve_variant(id, path);
+        }
+
+        if let Some(id) = self.resolve_item(path) {
+            match self.tree.definition(id).kind {
+                DefKind::Function(_) => {}
+                DefKind::Struct(_) | DefKind::Enum(_) if allow_type => {}
+                _ => return self.wrong_kind(path, "a value"),
+            }
+            self.resolution
+                .uses
+                .insert(path.location, Symbol::Definition(id));
+        }
+    }
+
+    fn resolve_item(&mut self, path: &Path) -> Option<DefId> {
+        match self.tree.resolve_path(self.module, path) {
+            Ok(id) => Some(id),
+            Err(Error::UnresolvedPath { .. }) if path.segments.len() == 1 => {
+                let name = path.name().to_string();
+                let suggestion = suggest(&name, self.visible_names());
+                self.diagnostics.error(Error::UndeclaredName {
+                    name,
+                    suggestion,
+                    location: path.location,
+                });
+                None
+            }
+            Err(error) => {
+                self.diagnostics.error(error);
+                None
+            }
+        }
+    }
+
+    fn declare(&mut self, name: &str, kind: LocalKind, location: Location) {
+        let id = LocalId(self.resolution.locals.len());
+
+        if let Some(&previous) = self.scopes.last().and_then(|scope| scope.get(name)) {
+            self.diagnostics.error(Error::DuplicateDefinition {
+                name: name.to_string(),
+                location,
+            });
+            // keep the earlier declaration so later uses stay consistent
+            self.resolution.declarations.insert(location, previous);
+            return;
+        }
+        if let Some(previous) = self.lookup_local(name) {
+            self.diagnostics.warning(Warning::Shadowing {
+                name: name.to_string(),
+                location,
+                previous: self.resolution.local(previous).location,
+            });
+        }
+
+        self.resolution.locals.push(Local {
+            name: name.to_string(),
+            kind,
+            location,
+            function: self.function,
+        });
+        self.resolution.declarations.insert(location, id);
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), id);
+        }
+    }
+
+    fn lookup_local(&self, name: &str) -> Option<LocalId> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+
+    fn visible_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .scopes
+            .iter()
+            .flat_map(|scope| scope.keys().map(String::as_str))
+            .collect();
+
+        let mut module = Some(self.module);
+        while let Some(id) = module {
+            let current = self.tree.module(id);
+            names.extend(current.items.keys().map(String::as_str));
+            names.extend(current.imports.keys().map(String::as_str));
+            module = current.parent;
+        }
+        names
+    }
+
+    fn wrong_kind(&mut self, path: &Path, expected: &'static str) {
+        self.diagnostics.error(Error::WrongKind {
+            path: path.to_string(),
+            expected,
+            location: path.location,
+        });
+    }
+}
+
+/// Picks the visible name closest to `name`, if any is close enough to be a
+/// plausible typo.
+fn suggest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let limit = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min()
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+/// Optimal string alignment distance: insertions, deletions, substitutions
+/// and swaps of adjacent characters each cost one.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut before: Vec<usize> = Vec::new();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            let mut distance = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+            if i > 0 && j > 0 && *ca == b[j - 1] && a[i - 1] == *cb {
+                distance = distance.min(before[j - 1] + 1);
+            }
+            current.push(distance);
+        }
+        before = std::mem::replace(&mut previous, current);
+    }
+    previous[b.len()]
+}
@@ -0,0 +1,2029 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use crate::{
+    Error,
+    diagnostics::Diagnostics,
+    module::{DefId, DefKind, Impl, ModuleId, ModuleTree},
+    parser::{
+        ast::{
+            self, BaseType, BinaryOperator, Declaration, Expression, ExpressionKind, FieldInit,
+            Literal, MatchArm, Member, Path, Statement, StatementKind, UnaryOperator,
+            VariantFields, Visibility,
+        },
+        tokens::Location,
+    },
+    sema::{
+        infer::InferenceTable,
+        resolve::{Resolution, Symbol},
+        typed::{
+            self, Arm, ArmBody, Expr, ExprKind, Field, Instance, InterfaceMethod, Iteration, Stmt,
+            StmtKind, TypedEnum, TypedFunction, TypedInterface, TypedLocal, TypedProgram,
+            TypedStruct, TypedVariant,
+        },
+        types::Ty,
+    },
+};
+
+#[derive(PartialEq)]
+struct Signature {
+    receiver: Option<Ty>,
+    parameters: Vec<Ty>,
+    return_type: Ty,
+}
+
+struct LoopFrame {
+    label: Option<String>,
+    is_loop: bool,
+    break_type: Option<Ty>,
+}
+
+/// Type checks the resolved program and builds the [`TypedProgram`].
+#[must_use]
+pub fn check(
+    tree: &ModuleTree,
+    resolution: &Resolution,
+    diagnostics: &mut Diagnostics,
+) -> TypedProgram {
+    let mut checker = Checker {
+        tree,
+        resolution,
+        diagnostics,
+        structs: BTreeMap::new(),
+        enums: BTreeMap::new(),
+        interfaces: BTreeMap::new(),
+        signatures: HashMap::new(),
+        self_types: HashMap::new(),
+        self_type: None,
+        module: ModuleTree::ROOT,
+        locals: vec![None; resolution.locals.len()],
+        return_type: Ty::Unit,
+        loops: Vec::new(),
+        table: InferenceTable::new(),
+    };
+
+    for (index, definition) in tree.definitions.iter().enumerate() {
+        match &definition.kind {
+            DefKind::Struct(structure) => {
+                let typed = checker.check_struct(DefId(index), structure);
+                checker.structs.insert(DefId(index), typed);
+            }
+            DefKind::Enum(enumeration) => {
+                let typed = checker.check_enum(DefId(index), enumeration);
+                checker.enums.insert(DefId(index), typed);
+            }
+            DefKind::Namespace(_) | DefKind::Interface(_) | DefKind::Function(_) => {}
+        }
+    }
+
+    for (index, definition) in tree.definitions.iter().enumerate() {
+        if let DefKind::Interface(interface) = &definition.kind {
+            let typed = checker.check_interface(DefId(index), interface);
+            checker.interfaces.insert(DefId(index), typed);
+        }
+    }
+    let targets: Vec<Ty> = tree
+        .impls
+        .iter()
+        .map(|block| checker.check_impl_target(block))
+        .collect();
+
+    for (index, definition) in tree.definitions.iter().enumerate() {
+        if let DefKind::Function(function) = &definition.kind {
+            checker.self_type = checker.self_types.get(&DefId(index)).cloned();
+            let signature = checker.check_signature(function);
+            checker.signatures.insert(DefId(index), signature);
+            checker.attach_method(DefId(index), function);
+        }
+    }
+    for (block, target) in tree.impls.iter().zip(&targets) {
+        checker.check_impl(block, target);
+    }
+
+    let mut functions = BTreeMap::new();
+    for (index, definition) in tree.definitions.iter().enumerate() {
+        if let DefKind::Function(function) = &definition.kind {
+            let id = DefId(index);
+            let typed = checker.check_function(id, function, definition.location);
+            let generics = (0..function.generics.len())
+                .map(|index| Ty::Param(id, index))
+                .collect();
+            functions.insert(Instance::new(id, generics), typed);
+        }
+    }
+
+    let locals = resolution
+        .locals
+        .iter()
+        .zip(checker.locals)
+        .map(|(local, ty)| TypedLocal {
+            name: local.name.clone(),
+            kind: local.kind,
+            ty: ty.unwrap_or(Ty::Error),
+            location: local.location,
+        })
+        .collect();
+
+    let bindings = resolution
+        .declarations
+        .iter()
+        .map(|(location, local)| (*location, *local))
+        .chain(
+            resolution
+                .uses
+                .iter()
+                .filter_map(|(location, symbol)| match symbol {
+                    Symbol::Local(local) => Some((*location, *local)),
+                    Symbol::Definition(_) | Symbol::Param(..) | Symbol::Variant(..) => None,
+                }),
+        )
+        .collect();
+
+    TypedProgram {
+        structs: checker.structs,
+        enums: checker.enums,
+        interfaces: checker.interfaces,
+        functions,
+        locals,
+        bindings,
+    }
+}
+
+struct Checker<'a> {
+    tree: &'a ModuleTree,
+    resolution: &'a Resolution,
+    diagnostics: &'a mut Diagnostics,
+    structs: BTreeMap<DefId, TypedStruct>,
+    enums: BTreeMap<DefId, TypedEnum>,
+    interfaces: BTreeMap<DefId, TypedInterface>,
+    signatures: HashMap<DefId, Signature>,
+    /// The type `Self` stands for in every method of an `impl` block.
+    self_types: HashMap<DefId, Ty>,
+    /// What `Self` stands for where types are lowered, if anywhere.
+    self_type: Option<Ty>,
+    /// Namespace of the function being checked.
+    module: ModuleId,
+    locals: Vec<Option<Ty>>,
+    return_type: Ty,
+    loops: Vec<LoopFrame>,
+    table: InferenceTable,
+}
+
+impl Checker<'_> {
+    fn check_struct(&mut self, id: DefId, structure: &ast::Struct) -> TypedStruct {
+        TypedStruct {
+            name: self.tree.qualified_name(id),
+            generics: structure
+                .generics
+                .iter()
+                .map(|generic| generic.name.clone())
+                .collect(),
+            fields: self.check_members(&structure.members),
+            methods: BTreeMap::new(),
+            interfaces: BTreeSet::new(),
+            location: self.tree.definition(id).location,
+        }
+    }
+
+    fn check_enum(&mut self, id: DefId, enumeration: &ast::Enum) -> TypedEnum {
+        let mut variants: Vec<TypedVariant> = Vec::new();
+        for variant in &enumeration.variants {
+            if variants.iter().any(|other| other.name == variant.name) {
+                self.diagnostics.error(Error::DuplicateDefinition {
+                    name: variant.name.clone(),
+                    location: variant.location,
+                });
+            }
+            let (fields, named) = match &variant.fields {
+                VariantFields::Unit => (Vec::new(), false),
+                VariantFields::Tuple(types) => {
+                    let fields = types
+                        .iter()
+                        .enumerate()
+                        .map(|(index, ty)| Field {
+                            name: index.to_string(),
+                            ty: self.lower_type(ty),
+                        })
+                        .collect();
+                    (fields, false)
+                }
+                VariantFields::Named(members) => (self.check_members(members), true),
+            };
+            variants.push(TypedVariant {
+                name: variant.name.clone(),
+                fields,
+                named,
+            });
+        }
+
+        TypedEnum {
+            name: self.tree.qualified_name(id),
+            generics: enumeration
+                .generics
+                .iter()
+                .map(|generic| generic.name.clone())
+                .collect(),
+            variants,
+            location: self.tree.definition(id).location,
+        }
+    }
+
+    fn check_members(&mut self, members: &[Member]) -> Vec<Field> {
+        let mut fields: Vec<Field> = Vec::new();
+        for member in members {
+            if fields.iter().any(|field| field.name == member.name) {
+                self.diagnostics.error(Error::DuplicateDefinition {
+                    name: member.name.clone(),
+                    location: member.location,
+                });
+                continue;
+            }
+            fields.push(Field {
+                name: member.name.clone(),
+                ty: self.lower_type(&member.ty),
+            });
+        }
+        fields
+    }
+
+    /// Checks the methods of an interface, in which `Self` is the
+    /// implementing type.
+    fn check_interface(&mut self, id: DefId, interface: &ast::Interface) -> TypedInterface {
+        let name = self.tree.qualified_name(id);
+        let this = Ty::Param(id, 0);
+        self.self_type = Some(this.clone());
+
+        let mut methods: Vec<InterfaceMethod> = Vec::new();
+        for method in &interface.methods {
+            let function = &method.function;
+            if methods.iter().any(|other| other.name == function.name) {
+                self.diagnostics.error(Error::DuplicateDefinition {
+                    name: function.name.clone(),
+                    location: method.location,
+                });
+            }
+            if !function.generics.is_empty() {
+                self.diagnostics.error(Error::GenericInterfaceMethod {
+                    location: method.location,
+                });
+            }
+
+            let signature = self.check_signature(function);
+            let receiver = match (&signature.receiver, &function.receiver) {
+                (Some(ty), _) if *ty == this || *ty == Ty::pointer(this.clone()) => ty.clone(),
+                (Some(ty), Some(receiver)) => {
+                    if !ty.is_error() {
+                        self.diagnostics.error(Error::InterfaceReceiver {
+                            ty: self.show(ty),
+                            location: receiver.ty.location,
+                        });
+                    }
+                    Ty::Error
+                }
+                _ => {
+                    self.diagnostics.error(Error::MissingReceiver {
+                        function: format!("{name}::{}", function.name),
+                        location: method.location,
+                    });
+                    Ty::Error
+                }
+            };
+            methods.push(InterfaceMethod {
+                name: function.name.clone(),
+                receiver,
+                parameters: signature.parameters,
+                return_type: signature.return_type,
+                location: method.location,
+            });
+        }
+
+        self.self_type = None;
+        TypedInterface {
+            name,
+            methods,
+            location: self.tree.definition(id).location,
+        }
+    }
+
+    /// Lowers the type an `impl` block is for, which `Self` stands for in
+    /// its methods.
+    fn check_impl_target(&mut self, block: &Impl) -> Ty {
+        let ty = match self.lower_type(&block.target) {
+            Ty::Struct(id, generics) if generics.is_empty() => Ty::Struct(id, generics),
+            Ty::Error => Ty::Error,
+            ty => {
+                self.diagnostics.error(Error::InvalidImplTarget {
+                    ty: self.show(&ty),
+                    location: block.target.location,
+                });
+                Ty::Error
+            }
+        };
+        for method in &block.methods {
+            self.self_types.insert(*method, ty.clone());
+        }
+        ty
+    }
+
+    /// Checks that an `impl` block is the only one of its interface for its
+    /// struct and defines exactly the declared methods.
+    fn check_impl(&mut self, block: &Impl, target: &Ty) {
+        let Some(Symbol::Definition(interface)) = self.resolution.symbol(block.interface.location)
+        else {
+            return;
+        };
+        let Ty::Struct(structure, _) = target else {
+            return;
+        };
+        let name = self.interfaces[&interface].name.clone();
+        let interfaces = &mut self
+            .structs
+            .get_mut(structure)
+            .expect("struct was checked")
+            .interfaces;
+        if !interfaces.insert(interface) {
+            self.diagnostics.error(Error::DuplicateImpl {
+                interface: name,
+                ty: self.show(target),
+                location: block.location,
+            });
+            return;
+        }
+
+        let generics = std::slice::from_ref(target);
+        let mut missing: Vec<String> = self.interfaces[&interface]
+            .methods
+            .iter()
+            .map(|method| method.name.clone())
+            .collect();
+        for &method in &block.methods {
+            let definition = self.tree.definition(method);
+            let Some(index) = self.interfaces[&interface].method(&definition.name) else {
+                self.diagnostics.error(Error::NotInInterface {
+                    method: definition.name.clone(),
+                    interface: name.clone(),
+                    location: definition.location,
+                });
+                continue;
+            };
+            missing.retain(|other| *other != definition.name);
+
+            let declared = &self.interfaces[&interface].methods[index];
+            let expected = Signature {
+                receiver: Some(declared.receiver.substitute(interface, generics)),
+                parameters: declared
+                    .parameters
+                    .iter()
+                    .map(|parameter| parameter.substitute(interface, generics))
+                    .collect(),
+                return_type: declared.return_type.substitute(interface, generics),
+            };
+            if !definition.generics().is_empty() {
+                self.diagnostics.error(Error::GenericInterfaceMethod {
+                    location: definition.location,
+                });
+            } else if self.signatures[&method] != expected {
+                self.diagnostics.error(Error::ImplSignature {
+                    method: definition.name.clone(),
+                    interface: name.clone(),
+                    expected: self.show_signature(&expected),
+                    location: definition.location,
+                });
+            }
+        }
+
+        if !missing.is_empty() {
+            self.diagnostics.error(Error::MissingMethods {
+                interface: name,
+                ty: self.show(target),
+                methods: missing.join(", "),
+                location: block.location,
+            });
+        }
+    }
+
+    fn check_signature(&mut self, function: &ast::Function) -> Signature {
+        let receiver = function.receiver.as_ref().map(|receiver| {
+            let ty = self.lower_type(&receiver.ty);
+            self.declare(receiver.location, ty.clone());
+            ty
+        });
+        let parameters = function
+            .parameters
+            .iter()
+            .map(|parameter| {
+                let ty = self.lower_type(&parameter.ty);
+                self.declare(parameter.location, ty.clone());
+                ty
+            })
+            .collect();
+        let return_type = function
+            .return_type
+            .as_ref()
+            .map_or(Ty::Unit, |ty| self.lower_type(ty));
+
+        Signature {
+            receiver,
+            parameters,
+            return_type,
+        }
+    }
+
+    /// Adds a function with a receiver to the methods of its struct.
+    fn attach_method(&mut self, id: DefId, function: &ast::Function) {
+        let Some(receiver) = &self.signatures[&id].receiver else {
+            if self.self_types.contains_key(&id) {
+                self.diagnostics.error(Error::MissingReceiver {
+                    function: function.name.clone(),
+                    location: self.tree.definition(id).location,
+                });
+            }
+            return;
+        };
+        let Some(structure) = receiver_struct(receiver) else {
+            if !receiver.is_error() {
+                self.diagnostics.error(Error::InvalidReceiver {
+                    ty: self.show(receiver),
+                    location: function
+                        .receiver
+                        .as_ref()
+                        .expect("signature has a receiver")
+                        .ty
+                        .location,
+                });
+            }
+            return;
+        };
+        let methods = &mut self
+            .structs
+            .get_mut(&structure)
+            .expect("struct was checked")
+            .methods;
+        if methods.contains_key(&function.name) {
+            self.diagnostics.error(Error::DuplicateDefinition {
+                name: function.name.clone(),
+                location: self.tree.definition(id).location,
+            });
+        } else {
+            methods.insert(function.name.clone(), id);
+        }
+    }
+
+    /// Qualified name of a function, methods are named after their struct.
+    fn function_name(&self, id: DefId) -> String {
+        match self.signatures[&id]
+            .receiver
+            .as_ref()
+            .and_then(receiver_struct)
+        {
+            Some(structure) => format!(
+                "{}::{}",
+                self.structs[&structure].name,
+                self.tree.definition(id).name
+            ),
+            None => self.tree.qualified_name(id),
+        }
+    }
+
+    fn check_function(
+        &mut self,
+        id: DefId,
+        function: &ast::Function,
+        location: Location,
+    ) -> TypedFunction {
+        self.return_type = self.signatures[&id].return_type.clone();
+        self.self_type = self.self_types.get(&id).cloned();
+        self.table = InferenceTable::new();
+        self.module = self.tree.definition(id).module;
+        let parameters = function
+            .receiver
+            .as_deref()
+            .into_iter()
+            .chain(&function.parameters)
+            .filter_map(|parameter| self.resolution.declarations.get(&parameter.location))
+            .copied()
+            .collect();
+        let mut body = self.check_block(&function.body);
+        self.finish_inference(id, &mut body);
+
+        TypedFunction {
+            name: self.function_name(id),
+            parameters,
+            return_type: self.return_type.clone(),
+            body,
+            external: function.external,
+            location,
+        }
+    }
+
+    fn check_block(&mut self, body: &[Statement]) -> Vec<Stmt> {
+        body.iter()
+            .map(|statement| self.check_statement(statement))
+            .collect()
+    }
+
+    fn check_statement(&mut self, statement: &Statement) -> Stmt {
+        let kind = match &statement.kind {
+            StatementKind::Compound(body) => StmtKind::Block(self.check_block(body)),
+            StatementKind::If(branch) => StmtKind::If {
+                condition: self.check_expression(&branch.condition, &Ty::Bool),
+                then: self.check_block(&branch.then),
+                otherwise: branch
+                    .otherwise
+                    .as_ref()
+                    .map(|otherwise| self.check_block(otherwise)),
+            },
+            StatementKind::For(for_loop) => self.check_for(for_loop),
+            StatementKind::Return(value) => {
+                let return_type = self.return_type.clone();
+                if value.is_none() {
+                    self.require(&Ty::Unit, &return_type, statement.location);
+                }
+                StmtKind::Return(
+                    value
+                        .as_ref()
+                        .map(|value| self.check_expression(value, &return_type)),
+                )
+            }
+            StatementKind::Break { label, value } => StmtKind::Break {
+                label: label.clone(),
+                value: self.check_break_value(label.as_deref(), value.as_ref(), statement.location),
+            },
+            StatementKind::Continue { label } => StmtKind::Continue {
+                label: label.clone(),
+            },
+            StatementKind::VarDecl(declaration) | StatementKind::ValDecl(declaration) => {
+                self.check_declaration(declaration)
+            }
+            StatementKind::Assign { target, value } => {
+                let target = self.infer_expression(target, None);
+                if !target.is_place() {
+                    self.diagnostics.error(Error::NotAPlace {
+                        usage: "assigned to",
+                        location: target.location,
+                    });
+                }
+                let value = self.check_expression(value, &target.ty);
+                StmtKind::Assign { target, value }
+            }
+            StatementKind::Expression(expression) => {
+                StmtKind::Expr(self.infer_expression(expression, None))
+            }
+        };
+
+        Stmt {
+            kind,
+            location: statement.location,
+        }
+    }
+
+    fn check_for(&mut self, for_loop: &ast::For) -> StmtKind {
+        let (iteration, element) = self.check_iteration(&for_loop.iterable);
+        self.declare(for_loop.binding_location, element);
+        let binding = self.resolution.declarations[&for_loop.binding_location];
+
+        self.loops.push(LoopFrame {
+            label: for_loop.label.clone(),
+            is_loop: false,
+            break_type: None,
+        });
+        let body = self.check_block(&for_loop.body);
+        self.loops.pop();
+
+        StmtKind::For {
+            label: for_loop.label.clone(),
+            binding,
+            iteration: Box::new(iteration),
+            body,
+        }
+    }
+
+    /// Checks the iterable of a `for` loop and returns the element type.
+    fn check_iteration(&mut self, iterable: &Expression) -> (Iteration, Ty) {
+        if let ExpressionKind::Range {
+            start,
+            end,
+            inclusive,
+            step,
+        } = &iterable.kind
+        {
+            return self.check_range(start, end, *inclusive, step.as_deref());
+        }
+
+        let iterable = self.infer_expression(iterable, None);
+        match self.table.shallow(&iterable.ty) {
+            Ty::Array(element) => (Iteration::Elements(iterable), *element),
+            Ty::Str => (Iteration::Elements(iterable), Ty::UInt),
+            Ty::Error => (Iteration::Elements(iterable), Ty::Error),
+            _ => self.check_iterator(iterable),
+        }
+    }
+
+    fn check_range(
+        &mut self,
+        start: &Expression,
+        end: &Expression,
+        inclusive: bool,
+        step: Option<&Expression>,
+    ) -> (Iteration, Ty) {
+        let start = self.infer_expression(start, None);
+        let end = self.check_expression(end, &start.ty);
+        let step = step.map(|step| self.check_expression(step, &start.ty));
+
+        let ty = self.table.shallow(&start.ty);
+        if !ty.is_error() && !self.table.is_integer(&ty) {
+            let operator = if inclusive { "..=" } else { ".." };
+            self.invalid_operand(operator, &ty, start.location);
+        }
+        if let Some(step) = &step
+            && constant(step) == Some(0)
+        {
+            self.diagnostics.error(Error::ZeroStep {
+                location: step.location,
+            });
+        }
+
+        let iteration = Iteration::Range {
+            start,
+            end,
+            step,
+            inclusive,
+        };
+        (iteration, ty)
+    }
+
+    /// Checks iteration over a struct or a pointer to one with a method
+    /// `next(self: *Self) -> Option<T>`, the elements are of type `T`.
+    fn check_iterator(&mut self, iterator: Expr) -> (Iteration, Ty) {
+        let location = iterator.location;
+        let ty = self.table.resolve(&iterator.ty);
+        let function = receiver_struct(&ty)
+            .and_then(|structure| self.structs[&structure].methods.get("next").copied());
+        let Some(function) = function else {
+            self.diagnostics.error(Error::NotIterable {
+                ty: self.show(&ty),
+                location,
+            });
+            return (Iteration::Elements(iterator), Ty::Error);
+        };
+        self.check_visible(function, location);
+
+        let generics = (0..generic_count(self.tree, function))
+            .map(|_| self.table.fresh())
+            .collect::<Vec<_>>();
+        let signature = &self.signatures[&function];
+        let receiver = signature
+            .receiver
+            .as_ref()
+            .map(|receiver| receiver.substitute(function, &generics));
+        let return_type = signature.return_type.substitute(function, &generics);
+        let element = match (&receiver, &return_type) {
+            (Some(Ty::Pointer(_)), Ty::Enum(id, arguments))
+                if signature.parameters.is_empty() && Some(*id) == self.tree.prelude("Option") =>
+            {
+                arguments[0].clone()
+            }
+            _ => {
+                let structure = match ty {
+                    Ty::Pointer(pointee) => *pointee,
+                    ty => ty,
+                };
+                self.diagnostics.error(Error::InvalidIterator {
+                    ty: self.show(&structure),
+                    location,
+                });
+                return (Iteration::Elements(iterator), Ty::Error);
+            }
+        };
+
+        let pointer = match &ty {
+            Ty::Pointer(_) => ty.clone(),
+            _ => Ty::pointer(ty.clone()),
+        };
+        self.require(
+            &pointer,
+            receiver.as_ref().expect("checked above"),
+            location,
+        );
+        let next = Expr {
+            kind: ExprKind::Call(Instance::new(function, generics), Vec::new()),
+            ty: return_type,
+            location,
+        };
+        (Iteration::Iterator { iterator, next }, element)
+    }
+
+    /// Checks the value of a `break`, `()` without one, against the other
+    /// breaks of the loop it leaves.
+    fn check_break_value(
+        &mut self,
+        label: Option<&str>,
+        value: Option<&Expression>,
+        location: Location,
+    ) -> Option<Expr> {
+        let frame = match label {
+            Some(label) => self
+                .loops
+                .iter()
+                .rposition(|frame| frame.label.as_deref() == Some(label)),
+            None => self.loops.len().checked_sub(1),
+        };
+        let expected = frame.and_then(|frame| self.loops[frame].break_type.clone());
+
+        let Some(value) = value else {
+            if let Some(frame) = frame
+                && self.loops[frame].is_loop
+            {
+                match expected {
+                    Some(expected) => self.require(&Ty::Unit, &expected, location),
+                    None => self.loops[frame].break_type = Some(Ty::Unit),
+                }
+            }
+            return None;
+        };
+        let value = match &expected {
+            Some(expected) => self.check_expression(value, expected),
+            None => self.infer_expression(value, None),
+        };
+        if let Some(frame) = frame
+            && self.loops[frame].is_loop
+            && expected.is_none()
+        {
+            self.loops[frame].break_type = Some(value.ty.clone());
+        }
+        Some(value)
+    }
+
+    fn check_declaration(&mut self, declaration: &Declaration) -> StmtKind {
+        let annotation = declaration.ty.as_ref().map(|ty| self.lower_type(ty));
+        let value = match (&declaration.value, &annotation) {
+            (Some(value), Some(ty)) => Some(self.check_expression(value, ty)),
+            (Some(value), None) => Some(self.infer_expression(value, None)),
+            (None, _) => None,
+        };
+
+        // without annotation and initialiser the type comes from later use
+        let ty = match (annotation, &value) {
+            (Some(ty), _) => ty,
+            (None, Some(value)) => value.ty.clone(),
+            (None, None) => self.table.fresh(),
+        };
+        self.declare(declaration.location, ty);
+
+        StmtKind::Let {
+            local: self.resolution.declarations[&declaration.location],
+            value,
+        }
+    }
+
+    /// Checks `expression` against `expected` and reports a mismatch.
+    fn check_expression(&mut self, expression: &Expression, expected: &Ty) -> Expr {
+        let typed = self.infer_expression(expression, Some(expected));
+        self.require(&typed.ty, expected, typed.location);
+        typed
+    }
+
+    /// Computes the type of `expression`. `expected` is only a hint, used to
+    /// type literals, the caller has to check the result.
+    #[allow(clippy::too_many_lines)]
+    fn infer_expression(&mut self, expression: &Expression, expected: Option<&Ty>) -> Expr {
+        let location = expression.location;
+        let (kind, ty) = match &expression.kind {
+            ExpressionKind::Identifier(path) => self.check_identifier(path),
+            ExpressionKind::Literal(Literal::Int(int)) => {
+                let ty = match expected {
+                    Some(expected) if self.table.is_integer(expected) => expected.clone(),
+                    _ => self.table.fresh_integer(),
+                };
+                (ExprKind::Int(*int), ty)
+            }
+            ExpressionKind::Literal(Literal::Bool(bool)) => (ExprKind::Bool(*bool), Ty::Bool),
+            ExpressionKind::Literal(Literal::Str(str)) => (ExprKind::Str(str.clone()), Ty::Str),
+            ExpressionKind::Unary(operator, operand) => {
+                return self.check_unary(*operator, operand, expected, location);
+            }
+            ExpressionKind::Binary(operator, lhs, rhs) => {
+                return self.check_binary(*operator, lhs, rhs, expected, location);
+            }
+            ExpressionKind::Call(callee, arguments) => self.check_call(callee, arguments),
+            ExpressionKind::Index(base, index) => self.check_index(base, index),
+            ExpressionKind::Member(base, member) => {
+                let base = self.infer_expression(base, None);
+                self.check_member(base, member, location)
+            }
+            ExpressionKind::StructInit(path, fields) => self.check_struct_init(path, fields),
+            ExpressionKind::ArrayInit(elements) => {
+                let element = match expected.map(|ty| self.table.shallow(ty)) {
+                    Some(Ty::Array(element)) => Some(*element),
+                    _ => None,
+                };
+                self.check_array_init(elements, element)
+            }
+            ExpressionKind::Match { scrutinee, arms } => {
+                self.check_match(scrutinee, arms, expected)
+            }
+            ExpressionKind::Loop { label, body } => {
+                self.loops.push(LoopFrame {
+                    label: label.clone(),
+                    is_loop: true,
+                    break_type: expected.cloned().filter(|ty| *ty != Ty::Unit),
+                });
+                let body = self.check_block(body);
+                let frame = self.loops.pop().expect("loop frame pushed above");
+                let kind = ExprKind::Loop {
+                    label: label.clone(),
+                    body,
+                };
+                (kind, frame.break_type.unwrap_or(Ty::Unit))
+            }
+            ExpressionKind::Range {
+                start, end, step, ..
+            } => {
+                self.diagnostics.error(Error::RangeOutsideFor { location });
+                for operand in [Some(start), Some(end), step.as_ref()]
+                    .into_iter()
+                    .flatten()
+                {
+                    self.infer_expression(operand, None);
+                }
+                (ExprKind::Error, Ty::Error)
+            }
+        };
+        Expr { kind, ty, location }
+    }
+
+    fn check_identifier(&mut self, path: &Path) -> (ExprKind, Ty) {
+        match self.resolution.symbol(path.location) {
+            Some(Symbol::Local(local)) => {
+                let ty = self.locals[local.0].clone().unwrap_or(Ty::Error);
+                (ExprKind::Local(local), ty)
+            }
+            Some(Symbol::Variant(id, index)) => self.check_variant(id, index, path, &[]),
+            Some(Symbol::Definition(_) | Symbol::Param(..)) => {
+                self.diagnostics.error(Error::WrongKind {
+                    path: path.to_string(),
+                    expected: "a value",
+                    location: path.location,
+                });
+                (ExprKind::Error, Ty::Error)
+            }
+            None => (ExprKind::Error, Ty::Error),
+        }
+    }
+
+    fn check_unary(
+        &mut self,
+        operator: UnaryOperator,
+        operand: &Expression,
+        expected: Option<&Ty>,
+        location: Location,
+    ) -> Expr {
+        if operator == UnaryOperator::Sizeof {
+            let ty = match (&operand.kind, self.resolution.symbol(operand.location)) {
+                (ExpressionKind::Identifier(path), Some(Symbol::Definition(id)))
+                    if !self.signatures.contains_key(&id) =>
+                {
+                    self.generic_args(id, path, false)
+                        .map_or(Ty::Error, |generics| self.named_type(id, generics))
+                }
+                (ExpressionKind::Identifier(_), Some(Symbol::Param(owner, index))) => {
+                    Ty::Param(owner, index)
+                }
+                _ => self.infer_expression(operand, None).ty,
+            };
+            return Expr {
+                kind: ExprKind::SizeOf(ty),
+                ty: Ty::UInt,
+                location,
+            };
+        }
+
+        let hint = match (operator, expected.map(|ty| self.table.shallow(ty))) {
+            (UnaryOperator::Address, Some(Ty::Pointer(pointee))) => Some(*pointee),
+            (UnaryOperator::Dereference, _) => None,
+            (_, expected) => expected,
+        };
+        let operand = self.infer_expression(operand, hint.as_ref());
+
+        if operator == UnaryOperator::Minus && self.table.is_integer(&operand.ty) {
+            // a negated literal without other constraints is signed
+            self.table.unify(&operand.ty, &Ty::Int);
+        }
+        let ty = match (operator, self.table.shallow(&operand.ty)) {
+            (_, Ty::Error) => Ty::Error,
+            (UnaryOperator::Minus, Ty::Int)
+            | (UnaryOperator::Not, Ty::Bool | Ty::Int | Ty::UInt) => operand.ty.clone(),
+            (UnaryOperator::Not, _) if self.table.is_integer(&operand.ty) => operand.ty.clone(),
+            // checked for the actual type when the function is instantiated
+            (UnaryOperator::Minus | UnaryOperator::Not, Ty::Param(..)) => operand.ty.clone(),
+            (UnaryOperator::Dereference, Ty::Pointer(pointee)) => *pointee,
+            (UnaryOperator::Address, _) => {
+                if !operand.is_place() {
+                    self.diagnostics.error(Error::NotAPlace {
+                        usage: "borrowed",
+                        location: operand.location,
+                    });
+                }
+                Ty::pointer(operand.ty.clone())
+            }
+            (_, ty) => {
+                self.invalid_operand(&operator.to_string(), &ty, location);
+                Ty::Error
+            }
+        };
+
+        Expr {
+            kind: ExprKind::Unary(operator, Box::new(operand)),
+            ty,
+            location,
+        }
+    }
+
+    fn check_binary(
+        &mut self,
+        operator: BinaryOperator,
+        lhs: &Expression,
+        rhs: &Expression,
+        expected: Option<&Ty>,
+        location: Location,
+    ) -> Expr {
+        use BinaryOperator as B;
+
+        let (lhs, rhs) = match operator {
+            B::LogicalAnd | B::LogicalOr => (
+                self.check_expression(lhs, &Ty::Bool),
+                self.check_expression(rhs, &Ty::Bool),
+            ),
+            B::ShiftLeft | B::ShiftRight => (
+                self.infer_expression(lhs, expected.filter(|ty| self.table.is_integer(ty))),
+                self.infer_expression(rhs, None),
+            ),
+            _ => {
+                let hint = match operator {
+                    B::Add | B::Sub | B::Mul | B::Div | B::Rem | B::And | B::Xor | B::Or => {
+                        expected.filter(|ty| self.table.is_integer(ty))
+                    }
+                    _ => None,
+                };
+                // let the non-literal side decide the type of an int literal
+                if is_int_literal(lhs) && !is_int_literal(rhs) {
+                    let rhs = self.infer_expression(rhs, hint);
+                    let lhs = self.infer_expression(lhs, Some(&rhs.ty));
+                    (lhs, rhs)
+                } else {
+                    let lhs = self.infer_expression(lhs, hint);
+                    let rhs_hint = if self.table.is_integer(&lhs.ty) {
+                        Some(&lhs.ty)
+                    } else {
+                        None
+                    };
+                    let rhs = self.infer_expression(rhs, rhs_hint);
+                    (lhs, rhs)
+                }
+            }
+        };
+
+        let ty = self
+            .binary_type(operator, &lhs.ty, &rhs.ty)
+            .unwrap_or_else(|| {
+                self.diagnostics.error(Error::InvalidOperands {
+                    operator: operator.to_string(),
+                    lhs: self.show(&lhs.ty),
+                    rhs: self.show(&rhs.ty),
+                    location,
+                });
+                Ty::Error
+            });
+
+        Expr {
+            kind: ExprKind::Binary(operator, Box::new(lhs), Box::new(rhs)),
+            ty,
+            location,
+        }
+    }
+
+    fn check_call(&mut self, callee: &Expression, arguments: &[Expression]) -> (ExprKind, Ty) {
+        if let ExpressionKind::Member(base, method) = &callee.kind {
+            return self.check_method_call(base, method, arguments, callee.location);
+        }
+
+        let function = match (&callee.kind, self.resolution.symbol(callee.location)) {
+            (ExpressionKind::Identifier(path), Some(Symbol::Variant(id, index))) => {
+                return self.check_variant(id, index, path, arguments);
+            }
+            (ExpressionKind::Identifier(path), Some(Symbol::Definition(id)))
+                if self.signatures.contains_key(&id) =>
+            {
+                self.generic_args(id, path, true)
+                    .map(|generics| Instance::new(id, generics))
+            }
+            (ExpressionKind::Identifier(_), None) => None,
+            _ => {
+                let callee = self.infer_expression(callee, None);
+                if !callee.ty.is_error() {
+                    self.diagnostics.error(Error::NotCallable {
+                        callee: self.show(&callee.ty),
+                        location: callee.location,
+                    });
+                }
+                None
+            }
+        };
+
+        let Some(function) = function else {
+            for argument in arguments {
+                self.infer_expression(argument, None);
+            }
+            return (ExprKind::Error, Ty::Error);
+        };
+
+        self.check_arguments(function, Vec::new(), arguments, callee.location)
+    }
+
+    /// Checks `base.method(arguments)`. The receiver is borrowed or
+    /// dereferenced as the method requires.
+    fn check_method_call(
+        &mut self,
+        base: &Expression,
+        method: &str,
+        arguments: &[Expression],
+        location: Location,
+    ) -> (ExprKind, Ty) {
+        let receiver = self.infer_expression(base, None);
+        if let Some(param) = receiver_param(&self.table.resolve(&receiver.ty)) {
+            return self.check_interface_call(receiver, param, method, arguments, location);
+        }
+        let function = receiver_struct(&self.table.resolve(&receiver.ty))
+            .and_then(|structure| self.structs[&structure].methods.get(method).copied());
+
+        let Some(function) = function else {
+            if !receiver.ty.is_error() {
+                self.diagnostics.error(Error::UnknownMethod {
+                    ty: self.show(&receiver.ty),
+                    method: method.to_string(),
+                    location,
+                });
+            }
+            for argument in arguments {
+                self.infer_expression(argument, None);
+            }
+            return (ExprKind::Error, Ty::Error);
+        };
+
+        self.check_visible(function, location);
+
+        let generics = (0..generic_count(self.tree, function))
+            .map(|_| self.table.fresh())
+            .collect::<Vec<_>>();
+        let expected = self.signatures[&function]
+            .receiver
+            .as_ref()
+            .expect("methods have a receiver")
+            .substitute(function, &generics);
+        let receiver = self.adjust_receiver(receiver, &expected);
+        let instance = Instance::new(function, generics);
+        self.check_arguments(instance, vec![receiver], arguments, location)
+    }
+
+    /// Reports a private method used outside of its namespace.
+    fn check_visible(&mut self, function: DefId, location: Location) {
+        let definition = self.tree.definition(function);
+        if definition.visibility == Visibility::Private
+            && !self.tree.is_within(self.module, definition.module)
+        {
+            self.diagnostics.error(Error::PrivateItem {
+                path: self.function_name(function),
+                location,
+            });
+        }
+    }
+
+    /// Checks a call of a method that the bounds of the type parameter
+    /// `param` declare.
+    fn check_interface_call(
+        &mut self,
+        receiver: Expr,
+        param: Ty,
+        method: &str,
+        arguments: &[Expression],
+        location: Location,
+    ) -> (ExprKind, Ty) {
+        let Ty::Param(owner, index) = param else {
+            unreachable!("receiver_param only returns type parameters")
+        };
+        let candidates: Vec<(DefId, usize)> = self
+            .bounds(owner, index)
+            .into_iter()
+            .filter_map(|interface| {
+                let method = self.interfaces[&interface].method(method)?;
+                Some((interface, method))
+            })
+            .collect();
+
+        let [(interface, index)] = candidates[..] else {
+            let ty = self.show(&receiver.ty);
+            let method = method.to_string();
+            self.diagnostics.error(if candidates.is_empty() {
+                Error::UnknownMethod {
+                    ty,
+                    method,
+                    location,
+                }
+            } else {
+                Error::AmbiguousMethod {
+                    method,
+                    ty,
+                    location,
+                }
+            });
+            for argument in arguments {
+                self.infer_expression(argument, None);
+            }
+            return (ExprKind::Error, Ty::Error);
+        };
+
+        let declared = self.interfaces[&interface].methods[index].clone();
+        let generics = std::slice::from_ref(&param);
+        let expected = declared.receiver.substitute(interface, generics);
+        let receiver = self.adjust_receiver(receiver, &expected);
+        let parameters: Vec<Ty> = declared
+            .parameters
+            .iter()
+            .map(|parameter| parameter.substitute(interface, generics))
+            .collect();
+        let name = format!("{}::{}", self.interfaces[&interface].name, declared.name);
+        let arguments =
+            self.check_argument_list(&name, &parameters, vec![receiver], arguments, location);
+        let return_type = declared.return_type.substitute(interface, generics);
+        (
+            ExprKind::InterfaceCall(interface, index, param, arguments),
+            return_type,
+        )
+    }
+
+    /// Takes the address of or dereferences `receiver` to match `expected`.
+    fn adjust_receiver(&mut self, receiver: Expr, expected: &Ty) -> Expr {
+        let found = self.table.resolve(&receiver.ty);
+        let location = receiver.location;
+        match (&found, expected) {
+            (Ty::Struct(..) | Ty::Param(..), Ty::Pointer(_)) => {
+                if !receiver.is_place() {
+                    self.diagnostics.error(Error::NotAPlace {
+                        usage: "borrowed",
+                        location,
+                    });
+                }
+                let ty = Ty::pointer(found);
+                self.require(&ty, expected, location);
+                Expr {
+                    kind: ExprKind::Unary(UnaryOperator::Address, Box::new(receiver)),
+                    ty,
+                    location,
+                }
+            }
+            (Ty::Pointer(pointee), Ty::Struct(..) | Ty::Param(..)) => {
+                self.require(pointee, expected, location);
+                Expr {
+                    ty: (**pointee).clone(),
+                    kind: ExprKind::Unary(UnaryOperator::Dereference, Box::new(receiver)),
+                    location,
+                }
+            }
+            _ => {
+                self.require(&found, expected, location);
+                receiver
+            }
+        }
+    }
+
+    /// Checks the arguments of a call to `function` after the already
+    /// checked `leading` ones.
+    fn check_arguments(
+        &mut self,
+        instance: Instance,
+        leading: Vec<Expr>,
+        arguments: &[Expression],
+        location: Location,
+    ) -> (ExprKind, Ty) {
+        let signature = &self.signatures[&instance.id];
+        let parameters: Vec<Ty> = signature
+            .parameters
+            .iter()
+            .map(|parameter| parameter.substitute(instance.id, &instance.generics))
+            .collect();
+        let return_type = signature
+            .return_type
+            .substitute(instance.id, &instance.generics);
+        let name = self.function_name(instance.id);
+        let arguments = self.check_argument_list(&name, &parameters, leading, arguments, location);
+        (ExprKind::Call(instance, arguments), return_type)
+    }
+
+    /// Checks `arguments` against `parameters` and appends them to the
+    /// already checked `leading` ones.
+    fn check_argument_list(
+        &mut self,
+        function: &str,
+        parameters: &[Ty],
+        mut leading: Vec<Expr>,
+        arguments: &[Expression],
+        location: Location,
+    ) -> Vec<Expr> {
+        if parameters.len() != arguments.len() {
+            self.diagnostics.error(Error::ArgumentCount {
+                function: function.to_string(),
+                expected: parameters.len(),
+                found: arguments.len(),
+                location,
+            });
+        }
+
+        for (index, argument) in arguments.iter().enumerate() {
+            let argument = match parameters.get(index) {
+                Some(parameter) => self.check_expression(argument, parameter),
+                None => self.infer_expression(argument, None),
+            };
+            leading.push(argument);
+        }
+        leading
+    }
+
+    /// Checks `base[index]`. Indices into arrays and strings are checked
+    /// against the length when the program runs, constant ones already here
+    /// as far as the length is known.
+    fn check_index(&mut self, base: &Expression, index: &Expression) -> (ExprKind, Ty) {
+        let base = self.infer_expression(base, None);
+        let index = self.infer_expression(index, None);
+        if !self.table.is_integer(&index.ty) {
+            self.invalid_operand("[]", &index.ty, index.location);
+        }
+        let ty = match self.table.shallow(&base.ty) {
+            Ty::Pointer(element) => *element,
+            Ty::Array(element) => {
+                self.check_constant_index(&base, &index);
+                *element
+            }
+            Ty::Str => {
+                self.check_constant_index(&base, &index);
+                Ty::UInt
+            }
+            Ty::Error => Ty::Error,
+            other => {
+                self.invalid_operand("[]", &other, base.location);
+                Ty::Error
+            }
+        };
+        (ExprKind::Index(Box::new(base), Box::new(index)), ty)
+    }
+
+    fn check_constant_index(&mut self, base: &Expr, index: &Expr) {
+        let Some(value) = constant(index) else {
+            return;
+        };
+        let length = match &base.kind {
+            ExprKind::ArrayInit(elements) => Some(elements.len()),
+            ExprKind::Str(str) => Some(str.len()),
+            _ => None,
+        };
+        if value < 0 {
+            self.diagnostics.error(Error::NegativeIndex {
+                index: value,
+                location: index.location,
+            });
+        } else if let Some(length) = length
+            && value >= length as i128
+        {
+            self.diagnostics.error(Error::IndexOutOfBounds {
+                index: value,
+                length,
+                location: index.location,
+            });
+        }
+    }
+
+    fn check_member(&mut self, base: Expr, member: &str, location: Location) -> (ExprKind, Ty) {
+        let target = match self.table.shallow(&base.ty) {
+            Ty::Pointer(pointee) => self.table.shallow(&pointee),
+            ty => ty,
+        };
+        if member == "len" && matches!(target, Ty::Array(_) | Ty::Str) {
+            return (ExprKind::Len(Box::new(base)), Ty::UInt);
+        }
+        let structure = match target {
+            Ty::Struct(id, generics) => Some((id, generics)),
+            _ => None,
+        };
+
+        let field = structure.and_then(|(id, generics)| {
+            let structure = &self.structs[&id];
+            structure.field(member).map(|index| {
+                let ty = structure.fields[index].ty.substitute(id, &generics);
+                (index, ty)
+            })
+        });
+
+        if let Some((index, ty)) = field {
+            return (ExprKind::Field(Box::new(base), index), ty);
+        }
+        if !base.ty.is_error() {
+            self.diagnostics.error(Error::UnknownField {
+                ty: self.show(&base.ty),
+                field: member.to_string(),
+                location,
+            });
+        }
+        (ExprKind::Error, Ty::Error)
+    }
+
+    /// Checks `path { fields }`, which builds a struct or a variant.
+    fn check_struct_init(&mut self, path: &Path, fields: &[FieldInit]) -> (ExprKind, Ty) {
+        let target = match self.resolution.symbol(path.location) {
+            Some(Symbol::Definition(id)) if self.structs.contains_key(&id) => Some((id, None)),
+            Some(Symbol::Variant(id, index)) => Some((id, Some(index))),
+            _ => None,
+        };
+        let generics = target.and_then(|(id, _)| self.generic_args(id, path, true));
+        let (Some((id, variant)), Some(generics)) = (target, generics) else {
+            for field in fields {
+                self.infer_expression(&field.value, None);
+            }
+            return (ExprKind::Error, Ty::Error);
+        };
+        let (name, declared) = self.constructor_fields(id, variant);
+        let ty = self.named_type(id, generics.clone());
+
+        let mut values: Vec<Option<Expr>> = vec![None; declared.len()];
+        for (position, field) in fields.iter().enumerate() {
+            let index = match &field.name {
+                Some(name) => declared.iter().position(|declared| declared.name == *name),
+                None => (position < declared.len()).then_some(position),
+            };
+            let Some(index) = index else {
+                self.diagnostics.error(Error::UnknownField {
+                    ty: name.clone(),
+                    field: field.name.clone().unwrap_or_else(|| position.to_string()),
+                    location: field.value.location,
+                });
+                self.infer_expression(&field.value, None);
+                continue;
+            };
+
+            let expected = declared[index].ty.substitute(id, &generics);
+            let value = self.check_expression(&field.value, &expected);
+            if values[index].is_some() {
+                self.diagnostics.error(Error::DuplicateField {
+                    ty: name.clone(),
+                    field: declared[index].name.clone(),
+                    location: value.location,
+                });
+            }
+            values[index] = Some(value);
+        }
+
+        let mut complete = Vec::new();
+        for (field, value) in declared.iter().zip(values) {
+            if let Some(value) = value {
+                complete.push(value);
+            } else {
+                self.diagnostics.error(Error::MissingField {
+                    ty: name.clone(),
+                    field: field.name.clone(),
+                    location: path.location,
+                });
+            }
+        }
+
+        if complete.len() != declared.len() {
+            return (ExprKind::Error, ty);
+        }
+        match variant {
+            Some(index) => (ExprKind::Variant(id, index, complete), ty),
+            None => (ExprKind::StructInit(id, complete), ty),
+        }
+    }
+
+    /// Checks `path(arguments)` or a plain `path` naming a variant, the
+    /// arguments give the fields in declaration order.
+    fn check_variant(
+        &mut self,
+        id: DefId,
+        index: usize,
+        path: &Path,
+        arguments: &[Expression],
+    ) -> (ExprKind, Ty) {
+        let Some(generics) = self.generic_args(id, path, true) else {
+            for argument in arguments {
+                self.infer_expression(argument, None);
+            }
+            return (ExprKind::Error, Ty::Error);
+        };
+        let (name, fields) = self.constructor_fields(id, Some(index));
+        if fields.len() != arguments.len() {
+            self.diagnostics.error(Error::ArgumentCount {
+                function: name,
+                expected: fields.len(),
+                found: arguments.len(),
+                location: path.location,
+            });
+        }
+
+        let values = arguments
+            .iter()
+            .enumerate()
+            .map(|(position, argument)| match fields.get(position) {
+                Some(field) => self.check_expression(argument, &field.ty.substitute(id, &generics)),
+                None => self.infer_expression(argument, None),
+            })
+            .collect();
+        let ty = Ty::Enum(id, generics);
+        if fields.len() == arguments.len() {
+            (ExprKind::Variant(id, index, values), ty)
+        } else {
+            (ExprKind::Error, ty)
+        }
+    }
+
+    /// Name and declared fields of a struct or, given `variant`, of a
+    /// variant of an enum.
+    fn constructor_fields(&self, id: DefId, variant: Option<usize>) -> (String, Vec<Field>) {
+        if let Some(index) = variant {
+            let enumeration = &self.enums[&id];
+            let variant = &enumeration.variants[index];
+            (
+                format!("{}::{}", enumeration.name, variant.name),
+                variant.fields.clone(),
+            )
+        } else {
+            let structure = &self.structs[&id];
+            (structure.name.clone(), structure.fields.clone())
+        }
+    }
+
+    /// Checks a `match`. Arms with an expression give the value of the
+    /// match, arms with a block have none and are checked by the flow pass.
+    fn check_match(
+        &mut self,
+        scrutinee: &Expression,
+        arms: &[MatchArm],
+        expected: Option<&Ty>,
+    ) -> (ExprKind, Ty) {
+        let scrutinee = self.infer_expression(scrutinee, None);
+        let mut result = expected.filter(|ty| **ty != Ty::Unit).cloned();
+
+        let mut typed = Vec::new();
+        for arm in arms {
+            let pattern = self.check_pattern(&arm.pattern, &scrutinee.ty);
+            let guard = arm
+                .guard
+                .as_ref()
+                .map(|guard| self.check_expression(guard, &Ty::Bool));
+            let body = match &arm.body {
+                ast::ArmBody::Expression(value) => {
+                    let value = match &result {
+                        Some(ty) => self.check_expression(value, ty),
+                        None => self.infer_expression(value, None),
+                    };
+                    result.get_or_insert_with(|| value.ty.clone());
+                    ArmBody::Expr(value)
+                }
+                ast::ArmBody::Block(body) => ArmBody::Block(self.check_block(body)),
+            };
+            typed.push(Arm {
+                pattern,
+                guard,
+                body,
+                location: arm.location,
+            });
+        }
+
+        let kind = ExprKind::Match {
+            scrutinee: Box::new(scrutinee),
+            arms: typed,
+        };
+        (kind, result.unwrap_or(Ty::Unit))
+    }
+
+    fn check_pattern(&mut self, pattern: &ast::Pattern, expected: &Ty) -> typed::Pattern {
+        use typed::PatternKind as P;
+
+        let location = pattern.location;
+        let kind = match &pattern.kind {
+            ast::PatternKind::Wildcard => P::Wildcard,
+            ast::PatternKind::Binding(_) => {
+                self.declare(location, expected.clone());
+                match self.resolution.declarations.get(&location) {
+                    Some(local) => P::Binding(*local),
+                    None => P::Wildcard,
+                }
+            }
+            ast::PatternKind::Literal { literal, negative } => match literal {
+                Literal::Int(int) => {
+                    let ty = if *negative {
+                        Ty::Int
+                    } else {
+                        self.table.fresh_integer()
+                    };
+                    self.require(&ty, expected, location);
+                    P::Int(if *negative { int.wrapping_neg() } else { *int })
+                }
+                Literal::Bool(bool) => {
+                    self.require(&Ty::Bool, expected, location);
+                    P::Bool(*bool)
+                }
+                Literal::Str(str) => {
+                    self.require(&Ty::Str, expected, location);
+                    P::Str(str.clone())
+                }
+            },
+            ast::PatternKind::Tuple(path, patterns) => {
+                let Some(Symbol::Variant(id, index)) = self.resolution.symbol(path.location) else {
+                    for pattern in patterns {
+                        self.check_pattern(pattern, &Ty::Error);
+                    }
+                    return Self::error_pattern(location);
+                };
+                let generics = self.pattern_generics(id, path, expected, location);
+                let (name, fields) = self.constructor_fields(id, Some(index));
+                if fields.len() != patterns.len() {
+                    self.diagnostics.error(Error::PatternFieldCount {
+                        name,
+                        expected: fields.len(),
+                        found: patterns.len(),
+                        location,
+                    });
+                }
+                let mut typed = Vec::new();
+                for (position, pattern) in patterns.iter().enumerate() {
+                    let ty = fields
+                        .get(position)
+                        .map_or(Ty::Error, |field| field.ty.substitute(id, &generics));
+                    typed.push(self.check_pattern(pattern, &ty));
+                }
+                typed.resize_with(fields.len(), || Self::error_pattern(location));
+                P::Variant(id, index, typed)
+            }
+            ast::PatternKind::Struct { path, fields, rest } => {
+                match self.check_struct_pattern(path, fields, *rest, expected, location) {
+                    Some(kind) => kind,
+                    None => return Self::error_pattern(location),
+                }
+            }
+        };
+
+        typed::Pattern {
+            kind,
+            ty: expected.clone(),
+            location,
+        }
+    }
+
+    /// Checks `path { field: pattern, .. }` against a struct or variant.
+    /// Fields that are left out match anything.
+    fn check_struct_pattern(
+        &mut self,
+        path: &Path,
+        fields: &[ast::FieldPattern],
+        rest: bool,
+        expected: &Ty,
+        location: Location,
+    ) -> Option<typed::PatternKind> {
+        use typed::PatternKind as P;
+
+        let (id, variant) = match self.resolution.symbol(path.location) {
+            Some(Symbol::Definition(id)) if self.structs.contains_key(&id) => (id, None),
+            Some(Symbol::Variant(id, index)) => (id, Some(index)),
+            _ => {
+                for field in fields {
+                    self.check_pattern(&field.pattern, &Ty::Error);
+                }
+                return None;
+            }
+        };
+        let generics = self.pattern_generics(id, path, expected, location);
+        let (name, declared) = self.constructor_fields(id, variant);
+
+        let mut typed: Vec<Option<typed::Pattern>> = vec![None; declared.len()];
+        for field in fields {
+            let Some(index) = declared.iter().position(|d| d.name == field.name) else {
+                self.diagnostics.error(Error::UnknownField {
+                    ty: name.clone(),
+                    field: field.name.clone(),
+                    location: field.pattern.location,
+                });
+                self.check_pattern(&field.pattern, &Ty::Error);
+                continue;
+            };
+            let ty = declared[index].ty.substitute(id, &generics);
+            let pattern = self.check_pattern(&field.pattern, &ty);
+            if typed[index].is_some() {
+                self.diagnostics.error(Error::DuplicateField {
+                    ty: name.clone(),
+                    field: field.name.clone(),
+                    location: pattern.location,
+                });
+            }
+            typed[index] = Some(pattern);
+        }
+
+        let mut complete = Vec::new();
+        for (field, pattern) in declared.iter().zip(typed) {
+            let pattern = pattern.unwrap_or_else(|| {
+                if !rest {
+                    self.diagnostics.error(Error::MissingField {
+                        ty: name.clone(),
+                        field: field.name.clone(),
+                        location,
+                    });
+                }
+                typed::Pattern {
+                    kind: P::Wildcard,
+                    ty: field.ty.substitute(id, &generics),
+                    location,
+                }
+            });
+            complete.push(pattern);
+        }
+        Some(match variant {
+            Some(index) => P::Variant(id, index, complete),
+            None => P::Struct(id, complete),
+        })
+    }
+
+    /// Type arguments of the struct or enum a pattern names, taken from the
+    /// matched type if it already is known.
+    fn pattern_generics(
+        &mut self,
+        id: DefId,
+        path: &Path,
+        expected: &Ty,
+        location: Location,
+    ) -> Vec<Ty> {
+        let Some(generics) = self.generic_args(id, path, true) else {
+            return Vec::new();
+        };
+        let ty = self.named_type(id, generics);
+        self.require(&ty, expected, location);
+        match self.table.resolve(&ty) {
+            Ty::Struct(_, generics) | Ty::Enum(_, generics) => generics,
+            _ => Vec::new(),
+        }
+    }
+
+    fn error_pattern(location: Location) -> typed::Pattern {
+        typed::Pattern {
+            kind: typed::PatternKind::Wildcard,
+            ty: Ty::Error,
+            location,
+        }
+    }
+
+    fn check_array_init(&mut self, elements: &[Expression], element: Option<Ty>) -> (ExprKind, Ty) {
+        let mut element = element;
+        let mut typed = Vec::new();
+        for value in elements {
+            let value = if let Some(ty) = &element {
+                self.check_expression(value, ty)
+            } else {
+                let value = self.infer_expression(value, None);
+                element = Some(value.ty.clone());
+                value
+            };
+            typed.push(value);
+        }
+
+        // an empty array learns its element type from later uses, it needs
+        // an annotation only if there are none
+        let element = element.unwrap_or_else(|| self.table.fresh());
+        (ExprKind::ArrayInit(typed), Ty::array(element))
+    }
+
+    fn lower_type(&mut self, ty: &ast::Type) -> Ty {
+        let mut lowered = match &ty.base {
+            BaseType::Int { unsigned: false } => Ty::Int,
+            BaseType::Int { unsigned: true } => Ty::UInt,
+            BaseType::Str => Ty::Str,
+            BaseType::Bool => Ty::Bool,
+            BaseType::SelfType => self.self_type.clone().unwrap_or_else(|| {
+                self.diagnostics.error(Error::SelfOutsideImpl {
+                    location: ty.location,
+                });
+                Ty::Error
+            }),
+            BaseType::Named(path) => match self.resolution.symbol(path.location) {
+                Some(Symbol::Definition(id)) => self
+                    .generic_args(id, path, false)
+                    .map_or(Ty::Error, |generics| self.named_type(id, generics)),
+                Some(Symbol::Param(owner, index)) => Ty::Param(owner, index),
+                _ => Ty::Error,
+            },
+        };
+        for _ in 0..ty.pointer {
+            lowered = Ty::pointer(lowered);
+        }
+        for _ in 0..ty.array {
+            lowered = Ty::array(lowered);
+        }
+        lowered
+    }
+
+    /// The type arguments written after `path`. Without any, `infer` asks
+    /// for fresh variables instead.
+    fn generic_args(&mut self, owner: DefId, path: &Path, infer: bool) -> Option<Vec<Ty>> {
+        let expected = generic_count(self.tree, owner);
+        if infer && path.generics.is_empty() {
+            return Some((0..expected).map(|_| self.table.fresh()).collect());
+        }
+        if path.generics.len() != expected {
+            self.diagnostics.error(Error::GenericCount {
+                name: path.segments.join("::"),
+                expected,
+                found: path.generics.len(),
+                location: path.location,
+            });
+            return None;
+        }
+        Some(path.generics.iter().map(|ty| self.lower_type(ty)).collect())
+    }
+
+    /// The type of the struct or enum `id` with `generics`.
+    fn named_type(&self, id: DefId, generics: Vec<Ty>) -> Ty {
+        if let DefKind::Enum(_) = self.tree.definition(id).kind {
+            Ty::Enum(id, generics)
+        } else {
+            Ty::Struct(id, generics)
+        }
+    }
+
+    fn declare(&mut self, location: Location, ty: Ty) {
+        if let Some(local) = self.resolution.declarations.get(&location) {
+            self.locals[local.0] = Some(ty);
+        }
+    }
+
+    fn require(&mut self, found: &Ty, expected: &Ty, location: Location) {
+        if !self.table.unify(found, expected) {
+            self.diagnostics.error(Error::TypeMismatch {
+                expected: self.show(expected),
+                found: self.show(found),
+                location,
+            });
+        }
+    }
+
+    fn invalid_operand(&mut self, operator: &str, operand: &Ty, location: Location) {
+        self.diagnostics.error(Error::InvalidOperand {
+            operator: operator.to_string(),
+            operand: self.show(operand),
+            location,
+        });
+    }
+
+    /// Reports an integer literal, possibly negated, whose value does not
+    /// fit in its type.
+    fn check_literal(&mut self, value: i128, ty: &Ty, location: Location) {
+        let range = match ty {
+            Ty::Int => i128::from(i64::MIN)..=i128::from(i64::MAX),
+            Ty::UInt => 0..=i128::from(u64::MAX),
+            _ => return,
+        };
+        if !range.contains(&value) {
+            self.diagnostics.error(Error::LiteralOutOfRange {
+                value,
+                ty: self.show(ty),
+                location,
+            });
+        }
+    }
+
+    fn show(&self, ty: &Ty) -> String {
+        match self.table.resolve(ty) {
+            Ty::Var(_) if self.table.is_integer(ty) => "{integer}".to_string(),
+            ty => ty.display(self.tree).to_string(),
+        }
+    }
+
+    fn show_signature(&self, signature: &Signature) -> String {
+        let receiver = signature
+            .receiver
+            .iter()
+            .map(|ty| format!("self: {}", self.show(ty)));
+        let parameters = signature.parameters.iter().map(|ty| self.show(ty));
+        let parameters: Vec<String> = receiver.chain(parameters).collect();
+        match &signature.return_type {
+            Ty::Unit => format!("fn({})", parameters.join(", ")),
+            ty => format!("fn({}) -> {}", parameters.join(", "), self.show(ty)),
+        }
+    }
+
+    /// Interfaces the type parameter `index` of `owner` is bound by.
+    fn bounds(&self, owner: DefId, index: usize) -> Vec<DefId> {
+        let generics = self.tree.definition(owner).generics();
+        generics.get(index).map_or_else(Vec::new, |generic| {
+            generic
+                .bounds
+                .iter()
+                .filter_map(|bound| match self.resolution.symbol(bound.location) {
+                    Some(Symbol::Definition(id)) => Some(id),
+                    _ => None,
+                })
+                .collect()
+        })
+    }
+
+    fn implements(&self, ty: &Ty, interface: DefId) -> bool {
+        match ty {
+            Ty::Struct(id, _) => self.structs[id].interfaces.contains(&interface),
+            Ty::Param(owner, index) => self.bounds(*owner, *index).contains(&interface),
+            Ty::Error => true,
+            _ => false,
+        }
+    }
+
+    /// Result type of a binary operator, `None` if the operands do not fit.
+    fn binary_type(&mut self, operator: BinaryOperator, lhs: &Ty, rhs: &Ty) -> Option<Ty> {
+        use BinaryOperator as B;
+
+        let integers = self.table.is_integer(lhs) && self.table.is_integer(rhs);
+        let (lhs, rhs) = (self.table.shallow(lhs), self.table.shallow(rhs));
+        let poisoned = lhs.is_error() || rhs.is_error();
+        let pointer = matches!(lhs, Ty::Pointer(_));
+
+        match operator {
+            // checked for the actual type when the function is instantiated
+            _ if matches!(lhs, Ty::Param(..)) && self.table.unify(&lhs, &rhs) => match operator {
+                B::Less
+                | B::LessEqual
+                | B::Greater
+                | B::GreaterEqual
+                | B::Equal
+                | B::NotEqual
+                | B::LogicalAnd
+                | B::LogicalOr => Some(Ty::Bool),
+                _ => Some(lhs),
+            },
+            B::Add | B::Sub if pointer && self.table.is_integer(&rhs) => Some(lhs),
+            B::Add | B::Sub | B::Mul | B::Div | B::Rem | B::And | B::Xor | B::Or
+                if integers && self.table.unify(&lhs, &rhs) =>
+            {
+                Some(if lhs.is_error() { rhs } else { lhs })
+            }
+            B::ShiftLeft | B::ShiftRight if integers => Some(lhs),
+            B::Less | B::LessEqual | B::Greater | B::GreaterEqual
+                if poisoned || ((integers || pointer) && self.table.unify(&lhs, &rhs)) =>
+            {
+                Some(Ty::Bool)
+            }
+            B::Equal | B::NotEqual
+                if !matches!(lhs, Ty::Struct(..) | Ty::Array(_) | Ty::Unit)
+                    && self.table.unify(&lhs, &rhs) =>
+            {
+                Some(Ty::Bool)
+            }
+            B::And | B::Xor | B::Or
+                if self.table.unify(&lhs, &Ty::Bool) && self.table.unify(&rhs, &Ty::Bool) =>
+            {
+                Some(Ty::Bool)
+            }
+            B::LogicalAnd | B::LogicalOr => Some(Ty::Bool),
+            _ if poisoned => Some(Ty::Error),
+            _ => None,
+        }
+    }
+
+    /// Defaults unconstrained integer literals and substitutes the solved
+    /// types into the body and the locals of `function`.
+    fn finish_inference(&mut self, function: DefId, body: &mut [Stmt]) {
+        self.table.default_integers();
+
+        // the types of locals already reported, their initialisers are not
+        let mut reported = Vec::new();
+        for (index, local) in self.resolution.locals.iter().enumerate() {
+            if local.function != function {
+                continue;
+            }
+            let Some(ty) = &self.locals[index] else {
+                continue;
+            };
+            let ty = self.table.resolve(ty);
+            if ty.has_variables() {
+                self.diagnostics.error(Error::AnnotationNeeded {
+                    name: local.name.clone(),
+                    location: local.location,
+                });
+                self.locals[index] = Some(Ty::Error);
+                reported.push(ty);
+            } else {
+                self.locals[index] = Some(ty);
+            }
+        }
+
+        // literals checked together with their minus, visited after it
+        let mut negated = HashSet::new();
+        typed::for_each_expr_mut(body, &mut |expression| {
+            let ty = self.table.resolve(&expression.ty);
+            if ty.has_variables() {
+                // the expressions inside are not visited once replaced
+                if !reported.contains(&ty) {
+                    self.diagnostics.error(Error::AnnotationNeeded {
+                        name: ty.display(self.tree).to_string(),
+                        location: expression.location,
+                    });
+                }
+                expression.kind = ExprKind::Error;
+                expression.ty = Ty::Error;
+                return;
+            }
+            expression.ty = ty;
+            match &mut expression.kind {
+                ExprKind::Int(int) if !negated.contains(&expression.location) => {
+                    self.check_literal(i128::from(*int), &expression.ty, expression.location);
+                }
+                ExprKind::Unary(UnaryOperator::Minus, operand) => {
+                    if let ExprKind::Int(int) = operand.kind {
+                        negated.insert(operand.location);
+                        self.check_literal(-i128::from(int), &expression.ty, expression.location);
+                    }
+                }
+                ExprKind::SizeOf(ty) => *ty = self.table.resolve(ty),
+                ExprKind::Match { arms, .. } => {
+                    for arm in arms {
+                        arm.pattern.for_each_mut(&mut |pattern| {
+                            let ty = self.table.resolve(&pattern.ty);
+                            pattern.ty = if ty.has_variables() { Ty::Error } else { ty };
+                        });
+                    }
+                }
+                ExprKind::Call(instance, _) => {
+                    for generic in &mut instance.generics {
+                        *generic = self.table.resolve(generic);
+                    }
+                    if instance.generics.iter().any(Ty::has_variables) {
+                        self.diagnostics.error(Error::AnnotationNeeded {
+                            name: self.tree.qualified_name(instance.id),
+                            location: expression.location,
+                        });
+                        expression.kind = ExprKind::Error;
+                        return;
+                    }
+                    for (index, generic) in instance.generics.iter().enumerate() {
+                        for interface in self.bounds(instance.id, index) {
+                            if !self.implements(generic, interface) {
+                                self.diagnostics.error(Error::UnsatisfiedBound {
+                                    ty: self.show(generic),
+                                    interface: self.interfaces[&interface].name.clone(),
+                                    location: expression.location,
+                                });
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        });
+    }
+}
+
+/// The struct a receiver of type `ty` belongs to.
+fn receiver_struct(ty: &Ty) -> Option<DefId> {
+    match ty {
+        Ty::Struct(id, _) => Some(*id),
+        Ty::Pointer(pointee) => match **pointee {
+            Ty::Struct(id, _) => Some(id),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The type parameter a receiver of type `ty` belongs to.
+fn receiver_param(ty: &Ty) -> Option<Ty> {
+    match ty {
+        Ty::Param(..) => Some(ty.clone()),
+        Ty::Pointer(pointee) if matches!(**pointee, Ty::Param(..)) => Some((**pointee).clone()),
+        _ => None,
+    }
+}
+
+fn generic_count(tree: &ModuleTree, id: DefId) -> usize {
+    tree.definition(id).generics().len()
+}
+
+/// The value of an integer literal, possibly negated.
+fn constant(expression: &Expr) -> Option<i128> {
+    match &expression.kind {
+        ExprKind::Int(int) => Some(i128::from(*int)),
+        ExprKind::Unary(UnaryOperator::Minus, operand) => constant(operand).map(|value| -value),
+        _ => None,
+    }
+}
+
+fn is_int_literal(expression: &Expression) -> bool {
+    match &expression.kind {
+        ExpressionKind::Literal(Literal::Int(_)) => true,
+        ExpressionKind::Unary(UnaryOperator::Minus, operand) => is_int_literal(operand),
+        _ => false,
+    }
+}
@@ -0,0 +1,672 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::{
+    module::DefId,
+    parser::{
+        ast::{BinaryOperator, UnaryOperator},
+        tokens::Location,
+    },
+    sema::{
+        resolve::{LocalId, LocalKind},
+        types::Ty,
+    },
+};
+
+/// The checked program. Every name is resolved and every expression carries
+/// its type, so later stages do not need the AST or the module tree.
+#[derive(Debug, Default)]
+pub struct TypedProgram {
+    pub structs: BTreeMap<DefId, TypedStruct>,
+    pub enums: BTreeMap<DefId, TypedEnum>,
+    pub interfaces: BTreeMap<DefId, TypedInterface>,
+    pub functions: BTreeMap<Instance, TypedFunction>,
+    pub locals: Vec<TypedLocal>,
+    /// Start of every local's declaration and use, for editor queries.
+    pub bindings: HashMap<Location, LocalId>,
+}
+
+impl TypedProgram {
+    #[must_use]
+    pub fn local(&self, id: LocalId) -> &TypedLocal {
+        &self.locals[id.0]
+    }
+
+    /// The local whose declaration or use covers `location`.
+    #[must_use]
+    pub fn binding_at(&self, location: Location) -> Option<&TypedLocal> {
+        self.bindings.iter().find_map(|(start, id)| {
+            let local = self.local(*id);
+            let covers = start.fid == location.fid
+                && start.l == location.l
+                && (start.c..start.c + local.name.len()).contains(&location.c);
+            covers.then_some(local)
+        })
+    }
+
+    /// The function called `main` in the root namespace.
+    #[must_use]
+    pub fn entry(&self) -> Option<&TypedFunction> {
+        self.functions
+            .values()
+            .find(|function| function.name == "main")
+    }
+}
+
+/// A function together with its type arguments. Generic functions are
+/// checked once with their own type parameters as arguments.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Instance {
+    pub id: DefId,
+    pub generics: Vec<Ty>,
+}
+
+impl Instance {
+    #[must_use]
+    pub fn new(id: DefId, generics: Vec<Ty>) -> Self {
+        Self { id, generics }
+    }
+
+    /// Returns `true` if the instance still mentions type parameters.
+    #[must_use]
+    pub fn is_generic(&self) -> bool {
+        self.generics.iter().any(Ty::has_params)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TypedStruct {
+    pub name: String,
+    /// Names of the type parameters, field types refer to them by index.
+    pub generics: Vec<String>,
+    pub fields: Vec<Field>,
+    /// Functions with a receiver of this struct or a pointer to it.
+    pub methods: BTreeMap<String, DefId>,
+    /// Interfaces with an `impl` block for this struct.
+    pub interfaces: BTreeSet<DefId>,
+    pub location: Location,
+}
+
+impl TypedStruct {
+    #[must_use]
+    pub fn field(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|field| field.name == name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TypedEnum {
+    pub name: String,
+    /// Names of the type parameters, field types refer to them by index.
+    pub generics: Vec<String>,
+    pub variants: Vec<TypedVariant>,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone)]
+pub struct TypedVariant {
+    pub name: String,
+    /// Fields of `name(a, b)` are named by their position.
+    pub fields: Vec<Field>,
+    /// Declared as `name { field: type }`.
+    pub named: bool,
+}
+
+impl TypedVariant {
+    #[must_use]
+    pub fn field(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|field| field.name == name)
+    }
+}
+
+/// An interface, its method types refer to the implementing type as
+/// `Ty::Param(interface, 0)`.
+#[derive(Debug, Clone)]
+pub struct TypedInterface {
+    pub name: String,
+    pub methods: Vec<InterfaceMethod>,
+    pub location: Location,
+}
+
+impl TypedInterface {
+    #[must_use]
+    pub fn method(&self, name: &str) -> Option<usize> {
+        self.methods.iter().position(|method| method.name == name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InterfaceMethod {
+    pub name: String,
+    pub receiver: Ty,
+    pub parameters: Vec<Ty>,
+    pub return_type: Ty,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub name: String,
+    pub ty: Ty,
+}
+
+#[derive(Debug, Clone)]
+pub struct TypedFunction {
+    /// Fully qualified name, e.g. `util::math::max`.
+    pub name: String,
+    /// The receiver, if any, comes first.
+    pub parameters: Vec<LocalId>,
+    pub return_type: Ty,
+    pub body: Vec<Stmt>,
+    /// Declared with `extern`, the body is empty.
+    pub external: bool,
+    pub location: Location,
+}
+
+impl TypedFunction {
+    /// The parameters followed by every local declared in the body, each
+    /// once, in the order of their declarations.
+    #[must_use]
+    pub fn locals(&self) -> Vec<LocalId> {
+        let mut locals = self.parameters.clone();
+        block_locals(&self.body, &mut locals);
+        locals
+    }
+}
+
+fn block_locals(body: &[Stmt], locals: &mut Vec<LocalId>) {
+    for statement in body {
+        match &statement.kind {
+            StmtKind::Block(body) => block_locals(body, locals),
+            StmtKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                expression_locals(condition, locals);
+                block_locals(then, locals);
+                if let Some(otherwise) = otherwise {
+                    block_locals(otherwise, locals);
+                }
+            }
+            StmtKind::For {
+                binding,
+                iteration,
+                body,
+                ..
+            } => {
+                for operand in iteration.operands() {
+                    expression_locals(operand, locals);
+                }
+                locals.push(*binding);
+                block_locals(body, locals);
+            }
+            StmtKind::Let { local, value } => {
+                if let Some(value) = value {
+                    expression_locals(value, locals);
+                }
+                locals.push(*local);
+            }
+            StmtKind::Return(value) | StmtKind::Break { value, .. } => {
+                if let Some(value) = value {
+                    expression_locals(value, locals);
+                }
+            }
+            StmtKind::Continue { .. } => {}
+            StmtKind::Assign { target, value } => {
+                expression_locals(target, locals);
+                expression_locals(value, locals);
+            }
+            StmtKind::Expr(expression) => expression_locals(expression, locals),
+        }
+    }
+}
+
+fn expression_locals(expression: &Expr, locals: &mut Vec<LocalId>) {
+    match &expression.kind {
+        ExprKind::Int(_)
+        | ExprKind::Bool(_)
+        | ExprKind::Str(_)
+        | ExprKind::Local(_)
+        | ExprKind::SizeOf(_)
+        | ExprKind::Error => {}
+        ExprKind::Unary(_, operand) | ExprKind::Field(operand, _) | ExprKind::Len(operand) => {
+            expression_locals(operand, locals);
+        }
+        ExprKind::Binary(_, lhs, rhs) | ExprKind::Index(lhs, rhs) => {
+            expression_locals(lhs, locals);
+            expression_locals(rhs, locals);
+        }
+        ExprKind::Call(_, elements)
+        | ExprKind::InterfaceCall(_, _, _, elements)
+        | ExprKind::StructInit(_, elements)
+        | ExprKind::Variant(_, _, elements)
+        | ExprKind::ArrayInit(elements) => {
+            for element in elements {
+                expression_locals(element, locals);
+            }
+        }
+        ExprKind::Loop { body, .. } => block_locals(body, locals),
+        ExprKind::Match { scrutinee, arms } => {
+            expression_locals(scrutinee, locals);
+            for arm in arms {
+                arm.pattern.bindings(locals);
+                if let Some(guard) = &arm.guard {
+                    expression_locals(guard, locals);
+                }
+                match &arm.body {
+                    ArmBody::Expr(value) => expression_locals(value, locals),
+                    ArmBody::Block(body) => block_locals(body, locals),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TypedLocal {
+    pub name: String,
+    pub kind: LocalKind,
+    pub ty: Ty,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone)]
+pub enum StmtKind {
+    Block(Vec<Stmt>),
+    If {
+        condition: Expr,
+        then: Vec<Stmt>,
+        otherwise: Option<Vec<Stmt>>,
+    },
+    For {
+        label: Option<String>,
+        binding: LocalId,
+        iteration: Box<Iteration>,
+        body: Vec<Stmt>,
+    },
+    Return(Option<Expr>),
+    Break {
+        label: Option<String>,
+        value: Option<Expr>,
+    },
+    Continue {
+        label: Option<String>,
+    },
+    Let {
+        local: LocalId,
+        value: Option<Expr>,
+    },
+    Assign {
+        target: Expr,
+        value: Expr,
+    },
+    Expr(Expr),
+}
+
+/// What a `for` loop iterates over. Every operand is evaluated once,
+/// before the first pass.
+#[derive(Debug, Clone)]
+pub enum Iteration {
+    /// `start..end` or `start..=end` of integers of the binding's type,
+    /// counting up by `step` or down if it is negative. A zero step panics.
+    Range {
+        start: Expr,
+        end: Expr,
+        step: Option<Expr>,
+        inclusive: bool,
+    },
+    /// Every element of an array or every byte of a string.
+    Elements(Expr),
+    /// Calls `next` until it returns `Option::None`. A struct is iterated
+    /// as a copy, a pointer to one is advanced in place. `next` is the call
+    /// without its receiver, the loop passes the address of the iterator.
+    Iterator { iterator: Expr, next: Expr },
+}
+
+impl Iteration {
+    /// Calls `f` on every expression of the iteration, outer ones first.
+    pub fn for_each_expr_mut<F: FnMut(&mut Expr)>(&mut self, f: &mut F) {
+        match self {
+            Self::Range {
+                start, end, step, ..
+            } => {
+                start.for_each_expr_mut(f);
+                end.for_each_expr_mut(f);
+                if let Some(step) = step {
+                    step.for_each_expr_mut(f);
+                }
+            }
+            Self::Elements(iterable) => iterable.for_each_expr_mut(f),
+            Self::Iterator { iterator, next } => {
+                iterator.for_each_expr_mut(f);
+                next.for_each_expr_mut(f);
+            }
+        }
+    }
+
+    /// The expressions of the iteration in evaluation order.
+    #[must_use]
+    pub fn operands(&self) -> Vec<&Expr> {
+        match self {
+            Self::Range {
+                start, end, step, ..
+            } => [Some(start), Some(end), step.as_ref()]
+                .into_iter()
+                .flatten()
+                .collect(),
+            Self::Elements(iterable) => vec![iterable],
+            Self::Iterator { iterator, next } => vec![iterator, next],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub ty: Ty,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone)]
+pub enum ExprKind {
+    Int(u64),
+    Bool(bool),
+    Str(String),
+    Local(LocalId),
+    Unary(UnaryOperator, Box<Expr>),
+    SizeOf(Ty),
+    Binary(BinaryOperator, Box<Expr>, Box<Expr>),
+    Call(Instance, Vec<Expr>),
+    /// Call of the method with the given index of an interface on a value
+    /// of a type parameter, the receiver comes first. The type is the
+    /// parameter until instantiation replaces the call with one of the
+    /// implementing method.
+    InterfaceCall(DefId, usize, Ty, Vec<Expr>),
+    /// Indexing an array or a string panics if the index is not below the
+    /// length, indexing a pointer is unchecked.
+    Index(Box<Expr>, Box<Expr>),
+    /// Field access by index, the operand is a struct or a pointer to one.
+    Field(Box<Expr>, usize),
+    /// `.len` of an array or a string or of a pointer to one, an
+    /// `unsigned int`.
+    Len(Box<Expr>),
+    StructInit(DefId, Vec<Expr>),
+    /// Construction of the variant with the given index, every field is
+    /// given in declaration order.
+    Variant(DefId, usize, Vec<Expr>),
+    ArrayInit(Vec<Expr>),
+    Loop {
+        label: Option<String>,
+        body: Vec<Stmt>,
+    },
+    Match {
+        scrutinee: Box<Expr>,
+        arms: Vec<Arm>,
+    },
+    /// Placeholder for an expression that failed to check.
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Arm {
+    pub pattern: Pattern,
+    pub guard: Option<Expr>,
+    pub body: ArmBody,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone)]
+pub enum ArmBody {
+    Expr(Expr),
+    Block(Vec<Stmt>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub ty: Ty,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone)]
+pub enum PatternKind {
+    Wildcard,
+    Binding(LocalId),
+    Int(u64),
+    Bool(bool),
+    Str(String),
+    /// A variant with a pattern for every field in declaration order.
+    Variant(DefId, usize, Vec<Pattern>),
+    /// A struct with a pattern for every field in declaration order.
+    Struct(DefId, Vec<Pattern>),
+}
+
+impl Pattern {
+    /// Appends the locals bound by the pattern, from left to right.
+    pub fn bindings(&self, locals: &mut Vec<LocalId>) {
+        match &self.kind {
+            PatternKind::Binding(local) => locals.push(*local),
+            PatternKind::Variant(_, _, fields) | PatternKind::Struct(_, fields) => {
+                for field in fields {
+                    field.bindings(locals);
+                }
+            }
+            PatternKind::Wildcard
+            | PatternKind::Int(_)
+            | PatternKind::Bool(_)
+            | PatternKind::Str(_) => {}
+        }
+    }
+
+    /// Calls `f` on this pattern and then on every pattern inside it.
+    pub fn for_each_mut<F: FnMut(&mut Pattern)>(&mut self, f: &mut F) {
+        f(self);
+        if let PatternKind::Variant(_, _, fields) | PatternKind::Struct(_, fields) = &mut self.kind
+        {
+            for field in fields {
+                field.for_each_mut(f);
+            }
+        }
+    }
+}
+
+impl Stmt {
+    /// Calls `f` on every expression of the statement, outer ones first.
+    pub fn for_each_expr_mut<F: FnMut(&mut Expr)>(&mut self, f: &mut F) {
+        match &mut self.kind {
+            StmtKind::Block(body) => for_each_expr_mut(body, f),
+            StmtKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                condition.for_each_expr_mut(f);
+                for_each_expr_mut(then, f);
+                if let Some(otherwise) = otherwise {
+                    for_each_expr_mut(otherwise, f);
+                }
+            }
+            StmtKind::For {
+                iteration, body, ..
+            } => {
+                iteration.for_each_expr_mut(f);
+                for_each_expr_mut(body, f);
+            }
+            StmtKind::Return(value)
+            | StmtKind::Break { value, .. }
+            | StmtKind::Let { value, .. } => {
+                if let Some(value) = value {
+                    value.for_each_expr_mut(f);
+                }
+            }
+            StmtKind::Continue { .. } => {}
+            StmtKind::Assign { target, value } => {
+                target.for_each_expr_mut(f);
+                value.for_each_expr_mut(f);
+            }
+            StmtKind::Expr(expression) => expression.for_each_expr_mut(f),
+        }
+    }
+
+    /// Calls `f` on every expression of the statement, outer ones first.
+    pub fn for_each_expr<F: FnMut(&Expr)>(&self, f: &mut F) {
+        match &self.kind {
+            StmtKind::Block(body) => for_each_expr(body, f),
+            StmtKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                condition.for_each_expr(f);
+                for_each_expr(then, f);
+                if let Some(otherwise) = otherwise {
+                    for_each_expr(otherwise, f);
+                }
+            }
+            StmtKind::For {
+                iteration, body, ..
+            } => {
+                for operand in iteration.operands() {
+                    operand.for_each_expr(f);
+                }
+                for_each_expr(body, f);
+            }
+            StmtKind::Return(value)
+            | StmtKind::Break { value, .. }
+            | StmtKind::Let { value, .. } => {
+                if let Some(value) = value {
+                    value.for_each_expr(f);
+                }
+            }
+            StmtKind::Continue { .. } => {}
+            StmtKind::Assign { target, value } => {
+                target.for_each_expr(f);
+                value.for_each_expr(f);
+            }
+            StmtKind::Expr(expression) => expression.for_each_expr(f),
+        }
+    }
+}
+
+/// Calls `f` on every expression of `body`, outer ones first.
+pub fn for_each_expr_mut<F: FnMut(&mut Expr)>(body: &mut [Stmt], f: &mut F) {
+    for statement in body {
+        statement.for_each_expr_mut(f);
+    }
+}
+
+/// Calls `f` on every expression of `body`, outer ones first.
+pub fn for_each_expr<F: FnMut(&Expr)>(body: &[Stmt], f: &mut F) {
+    for statement in body {
+        statement.for_each_expr(f);
+    }
+}
+
+impl Expr {
+    /// Calls `f` on this expression and then on every expression inside it.
+    pub fn for_each_expr_mut<F: FnMut(&mut Expr)>(&mut self, f: &mut F) {
+        f(self);
+        match &mut self.kind {
+            ExprKind::Int(_)
+            | ExprKind::Bool(_)
+            | ExprKind::Str(_)
+            | ExprKind::Local(_)
+            | ExprKind::SizeOf(_)
+            | ExprKind::Error => {}
+            ExprKind::Unary(_, operand) | ExprKind::Field(operand, _) | ExprKind::Len(operand) => {
+                operand.for_each_expr_mut(f);
+            }
+            ExprKind::Binary(_, lhs, rhs) | ExprKind::Index(lhs, rhs) => {
+                lhs.for_each_expr_mut(f);
+                rhs.for_each_expr_mut(f);
+            }
+            ExprKind::Call(_, elements)
+            | ExprKind::InterfaceCall(_, _, _, elements)
+            | ExprKind::StructInit(_, elements)
+            | ExprKind::Variant(_, _, elements)
+            | ExprKind::ArrayInit(elements) => {
+                for element in elements {
+                    element.for_each_expr_mut(f);
+                }
+            }
+            ExprKind::Loop { body, .. } => for_each_expr_mut(body, f),
+            ExprKind::Match { scrutinee, arms } => {
+                scrutinee.for_each_expr_mut(f);
+                for arm in arms {
+                    if let Some(guard) = &mut arm.guard {
+                        guard.for_each_expr_mut(f);
+                    }
+                    match &mut arm.body {
+                        ArmBody::Expr(value) => value.for_each_expr_mut(f),
+                        ArmBody::Block(body) => for_each_expr_mut(body, f),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Calls `f` on this expression and then on every expression inside it.
+    pub fn for_each_expr<F: FnMut(&Expr)>(&self, f: &mut F) {
+        f(self);
+        match &self.kind {
+            ExprKind::Int(_)
+            | ExprKind::Bool(_)
+            | ExprKind::Str(_)
+            | ExprKind::Local(_)
+            | ExprKind::SizeOf(_)
+            | ExprKind::Error => {}
+            ExprKind::Unary(_, operand) | ExprKind::Field(operand, _) | ExprKind::Len(operand) => {
+                operand.for_each_expr(f);
+            }
+            ExprKind::Binary(_, lhs, rhs) | ExprKind::Index(lhs, rhs) => {
+                lhs.for_each_expr(f);
+                rhs.for_each_expr(f);
+            }
+            ExprKind::Call(_, elements)
+            | ExprKind::InterfaceCall(_, _, _, elements)
+            | ExprKind::StructInit(_, elements)
+            | ExprKind::Variant(_, _, elements)
+            | ExprKind::ArrayInit(elements) => {
+                for element in elements {
+                    element.for_each_expr(f);
+                }
+            }
+            ExprKind::Loop { body, .. } => for_each_expr(body, f),
+            ExprKind::Match { scrutinee, arms } => {
+                scrutinee.for_each_expr(f);
+                for arm in arms {
+                    if let Some(guard) = &arm.guard {
+                        guard.for_each_expr(f);
+                    }
+                    match &arm.body {
+                        ArmBody::Expr(value) => value.for_each_expr(f),
+                        ArmBody::Block(body) => for_each_expr(body, f),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns `true` if the expression denotes a memory location.
+    #[must_use]
+    pub fn is_place(&self) -> bool {
+        match &self.kind {
+            ExprKind::Local(_)
+            | ExprKind::Unary(UnaryOperator::Dereference, _)
+            | ExprKind::Error => true,
+            ExprKind::Index(base, _) => !matches!(base.ty, Ty::Str),
+            ExprKind::Field(base, _) => matches!(base.ty, Ty::Pointer(_)) || base.is_place(),
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,209 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+use std::fmt::{self, Display};
+
+use crate::{
+    module::{DefId, DefKind, ModuleTree},
+    parser::ast::{BinaryOperator, UnaryOperator},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Ty {
+    Unit,
+    Bool,
+    Int,
+    UInt,
+    Str,
+    Pointer(Box<Ty>),
+    Array(Box<Ty>),
+    /// A struct with its type arguments.
+    Struct(DefId, Vec<Ty>),
+    /// An enum with its type arguments.
+    Enum(DefId, Vec<Ty>),
+    /// The type parameter with the given index of a generic struct or
+    /// function.
+    Param(DefId, usize),
+    /// Inference variable, only present while a function body is checked.
+    Var(usize),
+    /// Type of an expression that already failed to check. It is compatible
+    /// with everything so that one mistake is reported only once.
+    Error,
+}
+
+impl Ty {
+    #[must_use]
+    pub fn pointer(pointee: Ty) -> Self {
+        Ty::Pointer(Box::new(pointee))
+    }
+
+    #[must_use]
+    pub fn array(element: Ty) -> Self {
+        Ty::Array(Box::new(element))
+    }
+
+    /// A struct without type arguments.
+    #[must_use]
+    pub fn structure(id: DefId) -> Self {
+        Ty::Struct(id, Vec::new())
+    }
+
+    #[must_use]
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Ty::Int | Ty::UInt | Ty::Error)
+    }
+
+    #[must_use]
+    pub fn is_error(&self) -> bool {
+        *self == Ty::Error
+    }
+
+    #[must_use]
+    pub fn has_variables(&self) -> bool {
+        match self {
+            Ty::Var(_) => true,
+            Ty::Pointer(inner) | Ty::Array(inner) => inner.has_variables(),
+            Ty::Struct(_, generics) | Ty::Enum(_, generics) => {
+                generics.iter().any(Ty::has_variables)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the type mentions a type parameter.
+    #[must_use]
+    pub fn has_params(&self) -> bool {
+        match self {
+            Ty::Param(..) => true,
+            Ty::Pointer(inner) | Ty::Array(inner) => inner.has_params(),
+            Ty::Struct(_, generics) | Ty::Enum(_, generics) => generics.iter().any(Ty::has_params),
+            _ => false,
+        }
+    }
+
+    /// Replaces the type parameters of `owner` by `generics`.
+    #[must_use]
+    pub fn substitute(&self, owner: DefId, generics: &[Ty]) -> Ty {
+        match self {
+            Ty::Param(id, index) if *id == owner => generics[*index].clone(),
+            Ty::Pointer(pointee) => Ty::pointer(pointee.substitute(owner, generics)),
+            Ty::Array(element) => Ty::array(element.substitute(owner, generics)),
+            Ty::Struct(id, arguments) => Ty::Struct(
+                *id,
+                arguments
+                    .iter()
+                    .map(|argument| argument.substitute(owner, generics))
+                    .collect(),
+            ),
+            Ty::Enum(id, arguments) => Ty::Enum(
+                *id,
+                arguments
+                    .iter()
+                    .map(|argument| argument.substitute(owner, generics))
+                    .collect(),
+            ),
+            ty => ty.clone(),
+        }
+    }
+
+    /// Returns `true` if `operator` applies to two operands of this type.
+    /// Operators on type parameters are checked with this once the
+    /// parameters are known.
+    #[must_use]
+    pub fn supports_binary(&self, operator: BinaryOperator) -> bool {
+        use BinaryOperator as B;
+
+        match operator {
+            _ if self.is_error() => true,
+            B::Add | B::Sub | B::Mul | B::Div | B::Rem | B::ShiftLeft | B::ShiftRight => {
+                self.is_integer()
+            }
+            B::And | B::Xor | B::Or => self.is_integer() || *self == Ty::Bool,
+            B::Less | B::LessEqual | B::Greater | B::GreaterEqual => {
+                self.is_integer() || matches!(self, Ty::Pointer(_))
+            }
+            B::Equal | B::NotEqual => !matches!(
+                self,
+                Ty::Struct(..) | Ty::Enum(..) | Ty::Array(_) | Ty::Unit
+            ),
+            B::LogicalAnd | B::LogicalOr => *self == Ty::Bool,
+        }
+    }
+
+    /// Like [`Ty::supports_binary`] for `-` and `!`.
+    #[must_use]
+    pub fn supports_unary(&self, operator: UnaryOperator) -> bool {
+        match operator {
+            UnaryOperator::Minus => matches!(self, Ty::Int | Ty::Error),
+            UnaryOperator::Not => self.is_integer() || *self == Ty::Bool,
+            _ => true,
+        }
+    }
+
+    /// Two types are compatible if they are equal or one of them is poisoned.
+    #[must_use]
+    pub fn compatible(&self, other: &Ty) -> bool {
+        match (self, other) {
+            (Ty::Error, _) | (_, Ty::Error) => true,
+            (Ty::Pointer(a), Ty::Pointer(b)) | (Ty::Array(a), Ty::Array(b)) => a.compatible(b),
+            (Ty::Struct(a, xs), Ty::Struct(b, ys)) | (Ty::Enum(a, xs), Ty::Enum(b, ys)) => {
+                a == b && xs.len() == ys.len() && xs.iter().zip(ys).all(|(x, y)| x.compatible(y))
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    /// Renders the type with qualified struct names.
+    #[must_use]
+    pub fn display<'a>(&'a self, tree: &'a ModuleTree) -> TyDisplay<'a> {
+        TyDisplay { ty: self, tree }
+    }
+}
+
+pub struct TyDisplay<'a> {
+    ty: &'a Ty,
+    tree: &'a ModuleTree,
+}
+
+impl Display for TyDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ty {
+            Ty::Unit => write!(f, "()"),
+            Ty::Bool => write!(f, "bool"),
+            Ty::Int => write!(f, "int"),
+            Ty::UInt => write!(f, "unsigned int"),
+            Ty::Str => write!(f, "str"),
+            Ty::Pointer(pointee) if matches!(**pointee, Ty::Array(_)) => {
+                write!(f, "*({})", pointee.display(self.tree))
+            }
+            Ty::Pointer(pointee) => write!(f, "*{}", pointee.display(self.tree)),
+            Ty::Array(element) => write!(f, "{}[]", element.display(self.tree)),
+            Ty::Struct(id, generics) | Ty::Enum(id, generics) => {
+                write!(f, "{}", self.tree.qualified_name(*id))?;
+                if !generics.is_empty() {
+                    let generics: Vec<String> = generics
+                        .iter()
+                        .map(|ty| ty.display(self.tree).to_string())
+                        .collect();
+                    write!(f, "<{}>", generics.join(", "))?;
+                }
+                Ok(())
+            }
+            Ty::Param(owner, _)
+                if matches!(self.tree.definition(*owner).kind, DefKind::Interface(_)) =>
+            {
+                write!(f, "Self")
+            }
+            Ty::Param(owner, index) => {
+                let generics = self.tree.definition(*owner).generics();
+                match generics.get(*index) {
+                    Some(generic) => write!(f, "{}", generic.name),
+                    None => write!(f, "?"),
+                }
+            }
+            Ty::Var(_) => write!(f, "_"),
+            Ty::Error => write!(f, "{{error}}"),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! The machine a program is compiled for, named by a target triple.
+//!
+//! The target decides the size of pointers and `int`, how scalars are
+//! aligned and the byte order, which the layouts of types and `sizeof`
+//! follow, and which native backend emits its code. The IR and the
+//! interpreters work with eight byte words, so only targets with eight
+//! byte pointers and `int` are lowered or run; the others still check
+//! their programs and print their layouts and C source.
+
+use std::{fmt, str::FromStr};
+
+use crate::{Error, codegen::Architecture, sema::layout::Layout};
+
+/// The order of the bytes of a scalar in memory. It is only printed: the IR
+/// and the interpreters are little endian and `--emit=llvm` leaves the byte
+/// order to the target triple.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+/// The data layout and native backend of a target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub triple: String,
+    /// The native backend, `None` if there is none for the target.
+    pub architecture: Option<Architecture>,
+    pub endian: Endian,
+    /// Size of pointers in bytes.
+    pub pointer_size: u64,
+    /// Size of `int` and `unsigned int` in bytes.
+    pub int_size: u64,
+    /// The largest alignment of a scalar, bigger scalars are aligned to it.
+    pub max_align: u64,
+}
+
+impl Default for Target {
+    fn default() -> Self {
+        "x86_64-unknown-linux-gnu"
+            .parse()
+            .expect("the default triple is known")
+    }
+}
+
+impl FromStr for Target {
+    type Err = Error;
+
+    /// Parses a target triple by its architecture, the first segment.
+    fn from_str(triple: &str) -> Result<Self, Error> {
+        let (endian, pointer_size, max_align) = match triple.split('-').next() {
+            Some("x86_64" | "riscv64" | "riscv64gc" | "aarch64" | "arm64") => {
+                (Endian::Little, 8, 8)
+            }
+            Some("powerpc64" | "s390x") => (Endian::Big, 8, 8),
+            Some("i386" | "i486" | "i586" | "i686") => (Endian::Little, 4, 4),
+            Some("riscv32" | "riscv32gc" | "arm" | "armv7") => (Endian::Little, 4, 8),
+            _ => {
+                return Err(Error::Target {
+                    triple: triple.to_string(),
+                    message: "unknown architecture".to_string(),
+                });
+            }
+        };
+        Ok(Self {
+            triple: triple.to_string(),
+            architecture: triple.parse().ok(),
+            endian,
+            pointer_size,
+            int_size: 8,
+            max_align,
+        })
+    }
+}
+
+impl Target {
+    /// Layout of a scalar of `size` bytes.
+    #[must_use]
+    pub fn scalar(&self, size: u64) -> Layout {
+        Layout {
+            size,
+            align: size.min(self.max_align),
+        }
+    }
+
+    #[must_use]
+    pub fn pointer(&self) -> Layout {
+        self.scalar(self.pointer_size)
+    }
+
+    /// Layout of `int` and `unsigned int`.
+    #[must_use]
+    pub fn int(&self) -> Layout {
+        self.scalar(self.int_size)
+    }
+
+    /// Whether pointers and `int` are eight byte words, like in the IR and
+    /// the interpreters.
+    #[must_use]
+    pub fn has_words(&self) -> bool {
+        self.pointer_size == 8 && self.int_size == 8
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let endian = match self.endian {
+            Endian::Little => "little",
+            Endian::Big => "big",
+        };
+        write!(
+            f,
+            "{}: {} byte pointers, {} byte int, max align {}, {endian} endian",
+            self.triple, self.pointer_size, self.int_size, self.max_align
+        )
+    }
+}
@@ -0,0 +1,131 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+#![warn(clippy::pedantic)]
+
+mod common;
+
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+
+use cringe_lang::{
+    codegen::{PANIC_STATUS, c},
+    driver::{self, Compilation},
+};
+
+use common::{expected, path, programs};
+
+/// Whether there is a C compiler.
+fn compiler() -> bool {
+    let found = Command::new("cc").arg("--version").output().is_ok();
+    if !found {
+        eprintln!("skipped, no cc");
+    }
+    found
+}
+
+fn source(file: &Path) -> String {
+    let Compilation {
+        tree,
+        program,
+        layouts,
+    } = driver::compile(file).unwrap_or_else(|error| panic!("{}: {error}", file.display()));
+    c::emit(&tree, &program, &layouts)
+}
+
+/// Compiles the C for the program in `file` as strict C99 and runs it.
+fn run(file: &Path) -> Output {
+    let name = file.file_stem().unwrap().to_string_lossy();
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let translated = directory.join(format!("{name}.c"));
+    let executable = directory.join(format!("{name}-c"));
+    fs::write(&translated, source(file)).unwrap();
+    let result = Command::new("cc")
+        .args([
+            "-std=c99",
+            "-pedantic-errors",
+            "-Wall",
+            "-Wextra",
+            "-Werror",
+            "-Wno-unused",
+        ])
+        .arg("-o")
+        .arg(&executable)
+        .arg(&translated)
+        .output()
+        .unwrap();
+    assert!(
+        result.status.success(),
+        "{}: {}",
+        file.display(),
+        String::from_utf8_lossy(&result.stderr)
+    );
+    Command::new(&executable).output().unwrap()
+}
+
+#[test]
+fn programs_agree_with_the_vm() {
+    if !compiler() {
+        return;
+    }
+    let benches = ["fibonacci", "sieve", "shapes"]
+        .map(|name| path("benches/programs").join(name).with_extension("cringe"));
+    for file in programs().into_iter().chain(benches) {
+        let (output, status) = expected(&file);
+        let result = run(&file);
+        assert_eq!(
+            String::from_utf8(result.stdout).unwrap(),
+            output,
+            "{}",
+            file.display()
+        );
+        assert_eq!(result.status.code(), Some(status), "{}", file.display());
+    }
+}
+
+#[test]
+fn panics() {
+    if !compiler() {
+        return;
+    }
+    for (name, message) in [
+        (
+            "bounds.cringe",
+            "5:26: panicked: index 3 is out of bounds for length 3\n",
+        ),
+        ("division.cringe", "2:12: panicked: division by zero\n"),
+        ("step.cringe", "2:14: panicked: range with a step of zero\n"),
+    ] {
+        let file = path("tests/failures").join(name);
+        let result = run(&file);
+        assert_eq!(result.status.code(), Some(PANIC_STATUS), "{name}");
+        assert_eq!(
+            String::from_utf8(result.stderr).unwrap(),
+            format!("{}:{message}", file.display())
+        );
+        assert_eq!(String::from_utf8(result.stdout).unwrap(), "3\n");
+    }
+}
+
+#[test]
+fn readable_source() {
+    let file = path("tests/programs/loops.cringe");
+    let source = source(&file);
+    assert!(source.contains(&format!("#line 4 \"{}\"\n", file.display())));
+    assert!(source.contains("goto outer_next;"));
+    assert!(source.contains("goto outer_end;"));
+    assert!(source.contains("for (uint64_t n_3 = 0, n_3_end = limit; n_3 < n_3_end; n_3++) {"));
+
+    let source = self::source(&path("tests/programs/enums.cringe"));
+    assert!(source.contains("union Shape {"));
+    assert!(source.contains("} Circle;"));
+    assert!(source.contains("static cr_str name(int64_t value)"));
+    assert!(source.contains("union std_Option_bool {"));
+
+    let source = self::source(&path("tests/programs/generics.cringe"));
+    assert!(source.contains("struct Pair_int {"));
+}
@@ -0,0 +1,101 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+#![warn(clippy::pedantic)]
+
+mod common;
+
+use std::{env, fs, process::Command};
+
+use cringe_lang::{
+    Error,
+    driver::Options,
+    interpreter::{LIMIT_MAX_DEPTH, NATIVE_FRAME_SIZE},
+};
+
+use common::path;
+
+/// What the compiler prints for `args`, run from the crate root.
+fn stdout(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_cringe-lang"))
+        .current_dir(path(""))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{args:?} failed");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn hover(position: &str) -> String {
+    stdout(&["--hover", position, "tests/cli/hover.cringe"])
+}
+
+#[test]
+fn hover_prints_the_type_of_the_variable() {
+    assert_eq!(hover("2:9"), "big: int\n");
+    assert_eq!(hover("2:11"), "big: int\n");
+    assert_eq!(hover("3:9"), "wide: unsigned int\n");
+    assert_eq!(hover("4:9"), "small: int\n");
+    assert_eq!(hover("5:12"), "flag: bool\n");
+    // the element type of an empty array comes from a later assignment
+    assert_eq!(hover("6:9"), "empty: bool[]\n");
+}
+
+#[test]
+fn hover_names_positions_without_variable() {
+    assert_eq!(
+        hover("2:12"),
+        "no variable at tests/cli/hover.cringe:2:12\n"
+    );
+    assert_eq!(hover("8:5"), "no variable at tests/cli/hover.cringe:8:5\n");
+}
+
+/// `--print-layout` of `tests/cli/layout.cringe` matches `layout.txt`,
+/// which is rewritten instead if `CRINGE_BLESS` is set.
+#[test]
+fn print_layout() {
+    let text = stdout(&["--print-layout", "tests/cli/layout.cringe"]);
+    let expected = path("tests/cli/layout.txt");
+    if env::var_os("CRINGE_BLESS").is_some() {
+        fs::write(&expected, &text).unwrap();
+    }
+    assert_eq!(
+        text,
+        fs::read_to_string(&expected).unwrap_or_default(),
+        "set CRINGE_BLESS to accept the change"
+    );
+}
+
+#[test]
+fn disassemble_names_the_file() {
+    let text = stdout(&["--disassemble", "tests/failures/division.cringe"]);
+    assert!(
+        text.contains("      2  tests/failures/division.cringe:2:12 signed.div\n"),
+        "{text}"
+    );
+    assert!(
+        text.contains("      3  tests/failures/division.cringe:2:5  return 8\n"),
+        "{text}"
+    );
+}
+
+/// Depths whose native stack cannot be reserved are refused before anything
+/// runs.
+#[test]
+fn stack_depth_is_bounded() {
+    let parse = |depth: String| {
+        Options::parse(["--interpret", "--stack-depth", &depth, "main.cringe"].map(String::from))
+    };
+    assert_eq!(
+        parse(LIMIT_MAX_DEPTH.to_string()).unwrap().stack_depth,
+        LIMIT_MAX_DEPTH
+    );
+    for depth in [
+        (LIMIT_MAX_DEPTH + 1).to_string(),
+        (usize::MAX / NATIVE_FRAME_SIZE).to_string(),
+        usize::MAX.to_string(),
+    ] {
+        assert!(matches!(parse(depth), Err(Error::Usage(_))));
+    }
+}
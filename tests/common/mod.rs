@@ -0,0 +1,83 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use cringe_lang::{
+    bytecode::{compiler, vm::Vm},
+    driver::{self, Compilation},
+    ir::{
+        Module, lower,
+        opt::{Level, PassManager},
+        verify,
+    },
+    target::Target,
+};
+
+/// A path relative to the crate root.
+pub fn path(relative: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(relative)
+}
+
+/// The sources in `directory`, relative to the crate root, sorted.
+pub fn programs_in(directory: &str) -> Vec<PathBuf> {
+    let mut programs = fs::read_dir(path(directory))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "cringe")
+        })
+        .collect::<Vec<_>>();
+    programs.sort();
+    assert!(!programs.is_empty());
+    programs
+}
+
+/// The sample programs in `tests/programs`.
+pub fn programs() -> Vec<PathBuf> {
+    programs_in("tests/programs")
+}
+
+/// The output of the program on the VM and its result as an exit status.
+pub fn expected(file: &Path) -> (String, i32) {
+    let Compilation {
+        tree,
+        program,
+        layouts,
+    } = driver::compile(file).unwrap();
+    let module = compiler::compile(&tree, &program, &layouts);
+    let mut output = Vec::new();
+    let result = Vm::new(&module, &mut output).run().unwrap();
+    (
+        String::from_utf8(output).unwrap(),
+        i32::from(result.to_le_bytes()[0]),
+    )
+}
+
+/// The program in `file` compiled for `target` and lowered to IR optimised
+/// at `level`, verified after every pass.
+pub fn lowered(file: &Path, level: Level, target: &Target) -> (Compilation, Module) {
+    let compilation = driver::compile_for(file, target)
+        .unwrap_or_else(|error| panic!("{}: {error}", file.display()));
+    let mut module = lower::lower(
+        &compilation.tree,
+        &compilation.program,
+        &compilation.layouts,
+    );
+    verify::verify(&module).unwrap_or_else(|error| panic!("{}: {error}", file.display()));
+    PassManager::new(level)
+        .verify_each(true)
+        .run(&mut module)
+        .unwrap_or_else(|error| panic!("{}: {error}", file.display()));
+    verify::verify(&module).unwrap_or_else(|error| panic!("{}: {error}", file.display()));
+    (compilation, module)
+}
@@ -0,0 +1,53 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+#![warn(clippy::pedantic)]
+
+mod common;
+
+use std::{env, fs, path::Path, process::Command};
+
+use common::{path, programs_in};
+
+/// The warnings and errors the compiler logs when checking `file`, one per
+/// line without the log prefix.
+fn diagnostics(file: &Path) -> String {
+    let relative = file.strip_prefix(path("")).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_cringe-lang"))
+        .current_dir(path(""))
+        .env("RUST_LOG", "warn")
+        .arg(relative)
+        .output()
+        .unwrap();
+    let mut text = String::new();
+    for line in String::from_utf8(output.stderr).unwrap().lines() {
+        let message = match line.split_once("] ") {
+            Some((_, message)) if line.starts_with('[') => message,
+            _ => line.trim_start(),
+        };
+        text.push_str(message);
+        text.push('\n');
+    }
+    text
+}
+
+/// Compares the diagnostics of every program in `tests/errors` with the
+/// `.err` file next to it, which is rewritten instead if `CRINGE_BLESS` is
+/// set.
+#[test]
+fn errors() {
+    for file in programs_in("tests/errors") {
+        let text = diagnostics(&file);
+        let expected = file.with_extension("err");
+        if env::var_os("CRINGE_BLESS").is_some() {
+            fs::write(&expected, &text).unwrap();
+        }
+        assert_eq!(
+            text,
+            fs::read_to_string(&expected).unwrap_or_default(),
+            "{}, set CRINGE_BLESS to accept the change",
+            file.display()
+        );
+    }
+}
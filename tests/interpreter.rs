@@ -0,0 +1,119 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+#![warn(clippy::pedantic)]
+
+mod common;
+
+use std::{fs, path::Path, thread};
+
+use cringe_lang::{
+    Error,
+    driver::{self, Compilation},
+    interpreter::{DEFAULT_MAX_DEPTH, Interpreter, NATIVE_FRAME_SIZE},
+};
+
+/// Runs the program in `file` and returns its result and output.
+fn run(file: &Path, max_depth: usize) -> (Result<i64, Error>, String) {
+    let Compilation {
+        tree,
+        program,
+        layouts,
+    } = driver::compile(file).unwrap_or_else(|error| panic!("{}: {error}", file.display()));
+    let mut output = Vec::new();
+    let result = thread::scope(|scope| {
+        thread::Builder::new()
+            .stack_size((max_depth + 16) * NATIVE_FRAME_SIZE)
+            .spawn_scoped(scope, || {
+                Interpreter::new(&tree, &program, &layouts, &mut output)
+                    .with_max_depth(max_depth)
+                    .run()
+            })
+            .unwrap()
+            .join()
+            .unwrap()
+    });
+    (result, String::from_utf8(output).unwrap())
+}
+
+/// `message` at a position in `tests/failures/<name>`.
+fn at(name: &str, message: &str) -> String {
+    let file = common::path("tests/failures").join(name);
+    format!("{}:{message}", file.display())
+}
+
+fn failure(name: &str, max_depth: usize) -> (Error, String) {
+    let file = common::path("tests/failures").join(name);
+    let (result, output) = run(&file, max_depth);
+    (result.expect_err("the program fails"), output)
+}
+
+#[test]
+fn programs() {
+    for program in common::programs() {
+        let expected = fs::read_to_string(program.with_extension("out")).unwrap();
+        let (result, output) = run(&program, DEFAULT_MAX_DEPTH);
+        if let Err(error) = result {
+            panic!("{}: {error}", program.display());
+        }
+        assert_eq!(output, expected, "{}", program.display());
+    }
+}
+
+#[test]
+fn exit_status() {
+    let file = common::path("tests/programs/arithmetic.cringe");
+    assert_eq!(run(&file, DEFAULT_MAX_DEPTH).0.unwrap(), 3);
+}
+
+#[test]
+fn stack_overflow() {
+    let (error, output) = failure("recursion.cringe", 64);
+    assert!(
+        matches!(error, Error::StackOverflow { depth: 64, .. }),
+        "{error}"
+    );
+    assert_eq!(output, "start\n");
+}
+
+#[test]
+fn index_out_of_bounds() {
+    let (error, output) = failure("bounds.cringe", DEFAULT_MAX_DEPTH);
+    assert!(matches!(error, Error::Panic { .. }), "{error}");
+    assert_eq!(
+        error.to_string(),
+        at(
+            "bounds.cringe",
+            "5:26: panicked: index 3 is out of bounds for length 3"
+        )
+    );
+    assert_eq!(output, "3\n");
+}
+
+#[test]
+fn division_by_zero() {
+    let (error, output) = failure("division.cringe", DEFAULT_MAX_DEPTH);
+    assert_eq!(
+        error.to_string(),
+        at("division.cringe", "2:12: panicked: division by zero")
+    );
+    assert_eq!(output, "3\n");
+}
+
+#[test]
+fn step_zero() {
+    let (error, output) = failure("step.cringe", DEFAULT_MAX_DEPTH);
+    assert_eq!(
+        error.to_string(),
+        at("step.cringe", "2:14: panicked: range with a step of zero")
+    );
+    assert_eq!(output, "3\n");
+}
+
+#[test]
+fn use_after_free() {
+    let (error, output) = failure("free.cringe", DEFAULT_MAX_DEPTH);
+    assert!(error.to_string().contains("invalid access of 8 bytes"));
+    assert_eq!(output, "freed\n");
+}
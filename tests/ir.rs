@@ -0,0 +1,311 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+#![warn(clippy::pedantic)]
+
+mod common;
+
+use std::{fs, path::Path};
+
+use cringe_lang::{
+    Error,
+    driver::{self, Compilation},
+    interpreter::{DEFAULT_MAX_DEPTH, Interpreter},
+    ir::{Module, interpret::Evaluator, opt::Level, parse, verify},
+    target::Target,
+};
+
+use common::{lowered, path, programs};
+
+fn module(file: &Path) -> Module {
+    lowered(file, Level::O0, &Target::default()).1
+}
+
+/// Evaluates `main` of the module and returns its result and output.
+fn evaluate(module: &Module, max_depth: usize) -> (Result<i64, Error>, String) {
+    let mut output = Vec::new();
+    let result = Evaluator::new(module, &mut output)
+        .with_max_depth(max_depth)
+        .run();
+    (result, String::from_utf8(output).unwrap())
+}
+
+fn run(file: &Path) -> (Result<i64, Error>, String) {
+    evaluate(&module(file), DEFAULT_MAX_DEPTH)
+}
+
+fn invalid(source: &str) -> String {
+    let module = parse::parse(source).unwrap_or_else(|error| panic!("{error}"));
+    match verify::verify(&module) {
+        Err(Error::InvalidIr { message, .. }) => message,
+        other => panic!("expected invalid IR, got {other:?}"),
+    }
+}
+
+#[test]
+fn lowered_programs() {
+    for program in programs() {
+        let expected = fs::read_to_string(program.with_extension("out")).unwrap();
+        let (result, output) = run(&program);
+        if let Err(error) = result {
+            panic!("{}: {error}", program.display());
+        }
+        assert_eq!(output, expected, "{}", program.display());
+    }
+}
+
+#[test]
+fn engines_agree() {
+    for name in ["fibonacci", "sieve", "shapes"] {
+        let file = path("benches/programs").join(name).with_extension("cringe");
+        let Compilation {
+            tree,
+            program,
+            layouts,
+        } = driver::compile(&file).unwrap();
+        let mut expected = Vec::new();
+        Interpreter::new(&tree, &program, &layouts, &mut expected)
+            .run()
+            .unwrap();
+        let (result, output) = run(&file);
+        assert_eq!(result.unwrap(), 0);
+        assert_eq!(output.as_bytes(), expected, "{name}");
+    }
+}
+
+#[test]
+fn round_trip() {
+    for program in programs() {
+        let module = module(&program);
+        for text in [format!("{module}"), format!("{module:#}")] {
+            let parsed = parse::parse(&text)
+                .unwrap_or_else(|error| panic!("{}: {error}", program.display()));
+            verify::verify(&parsed).unwrap();
+            assert_eq!(
+                parsed.to_string(),
+                module.to_string(),
+                "{}",
+                program.display()
+            );
+        }
+        let parsed = parse::parse(&format!("{module:#}")).unwrap();
+        assert_eq!(format!("{parsed:#}"), format!("{module:#}"));
+    }
+}
+
+#[test]
+fn exit_status() {
+    let (result, _) = run(&path("tests/programs/arithmetic.cringe"));
+    assert_eq!(result.unwrap(), 3);
+}
+
+#[test]
+fn panics() {
+    for (name, message, output) in [
+        (
+            "bounds.cringe",
+            "5:26: panicked: index out of bounds",
+            "3\n",
+        ),
+        ("division.cringe", "2:12: panicked: division by zero", "3\n"),
+        (
+            "step.cringe",
+            "2:14: panicked: range with a step of zero",
+            "3\n",
+        ),
+    ] {
+        let file = path("tests/failures").join(name);
+        let (result, printed) = run(&file);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            format!("{}:{message}", file.display())
+        );
+        assert_eq!(printed, output);
+    }
+    let (result, _) = evaluate(&module(&path("tests/failures/recursion.cringe")), 200);
+    assert!(matches!(
+        result.unwrap_err(),
+        Error::StackOverflow { depth: 200, .. }
+    ));
+}
+
+#[test]
+fn handwritten() {
+    let module = parse::parse(
+        "
+        data #0 = \"negative\"
+        extern fn @std::print_int(i64)
+
+        ; sums 1 to n
+        fn @sum(i64) -> i64 {
+        b0(%n: i64):
+          %zero: i64 = const 0
+          jump b1(%zero, %n)
+        b1(%total: i64, %i: i64):
+          %done: i1 = sle %i, %zero
+          branch %done, b3, b2
+        b2:
+          %next: i64 = add %total, %i
+          %one: i64 = const 1
+          %less: i64 = sub %i, %one
+          jump b1(%next, %less)
+        b3:
+          ret %total
+        }
+
+        fn @main() -> i64 {
+        b0:
+          %n: i64 = const 10
+          %sum: i64 = call @sum(%n)
+          call @std::print_int(%sum)
+          %minus: i64 = const -1
+          %negative: i1 = slt %sum, %minus
+          branch %negative, b1, b2
+        b1:
+          trap #0
+        b2:
+          ret %n
+        }
+        ",
+    )
+    .unwrap();
+    verify::verify(&module).unwrap();
+    let (result, output) = evaluate(&module, DEFAULT_MAX_DEPTH);
+    assert_eq!(result.unwrap(), 10);
+    assert_eq!(output, "55");
+    // values are renumbered in the order of their definitions
+    let text = module.to_string();
+    assert!(text.contains("b1(%2: i64, %3: i64):"), "{text}");
+    assert!(text.contains("%2: i64 = const -1"), "{text}");
+}
+
+#[test]
+fn syntax_errors() {
+    for (source, line) in [
+        ("fn @f() {\nb0:\n  %0: i64 = frobnicate\n  ret\n}", 3),
+        // undefined blocks and values are only known at the end
+        ("fn @f() {\nb0:\n  jump b1\n}", 4),
+        ("fn @f() {\nb0:\n  call @g()\n  ret\n}", 3),
+        ("data #0 = 1", 1),
+        ("type $1 = \"str\" (16, 8) { }", 1),
+    ] {
+        match parse::parse(source) {
+            Err(Error::IrSyntax { line: found, .. }) => assert_eq!(found, line, "{source}"),
+            other => panic!("{source}: expected a syntax error, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn verifier() {
+    let dominance = invalid(
+        "
+        fn @f(i1) -> i64 {
+        b0(%c: i1):
+          branch %c, b1, b2
+        b1:
+          %x: i64 = const 1
+          jump b2
+        b2:
+          ret %x
+        }
+        ",
+    );
+    assert!(dominance.contains("does not dominate"), "{dominance}");
+
+    let arguments = invalid(
+        "
+        fn @f() {
+        b0:
+          %x: i64 = const 1
+          jump b1(%x)
+        b1:
+          ret
+        }
+        ",
+    );
+    assert!(
+        arguments.contains("takes 0 arguments, 1 given"),
+        "{arguments}"
+    );
+
+    let types = invalid(
+        "
+        fn @f() -> i64 {
+        b0:
+          %x: i64 = const 1
+          %y: i32 = const 2
+          %z: i64 = add %x, %y
+          ret %z
+        }
+        ",
+    );
+    assert!(types.contains("is i32, expected i64"), "{types}");
+
+    let condition = invalid(
+        "
+        fn @f() {
+        b0:
+          %x: i64 = const 1
+          branch %x, b1, b1
+        b1:
+          ret
+        }
+        ",
+    );
+    assert!(condition.contains("is not an i1"), "{condition}");
+
+    let result = invalid(
+        "
+        fn @f() -> i64 {
+        b0:
+          ret
+        }
+        ",
+    );
+    assert!(result.contains("does not match the signature"), "{result}");
+
+    let call = invalid(
+        "
+        fn @g(ptr) {
+        b0(%p: ptr):
+          ret
+        }
+
+        fn @f() {
+        b0:
+          %x: i64 = const 1
+          call @g(%x)
+          ret
+        }
+        ",
+    );
+    assert!(call.contains("is i64, expected ptr"), "{call}");
+
+    let slot = invalid(
+        "
+        type $0 = \"str\" (16, 8) { \"ptr\" 0: ptr, \"len\" 8: i64 }
+
+        fn @f() {
+        b0:
+          %s: ptr = slot 8, 8, $0
+          ret
+        }
+        ",
+    );
+    assert!(slot.contains("the slot does not fit $0"), "{slot}");
+
+    let nested = invalid("type $0 = \"Node\" (8, 8) { \"next\" 0: $0 }");
+    assert!(nested.contains("$0 is not defined before"), "{nested}");
+}
+
+#[test]
+fn emitted_locations() {
+    let module = module(&path("tests/failures/division.cringe"));
+    let text = format!("{module:#}");
+    assert!(text.contains("sdiv"), "{text}");
+    assert!(text.contains("trap #"), "{text}");
+    assert!(text.contains("!0:2:12"), "{text}");
+    assert!(!module.to_string().contains('!'));
+}
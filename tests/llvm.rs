@@ -0,0 +1,216 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+#![warn(clippy::pedantic)]
+
+mod common;
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    sync::OnceLock,
+};
+
+use cringe_lang::{
+    codegen::{PANIC_STATUS, RUNTIME, llvm},
+    ir::opt::Level,
+    target::Target,
+};
+
+use common::{expected, lowered, path, programs};
+
+/// The arguments `llc` needs for opaque pointers, `None` without `llc` or
+/// a C compiler.
+fn llc() -> Option<Vec<&'static str>> {
+    let version = Command::new("llc")
+        .arg("--version")
+        .output()
+        .ok()
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned());
+    let tools = Command::new("cc").arg("--version").output().is_ok()
+        && cfg!(all(target_arch = "x86_64", target_os = "linux"));
+    let Some(version) = version.filter(|_| tools) else {
+        eprintln!("skipped, no llc or cc");
+        return None;
+    };
+    let major = version
+        .split("version ")
+        .nth(1)
+        .and_then(|rest| rest.split('.').next())
+        .and_then(|major| major.parse::<u32>().ok())
+        .unwrap_or(u32::MAX);
+    Some(if major < 15 {
+        vec!["-opaque-pointers"]
+    } else {
+        Vec::new()
+    })
+}
+
+fn ir(file: &Path, level: Level, target: &Target) -> String {
+    let (compilation, module) = lowered(file, level, target);
+    llvm::emit(&module, &compilation.tree.files, target)
+}
+
+/// Runs a tool, failing the test with its diagnostics.
+fn tool(command: &mut Command, file: &Path) {
+    let result = command.output().unwrap();
+    assert!(
+        result.status.success(),
+        "{}: {}",
+        file.display(),
+        String::from_utf8_lossy(&result.stderr)
+    );
+}
+
+/// The source of the runtime, written once for all tests running in
+/// parallel.
+fn runtime() -> &'static Path {
+    static RUNTIME_SOURCE: OnceLock<PathBuf> = OnceLock::new();
+    RUNTIME_SOURCE.get_or_init(|| {
+        let runtime = Path::new(env!("CARGO_TARGET_TMPDIR")).join("runtime.c");
+        fs::write(&runtime, RUNTIME).unwrap();
+        runtime
+    })
+}
+
+/// Compiles the LLVM IR of the program in `file` with `llc`, links it with
+/// the runtime and runs it.
+fn run(file: &Path, level: Level, arguments: &[&str]) -> Output {
+    let name = format!(
+        "{}-llvm-{level:?}",
+        file.file_stem().unwrap().to_string_lossy()
+    );
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let source = directory.join(&name).with_extension("ll");
+    let object = directory.join(&name).with_extension("o");
+    let executable = directory.join(&name);
+    fs::write(&source, ir(file, level, &Target::default())).unwrap();
+    tool(
+        Command::new("llc")
+            .args(arguments)
+            .args(["-filetype=obj", "-relocation-model=pic"])
+            .arg("-o")
+            .arg(&object)
+            .arg(&source),
+        file,
+    );
+    tool(
+        Command::new("cc")
+            .arg("-o")
+            .arg(&executable)
+            .arg(&object)
+            .arg(runtime()),
+        file,
+    );
+    Command::new(&executable).output().unwrap()
+}
+
+#[test]
+fn programs_agree_with_the_vm() {
+    let Some(arguments) = llc() else {
+        return;
+    };
+    let benches = ["fibonacci", "sieve", "shapes"]
+        .map(|name| path("benches/programs").join(name).with_extension("cringe"));
+    for file in programs().into_iter().chain(benches) {
+        let (output, status) = expected(&file);
+        for level in [Level::O0, Level::O2] {
+            let result = run(&file, level, &arguments);
+            assert_eq!(
+                String::from_utf8(result.stdout).unwrap(),
+                output,
+                "{} at {level:?}",
+                file.display()
+            );
+            assert_eq!(result.status.code(), Some(status), "{}", file.display());
+        }
+    }
+}
+
+#[test]
+fn panics() {
+    let Some(arguments) = llc() else {
+        return;
+    };
+    for (name, message) in [
+        ("bounds.cringe", "5:26: panicked: index out of bounds\n"),
+        ("division.cringe", "2:12: panicked: division by zero\n"),
+        ("step.cringe", "2:14: panicked: range with a step of zero\n"),
+    ] {
+        let file = path("tests/failures").join(name);
+        let result = run(&file, Level::O1, &arguments);
+        assert_eq!(result.status.code(), Some(PANIC_STATUS), "{name}");
+        assert_eq!(
+            String::from_utf8(result.stderr).unwrap(),
+            format!("{}:{message}", file.display())
+        );
+        assert_eq!(String::from_utf8(result.stdout).unwrap(), "3\n");
+    }
+}
+
+/// Compares the IR of a program with `tests/llvm/<name>.ll`, which is
+/// rewritten instead if `CRINGE_BLESS` is set.
+#[test]
+fn snapshots() {
+    for name in ["structs", "enums"] {
+        let file = path("tests/programs").join(name).with_extension("cringe");
+        let root = format!("{}/", env!("CARGO_MANIFEST_DIR"));
+        let text = ir(&file, Level::O1, &Target::default()).replace(&root, "");
+        let snapshot = path("tests/llvm").join(name).with_extension("ll");
+        if env::var_os("CRINGE_BLESS").is_some() {
+            fs::write(&snapshot, &text).unwrap();
+        }
+        assert_eq!(
+            text,
+            fs::read_to_string(&snapshot).unwrap(),
+            "{name}, set CRINGE_BLESS to accept the change"
+        );
+    }
+}
+
+#[test]
+fn types_and_debug_information() {
+    let text = ir(
+        &path("tests/programs/structs.cringe"),
+        Level::O0,
+        &Target::default(),
+    );
+    assert!(text.contains("%Line = type <{ %Point, %Point }>\n"));
+    assert!(text.contains("alloca %Line, align 8"));
+    assert!(text.contains("getelementptr inbounds %Line, ptr "));
+    assert!(text.contains("define i64 @cr.main() !dbg !"));
+    assert!(text.contains("!DICompositeType(tag: DW_TAG_structure_type, name: \"Line\""));
+    assert!(text.contains("!DILocation(line: 47, column: 17, scope: !"));
+}
+
+/// Compiles the IR for other architectures with `llc`, which takes the
+/// target from the triple in the IR.
+#[test]
+fn cross_compiles() {
+    let Some(arguments) = llc() else {
+        return;
+    };
+    let object = Path::new(env!("CARGO_TARGET_TMPDIR")).join("cross.o");
+    for triple in [
+        "aarch64-linux-gnu",
+        "riscv64-linux-gnu",
+        "powerpc64-linux-gnu",
+    ] {
+        let file = path("tests/programs/enums.cringe");
+        let text = ir(&file, Level::O1, &triple.parse().unwrap());
+        assert!(text.contains(&format!("target triple = \"{triple}\"\n")));
+        let source = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{triple}.ll"));
+        fs::write(&source, text).unwrap();
+        tool(
+            Command::new("llc")
+                .args(&arguments)
+                .arg("-filetype=obj")
+                .arg("-o")
+                .arg(&object)
+                .arg(&source),
+            &file,
+        );
+    }
+}
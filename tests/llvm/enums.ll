@@ -0,0 +1,323 @@
+source_filename = "tests/programs/enums.cringe"
+target triple = "x86_64-unknown-linux-gnu"
+
+%Shape = type <{ i8, [23 x i8] }>
+%str = type <{ ptr, i64 }>
+%"std::Option<bool>" = type <{ i8, [1 x i8] }>
+
+@data.0 = private unnamed_addr constant [6 x i8] c"square"
+@data.1 = private unnamed_addr constant [4 x i8] c"zero"
+@data.2 = private unnamed_addr constant [9 x i8] c"minus one"
+@data.3 = private unnamed_addr constant [4 x i8] c"many"
+@data.4 = private unnamed_addr constant [0 x i8] zeroinitializer
+@data.5 = private unnamed_addr constant [9 x i8] c"some true"
+@data.6 = private unnamed_addr constant [10 x i8] c"some false"
+@data.7 = private unnamed_addr constant [4 x i8] c"none"
+
+declare void @cr.std.print(ptr)
+declare void @cr.std.println(ptr)
+declare void @cr.std.print_int(i64)
+declare void @cr.std.print_uint(i64)
+declare void @cr.trap(ptr, i64, ptr, i64, i64) noreturn
+declare void @llvm.memcpy.p0.p0.i64(ptr, ptr, i64, i1)
+
+define i64 @cr.area(ptr %v0) !dbg !15 {
+b0:
+  %v1 = alloca %Shape, align 8, !dbg !16
+  %v2 = alloca %str, align 8, !dbg !17
+  call void @llvm.memcpy.p0.p0.i64(ptr %v1, ptr %v0, i64 24, i1 false), !dbg !16
+  %v3 = load i8, ptr %v1, align 1, !dbg !18
+  %v5 = icmp eq i8 %v3, 0, !dbg !18
+  br i1 %v5, label %b3, label %b2, !dbg !18
+b1:
+  %v6 = phi i64 [ %v15, %b3 ], [ %v38, %b8 ], [ 0, %b10 ]
+  ret i64 %v6, !dbg !19
+b2:
+  %v7 = load i8, ptr %v1, align 1, !dbg !20
+  %v9 = icmp eq i8 %v7, 1, !dbg !20
+  br i1 %v9, label %b5, label %b4, !dbg !20
+b3:
+  %v11 = getelementptr i8, ptr %v1, i64 8, !dbg !21
+  %v12 = load i64, ptr %v11, align 1, !dbg !21
+  %v14 = mul i64 3, %v12, !dbg !22
+  %v15 = mul i64 %v14, %v12, !dbg !22
+  br label %b1, !dbg !18
+b4:
+  %v16 = load i8, ptr %v1, align 1, !dbg !23
+  %v17 = icmp eq i8 %v16, 1, !dbg !23
+  br i1 %v17, label %b8, label %b7, !dbg !23
+b5:
+  %v19 = getelementptr i8, ptr %v1, i64 8, !dbg !24
+  %v20 = load i64, ptr %v19, align 1, !dbg !24
+  %v22 = getelementptr i8, ptr %v1, i64 16, !dbg !25
+  %v23 = load i64, ptr %v22, align 1, !dbg !25
+  %v24 = icmp eq i64 %v20, %v23, !dbg !26
+  br i1 %v24, label %b6, label %b4, !dbg !26
+b6:
+  store ptr @data.0, ptr %v2, align 1, !dbg !17
+  %v27 = getelementptr inbounds %str, ptr %v2, i32 0, i32 1, !dbg !17
+  store i64 6, ptr %v27, align 1, !dbg !17
+  call void @cr.std.println(ptr %v2), !dbg !27
+  %v28 = mul i64 %v20, %v20, !dbg !28
+  ret i64 %v28, !dbg !29
+b7:
+  %v29 = load i8, ptr %v1, align 1, !dbg !30
+  %v31 = icmp eq i8 %v29, 2, !dbg !30
+  br i1 %v31, label %b10, label %b9, !dbg !30
+b8:
+  %v33 = getelementptr i8, ptr %v1, i64 8, !dbg !31
+  %v34 = load i64, ptr %v33, align 1, !dbg !31
+  %v36 = getelementptr i8, ptr %v1, i64 16, !dbg !32
+  %v37 = load i64, ptr %v36, align 1, !dbg !32
+  %v38 = mul i64 %v34, %v37, !dbg !33
+  br label %b1, !dbg !23
+b9:
+  unreachable, !dbg !34
+b10:
+  br label %b1, !dbg !30
+}
+
+define void @cr.name(ptr %v0, i64 %v1) !dbg !37 {
+b0:
+  %v2 = alloca %str, align 8, !dbg !38
+  %v3 = alloca %str, align 8, !dbg !39
+  %v4 = alloca %str, align 8, !dbg !40
+  %v5 = alloca %str, align 8, !dbg !41
+  %v7 = icmp eq i64 %v1, 0, !dbg !42
+  br i1 %v7, label %b3, label %b2, !dbg !42
+b1:
+  call void @llvm.memcpy.p0.p0.i64(ptr %v0, ptr %v2, i64 16, i1 false), !dbg !43
+  ret void, !dbg !43
+b2:
+  %v9 = icmp eq i64 %v1, -1, !dbg !44
+  br i1 %v9, label %b5, label %b4, !dbg !44
+b3:
+  store ptr @data.1, ptr %v3, align 1, !dbg !39
+  %v13 = getelementptr inbounds %str, ptr %v3, i32 0, i32 1, !dbg !39
+  store i64 4, ptr %v13, align 1, !dbg !39
+  call void @llvm.memcpy.p0.p0.i64(ptr %v2, ptr %v3, i64 16, i1 false), !dbg !42
+  br label %b1, !dbg !42
+b4:
+  store ptr @data.3, ptr %v5, align 1, !dbg !41
+  %v17 = getelementptr inbounds %str, ptr %v5, i32 0, i32 1, !dbg !41
+  store i64 4, ptr %v17, align 1, !dbg !41
+  call void @llvm.memcpy.p0.p0.i64(ptr %v2, ptr %v5, i64 16, i1 false), !dbg !45
+  br label %b1, !dbg !45
+b5:
+  store ptr @data.2, ptr %v4, align 1, !dbg !40
+  %v21 = getelementptr inbounds %str, ptr %v4, i32 0, i32 1, !dbg !40
+  store i64 9, ptr %v21, align 1, !dbg !40
+  call void @llvm.memcpy.p0.p0.i64(ptr %v2, ptr %v4, i64 16, i1 false), !dbg !44
+  br label %b1, !dbg !44
+}
+
+define i64 @cr.main() !dbg !48 {
+b0:
+  %v0 = alloca %"std::Option<bool>", align 1, !dbg !49
+  %v1 = alloca %Shape, align 8, !dbg !50
+  %v2 = alloca %str, align 8, !dbg !51
+  %v3 = alloca %Shape, align 8, !dbg !52
+  %v4 = alloca %str, align 8, !dbg !53
+  %v5 = alloca %Shape, align 8, !dbg !54
+  %v6 = alloca %str, align 8, !dbg !55
+  %v7 = alloca %Shape, align 8, !dbg !56
+  %v8 = alloca %str, align 8, !dbg !57
+  %v9 = alloca %str, align 8, !dbg !58
+  %v10 = alloca %str, align 8, !dbg !59
+  %v11 = alloca %str, align 8, !dbg !60
+  %v12 = alloca %"std::Option<bool>", align 1, !dbg !61
+  %v13 = alloca %"std::Option<bool>", align 1, !dbg !62
+  %v14 = alloca %str, align 8, !dbg !63
+  %v15 = alloca %str, align 8, !dbg !64
+  %v16 = alloca %str, align 8, !dbg !65
+  store i8 0, ptr %v1, align 1, !dbg !50
+  %v20 = getelementptr i8, ptr %v1, i64 8, !dbg !50
+  store i64 2, ptr %v20, align 1, !dbg !50
+  %v21 = call i64 @cr.area(ptr %v1), !dbg !66
+  call void @cr.std.print_int(i64 %v21), !dbg !67
+  store ptr @data.4, ptr %v2, align 1, !dbg !51
+  %v24 = getelementptr inbounds %str, ptr %v2, i32 0, i32 1, !dbg !51
+  store i64 0, ptr %v24, align 1, !dbg !51
+  call void @cr.std.println(ptr %v2), !dbg !68
+  store i8 1, ptr %v3, align 1, !dbg !52
+  %v27 = getelementptr i8, ptr %v3, i64 8, !dbg !52
+  store i64 3, ptr %v27, align 1, !dbg !52
+  %v29 = getelementptr i8, ptr %v3, i64 16, !dbg !52
+  store i64 3, ptr %v29, align 1, !dbg !52
+  %v30 = call i64 @cr.area(ptr %v3), !dbg !69
+  call void @cr.std.print_int(i64 %v30), !dbg !70
+  store ptr @data.4, ptr %v4, align 1, !dbg !53
+  %v31 = getelementptr inbounds %str, ptr %v4, i32 0, i32 1, !dbg !53
+  store i64 0, ptr %v31, align 1, !dbg !53
+  call void @cr.std.println(ptr %v4), !dbg !71
+  store i8 1, ptr %v5, align 1, !dbg !54
+  %v32 = getelementptr i8, ptr %v5, i64 8, !dbg !54
+  store i64 2, ptr %v32, align 1, !dbg !54
+  %v34 = getelementptr i8, ptr %v5, i64 16, !dbg !54
+  store i64 5, ptr %v34, align 1, !dbg !54
+  %v35 = call i64 @cr.area(ptr %v5), !dbg !72
+  call void @cr.std.print_int(i64 %v35), !dbg !73
+  store ptr @data.4, ptr %v6, align 1, !dbg !55
+  %v36 = getelementptr inbounds %str, ptr %v6, i32 0, i32 1, !dbg !55
+  store i64 0, ptr %v36, align 1, !dbg !55
+  call void @cr.std.println(ptr %v6), !dbg !74
+  store i8 2, ptr %v7, align 1, !dbg !56
+  %v38 = call i64 @cr.area(ptr %v7), !dbg !75
+  call void @cr.std.print_int(i64 %v38), !dbg !76
+  store ptr @data.4, ptr %v8, align 1, !dbg !57
+  %v39 = getelementptr inbounds %str, ptr %v8, i32 0, i32 1, !dbg !57
+  store i64 0, ptr %v39, align 1, !dbg !57
+  call void @cr.std.println(ptr %v8), !dbg !77
+  call void @cr.name(ptr %v9, i64 0), !dbg !58
+  call void @cr.std.println(ptr %v9), !dbg !78
+  call void @cr.name(ptr %v10, i64 -1), !dbg !59
+  call void @cr.std.println(ptr %v10), !dbg !79
+  call void @cr.name(ptr %v11, i64 7), !dbg !60
+  call void @cr.std.println(ptr %v11), !dbg !80
+  store i8 0, ptr %v12, align 1, !dbg !61
+  %v44 = getelementptr i8, ptr %v12, i64 1, !dbg !61
+  store i1 true, ptr %v44, align 1, !dbg !61
+  call void @llvm.memcpy.p0.p0.i64(ptr %v0, ptr %v12, i64 2, i1 false), !dbg !81
+  call void @llvm.memcpy.p0.p0.i64(ptr %v13, ptr %v0, i64 2, i1 false), !dbg !62
+  %v45 = load i8, ptr %v13, align 1, !dbg !82
+  %v46 = icmp eq i8 %v45, 0, !dbg !82
+  br i1 %v46, label %b3, label %b2, !dbg !82
+b1:
+  ret i64 0, !dbg !49
+b2:
+  %v47 = load i8, ptr %v13, align 1, !dbg !83
+  %v48 = icmp eq i8 %v47, 0, !dbg !83
+  br i1 %v48, label %b6, label %b5, !dbg !83
+b3:
+  %v49 = getelementptr i8, ptr %v13, i64 1, !dbg !84
+  %v50 = load i1, ptr %v49, align 1, !dbg !84
+  %v51 = icmp eq i1 %v50, true, !dbg !84
+  br i1 %v51, label %b4, label %b2, !dbg !84
+b4:
+  store ptr @data.5, ptr %v14, align 1, !dbg !63
+  %v54 = getelementptr inbounds %str, ptr %v14, i32 0, i32 1, !dbg !63
+  store i64 9, ptr %v54, align 1, !dbg !63
+  call void @cr.std.println(ptr %v14), !dbg !85
+  br label %b1, !dbg !82
+b5:
+  %v55 = load i8, ptr %v13, align 1, !dbg !86
+  %v56 = icmp eq i8 %v55, 1, !dbg !86
+  br i1 %v56, label %b9, label %b8, !dbg !86
+b6:
+  %v57 = getelementptr i8, ptr %v13, i64 1, !dbg !87
+  %v58 = load i1, ptr %v57, align 1, !dbg !87
+  %v60 = icmp eq i1 %v58, false, !dbg !87
+  br i1 %v60, label %b7, label %b5, !dbg !87
+b7:
+  store ptr @data.6, ptr %v15, align 1, !dbg !64
+  %v63 = getelementptr inbounds %str, ptr %v15, i32 0, i32 1, !dbg !64
+  store i64 10, ptr %v63, align 1, !dbg !64
+  call void @cr.std.println(ptr %v15), !dbg !88
+  br label %b1, !dbg !83
+b8:
+  unreachable, !dbg !89
+b9:
+  store ptr @data.7, ptr %v16, align 1, !dbg !65
+  %v66 = getelementptr inbounds %str, ptr %v16, i32 0, i32 1, !dbg !65
+  store i64 4, ptr %v66, align 1, !dbg !65
+  call void @cr.std.println(ptr %v16), !dbg !90
+  br label %b1, !dbg !86
+}
+
+!llvm.dbg.cu = !{!0}
+!llvm.module.flags = !{!92, !93}
+
+!0 = distinct !DICompileUnit(language: DW_LANG_C99, file: !1, producer: "cringe-lang", isOptimized: false, runtimeVersion: 0, emissionKind: FullDebug, retainedTypes: !91)
+!1 = !DIFile(filename: "enums.cringe", directory: "tests/programs")
+!2 = !DIBasicType(name: "i8", size: 8, encoding: DW_ATE_signed)
+!3 = !DIDerivedType(tag: DW_TAG_member, name: "tag", file: !1, baseType: !2, size: 8, offset: 0)
+!4 = !{!3}
+!5 = distinct !DICompositeType(tag: DW_TAG_structure_type, name: "Shape", file: !1, size: 192, align: 64, elements: !4)
+!6 = !DIDerivedType(tag: DW_TAG_pointer_type, baseType: null, size: 64)
+!7 = !DIDerivedType(tag: DW_TAG_member, name: "ptr", file: !1, baseType: !6, size: 64, offset: 0)
+!8 = !DIBasicType(name: "i64", size: 64, encoding: DW_ATE_signed)
+!9 = !DIDerivedType(tag: DW_TAG_member, name: "len", file: !1, baseType: !8, size: 64, offset: 64)
+!10 = !{!7, !9}
+!11 = distinct !DICompositeType(tag: DW_TAG_structure_type, name: "str", file: !1, size: 128, align: 64, elements: !10)
+!12 = distinct !DICompositeType(tag: DW_TAG_structure_type, name: "std::Option<bool>", file: !1, size: 16, align: 8, elements: !4)
+!13 = !{!8, !6}
+!14 = !DISubroutineType(types: !13)
+!15 = distinct !DISubprogram(name: "area", linkageName: "cr.area", scope: !1, file: !1, line: 7, type: !14, scopeLine: 7, spFlags: DISPFlagDefinition, unit: !0)
+!16 = !DILocation(line: 8, column: 18, scope: !15)
+!17 = !DILocation(line: 11, column: 26, scope: !15)
+!18 = !DILocation(line: 9, column: 9, scope: !15)
+!19 = !DILocation(line: 8, column: 5, scope: !15)
+!20 = !DILocation(line: 10, column: 9, scope: !15)
+!21 = !DILocation(line: 9, column: 23, scope: !15)
+!22 = !DILocation(line: 9, column: 29, scope: !15)
+!23 = !DILocation(line: 14, column: 9, scope: !15)
+!24 = !DILocation(line: 10, column: 23, scope: !15)
+!25 = !DILocation(line: 10, column: 26, scope: !15)
+!26 = !DILocation(line: 10, column: 33, scope: !15)
+!27 = !DILocation(line: 11, column: 25, scope: !15)
+!28 = !DILocation(line: 12, column: 20, scope: !15)
+!29 = !DILocation(line: 12, column: 13, scope: !15)
+!30 = !DILocation(line: 15, column: 9, scope: !15)
+!31 = !DILocation(line: 14, column: 23, scope: !15)
+!32 = !DILocation(line: 14, column: 26, scope: !15)
+!33 = !DILocation(line: 14, column: 33, scope: !15)
+!34 = !DILocation(line: 8, column: 12, scope: !15)
+!35 = !{null, !6, !8}
+!36 = !DISubroutineType(types: !35)
+!37 = distinct !DISubprogram(name: "name", linkageName: "cr.name", scope: !1, file: !1, line: 19, type: !36, scopeLine: 19, spFlags: DISPFlagDefinition, unit: !0)
+!38 = !DILocation(line: 20, column: 12, scope: !37)
+!39 = !DILocation(line: 21, column: 14, scope: !37)
+!40 = !DILocation(line: 22, column: 15, scope: !37)
+!41 = !DILocation(line: 23, column: 14, scope: !37)
+!42 = !DILocation(line: 21, column: 9, scope: !37)
+!43 = !DILocation(line: 20, column: 5, scope: !37)
+!44 = !DILocation(line: 22, column: 9, scope: !37)
+!45 = !DILocation(line: 23, column: 9, scope: !37)
+!46 = !{null}
+!47 = !DISubroutineType(types: !46)
+!48 = distinct !DISubprogram(name: "main", linkageName: "cr.main", scope: !1, file: !1, line: 27, type: !47, scopeLine: 27, spFlags: DISPFlagDefinition, unit: !0)
+!49 = !DILocation(line: 27, column: 1, scope: !48)
+!50 = !DILocation(line: 28, column: 38, scope: !48)
+!51 = !DILocation(line: 29, column: 18, scope: !48)
+!52 = !DILocation(line: 30, column: 25, scope: !48)
+!53 = !DILocation(line: 31, column: 18, scope: !48)
+!54 = !DILocation(line: 32, column: 25, scope: !48)
+!55 = !DILocation(line: 33, column: 18, scope: !48)
+!56 = !DILocation(line: 34, column: 25, scope: !48)
+!57 = !DILocation(line: 35, column: 18, scope: !48)
+!58 = !DILocation(line: 36, column: 22, scope: !48)
+!59 = !DILocation(line: 37, column: 22, scope: !48)
+!60 = !DILocation(line: 38, column: 22, scope: !48)
+!61 = !DILocation(line: 39, column: 43, scope: !48)
+!62 = !DILocation(line: 40, column: 11, scope: !48)
+!63 = !DILocation(line: 41, column: 44, scope: !48)
+!64 = !DILocation(line: 42, column: 45, scope: !48)
+!65 = !DILocation(line: 43, column: 38, scope: !48)
+!66 = !DILocation(line: 28, column: 24, scope: !48)
+!67 = !DILocation(line: 28, column: 19, scope: !48)
+!68 = !DILocation(line: 29, column: 17, scope: !48)
+!69 = !DILocation(line: 30, column: 24, scope: !48)
+!70 = !DILocation(line: 30, column: 19, scope: !48)
+!71 = !DILocation(line: 31, column: 17, scope: !48)
+!72 = !DILocation(line: 32, column: 24, scope: !48)
+!73 = !DILocation(line: 32, column: 19, scope: !48)
+!74 = !DILocation(line: 33, column: 17, scope: !48)
+!75 = !DILocation(line: 34, column: 24, scope: !48)
+!76 = !DILocation(line: 34, column: 19, scope: !48)
+!77 = !DILocation(line: 35, column: 17, scope: !48)
+!78 = !DILocation(line: 36, column: 17, scope: !48)
+!79 = !DILocation(line: 37, column: 17, scope: !48)
+!80 = !DILocation(line: 38, column: 17, scope: !48)
+!81 = !DILocation(line: 39, column: 5, scope: !48)
+!82 = !DILocation(line: 41, column: 9, scope: !48)
+!83 = !DILocation(line: 42, column: 9, scope: !48)
+!84 = !DILocation(line: 41, column: 22, scope: !48)
+!85 = !DILocation(line: 41, column: 43, scope: !48)
+!86 = !DILocation(line: 43, column: 9, scope: !48)
+!87 = !DILocation(line: 42, column: 22, scope: !48)
+!88 = !DILocation(line: 42, column: 44, scope: !48)
+!89 = !DILocation(line: 40, column: 5, scope: !48)
+!90 = !DILocation(line: 43, column: 37, scope: !48)
+!91 = !{!5, !11, !12}
+!92 = !{i32 7, !"Dwarf Version", i32 4}
+!93 = !{i32 2, !"Debug Info Version", i32 3}
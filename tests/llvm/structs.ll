@@ -0,0 +1,220 @@
+source_filename = "tests/programs/structs.cringe"
+target triple = "x86_64-unknown-linux-gnu"
+
+%Point = type <{ i64, i64 }>
+%Line = type <{ %Point, %Point }>
+%str = type <{ ptr, i64 }>
+
+@data.0 = private unnamed_addr constant [0 x i8] zeroinitializer
+
+declare void @cr.std.print(ptr)
+declare void @cr.std.println(ptr)
+declare void @cr.std.print_int(i64)
+declare void @cr.std.print_uint(i64)
+declare void @cr.trap(ptr, i64, ptr, i64, i64) noreturn
+declare void @llvm.memcpy.p0.p0.i64(ptr, ptr, i64, i1)
+
+define i64 @cr.Line.length(ptr %v0) !dbg !18 {
+b0:
+  %v2 = getelementptr i8, ptr %v0, i64 16, !dbg !19
+  %v3 = load i64, ptr %v2, align 1, !dbg !20
+  %v4 = load i64, ptr %v0, align 1, !dbg !21
+  %v5 = sub i64 %v3, %v4, !dbg !20
+  %v7 = getelementptr i8, ptr %v2, i64 8, !dbg !22
+  %v8 = load i64, ptr %v7, align 1, !dbg !22
+  %v9 = add i64 %v5, %v8, !dbg !20
+  %v10 = getelementptr i8, ptr %v0, i64 8, !dbg !23
+  %v11 = load i64, ptr %v10, align 1, !dbg !23
+  %v12 = sub i64 %v9, %v11, !dbg !20
+  ret i64 %v12, !dbg !24
+}
+
+define void @cr.Point.shift(ptr %v0, i64 %v1) !dbg !27 {
+b0:
+  %v2 = load i64, ptr %v0, align 1, !dbg !28
+  %v3 = add i64 %v2, %v1, !dbg !28
+  store i64 %v3, ptr %v0, align 1, !dbg !29
+  %v5 = getelementptr i8, ptr %v0, i64 8, !dbg !30
+  %v6 = load i64, ptr %v5, align 1, !dbg !30
+  %v7 = add i64 %v6, %v1, !dbg !30
+  store i64 %v7, ptr %v5, align 1, !dbg !31
+  ret void, !dbg !32
+}
+
+define void @cr.swap(ptr %v0, ptr %v1) !dbg !35 {
+b0:
+  %v2 = load i64, ptr %v0, align 1, !dbg !36
+  %v3 = load i64, ptr %v1, align 1, !dbg !37
+  store i64 %v3, ptr %v0, align 1, !dbg !38
+  store i64 %v2, ptr %v1, align 1, !dbg !39
+  ret void, !dbg !40
+}
+
+define i64 @cr.main() !dbg !43 {
+b0:
+  %v0 = alloca %Line, align 8, !dbg !44
+  %v1 = alloca %Line, align 8, !dbg !44
+  %v2 = alloca [8 x i8], align 8, !dbg !44
+  %v3 = alloca [8 x i8], align 8, !dbg !44
+  %v4 = alloca %Line, align 8, !dbg !45
+  %v5 = alloca %Point, align 8, !dbg !46
+  %v6 = alloca %Point, align 8, !dbg !47
+  %v7 = alloca %Line, align 8, !dbg !48
+  %v8 = alloca %str, align 8, !dbg !49
+  %v9 = alloca %Line, align 8, !dbg !50
+  %v10 = alloca %str, align 8, !dbg !51
+  %v11 = alloca %str, align 8, !dbg !52
+  %v12 = alloca %str, align 8, !dbg !53
+  %v13 = alloca %str, align 8, !dbg !54
+  %v14 = alloca %str, align 8, !dbg !55
+  store i64 1, ptr %v5, align 1, !dbg !46
+  %v18 = getelementptr inbounds %Point, ptr %v5, i32 0, i32 1, !dbg !46
+  store i64 2, ptr %v18, align 1, !dbg !46
+  call void @llvm.memcpy.p0.p0.i64(ptr %v4, ptr %v5, i64 16, i1 false), !dbg !45
+  store i64 4, ptr %v6, align 1, !dbg !47
+  %v20 = getelementptr inbounds %Point, ptr %v6, i32 0, i32 1, !dbg !47
+  store i64 8, ptr %v20, align 1, !dbg !47
+  %v22 = getelementptr inbounds %Line, ptr %v4, i32 0, i32 1, !dbg !45
+  call void @llvm.memcpy.p0.p0.i64(ptr %v22, ptr %v6, i64 16, i1 false), !dbg !45
+  call void @llvm.memcpy.p0.p0.i64(ptr %v0, ptr %v4, i64 32, i1 false), !dbg !56
+  call void @llvm.memcpy.p0.p0.i64(ptr %v7, ptr %v0, i64 32, i1 false), !dbg !48
+  %v23 = call i64 @cr.Line.length(ptr %v7), !dbg !57
+  call void @cr.std.print_int(i64 %v23), !dbg !58
+  store ptr @data.0, ptr %v8, align 1, !dbg !49
+  %v26 = getelementptr inbounds %str, ptr %v8, i32 0, i32 1, !dbg !49
+  store i64 0, ptr %v26, align 1, !dbg !49
+  call void @cr.std.println(ptr %v8), !dbg !59
+  %v27 = getelementptr inbounds %Line, ptr %v0, i32 0, i32 1, !dbg !60
+  call void @cr.Point.shift(ptr %v27, i64 2), !dbg !61
+  call void @llvm.memcpy.p0.p0.i64(ptr %v9, ptr %v0, i64 32, i1 false), !dbg !50
+  %v28 = call i64 @cr.Line.length(ptr %v9), !dbg !62
+  call void @cr.std.print_int(i64 %v28), !dbg !63
+  store ptr @data.0, ptr %v10, align 1, !dbg !51
+  %v29 = getelementptr inbounds %str, ptr %v10, i32 0, i32 1, !dbg !51
+  store i64 0, ptr %v29, align 1, !dbg !51
+  call void @cr.std.println(ptr %v10), !dbg !64
+  call void @llvm.memcpy.p0.p0.i64(ptr %v1, ptr %v0, i64 32, i1 false), !dbg !65
+  store i64 100, ptr %v0, align 1, !dbg !66
+  %v31 = load i64, ptr %v1, align 1, !dbg !67
+  call void @cr.std.print_int(i64 %v31), !dbg !68
+  store ptr @data.0, ptr %v11, align 1, !dbg !52
+  %v32 = getelementptr inbounds %str, ptr %v11, i32 0, i32 1, !dbg !52
+  store i64 0, ptr %v32, align 1, !dbg !52
+  call void @cr.std.println(ptr %v11), !dbg !69
+  store i64 1, ptr %v2, align 1, !dbg !70
+  store i64 2, ptr %v3, align 1, !dbg !71
+  call void @cr.swap(ptr %v2, ptr %v3), !dbg !72
+  %v33 = load i64, ptr %v2, align 1, !dbg !73
+  %v35 = mul i64 %v33, 10, !dbg !73
+  %v36 = load i64, ptr %v3, align 1, !dbg !74
+  %v37 = add i64 %v35, %v36, !dbg !73
+  call void @cr.std.print_int(i64 %v37), !dbg !75
+  store ptr @data.0, ptr %v12, align 1, !dbg !53
+  %v38 = getelementptr inbounds %str, ptr %v12, i32 0, i32 1, !dbg !53
+  store i64 0, ptr %v38, align 1, !dbg !53
+  call void @cr.std.println(ptr %v12), !dbg !76
+  store i64 0, ptr %v27, align 1, !dbg !77
+  %v39 = getelementptr inbounds %Point, ptr %v27, i32 0, i32 1, !dbg !78
+  %v40 = load i64, ptr %v39, align 1, !dbg !78
+  call void @cr.std.print_int(i64 %v40), !dbg !79
+  store ptr @data.0, ptr %v13, align 1, !dbg !54
+  %v41 = getelementptr inbounds %str, ptr %v13, i32 0, i32 1, !dbg !54
+  store i64 0, ptr %v41, align 1, !dbg !54
+  call void @cr.std.println(ptr %v13), !dbg !80
+  call void @cr.std.print_uint(i64 32), !dbg !81
+  store ptr @data.0, ptr %v14, align 1, !dbg !55
+  %v43 = getelementptr inbounds %str, ptr %v14, i32 0, i32 1, !dbg !55
+  store i64 0, ptr %v43, align 1, !dbg !55
+  call void @cr.std.println(ptr %v14), !dbg !82
+  ret i64 0, !dbg !44
+}
+
+!llvm.dbg.cu = !{!0}
+!llvm.module.flags = !{!84, !85}
+
+!0 = distinct !DICompileUnit(language: DW_LANG_C99, file: !1, producer: "cringe-lang", isOptimized: false, runtimeVersion: 0, emissionKind: FullDebug, retainedTypes: !83)
+!1 = !DIFile(filename: "structs.cringe", directory: "tests/programs")
+!2 = !DIBasicType(name: "i64", size: 64, encoding: DW_ATE_signed)
+!3 = !DIDerivedType(tag: DW_TAG_member, name: "x", file: !1, baseType: !2, size: 64, offset: 0)
+!4 = !DIDerivedType(tag: DW_TAG_member, name: "y", file: !1, baseType: !2, size: 64, offset: 64)
+!5 = !{!3, !4}
+!6 = distinct !DICompositeType(tag: DW_TAG_structure_type, name: "Point", file: !1, size: 128, align: 64, elements: !5)
+!7 = !DIDerivedType(tag: DW_TAG_member, name: "from", file: !1, baseType: !6, size: 128, offset: 0)
+!8 = !DIDerivedType(tag: DW_TAG_member, name: "to", file: !1, baseType: !6, size: 128, offset: 128)
+!9 = !{!7, !8}
+!10 = distinct !DICompositeType(tag: DW_TAG_structure_type, name: "Line", file: !1, size: 256, align: 64, elements: !9)
+!11 = !DIDerivedType(tag: DW_TAG_pointer_type, baseType: null, size: 64)
+!12 = !DIDerivedType(tag: DW_TAG_member, name: "ptr", file: !1, baseType: !11, size: 64, offset: 0)
+!13 = !DIDerivedType(tag: DW_TAG_member, name: "len", file: !1, baseType: !2, size: 64, offset: 64)
+!14 = !{!12, !13}
+!15 = distinct !DICompositeType(tag: DW_TAG_structure_type, name: "str", file: !1, size: 128, align: 64, elements: !14)
+!16 = !{!2, !11}
+!17 = !DISubroutineType(types: !16)
+!18 = distinct !DISubprogram(name: "Line::length", linkageName: "cr.Line.length", scope: !1, file: !1, line: 11, type: !17, scopeLine: 11, spFlags: DISPFlagDefinition, unit: !0)
+!19 = !DILocation(line: 12, column: 16, scope: !18)
+!20 = !DILocation(line: 12, column: 19, scope: !18)
+!21 = !DILocation(line: 12, column: 33, scope: !18)
+!22 = !DILocation(line: 12, column: 45, scope: !18)
+!23 = !DILocation(line: 12, column: 59, scope: !18)
+!24 = !DILocation(line: 12, column: 5, scope: !18)
+!25 = !{null, !11, !2}
+!26 = !DISubroutineType(types: !25)
+!27 = distinct !DISubprogram(name: "Point::shift", linkageName: "cr.Point.shift", scope: !1, file: !1, line: 15, type: !26, scopeLine: 15, spFlags: DISPFlagDefinition, unit: !0)
+!28 = !DILocation(line: 16, column: 18, scope: !27)
+!29 = !DILocation(line: 16, column: 5, scope: !27)
+!30 = !DILocation(line: 17, column: 18, scope: !27)
+!31 = !DILocation(line: 17, column: 5, scope: !27)
+!32 = !DILocation(line: 15, column: 1, scope: !27)
+!33 = !{null, !11, !11}
+!34 = !DISubroutineType(types: !33)
+!35 = distinct !DISubprogram(name: "swap", linkageName: "cr.swap", scope: !1, file: !1, line: 20, type: !34, scopeLine: 20, spFlags: DISPFlagDefinition, unit: !0)
+!36 = !DILocation(line: 21, column: 18, scope: !35)
+!37 = !DILocation(line: 22, column: 10, scope: !35)
+!38 = !DILocation(line: 22, column: 5, scope: !35)
+!39 = !DILocation(line: 23, column: 5, scope: !35)
+!40 = !DILocation(line: 20, column: 1, scope: !35)
+!41 = !{null}
+!42 = !DISubroutineType(types: !41)
+!43 = distinct !DISubprogram(name: "main", linkageName: "cr.main", scope: !1, file: !1, line: 26, type: !42, scopeLine: 26, spFlags: DISPFlagDefinition, unit: !0)
+!44 = !DILocation(line: 26, column: 1, scope: !43)
+!45 = !DILocation(line: 27, column: 16, scope: !43)
+!46 = !DILocation(line: 27, column: 29, scope: !43)
+!47 = !DILocation(line: 27, column: 55, scope: !43)
+!48 = !DILocation(line: 28, column: 20, scope: !43)
+!49 = !DILocation(line: 29, column: 18, scope: !43)
+!50 = !DILocation(line: 31, column: 20, scope: !43)
+!51 = !DILocation(line: 32, column: 18, scope: !43)
+!52 = !DILocation(line: 36, column: 18, scope: !43)
+!53 = !DILocation(line: 41, column: 18, scope: !43)
+!54 = !DILocation(line: 45, column: 18, scope: !43)
+!55 = !DILocation(line: 47, column: 18, scope: !43)
+!56 = !DILocation(line: 27, column: 5, scope: !43)
+!57 = !DILocation(line: 28, column: 31, scope: !43)
+!58 = !DILocation(line: 28, column: 19, scope: !43)
+!59 = !DILocation(line: 29, column: 17, scope: !43)
+!60 = !DILocation(line: 30, column: 9, scope: !43)
+!61 = !DILocation(line: 30, column: 18, scope: !43)
+!62 = !DILocation(line: 31, column: 31, scope: !43)
+!63 = !DILocation(line: 31, column: 19, scope: !43)
+!64 = !DILocation(line: 32, column: 17, scope: !43)
+!65 = !DILocation(line: 33, column: 5, scope: !43)
+!66 = !DILocation(line: 34, column: 5, scope: !43)
+!67 = !DILocation(line: 35, column: 29, scope: !43)
+!68 = !DILocation(line: 35, column: 19, scope: !43)
+!69 = !DILocation(line: 36, column: 17, scope: !43)
+!70 = !DILocation(line: 37, column: 5, scope: !43)
+!71 = !DILocation(line: 38, column: 5, scope: !43)
+!72 = !DILocation(line: 39, column: 9, scope: !43)
+!73 = !DILocation(line: 40, column: 20, scope: !43)
+!74 = !DILocation(line: 40, column: 29, scope: !43)
+!75 = !DILocation(line: 40, column: 19, scope: !43)
+!76 = !DILocation(line: 41, column: 17, scope: !43)
+!77 = !DILocation(line: 43, column: 5, scope: !43)
+!78 = !DILocation(line: 44, column: 36, scope: !43)
+!79 = !DILocation(line: 44, column: 19, scope: !43)
+!80 = !DILocation(line: 45, column: 17, scope: !43)
+!81 = !DILocation(line: 46, column: 20, scope: !43)
+!82 = !DILocation(line: 47, column: 17, scope: !43)
+!83 = !{!6, !10, !15}
+!84 = !{i32 7, !"Dwarf Version", i32 4}
+!85 = !{i32 2, !"Debug Info Version", i32 3}
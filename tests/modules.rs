@@ -0,0 +1,121 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+#![warn(clippy::pedantic)]
+
+mod common;
+
+use std::{fs, path::Path};
+
+use cringe_lang::{
+    bytecode::{compiler, vm::Vm},
+    driver::{self, Compilation},
+};
+
+use common::{expected, path};
+
+/// The error of compiling `tests/modules/<name>/main.cringe`, with paths
+/// relative to the crate root.
+fn error(name: &str) -> String {
+    let file = path("tests/modules").join(name).join("main.cringe");
+    let Err(error) = driver::compile(&file) else {
+        panic!("{name} compiles");
+    };
+    let root = format!("{}/", env!("CARGO_MANIFEST_DIR"));
+    error.to_string().replace(&root, "")
+}
+
+#[test]
+fn errors_name_their_file() {
+    assert_eq!(
+        error("fields"),
+        "tests/modules/fields/geo.cringe:6:16: geo::Rect has no field q"
+    );
+}
+
+/// A panic in a file other than the root names that file.
+#[test]
+fn runtime_errors_name_their_file() {
+    let Compilation {
+        tree,
+        program,
+        layouts,
+    } = driver::compile(&path("tests/modules/panic/main.cringe")).unwrap();
+    let module = compiler::compile(&tree, &program, &layouts);
+    let error = Vm::new(&module, &mut Vec::new()).run().unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "{}:2:12: panicked: division by zero",
+            path("tests/modules/panic/lib.cringe").display()
+        )
+    );
+}
+
+/// `app` spreads over `foo.cringe` and `foo/mod.cringe` files, with private
+/// helpers, imports and names found in enclosing namespaces.
+#[test]
+fn program_across_files() {
+    let file = path("tests/modules/app/main.cringe");
+    let (output, status) = expected(&file);
+    assert_eq!(
+        output,
+        fs::read_to_string(file.with_extension("out")).unwrap()
+    );
+    assert_eq!(status, 0);
+}
+
+#[test]
+fn namespace_files() {
+    assert_eq!(
+        error("missing"),
+        "tests/modules/missing/main.cringe:1:1: no source for namespace nowhere, \
+         expected tests/modules/missing/nowhere.cringe"
+    );
+    assert_eq!(
+        error("ambiguous"),
+        "tests/modules/ambiguous/main.cringe:1:1: namespace both is defined by both \
+         tests/modules/ambiguous/both.cringe and tests/modules/ambiguous/both/mod.cringe"
+    );
+}
+
+#[test]
+fn private_items() {
+    assert_eq!(
+        error("private"),
+        "tests/modules/private/main.cringe:4:12: lib::hidden is private"
+    );
+}
+
+#[test]
+fn import_cycle() {
+    assert_eq!(
+        error("import_cycle"),
+        "tests/modules/import_cycle/main.cringe:6:13: import cycle while resolving a::f"
+    );
+}
+
+/// Files that include each other, through a link back to the root file.
+#[cfg(unix)]
+#[test]
+fn file_cycle() {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("file_cycle");
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(directory.join("a")).unwrap();
+    let root = directory.join("main.cringe");
+    fs::write(&root, "namespace a;\n\nfn main() {}\n").unwrap();
+    fs::write(directory.join("a.cringe"), "namespace b;\n").unwrap();
+    std::os::unix::fs::symlink(&root, directory.join("a/b.cringe")).unwrap();
+
+    let Err(error) = driver::compile(&root) else {
+        panic!("a file cycle compiles");
+    };
+    let canonical = fs::canonicalize(&directory).unwrap();
+    let [main, a] =
+        ["main.cringe", "a.cringe"].map(|file| canonical.join(file).display().to_string());
+    assert_eq!(
+        error.to_string(),
+        format!("Namespace cycle: {main} -> {a} -> {main}")
+    );
+}
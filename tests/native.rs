@@ -0,0 +1,120 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+#![warn(clippy::pedantic)]
+
+mod common;
+
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+
+use cringe_lang::{
+    codegen::{PANIC_STATUS, link, x86_64},
+    ir::opt::Level,
+    target::Target,
+};
+
+use common::{expected, lowered, path, programs};
+
+/// Whether the assembler and C compiler native programs need are there.
+fn toolchain() -> bool {
+    let found = Command::new("cc").arg("--version").output().is_ok()
+        && Command::new("as").arg("--version").output().is_ok();
+    if !found {
+        eprintln!("skipped, no cc or as");
+    }
+    found && cfg!(all(target_arch = "x86_64", target_os = "linux"))
+}
+
+fn assembly(file: &Path, level: Level) -> String {
+    x86_64::emit(&lowered(file, level, &Target::default()).1)
+}
+
+/// Builds the program in `file` into an executable and runs it.
+fn run(file: &Path, level: Level) -> Output {
+    let name = format!("{}-{level:?}", file.file_stem().unwrap().to_string_lossy());
+    let executable = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    link::link(&assembly(file, level), &executable)
+        .unwrap_or_else(|error| panic!("{}: {error}", file.display()));
+    Command::new(&executable).output().unwrap()
+}
+
+#[test]
+fn programs_agree_with_the_vm() {
+    if !toolchain() {
+        return;
+    }
+    let benches = ["fibonacci", "sieve", "shapes"]
+        .map(|name| path("benches/programs").join(name).with_extension("cringe"));
+    for file in programs().into_iter().chain(benches) {
+        let (output, status) = expected(&file);
+        if let Ok(expected) = fs::read_to_string(file.with_extension("out")) {
+            assert_eq!(output, expected, "{}", file.display());
+        }
+        for level in [Level::O0, Level::O2] {
+            let result = run(&file, level);
+            assert_eq!(
+                String::from_utf8(result.stdout).unwrap(),
+                output,
+                "{} at {level:?}",
+                file.display()
+            );
+            assert_eq!(result.status.code(), Some(status), "{}", file.display());
+        }
+    }
+}
+
+#[test]
+fn exit_status() {
+    if !toolchain() {
+        return;
+    }
+    let result = run(&path("tests/programs/arithmetic.cringe"), Level::O1);
+    assert_eq!(result.status.code(), Some(3));
+}
+
+#[test]
+fn panics() {
+    if !toolchain() {
+        return;
+    }
+    for (name, message) in [
+        ("bounds.cringe", "5:26: panicked: index out of bounds\n"),
+        ("division.cringe", "2:12: panicked: division by zero\n"),
+        ("step.cringe", "2:14: panicked: range with a step of zero\n"),
+    ] {
+        let file = path("tests/failures").join(name);
+        for level in [Level::O0, Level::O2] {
+            let result = run(&file, level);
+            assert_eq!(result.status.code(), Some(PANIC_STATUS), "{name}");
+            assert_eq!(
+                String::from_utf8(result.stderr).unwrap(),
+                format!("{}:{message}", file.display())
+            );
+            assert_eq!(String::from_utf8(result.stdout).unwrap(), "3\n");
+        }
+    }
+}
+
+#[test]
+fn emitted_assembly() {
+    let assembly = assembly(&path("tests/programs/structs.cringe"), Level::O0);
+    assert!(assembly.contains("\t.globl cr.main\n"));
+    assert!(assembly.contains("call cr.std.println@PLT"));
+    assert!(assembly.contains(".section .note.GNU-stack"));
+    assert!(assembly.contains("leave\n\tret\n"));
+}
+
+#[test]
+fn assembler_errors() {
+    if !toolchain() {
+        return;
+    }
+    let error = link::link("\tthis is not assembly\n", Path::new("unused"))
+        .expect_err("the assembler rejects it");
+    assert!(error.to_string().starts_with("as failed: "), "{error}");
+}
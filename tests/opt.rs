@@ -0,0 +1,298 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+#![warn(clippy::pedantic)]
+
+mod common;
+
+use std::{fs, path::Path};
+
+use cringe_lang::{
+    Error,
+    interpreter::DEFAULT_MAX_DEPTH,
+    ir::{
+        Module,
+        interpret::Evaluator,
+        opt::{Level, Pass, PassManager},
+        parse, verify,
+    },
+    target::Target,
+};
+
+use common::{lowered, path, programs_in};
+
+fn module(file: &Path, level: Level) -> Module {
+    lowered(file, level, &Target::default()).1
+}
+
+fn evaluate(module: &Module) -> (Result<i64, Error>, String) {
+    let mut output = Vec::new();
+    let result = Evaluator::new(module, &mut output)
+        .with_max_depth(DEFAULT_MAX_DEPTH)
+        .run();
+    (result, String::from_utf8(output).unwrap())
+}
+
+/// Runs the given passes once over the IR in `source`.
+fn optimise(source: &str, passes: &[Pass]) -> String {
+    let mut module = parse::parse(source).unwrap_or_else(|error| panic!("{error}"));
+    verify::verify(&module).unwrap();
+    PassManager::with_passes(passes.to_vec())
+        .with_max_rounds(1)
+        .verify_each(true)
+        .run(&mut module)
+        .unwrap();
+    module.to_string()
+}
+
+fn insts(module: &Module) -> usize {
+    module
+        .functions
+        .iter()
+        .flat_map(|function| &function.blocks)
+        .map(|block| block.insts.len())
+        .sum()
+}
+
+#[test]
+fn levels_preserve_behaviour() {
+    for level in [Level::O1, Level::O2] {
+        for program in programs_in("tests/programs") {
+            let expected = fs::read_to_string(program.with_extension("out")).unwrap();
+            let (result, output) = evaluate(&module(&program, level));
+            if let Err(error) = result {
+                panic!("{level:?} {}: {error}", program.display());
+            }
+            assert_eq!(output, expected, "{level:?} {}", program.display());
+        }
+        for program in programs_in("benches/programs") {
+            let (result, output) = evaluate(&module(&program, Level::O0));
+            let (optimised, optimised_output) = evaluate(&module(&program, level));
+            assert_eq!(result.unwrap(), optimised.unwrap());
+            assert_eq!(output, optimised_output, "{level:?} {}", program.display());
+        }
+    }
+}
+
+#[test]
+fn failures_keep_their_location() {
+    let file = path("tests/failures/division.cringe");
+    for level in [Level::O1, Level::O2] {
+        let (result, output) = evaluate(&module(&file, level));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            format!("{}:2:12: panicked: division by zero", file.display())
+        );
+        assert_eq!(output, "3\n");
+        let (result, _) = evaluate(&module(&path("tests/programs/arithmetic.cringe"), level));
+        assert_eq!(result.unwrap(), 3);
+    }
+}
+
+#[test]
+fn levels_shrink_programs() {
+    for program in programs_in("tests/programs") {
+        let sizes = [Level::O0, Level::O1, Level::O2].map(|level| insts(&module(&program, level)));
+        assert!(sizes[1] < sizes[0], "{}: {sizes:?}", program.display());
+    }
+}
+
+#[test]
+fn fold() {
+    let folded = optimise(
+        "
+        fn @f(i64) -> i64 {
+        b0(%x: i64):
+          %a: i64 = const 6
+          %b: i64 = const 7
+          %c: i64 = mul %a, %b
+          %zero: i64 = const 0
+          %same: i64 = add %x, %zero
+          %big: i1 = sgt %c, %a
+          branch %big, b1, b2
+        b1:
+          %r: i64 = sub %same, %c
+          ret %r
+        b2:
+          ret %x
+        }
+        ",
+        &[Pass::FOLD],
+    );
+    assert!(folded.contains("const 42"), "{folded}");
+    assert!(folded.contains("const 1"), "{folded}");
+    assert!(folded.contains("jump b1"), "{folded}");
+    assert!(folded.contains("sub %0,"), "{folded}");
+}
+
+#[test]
+fn fold_literals() {
+    // "abc" == "abc" and the length of a literal stored in a slot
+    let folded = optimise(
+        "
+        data #0 = \"abc\"
+        data #1 = \"abd\"
+        fn @cringe.str_eq(ptr, i64, ptr, i64) -> i1 {
+        b0(%a: ptr, %n: i64, %b: ptr, %m: i64):
+          %r: i1 = eq %n, %m
+          ret %r
+        }
+
+        fn @f() -> i64 {
+        b0:
+          %s: ptr = slot 16, 8
+          %p: ptr = data #0
+          %n: i64 = const 3
+          store %p, %s
+          %eight: i64 = const 8
+          %l: ptr = ptradd %s, %eight
+          store %n, %l
+          %length: i64 = load %l
+          %q: ptr = data #1
+          %same: i1 = call @cringe.str_eq(%p, %length, %p, %n)
+          %other: i1 = call @cringe.str_eq(%p, %n, %q, %n)
+          %a: i64 = extend %same
+          %b: i64 = extend %other
+          %r: i64 = add %a, %b
+          ret %r
+        }
+        ",
+        &[Pass::FOLD, Pass::DCE],
+    );
+    let f = &folded[folded.find("fn @f").unwrap()..];
+    assert!(!f.contains("call"), "{folded}");
+    assert!(!f.contains("load"), "{folded}");
+    assert!(f.contains("const 1"), "{folded}");
+}
+
+#[test]
+fn dce() {
+    let reduced = optimise(
+        "
+        fn @f(i64) -> i64 {
+        b0(%x: i64):
+          %unused: i64 = mul %x, %x
+          %s: ptr = slot 8, 8
+          store %x, %s
+          jump b1(%x, %unused)
+        b1(%y: i64, %dead: i64):
+          ret %y
+        }
+        ",
+        &[Pass::DCE],
+    );
+    assert!(!reduced.contains("mul"), "{reduced}");
+    assert!(!reduced.contains("slot"), "{reduced}");
+    assert!(reduced.contains("b1(%1: i64):"), "{reduced}");
+}
+
+#[test]
+fn cse() {
+    let reduced = optimise(
+        "
+        fn @f(i64, i64, i1) -> i64 {
+        b0(%x: i64, %y: i64, %c: i1):
+          %a: i64 = add %x, %y
+          branch %c, b1, b2
+        b1:
+          %b: i64 = add %y, %x
+          ret %b
+        b2:
+          %d: i64 = mul %x, %y
+          ret %d
+        }
+        ",
+        &[Pass::CSE],
+    );
+    assert_eq!(reduced.matches("add").count(), 1, "{reduced}");
+    assert!(reduced.contains("ret %3"), "{reduced}");
+}
+
+#[test]
+fn inline() {
+    let inlined = optimise(
+        "
+        extern fn @std::print_int(i64)
+
+        fn @double(i64) -> i64 {
+        b0(%x: i64):
+          %s: ptr = slot 8, 8
+          %r: i64 = add %x, %x
+          ret %r
+        }
+
+        fn @loop(i64) -> i64 {
+        b0(%x: i64):
+          %r: i64 = call @loop(%x)
+          ret %r
+        }
+
+        fn @main() -> i64 {
+        b0:
+          %one: i64 = const 1
+          %two: i64 = call @double(%one)
+          call @std::print_int(%two)
+          %r: i64 = call @loop(%two)
+          ret %r
+        }
+        ",
+        &[Pass::INLINE],
+    );
+    let main = &inlined[inlined.find("fn @main").unwrap()..];
+    assert!(!main.contains("@double"), "{inlined}");
+    assert!(main.contains("slot 8, 8"), "{inlined}");
+    assert!(main.contains("call @std::print_int"), "{inlined}");
+    // recursive functions stay
+    assert!(inlined.contains("call @loop(%0)"), "{inlined}");
+}
+
+#[test]
+fn licm() {
+    let hoisted = optimise(
+        "
+        fn @f(i64, i64) -> i64 {
+        b0(%n: i64, %k: i64):
+          %zero: i64 = const 0
+          jump b1(%zero, %zero)
+        b1(%i: i64, %sum: i64):
+          %done: i1 = sge %i, %n
+          branch %done, b3, b2
+        b2:
+          %square: i64 = mul %k, %k
+          %quotient: i64 = sdiv %sum, %k
+          %next: i64 = add %sum, %square
+          %one: i64 = const 1
+          %step: i64 = add %i, %one
+          jump b1(%step, %next)
+        b3:
+          ret %sum
+        }
+        ",
+        &[Pass::LICM],
+    );
+    let entry = &hoisted[..hoisted.find("b1(").unwrap()];
+    assert!(entry.contains("mul %1, %1"), "{hoisted}");
+    assert!(entry.contains("const 1"), "{hoisted}");
+    // a division may only run where the loop would run it
+    assert!(!entry.contains("sdiv"), "{hoisted}");
+}
+
+#[test]
+fn timings() {
+    let mut module = module(&path("tests/programs/loops.cringe"), Level::O0);
+    let mut passes = PassManager::new(Level::O2);
+    passes.run(&mut module).unwrap();
+    let names = passes
+        .timings()
+        .iter()
+        .map(|timing| timing.pass)
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["inline", "fold", "cse", "licm", "dce", "simplify"]);
+    for timing in passes.timings() {
+        assert!(timing.runs >= 1);
+        assert!(timing.changes <= timing.runs);
+        assert!(timing.to_string().contains(timing.pass));
+    }
+    assert!(PassManager::new(Level::O0).timings().is_empty());
+}
@@ -0,0 +1,118 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+#![warn(clippy::pedantic)]
+
+use cringe_lang::parser::{
+    Parser,
+    ast::{Expression, ExpressionKind, ItemKind, Literal, StatementKind},
+};
+
+/// Writes `expression` with every operator application in parentheses.
+fn group(expression: &Expression) -> String {
+    match &expression.kind {
+        ExpressionKind::Identifier(path) => path.to_string(),
+        ExpressionKind::Literal(Literal::Int(value)) => value.to_string(),
+        ExpressionKind::Unary(operator, operand) => format!("({operator}{})", group(operand)),
+        ExpressionKind::Binary(operator, lhs, rhs) => {
+            format!("({} {operator} {})", group(lhs), group(rhs))
+        }
+        ExpressionKind::Call(callee, arguments) => {
+            let arguments: Vec<String> = arguments.iter().map(group).collect();
+            format!("({}({}))", group(callee), arguments.join(", "))
+        }
+        ExpressionKind::Index(base, index) => format!("({}[{}])", group(base), group(index)),
+        ExpressionKind::Member(base, member) => format!("({}.{member})", group(base)),
+        kind => panic!("no grouping for {kind:?}"),
+    }
+}
+
+/// Parses `expression` as the value of a `return` and groups it.
+fn parse(expression: &str) -> String {
+    let source = format!("fn f() -> int {{ return {expression}; }}");
+    let root = Parser::new(source.as_bytes(), 0)
+        .parse()
+        .unwrap_or_else(|error| panic!("{expression}: {error}"));
+    let ItemKind::Function(function) = &root.items[0].kind else {
+        panic!("{expression}: not a function");
+    };
+    let StatementKind::Return(Some(value)) = &function.body[0].kind else {
+        panic!("{expression}: not a return");
+    };
+    group(value)
+}
+
+#[test]
+fn binary_operators_are_left_associative() {
+    for operator in [
+        "*", "/", "%", "+", "-", "<<", ">>", "<", "<=", ">", ">=", "==", "!=", "&", "^", "|", "&&",
+        "||",
+    ] {
+        assert_eq!(
+            parse(&format!("a {operator} b {operator} c")),
+            format!("((a {operator} b) {operator} c)")
+        );
+    }
+}
+
+#[test]
+fn each_level_binds_tighter_than_the_next() {
+    // one operator of every binary level, tightest first
+    let levels = ["*", "+", "<<", "<", "==", "&", "^", "|", "&&", "||"];
+    for pair in levels.windows(2) {
+        let (tight, loose) = (pair[0], pair[1]);
+        assert_eq!(
+            parse(&format!("a {loose} b {tight} c")),
+            format!("(a {loose} (b {tight} c))")
+        );
+        assert_eq!(
+            parse(&format!("a {tight} b {loose} c")),
+            format!("((a {tight} b) {loose} c)")
+        );
+    }
+}
+
+#[test]
+fn operators_of_one_level_bind_equally() {
+    assert_eq!(parse("a * b / c % d"), "(((a * b) / c) % d)");
+    assert_eq!(parse("a - b + c"), "((a - b) + c)");
+    assert_eq!(parse("a >> b << c"), "((a >> b) << c)");
+    assert_eq!(parse("a >= b < c"), "((a >= b) < c)");
+    assert_eq!(parse("a < b == c > d"), "((a < b) == (c > d))");
+    assert_eq!(parse("a != b == c"), "((a != b) == c)");
+}
+
+#[test]
+fn shifts_bind_looser_than_arithmetic() {
+    assert_eq!(parse("x << 1 + 2"), "(x << (1 + 2))");
+    assert_eq!(parse("x >> 2 * y"), "(x >> (2 * y))");
+}
+
+#[test]
+fn postfix_operators_bind_tighter_than_prefix() {
+    assert_eq!(parse("-a.b"), "(-(a.b))");
+    assert_eq!(parse("*p.x"), "(*(p.x))");
+    assert_eq!(parse("&a[0]"), "(&(a[0]))");
+    assert_eq!(parse("!f(x)"), "(!(f(x)))");
+    assert_eq!(parse("sizeof a.b"), "(sizeof(a.b))");
+    assert_eq!(parse("a.b[1](c).d"), "((((a.b)[1])(c)).d)");
+}
+
+#[test]
+fn prefix_operators_nest_and_bind_tighter_than_binary() {
+    assert_eq!(parse("- -a"), "(-(-a))");
+    assert_eq!(parse("*&a"), "(*(&a))");
+    assert_eq!(parse("-a * b"), "((-a) * b)");
+    assert_eq!(parse("a * -b"), "(a * (-b))");
+    assert_eq!(parse("*p * *q"), "((*p) * (*q))");
+    assert_eq!(parse("a & &b"), "(a & (&b))");
+}
+
+#[test]
+fn parentheses_group() {
+    assert_eq!(parse("(a - b) - c"), "((a - b) - c)");
+    assert_eq!(parse("a - (b - c)"), "(a - (b - c))");
+    assert_eq!(parse("(*p).x"), "((*p).x)");
+    assert_eq!(parse("(a || b) && c"), "((a || b) && c)");
+}
@@ -0,0 +1,130 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+#![warn(clippy::pedantic)]
+
+mod common;
+
+use std::{fmt::Write, path::Path};
+
+use cringe_lang::{
+    codegen::{
+        regalloc::{self, Place},
+        x86_64::REGISTERS,
+    },
+    ir::{Module, opt::Level, parse, verify},
+    target::Target,
+};
+
+use common::{lowered, path, programs};
+
+fn module(file: &Path, level: Level) -> Module {
+    lowered(file, level, &Target::default()).1
+}
+
+#[test]
+fn live_values_do_not_share_places() {
+    let benches = ["fibonacci", "sieve", "shapes"]
+        .map(|name| path("benches/programs").join(name).with_extension("cringe"));
+    for file in programs().into_iter().chain(benches) {
+        for level in [Level::O0, Level::O2] {
+            let module = module(&file, level);
+            for function in module.functions.iter().filter(|f| !f.is_extern()) {
+                let allocation = regalloc::allocate(function, &REGISTERS);
+                let intervals = &allocation.intervals;
+                for (index, interval) in intervals.iter().enumerate() {
+                    let place = allocation.place(interval.value).unwrap();
+                    if interval.crosses_call {
+                        assert!(
+                            !matches!(place, Place::Register(register)
+                                if REGISTERS.caller_saved.contains(&register)),
+                            "{} in {}",
+                            interval.value,
+                            function.name
+                        );
+                    }
+                    for other in &intervals[index + 1..] {
+                        if other.start < interval.end {
+                            assert_ne!(
+                                Some(place),
+                                allocation.place(other.value),
+                                "{} and {} in {} of {}\n{allocation}",
+                                interval.value,
+                                other.value,
+                                function.name,
+                                file.display()
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn live_ranges() {
+    let module = parse::parse(
+        "
+        extern fn @show(i32)
+
+        fn @main(i32) -> i64 {
+        b0(%n: i32):
+          %one: i32 = const 1
+          %m: i32 = add %n, %one
+          call @show(%m)
+          jump b1(%m)
+        b1(%x: i32):
+          %wide: i64 = extend %x
+          ret %wide
+        }
+        ",
+    )
+    .unwrap();
+    verify::verify(&module).unwrap();
+    let allocation = regalloc::allocate(&module.functions[1], &REGISTERS);
+    assert_eq!(
+        allocation.to_string(),
+        "fn main
+  b0 0..9, b1 10..15
+  %0     [0, 4]       %r10
+  %1     [2, 4]       %r11
+  %2     [4, 8]       %rbx, crosses a call
+  %3     [10, 12]     %rbx
+  %4     [12, 14]     %rbx
+  2 of 2 moves coalesced, 0 spill slots, saves %rbx
+"
+    );
+}
+
+#[test]
+fn spills() {
+    let mut source = String::from("fn @main() -> i64 {\nb0:\n");
+    for index in 0..10 {
+        writeln!(source, "  %c{index}: i64 = const {index}").unwrap();
+    }
+    source.push_str("  %s0: i64 = add %c0, %c1\n");
+    for index in 2..10 {
+        writeln!(
+            source,
+            "  %s{}: i64 = add %s{}, %c{index}",
+            index - 1,
+            index - 2
+        )
+        .unwrap();
+    }
+    source.push_str("  ret %s8\n}\n");
+    let module = parse::parse(&source).unwrap();
+    verify::verify(&module).unwrap();
+    let allocation = regalloc::allocate(&module.functions[0], &REGISTERS);
+    assert_eq!(allocation.spill_slots, 3, "{allocation}");
+    assert_eq!(allocation.saved.len(), REGISTERS.callee_saved.len());
+    // the constants used last are spilled, the sums reuse the registers
+    let last = module.functions[0].blocks[0].insts[9].result.unwrap();
+    assert_eq!(
+        allocation.place(last),
+        Some(Place::Spill(2)),
+        "{allocation}"
+    );
+}
@@ -0,0 +1,284 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+#![warn(clippy::pedantic)]
+
+mod common;
+
+use std::{
+    env, fs,
+    path::Path,
+    process::{Command, Output},
+};
+
+use cringe_lang::{
+    Error,
+    codegen::{Architecture, PANIC_STATUS, c, link},
+    driver::{self, Compilation, Options},
+    ir::opt::Level,
+    sema::layout::Layout,
+    target::{Endian, Target},
+};
+
+use common::{expected, lowered, path, programs};
+
+const TARGETS: [(Architecture, &str); 2] = [
+    (Architecture::Riscv64, "riscv64"),
+    (Architecture::Aarch64, "aarch64"),
+];
+
+fn assembly(file: &Path, level: Level, architecture: Architecture) -> String {
+    architecture.emit(&lowered(file, level, &Target::default()).1)
+}
+
+/// Whether a tool runs with `--version`.
+fn found(tool: &str) -> bool {
+    Command::new(tool).arg("--version").output().is_ok()
+}
+
+/// Builds the program in `file` with the cross tools and runs it with
+/// `qemu-user`, `None` if one of them is missing.
+fn run(file: &Path, level: Level, architecture: Architecture, name: &str) -> Option<Output> {
+    let (assembler, compiler) = architecture.tools();
+    let qemu = format!("qemu-{name}");
+    if ![assembler.as_str(), compiler.as_str(), qemu.as_str()]
+        .into_iter()
+        .all(found)
+    {
+        eprintln!("skipped, no {assembler}, {compiler} or {qemu}");
+        return None;
+    }
+    let stem = file.file_stem().unwrap().to_string_lossy();
+    let executable =
+        Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{stem}-{name}-{level:?}"));
+    link::link_with(
+        &assembly(file, level, architecture),
+        &executable,
+        &assembler,
+        &compiler,
+    )
+    .unwrap_or_else(|error| panic!("{}: {error}", file.display()));
+    let output = Command::new(&qemu)
+        .arg("-L")
+        .arg(format!("/usr/{name}-linux-gnu"))
+        .arg(&executable)
+        .output()
+        .unwrap();
+    Some(output)
+}
+
+#[test]
+fn programs_agree_with_the_vm() {
+    let benches = ["fibonacci", "sieve", "shapes"]
+        .map(|name| path("benches/programs").join(name).with_extension("cringe"));
+    'targets: for (architecture, name) in TARGETS {
+        for file in programs().iter().chain(&benches) {
+            let (output, status) = expected(file);
+            for level in [Level::O0, Level::O2] {
+                // without the tools of one target, the others still run
+                let Some(result) = run(file, level, architecture, name) else {
+                    continue 'targets;
+                };
+                assert_eq!(
+                    String::from_utf8(result.stdout).unwrap(),
+                    output,
+                    "{} at {level:?} on {name}",
+                    file.display()
+                );
+                assert_eq!(result.status.code(), Some(status), "{}", file.display());
+            }
+        }
+    }
+}
+
+#[test]
+fn panics() {
+    for (architecture, name) in TARGETS {
+        let file = path("tests/failures/division.cringe");
+        let Some(result) = run(&file, Level::O1, architecture, name) else {
+            continue;
+        };
+        assert_eq!(result.status.code(), Some(PANIC_STATUS), "{name}");
+        assert_eq!(
+            String::from_utf8(result.stderr).unwrap(),
+            format!("{}:2:12: panicked: division by zero\n", file.display())
+        );
+    }
+}
+
+/// Assembles every program with `llvm-mc`, which knows every target.
+#[test]
+fn programs_assemble() {
+    if !found("llvm-mc") {
+        eprintln!("skipped, no llvm-mc");
+        return;
+    }
+    let object = Path::new(env!("CARGO_TARGET_TMPDIR")).join("targets.o");
+    for (architecture, name) in TARGETS {
+        for file in programs() {
+            for level in [Level::O0, Level::O2] {
+                let source = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.s"));
+                fs::write(&source, assembly(&file, level, architecture)).unwrap();
+                let result = Command::new("llvm-mc")
+                    .arg(format!("-triple={name}-linux-gnu"))
+                    .args(
+                        ["-mattr=+m,+a,+f,+d,+c"]
+                            .iter()
+                            .filter(|_| name == "riscv64"),
+                    )
+                    .arg("-filetype=obj")
+                    .arg("-o")
+                    .arg(&object)
+                    .arg(&source)
+                    .output()
+                    .unwrap();
+                assert!(
+                    result.status.success(),
+                    "{} at {level:?} on {name}: {}",
+                    file.display(),
+                    String::from_utf8_lossy(&result.stderr)
+                );
+            }
+        }
+    }
+}
+
+/// Compares the assembly of a program with `tests/targets/<name>.s`, which
+/// is rewritten instead if `CRINGE_BLESS` is set.
+#[test]
+fn snapshots() {
+    for (architecture, name) in TARGETS {
+        let text = assembly(
+            &path("tests/programs/calls.cringe"),
+            Level::O1,
+            architecture,
+        );
+        let snapshot = path("tests/targets").join(name).with_extension("s");
+        if env::var_os("CRINGE_BLESS").is_some() {
+            fs::write(&snapshot, &text).unwrap();
+        }
+        assert_eq!(
+            text,
+            fs::read_to_string(&snapshot).unwrap(),
+            "{name}, set CRINGE_BLESS to accept the change"
+        );
+    }
+}
+
+#[test]
+fn target_triples() {
+    for (triple, architecture) in [
+        ("x86_64-unknown-linux-gnu", Architecture::X86_64),
+        ("riscv64gc-unknown-linux-gnu", Architecture::Riscv64),
+        ("aarch64-linux-gnu", Architecture::Aarch64),
+    ] {
+        assert_eq!(triple.parse(), Ok(architecture));
+    }
+    assert_eq!("mips-linux-gnu".parse::<Architecture>(), Err(()));
+    let riscv = assembly(
+        &path("tests/programs/calls.cringe"),
+        Level::O0,
+        Architecture::Riscv64,
+    );
+    assert!(riscv.contains("\tcall cr.std.println\n"));
+    assert!(riscv.contains("\tsd ra, 8(sp)\n"));
+    let arm = assembly(
+        &path("tests/programs/calls.cringe"),
+        Level::O0,
+        Architecture::Aarch64,
+    );
+    assert!(arm.contains("\tbl cr.std.println\n"));
+    assert!(arm.contains("\tstp x29, x30, [sp, #-16]!\n"));
+}
+
+#[test]
+fn data_layouts() {
+    for (triple, architecture, endian, pointer, align) in [
+        (
+            "x86_64-unknown-linux-gnu",
+            Some(Architecture::X86_64),
+            Endian::Little,
+            8,
+            8,
+        ),
+        (
+            "riscv64gc-linux-gnu",
+            Some(Architecture::Riscv64),
+            Endian::Little,
+            8,
+            8,
+        ),
+        ("powerpc64-linux-gnu", None, Endian::Big, 8, 8),
+        ("i686-linux-gnu", None, Endian::Little, 4, 4),
+        ("armv7-linux-gnueabihf", None, Endian::Little, 4, 8),
+    ] {
+        let target = triple.parse::<Target>().unwrap();
+        assert_eq!(target.architecture, architecture, "{triple}");
+        assert_eq!(target.endian, endian, "{triple}");
+        let expected = Layout {
+            size: pointer,
+            align: pointer,
+        };
+        assert_eq!(target.pointer(), expected, "{triple}");
+        assert_eq!(target.int().align, align, "{triple}");
+    }
+    assert_eq!(
+        "mips-linux-gnu".parse::<Target>().unwrap_err().to_string(),
+        "target mips-linux-gnu: unknown architecture"
+    );
+    assert_eq!(Target::default().triple, "x86_64-unknown-linux-gnu");
+}
+
+/// `tests/targets/layout.cringe` prints the size of a struct with a pointer
+/// and a string.
+#[test]
+fn sizeof_follows_the_target() {
+    let file = path("tests/targets/layout.cringe");
+    for (triple, size, offsets) in [
+        ("x86_64-linux-gnu", 40, [0, 8, 16, 24]),
+        ("i686-linux-gnu", 28, [0, 4, 12, 16]),
+        ("armv7-linux-gnueabihf", 40, [0, 8, 16, 24]),
+    ] {
+        let Compilation {
+            tree,
+            program,
+            layouts,
+        } = driver::compile_for(&file, &triple.parse().unwrap()).unwrap();
+        let (_, node) = layouts.structs.first_key_value().unwrap();
+        assert_eq!(node.layout.size, size, "{triple}");
+        assert_eq!(node.offsets, offsets, "{triple}");
+        let source = c::emit(&tree, &program, &layouts);
+        assert!(
+            source.contains(&format!("std_print_uint({size});")),
+            "{triple}"
+        );
+    }
+}
+
+#[test]
+fn targets_without_words_are_not_lowered() {
+    let file = path("tests/targets/layout.cringe").display().to_string();
+    for arguments in [["--run"], ["--emit=ir"], ["--disassemble"]] {
+        let options = Options::parse(arguments.map(String::from).into_iter().chain([
+            "--target".to_string(),
+            "i686-linux-gnu".to_string(),
+            file.clone(),
+        ]))
+        .unwrap();
+        assert!(
+            matches!(driver::run(&options), Err(Error::Target { .. })),
+            "{arguments:?}"
+        );
+    }
+    let options =
+        Options::parse(["--emit=asm", "--target", "powerpc64-linux-gnu", &file].map(String::from))
+            .unwrap();
+    let Err(error) = driver::run(&options) else {
+        panic!("powerpc64 has no native backend");
+    };
+    assert_eq!(
+        error.to_string(),
+        "target powerpc64-linux-gnu: no native backend"
+    );
+}
@@ -0,0 +1,343 @@
+	.text
+	.globl cr.weigh
+	.type cr.weigh, @function
+cr.weigh:
+	stp x29, x30, [sp, #-16]!
+	mov x29, sp
+	sub sp, sp, #48
+	stur x19, [x29, #-8]
+	stur x20, [x29, #-16]
+	stur x21, [x29, #-24]
+	stur x22, [x29, #-32]
+	stur x23, [x29, #-40]
+	stur x24, [x29, #-48]
+	mov x13, x0
+	mov x14, x1
+	mov x15, x2
+	mov x19, x3
+	mov x20, x4
+	mov x21, x5
+	mov x22, x6
+	mov x23, x7
+.Lf0b0:
+	mov x24, #2
+	mul x14, x24, x14
+	add x13, x13, x14
+	mov x14, #3
+	mul x14, x14, x15
+	add x13, x13, x14
+	mov x14, #4
+	mul x14, x14, x19
+	add x13, x13, x14
+	mov x14, #5
+	mul x14, x14, x20
+	add x13, x13, x14
+	mov x14, #6
+	mul x14, x14, x21
+	add x13, x13, x14
+	mov x14, #7
+	mul x14, x14, x22
+	add x13, x13, x14
+	mov x14, #8
+	mul x14, x14, x23
+	add x13, x13, x14
+	mov x0, x13
+	ldur x19, [x29, #-8]
+	ldur x20, [x29, #-16]
+	ldur x21, [x29, #-24]
+	ldur x22, [x29, #-32]
+	ldur x23, [x29, #-40]
+	ldur x24, [x29, #-48]
+	mov sp, x29
+	ldp x29, x30, [sp], #16
+	ret
+	.size cr.weigh, .-cr.weigh
+	.text
+	.globl cr.swap
+	.type cr.swap, @function
+cr.swap:
+	stp x29, x30, [sp, #-16]!
+	mov x29, sp
+	sub sp, sp, #96
+	stur x19, [x29, #-8]
+	stur x20, [x29, #-16]
+	stur x21, [x29, #-24]
+	stur x22, [x29, #-32]
+	stur x23, [x29, #-40]
+	stur x24, [x29, #-48]
+	stur x25, [x29, #-56]
+	stur x26, [x29, #-64]
+	mov x19, x0
+	mov x20, x1
+	mov x13, x2
+	mov x21, x3
+	mov x22, x4
+	mov x23, x5
+	mov x24, x6
+	mov x25, x7
+.Lf1b0:
+	sub x14, x29, #80
+	sub x26, x29, #96
+	mov x9, x14
+	mov x10, x13
+	mov x11, #16
+1:
+	cbz x11, 2f
+	ldrb w12, [x10], #1
+	strb w12, [x9], #1
+	sub x11, x11, #1
+	b 1b
+2:
+	mov x0, x14
+	bl cr.std.println
+	mov x13, #8
+	add x14, x20, x13
+	ldr x14, [x14]
+	mul x14, x14, x21
+	add x14, x14, x22
+	add x14, x14, x23
+	str x14, [x26]
+	ldr x14, [x20]
+	mul x14, x14, x21
+	add x14, x14, x24
+	add x14, x14, x25
+	add x13, x26, x13
+	str x14, [x13]
+	mov x9, x19
+	mov x10, x26
+	mov x11, #16
+1:
+	cbz x11, 2f
+	ldrb w12, [x10], #1
+	strb w12, [x9], #1
+	sub x11, x11, #1
+	b 1b
+2:
+	ldur x19, [x29, #-8]
+	ldur x20, [x29, #-16]
+	ldur x21, [x29, #-24]
+	ldur x22, [x29, #-32]
+	ldur x23, [x29, #-40]
+	ldur x24, [x29, #-48]
+	ldur x25, [x29, #-56]
+	ldur x26, [x29, #-64]
+	mov sp, x29
+	ldp x29, x30, [sp], #16
+	ret
+	.size cr.swap, .-cr.swap
+	.text
+	.globl cr.main
+	.type cr.main, @function
+cr.main:
+	stp x29, x30, [sp, #-16]!
+	mov x29, sp
+	sub sp, sp, #368
+	stur x19, [x29, #-96]
+	stur x20, [x29, #-104]
+	stur x21, [x29, #-112]
+	stur x22, [x29, #-120]
+	stur x23, [x29, #-128]
+	stur x24, [x29, #-136]
+	stur x25, [x29, #-144]
+	stur x26, [x29, #-152]
+	stur x27, [x29, #-160]
+	stur x28, [x29, #-168]
+.Lf2b0:
+	sub x19, x29, #184
+	sub x20, x29, #200
+	sub x21, x29, #216
+	sub x22, x29, #232
+	sub x23, x29, #248
+	sub x24, x29, #264
+	sub x9, x29, #280
+	stur x9, [x29, #-8]
+	sub x9, x29, #296
+	stur x9, [x29, #-16]
+	sub x9, x29, #312
+	stur x9, [x29, #-24]
+	sub x9, x29, #328
+	stur x9, [x29, #-32]
+	sub x9, x29, #344
+	stur x9, [x29, #-40]
+	sub x9, x29, #360
+	stur x9, [x29, #-48]
+	mov x28, #1
+	mov x9, #2
+	stur x9, [x29, #-56]
+	mov x27, #3
+	mov x26, #4
+	mov x9, #5
+	stur x9, [x29, #-64]
+	mov x13, #6
+	mov x25, #7
+	mov x9, #8
+	stur x9, [x29, #-72]
+	mov x0, x28
+	ldur x1, [x29, #-56]
+	mov x2, x27
+	mov x3, x26
+	ldur x4, [x29, #-64]
+	mov x5, x13
+	mov x6, x25
+	ldur x7, [x29, #-72]
+	bl cr.weigh
+	mov x13, x0
+	mov x0, x13
+	bl cr.std.print_int
+	adrp x9, .Ldata0
+	add x9, x9, :lo12:.Ldata0
+	stur x9, [x29, #-80]
+	mov x9, #0
+	stur x9, [x29, #-88]
+	ldur x9, [x29, #-80]
+	str x9, [x20]
+	ldur x10, [x29, #-72]
+	add x13, x20, x10
+	ldur x9, [x29, #-88]
+	str x9, [x13]
+	mov x0, x20
+	bl cr.std.println
+	str x28, [x22]
+	ldur x10, [x29, #-72]
+	add x13, x22, x10
+	ldur x9, [x29, #-56]
+	str x9, [x13]
+	adrp x13, .Ldata1
+	add x13, x13, :lo12:.Ldata1
+	str x13, [x23]
+	ldur x10, [x29, #-72]
+	add x13, x23, x10
+	str x25, [x13]
+	mov x13, #10
+	mov x0, x21
+	mov x1, x22
+	mov x2, x23
+	mov x3, x13
+	mov x4, x28
+	ldur x5, [x29, #-56]
+	mov x6, x27
+	mov x7, x26
+	bl cr.swap
+	mov x9, x19
+	mov x10, x21
+	mov x11, #16
+1:
+	cbz x11, 2f
+	ldrb w12, [x10], #1
+	strb w12, [x9], #1
+	sub x11, x11, #1
+	b 1b
+2:
+	ldr x13, [x19]
+	mov x0, x13
+	bl cr.std.print_int
+	ldur x9, [x29, #-80]
+	str x9, [x24]
+	ldur x10, [x29, #-72]
+	add x13, x24, x10
+	ldur x9, [x29, #-88]
+	str x9, [x13]
+	mov x0, x24
+	bl cr.std.println
+	ldur x10, [x29, #-72]
+	add x13, x19, x10
+	ldr x13, [x13]
+	mov x0, x13
+	bl cr.std.print_int
+	ldur x9, [x29, #-80]
+	ldur x10, [x29, #-8]
+	str x9, [x10]
+	ldur x9, [x29, #-8]
+	ldur x10, [x29, #-72]
+	add x13, x9, x10
+	ldur x9, [x29, #-88]
+	str x9, [x13]
+	ldur x0, [x29, #-8]
+	bl cr.std.println
+	movz x13, #32768, lsl #48
+	mov x0, x13
+	bl cr.std.print_int
+	ldur x9, [x29, #-80]
+	ldur x10, [x29, #-16]
+	str x9, [x10]
+	ldur x9, [x29, #-16]
+	ldur x10, [x29, #-72]
+	add x13, x9, x10
+	ldur x9, [x29, #-88]
+	str x9, [x13]
+	ldur x0, [x29, #-16]
+	bl cr.std.println
+	ldur x0, [x29, #-88]
+	bl cr.std.print_int
+	ldur x9, [x29, #-80]
+	ldur x10, [x29, #-24]
+	str x9, [x10]
+	ldur x9, [x29, #-24]
+	ldur x10, [x29, #-72]
+	add x13, x9, x10
+	ldur x9, [x29, #-88]
+	str x9, [x13]
+	ldur x0, [x29, #-24]
+	bl cr.std.println
+	movz x13, #21845, lsl #0
+	movk x13, #21845, lsl #16
+	movk x13, #21845, lsl #32
+	movk x13, #21845, lsl #48
+	mov x0, x13
+	bl cr.std.print_uint
+	ldur x9, [x29, #-80]
+	ldur x10, [x29, #-32]
+	str x9, [x10]
+	ldur x9, [x29, #-32]
+	ldur x10, [x29, #-72]
+	add x13, x9, x10
+	ldur x9, [x29, #-88]
+	str x9, [x13]
+	ldur x0, [x29, #-32]
+	bl cr.std.println
+	ldur x0, [x29, #-64]
+	bl cr.std.print_uint
+	ldur x9, [x29, #-80]
+	ldur x10, [x29, #-40]
+	str x9, [x10]
+	ldur x9, [x29, #-40]
+	ldur x10, [x29, #-72]
+	add x13, x9, x10
+	ldur x9, [x29, #-88]
+	str x9, [x13]
+	ldur x0, [x29, #-40]
+	bl cr.std.println
+	ldur x0, [x29, #-56]
+	bl cr.std.print_int
+	ldur x9, [x29, #-80]
+	ldur x10, [x29, #-48]
+	str x9, [x10]
+	ldur x9, [x29, #-48]
+	ldur x10, [x29, #-72]
+	add x13, x9, x10
+	ldur x9, [x29, #-88]
+	str x9, [x13]
+	ldur x0, [x29, #-48]
+	bl cr.std.println
+	mov x0, #0
+	ldur x19, [x29, #-96]
+	ldur x20, [x29, #-104]
+	ldur x21, [x29, #-112]
+	ldur x22, [x29, #-120]
+	ldur x23, [x29, #-128]
+	ldur x24, [x29, #-136]
+	ldur x25, [x29, #-144]
+	ldur x26, [x29, #-152]
+	ldur x27, [x29, #-160]
+	ldur x28, [x29, #-168]
+	mov sp, x29
+	ldp x29, x30, [sp], #16
+	ret
+	.size cr.main, .-cr.main
+	.section .rodata
+.Ldata0:
+.Ldata1:
+	.byte 115,119,97,112,112,101,100
+.Ldata2:
+	.byte 100,105,118,105,115,105,111,110,32,98,121,32,122,101,114,111
+	.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,357 @@
+	.text
+	.globl cr.weigh
+	.type cr.weigh, @function
+cr.weigh:
+	addi sp, sp, -16
+	sd ra, 8(sp)
+	sd s0, 0(sp)
+	mv s0, sp
+	addi sp, sp, -64
+	sd s1, -8(s0)
+	sd s2, -16(s0)
+	sd s3, -24(s0)
+	sd s4, -32(s0)
+	sd s5, -40(s0)
+	sd s6, -48(s0)
+	sd s7, -56(s0)
+	mv t4, a0
+	mv t5, a1
+	mv s1, a2
+	mv s2, a3
+	mv s3, a4
+	mv s4, a5
+	mv s5, a6
+	mv s6, a7
+.Lf0b0:
+	li s7, 2
+	mul t5, s7, t5
+	add t4, t4, t5
+	li t5, 3
+	mul t5, t5, s1
+	add t4, t4, t5
+	li t5, 4
+	mul t5, t5, s2
+	add t4, t4, t5
+	li t5, 5
+	mul t5, t5, s3
+	add t4, t4, t5
+	li t5, 6
+	mul t5, t5, s4
+	add t4, t4, t5
+	li t5, 7
+	mul t5, t5, s5
+	add t4, t4, t5
+	li t5, 8
+	mul t5, t5, s6
+	add t4, t4, t5
+	mv a0, t4
+	ld s1, -8(s0)
+	ld s2, -16(s0)
+	ld s3, -24(s0)
+	ld s4, -32(s0)
+	ld s5, -40(s0)
+	ld s6, -48(s0)
+	ld s7, -56(s0)
+	mv sp, s0
+	ld ra, 8(sp)
+	ld s0, 0(sp)
+	addi sp, sp, 16
+	ret
+	.size cr.weigh, .-cr.weigh
+	.text
+	.globl cr.swap
+	.type cr.swap, @function
+cr.swap:
+	addi sp, sp, -16
+	sd ra, 8(sp)
+	sd s0, 0(sp)
+	mv s0, sp
+	addi sp, sp, -96
+	sd s1, -8(s0)
+	sd s2, -16(s0)
+	sd s3, -24(s0)
+	sd s4, -32(s0)
+	sd s5, -40(s0)
+	sd s6, -48(s0)
+	sd s7, -56(s0)
+	sd s8, -64(s0)
+	mv s1, a0
+	mv s2, a1
+	mv t4, a2
+	mv s3, a3
+	mv s4, a4
+	mv s5, a5
+	mv s6, a6
+	mv s7, a7
+.Lf1b0:
+	addi t5, s0, -80
+	addi s8, s0, -96
+	mv t0, t5
+	mv t1, t4
+	li t2, 16
+1:
+	beqz t2, 2f
+	lbu t3, 0(t1)
+	sb t3, 0(t0)
+	addi t0, t0, 1
+	addi t1, t1, 1
+	addi t2, t2, -1
+	j 1b
+2:
+	mv a0, t5
+	call cr.std.println
+	li t4, 8
+	add t5, s2, t4
+	ld t5, 0(t5)
+	mul t5, t5, s3
+	add t5, t5, s4
+	add t5, t5, s5
+	sd t5, 0(s8)
+	ld t5, 0(s2)
+	mul t5, t5, s3
+	add t5, t5, s6
+	add t5, t5, s7
+	add t4, s8, t4
+	sd t5, 0(t4)
+	mv t0, s1
+	mv t1, s8
+	li t2, 16
+1:
+	beqz t2, 2f
+	lbu t3, 0(t1)
+	sb t3, 0(t0)
+	addi t0, t0, 1
+	addi t1, t1, 1
+	addi t2, t2, -1
+	j 1b
+2:
+	ld s1, -8(s0)
+	ld s2, -16(s0)
+	ld s3, -24(s0)
+	ld s4, -32(s0)
+	ld s5, -40(s0)
+	ld s6, -48(s0)
+	ld s7, -56(s0)
+	ld s8, -64(s0)
+	mv sp, s0
+	ld ra, 8(sp)
+	ld s0, 0(sp)
+	addi sp, sp, 16
+	ret
+	.size cr.swap, .-cr.swap
+	.text
+	.globl cr.main
+	.type cr.main, @function
+cr.main:
+	addi sp, sp, -16
+	sd ra, 8(sp)
+	sd s0, 0(sp)
+	mv s0, sp
+	addi sp, sp, -368
+	sd s1, -88(s0)
+	sd s2, -96(s0)
+	sd s3, -104(s0)
+	sd s4, -112(s0)
+	sd s5, -120(s0)
+	sd s6, -128(s0)
+	sd s7, -136(s0)
+	sd s8, -144(s0)
+	sd s9, -152(s0)
+	sd s10, -160(s0)
+	sd s11, -168(s0)
+.Lf2b0:
+	addi s1, s0, -184
+	addi s2, s0, -200
+	addi s3, s0, -216
+	addi s4, s0, -232
+	addi s5, s0, -248
+	addi s6, s0, -264
+	addi s7, s0, -280
+	addi t0, s0, -296
+	sd t0, -8(s0)
+	addi t0, s0, -312
+	sd t0, -16(s0)
+	addi t0, s0, -328
+	sd t0, -24(s0)
+	addi t0, s0, -344
+	sd t0, -32(s0)
+	addi t0, s0, -360
+	sd t0, -40(s0)
+	li s11, 1
+	li t0, 2
+	sd t0, -48(s0)
+	li s10, 3
+	li s9, 4
+	li t0, 5
+	sd t0, -56(s0)
+	li t4, 6
+	li s8, 7
+	li t0, 8
+	sd t0, -64(s0)
+	mv a0, s11
+	ld a1, -48(s0)
+	mv a2, s10
+	mv a3, s9
+	ld a4, -56(s0)
+	mv a5, t4
+	mv a6, s8
+	ld a7, -64(s0)
+	call cr.weigh
+	mv t4, a0
+	mv a0, t4
+	call cr.std.print_int
+	lla t0, .Ldata0
+	sd t0, -72(s0)
+	li t0, 0
+	sd t0, -80(s0)
+	ld t0, -72(s0)
+	sd t0, 0(s2)
+	ld t1, -64(s0)
+	add t4, s2, t1
+	ld t0, -80(s0)
+	sd t0, 0(t4)
+	mv a0, s2
+	call cr.std.println
+	sd s11, 0(s4)
+	ld t1, -64(s0)
+	add t4, s4, t1
+	ld t0, -48(s0)
+	sd t0, 0(t4)
+	lla t4, .Ldata1
+	sd t4, 0(s5)
+	ld t1, -64(s0)
+	add t4, s5, t1
+	sd s8, 0(t4)
+	li t4, 10
+	mv a0, s3
+	mv a1, s4
+	mv a2, s5
+	mv a3, t4
+	mv a4, s11
+	ld a5, -48(s0)
+	mv a6, s10
+	mv a7, s9
+	call cr.swap
+	mv t0, s1
+	mv t1, s3
+	li t2, 16
+1:
+	beqz t2, 2f
+	lbu t3, 0(t1)
+	sb t3, 0(t0)
+	addi t0, t0, 1
+	addi t1, t1, 1
+	addi t2, t2, -1
+	j 1b
+2:
+	ld t4, 0(s1)
+	mv a0, t4
+	call cr.std.print_int
+	ld t0, -72(s0)
+	sd t0, 0(s6)
+	ld t1, -64(s0)
+	add t4, s6, t1
+	ld t0, -80(s0)
+	sd t0, 0(t4)
+	mv a0, s6
+	call cr.std.println
+	ld t1, -64(s0)
+	add t4, s1, t1
+	ld t4, 0(t4)
+	mv a0, t4
+	call cr.std.print_int
+	ld t0, -72(s0)
+	sd t0, 0(s7)
+	ld t1, -64(s0)
+	add t4, s7, t1
+	ld t0, -80(s0)
+	sd t0, 0(t4)
+	mv a0, s7
+	call cr.std.println
+	li t4, -9223372036854775808
+	mv a0, t4
+	call cr.std.print_int
+	ld t0, -72(s0)
+	ld t1, -8(s0)
+	sd t0, 0(t1)
+	ld t0, -8(s0)
+	ld t1, -64(s0)
+	add t4, t0, t1
+	ld t0, -80(s0)
+	sd t0, 0(t4)
+	ld a0, -8(s0)
+	call cr.std.println
+	ld a0, -80(s0)
+	call cr.std.print_int
+	ld t0, -72(s0)
+	ld t1, -16(s0)
+	sd t0, 0(t1)
+	ld t0, -16(s0)
+	ld t1, -64(s0)
+	add t4, t0, t1
+	ld t0, -80(s0)
+	sd t0, 0(t4)
+	ld a0, -16(s0)
+	call cr.std.println
+	li t4, 6148914691236517205
+	mv a0, t4
+	call cr.std.print_uint
+	ld t0, -72(s0)
+	ld t1, -24(s0)
+	sd t0, 0(t1)
+	ld t0, -24(s0)
+	ld t1, -64(s0)
+	add t4, t0, t1
+	ld t0, -80(s0)
+	sd t0, 0(t4)
+	ld a0, -24(s0)
+	call cr.std.println
+	ld a0, -56(s0)
+	call cr.std.print_uint
+	ld t0, -72(s0)
+	ld t1, -32(s0)
+	sd t0, 0(t1)
+	ld t0, -32(s0)
+	ld t1, -64(s0)
+	add t4, t0, t1
+	ld t0, -80(s0)
+	sd t0, 0(t4)
+	ld a0, -32(s0)
+	call cr.std.println
+	ld a0, -48(s0)
+	call cr.std.print_int
+	ld t0, -72(s0)
+	ld t1, -40(s0)
+	sd t0, 0(t1)
+	ld t0, -40(s0)
+	ld t1, -64(s0)
+	add t4, t0, t1
+	ld t0, -80(s0)
+	sd t0, 0(t4)
+	ld a0, -40(s0)
+	call cr.std.println
+	li a0, 0
+	ld s1, -88(s0)
+	ld s2, -96(s0)
+	ld s3, -104(s0)
+	ld s4, -112(s0)
+	ld s5, -120(s0)
+	ld s6, -128(s0)
+	ld s7, -136(s0)
+	ld s8, -144(s0)
+	ld s9, -152(s0)
+	ld s10, -160(s0)
+	ld s11, -168(s0)
+	mv sp, s0
+	ld ra, 8(sp)
+	ld s0, 0(sp)
+	addi sp, sp, 16
+	ret
+	.size cr.main, .-cr.main
+	.section .rodata
+.Ldata0:
+.Ldata1:
+	.byte 115,119,97,112,112,101,100
+.Ldata2:
+	.byte 100,105,118,105,115,105,111,110,32,98,121,32,122,101,114,111
+	.section .note.GNU-stack,"",@progbits
@@ -0,0 +1,125 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+#![warn(clippy::pedantic)]
+
+mod common;
+
+use std::{fs, path::Path};
+
+use cringe_lang::{
+    Error,
+    bytecode::{Module, compiler, vm::Vm},
+    driver::{self, Compilation},
+    interpreter::{DEFAULT_MAX_DEPTH, Interpreter},
+};
+
+use common::path;
+
+fn module(file: &Path) -> Module {
+    let Compilation {
+        tree,
+        program,
+        layouts,
+    } = driver::compile(file).unwrap_or_else(|error| panic!("{}: {error}", file.display()));
+    compiler::compile(&tree, &program, &layouts)
+}
+
+/// Runs the program in `file` on the VM and returns its result and output.
+fn run(file: &Path, max_depth: usize) -> (Result<i64, Error>, String) {
+    let module = module(file);
+    let mut output = Vec::new();
+    let result = Vm::new(&module, &mut output)
+        .with_max_depth(max_depth)
+        .run();
+    (result, String::from_utf8(output).unwrap())
+}
+
+fn failure(name: &str, max_depth: usize) -> (Error, String) {
+    let (result, output) = run(&path("tests/failures").join(name), max_depth);
+    (result.expect_err("the program fails"), output)
+}
+
+#[test]
+fn programs() {
+    for program in common::programs() {
+        let expected = fs::read_to_string(program.with_extension("out")).unwrap();
+        let (result, output) = run(&program, DEFAULT_MAX_DEPTH);
+        if let Err(error) = result {
+            panic!("{}: {error}", program.display());
+        }
+        assert_eq!(output, expected, "{}", program.display());
+    }
+}
+
+#[test]
+fn engines_agree() {
+    for name in ["fibonacci", "sieve", "shapes"] {
+        let file = path("benches/programs").join(name).with_extension("cringe");
+        let Compilation {
+            tree,
+            program,
+            layouts,
+        } = driver::compile(&file).unwrap();
+        let mut expected = Vec::new();
+        Interpreter::new(&tree, &program, &layouts, &mut expected)
+            .run()
+            .unwrap();
+        let (result, output) = run(&file, DEFAULT_MAX_DEPTH);
+        assert_eq!(result.unwrap(), 0);
+        assert_eq!(output.as_bytes(), expected, "{name}");
+    }
+}
+
+#[test]
+fn exit_status() {
+    let (result, _) = run(&path("tests/programs/arithmetic.cringe"), DEFAULT_MAX_DEPTH);
+    assert_eq!(result.unwrap(), 3);
+}
+
+#[test]
+fn deep_recursion() {
+    // the VM does not recurse itself, so deep calls need no native stack
+    let (error, output) = failure("recursion.cringe", 100_000);
+    assert!(
+        matches!(error, Error::StackOverflow { depth: 100_000, .. }),
+        "{error}"
+    );
+    assert_eq!(output, "start\n");
+}
+
+#[test]
+fn panics() {
+    for (name, message, output) in [
+        (
+            "bounds.cringe",
+            "5:26: panicked: index 3 is out of bounds for length 3",
+            "3\n",
+        ),
+        ("division.cringe", "2:12: panicked: division by zero", "3\n"),
+        (
+            "step.cringe",
+            "2:14: panicked: range with a step of zero",
+            "3\n",
+        ),
+    ] {
+        let (error, printed) = failure(name, DEFAULT_MAX_DEPTH);
+        let file = path("tests/failures").join(name);
+        assert_eq!(error.to_string(), format!("{}:{message}", file.display()));
+        assert_eq!(printed, output);
+    }
+    let (error, output) = failure("free.cringe", DEFAULT_MAX_DEPTH);
+    assert!(error.to_string().contains("invalid access of 8 bytes"));
+    assert_eq!(output, "freed\n");
+}
+
+#[test]
+fn disassembly() {
+    let file = path("tests/failures/division.cringe");
+    let disassembly = module(&file).to_string();
+    assert!(disassembly.contains("$0 divide (frame 16 bytes, align 8)"));
+    assert!(disassembly.contains(&format!("{}:2:12 signed.div", file.display())));
+    assert!(disassembly.contains("call $0"));
+    assert!(disassembly.contains("print.int"));
+}
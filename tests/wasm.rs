@@ -0,0 +1,185 @@
+/*
+ * Copyright (c) 2025 Jonathan "Nath" Schild. Licensed under the EUPL-1.2
+ */
+
+#![warn(clippy::pedantic)]
+
+mod common;
+
+use std::{fmt::Write, path::Path};
+
+use cringe_lang::{
+    codegen::{PANIC_STATUS, wasm},
+    ir::{Module, opt::Level},
+    target::Target,
+};
+
+use common::{expected, lowered, path, programs};
+use wasmi::{Caller, Engine, Extern, Linker, Store, Val};
+
+fn module(file: &Path, level: Level) -> Module {
+    lowered(file, level, &Target::default()).1
+}
+
+/// What the embedder collects from a running module.
+#[derive(Default)]
+struct Host {
+    stdout: String,
+    stderr: String,
+}
+
+/// The bytes at `address` in the exported memory of the caller.
+fn read(caller: &Caller<'_, Host>, address: i32, length: i32) -> String {
+    let memory = caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .unwrap();
+    let start = usize::try_from(address).unwrap();
+    let end = start + usize::try_from(length).unwrap();
+    String::from_utf8_lossy(&memory.data(caller)[start..end]).into_owned()
+}
+
+/// Runs `main` of the module in `bytes`, returning what it printed and its exit status.
+fn run(bytes: &[u8]) -> (String, String, i32) {
+    let engine = Engine::default();
+    let module = wasmi::Module::new(&engine, bytes).unwrap();
+    let mut store = Store::new(&engine, Host::default());
+    let mut linker = Linker::<Host>::new(&engine);
+    linker
+        .func_wrap(
+            "cringe",
+            "write",
+            |mut caller: Caller<'_, Host>, address: i32, length: i32| {
+                let text = read(&caller, address, length);
+                caller.data_mut().stdout.push_str(&text);
+            },
+        )
+        .unwrap()
+        .func_wrap(
+            "cringe",
+            "print_int",
+            |mut caller: Caller<'_, Host>, value: i64| {
+                write!(caller.data_mut().stdout, "{value}").unwrap();
+            },
+        )
+        .unwrap()
+        .func_wrap(
+            "cringe",
+            "print_uint",
+            |mut caller: Caller<'_, Host>, value: i64| {
+                write!(caller.data_mut().stdout, "{}", value.cast_unsigned()).unwrap();
+            },
+        )
+        .unwrap()
+        .func_wrap(
+            "cringe",
+            "trap",
+            |mut caller: Caller<'_, Host>,
+             message: i32,
+             length: i32,
+             file: i32,
+             file_length: i32,
+             line: i32,
+             column: i32|
+             -> Result<(), wasmi::Error> {
+                let message = read(&caller, message, length);
+                let file = read(&caller, file, file_length);
+                caller.data_mut().stderr = format!("{file}:{line}:{column}: panicked: {message}\n");
+                Err(wasmi::Error::i32_exit(PANIC_STATUS))
+            },
+        )
+        .unwrap()
+        .func_wrap(
+            "cringe",
+            "panic",
+            |mut caller: Caller<'_, Host>, message: i32, length: i32| -> Result<(), wasmi::Error> {
+                let message = read(&caller, message, length);
+                caller.data_mut().stderr = format!("panicked: {message}\n");
+                Err(wasmi::Error::i32_exit(PANIC_STATUS))
+            },
+        )
+        .unwrap();
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let main = instance.get_func(&store, "main").expect("main is exported");
+    let mut results = vec![Val::I64(0); main.ty(&store).results().len()];
+    let status = match main.call(&mut store, &[], &mut results) {
+        Ok(()) => results
+            .first()
+            .and_then(Val::i64)
+            .map_or(0, |result| i32::from(result.to_le_bytes()[0])),
+        Err(error) => error.i32_exit_status().unwrap_or_else(|| panic!("{error}")),
+    };
+    let Host { stdout, stderr } = store.into_data();
+    (stdout, stderr, status)
+}
+
+#[test]
+fn programs_agree_with_the_vm() {
+    let benches = ["fibonacci", "sieve", "shapes"]
+        .map(|name| path("benches/programs").join(name).with_extension("cringe"));
+    for file in programs().into_iter().chain(benches) {
+        let (output, status) = expected(&file);
+        for level in [Level::O0, Level::O2] {
+            let (stdout, stderr, code) = run(&wasm::binary(&module(&file, level)));
+            assert_eq!(stdout, output, "{} at {level:?}", file.display());
+            assert_eq!(stderr, "", "{} at {level:?}", file.display());
+            assert_eq!(code, status, "{} at {level:?}", file.display());
+        }
+    }
+}
+
+#[test]
+fn text_assembles_to_the_same_program() {
+    for file in programs() {
+        let module = module(&file, Level::O1);
+        let text = wasm::text(&module);
+        let bytes = wat::parse_str(&text).unwrap_or_else(|error| panic!("{error}\n{text}"));
+        assert_eq!(
+            run(&bytes),
+            run(&wasm::binary(&module)),
+            "{}",
+            file.display()
+        );
+    }
+}
+
+#[test]
+fn panics() {
+    for (name, message) in [
+        ("bounds.cringe", "5:26: panicked: index out of bounds\n"),
+        ("division.cringe", "2:12: panicked: division by zero\n"),
+        ("step.cringe", "2:14: panicked: range with a step of zero\n"),
+    ] {
+        let file = path("tests/failures").join(name);
+        for level in [Level::O0, Level::O2] {
+            let (stdout, stderr, status) = run(&wasm::binary(&module(&file, level)));
+            assert_eq!(status, PANIC_STATUS, "{name}");
+            assert_eq!(stderr, format!("{}:{message}", file.display()));
+            assert_eq!(stdout, "3\n");
+        }
+    }
+}
+
+#[test]
+fn emitted_text() {
+    let text = wasm::text(&module(&path("tests/programs/structs.cringe"), Level::O0));
+    assert!(text.starts_with("(module\n"));
+    assert!(text.contains("(import \"cringe\" \"write\" (func "));
+    assert!(text.contains("(memory (export \"memory\") "));
+    assert!(text.contains("(export \"main\")"));
+    assert!(text.contains("br_table"));
+}
+
+#[test]
+fn emitted_binary() {
+    let bytes = wasm::binary(&module(
+        &path("tests/programs/arithmetic.cringe"),
+        Level::O1,
+    ));
+    assert_eq!(&bytes[..8], b"\0asm\x01\0\0\0");
+    assert_eq!(run(&bytes).2, 3);
+}